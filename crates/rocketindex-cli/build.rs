@@ -19,4 +19,6 @@ fn main() {
     println!("cargo:rustc-env=RKT_VERSION={}", version);
     println!("cargo:rerun-if-changed=.git/HEAD");
     println!("cargo:rerun-if-changed=.git/refs/tags");
+    // Public key release archives are signed with, checked by `rkt update`
+    println!("cargo:rerun-if-env-changed=RKT_UPDATE_VERIFYING_KEY");
 }
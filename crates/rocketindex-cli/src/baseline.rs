@@ -0,0 +1,137 @@
+//! `rkt refs --diff-baseline`: what changed since a saved result.
+//!
+//! The baseline is the output of an earlier `rkt refs --format json`, as a
+//! list or wrapped in `{"results": [...]}` (see [`crate::budget`] and
+//! [`crate::freshness`]). References are the same when their file, line
+//! and column are, so one that moved counts as removed and added. Paths
+//! are compared relative to the workspace root, and a baseline saved in
+//! another checkout matches by the end of its paths.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+
+/// References added and removed since a baseline.
+#[derive(Debug, Default, PartialEq)]
+pub struct Diff {
+    /// Current references not in the baseline
+    pub added: Vec<Value>,
+    /// Baseline references no longer found
+    pub removed: Vec<Value>,
+}
+
+/// Read the references of a saved result.
+pub fn read(path: &Path) -> Result<Vec<Value>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let saved: Value = serde_json::from_str(&text)
+        .with_context(|| format!("Invalid JSON in {}", path.display()))?;
+    match saved {
+        Value::Array(items) => Ok(items),
+        Value::Object(mut fields) => match fields.remove("results") {
+            Some(Value::Array(items)) => Ok(items),
+            _ => anyhow::bail!("{} isn't a saved 'rkt refs' result", path.display()),
+        },
+        _ => anyhow::bail!("{} isn't a saved 'rkt refs' result", path.display()),
+    }
+}
+
+/// Compare `current` references with `baseline`, both as `rkt refs` prints
+/// them, in a workspace at `root`.
+#[must_use]
+pub fn diff(baseline: Vec<Value>, current: Vec<Value>, root: &Path) -> Diff {
+    let current_keys: Vec<Key> = current.iter().map(|r| key(r, root)).collect();
+    let files: HashSet<&str> = current_keys.iter().map(|k| k.0.as_str()).collect();
+
+    // How many times each baseline reference is still to be matched
+    let mut unmatched: HashMap<Key, usize> = HashMap::new();
+    let mut baseline_keys = Vec::with_capacity(baseline.len());
+    for reference in &baseline {
+        let mut k = key(reference, root);
+        k.0 = matching_file(&k.0, &files).unwrap_or(k.0);
+        *unmatched.entry(k.clone()).or_default() += 1;
+        baseline_keys.push(k);
+    }
+
+    let mut diff = Diff::default();
+    for (reference, k) in current.into_iter().zip(&current_keys) {
+        match unmatched.get_mut(k).filter(|count| **count > 0) {
+            Some(count) => *count -= 1,
+            None => diff.added.push(reference),
+        }
+    }
+    for (reference, k) in baseline.into_iter().zip(&baseline_keys) {
+        if let Some(count) = unmatched.get_mut(k).filter(|count| **count > 0) {
+            *count -= 1;
+            diff.removed.push(reference);
+        }
+    }
+    diff
+}
+
+/// File (relative to `root` when under it), line and column
+type Key = (String, u64, u64);
+
+fn key(reference: &Value, root: &Path) -> Key {
+    let file = reference["file"].as_str().unwrap_or_default();
+    let file = Path::new(file)
+        .strip_prefix(root)
+        .map_or_else(|_| file.to_string(), |p| p.display().to_string())
+        .replace('\\', "/");
+    (
+        file,
+        reference["line"].as_u64().unwrap_or_default(),
+        reference["column"].as_u64().unwrap_or_default(),
+    )
+}
+
+/// The current file a baseline path from another checkout stands for: the
+/// longest end of it that's a current file.
+fn matching_file(file: &str, files: &HashSet<&str>) -> Option<String> {
+    if files.contains(file) {
+        return None;
+    }
+    let mut rest = file;
+    while let Some((_, tail)) = rest.split_once('/') {
+        if files.contains(tail) {
+            return Some(tail.to_string());
+        }
+        rest = tail;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn reference(file: &str, line: u64) -> Value {
+        json!({ "name": "charge", "file": file, "line": line, "column": 5 })
+    }
+
+    #[test]
+    fn reports_added_and_removed_references() {
+        let root = Path::new("/ws");
+        // Saved in a CI checkout elsewhere
+        let baseline = vec![
+            reference("/builds/shop/src/billing.py", 10),
+            reference("/builds/shop/src/billing.py", 20),
+            reference("/builds/shop/src/orders.py", 3),
+        ];
+        let current = vec![
+            reference("/ws/src/billing.py", 10),
+            reference("/ws/src/orders.py", 3),
+            reference("/ws/src/refunds.py", 7),
+        ];
+
+        let diff = diff(baseline, current, root);
+        assert_eq!(diff.added, vec![reference("/ws/src/refunds.py", 7)]);
+        assert_eq!(
+            diff.removed,
+            vec![reference("/builds/shop/src/billing.py", 20)]
+        );
+    }
+}
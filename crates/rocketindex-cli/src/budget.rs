@@ -0,0 +1,242 @@
+//! Output budgets: `--max-bytes` and `--max-items`.
+//!
+//! Agents with small context windows can cap what `refs`, `symbols`,
+//! `spider` and `callers` print as JSON. Results are kept in the order
+//! they'd be printed in until the budget runs out, so the same index and
+//! budget always keep the same ones, and the output says how many there
+//! were in total. Bytes are measured as compact JSON (`--concise`); pretty
+//! output adds whitespace on top.
+//!
+//! Without a budget the output is unchanged. With one, lists are wrapped as
+//! `{"results": [...], "total": N, "truncated": bool}`.
+
+use std::collections::HashSet;
+
+use serde_json::Value;
+
+/// Limits set with `--max-bytes` and `--max-items`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Budget {
+    pub max_bytes: Option<usize>,
+    pub max_items: Option<usize>,
+}
+
+impl Budget {
+    pub fn is_unlimited(&self) -> bool {
+        self.max_bytes.is_none() && self.max_items.is_none()
+    }
+
+    /// A list of results, wrapped with its total when there's a budget.
+    pub fn list(&self, mut items: Vec<Value>) -> Value {
+        if self.is_unlimited() {
+            return Value::Array(items);
+        }
+        let total = items.len();
+        // The envelope comes out of the byte budget too
+        let envelope = serde_json::json!({
+            "results": [],
+            "total": total,
+            "truncated": true,
+        });
+        items.truncate(self.fit(&items, json_len(&envelope)));
+        serde_json::json!({
+            "truncated": items.len() < total,
+            "results": items,
+            "total": total,
+        })
+    }
+
+    /// Trim `list`, a field of `output`, to fit, adding `total` and
+    /// `truncated` beside it.
+    pub fn trim_field(&self, output: &mut Value, list: &str) {
+        if self.is_unlimited() {
+            return;
+        }
+        let Some(Value::Array(mut items)) = output.get_mut(list).map(Value::take) else {
+            return;
+        };
+        let total = items.len();
+        output["total"] = total.into();
+        output["truncated"] = true.into();
+        items.truncate(self.fit(&items, json_len(output)));
+        output["truncated"] = (items.len() < total).into();
+        output[list] = Value::Array(items);
+    }
+
+    /// Trim a `rkt spider` graph to fit.
+    ///
+    /// Nodes are kept nearest first, each with the edges between it and
+    /// the nodes already kept. External nodes are kept only when they all
+    /// fit after that. `total_nodes` and `total_edges` are added, and
+    /// `truncated` is set when anything was left out.
+    pub fn trim_graph(&self, output: &mut Value) {
+        if self.is_unlimited() {
+            return;
+        }
+        let nodes = take_array(output, "nodes");
+        let edges = take_array(output, "edges");
+        let external = output.get_mut("external_nodes").map(Value::take);
+        let was_truncated = output["truncated"].as_bool().unwrap_or(false);
+        output["total_nodes"] = nodes.len().into();
+        output["total_edges"] = edges.len().into();
+        output["truncated"] = true.into();
+        output["nodes"] = Value::Array(Vec::new());
+        output["edges"] = Value::Array(Vec::new());
+        output["external_nodes"] = Value::Array(Vec::new());
+
+        let max_items = self.max_items.unwrap_or(usize::MAX);
+        let mut bytes = json_len(output);
+        let fits = |bytes: usize| self.max_bytes.is_none_or(|max| bytes <= max);
+        let (total_nodes, total_edges) = (nodes.len(), edges.len());
+
+        let mut kept_names: HashSet<String> = HashSet::new();
+        let mut kept_nodes = Vec::new();
+        let mut kept_edges: Vec<Value> = Vec::new();
+        let mut pending: Vec<Option<Value>> = edges.into_iter().map(Some).collect();
+        for node in nodes {
+            if kept_nodes.len() == max_items {
+                break;
+            }
+            let name = node["qualified"].as_str().unwrap_or_default().to_string();
+            let joins = |edge: &Value| {
+                let (from, to) = (edge["from"].as_str(), edge["to"].as_str());
+                let known = |end: Option<&str>| {
+                    end.is_some_and(|end| end == name || kept_names.contains(end))
+                };
+                (from == Some(&name) || to == Some(&name)) && known(from) && known(to)
+            };
+            let new_edges: Vec<usize> = pending
+                .iter()
+                .enumerate()
+                .filter(|(_, edge)| edge.as_ref().is_some_and(joins))
+                .map(|(i, _)| i)
+                .collect();
+            let cost = json_len(&node)
+                + 1
+                + new_edges
+                    .iter()
+                    .map(|&i| pending[i].as_ref().map_or(0, json_len) + 1)
+                    .sum::<usize>();
+            if !fits(bytes + cost) {
+                break;
+            }
+            bytes += cost;
+            kept_names.insert(name);
+            kept_nodes.push(node);
+            kept_edges.extend(new_edges.into_iter().filter_map(|i| pending[i].take()));
+        }
+
+        let mut truncated =
+            was_truncated || kept_nodes.len() < total_nodes || kept_edges.len() < total_edges;
+        if let Some(external) = external {
+            if !truncated && fits(bytes + json_len(&external)) {
+                output["external_nodes"] = external;
+            } else if external.as_array().is_some_and(|groups| !groups.is_empty()) {
+                truncated = true;
+            }
+        }
+        output["nodes"] = Value::Array(kept_nodes);
+        output["edges"] = Value::Array(kept_edges);
+        output["truncated"] = truncated.into();
+    }
+
+    /// How many of `items` fit after `overhead` bytes.
+    fn fit(&self, items: &[Value], overhead: usize) -> usize {
+        let mut bytes = overhead;
+        items
+            .iter()
+            .take(self.max_items.unwrap_or(usize::MAX))
+            .take_while(|item| {
+                // Plus the separating comma
+                bytes += json_len(item) + 1;
+                self.max_bytes.is_none_or(|max| bytes <= max)
+            })
+            .count()
+    }
+}
+
+fn json_len(value: &Value) -> usize {
+    serde_json::to_string(value).map_or(0, |json| json.len())
+}
+
+fn take_array(output: &mut Value, field: &str) -> Vec<Value> {
+    match output.get_mut(field).map(Value::take) {
+        Some(Value::Array(items)) => items,
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn refs(n: usize) -> Vec<Value> {
+        (1..=n)
+            .map(|line| json!({ "file": "src/App.fs", "line": line }))
+            .collect()
+    }
+
+    #[test]
+    fn unlimited_leaves_output_alone() {
+        let budget = Budget::default();
+        assert_eq!(budget.list(refs(3)), Value::Array(refs(3)));
+
+        let mut output = json!({ "symbol": "run", "callers": refs(3) });
+        budget.trim_field(&mut output, "callers");
+        assert_eq!(output, json!({ "symbol": "run", "callers": refs(3) }));
+    }
+
+    #[test]
+    fn lists_keep_a_prefix_within_the_budget() {
+        let by_items = Budget {
+            max_items: Some(2),
+            ..Budget::default()
+        };
+        let output = by_items.list(refs(5));
+        assert_eq!(output["results"], Value::Array(refs(2)));
+        assert_eq!(output["total"], 5);
+        assert_eq!(output["truncated"], true);
+
+        let by_bytes = Budget {
+            max_bytes: Some(120),
+            ..Budget::default()
+        };
+        let output = by_bytes.list(refs(5));
+        assert!(serde_json::to_string(&output).unwrap().len() <= 120);
+        assert_eq!(output["results"].as_array().unwrap()[0], refs(1)[0]);
+        assert_eq!(output["truncated"], true);
+        // The same budget keeps the same results
+        assert_eq!(by_bytes.list(refs(5)), output);
+
+        let roomy = by_items.list(refs(2));
+        assert_eq!(roomy["truncated"], false);
+    }
+
+    #[test]
+    fn graphs_keep_the_nearest_nodes_and_their_edges() {
+        let node = |name: &str, depth: usize| json!({ "qualified": name, "depth": depth });
+        let edge = |from: &str, to: &str| json!({ "from": from, "to": to });
+        let mut output = json!({
+            "nodes": [node("main", 0), node("run", 1), node("load", 1), node("parse", 2)],
+            "edges": [edge("main", "run"), edge("main", "load"), edge("load", "parse")],
+            "external_nodes": [{ "package": null, "symbols": ["printfn"] }],
+            "truncated": false,
+        });
+        Budget {
+            max_items: Some(3),
+            ..Budget::default()
+        }
+        .trim_graph(&mut output);
+
+        assert_eq!(output["nodes"].as_array().unwrap().len(), 3);
+        assert_eq!(
+            output["edges"],
+            json!([edge("main", "run"), edge("main", "load")])
+        );
+        assert_eq!(output["external_nodes"], json!([]));
+        assert_eq!(output["total_nodes"], 4);
+        assert_eq!(output["total_edges"], 3);
+        assert_eq!(output["truncated"], true);
+    }
+}
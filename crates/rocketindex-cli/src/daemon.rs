@@ -0,0 +1,540 @@
+//! `rkt daemon`: watch mode that also answers queries.
+//!
+//! Building a `CodeIndex` from SQLite dominates `rkt spider` and
+//! `rkt callers` on big repositories. The daemon keeps one in memory,
+//! rebuilds it after every batch of file changes, and serves those queries
+//! over a Unix socket at `.rocketindex/daemon.sock`, or on Windows a named
+//! pipe named after the workspace: one JSON request and one JSON answer per
+//! line. The CLI asks the daemon first and falls back to the database when
+//! nothing answers, so a missing or outdated daemon only costs speed.
+
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::Duration;
+
+use rocketindex::fuzzy::Suggestion;
+use rocketindex::spider::{reverse_spider_with_confidence, spider_with_confidence, SpiderResult};
+use rocketindex::{CodeIndex, Confidence};
+use serde::{Deserialize, Serialize};
+
+/// Socket file inside `.rocketindex/`
+pub const SOCKET_NAME: &str = "daemon.sock";
+
+/// Requests and answers carry the CLI release (from `git describe`, since the
+/// crate version stays put across releases); a daemon left running across an
+/// upgrade declines, and the CLI falls back to the database.
+const PROTOCOL_VERSION: &str = env!("RKT_VERSION");
+
+/// A query the daemon can answer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "query", rename_all = "kebab-case")]
+pub enum Query {
+    /// Spider from `symbol` (callers instead of callees when `reverse`)
+    Spider {
+        symbol: String,
+        depth: usize,
+        reverse: bool,
+        min_confidence: Confidence,
+    },
+    /// What the daemon is serving
+    Status,
+}
+
+/// The answer to a [`Query`]
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "answer", rename_all = "kebab-case")]
+pub enum Answer {
+    Spider {
+        /// Qualified name the symbol resolved to
+        entry: String,
+        result: SpiderResult,
+        workspace_root: Option<PathBuf>,
+    },
+    /// The symbol matched nothing; closest names first
+    NotFound {
+        suggestions: Vec<Suggestion>,
+    },
+    Status {
+        pid: u32,
+        symbols: usize,
+    },
+    Error {
+        message: String,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+struct Envelope<T> {
+    version: String,
+    #[serde(flatten)]
+    body: T,
+}
+
+/// How long the CLI waits for an answer before reading the database itself
+const ANSWER_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Path of the daemon socket for the workspace at `root`.
+#[cfg(unix)]
+pub fn socket_path(root: &Path) -> PathBuf {
+    root.join(".rocketindex").join(SOCKET_NAME)
+}
+
+/// Name of the daemon pipe for the workspace at `root`. Pipes live in one
+/// namespace for the whole machine, so the name carries a hash of the path;
+/// the hash only has to agree within a release (see [`PROTOCOL_VERSION`]).
+#[cfg(windows)]
+pub fn pipe_name(root: &Path) -> String {
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    root.hash(&mut hasher);
+    format!(r"\\.\pipe\rocketindex-{:016x}", hasher.finish())
+}
+
+/// Where the daemon for `root` listens, for messages.
+#[cfg(unix)]
+pub fn address(root: &Path) -> String {
+    socket_path(root).display().to_string()
+}
+
+/// Where the daemon for `root` listens, for messages.
+#[cfg(windows)]
+pub fn address(root: &Path) -> String {
+    pipe_name(root)
+}
+
+/// Answer one request line from the CLI with one answer line.
+fn respond(line: &str, index: &RwLock<CodeIndex>) -> Result<String, serde_json::Error> {
+    let body = match serde_json::from_str::<Envelope<Query>>(line) {
+        Ok(request) if request.version == PROTOCOL_VERSION => {
+            let index = index.read().expect("daemon index lock poisoned");
+            answer(&index, &request.body)
+        }
+        Ok(request) => Answer::Error {
+            message: format!(
+                "daemon runs version {}, request is from {}",
+                PROTOCOL_VERSION, request.version
+            ),
+        },
+        Err(e) => Answer::Error {
+            message: format!("invalid request: {}", e),
+        },
+    };
+    serde_json::to_string(&Envelope {
+        version: PROTOCOL_VERSION.to_string(),
+        body,
+    })
+}
+
+/// The request line for `query`.
+fn request(query: &Query) -> Result<String, serde_json::Error> {
+    serde_json::to_string(&Envelope {
+        version: PROTOCOL_VERSION.to_string(),
+        body: query,
+    })
+}
+
+/// Read the daemon's answer line.
+fn read_answer(line: &str) -> anyhow::Result<Answer> {
+    let envelope: Envelope<Answer> = serde_json::from_str(line)?;
+    if envelope.version != PROTOCOL_VERSION {
+        anyhow::bail!("daemon runs version {}", envelope.version);
+    }
+    Ok(envelope.body)
+}
+
+/// Log why the daemon didn't answer, and hand back answers it did give.
+fn accept(address: &str, result: anyhow::Result<Answer>) -> Option<Answer> {
+    match result {
+        Ok(Answer::Error { message }) => {
+            tracing::debug!("Daemon declined query: {}", message);
+            None
+        }
+        Ok(answer) => {
+            tracing::debug!("Query answered by daemon at {}", address);
+            Some(answer)
+        }
+        Err(e) => {
+            tracing::debug!("Daemon at {} unavailable: {}", address, e);
+            None
+        }
+    }
+}
+
+/// Answer `query` from `index`. The daemon and the CLI's fallback share this,
+/// so both paths give the same results.
+pub fn answer(index: &CodeIndex, query: &Query) -> Answer {
+    match query {
+        Query::Spider {
+            symbol,
+            depth,
+            reverse,
+            min_confidence,
+        } => {
+            let entry = match resolve(index, symbol) {
+                Ok(entry) => entry,
+                Err(suggestions) => return Answer::NotFound { suggestions },
+            };
+            let result = if *reverse {
+                reverse_spider_with_confidence(index, &entry, *depth, *min_confidence)
+            } else {
+                spider_with_confidence(index, &entry, *depth, *min_confidence)
+            };
+            Answer::Spider {
+                entry,
+                result,
+                workspace_root: index.workspace_root().map(Path::to_path_buf),
+            }
+        }
+        Query::Status => Answer::Status {
+            pid: std::process::id(),
+            symbols: index.symbol_count(),
+        },
+    }
+}
+
+/// Resolve `symbol` to a qualified name: exactly, then by search, else
+/// return fuzzy suggestions.
+fn resolve(index: &CodeIndex, symbol: &str) -> Result<String, Vec<Suggestion>> {
+    if index.get(symbol).is_some() {
+        return Ok(symbol.to_string());
+    }
+    if let Some(first) = index.search(symbol).first() {
+        return Ok(first.qualified.clone());
+    }
+    Err(index.suggest(symbol))
+}
+
+#[cfg(unix)]
+pub use unix::{ask, serve};
+
+#[cfg(windows)]
+pub use windows::{ask, serve};
+
+#[cfg(not(any(unix, windows)))]
+pub use fallback::{address, ask, serve};
+
+#[cfg(unix)]
+mod unix {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::{Path, PathBuf};
+    use std::sync::{Arc, RwLock};
+
+    use anyhow::{Context, Result};
+    use rocketindex::CodeIndex;
+
+    use super::{
+        accept, read_answer, request, respond, socket_path, Answer, Query, ANSWER_TIMEOUT,
+    };
+
+    /// Removes the socket when the daemon stops
+    pub struct SocketGuard(PathBuf);
+
+    impl Drop for SocketGuard {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    /// Serve queries about the workspace at `root` from `index` on a
+    /// background thread, until the returned guard is dropped and the
+    /// process exits.
+    pub fn serve(root: &Path, index: Arc<RwLock<CodeIndex>>) -> Result<SocketGuard> {
+        let path = socket_path(root);
+        if path.exists() {
+            if UnixStream::connect(&path).is_ok() {
+                anyhow::bail!("A daemon is already serving {}", path.display());
+            }
+            // Left behind by a daemon that didn't shut down cleanly
+            std::fs::remove_file(&path)
+                .with_context(|| format!("Failed to remove stale {}", path.display()))?;
+        }
+        let listener = UnixListener::bind(&path)
+            .with_context(|| format!("Failed to listen on {}", path.display()))?;
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let index = Arc::clone(&index);
+                std::thread::spawn(move || {
+                    if let Err(e) = handle(stream, &index) {
+                        tracing::debug!("Daemon connection failed: {}", e);
+                    }
+                });
+            }
+        });
+
+        Ok(SocketGuard(path))
+    }
+
+    fn handle(stream: UnixStream, index: &RwLock<CodeIndex>) -> Result<()> {
+        let mut line = String::new();
+        BufReader::new(&stream).read_line(&mut line)?;
+        let mut writer = &stream;
+        writeln!(writer, "{}", respond(&line, index)?)?;
+        Ok(())
+    }
+
+    /// Ask the daemon serving `root`, if any. `None` when no daemon answers
+    /// or it can't answer this query; the caller then reads the database.
+    pub fn ask(root: &Path, query: &Query) -> Option<Answer> {
+        let path = socket_path(root);
+        if !path.exists() {
+            return None;
+        }
+        accept(&path.display().to_string(), try_ask(&path, query))
+    }
+
+    fn try_ask(path: &Path, query: &Query) -> Result<Answer> {
+        let stream = UnixStream::connect(path)?;
+        stream.set_read_timeout(Some(ANSWER_TIMEOUT))?;
+        let mut writer = &stream;
+        writeln!(writer, "{}", request(query)?)?;
+
+        let mut line = String::new();
+        BufReader::new(&stream).read_line(&mut line)?;
+        read_answer(&line)
+    }
+}
+
+#[cfg(windows)]
+mod windows {
+    use std::path::Path;
+    use std::sync::{Arc, RwLock};
+
+    use anyhow::{Context, Result};
+    use rocketindex::CodeIndex;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::windows::named_pipe::{ClientOptions, NamedPipeServer, ServerOptions};
+
+    use super::{accept, pipe_name, read_answer, request, respond, Answer, Query, ANSWER_TIMEOUT};
+
+    /// Keeps the pipe served; the pipe goes away with the runtime when the
+    /// daemon stops
+    pub struct SocketGuard {
+        _runtime: tokio::runtime::Runtime,
+    }
+
+    /// Serve queries about the workspace at `root` from `index` on
+    /// background threads, until the returned guard is dropped and the
+    /// process exits.
+    pub fn serve(root: &Path, index: Arc<RwLock<CodeIndex>>) -> Result<SocketGuard> {
+        let name = pipe_name(root);
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .enable_all()
+            .build()?;
+        // The first instance fails if another daemon owns the name
+        let first = {
+            let _context = runtime.enter();
+            ServerOptions::new()
+                .first_pipe_instance(true)
+                .reject_remote_clients(true)
+                .create(&name)
+                .with_context(|| format!("A daemon is already serving {}", name))?
+        };
+
+        runtime.spawn(async move {
+            let mut server = first;
+            loop {
+                if let Err(e) = server.connect().await {
+                    tracing::debug!("Daemon connection failed: {}", e);
+                    continue;
+                }
+                // Create the next instance before handing this one off, so
+                // there's always one for clients to open
+                let next = match ServerOptions::new()
+                    .reject_remote_clients(true)
+                    .create(&name)
+                {
+                    Ok(next) => next,
+                    Err(e) => {
+                        tracing::warn!("Daemon stopped listening on {}: {}", name, e);
+                        return;
+                    }
+                };
+                let connected = std::mem::replace(&mut server, next);
+                let index = Arc::clone(&index);
+                tokio::spawn(async move {
+                    if let Err(e) = handle(connected, index).await {
+                        tracing::debug!("Daemon connection failed: {}", e);
+                    }
+                });
+            }
+        });
+
+        Ok(SocketGuard { _runtime: runtime })
+    }
+
+    async fn handle(pipe: NamedPipeServer, index: Arc<RwLock<CodeIndex>>) -> Result<()> {
+        let mut pipe = BufReader::new(pipe);
+        let mut line = String::new();
+        pipe.read_line(&mut line).await?;
+        // Answering walks the index; keep it off the I/O threads
+        let answer = tokio::task::spawn_blocking(move || respond(&line, &index)).await??;
+        pipe.write_all(format!("{}\n", answer).as_bytes()).await?;
+        pipe.flush().await?;
+        Ok(())
+    }
+
+    /// Ask the daemon serving `root`, if any. `None` when no daemon answers
+    /// or it can't answer this query; the caller then reads the database.
+    pub fn ask(root: &Path, query: &Query) -> Option<Answer> {
+        let name = pipe_name(root);
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .ok()?;
+        let result = runtime.block_on(async {
+            tokio::time::timeout(ANSWER_TIMEOUT, try_ask(&name, query))
+                .await
+                .unwrap_or_else(|_| Err(anyhow::anyhow!("no answer in time")))
+        });
+        accept(&name, result)
+    }
+
+    async fn try_ask(name: &str, query: &Query) -> Result<Answer> {
+        // Fails at once with NotFound when no daemon serves this workspace
+        let pipe = ClientOptions::new().open(name)?;
+        let mut pipe = BufReader::new(pipe);
+        pipe.write_all(format!("{}\n", request(query)?).as_bytes())
+            .await?;
+        pipe.flush().await?;
+
+        let mut line = String::new();
+        pipe.read_line(&mut line).await?;
+        read_answer(&line)
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod fallback {
+    use std::path::Path;
+    use std::sync::{Arc, RwLock};
+
+    use anyhow::Result;
+    use rocketindex::CodeIndex;
+
+    use super::{Answer, Query};
+
+    pub struct SocketGuard;
+
+    pub fn serve(_root: &Path, _index: Arc<RwLock<CodeIndex>>) -> Result<SocketGuard> {
+        anyhow::bail!(
+            "rkt daemon needs Unix sockets or named pipes; use 'rkt watch' on this platform"
+        )
+    }
+
+    pub fn ask(_root: &Path, _query: &Query) -> Option<Answer> {
+        None
+    }
+
+    pub fn address(root: &Path) -> String {
+        root.display().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocketindex::index::Reference;
+    use rocketindex::{Location, ReferenceKind, Symbol, SymbolKind, Visibility};
+
+    fn index() -> CodeIndex {
+        let mut index = CodeIndex::new();
+        for (name, line) in [("charge", 1), ("checkout", 5)] {
+            index.add_symbol(Symbol::new(
+                name.to_string(),
+                format!("Billing.{}", name),
+                SymbolKind::Function,
+                Location::new(PathBuf::from("src/Billing.fs"), line, 5),
+                Visibility::Public,
+                "fsharp".to_string(),
+            ));
+        }
+        index.add_reference(
+            PathBuf::from("src/Billing.fs"),
+            Reference {
+                name: "charge".to_string(),
+                location: Location::new(PathBuf::from("src/Billing.fs"), 6, 5),
+                kind: ReferenceKind::Read,
+            },
+        );
+        index
+    }
+
+    #[test]
+    fn test_answer_round_trips_through_json() {
+        let query = Query::Spider {
+            symbol: "Billing.charge".to_string(),
+            depth: 1,
+            reverse: true,
+            min_confidence: Confidence::StringMatch,
+        };
+        let request = serde_json::to_string(&Envelope {
+            version: PROTOCOL_VERSION.to_string(),
+            body: &query,
+        })
+        .unwrap();
+        let request: Envelope<Query> = serde_json::from_str(&request).unwrap();
+
+        let json = serde_json::to_string(&answer(&index(), &request.body)).unwrap();
+        match serde_json::from_str::<Answer>(&json).unwrap() {
+            Answer::Spider { entry, result, .. } => {
+                assert_eq!(entry, "Billing.charge");
+                let callers: Vec<&str> = result
+                    .at_depth(1)
+                    .iter()
+                    .map(|n| n.symbol.qualified.as_str())
+                    .collect();
+                assert_eq!(callers, vec!["Billing.checkout"]);
+            }
+            other => panic!("unexpected answer: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_answer_suggests_for_unknown_symbols() {
+        let query = Query::Spider {
+            symbol: "Billing.chrage".to_string(),
+            depth: 1,
+            reverse: false,
+            min_confidence: Confidence::StringMatch,
+        };
+        match answer(&index(), &query) {
+            Answer::NotFound { suggestions } => {
+                assert_eq!(suggestions[0].value, "Billing.charge");
+            }
+            other => panic!("unexpected answer: {:?}", other),
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_ask_falls_back_without_daemon() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(ask(dir.path(), &Query::Status).is_none());
+
+        // A socket file nobody listens on is ignored too
+        std::fs::create_dir_all(dir.path().join(".rocketindex")).unwrap();
+        std::fs::write(socket_path(dir.path()), "").unwrap();
+        assert!(ask(dir.path(), &Query::Status).is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_serve_answers_queries() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join(".rocketindex")).unwrap();
+        let shared = std::sync::Arc::new(std::sync::RwLock::new(index()));
+        let guard = serve(dir.path(), shared).unwrap();
+
+        match ask(dir.path(), &Query::Status) {
+            Some(Answer::Status { symbols, .. }) => assert_eq!(symbols, 2),
+            other => panic!("unexpected answer: {:?}", other),
+        }
+
+        drop(guard);
+        assert!(!socket_path(dir.path()).exists());
+    }
+}
@@ -0,0 +1,269 @@
+//! `rkt enrich` JSON output, versioned as a contract for agent prompts.
+//!
+//! [`Enrichment`] is what `rkt enrich --format json` prints and [`schema`]
+//! describes it (`rkt enrich --json-schema`). Every field is always there,
+//! null or empty when there is nothing to report, so a prompt written
+//! against one version keeps parsing. Adding a field is compatible;
+//! renaming, removing or retyping one bumps [`VERSION`].
+
+use rocketindex::owners::Ownership;
+use rocketindex::Route;
+use serde::Serialize;
+
+/// Version of the output contract, reported as `schema_version`.
+pub const VERSION: u32 = 1;
+
+/// Everything `rkt enrich` reports about a symbol.
+#[derive(Debug, Clone, Serialize)]
+pub struct Enrichment {
+    pub schema_version: u32,
+    pub symbol: String,
+    /// Stable across file moves (see [`rocketindex::db::symbol_guid`])
+    pub guid: String,
+    pub kind: String,
+    pub file: String,
+    pub line: u32,
+    pub signature: Option<String>,
+    pub doc: Option<String>,
+    pub callers_depth: usize,
+    pub callers_count: usize,
+    /// Qualified names, nearest first
+    pub callers: Vec<String>,
+    pub dependencies_depth: usize,
+    pub dependencies_count: usize,
+    /// Qualified names, nearest first
+    pub dependencies: Vec<String>,
+    pub blame: Option<Blame>,
+    /// `None` unless asked for with `--tests`
+    pub tests: Option<Vec<Test>>,
+    /// `None` unless asked for with `--routes`
+    pub routes: Option<Vec<Route>>,
+    /// `None` when there's neither a CODEOWNERS rule nor committed lines
+    pub owner: Option<Ownership>,
+}
+
+/// The last change to the symbol's first line.
+#[derive(Debug, Clone, Serialize)]
+pub struct Blame {
+    pub commit: String,
+    pub author: String,
+    pub date_relative: String,
+    pub message: String,
+}
+
+/// A test that (transitively) calls the symbol.
+#[derive(Debug, Clone, Serialize)]
+pub struct Test {
+    /// `None` for files whose tests aren't indexed as symbols (RSpec `it` blocks)
+    pub qualified: Option<String>,
+    pub file: String,
+    pub line: u32,
+    /// Caller edges between the test and the symbol
+    pub depth: usize,
+}
+
+/// JSON Schema of [`Enrichment`].
+pub fn schema() -> serde_json::Value {
+    let string = serde_json::json!({ "type": "string" });
+    let nullable_string = serde_json::json!({ "type": ["string", "null"] });
+    let count = serde_json::json!({ "type": "integer", "minimum": 0 });
+    let names = serde_json::json!({ "type": "array", "items": { "type": "string" } });
+    let object = |description: &str, properties: serde_json::Value| {
+        let required: Vec<&String> = properties.as_object().unwrap().keys().collect();
+        serde_json::json!({
+            "type": "object",
+            "description": description,
+            "properties": properties,
+            "required": required,
+            "additionalProperties": false,
+        })
+    };
+
+    let blame = object(
+        "The last change to the symbol's first line",
+        serde_json::json!({
+            "commit": string,
+            "author": string,
+            "date_relative": string,
+            "message": string,
+        }),
+    );
+    let test = object(
+        "A test that (transitively) calls the symbol",
+        serde_json::json!({
+            "qualified": nullable_string,
+            "file": string,
+            "line": count,
+            "depth": count,
+        }),
+    );
+    let route = object(
+        "An HTTP route the symbol handles, from a route attribute or decorator",
+        serde_json::json!({
+            "method": nullable_string,
+            "path": string,
+        }),
+    );
+    let owner = object(
+        "Who owns the symbol's file (CODEOWNERS) and who wrote its lines (git blame)",
+        serde_json::json!({
+            "rule": {
+                "oneOf": [
+                    object(
+                        "The last CODEOWNERS rule matching the file",
+                        serde_json::json!({
+                            "source": string,
+                            "pattern": string,
+                            "owners": names,
+                        }),
+                    ),
+                    { "type": "null" },
+                ],
+            },
+            "contributors": {
+                "type": "array",
+                "items": object(
+                    "An author of the symbol's lines, most lines first",
+                    serde_json::json!({
+                        "author": string,
+                        "email": string,
+                        "lines": count,
+                    }),
+                ),
+            },
+        }),
+    );
+
+    let mut enrichment = object(
+        "A symbol with its callers, dependencies, blame, tests, routes and owner",
+        serde_json::json!({
+            "schema_version": { "const": VERSION },
+            "symbol": string,
+            "guid": string,
+            "kind": string,
+            "file": string,
+            "line": count,
+            "signature": nullable_string,
+            "doc": nullable_string,
+            "callers_depth": count,
+            "callers_count": count,
+            "callers": names,
+            "dependencies_depth": count,
+            "dependencies_count": count,
+            "dependencies": names,
+            "blame": { "oneOf": [blame, { "type": "null" }] },
+            "tests": {
+                "description": "Null unless requested with --tests",
+                "oneOf": [{ "type": "array", "items": test }, { "type": "null" }],
+            },
+            "routes": {
+                "description": "Null unless requested with --routes",
+                "oneOf": [{ "type": "array", "items": route }, { "type": "null" }],
+            },
+            "owner": { "oneOf": [owner, { "type": "null" }] },
+        }),
+    );
+    enrichment["$schema"] = "https://json-schema.org/draft/2020-12/schema".into();
+    enrichment["title"] = "rkt enrich".into();
+    enrichment
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocketindex::git::Contributor;
+    use rocketindex::owners::OwnerRule;
+    use serde_json::Value;
+
+    /// Whether `value` has exactly the fields `schema` declares, recursively.
+    fn conforms(value: &Value, schema: &Value) -> bool {
+        if let Some(options) = schema["oneOf"].as_array() {
+            return options.iter().any(|option| conforms(value, option));
+        }
+        match (value, schema["type"].as_str()) {
+            (Value::Null, _) => schema["type"] == "null" || schema["type"][1] == "null",
+            (Value::Object(fields), Some("object")) => {
+                let properties = schema["properties"].as_object().unwrap();
+                fields.len() == properties.len()
+                    && fields.iter().all(|(key, field)| {
+                        properties
+                            .get(key)
+                            .is_some_and(|schema| conforms(field, schema))
+                    })
+            }
+            (Value::Array(items), Some("array")) => {
+                items.iter().all(|item| conforms(item, &schema["items"]))
+            }
+            (Value::Object(_) | Value::Array(_), _) => false,
+            _ => true,
+        }
+    }
+
+    fn enrichment() -> Enrichment {
+        Enrichment {
+            schema_version: VERSION,
+            symbol: "Users.show".to_string(),
+            guid: "5d1e5c2a-8f0b-6a41-93c7-0e2d4b7f1a68".to_string(),
+            kind: "Function".to_string(),
+            file: "src/users.py".to_string(),
+            line: 12,
+            signature: Some("def show(id)".to_string()),
+            doc: None,
+            callers_depth: 1,
+            callers_count: 1,
+            callers: vec!["App.main".to_string()],
+            dependencies_depth: 1,
+            dependencies_count: 0,
+            dependencies: vec![],
+            blame: Some(Blame {
+                commit: "abc123".to_string(),
+                author: "dev".to_string(),
+                date_relative: "2 days ago".to_string(),
+                message: "Add show".to_string(),
+            }),
+            tests: Some(vec![Test {
+                qualified: Some("test_show".to_string()),
+                file: "tests/test_users.py".to_string(),
+                line: 3,
+                depth: 1,
+            }]),
+            routes: Some(vec![Route {
+                method: Some("GET".to_string()),
+                path: "/users/<id>".to_string(),
+            }]),
+            owner: Some(Ownership {
+                rule: Some(OwnerRule {
+                    source: ".github/CODEOWNERS:3".to_string(),
+                    pattern: "/src/".to_string(),
+                    owners: vec!["@acme/users".to_string()],
+                }),
+                contributors: vec![Contributor {
+                    author: "dev".to_string(),
+                    email: "dev@example.com".to_string(),
+                    lines: 4,
+                }],
+            }),
+        }
+    }
+
+    #[test]
+    fn output_matches_schema() {
+        let schema = schema();
+        let full = serde_json::to_value(enrichment()).unwrap();
+        assert!(conforms(&full, &schema));
+
+        let bare = Enrichment {
+            blame: None,
+            tests: None,
+            routes: None,
+            owner: None,
+            ..enrichment()
+        };
+        assert!(conforms(&serde_json::to_value(bare).unwrap(), &schema));
+
+        // A field the schema doesn't know about breaks the contract
+        let mut extra = full;
+        extra["callers_by_file"] = serde_json::json!({});
+        assert!(!conforms(&extra, &schema));
+    }
+}
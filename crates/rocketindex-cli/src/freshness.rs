@@ -0,0 +1,112 @@
+//! Partial-index queries: `--refresh-budget`.
+//!
+//! Queries normally bring the index up to date before answering, however
+//! long that takes. With a refresh budget, stale files are reindexed only
+//! until the budget runs out and the query answers from the index as it is,
+//! saying how far behind it was.
+//!
+//! With a budget the JSON output of `def`, `refs`, `symbols`, `spider` and
+//! `callers` carries `stale_files` (files changed on disk but not yet
+//! reindexed) and `index_age_seconds` (time since the index was last
+//! written). Objects get the two fields added; lists are wrapped as
+//! `{"results": [...], "stale_files": [...], "index_age_seconds": N}`.
+
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde_json::Value;
+
+/// How fresh the index a query answered from was.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Freshness {
+    /// Files that changed on disk and weren't reindexed
+    pub stale_files: Vec<PathBuf>,
+    /// Seconds since the index was last written
+    pub index_age_seconds: u64,
+}
+
+impl Freshness {
+    /// Freshness of the database at `db_path`, with `stale_files` still
+    /// unindexed. Paths under `root` are reported relative to it.
+    pub fn measure(db_path: &Path, root: &Path, stale_files: &[PathBuf]) -> Self {
+        Self {
+            stale_files: stale_files
+                .iter()
+                .map(|path| path.strip_prefix(root).unwrap_or(path).to_path_buf())
+                .collect(),
+            index_age_seconds: index_age(db_path).map_or(0, |age| age.as_secs()),
+        }
+    }
+
+    /// Add the freshness fields to a query's JSON output.
+    pub fn annotate(&self, output: Value) -> Value {
+        let mut output = match output {
+            Value::Object(_) => output,
+            other => serde_json::json!({ "results": other }),
+        };
+        output["stale_files"] = serde_json::json!(self.stale_files);
+        output["index_age_seconds"] = self.index_age_seconds.into();
+        output
+    }
+}
+
+/// Time since the database at `db_path` or its write-ahead log last changed.
+fn index_age(db_path: &Path) -> Option<std::time::Duration> {
+    let mut wal = db_path.as_os_str().to_owned();
+    wal.push("-wal");
+    let written = [db_path.to_path_buf(), PathBuf::from(wal)]
+        .iter()
+        .filter_map(|path| std::fs::metadata(path).and_then(|m| m.modified()).ok())
+        .max()?;
+    SystemTime::now().duration_since(written).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn annotates_objects_and_wraps_lists() {
+        let freshness = Freshness {
+            stale_files: vec![PathBuf::from("src/App.fs")],
+            index_age_seconds: 42,
+        };
+
+        let output = freshness.annotate(json!({ "symbol": "run", "callers": [] }));
+        assert_eq!(
+            output,
+            json!({
+                "symbol": "run",
+                "callers": [],
+                "stale_files": ["src/App.fs"],
+                "index_age_seconds": 42,
+            })
+        );
+
+        let output = freshness.annotate(json!([{ "line": 1 }]));
+        assert_eq!(output["results"], json!([{ "line": 1 }]));
+        assert_eq!(output["stale_files"], json!(["src/App.fs"]));
+    }
+
+    #[test]
+    fn measures_relative_to_the_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("index.db");
+        std::fs::write(&db_path, "").unwrap();
+
+        let freshness = Freshness::measure(
+            &db_path,
+            dir.path(),
+            &[
+                dir.path().join("src/App.fs"),
+                PathBuf::from("/elsewhere.fs"),
+            ],
+        );
+        assert_eq!(
+            freshness.stale_files,
+            vec![PathBuf::from("src/App.fs"), PathBuf::from("/elsewhere.fs")]
+        );
+        assert!(freshness.index_age_seconds < 60);
+    }
+}
@@ -0,0 +1,463 @@
+//! `rkt serve --http`: the query commands as a JSON API.
+//!
+//! A deliberately small HTTP/1.1 server: `GET` only, one request per
+//! connection. Each endpoint takes the command's flags as query parameters
+//! and returns the JSON `rkt <command>` prints, so dashboards and bots can
+//! use rocketindex without shelling out.
+//!
+//! Requests run concurrently under the MCP server's [`QueryLimits`], with
+//! each peer address counted as a client: a request that can't get a slot
+//! in time is answered 503, one that runs too long 504.
+//!
+//! Only requests whose `Host` names this server (`localhost`, a loopback
+//! address or the address it listens on, with its port) are answered, so a
+//! page in a browser can't read source through a rebound DNS name.
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::mcp::limits::{LimitError, QueryLimiter, QueryLimits};
+
+/// Slow or idle clients don't hold up the next request for longer than this
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Most bytes read for the request line and headers together
+const MAX_HEAD_BYTES: u64 = 8 * 1024;
+
+/// A parsed `GET` request
+#[derive(Debug)]
+pub struct Request {
+    /// Path without the query string, e.g. `/def`
+    pub path: String,
+    params: HashMap<String, String>,
+}
+
+impl Request {
+    /// Parse the request line of an HTTP request (`GET /def?symbol=x HTTP/1.1`).
+    pub fn parse(request_line: &str) -> Option<Self> {
+        let mut parts = request_line.split_whitespace();
+        if parts.next()? != "GET" {
+            return None;
+        }
+        let target = parts.next()?;
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let params = query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+                (percent_decode(key), percent_decode(value))
+            })
+            .collect();
+        Some(Self {
+            path: percent_decode(path),
+            params,
+        })
+    }
+
+    /// Value of a query parameter
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params.get(name).map(String::as_str)
+    }
+
+    /// A required query parameter, or a 400 response naming it
+    pub fn require(&self, name: &str) -> std::result::Result<&str, Response> {
+        self.param(name)
+            .ok_or_else(|| Response::bad_request(format!("Missing query parameter '{}'", name)))
+    }
+
+    /// A query parameter parsed with `FromStr`, or a 400 response
+    pub fn parse_param<T: std::str::FromStr>(
+        &self,
+        name: &str,
+    ) -> std::result::Result<Option<T>, Response> {
+        self.param(name)
+            .map(|value| {
+                value.parse().map_err(|_| {
+                    Response::bad_request(format!("Invalid value for '{}': {}", name, value))
+                })
+            })
+            .transpose()
+    }
+
+    /// A boolean flag: present without a value, or `true`/`1`
+    pub fn flag(&self, name: &str) -> bool {
+        matches!(self.param(name), Some("" | "true" | "1"))
+    }
+}
+
+/// A JSON response
+#[derive(Debug)]
+pub struct Response {
+    pub status: u16,
+    pub body: serde_json::Value,
+}
+
+impl Response {
+    pub fn new(status: u16, body: serde_json::Value) -> Self {
+        Self { status, body }
+    }
+
+    pub fn bad_request(message: String) -> Self {
+        Self::new(400, serde_json::json!({ "error": message }))
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            403 => "Forbidden",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            431 => "Request Header Fields Too Large",
+            503 => "Service Unavailable",
+            504 => "Gateway Timeout",
+            _ => "Internal Server Error",
+        }
+    }
+}
+
+/// Answer requests on `listener` with `handle` until the process exits.
+pub fn serve<H>(listener: TcpListener, limits: QueryLimits, handle: H) -> Result<()>
+where
+    H: Fn(&Request) -> Response + Send + Sync + 'static,
+{
+    let runtime = tokio::runtime::Runtime::new()?;
+    let limiter = Arc::new(QueryLimiter::new(limits));
+    let handle = Arc::new(handle);
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                tracing::debug!("HTTP accept failed: {}", e);
+                continue;
+            }
+        };
+        let client = stream
+            .peer_addr()
+            .map(|addr| addr.ip().to_string())
+            .unwrap_or_else(|_| "unknown".to_string());
+        let (limiter, handle) = (limiter.clone(), handle.clone());
+        let runtime_handle = runtime.handle().clone();
+        runtime.spawn_blocking(move || {
+            let answer = |request: Request| {
+                let query = tokio::task::spawn_blocking(move || handle(&request));
+                limited(runtime_handle.block_on(limiter.run(&client, query)))
+            };
+            if let Err(e) = respond(stream, answer) {
+                tracing::debug!("HTTP connection failed: {}", e);
+            }
+        });
+    }
+    Ok(())
+}
+
+/// The response to a request run under the query limits.
+fn limited(
+    outcome: std::result::Result<std::result::Result<Response, tokio::task::JoinError>, LimitError>,
+) -> Response {
+    let error =
+        |status, message: String| Response::new(status, serde_json::json!({ "error": message }));
+    match outcome {
+        Ok(Ok(response)) => response,
+        Ok(Err(e)) => error(500, LimitError::Failed(e.to_string()).to_string()),
+        Err(e @ LimitError::Busy) => error(503, e.to_string()),
+        Err(e @ LimitError::TimedOut(_)) => error(504, e.to_string()),
+        Err(e @ LimitError::Failed(_)) => error(500, e.to_string()),
+    }
+}
+
+fn respond(stream: TcpStream, handle: impl FnOnce(Request) -> Response) -> Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let local = stream.local_addr()?;
+    let mut head = BufReader::new(&stream).take(MAX_HEAD_BYTES);
+    let mut request_line = String::new();
+    head.read_line(&mut request_line)?;
+    // Only `Host` matters; GET requests carry no body
+    let mut host = None;
+    let mut complete = false;
+    let mut header = String::new();
+    while head.read_line(&mut header)? > 0 {
+        if header.ends_with('\n') && header.trim_end().is_empty() {
+            complete = true;
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("host") {
+                host = Some(value.trim().to_string());
+            }
+        }
+        header.clear();
+    }
+
+    let error =
+        |status, message: &str| Response::new(status, serde_json::json!({ "error": message }));
+    let response = if !complete && head.limit() == 0 {
+        error(431, "Request line and headers are too large")
+    } else if !complete {
+        error(400, "Incomplete request")
+    } else if !host
+        .as_deref()
+        .is_some_and(|host| names_this_server(host, local))
+    {
+        error(403, "The Host header must name this server, e.g. localhost")
+    } else {
+        match Request::parse(&request_line) {
+            Some(request) => {
+                tracing::debug!("HTTP {}", request_line.trim_end());
+                handle(request)
+            }
+            None => error(405, "Only GET requests are supported"),
+        }
+    };
+
+    let body = serde_json::to_string(&response.body)?;
+    let mut writer = &stream;
+    write!(
+        writer,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        response.reason(),
+        body.len(),
+        body
+    )?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Whether a `Host` header names the server listening on `local`: `localhost`,
+/// a loopback address or `local`'s own address, with `local`'s port (which
+/// may only be left out when it's 80).
+fn names_this_server(host: &str, local: SocketAddr) -> bool {
+    let (name, port) = match host.strip_prefix('[') {
+        Some(bracketed) => match bracketed.split_once(']') {
+            Some((address, rest)) => (format!("[{}]", address), rest.strip_prefix(':')),
+            None => return false,
+        },
+        None => match host.split_once(':') {
+            Some((name, port)) => (name.to_string(), Some(port)),
+            None => (host.to_string(), None),
+        },
+    };
+    let port_ok = match port {
+        Some(port) => port.parse::<u16>() == Ok(local.port()),
+        None => local.port() == 80,
+    };
+    let own_address = match local {
+        SocketAddr::V4(addr) => addr.ip().to_string(),
+        SocketAddr::V6(addr) => format!("[{}]", addr.ip()),
+    };
+    port_ok
+        && (name.eq_ignore_ascii_case("localhost")
+            || name == "127.0.0.1"
+            || name == "[::1]"
+            || name == own_address)
+}
+
+/// Decode `%XX` escapes and `+` (space) in a URL component. Malformed
+/// escapes are kept as written.
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], escaped) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+            (b'+', _) => out.push(b' '),
+            (byte, _) => out.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_request_line() {
+        let request =
+            Request::parse("GET /spider?symbol=Billing.charge&depth=2&reverse HTTP/1.1\r\n")
+                .unwrap();
+        assert_eq!(request.path, "/spider");
+        assert_eq!(request.param("symbol"), Some("Billing.charge"));
+        assert_eq!(request.parse_param::<usize>("depth").unwrap(), Some(2));
+        assert!(request.flag("reverse"));
+        assert!(!request.flag("concise"));
+        assert!(request.require("language").is_err());
+        assert_eq!(
+            request.parse_param::<usize>("symbol").unwrap_err().status,
+            400
+        );
+
+        assert!(Request::parse("POST /def HTTP/1.1").is_none());
+    }
+
+    #[test]
+    fn test_percent_decode() {
+        assert_eq!(percent_decode("Foo%3A%3Abar"), "Foo::bar");
+        assert_eq!(percent_decode("a+b%20c"), "a b c");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%zz"), "%zz");
+    }
+
+    fn limits(max_concurrent: usize, query_timeout: Duration) -> QueryLimits {
+        QueryLimits {
+            max_concurrent,
+            max_per_client: max_concurrent,
+            queue_timeout: Duration::from_millis(50),
+            query_timeout,
+        }
+    }
+
+    fn spawn_server(
+        limits: QueryLimits,
+        handle: impl Fn(&Request) -> Response + Send + Sync + 'static,
+    ) -> std::net::SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || serve(listener, limits, handle));
+        addr
+    }
+
+    fn get(addr: std::net::SocketAddr, target: &str) -> String {
+        send(
+            addr,
+            &format!(
+                "GET {} HTTP/1.1\r\nHost: localhost:{}\r\n\r\n",
+                target,
+                addr.port()
+            ),
+        )
+    }
+
+    fn send(addr: std::net::SocketAddr, request: &str) -> String {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        // The server may answer before reading all of an oversized request
+        let _ = stream.write_all(request.as_bytes());
+        let mut response = String::new();
+        let _ = std::io::Read::read_to_string(&mut stream, &mut response);
+        response
+    }
+
+    #[test]
+    fn test_serve_writes_json_responses() {
+        let addr = spawn_server(limits(4, Duration::from_secs(5)), |request| {
+            Response::new(200, serde_json::json!({ "path": request.path }))
+        });
+
+        let response = get(addr, "/def?symbol=x");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+        assert!(response.contains("Content-Type: application/json"));
+        assert!(response.ends_with(r#"{"path":"/def"}"#), "{}", response);
+    }
+
+    #[test]
+    fn test_host_must_name_this_server() {
+        let v4: SocketAddr = "127.0.0.1:8080".parse().unwrap();
+        assert!(names_this_server("localhost:8080", v4));
+        assert!(names_this_server("127.0.0.1:8080", v4));
+        assert!(names_this_server("[::1]:8080", v4));
+        assert!(!names_this_server("localhost", v4));
+        assert!(!names_this_server("localhost:9090", v4));
+        assert!(!names_this_server("evil.example:8080", v4));
+        assert!(!names_this_server("localhost.evil.example:8080", v4));
+
+        let lan: SocketAddr = "192.168.1.5:80".parse().unwrap();
+        assert!(names_this_server("192.168.1.5", lan));
+        assert!(names_this_server("LOCALHOST:80", lan));
+        let v6: SocketAddr = "[fe80::1]:8080".parse().unwrap();
+        assert!(names_this_server("[fe80::1]:8080", v6));
+        assert!(!names_this_server("[fe80::1", v6));
+    }
+
+    #[test]
+    fn test_serve_rejects_oversized_and_foreign_requests() {
+        let addr = spawn_server(limits(4, Duration::from_secs(5)), |_| {
+            Response::new(200, serde_json::json!({}))
+        });
+
+        let oversized = format!(
+            "GET /def HTTP/1.1\r\nHost: localhost:{}\r\nX-Pad: {}\r\n\r\n",
+            addr.port(),
+            "a".repeat(MAX_HEAD_BYTES as usize)
+        );
+        let response = send(addr, &oversized);
+        assert!(response.starts_with("HTTP/1.1 431 "), "{}", response);
+
+        let rebound = send(addr, "GET /def HTTP/1.1\r\nHost: evil.example\r\n\r\n");
+        assert!(
+            rebound.starts_with("HTTP/1.1 403 Forbidden\r\n"),
+            "{}",
+            rebound
+        );
+        let missing = send(addr, "GET /def HTTP/1.1\r\n\r\n");
+        assert!(
+            missing.starts_with("HTTP/1.1 403 Forbidden\r\n"),
+            "{}",
+            missing
+        );
+    }
+
+    #[test]
+    fn test_serve_times_out_slow_queries() {
+        let addr = spawn_server(limits(4, Duration::from_millis(50)), |_| {
+            std::thread::sleep(Duration::from_millis(500));
+            Response::new(200, serde_json::json!({}))
+        });
+
+        let response = get(addr, "/spider?symbol=x");
+        assert!(
+            response.starts_with("HTTP/1.1 504 Gateway Timeout\r\n"),
+            "{}",
+            response
+        );
+        assert!(response.contains("timed out"), "{}", response);
+    }
+
+    #[test]
+    fn test_serve_rejects_queries_over_the_limit() {
+        let addr = spawn_server(limits(1, Duration::from_secs(5)), |_| {
+            std::thread::sleep(Duration::from_millis(500));
+            Response::new(200, serde_json::json!({}))
+        });
+
+        let slow = std::thread::spawn(move || get(addr, "/spider?symbol=x"));
+        std::thread::sleep(Duration::from_millis(100));
+        let rejected = get(addr, "/def?symbol=x");
+        assert!(
+            rejected.starts_with("HTTP/1.1 503 Service Unavailable\r\n"),
+            "{}",
+            rejected
+        );
+        assert!(slow.join().unwrap().starts_with("HTTP/1.1 200 OK\r\n"));
+    }
+
+    #[test]
+    fn test_serve_survives_a_panicking_handler() {
+        let addr = spawn_server(limits(4, Duration::from_secs(5)), |request| {
+            if request.path == "/boom" {
+                panic!("boom");
+            }
+            Response::new(200, serde_json::json!({}))
+        });
+
+        assert!(get(addr, "/boom").starts_with("HTTP/1.1 500 Internal Server Error\r\n"));
+        assert!(get(addr, "/def").starts_with("HTTP/1.1 200 OK\r\n"));
+    }
+}
@@ -0,0 +1,61 @@
+//! `--format editor`: one `file:line:column:text` line per location, the
+//! way `grep -n --column` and `rg --vimgrep` print matches.
+//!
+//! Quickfix integrations read it as they read grep output (`vim -q`,
+//! `:cexpr`, Emacs `grep-mode`, VS Code problem matchers). Paths are
+//! relative to the current directory when they're under it, and the text is
+//! the source line at the location.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use rocketindex::Location;
+
+/// Print `locations`, one per line.
+pub fn print<'a>(locations: impl IntoIterator<Item = &'a Location>) {
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let mut sources: HashMap<&Path, Option<Vec<String>>> = HashMap::new();
+    for location in locations {
+        let text = sources
+            .entry(&location.file)
+            .or_insert_with(|| {
+                std::fs::read_to_string(&location.file)
+                    .ok()
+                    .map(|source| source.lines().map(String::from).collect())
+            })
+            .as_ref()
+            .and_then(|lines| lines.get(location.line.saturating_sub(1) as usize))
+            .map_or("", String::as_str);
+        println!("{}", line(&cwd, location, text));
+    }
+}
+
+fn line(cwd: &Path, location: &Location, text: &str) -> String {
+    let file = location.file.strip_prefix(cwd).unwrap_or(&location.file);
+    format!(
+        "{}:{}:{}:{}",
+        file.display(),
+        location.line,
+        location.column,
+        text.trim_end_matches('\r')
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn formats_like_ripgrep_vimgrep() {
+        let cwd = Path::new("/ws");
+        let inside = Location::new(PathBuf::from("/ws/src/billing.py"), 12, 5);
+        assert_eq!(
+            line(cwd, &inside, "    charge(order)\r"),
+            "src/billing.py:12:5:    charge(order)"
+        );
+
+        let outside = Location::new(PathBuf::from("/vendor/lib.py"), 1, 1);
+        assert_eq!(line(cwd, &outside, ""), "/vendor/lib.py:1:1:");
+    }
+}
@@ -3,4 +3,5 @@
 //! This module exposes internal functionality for integration testing.
 //! The main entry point is the `rkt` binary in main.rs.
 
+pub mod network;
 pub mod version_check;
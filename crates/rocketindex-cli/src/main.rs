@@ -9,7 +9,7 @@
 
 use std::path::{Path, PathBuf};
 use std::process::ExitCode;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
 use clap::{CommandFactory, Parser, Subcommand};
@@ -19,14 +19,31 @@ use indicatif::{ProgressBar, ProgressStyle};
 use rayon::prelude::*;
 use rocketindex::git;
 use rocketindex::{
+    affected::{affected_tests, TestRunner},
     batch::{BatchProcessor, BatchStats, DEFAULT_BATCH_INTERVAL},
+    check::{self, Severity},
+    conditional::{self, Compilation},
     config::Config,
-    db::DEFAULT_DB_NAME,
-    find_fsproj_files, parse_fsproj,
-    pidfile::{acquire_watch_lock, find_watch_process, PidFileGuard},
-    spider::{format_spider_result, reverse_spider, spider},
-    watch::find_source_files_with_config,
-    CodeIndex, SqliteIndex,
+    context::{build_context, ContextOptions, BYTES_PER_TOKEN},
+    cycles::{cycles_to_dot, ModuleGraph},
+    db::{MaintenanceReport, OpenSite, OpenStatus, SearchFilter, DEFAULT_DB_NAME},
+    dependencies::index_dependencies,
+    external_index::index_workspace_assemblies,
+    federation, find_fsproj_files,
+    grep::{self, TextMatch},
+    languages::ruby::rails,
+    metrics::{self, Grouping},
+    owners::{self, CodeOwners},
+    pack, parse_fsproj,
+    pidfile::{acquire_watch_lock, find_watch_process, stop_watch_process, PidFileGuard},
+    rename,
+    spider::{
+        format_spider_result, reverse_spider, spider, spider_to_dot, spider_to_mermaid,
+        SpiderResult,
+    },
+    watch::{find_source_files_with_config, is_supported_file, IgnoreFiles, WatchEvent},
+    write_lock::{self, DEFAULT_LOCK_TIMEOUT},
+    CodeIndex, Confidence, Location, ReferenceKind, SqliteIndex, SymbolKind, Visibility,
 };
 use tracing_indicatif::IndicatifLayer;
 
@@ -42,10 +59,25 @@ mod exit_codes {
     pub const ERROR: u8 = 2;
 }
 
+mod baseline;
+mod budget;
+mod daemon;
+mod enrich;
+mod freshness;
 mod guidelines;
+mod http;
+mod jump;
 mod mcp;
+mod mcp_clients;
+mod notice;
+mod prebuilt;
+mod quick;
+mod service;
+mod skills;
+mod telemetry;
+mod uninstall;
 
-use rocketindex_cli::version_check;
+use rocketindex_cli::{network, version_check};
 
 // File change tracking utilities (used by setup wizards)
 #[allow(dead_code)]
@@ -83,6 +115,15 @@ impl FileChange {
 }
 
 #[allow(dead_code)]
+/// `path` made absolute against the current directory, without `.` segments.
+fn absolute_path(path: &Path) -> Result<PathBuf> {
+    Ok(std::env::current_dir()?
+        .join(path)
+        .components()
+        .filter(|c| !matches!(c, std::path::Component::CurDir))
+        .collect())
+}
+
 fn workspace_relative_path(path: &Path, cwd: &Path) -> String {
     match path.strip_prefix(cwd) {
         Ok(relative) => relative.display().to_string(),
@@ -150,6 +191,36 @@ enum OutputFormat {
     Json,
     Pretty,
     Text,
+    /// SCIP protobuf index (`rkt export` only)
+    Scip,
+    /// Exuberant Ctags `tags` file (`rkt export` only)
+    Ctags,
+    /// Emacs `TAGS` file (`rkt export` only)
+    Etags,
+    /// Graphviz DOT graph (`rkt spider` and `rkt cycles` only)
+    Dot,
+    /// Mermaid flowchart (`rkt spider` and `rkt hierarchy` only)
+    Mermaid,
+    /// Markdown document (`rkt context` only)
+    Markdown,
+    /// `file:line:column:text` per location, as `rg --vimgrep` prints
+    /// matches, for quickfix lists (`vim -q`)
+    Editor,
+}
+
+impl OutputFormat {
+    /// Formats that only make sense for `rkt export`
+    fn is_export_only(self) -> bool {
+        matches!(
+            self,
+            OutputFormat::Scip | OutputFormat::Ctags | OutputFormat::Etags
+        )
+    }
+
+    /// Graph formats, for commands that produce a graph
+    fn is_graph(self) -> bool {
+        matches!(self, OutputFormat::Dot | OutputFormat::Mermaid)
+    }
 }
 
 /// Rocket-fast F# codebase indexing and navigation tool
@@ -171,6 +242,62 @@ struct Cli {
     /// Use compact output (no pretty-printing, minimal fields)
     #[arg(long, global = true)]
     concise: bool,
+
+    /// Only report results from this project of a multi-root workspace
+    /// (see `roots` in .rocketindex.toml)
+    #[arg(long, global = true, value_name = "NAME")]
+    project: Option<String>,
+
+    /// Cap the JSON output of refs, symbols, spider and callers at about
+    /// this many bytes, keeping the first results and reporting the total
+    #[arg(long, global = true, value_name = "BYTES")]
+    max_bytes: Option<usize>,
+
+    /// Cap the JSON output of refs, symbols, spider and callers at this many
+    /// results (nodes for spider), reporting the total
+    #[arg(long, global = true, value_name = "N")]
+    max_items: Option<usize>,
+
+    /// Give up on git, dotnet and network requests (update checks, prebuilt
+    /// indexes) that take longer than this many seconds
+    #[arg(long, global = true, value_name = "SECS")]
+    timeout: Option<u64>,
+
+    /// Never touch the network: update checks only read their cache, spans
+    /// aren't exported, and anything that needs a download or upload fails
+    #[arg(long, global = true)]
+    offline: bool,
+
+    /// Spend at most about this many milliseconds reindexing stale files
+    /// before a query, then answer from the index as it is; JSON output
+    /// reports `stale_files` and `index_age_seconds`
+    #[arg(long, global = true, value_name = "MS")]
+    refresh_budget: Option<u64>,
+}
+
+/// Project set with `--project`, applied wherever the index is loaded
+static PROJECT: std::sync::OnceLock<String> = std::sync::OnceLock::new();
+
+/// `--max-bytes` and `--max-items` of this run
+static BUDGET: std::sync::OnceLock<budget::Budget> = std::sync::OnceLock::new();
+
+fn output_budget() -> budget::Budget {
+    BUDGET.get().copied().unwrap_or_default()
+}
+
+/// `--refresh-budget` of this run
+static REFRESH_BUDGET: std::sync::OnceLock<Duration> = std::sync::OnceLock::new();
+
+/// How fresh the index was when it was loaded with a refresh budget
+static FRESHNESS: std::sync::OnceLock<freshness::Freshness> = std::sync::OnceLock::new();
+
+/// Add `stale_files` and `index_age_seconds` to a query's JSON output when
+/// it ran with a refresh budget.
+fn annotate_freshness(output: serde_json::Value) -> serde_json::Value {
+    match FRESHNESS.get() {
+        Some(freshness) => freshness.annotate(output),
+        None => output,
+    }
 }
 
 #[derive(Subcommand)]
@@ -192,6 +319,48 @@ enum Commands {
         /// Force full rebuild (ignore cached index)
         #[arg(long)]
         rebuild: bool,
+
+        /// Only reindex files git reports as changed since the last index,
+        /// instead of checking every file in the workspace
+        #[arg(long, conflicts_with = "rebuild")]
+        changed: bool,
+
+        /// Also record symbol names found in string literals (reflection, DI)
+        #[arg(long)]
+        string_refs: bool,
+
+        /// First restore the index published for HEAD, or its nearest
+        /// ancestor, from URL, then reindex only what changed since
+        #[arg(long, value_name = "URL", conflicts_with_all = ["rebuild", "publish"])]
+        pull: Option<String>,
+
+        /// Then publish the index for HEAD to URL (a directory, or an HTTP
+        /// endpoint accepting PUT) for CI jobs and fresh clones to pull
+        #[arg(long, value_name = "URL")]
+        publish: Option<String>,
+
+        /// Conditional compilation symbol defined for the build (repeatable):
+        /// `--define DEBUG`, `--define feature=serde`. `rkt def` reports
+        /// whether symbols under `#if` or `#[cfg]` are compiled with them
+        #[arg(long = "define", value_name = "SYMBOL")]
+        defines: Vec<String>,
+
+        /// On a repository without an index, first index only the files
+        /// touched by the last COMMITS commits (default 50) and the files
+        /// they import, then index the rest in the background. Queries
+        /// work as soon as this returns
+        #[arg(
+            long,
+            value_name = "COMMITS",
+            num_args = 0..=1,
+            default_missing_value = "50",
+            conflicts_with_all = ["rebuild", "changed", "pull", "publish", "extract_types"]
+        )]
+        quick: Option<usize>,
+
+        /// Index what a `--quick` run left for the background
+        #[arg(long, hide = true)]
+        complete_quick: bool,
     },
 
     /// Find the definition of a symbol
@@ -206,6 +375,30 @@ enum Commands {
         /// Show git provenance information (author, date, commit)
         #[arg(long)]
         git: bool,
+
+        /// Only definitions in this file, under this directory, or matching
+        /// this glob (e.g. "crates/*-cli")
+        #[arg(long)]
+        path: Option<PathBuf>,
+    },
+
+    /// Print the source of a symbol's definition
+    Cat {
+        /// Symbol name (can be qualified like "MyModule.myFunction")
+        symbol: String,
+
+        /// Number of context lines to show before and after the definition
+        #[arg(short, long, default_value = "0")]
+        context: u32,
+    },
+
+    /// Find where a symbol went after it disappeared from the index
+    ///
+    /// Symbols that vanish are remembered; when one with the same name and
+    /// kind appears elsewhere, that's recorded as a move.
+    Moved {
+        /// Qualified name the symbol used to have
+        symbol: String,
     },
 
     /// Find references to a symbol or list references in a file
@@ -225,6 +418,53 @@ enum Commands {
         /// Number of context lines to show around each reference
         #[arg(short, long, default_value = "0")]
         context: usize,
+
+        /// Only show references at or above this confidence
+        /// (exact, qualified, heuristic, string-match)
+        #[arg(long, value_name = "LEVEL")]
+        min_confidence: Option<Confidence>,
+
+        /// Only show references of these kinds, comma-separated
+        /// (call, type-use, import, inherit, implement, read, write)
+        #[arg(long, value_name = "KIND", value_delimiter = ',')]
+        kind: Vec<ReferenceKind>,
+
+        /// Only show assignments and mutations of the value (same as
+        /// `--kind write`), to track where state changes
+        #[arg(long, conflicts_with = "kind")]
+        writes: bool,
+
+        /// Also search source text for the symbol's name, for uses the index
+        /// can't see (dynamic dispatch, reflection, string lookups)
+        #[arg(long, conflicts_with = "file")]
+        grep: bool,
+
+        /// Compare with a result saved from `rkt refs --format json` and
+        /// report only references added or removed since, e.g. to check a
+        /// deprecation is shrinking usage
+        #[arg(long, value_name = "FILE", conflicts_with = "file")]
+        diff_baseline: Option<PathBuf>,
+    },
+
+    /// Query the import graph: who opens a module, and what a file opens
+    ///
+    /// Opens are F# `open`s and their equivalents (`import`, `using`,
+    /// `use`, ...). An open is unused when its module is indexed but nothing
+    /// in the file refers to it; modules with nothing indexed under them
+    /// (packages, the standard library) are reported as external.
+    #[command(verbatim_doc_comment)]
+    Opens {
+        /// Module to find the opens of (e.g., "MyApp.Utils")
+        #[arg(long, conflicts_with_all = ["file", "unused"], required_unless_present_any = ["file", "unused"])]
+        module: Option<String>,
+
+        /// File to list the opens of, with whether each is used
+        #[arg(short, long)]
+        file: Option<PathBuf>,
+
+        /// Only list unused opens (of --file, or of every file)
+        #[arg(long)]
+        unused: bool,
     },
 
     /// Spider from an entry point symbol
@@ -239,6 +479,11 @@ enum Commands {
         /// Reverse spider: find callers instead of callees (impact analysis)
         #[arg(short, long)]
         reverse: bool,
+
+        /// Only follow edges at or above this confidence
+        /// (exact, qualified, heuristic, string-match)
+        #[arg(long, value_name = "LEVEL")]
+        min_confidence: Option<Confidence>,
     },
 
     /// Search for symbols matching a pattern
@@ -250,15 +495,37 @@ enum Commands {
         #[arg(short, long)]
         language: Option<String>,
 
-        /// Use fuzzy matching (find symbols within edit distance of pattern)
+        /// Use fuzzy matching (abbreviations like `usrsvc` and typos, best match first)
         #[arg(long)]
         fuzzy: bool,
+
+        /// Only symbols of this kind (e.g. Function, Class, Member)
+        #[arg(long)]
+        kind: Option<SymbolKind>,
+
+        /// Only symbols with this visibility (public, internal, private)
+        #[arg(long)]
+        visibility: Option<Visibility>,
+
+        /// Only symbols in this file, under this directory, or matching this
+        /// glob, where `*` matches across directories (e.g. "crates/*-cli")
+        #[arg(long)]
+        path: Option<PathBuf>,
+
+        /// Only symbols with this attribute, annotation or decorator (e.g. Obsolete)
+        #[arg(long)]
+        attribute: Option<String>,
     },
 
     /// Find direct callers of a symbol (single-level reverse spider)
     Callers {
         /// Symbol to find callers for (qualified name)
         symbol: String,
+
+        /// Only show callers at or above this confidence
+        /// (exact, qualified, heuristic, string-match)
+        #[arg(long, value_name = "LEVEL")]
+        min_confidence: Option<Confidence>,
     },
 
     /// Find classes that inherit from a parent class
@@ -267,19 +534,135 @@ enum Commands {
         parent: String,
     },
 
-    /// Find types that implement an interface
+    /// Find types that implement an interface or trait (also `rkt impls`)
+    ///
+    /// Names match without namespaces or type arguments, so `IComparable`
+    /// finds `System.IComparable<T>` and `Display` finds `std::fmt::Display`.
+    #[command(alias = "impls")]
     Implements {
-        /// Interface name to find implementers of
+        /// Interface or trait name to find implementers of
         interface: String,
     },
 
+    /// Show the full inheritance tree of a type
+    ///
+    /// Lists what the type inherits from (base classes, interfaces and
+    /// traits, Ruby mixins) and what inherits from it, transitively, with
+    /// each entry's depth. Types met again on the same path are marked as a
+    /// cycle instead of being expanded. Use `--format mermaid` for a graph.
+    Hierarchy {
+        /// Type to show the hierarchy of (qualified or bare name)
+        #[arg(value_name = "TYPE")]
+        type_name: String,
+
+        /// Maximum number of levels to follow in each direction
+        #[arg(short, long, default_value = "10")]
+        depth: usize,
+    },
+
+    /// Find dependency cycles between modules
+    ///
+    /// Builds the module dependency graph from resolved references and
+    /// opens/imports and reports groups of modules that depend on each other.
+    /// Use `--format dot` for a Graphviz graph of the cycles.
+    Cycles {
+        /// Only report cycles with at most this many modules
+        #[arg(long)]
+        max_size: Option<usize>,
+    },
+
+    /// Report references that resolve to no symbol, grouped by file
+    ///
+    /// Resolves every call, type use, base type and implemented interface.
+    /// Failures are `error` when the name is close to a workspace symbol
+    /// (likely a typo), `warning` when it names a symbol in a module the
+    /// file doesn't open (F#, Python), and `info` when nothing in the
+    /// workspace is close (usually library code). Exits 1 when a finding at
+    /// `--fail-on` or above is reported, for CI.
+    Check {
+        /// Lowest severity to report: error, warning or info
+        #[arg(long, default_value = "warning")]
+        min_severity: Severity,
+
+        /// Exit 1 when any reported finding is at least this severe
+        #[arg(long, default_value = "error")]
+        fail_on: Severity,
+    },
+
+    /// Complexity, nesting, symbol counts and fan-in/fan-out, most complex
+    /// first
+    ///
+    /// Complexity is an approximate cyclomatic complexity summed over each
+    /// file; fan-in and fan-out count the other files (or modules) that
+    /// reference it and that it references.
+    Metrics {
+        /// Roll up by file, or by module (directory)
+        #[arg(long, default_value = "file")]
+        by: Grouping,
+
+        /// Show only the N most complex (0 for all)
+        #[arg(long, default_value = "20")]
+        top: usize,
+    },
+
+    /// Files or modules ranked by churn × complexity × fan-in, where
+    /// refactoring pays off most
+    ///
+    /// Churn is the number of commits that changed the file since --since;
+    /// complexity and fan-in are as `rkt metrics` reports them.
+    Hotspots {
+        /// Count commits since this date (anything `git log --since`
+        /// accepts), or "all" for the whole history
+        #[arg(long, value_name = "DATE", default_value = "1 year ago")]
+        since: String,
+
+        /// Roll up by file, or by module (directory)
+        #[arg(long, default_value = "file")]
+        by: Grouping,
+
+        /// Show only the N hottest (0 for all)
+        #[arg(long, default_value = "20")]
+        top: usize,
+    },
+
     /// Watch for file changes and update the index
     Watch {
         /// Root directory to watch (defaults to current directory)
         #[arg(short, long, default_value = ".")]
         root: PathBuf,
+
+        /// Detach and log to .rocketindex/logs/watch.log (stop with 'rkt stop')
+        #[arg(long)]
+        background: bool,
+
+        /// Also index the public symbols of imported packages (node_modules,
+        /// vendored gems, NuGet sources), so definitions land in them
+        #[arg(long)]
+        dependencies: bool,
+    },
+
+    /// Watch mode that also keeps the index in memory and answers queries
+    ///
+    /// `rkt spider`, `rkt callers` and the HTTP API's /spider and /callers
+    /// ask the daemon over `.rocketindex/daemon.sock` (a named pipe on
+    /// Windows) before loading the index themselves.
+    #[command(args_conflicts_with_subcommands = true)]
+    Daemon {
+        #[command(subcommand)]
+        action: Option<DaemonAction>,
+
+        /// Root directory to serve (defaults to current directory)
+        #[arg(short, long, default_value = ".")]
+        root: PathBuf,
+
+        /// Detach and log to .rocketindex/logs/watch.log (stop with 'rkt stop')
+        #[arg(long)]
+        background: bool,
     },
 
+    /// Stop the watch session (or daemon) running for this directory
+    Stop,
+
     /// Extract type information from a project (requires dotnet fsi)
     #[command(hide = true)]
     ExtractTypes {
@@ -321,9 +704,221 @@ enum Commands {
         symbol: String,
     },
 
+    /// Show who owns a symbol (CODEOWNERS) and who wrote it (git blame)
+    Owner {
+        /// Symbol name (qualified or partial)
+        symbol: String,
+    },
+
+    /// Rename a symbol and every reference to it across the workspace
+    ///
+    /// References written through the symbol are renamed too (`Utils.run`
+    /// when renaming the module `Utils`), using the same engine as the
+    /// language server's rename. Edits are checked against the files on disk
+    /// before anything is written; originals are copied to
+    /// `.rocketindex/backups/` first. The old name is kept as an alias (see
+    /// `rkt alias`).
+    Rename {
+        /// Qualified name of the symbol to rename (e.g. "MyApp.Utils.helper")
+        symbol: String,
+
+        /// New short name
+        new_name: String,
+
+        /// Print the edits as a unified diff instead of applying them
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Also rename matches in string literals (DI registrations,
+        /// reflection; needs `rkt index --string-refs`). They're flagged for
+        /// checking by hand: preview with --dry-run first
+        #[arg(long)]
+        strings: bool,
+    },
+
+    /// Manage "old name → new name" aliases for renamed symbols
+    ///
+    /// Queries for an old name (def, refs, callers, spider) answer for the
+    /// new one, with a deprecation note. `rkt rename` adds aliases itself.
+    Alias {
+        #[command(subcommand)]
+        action: AliasAction,
+    },
+
+    /// Export or import hand-recorded metadata as a committable file
+    ///
+    /// The index is a cache; `rkt meta export` saves what was recorded by
+    /// hand (aliases, notes, watchlists and layer rules) to
+    /// .rocketindex-meta.json so it can be committed and shared, and
+    /// `rkt meta import` merges it into a fresh index. Entries are keyed by
+    /// symbol GUID, falling back to the qualified name.
+    Meta {
+        #[command(subcommand)]
+        action: MetaAction,
+    },
+
+    /// Add, remove or list notes on symbols
+    ///
+    /// Notes are attached to the symbol's GUID, so they stay with it when
+    /// other symbols of the same name come and go.
+    Note {
+        #[command(subcommand)]
+        action: NoteAction,
+    },
+
+    /// Keep named lists of symbols to keep an eye on
+    Watchlist {
+        #[command(subcommand)]
+        action: WatchlistAction,
+    },
+
+    /// Record and check layering rules ("src/Domain/* must not depend on src/Web/*")
+    ///
+    /// Paths are relative to the workspace root; `*` matches any run of
+    /// characters. `rkt layers check` exits 1 if any file breaks a rule.
+    Layers {
+        #[command(subcommand)]
+        action: LayersAction,
+    },
+
+    /// List the symbols in a file, or the symbols touched by uncommitted changes
+    ///
+    /// With --changed, each changed line of `git diff HEAD` is mapped to the
+    /// symbol defined closest above it, e.g. for commit messages or picking
+    /// which tests to run.
+    SymbolsIn {
+        /// File to list symbols for
+        #[arg(required_unless_present = "changed", conflicts_with = "changed")]
+        file: Option<PathBuf>,
+
+        /// Use the symbols enclosing the working tree's uncommitted changes
+        #[arg(long)]
+        changed: bool,
+
+        /// With --changed, only consider staged changes
+        #[arg(long, requires = "changed")]
+        staged: bool,
+    },
+
+    /// List the tests affected by uncommitted changes
+    ///
+    /// Maps the working-tree diff to symbols, follows their callers, and keeps
+    /// the ones in test files. With --runner, prints a filter to pass to the
+    /// test runner instead, e.g. `dotnet test --filter "$(rkt affected-tests --runner dotnet)"`.
+    AffectedTests {
+        /// Maximum caller depth between a test and a changed symbol
+        #[arg(short, long, default_value = "5")]
+        depth: usize,
+
+        /// Only consider staged changes
+        #[arg(long)]
+        staged: bool,
+
+        /// Print a filter for this runner (dotnet, rspec, pytest)
+        #[arg(long, value_name = "RUNNER")]
+        runner: Option<TestRunner>,
+
+        /// Only follow caller edges at or above this confidence
+        /// (exact, qualified, heuristic, string-match)
+        #[arg(long, value_name = "LEVEL")]
+        min_confidence: Option<Confidence>,
+    },
+
+    /// List the tests that (transitively) call a symbol
+    ///
+    /// Follows callers of the symbol and keeps the ones classified as tests
+    /// (by attributes like `[<Fact>]` or `#[test]`, or `test_*` names in test
+    /// files), e.g. to know what to run before refactoring it.
+    TestsFor {
+        /// Qualified name of the symbol
+        symbol: String,
+
+        /// Maximum caller depth between a test and the symbol
+        #[arg(short, long, default_value_t = TESTS_FOR_DEPTH)]
+        depth: usize,
+
+        /// Only follow caller edges at or above this confidence
+        /// (exact, qualified, heuristic, string-match)
+        #[arg(long, value_name = "LEVEL")]
+        min_confidence: Option<Confidence>,
+    },
+
+    /// Show the blast radius of a diff: changed symbols, their callers, entry points and tests
+    ///
+    /// Without --base this is the uncommitted changes; with --base it is
+    /// everything since the branch forked from BASE, e.g. `rkt impact --base main`
+    /// before opening a pull request.
+    Impact {
+        /// Branch, tag or commit to compare against
+        #[arg(long)]
+        base: Option<String>,
+
+        /// Maximum caller depth to follow from a changed symbol
+        #[arg(short, long, default_value = "5")]
+        depth: usize,
+
+        /// Only follow caller edges at or above this confidence
+        /// (exact, qualified, heuristic, string-match)
+        #[arg(long, value_name = "LEVEL")]
+        min_confidence: Option<Confidence>,
+    },
+
+    /// Symbols added, removed and changed between two revisions, and the callers affected
+    ///
+    /// Both revisions are indexed in memory from git, so neither needs to be
+    /// checked out, e.g. `rkt compare-branches main feature/billing`.
+    CompareBranches {
+        /// Older revision (branch, tag or commit)
+        base: String,
+
+        /// Newer revision (branch, tag or commit)
+        head: String,
+
+        /// Maximum caller depth to follow from a changed or removed symbol
+        #[arg(short, long, default_value = "3")]
+        depth: usize,
+
+        /// Only follow caller edges at or above this confidence
+        /// (exact, qualified, heuristic, string-match)
+        #[arg(long, value_name = "LEVEL")]
+        min_confidence: Option<Confidence>,
+    },
+
     /// Check RocketIndex health and configuration
     Doctor,
 
+    /// Show where the index came from: schema and tool version, creation
+    /// time, workspace root, git commit, per-language counts and size
+    Info,
+
+    /// Export the index for other tools (`--format scip`, `ctags` or `etags`)
+    Export {
+        /// Output file (defaults to index.scip for SCIP)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Write a compact, read-only copy of the index for distribution
+    ///
+    /// The pack (index.db + manifest.json) can be stored as a build artifact
+    /// and dropped into `.rocketindex/pack` of another checkout, where
+    /// commands open it read-only when no local index exists.
+    PackIndex {
+        /// Output directory (defaults to .rocketindex/pack)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Replace an existing pack in the output directory
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Compact the index: prune orphaned references, rebuild full-text
+    /// search, refresh statistics and vacuum
+    ///
+    /// Watch mode does this on its own once enough of the file is free space.
+    Maintain,
+
     /// Show documentation for a symbol
     Doc {
         /// Symbol name (qualified name like "MyModule.myFunction")
@@ -331,12 +926,60 @@ enum Commands {
     },
 
     /// Enrich a symbol with debugging context (for stacktrace analysis)
+    ///
+    /// The JSON output is a versioned contract (see --json-schema): fields
+    /// are only ever added, and `schema_version` changes if one is renamed,
+    /// removed or retyped.
     Enrich {
+        /// Symbol name (qualified or partial)
+        #[arg(required_unless_present = "json_schema")]
+        symbol: Option<String>,
+
+        /// Caller edges to follow from the symbol
+        #[arg(long, default_value_t = 1)]
+        callers_depth: usize,
+
+        /// Dependency edges to follow from the symbol
+        #[arg(long, default_value_t = 1)]
+        deps_depth: usize,
+
+        /// Include the tests that (transitively) call the symbol
+        #[arg(long)]
+        tests: bool,
+
+        /// Include the HTTP routes the symbol handles (from route attributes)
+        #[arg(long)]
+        routes: bool,
+
+        /// Print the JSON Schema of the output and exit
+        #[arg(long)]
+        json_schema: bool,
+    },
+
+    /// Self-contained context for a symbol, for AI agents
+    ///
+    /// Its source, doc comment, the signatures of what it depends on and its
+    /// call sites with surrounding lines, cut to fit a token budget. Use
+    /// `--format markdown` to paste it into a prompt.
+    Context {
         /// Symbol name (qualified or partial)
         symbol: String,
+
+        /// Lines of context above and below each call site
+        #[arg(long, default_value_t = 3)]
+        lines: usize,
+
+        /// Token budget for the bundle (estimated at 4 bytes per token)
+        #[arg(long, default_value_t = 4000)]
+        max_tokens: usize,
     },
 
     /// Analyze a stacktrace and enrich each frame with code context
+    ///
+    /// Also reports where the trace's exception type is defined and every
+    /// line that raises or throws it, for frames inlined or cut off before
+    /// the origin. With --sourcemaps, frames in bundled or minified
+    /// JavaScript are mapped back to their original sources first.
     Analyze {
         /// Stacktrace text (if not provided, reads from stdin)
         stacktrace: Option<String>,
@@ -344,18 +987,68 @@ enum Commands {
         /// Only include user code frames (filter out framework/library code)
         #[arg(long)]
         user_only: bool,
+
+        /// Map JavaScript frames through the source maps of their files
+        #[arg(long)]
+        sourcemaps: bool,
+
+        /// Where to look for bundles named by URL, such as a build output
+        /// directory (repeatable; implies --sourcemaps)
+        #[arg(long, value_name = "DIR")]
+        sourcemap_dir: Vec<PathBuf>,
+
+        /// Keep reading a log, FILE (from its end, as `tail -f` does) or
+        /// stdin, and report each stacktrace as it appears; with
+        /// `--format json`, one object per line
+        #[arg(long, value_name = "FILE", num_args = 0..=1, conflicts_with = "stacktrace")]
+        follow: Option<Option<PathBuf>>,
+
+        /// Analyze an error tracker's event instead: a Sentry issue URL
+        /// (fetched over https with $SENTRY_AUTH_TOKEN from sentry.io or the
+        /// $SENTRY_URL host), or a Sentry event or Rollbar item saved as JSON
+        /// ("-" for stdin)
+        #[arg(
+            long,
+            visible_alias = "rollbar",
+            value_name = "URL|FILE",
+            conflicts_with_all = ["stacktrace", "follow"]
+        )]
+        sentry: Option<String>,
     },
 
     /// Set up editor integrations (slash commands, rules, etc.)
     Setup {
-        /// Editor to set up: claude, cursor, copilot, zed, gemini
+        /// Editor to set up: claude, claude-desktop, cursor, copilot, zed, gemini
         editor: String,
+
+        #[command(flatten)]
+        options: SetupOptions,
     },
 
     /// Start a coding session (runs setup if needed, then starts watch mode)
     Start {
         /// Target agent: claude, cursor, copilot, zed, gemini
         agent: String,
+
+        #[command(flatten)]
+        options: SetupOptions,
+
+        /// Run watch mode detached, logging to .rocketindex/logs/watch.log
+        /// (stop with 'rkt stop')
+        #[arg(long)]
+        background: bool,
+    },
+
+    /// Remove everything `rkt setup` added to this project
+    ///
+    /// Deletes installed agents and skills, rules files setup created and the
+    /// `.rocketindex/` directory, and cuts the RocketIndex sections out of
+    /// files it edited (CLAUDE.md, .gitignore, ...).
+    #[command(alias = "teardown")]
+    Uninstall {
+        /// List what would be removed without changing anything
+        #[arg(long)]
+        dry_run: bool,
     },
 
     /// Generate shell completions
@@ -373,57 +1066,257 @@ enum Commands {
     },
 
     /// Start MCP server for AI assistant integration
+    ///
+    /// With --http, serve `def`, `refs`, `symbols`, `spider` and `callers` as a
+    /// JSON API instead (e.g. `GET /callers?symbol=Billing.charge`).
+    #[command(args_conflicts_with_subcommands = true)]
     Serve {
         #[command(subcommand)]
         action: Option<ServeAction>,
+
+        /// Serve the HTTP JSON API on this port (0 picks a free one)
+        #[arg(long, value_name = "PORT")]
+        http: Option<u16>,
+
+        /// Address the HTTP API listens on
+        #[arg(
+            long,
+            value_name = "ADDR",
+            default_value = "127.0.0.1",
+            requires = "http"
+        )]
+        bind: std::net::IpAddr,
     },
 
     /// Update RocketIndex to the latest version
-    Update,
+    Update {
+        /// Release channel: stable or beta (default: the channel of the installed version)
+        #[arg(long)]
+        channel: Option<version_check::Channel>,
+
+        /// Restore the binary replaced by the last update
+        #[arg(long, conflicts_with = "channel")]
+        rollback: bool,
+
+        /// Install a release without checking its signature, when this build
+        /// has no update signing key (source and `cargo install` builds)
+        #[arg(long, conflicts_with = "rollback")]
+        allow_unsigned: bool,
+    },
 }
 
-/// Actions for the serve subcommand
+/// Options shared by `rkt setup` and `rkt start`, so both can be scripted
+#[derive(clap::Args, Clone, Default)]
+struct SetupOptions {
+    /// Accept every default without prompting (devcontainers, provisioning scripts)
+    #[arg(short = 'y', long)]
+    yes: bool,
+
+    /// Agents and skills to install, comma-separated, or "none" (claude only)
+    #[arg(long, value_delimiter = ',', value_name = "NAMES")]
+    agents: Option<Vec<String>>,
+
+    /// Don't build the index during setup
+    #[arg(long)]
+    no_index: bool,
+
+    /// Also offer the agents and skills in this pack (directory or git URL; claude only)
+    #[arg(long)]
+    pack: Option<String>,
+
+    /// Register `rkt serve` in the editor's MCP config (.mcp.json, .cursor/mcp.json, ...)
+    #[arg(long)]
+    mcp: bool,
+}
+
+/// Actions for the alias subcommand
 #[derive(Subcommand)]
-enum ServeAction {
-    /// Add a project to the MCP server configuration
+enum AliasAction {
+    /// Record that a symbol was renamed
     Add {
-        /// Path to the project root directory
-        path: PathBuf,
+        /// Qualified name the symbol used to have
+        old: String,
+        /// Qualified name it has now
+        new: String,
     },
-    /// Remove a project from the MCP server configuration
+    /// Forget the alias for an old name
     Remove {
-        /// Path to the project root directory
-        path: PathBuf,
+        /// Old qualified name
+        old: String,
     },
-    /// List registered projects
+    /// List aliases
     List,
 }
 
-fn main() -> ExitCode {
-    // Parse CLI first so we can respect --quiet for logging
-    let cli = Cli::parse();
-
-    // Initialize logging with indicatif integration
-    // This prevents terminal corruption when progress bars and log messages overlap
-    let indicatif_layer = IndicatifLayer::new();
+/// Actions for the meta subcommand
+#[derive(Subcommand)]
+enum MetaAction {
+    /// Write the index's metadata to a file
+    Export {
+        /// File to write (defaults to .rocketindex-meta.json)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Merge metadata from a file into the index
+    Import {
+        /// File to read (defaults to .rocketindex-meta.json)
+        file: Option<PathBuf>,
+    },
+}
 
-    use tracing_subscriber::layer::SubscriberExt;
-    use tracing_subscriber::util::SubscriberInitExt;
+/// Actions for the note subcommand
+#[derive(Subcommand)]
+enum NoteAction {
+    /// Add a note on a symbol
+    Add {
+        /// Qualified name of the symbol
+        symbol: String,
+        /// Text of the note
+        text: String,
+    },
+    /// Delete a note
+    Remove {
+        /// Id of the note, from `rkt note list`
+        id: i64,
+    },
+    /// List notes, on one symbol or all of them
+    List {
+        /// Qualified name of the symbol
+        symbol: Option<String>,
+    },
+}
 
-    // Set log level based on --quiet flag
-    // When quiet, only show errors (suppress warnings that break JSON parsing)
-    let log_level = if cli.quiet {
-        tracing::Level::ERROR
-    } else {
-        tracing::Level::WARN
-    };
+/// Actions for the watchlist subcommand
+#[derive(Subcommand)]
+enum WatchlistAction {
+    /// Put a symbol on a watchlist
+    Add {
+        /// Name of the watchlist
+        list: String,
+        /// Qualified name of the symbol
+        symbol: String,
+    },
+    /// Take a symbol off a watchlist
+    Remove {
+        /// Name of the watchlist
+        list: String,
+        /// Qualified name (or GUID) of the symbol
+        symbol: String,
+    },
+    /// Show the symbols on a watchlist, or on all of them
+    Show {
+        /// Name of the watchlist
+        list: Option<String>,
+    },
+}
+
+/// Actions for the layers subcommand
+#[derive(Subcommand)]
+enum LayersAction {
+    /// Forbid files matching FROM from depending on files matching TO
+    Add {
+        /// Path pattern of the dependent files, e.g. "src/Domain/*"
+        from: String,
+        /// Path pattern they must not depend on, e.g. "src/Web/*"
+        to: String,
+    },
+    /// Forget a layering rule
+    Remove { from: String, to: String },
+    /// List layering rules
+    List,
+    /// Report file dependencies that break a rule
+    Check,
+}
+
+/// Actions for the daemon subcommand
+#[derive(Subcommand)]
+enum DaemonAction {
+    /// Run the daemon as a user service that starts at login
+    /// (launchd on macOS, systemd on Linux, Task Scheduler on Windows)
+    Install {
+        /// Root directory to serve (defaults to current directory)
+        #[arg(short, long, default_value = ".")]
+        root: PathBuf,
+    },
+    /// Stop and remove the service installed for this directory
+    Uninstall {
+        /// Root directory the service was installed for (defaults to current directory)
+        #[arg(short, long, default_value = ".")]
+        root: PathBuf,
+    },
+}
+
+/// Actions for the serve subcommand
+#[derive(Subcommand)]
+enum ServeAction {
+    /// Add a project to the MCP server configuration
+    Add {
+        /// Path to the project root directory
+        path: PathBuf,
+    },
+    /// Remove a project from the MCP server configuration
+    Remove {
+        /// Path to the project root directory
+        path: PathBuf,
+    },
+    /// List registered projects
+    List,
+}
+
+fn main() -> ExitCode {
+    // Parse CLI first so we can respect --quiet for logging
+    let cli = Cli::parse();
+
+    // Initialize logging with indicatif integration
+    // This prevents terminal corruption when progress bars and log messages overlap
+    let indicatif_layer = IndicatifLayer::new();
+
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::util::SubscriberInitExt;
+    use tracing_subscriber::Layer;
+
+    // Set log level based on --quiet flag
+    // When quiet, only show errors (suppress warnings that break JSON parsing)
+    let log_level = if cli.quiet {
+        tracing::Level::ERROR
+    } else {
+        tracing::Level::WARN
+    };
+
+    // The log level only filters what reaches the terminal; exported spans
+    // have their own filter
+    let terminal_filter =
+        || tracing_subscriber::EnvFilter::from_default_env().add_directive(log_level.into());
+    let (otel_layer, _telemetry) = telemetry::init(cli.offline);
 
     tracing_subscriber::registry()
-        .with(tracing_subscriber::fmt::layer().with_writer(indicatif_layer.get_stderr_writer()))
-        .with(tracing_subscriber::EnvFilter::from_default_env().add_directive(log_level.into()))
-        .with(indicatif_layer)
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_writer(indicatif_layer.get_stderr_writer())
+                .with_filter(terminal_filter()),
+        )
+        .with(indicatif_layer.with_filter(terminal_filter()))
+        .with(otel_layer)
         .init();
 
+    rocketindex::db::set_tool_version(env!("RKT_VERSION"));
+    if let Some(project) = cli.project {
+        let _ = PROJECT.set(project);
+    }
+    notice::init(cli.format == OutputFormat::Json, cli.quiet);
+    let _ = BUDGET.set(budget::Budget {
+        max_bytes: cli.max_bytes,
+        max_items: cli.max_items,
+    });
+    if let Some(secs) = cli.timeout {
+        rocketindex::subprocess::set_timeout(Duration::from_secs(secs));
+        network::set_timeout(Duration::from_secs(secs));
+    }
+    network::set_offline(cli.offline);
+    if let Some(ms) = cli.refresh_budget {
+        let _ = REFRESH_BUDGET.set(Duration::from_millis(ms));
+    }
+
     match run(cli.command, cli.format, cli.quiet, cli.concise) {
         Ok(code) => ExitCode::from(code),
         Err(e) => {
@@ -442,29 +1335,135 @@ fn main() -> ExitCode {
 }
 
 fn run(command: Commands, format: OutputFormat, quiet: bool, concise: bool) -> Result<u8> {
+    if format.is_export_only() && !matches!(command, Commands::Export { .. }) {
+        anyhow::bail!(
+            "--format {} is only supported by 'rkt export'",
+            format_name(format)
+        );
+    }
+    match (&command, format) {
+        (
+            Commands::Def { .. }
+            | Commands::Refs { .. }
+            | Commands::Symbols { .. }
+            | Commands::Callers { .. }
+            | Commands::Subclasses { .. }
+            | Commands::Implements { .. }
+            | Commands::Check { .. },
+            OutputFormat::Editor,
+        ) => {}
+        (_, OutputFormat::Editor) => anyhow::bail!(
+            "--format editor is only supported by commands that list locations: \
+             'rkt def', 'rkt refs', 'rkt symbols', 'rkt callers', 'rkt subclasses', \
+             'rkt implements' and 'rkt check'"
+        ),
+        (Commands::Spider { .. }, _)
+        | (Commands::Cycles { .. }, OutputFormat::Dot)
+        | (Commands::Hierarchy { .. }, OutputFormat::Mermaid) => {}
+        (Commands::Cycles { .. }, _) if format.is_graph() => anyhow::bail!(
+            "--format {} is not supported by 'rkt cycles' (use dot)",
+            format_name(format)
+        ),
+        (Commands::Hierarchy { .. }, _) if format.is_graph() => anyhow::bail!(
+            "--format {} is not supported by 'rkt hierarchy' (use mermaid)",
+            format_name(format)
+        ),
+        _ if format.is_graph() => anyhow::bail!(
+            "--format {} is only supported by 'rkt spider', 'rkt cycles' and 'rkt hierarchy'",
+            format_name(format)
+        ),
+        (Commands::Context { .. }, _) => {}
+        (_, OutputFormat::Markdown) => {
+            anyhow::bail!("--format markdown is only supported by 'rkt context'")
+        }
+        _ => {}
+    }
+
     match command {
         Commands::Index {
             root,
             extract_types,
             batch_size,
             rebuild,
-        } => cmd_index(&root, extract_types, batch_size, rebuild, format, quiet),
+            changed,
+            string_refs,
+            pull,
+            publish,
+            defines,
+            quick,
+            complete_quick,
+        } => {
+            if complete_quick {
+                return cmd_complete_quick_index(&root, batch_size, string_refs);
+            }
+            let db_path = root.join(".rocketindex").join(DEFAULT_DB_NAME);
+            if let Some(commits) = quick.filter(|_| !db_path.exists()) {
+                let code = cmd_index_quick(&root, commits, batch_size, string_refs, format, quiet)?;
+                if code == exit_codes::SUCCESS {
+                    record_defines(&root, &defines)?;
+                }
+                return Ok(code);
+            }
+            let pulled = match &pull {
+                Some(url) => pull_index(&root, url)?,
+                None => false,
+            };
+            let code = cmd_index(
+                &root,
+                extract_types,
+                batch_size,
+                rebuild,
+                changed || pulled,
+                string_refs,
+                format,
+                quiet,
+            )?;
+            if code == exit_codes::SUCCESS {
+                record_defines(&root, &defines)?;
+            }
+            match publish {
+                Some(url) if code == exit_codes::SUCCESS => publish_index(&root, &url),
+                _ => Ok(code),
+            }
+        }
 
         Commands::Def {
             symbol,
             context,
             git,
-        } => cmd_def(&symbol, context, git, format, quiet, concise),
+            path,
+        } => {
+            let filter = SearchFilter {
+                path: path.map(|p| absolute_path(&p)).transpose()?,
+                ..SearchFilter::default()
+            };
+            cmd_def(&symbol, context, git, &filter, format, quiet, concise)
+        }
+        Commands::Cat { symbol, context } => cmd_cat(&symbol, context, format, quiet, concise),
+        Commands::Moved { symbol } => cmd_moved(&symbol, format, quiet, concise),
         Commands::Refs {
             file,
             symbol,
             path,
             context,
+            min_confidence,
+            kind,
+            writes,
+            grep,
+            diff_baseline,
         } => cmd_refs(
             file.as_deref(),
             symbol.as_deref(),
             path.as_deref(),
             context,
+            min_confidence.unwrap_or(Confidence::StringMatch),
+            if writes {
+                &[ReferenceKind::Write]
+            } else {
+                &kind
+            },
+            grep,
+            diff_baseline.as_deref(),
             format,
             quiet,
             concise,
@@ -473,16 +1472,90 @@ fn run(command: Commands, format: OutputFormat, quiet: bool, concise: bool) -> R
             symbol,
             depth,
             reverse,
-        } => cmd_spider(&symbol, depth, reverse, format, quiet, concise),
+            min_confidence,
+        } => cmd_spider(
+            &symbol,
+            depth,
+            reverse,
+            min_confidence.unwrap_or(Confidence::StringMatch),
+            format,
+            quiet,
+            concise,
+        ),
         Commands::Symbols {
             pattern,
             language,
             fuzzy,
-        } => cmd_symbols(&pattern, language.as_deref(), fuzzy, format, quiet, concise),
-        Commands::Callers { symbol } => cmd_callers(&symbol, format, quiet, concise),
+            kind,
+            visibility,
+            path,
+            attribute,
+        } => {
+            let filter = SearchFilter {
+                language,
+                kind,
+                visibility,
+                path: path.map(|p| absolute_path(&p)).transpose()?,
+                attribute,
+            };
+            cmd_symbols(&pattern, &filter, fuzzy, format, quiet, concise)
+        }
+        Commands::Callers {
+            symbol,
+            min_confidence,
+        } => cmd_callers(
+            &symbol,
+            min_confidence.unwrap_or(Confidence::StringMatch),
+            format,
+            quiet,
+            concise,
+        ),
+        Commands::Opens {
+            module,
+            file,
+            unused,
+        } => cmd_opens(module.as_deref(), file.as_deref(), unused, format, quiet),
         Commands::Subclasses { parent } => cmd_subclasses(&parent, format, quiet, concise),
         Commands::Implements { interface } => cmd_implements(&interface, format, quiet, concise),
-        Commands::Watch { root } => cmd_watch(&root, format, quiet),
+        Commands::Hierarchy { type_name, depth } => {
+            cmd_hierarchy(&type_name, depth, format, quiet, concise)
+        }
+        Commands::Cycles { max_size } => cmd_cycles(max_size, format, quiet, concise),
+        Commands::Check {
+            min_severity,
+            fail_on,
+        } => cmd_check(min_severity, fail_on, format, quiet, concise),
+        Commands::Metrics { by, top } => cmd_metrics(by, top, format, quiet, concise),
+        Commands::Hotspots { since, by, top } => {
+            cmd_hotspots(&since, by, top, format, quiet, concise)
+        }
+        Commands::Watch {
+            root,
+            background,
+            dependencies,
+        } => {
+            if background {
+                cmd_watch_background(&root, false, dependencies, format, quiet)
+            } else {
+                cmd_watch(&root, false, dependencies, format, quiet)
+            }
+        }
+        Commands::Daemon {
+            action: Some(action),
+            ..
+        } => cmd_daemon_service(action, format, quiet),
+        Commands::Daemon {
+            action: None,
+            root,
+            background,
+        } => {
+            if background {
+                cmd_watch_background(&root, true, false, format, quiet)
+            } else {
+                cmd_watch(&root, true, false, format, quiet)
+            }
+        }
+        Commands::Stop => cmd_stop(format, quiet),
         Commands::ExtractTypes {
             project,
             output,
@@ -493,15 +1566,160 @@ fn run(command: Commands, format: OutputFormat, quiet: bool, concise: bool) -> R
         }
         Commands::Blame { target } => cmd_blame(&target, format, quiet, concise),
         Commands::History { symbol } => cmd_history(&symbol, format, quiet, concise),
+        Commands::Owner { symbol } => cmd_owner(&symbol, format, quiet, concise),
+        Commands::Rename {
+            symbol,
+            new_name,
+            dry_run,
+            strings,
+        } => cmd_rename(&symbol, &new_name, dry_run, strings, format, quiet),
+        Commands::Alias { action } => cmd_alias(action, format, quiet, concise),
+        Commands::Meta { action } => cmd_meta(action, format, quiet, concise),
+        Commands::Note { action } => cmd_note(action, format, quiet, concise),
+        Commands::Watchlist { action } => cmd_watchlist(action, format, quiet, concise),
+        Commands::Layers { action } => cmd_layers(action, format, quiet, concise),
+        Commands::SymbolsIn {
+            file,
+            changed: _,
+            staged,
+        } => cmd_symbols_in(file.as_deref(), staged, format, quiet, concise),
+        Commands::AffectedTests {
+            depth,
+            staged,
+            runner,
+            min_confidence,
+        } => cmd_affected_tests(
+            depth,
+            staged,
+            runner,
+            min_confidence.unwrap_or(Confidence::StringMatch),
+            format,
+            quiet,
+            concise,
+        ),
+        Commands::TestsFor {
+            symbol,
+            depth,
+            min_confidence,
+        } => cmd_tests_for(
+            &symbol,
+            depth,
+            min_confidence.unwrap_or(Confidence::StringMatch),
+            format,
+            quiet,
+            concise,
+        ),
+        Commands::Impact {
+            base,
+            depth,
+            min_confidence,
+        } => cmd_impact(
+            base.as_deref(),
+            depth,
+            min_confidence.unwrap_or(Confidence::StringMatch),
+            format,
+            quiet,
+            concise,
+        ),
+        Commands::CompareBranches {
+            base,
+            head,
+            depth,
+            min_confidence,
+        } => cmd_compare_branches(
+            &base,
+            &head,
+            depth,
+            min_confidence.unwrap_or(Confidence::StringMatch),
+            format,
+            quiet,
+            concise,
+        ),
         Commands::Doctor => cmd_doctor(format, quiet),
+        Commands::Info => cmd_info(format, quiet),
+        Commands::Export { output } => cmd_export(output.as_deref(), format, quiet),
+        Commands::PackIndex { output, force } => {
+            cmd_pack_index(output.as_deref(), force, format, quiet)
+        }
+        Commands::Maintain => cmd_maintain(format, quiet),
         Commands::Doc { symbol } => cmd_doc(&symbol, format, quiet),
-        Commands::Enrich { symbol } => cmd_enrich(&symbol, format, quiet),
+        Commands::Enrich {
+            json_schema: true, ..
+        } => {
+            println!("{}", serde_json::to_string_pretty(&enrich::schema())?);
+            Ok(exit_codes::SUCCESS)
+        }
+        Commands::Enrich {
+            symbol,
+            callers_depth,
+            deps_depth,
+            tests,
+            routes,
+            json_schema: false,
+        } => {
+            let options = EnrichOptions {
+                callers_depth,
+                deps_depth,
+                tests,
+                routes,
+            };
+            cmd_enrich(
+                symbol.as_deref().unwrap_or_default(),
+                &options,
+                format,
+                quiet,
+            )
+        }
+        Commands::Context {
+            symbol,
+            lines,
+            max_tokens,
+        } => {
+            let options = ContextOptions {
+                call_site_lines: lines,
+                max_bytes: max_tokens.saturating_mul(BYTES_PER_TOKEN),
+            };
+            cmd_context(&symbol, &options, format, quiet, concise)
+        }
         Commands::Analyze {
             stacktrace,
             user_only,
-        } => cmd_analyze(stacktrace.as_deref(), user_only, format, quiet),
-        Commands::Setup { editor } => cmd_setup(&editor, format, quiet),
-        Commands::Start { agent } => cmd_start(&agent, format, quiet),
+            sourcemaps,
+            sourcemap_dir,
+            follow,
+            sentry,
+        } => {
+            // Bundles named by URL are also looked for in the workspace
+            let sourcemap_dirs = (sourcemaps || !sourcemap_dir.is_empty()).then(|| {
+                let mut dirs = sourcemap_dir;
+                dirs.push(PathBuf::from("."));
+                dirs
+            });
+            match follow {
+                Some(file) => cmd_analyze_follow(
+                    file.as_deref(),
+                    user_only,
+                    sourcemap_dirs.as_deref(),
+                    format,
+                    quiet,
+                ),
+                None => cmd_analyze(
+                    stacktrace.as_deref(),
+                    sentry.as_deref(),
+                    user_only,
+                    sourcemap_dirs.as_deref(),
+                    format,
+                    quiet,
+                ),
+            }
+        }
+        Commands::Setup { editor, options } => cmd_setup(&editor, &options, format, quiet),
+        Commands::Start {
+            agent,
+            options,
+            background,
+        } => cmd_start(&agent, &options, background, format, quiet),
+        Commands::Uninstall { dry_run } => cmd_uninstall(dry_run, format, quiet),
         Commands::Completions { shell } => {
             generate(shell, &mut Cli::command(), "rkt", &mut std::io::stdout());
             Ok(exit_codes::SUCCESS)
@@ -512,10 +1730,21 @@ fn run(command: Commands, format: OutputFormat, quiet: bool, concise: bool) -> R
             Ok(exit_codes::SUCCESS)
         }
 
-        Commands::Serve { action } => cmd_serve(action),
+        Commands::Serve { action, http, bind } => match http {
+            Some(port) => cmd_serve_http(bind, port, format, quiet),
+            None => cmd_serve(action),
+        },
 
-        Commands::Update => {
-            version_check::self_update()?;
+        Commands::Update {
+            channel,
+            rollback,
+            allow_unsigned,
+        } => {
+            if rollback {
+                version_check::rollback()?;
+            } else {
+                version_check::self_update(channel, allow_unsigned)?;
+            }
             Ok(exit_codes::SUCCESS)
         }
     }
@@ -598,6 +1827,209 @@ fn cmd_serve(action: Option<ServeAction>) -> Result<u8> {
     }
 }
 
+/// Endpoints of `rkt serve --http`, each answering like the command it names
+const HTTP_ENDPOINTS: [&str; 5] = ["/def", "/refs", "/symbols", "/spider", "/callers"];
+
+/// Serve the query commands as a JSON API (see [`http`]).
+fn cmd_serve_http(
+    bind: std::net::IpAddr,
+    port: u16,
+    format: OutputFormat,
+    quiet: bool,
+) -> Result<u8> {
+    // Surface a missing index (or unknown --project) now, not on every request
+    load_sqlite_index()?;
+
+    let listener = std::net::TcpListener::bind((bind, port))
+        .with_context(|| format!("Failed to listen on {}:{}", bind, port))?;
+    let url = format!("http://{}", listener.local_addr()?);
+    if format == OutputFormat::Json {
+        println!(
+            "{}",
+            serde_json::json!({
+                "status": "listening",
+                "url": url,
+                "endpoints": HTTP_ENDPOINTS,
+            })
+        );
+    } else if !quiet {
+        println!("Serving the HTTP API on {}", url);
+        println!("Endpoints: {}", HTTP_ENDPOINTS.join(" "));
+    }
+
+    // Source read for `context` goes through the same sandbox as MCP tools
+    let root = std::env::current_dir()?;
+    let limits = mcp::limits::QueryLimits::from_config(&mcp::McpConfig::load());
+    http::serve(listener, limits, move |request| {
+        http_answer(request, &root).unwrap_or_else(|response| response)
+    })?;
+    Ok(exit_codes::SUCCESS)
+}
+
+/// Answer an HTTP API request with the JSON the matching command prints:
+/// 200 where the command exits 0, 404 where it exits 1. Source files are
+/// only read inside `root`.
+fn http_answer(
+    request: &http::Request,
+    root: &Path,
+) -> std::result::Result<http::Response, http::Response> {
+    use http::Response;
+
+    let internal =
+        |e: anyhow::Error| Response::new(500, serde_json::json!({ "error": format!("{:#}", e) }));
+    let concise = request.flag("concise");
+    let min_confidence = request
+        .parse_param("min_confidence")?
+        .unwrap_or(Confidence::StringMatch);
+    let budget = budget::Budget {
+        max_bytes: request.parse_param("max_bytes")?,
+        max_items: request.parse_param("max_items")?,
+    };
+
+    match request.path.as_str() {
+        "/def" => {
+            let symbol = request.require("symbol")?;
+            let index = load_sqlite_index().map_err(internal)?;
+            let renamed = index.follow_alias(symbol).map_err(|e| internal(e.into()))?;
+            Ok(
+                match resolve_definition(
+                    &index,
+                    renamed.as_deref().unwrap_or(symbol),
+                    &SearchFilter::default(),
+                ) {
+                    Ok((sym, source)) => {
+                        let mut body = location_json(
+                            &sym,
+                            request.flag("context"),
+                            request.flag("git"),
+                            concise,
+                            Some(root),
+                        );
+                        note_renamed(
+                            &mut body,
+                            renamed.is_some().then_some(symbol),
+                            &sym.qualified,
+                        );
+                        note_source(&mut body, source.as_deref());
+                        note_compilation(&mut body, compilation_of(&index, &sym).as_ref());
+                        Response::new(200, body)
+                    }
+                    Err(suggestions) => Response::new(
+                        404,
+                        not_found_json("Symbol not found", symbol, &suggestions),
+                    ),
+                },
+            )
+        }
+        "/refs" => {
+            let symbol = request.require("symbol")?;
+            let context_lines = request.parse_param("context")?.unwrap_or(0);
+            let index = load_sqlite_index().map_err(internal)?;
+            let renamed = index.follow_alias(symbol).map_err(|e| internal(e.into()))?;
+            let references = find_symbol_references(
+                &index,
+                renamed.as_deref().unwrap_or(symbol),
+                request.param("path").map(Path::new),
+                min_confidence,
+            )
+            .map_err(internal)?;
+            let status = if references.is_empty() { 404 } else { 200 };
+            let body = budget.list(refs_json(&references, context_lines, Some(root)));
+            Ok(Response::new(status, body))
+        }
+        "/symbols" => {
+            let pattern = request.require("pattern")?;
+            let filter = SearchFilter {
+                language: request.param("language").map(str::to_string),
+                kind: request.parse_param("kind")?,
+                visibility: request.parse_param("visibility")?,
+                path: request
+                    .param("path")
+                    .map(|p| absolute_path(Path::new(p)))
+                    .transpose()
+                    .map_err(internal)?,
+                attribute: request.param("attribute").map(str::to_string),
+            };
+            let index = load_sqlite_index().map_err(internal)?;
+            let body = if request.flag("fuzzy") {
+                let mut matches = index
+                    .fuzzy_search(
+                        pattern,
+                        rocketindex::fuzzy::DEFAULT_MAX_DISTANCE,
+                        100,
+                        filter.language.as_deref(),
+                    )
+                    .map_err(|e| internal(e.into()))?;
+                matches.retain(|m| filter.matches(&m.symbol));
+                budget.list(fuzzy_symbols_json(&matches, concise))
+            } else {
+                let matches = index
+                    .search_with(pattern, 100, &filter)
+                    .map_err(|e| internal(e.into()))?;
+                budget.list(symbols_json(&matches, concise))
+            };
+            Ok(Response::new(200, body))
+        }
+        "/spider" | "/callers" => {
+            let symbol = request.require("symbol")?;
+            let callers = request.path == "/callers";
+            let renamed = renamed_to(symbol);
+            let query = daemon::Query::Spider {
+                symbol: renamed.clone().unwrap_or_else(|| symbol.to_string()),
+                depth: if callers {
+                    1
+                } else {
+                    request.parse_param("depth")?.unwrap_or(5)
+                },
+                reverse: callers || request.flag("reverse"),
+                min_confidence,
+            };
+            Ok(match query_code_index(&query).map_err(internal)? {
+                daemon::Answer::Spider { entry, result, .. } => {
+                    let mut body = if callers {
+                        let mut body = callers_json(&entry, &result, concise);
+                        budget.trim_field(&mut body, "callers");
+                        body
+                    } else {
+                        let mut body = spider_json(&result, concise);
+                        budget.trim_graph(&mut body);
+                        body
+                    };
+                    if let Some(new) = &renamed {
+                        note_renamed(&mut body, Some(symbol), new);
+                    }
+                    Response::new(200, body)
+                }
+                daemon::Answer::NotFound { suggestions } => Response::new(
+                    404,
+                    not_found_json(
+                        if callers {
+                            "Symbol not found"
+                        } else {
+                            "Entry point not found"
+                        },
+                        symbol,
+                        &suggestions,
+                    ),
+                ),
+                other => {
+                    return Err(internal(anyhow::anyhow!(
+                        "Unexpected answer to a spider query: {:?}",
+                        other
+                    )))
+                }
+            })
+        }
+        _ => Err(Response::new(
+            404,
+            serde_json::json!({
+                "error": format!("Unknown endpoint: {}", request.path),
+                "endpoints": HTTP_ENDPOINTS,
+            }),
+        )),
+    }
+}
+
 /// Generate man pages for the CLI
 fn cmd_manpages(out_dir: &Path) -> Result<()> {
     use std::fs;
@@ -645,12 +2077,50 @@ fn cmd_manpages(out_dir: &Path) -> Result<()> {
     Ok(())
 }
 
+/// What the workspace's .fsproj files say about compilation
+struct FsprojLayout {
+    /// Compilation order, later projects' files after earlier ones'
+    file_order: Vec<PathBuf>,
+    /// Package references, sorted and deduplicated by name
+    packages: Vec<rocketindex::fsproj::PackageReference>,
+    /// Number of .fsproj files that parsed
+    parsed: usize,
+}
+
+fn fsproj_layout(fsproj_files: &[PathBuf]) -> FsprojLayout {
+    let mut layout = FsprojLayout {
+        file_order: Vec::new(),
+        packages: Vec::new(),
+        parsed: 0,
+    };
+    for fsproj_path in fsproj_files {
+        if let Ok(info) = parse_fsproj(fsproj_path) {
+            layout.packages.extend(info.package_references);
+            // Merge file orders from all .fsproj files
+            // Files from later projects are appended (they can reference earlier ones)
+            for file in info.compile_files {
+                if !layout.file_order.contains(&file) {
+                    layout.file_order.push(file);
+                }
+            }
+            layout.parsed += 1;
+        }
+    }
+    layout.packages.sort_by(|a, b| a.name.cmp(&b.name));
+    layout.packages.dedup_by(|a, b| a.name == b.name);
+    layout
+}
+
 /// Index the codebase using SQLite (build or rebuild)
+#[tracing::instrument(name = "index", skip_all, fields(root = %root.display(), rebuild = rebuild))]
+#[allow(clippy::too_many_arguments)]
 fn cmd_index(
     root: &Path,
     extract_types: bool,
     batch_size: usize,
     rebuild: bool,
+    changed: bool,
+    string_refs: bool,
     format: OutputFormat,
     quiet: bool,
 ) -> Result<u8> {
@@ -660,33 +2130,43 @@ fn cmd_index(
 
     // Load configuration
     let config = Config::load(&root);
-    let exclude_dirs = config.excluded_dirs();
 
     if !quiet && !config.exclude_dirs.is_empty() {
         eprintln!("Custom exclusions: {}", config.exclude_dirs.join(", "));
     }
 
-    let all_files = find_source_files_with_config(&root, &exclude_dirs, config.respect_gitignore)
-        .context("Failed to find source files")?;
-
-    // Try to find and parse .fsproj files for compilation order
-    let fsproj_files = find_fsproj_files(&root);
-    let mut file_order: Vec<PathBuf> = Vec::new();
-    let mut fsproj_count = 0;
+    let projects = config.projects(&root);
+    if !config.roots.is_empty() {
+        if projects.is_empty() {
+            notice::warning(
+                "NoProjectRoots",
+                format!("no directories match roots {}", config.roots.join(", ")),
+            )
+            .with("roots", &config.roots)
+            .emit();
+        } else if !quiet && format != OutputFormat::Json {
+            eprintln!("Projects: {}", project_names(&projects).join(", "));
+        }
+    }
 
-    for fsproj_path in &fsproj_files {
-        if let Ok(info) = parse_fsproj(fsproj_path) {
-            // Merge file orders from all .fsproj files
-            // Files from later projects are appended (they can reference earlier ones)
-            for file in info.compile_files {
-                if !file_order.contains(&file) {
-                    file_order.push(file);
-                }
-            }
-            fsproj_count += 1;
+    if changed {
+        if let Some(code) = index_changed_files(&root, &config, string_refs, format, quiet)? {
+            return Ok(code);
+        }
+        if !quiet && format != OutputFormat::Json {
+            eprintln!("No git baseline for --changed; checking every file");
         }
     }
 
+    let all_files = workspace_source_files(&root, &config)?;
+
+    let fsproj_files = find_fsproj_files(&root);
+    let FsprojLayout {
+        file_order,
+        packages,
+        parsed: fsproj_count,
+    } = fsproj_layout(&fsproj_files);
+
     // Create SQLite index before processing (for incremental writes)
     let index_dir = root.join(".rocketindex");
     std::fs::create_dir_all(&index_dir).context("Failed to create index directory")?;
@@ -700,10 +2180,45 @@ fn cmd_index(
 
     let db_path = index_dir.join(DEFAULT_DB_NAME);
 
+    // Keep auto-refreshes, watch mode and the LSP out until this is done
+    let _lock = write_lock::acquire(&db_path, DEFAULT_LOCK_TIMEOUT)
+        .context("Failed to lock the index for writing")?;
+
     // Determine if we can do incremental indexing
-    let (index, files_to_process, deleted_count, is_incremental) = if db_path.exists() && !rebuild {
+    let existing = if db_path.exists() && !rebuild {
+        match SqliteIndex::open(&db_path) {
+            // Falls through to a full rebuild
+            Err(e) if e.needs_rebuild() => {
+                report_schema_rebuild(&e);
+                None
+            }
+            result => Some(result.context("Failed to open existing index")?),
+        }
+    } else {
+        None
+    };
+    // Visibility rules apply as files are written, so new ones mean
+    // writing every file again
+    let existing = match existing {
+        Some(index) if index.visibility_rules()? != config.visibility => {
+            notice::info(
+                "IndexRebuilt",
+                "Visibility rules changed. Rebuilding the index.",
+            )
+            .with("reason", "visibility rules changed")
+            .emit();
+            None
+        }
+        existing => existing,
+    };
+    let (index, files_to_process, deleted_count, is_incremental) = if let Some(index) = existing {
         // Try incremental update
-        let index = SqliteIndex::open(&db_path).context("Failed to open existing index")?;
+        index
+            .set_projects(&projects)
+            .context("Failed to record projects")?;
+        index
+            .set_packages(&packages)
+            .context("Failed to record package references")?;
 
         // Find stale files (modified, deleted, new)
         let stale = index
@@ -727,6 +2242,7 @@ fn cmd_index(
             } else if !quiet {
                 println!("Index is up to date ({} files)", all_files.len());
             }
+            record_git_state(&index, &root);
             return Ok(exit_codes::SUCCESS);
         }
 
@@ -737,14 +2253,14 @@ fn cmd_index(
         for (path, reason) in stale {
             match reason {
                 "deleted" => {
-                    index.delete_symbols_in_file(&path)?;
+                    index.clear_file(&path)?;
                     index.delete_file_mtime(&path)?;
                     deleted += 1;
                 }
                 "modified" | "new" => {
                     if reason == "modified" {
-                        // Clear old data for modified files
-                        index.delete_symbols_in_file(&path)?;
+                        // Clear old symbols, references and opens
+                        index.clear_file(&path)?;
                     }
                     files_to_update.push(path);
                 }
@@ -761,17 +2277,44 @@ fn cmd_index(
 
         (index, files_to_update, deleted, true)
     } else {
-        // Full rebuild
+        // Full rebuild; aliases, notes, watchlists and layer rules are
+        // recorded by hand (see rocketindex::meta), and moves can't be seen
+        // again once the old symbols are gone, so keep them
+        let mut meta = None;
+        let mut moves = Vec::new();
         if db_path.exists() {
-            std::fs::remove_file(&db_path).context("Failed to remove existing index")?;
+            if let Ok(old) = SqliteIndex::open(&db_path) {
+                meta = rocketindex::meta::export(&old).ok();
+                moves = old.symbol_moves().unwrap_or_default();
+            }
         }
 
-        let index = SqliteIndex::create(&db_path).context("Failed to create SQLite index")?;
+        // Built beside the old index, which keeps serving queries until the
+        // new one is copied in below
+        let index =
+            SqliteIndex::create_staged(&db_path).context("Failed to create SQLite index")?;
+        if let Some(meta) = &meta {
+            rocketindex::meta::import(&index, meta).context("Failed to restore metadata")?;
+        }
+        for symbol_move in &moves {
+            index
+                .add_symbol_move(symbol_move)
+                .context("Failed to restore symbol history")?;
+        }
 
         // Store workspace root in metadata
         index
             .set_metadata("workspace_root", &root.to_string_lossy())
             .context("Failed to set workspace root")?;
+        index
+            .set_visibility_rules(&config.visibility)
+            .context("Failed to record visibility rules")?;
+        index
+            .set_projects(&projects)
+            .context("Failed to record projects")?;
+        index
+            .set_packages(&packages)
+            .context("Failed to record package references")?;
 
         if !quiet && format != OutputFormat::Json {
             eprintln!("Building full index ({} files)", all_files.len());
@@ -815,127 +2358,181 @@ fn cmd_index(
 
     // Process files in chunks to bound memory usage
     // Memory usage is O(batch_size) instead of O(total_files)
-    for chunk in files.chunks(batch_size) {
-        // Parse this chunk in parallel
-        let parse_results: Vec<_> = chunk
-            .par_iter()
-            .map(|file| {
-                let result = match std::fs::read_to_string(file) {
-                    Ok(source) => {
-                        let result = rocketindex::extract_symbols(file, &source, max_depth);
-                        Ok((file.clone(), result))
-                    }
-                    Err(e) => Err(format!("{}: {}", file.display(), e)),
-                };
-                if let Some(ref pb) = progress {
-                    pb.inc(1);
+    //
+    // A parser thread parses the next chunk in parallel while this thread
+    // writes the previous one, all in a single bulk-load transaction
+    let mut bulk = index
+        .bulk_load()
+        .context("Failed to start writing the index")?;
+    std::thread::scope(|scope| {
+        let (sender, receiver) = std::sync::mpsc::sync_channel(1);
+        let progress = progress.as_ref();
+        scope.spawn(move || {
+            for chunk in files.chunks(batch_size) {
+                // Parse this chunk in parallel
+                let parse_results: Vec<_> = chunk
+                    .par_iter()
+                    .map(|file| {
+                        let result = match std::fs::read_to_string(file) {
+                            Ok(source) => {
+                                let result = rocketindex::extract_symbols(file, &source, max_depth);
+                                Ok((file.clone(), result))
+                            }
+                            Err(e) => Err(format!("{}: {}", file.display(), e)),
+                        };
+                        if let Some(pb) = progress {
+                            pb.inc(1);
+                        }
+                        result
+                    })
+                    .collect();
+
+                if sender.send((chunk, parse_results)).is_err() {
+                    break;
                 }
-                result
-            })
-            .collect();
+            }
+        });
 
-        // Collect results for this chunk only
-        let mut chunk_symbols = Vec::new();
-        let mut chunk_references: Vec<(PathBuf, rocketindex::index::Reference)> = Vec::new();
-        let mut chunk_opens: Vec<(PathBuf, String, u32)> = Vec::new();
+        for (chunk, parse_results) in receiver {
+            // Collect results for this chunk only
+            let mut chunk_symbols = Vec::new();
+            let mut chunk_references: Vec<(PathBuf, rocketindex::index::Reference)> = Vec::new();
+            let mut chunk_opens: Vec<(PathBuf, String, u32)> = Vec::new();
+            let mut chunk_aliases: Vec<(PathBuf, rocketindex::ModuleAlias, u32)> = Vec::new();
+            let mut chunk_metrics = Vec::new();
+
+            for result in parse_results {
+                match result {
+                    Ok((file, parse_result)) => {
+                        chunk_symbols.extend(parse_result.symbols);
+
+                        for reference in parse_result.references {
+                            chunk_references.push((file.clone(), reference));
+                        }
 
-        for result in parse_results {
-            match result {
-                Ok((file, parse_result)) => {
-                    chunk_symbols.extend(parse_result.symbols);
+                        for (line, open) in parse_result.opens.into_iter().enumerate() {
+                            chunk_opens.push((file.clone(), open, line as u32 + 1));
+                        }
 
-                    for reference in parse_result.references {
-                        chunk_references.push((file.clone(), reference));
-                    }
+                        for (line, alias) in parse_result.aliases.into_iter().enumerate() {
+                            chunk_aliases.push((file.clone(), alias, line as u32 + 1));
+                        }
+                        chunk_metrics.push((file.clone(), parse_result.metrics));
 
-                    for (line, open) in parse_result.opens.into_iter().enumerate() {
-                        chunk_opens.push((file.clone(), open, line as u32 + 1));
+                        // Collect warnings (capped to avoid memory issues)
+                        if warnings.len() < 1000 {
+                            for warning in parse_result.warnings {
+                                warnings.push((file.clone(), warning));
+                            }
+                        }
                     }
-
-                    // Collect warnings (capped to avoid memory issues)
-                    if warnings.len() < 1000 {
-                        for warning in parse_result.warnings {
-                            warnings.push(format!(
-                                "{}: {} ({})",
-                                file.display(),
-                                warning.message,
-                                warning
-                                    .location
-                                    .map(|l| format!("{}:{}", l.line, l.column))
-                                    .unwrap_or_else(|| "unknown location".to_string())
-                            ));
+                    Err(e) => {
+                        if errors.len() < 1000 {
+                            errors.push(e);
                         }
                     }
                 }
-                Err(e) => {
-                    if errors.len() < 1000 {
-                        errors.push(e);
-                    }
-                }
             }
-        }
 
-        // Track counts before inserting
-        total_symbols += chunk_symbols.len();
-        total_refs += chunk_references.len();
-        total_opens += chunk_opens.len();
+            // Track counts before inserting
+            total_symbols += chunk_symbols.len();
+            total_refs += chunk_references.len();
+            total_opens += chunk_opens.len();
 
-        // Insert this chunk's data immediately
-        if let Err(e) = index.insert_symbols(&chunk_symbols) {
-            errors.push(format!("Failed to insert symbols: {}", e));
-        }
+            // Insert this chunk's data immediately
+            if let Err(e) = bulk.insert_symbols(&chunk_symbols) {
+                errors.push(format!("Failed to insert symbols: {}", e));
+            }
 
-        let ref_tuples: Vec<_> = chunk_references
-            .iter()
-            .map(|(f, r)| (f.as_path(), r))
-            .collect();
-        if let Err(e) = index.insert_references(&ref_tuples) {
-            errors.push(format!("Failed to insert references: {}", e));
-        }
+            let ref_tuples: Vec<_> = chunk_references
+                .iter()
+                .map(|(f, r)| (f.as_path(), r))
+                .collect();
+            if let Err(e) = bulk.insert_references(&ref_tuples) {
+                errors.push(format!("Failed to insert references: {}", e));
+            }
 
-        let open_tuples: Vec<_> = chunk_opens
-            .iter()
-            .map(|(f, m, l)| (f.as_path(), m.as_str(), *l))
-            .collect();
-        if let Err(e) = index.insert_opens(&open_tuples) {
-            errors.push(format!("Failed to insert opens: {}", e));
-        }
-
-        // Record file modification times for this chunk
-        for file in chunk {
-            if let Ok(metadata) = std::fs::metadata(file) {
-                if let Ok(modified) = metadata.modified() {
-                    let mtime = modified
-                        .duration_since(std::time::UNIX_EPOCH)
-                        .map(|d| d.as_secs())
-                        .unwrap_or(0);
-                    if let Err(e) = index.set_file_mtime(file, mtime) {
+            let open_tuples: Vec<_> = chunk_opens
+                .iter()
+                .map(|(f, m, l)| (f.as_path(), m.as_str(), *l))
+                .collect();
+            if let Err(e) = bulk.insert_opens(&open_tuples) {
+                errors.push(format!("Failed to insert opens: {}", e));
+            }
+
+            let alias_tuples: Vec<_> = chunk_aliases
+                .iter()
+                .map(|(f, a, l)| (f.as_path(), a, *l))
+                .collect();
+            if let Err(e) = bulk.insert_aliases(&alias_tuples) {
+                errors.push(format!("Failed to insert module aliases: {}", e));
+            }
+
+            for (file, metrics) in &chunk_metrics {
+                if let Err(e) = bulk.set_file_metrics(file, metrics) {
+                    errors.push(format!(
+                        "Failed to record metrics for {}: {}",
+                        file.display(),
+                        e
+                    ));
+                }
+            }
+
+            // Record file modification times for this chunk
+            for file in chunk {
+                if let Some(mtime) = file_mtime(file) {
+                    if let Err(e) = bulk.set_file_mtime(file, mtime) {
                         tracing::warn!("Failed to record mtime for {:?}: {}", file, e);
                     }
                 }
             }
-        }
 
-        // Memory for chunk_symbols, chunk_references, chunk_opens is dropped here
-    }
+            // Memory for chunk_symbols, chunk_references, chunk_opens is dropped here
+        }
+    });
+    bulk.finish().context("Failed to commit the index")?;
 
     if let Some(pb) = progress {
         pb.finish_with_message("Indexing complete");
     }
 
-    let symbol_count = total_symbols;
-    let _ref_count = total_refs;
-    let _open_count = total_opens;
+    // String-literal references are rebuilt over all files, since new symbols
+    // can create matches in files that didn't change
+    let string_refs = string_refs || config.string_references;
+    let string_ref_count = if string_refs {
+        match rocketindex::string_refs::index_string_references(&index, &all_files) {
+            Ok(count) => count,
+            Err(e) => {
+                errors.push(format!("Failed to index string references: {}", e));
+                0
+            }
+        }
+    } else {
+        0
+    };
+
+    // Now that every file's symbols are in place, mark references that name
+    // an indexed symbol exactly
+    if let Err(e) = index.promote_exact_references() {
+        errors.push(format!("Failed to score references: {}", e));
+    }
+    record_git_state(&index, &root);
 
-    // Get total symbol count (for incremental, includes existing + new)
     let total_symbol_count = if is_incremental {
-        index.count_symbols().unwrap_or(symbol_count)
+        // Includes the symbols already in the index
+        index.count_symbols().unwrap_or(total_symbols)
     } else {
-        symbol_count
+        index
+            .replace(&db_path)
+            .context("Failed to replace the existing index")?;
+        total_symbols
     };
+    let symbol_count = total_symbols;
+    let _ref_count = total_refs;
+    let _open_count = total_opens;
 
     if format == OutputFormat::Json {
-        let output = serde_json::json!({
+        let mut output = serde_json::json!({
             "files": all_files.len(),
             "files_updated": files.len(),
             "files_deleted": deleted_count,
@@ -945,9 +2542,18 @@ fn cmd_index(
             "fsproj_files": fsproj_count,
             "file_order_count": file_order.len(),
             "errors": errors,
-            "warnings": warnings,
+            "warnings": warnings
+                .iter()
+                .map(|(file, warning)| parse_warning_text(file, warning))
+                .collect::<Vec<_>>(),
             "database": db_path.display().to_string(),
         });
+        if string_refs {
+            output["string_references"] = serde_json::json!(string_ref_count);
+        }
+        if !projects.is_empty() {
+            output["projects"] = serde_json::json!(project_names(&projects));
+        }
         println!("{}", serde_json::to_string_pretty(&output)?);
     } else if !quiet {
         if is_incremental {
@@ -963,6 +2569,9 @@ fn cmd_index(
             println!("Indexed {} files, {} symbols", files.len(), symbol_count);
         }
         println!("Database: {}", db_path.display());
+        if string_refs {
+            println!("Recorded {} string-literal reference(s)", string_ref_count);
+        }
         if fsproj_count > 0 {
             println!(
                 "Found {} .fsproj file(s), {} files in compilation order",
@@ -970,25 +2579,8 @@ fn cmd_index(
                 file_order.len()
             );
         }
-        if !errors.is_empty() {
-            eprintln!("Warnings:");
-            for error in &errors[..errors.len().min(10)] {
-                eprintln!("  {}", error);
-            }
-            if errors.len() > 10 {
-                eprintln!("  ... and {} more", errors.len() - 10);
-            }
-        }
-        if !warnings.is_empty() {
-            eprintln!("Warnings:");
-            for warning in &warnings[..warnings.len().min(10)] {
-                eprintln!("  {}", warning);
-            }
-            if warnings.len() > 10 {
-                eprintln!("  ... and {} more", warnings.len() - 10);
-            }
-        }
     }
+    report_index_problems(&errors, &warnings, format);
 
     // Optionally run type extraction
     if extract_types {
@@ -997,13 +2589,16 @@ fn cmd_index(
                 println!("Extracting types from: {}", fsproj_path.display());
             }
             if let Err(e) = run_type_extraction(fsproj_path, None, false) {
-                if !quiet && format != OutputFormat::Json {
-                    eprintln!(
-                        "Warning: Type extraction failed for {}: {}",
+                notice::warning(
+                    "TypeExtractionFailed",
+                    format!(
+                        "Type extraction failed for {}: {}",
                         fsproj_path.display(),
                         e
-                    );
-                }
+                    ),
+                )
+                .with("project", fsproj_path)
+                .emit();
             }
         }
     }
@@ -1011,6 +2606,470 @@ fn cmd_index(
     Ok(exit_codes::SUCCESS)
 }
 
+/// `rkt index --quick`: index the files recent commits touched, and what
+/// they import, then start a background `rkt index` for the rest.
+fn cmd_index_quick(
+    root: &Path,
+    commits: usize,
+    batch_size: usize,
+    string_refs: bool,
+    format: OutputFormat,
+    quiet: bool,
+) -> Result<u8> {
+    let started = Instant::now();
+    let root = root
+        .canonicalize()
+        .context("Failed to resolve root directory")?;
+    let config = Config::load(&root);
+    let all_files = workspace_source_files(&root, &config)?;
+    let sample = quick::sample(&root, &all_files, commits, config.max_recursion_depth);
+
+    let index_dir = root.join(".rocketindex");
+    std::fs::create_dir_all(&index_dir).context("Failed to create index directory")?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = std::fs::set_permissions(&index_dir, std::fs::Permissions::from_mode(0o700));
+    }
+    let db_path = index_dir.join(DEFAULT_DB_NAME);
+
+    // Held until the background process is recorded, so it starts only
+    // once this is done
+    let _lock = write_lock::acquire(&db_path, DEFAULT_LOCK_TIMEOUT)
+        .context("Failed to lock the index for writing")?;
+    // Created in place rather than staged, so queries can read it while
+    // the rest is indexed
+    let index = SqliteIndex::create(&db_path).context("Failed to create SQLite index")?;
+    let layout = fsproj_layout(&find_fsproj_files(&root));
+    index
+        .set_metadata("workspace_root", &root.to_string_lossy())
+        .context("Failed to set workspace root")?;
+    index
+        .set_visibility_rules(&config.visibility)
+        .context("Failed to record visibility rules")?;
+    index
+        .set_projects(&config.projects(&root))
+        .context("Failed to record projects")?;
+    index
+        .set_packages(&layout.packages)
+        .context("Failed to record package references")?;
+    if !layout.file_order.is_empty() {
+        index
+            .set_metadata("file_order", &serde_json::to_string(&layout.file_order)?)
+            .context("Failed to set file order")?;
+    }
+
+    let sample: Vec<(PathBuf, &str)> = sample.into_iter().map(|path| (path, "new")).collect();
+    reindex_files(&index, &sample, config.max_recursion_depth);
+    let remaining = all_files.len() - sample.len();
+
+    let background = if remaining > 0 {
+        let log_path = index_dir.join(quick::LOG);
+        if let Some(parent) = log_path.parent() {
+            std::fs::create_dir_all(parent).context("Failed to create log directory")?;
+        }
+        let log = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&log_path)
+            .with_context(|| format!("Failed to open {}", log_path.display()))?;
+
+        let mut command = std::process::Command::new(std::env::current_exe()?);
+        command
+            .arg("index")
+            .arg("--root")
+            .arg(&root)
+            .args(["--complete-quick", "--format", "text"])
+            .arg("--batch-size")
+            .arg(batch_size.to_string())
+            .current_dir(&root)
+            .stdin(std::process::Stdio::null())
+            .stdout(log.try_clone()?)
+            .stderr(log);
+        if string_refs {
+            command.arg("--string-refs");
+        }
+        detach(&mut command);
+        let pid = command
+            .spawn()
+            .context("Failed to start background indexing")?
+            .id();
+        index
+            .set_metadata(quick::PENDING_KEY, &pid.to_string())
+            .context("Failed to record background indexing")?;
+        Some((pid, log_path))
+    } else {
+        // Nothing left for the background, so finish here
+        index.promote_exact_references()?;
+        record_git_state(&index, &root);
+        None
+    };
+
+    let symbols = index.count_symbols().unwrap_or(0);
+    if format == OutputFormat::Json {
+        let output = serde_json::json!({
+            "files": all_files.len(),
+            "files_indexed": sample.len(),
+            "remaining": remaining,
+            "symbols": symbols,
+            "background_pid": background.as_ref().map(|(pid, _)| *pid),
+            "database": db_path.display().to_string(),
+        });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+    } else if !quiet {
+        println!(
+            "Indexed {} of {} files, {} symbols in {:.1?}",
+            sample.len(),
+            all_files.len(),
+            symbols,
+            started.elapsed()
+        );
+        if let Some((pid, log_path)) = &background {
+            println!(
+                "Indexing the remaining {} in the background (pid {}, log {})",
+                remaining,
+                pid,
+                log_path.display()
+            );
+        }
+        println!("Database: {}", db_path.display());
+    }
+    Ok(exit_codes::SUCCESS)
+}
+
+/// The background half of `rkt index --quick`: index every file not yet in
+/// the index, a batch at a time so queries get the lock in between.
+fn cmd_complete_quick_index(root: &Path, batch_size: usize, string_refs: bool) -> Result<u8> {
+    let started = Instant::now();
+    let root = root
+        .canonicalize()
+        .context("Failed to resolve root directory")?;
+    let config = Config::load(&root);
+    let db_path = root.join(".rocketindex").join(DEFAULT_DB_NAME);
+    let index = SqliteIndex::open(&db_path).context("Failed to open index")?;
+    let all_files = workspace_source_files(&root, &config)?;
+
+    let stale = index
+        .find_stale_files(&all_files)
+        .context("Failed to check for stale files")?;
+    for chunk in stale.chunks(batch_size.max(1)) {
+        let _lock = index
+            .lock_for_writing(DEFAULT_LOCK_TIMEOUT)
+            .context("Failed to lock the index for writing")?;
+        reindex_files(&index, chunk, config.max_recursion_depth);
+    }
+
+    let _lock = index
+        .lock_for_writing(DEFAULT_LOCK_TIMEOUT)
+        .context("Failed to lock the index for writing")?;
+    if string_refs || config.string_references {
+        rocketindex::string_refs::index_string_references(&index, &all_files)
+            .context("Failed to index string references")?;
+    }
+    index
+        .promote_exact_references()
+        .context("Failed to score references")?;
+    record_git_state(&index, &root);
+    index
+        .remove_metadata(quick::PENDING_KEY)
+        .context("Failed to record completion")?;
+    println!(
+        "Indexed the remaining {} file(s) in {:.1?}",
+        stale.len(),
+        started.elapsed()
+    );
+    Ok(exit_codes::SUCCESS)
+}
+
+/// Metadata key for the commit checked out when the index was last updated
+const GIT_HEAD_KEY: &str = "git_head";
+/// Metadata key for the files `git status` listed at that point
+const GIT_DIRTY_KEY: &str = "git_dirty";
+
+/// Remember what git reported when the index was brought up to date, as the
+/// baseline for `rkt index --changed`. Outside a repository this is a no-op.
+fn record_git_state(index: &SqliteIndex, root: &Path) {
+    let (Ok(head), Ok(dirty)) = (git::head_commit(root), git::working_tree_changes(root)) else {
+        return;
+    };
+    let dirty = serde_json::to_string(&dirty).unwrap_or_default();
+    if let Err(e) = index
+        .set_metadata(GIT_HEAD_KEY, &head)
+        .and_then(|_| index.set_metadata(GIT_DIRTY_KEY, &dirty))
+    {
+        tracing::warn!("Failed to record git state: {}", e);
+    }
+}
+
+/// Parse warnings after indexing, as JSON output has always listed them
+fn parse_warning_text(file: &Path, warning: &rocketindex::ParseWarning) -> String {
+    format!(
+        "{}: {} ({})",
+        file.display(),
+        warning.message,
+        warning
+            .location
+            .as_ref()
+            .map(|l| format!("{}:{}", l.line, l.column))
+            .unwrap_or_else(|| "unknown location".to_string())
+    )
+}
+
+/// Emit the files that failed to index and the parse warnings of those that
+/// did. Text output shows the first few of each.
+fn report_index_problems(
+    errors: &[String],
+    warnings: &[(PathBuf, rocketindex::ParseWarning)],
+    format: OutputFormat,
+) {
+    let shown = if format == OutputFormat::Json {
+        usize::MAX
+    } else {
+        10
+    };
+    for error in errors.iter().take(shown) {
+        notice::warning("IndexError", error).emit();
+    }
+    for (file, warning) in warnings.iter().take(shown) {
+        notice::warning("ParseWarning", parse_warning_text(file, warning))
+            .with("file", file)
+            .with("line", warning.location.as_ref().map(|l| l.line))
+            .with("column", warning.location.as_ref().map(|l| l.column))
+            .with("reason", &warning.message)
+            .emit();
+    }
+    let hidden = errors.len().saturating_sub(shown) + warnings.len().saturating_sub(shown);
+    if hidden > 0 {
+        notice::warning(
+            "MoreWarnings",
+            format!("... and {} more (see --format json)", hidden),
+        )
+        .with("count", hidden)
+        .emit();
+    }
+}
+
+/// `rkt index --pull`: restore a published index and leave the files
+/// changed since it was built for `--changed` to pick up. Returns whether an
+/// index was found.
+fn pull_index(root: &Path, url: &str) -> Result<bool> {
+    let root = root
+        .canonicalize()
+        .context("Failed to resolve root directory")?;
+    let index_dir = root.join(".rocketindex");
+    std::fs::create_dir_all(&index_dir).context("Failed to create index directory")?;
+    // Released before the incremental index that follows takes it again
+    let _lock = write_lock::acquire(&index_dir.join(DEFAULT_DB_NAME), DEFAULT_LOCK_TIMEOUT)
+        .context("Failed to lock the index for writing")?;
+    let Some(pulled) = prebuilt::pull(&root, url)? else {
+        notice::info(
+            "NoPublishedIndex",
+            format!(
+                "No index published for HEAD or its last {} ancestors; indexing from scratch",
+                prebuilt::MAX_ANCESTORS - 1
+            ),
+        )
+        .emit();
+        return Ok(false);
+    };
+
+    // Files changed since the build get an mtime no file has, so --changed
+    // reindexes them, or drops them if they're gone
+    let commit = &pulled.published.commit;
+    let mut stale: std::collections::HashSet<PathBuf> =
+        git::changed_since(&root, commit)?.into_iter().collect();
+    stale.extend(git::working_tree_changes(&root)?);
+    let files = pulled.index.list_files()?;
+    let mut load = pulled.index.bulk_load()?;
+    for file in files {
+        let mtime = if stale.contains(&file) {
+            Some(0)
+        } else {
+            file_mtime(&file)
+        };
+        if let Some(mtime) = mtime {
+            load.set_file_mtime(&file, mtime)?;
+        }
+    }
+    load.finish()?;
+    pulled.index.set_metadata(GIT_HEAD_KEY, commit)?;
+    pulled.index.set_metadata(GIT_DIRTY_KEY, "[]")?;
+
+    notice::info(
+        "IndexPulled",
+        format!(
+            "Pulled index for {} ({} symbols, {} commits behind HEAD)",
+            commit, pulled.published.pack.symbols, pulled.behind
+        ),
+    )
+    .with("commit", commit)
+    .with("behind", pulled.behind)
+    .with("symbols", pulled.published.pack.symbols)
+    .emit();
+    Ok(true)
+}
+
+/// `rkt index --publish`: upload the index just built for HEAD.
+fn publish_index(root: &Path, url: &str) -> Result<u8> {
+    let root = root
+        .canonicalize()
+        .context("Failed to resolve root directory")?;
+    let published = prebuilt::publish(&root, url)?;
+    notice::info(
+        "IndexPublished",
+        format!(
+            "Published index for {} to {} ({} bytes compressed)",
+            published.commit, url, published.compressed_bytes
+        ),
+    )
+    .with("commit", &published.commit)
+    .with("sha256", &published.sha256)
+    .with("compressed_bytes", published.compressed_bytes)
+    .emit();
+    Ok(exit_codes::SUCCESS)
+}
+
+/// `rkt index --changed`: reindex only the files git says may have changed
+/// since the index was last brought up to date, through the same
+/// [`BatchProcessor`] the watcher uses.
+///
+/// Candidates are files changed by commits since the recorded HEAD, files
+/// `git status` lists now, and files it listed then (they may have been
+/// reverted since). Of those, files whose mtime matches the index are
+/// skipped. Returns `None`, to fall back to a full check, when there's no
+/// index or no recorded baseline.
+fn index_changed_files(
+    root: &Path,
+    config: &Config,
+    string_refs: bool,
+    format: OutputFormat,
+    quiet: bool,
+) -> Result<Option<u8>> {
+    let db_path = root.join(".rocketindex").join(DEFAULT_DB_NAME);
+    if !db_path.exists() {
+        return Ok(None);
+    }
+    let index = match SqliteIndex::open(&db_path) {
+        Err(e) if e.needs_rebuild() => return Ok(None),
+        result => result.context("Failed to open existing index")?,
+    };
+    let Some(since) = index.get_metadata(GIT_HEAD_KEY)? else {
+        return Ok(None);
+    };
+    // History rewritten past the baseline: can't tell what changed
+    let Ok(mut candidates) = git::changed_since(root, &since) else {
+        return Ok(None);
+    };
+    candidates.extend(git::working_tree_changes(root)?);
+    if let Some(dirty) = index.get_metadata(GIT_DIRTY_KEY)? {
+        candidates.extend(serde_json::from_str::<Vec<PathBuf>>(&dirty).unwrap_or_default());
+    }
+    candidates.sort();
+    candidates.dedup();
+
+    let projects = config.projects(root);
+    let exclude_dirs = config.excluded_dirs();
+    let ignore_files = IgnoreFiles::new(root);
+    let in_workspace = |path: &Path| {
+        let Ok(relative) = path.strip_prefix(root) else {
+            return false;
+        };
+        let excluded = relative.components().any(|c| {
+            let c = c.as_os_str().to_string_lossy();
+            c.starts_with('.') || exclude_dirs.contains(&c.as_ref())
+        }) || exclude_dirs.iter().any(|dir| relative.starts_with(dir))
+            || ignore_files.is_ignored(path);
+        !excluded && (projects.is_empty() || projects.iter().any(|p| path.starts_with(&p.root)))
+    };
+
+    let mut batch = BatchProcessor::with_defaults(config.max_recursion_depth);
+    let mut updated = Vec::new();
+    let mut deleted = Vec::new();
+    for path in candidates {
+        if !is_supported_file(&path) || !in_workspace(&path) {
+            continue;
+        }
+        let indexed_mtime = index.get_file_mtime(&path)?;
+        match file_mtime(&path) {
+            Some(mtime) if indexed_mtime == Some(mtime) => {}
+            Some(_) => {
+                batch.add_event(WatchEvent::Modified(path.clone()));
+                updated.push(path);
+            }
+            None if indexed_mtime.is_some() => {
+                batch.add_event(WatchEvent::Deleted(path.clone()));
+                deleted.push(path);
+            }
+            None => {}
+        }
+    }
+
+    let stats = batch
+        .force_flush(&index)
+        .context("Failed to update changed files")?;
+    for path in &updated {
+        if let Some(mtime) = file_mtime(path) {
+            index.set_file_mtime(path, mtime)?;
+        }
+    }
+    for path in &deleted {
+        index.delete_file_mtime(path)?;
+    }
+
+    let mut errors = Vec::new();
+    let string_refs = string_refs || config.string_references;
+    if string_refs && !(updated.is_empty() && deleted.is_empty()) {
+        let files = index.get_tracked_files()?;
+        if let Err(e) = rocketindex::string_refs::index_string_references(&index, &files) {
+            errors.push(format!("Failed to index string references: {}", e));
+        }
+    }
+    if let Err(e) = index.promote_exact_references() {
+        errors.push(format!("Failed to score references: {}", e));
+    }
+    record_git_state(&index, root);
+
+    let total_symbols = index.count_symbols().unwrap_or(0);
+    if format == OutputFormat::Json {
+        let output = serde_json::json!({
+            "files_updated": stats.files_updated,
+            "files_deleted": stats.files_deleted,
+            "symbols": total_symbols,
+            "symbols_added": stats.symbols_inserted,
+            "incremental": true,
+            "changed": true,
+            "errors": errors,
+            "database": db_path.display().to_string(),
+        });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+    } else if !quiet {
+        println!(
+            "Updated {} changed file(s), {} total symbols",
+            stats.files_updated, total_symbols
+        );
+        if stats.files_deleted > 0 {
+            println!("Removed {} deleted file(s)", stats.files_deleted);
+        }
+        println!("Database: {}", db_path.display());
+    }
+    for error in &errors {
+        notice::warning("IndexError", error).emit();
+    }
+
+    Ok(Some(exit_codes::SUCCESS))
+}
+
+/// Modification time in seconds, as recorded in the index.
+fn file_mtime(path: &Path) -> Option<u64> {
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    Some(
+        modified
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+    )
+}
+
 /// Run the F# type extraction script
 fn run_type_extraction(
     project: &PathBuf,
@@ -1068,8 +3127,7 @@ fn run_type_extraction(
         cmd.arg("--verbose");
     }
 
-    let status = cmd
-        .status()
+    let status = rocketindex::subprocess::status(&mut cmd)
         .context("Failed to run dotnet fsi - is .NET SDK installed?")?;
 
     if !status.success() {
@@ -1234,6 +3292,7 @@ fn cmd_def(
     symbol: &str,
     context: bool,
     git: bool,
+    filter: &SearchFilter,
     format: OutputFormat,
     quiet: bool,
     concise: bool,
@@ -1241,36 +3300,68 @@ fn cmd_def(
     warn_if_no_session(quiet);
     let index = load_sqlite_index()?;
 
-    // Try exact match first
-    if let Ok(Some(sym)) = index.find_by_qualified(symbol) {
-        output_location(&sym, context, git, format, quiet, concise)?;
-        return Ok(exit_codes::SUCCESS);
-    }
-
-    // Try searching for partial matches
-    if let Ok(matches) = index.search(symbol, 10, None) {
-        if let Some(sym) = matches.first() {
-            output_location(sym, context, git, format, quiet, concise)?;
+    let renamed = index.follow_alias(symbol)?;
+    let suggestions = match resolve_definition(&index, renamed.as_deref().unwrap_or(symbol), filter)
+    {
+        Ok((sym, source)) => {
+            let renamed_from = renamed.is_some().then_some(symbol);
+            let views = match std::env::current_dir() {
+                Ok(cwd) if Config::load(&cwd).rails => rails::action_views(&cwd, &sym.qualified)
+                    .into_iter()
+                    .map(|view| cwd.join(view))
+                    .collect(),
+                _ => Vec::new(),
+            };
+            let compilation = compilation_of(&index, &sym);
+            output_location(
+                &sym,
+                context,
+                git,
+                renamed_from,
+                source.as_deref(),
+                &views,
+                compilation.as_ref(),
+                format,
+                quiet,
+                concise,
+            )?;
             return Ok(exit_codes::SUCCESS);
         }
-    }
+        Err(suggestions) => suggestions,
+    };
 
-    // Symbol not found - try to provide helpful suggestions
-    let suggestions = index
-        .suggest_similar(
-            symbol,
-            rocketindex::fuzzy::DEFAULT_MAX_DISTANCE,
-            rocketindex::fuzzy::DEFAULT_MAX_SUGGESTIONS,
-        )
-        .unwrap_or_default();
+    report_not_found(symbol, &suggestions, format, quiet, concise)?;
+    Ok(exit_codes::NOT_FOUND)
+}
+
+fn cmd_cat(
+    symbol: &str,
+    context: u32,
+    format: OutputFormat,
+    quiet: bool,
+    concise: bool,
+) -> Result<u8> {
+    warn_if_no_session(quiet);
+    let index = load_sqlite_index()?;
+
+    let renamed = index.follow_alias(symbol)?;
+    let sym = match find_definition(&index, renamed.as_deref().unwrap_or(symbol)) {
+        Ok(sym) => sym,
+        Err(suggestions) => {
+            report_not_found(symbol, &suggestions, format, quiet, concise)?;
+            return Ok(exit_codes::NOT_FOUND);
+        }
+    };
+    let renamed_from = renamed.is_some().then_some(symbol);
+
+    let snippet = index
+        .get_source(&sym, context)
+        .with_context(|| format!("Failed to read {}", sym.location.file.display()))?;
 
     if format == OutputFormat::Json {
-        let suggestion_strs: Vec<&str> = suggestions.iter().map(|s| s.value.as_str()).collect();
-        let output = serde_json::json!({
-            "error": "Symbol not found",
-            "symbol": symbol,
-            "suggestions": suggestion_strs
-        });
+        let mut output = serde_json::to_value(&snippet)?;
+        output["symbol"] = serde_json::Value::String(sym.qualified.clone());
+        note_renamed(&mut output, renamed_from, &sym.qualified);
         println!(
             "{}",
             if concise {
@@ -1280,26 +3371,331 @@ fn cmd_def(
             }
         );
     } else if !quiet {
-        eprintln!("Symbol not found: {}", symbol);
+        if let Some(old) = renamed_from {
+            report_renamed(old, &sym.qualified);
+        }
+        println!("{}", snippet.text);
+    }
+
+    Ok(exit_codes::SUCCESS)
+}
+
+/// Follow a symbol through the moves recorded in its history
+fn cmd_moved(symbol: &str, format: OutputFormat, quiet: bool, concise: bool) -> Result<u8> {
+    warn_if_no_session(quiet);
+    let index = load_sqlite_index()?;
+
+    let current = index.find_by_qualified(symbol)?;
+    let moves = if current.is_some() {
+        Vec::new()
+    } else {
+        index.moves_of(symbol)?
+    };
+    if current.is_none() && moves.is_empty() {
+        let suggestions = find_definition(&index, symbol).err().unwrap_or_default();
+        report_not_found(symbol, &suggestions, format, quiet, concise)?;
+        return Ok(exit_codes::NOT_FOUND);
+    }
+
+    // Where it is now: still here, at the end of its moves, or (when it was
+    // removed) any symbol of the same name and kind
+    let destination = match (&current, moves.last()) {
+        (Some(sym), _) => Some(sym.clone()),
+        (None, Some(last)) => match &last.to {
+            Some(to) => index.find_by_qualified(to)?,
+            None => None,
+        },
+        (None, None) => None,
+    };
+    let candidates: Vec<rocketindex::Symbol> = match moves.last() {
+        Some(last) if last.to.is_none() => index
+            .search(&last.name, 20, None)?
+            .into_iter()
+            .filter(|sym| sym.name == last.name && sym.kind == last.kind)
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    if format == OutputFormat::Json {
+        let output = serde_json::json!({
+            "symbol": symbol,
+            "moves": moves,
+            "current": destination.as_ref().map(|sym| serde_json::json!({
+                "qualified": sym.qualified,
+                "file": sym.location.file,
+                "line": sym.location.line,
+            })),
+            "candidates": candidates.iter().map(|sym| serde_json::json!({
+                "qualified": sym.qualified,
+                "file": sym.location.file,
+                "line": sym.location.line,
+            })).collect::<Vec<_>>(),
+        });
+        println!(
+            "{}",
+            if concise {
+                serde_json::to_string(&output)?
+            } else {
+                serde_json::to_string_pretty(&output)?
+            }
+        );
+    } else if !quiet {
+        if current.is_some() {
+            println!("{} has not moved", symbol);
+        }
+        for hop in &moves {
+            match (&hop.to, &hop.to_file, hop.to_line) {
+                (Some(to), Some(file), Some(line)) => println!(
+                    "{} ({}:{}) -> {} ({}:{})",
+                    hop.from,
+                    hop.from_file.display(),
+                    hop.from_line,
+                    to,
+                    file.display(),
+                    line
+                ),
+                _ => println!(
+                    "{} removed from {}:{}",
+                    hop.from,
+                    hop.from_file.display(),
+                    hop.from_line
+                ),
+            }
+        }
+        if let Some(sym) = &destination {
+            println!(
+                "Now at {}:{}: {}",
+                sym.location.file.display(),
+                sym.location.line,
+                sym.qualified
+            );
+        }
+        if !candidates.is_empty() {
+            println!("Possibly now:");
+            for sym in &candidates {
+                println!(
+                    "  {} ({}:{})",
+                    sym.qualified,
+                    sym.location.file.display(),
+                    sym.location.line
+                );
+            }
+        }
+    }
+
+    Ok(exit_codes::SUCCESS)
+}
+
+/// Print "symbol not found", with "did you mean" suggestions
+fn report_not_found(
+    symbol: &str,
+    suggestions: &[rocketindex::fuzzy::Suggestion],
+    format: OutputFormat,
+    quiet: bool,
+    concise: bool,
+) -> Result<()> {
+    if format == OutputFormat::Json {
+        let output = not_found_json("Symbol not found", symbol, suggestions);
+        println!(
+            "{}",
+            if concise {
+                serde_json::to_string(&output)?
+            } else {
+                serde_json::to_string_pretty(&output)?
+            }
+        );
+    } else if !quiet {
+        eprintln!("Symbol not found: {}", symbol);
         if !suggestions.is_empty() {
             eprintln!("Did you mean:");
-            for suggestion in &suggestions {
+            for suggestion in suggestions {
                 eprintln!("  {} (distance: {})", suggestion.value, suggestion.distance);
             }
         }
     }
+    Ok(())
+}
 
-    Ok(exit_codes::NOT_FOUND)
+/// Resolve `symbol` to its definition: exactly, then by search, else
+/// return "did you mean" suggestions.
+fn find_definition(
+    index: &SqliteIndex,
+    symbol: &str,
+) -> std::result::Result<rocketindex::Symbol, Vec<rocketindex::fuzzy::Suggestion>> {
+    find_definition_with(index, symbol, &SearchFilter::default())
 }
 
-fn output_location(
+/// [`find_definition`], keeping only definitions that pass `filter`.
+fn find_definition_with(
+    index: &SqliteIndex,
+    symbol: &str,
+    filter: &SearchFilter,
+) -> std::result::Result<rocketindex::Symbol, Vec<rocketindex::fuzzy::Suggestion>> {
+    // Try exact match first
+    if let Some(sym) = find_exact_definition(index, symbol, filter) {
+        return Ok(sym);
+    }
+
+    // Try searching for partial matches
+    if let Ok(mut matches) = index.search_with(symbol, 10, filter) {
+        if !matches.is_empty() {
+            return Ok(matches.swap_remove(0));
+        }
+    }
+
+    // Symbol not found - try to provide helpful suggestions
+    Err(suggest(index, symbol))
+}
+
+/// "Did you mean" suggestions for a symbol that resolved to nothing
+fn suggest(index: &SqliteIndex, symbol: &str) -> Vec<rocketindex::fuzzy::Suggestion> {
+    index
+        .suggest_similar(
+            symbol,
+            rocketindex::fuzzy::DEFAULT_MAX_DISTANCE,
+            rocketindex::fuzzy::DEFAULT_MAX_SUGGESTIONS,
+        )
+        .unwrap_or_default()
+}
+
+/// Error for a symbol that resolved to nothing, naming the suggestions
+fn symbol_not_found(index: &SqliteIndex, symbol: &str) -> anyhow::Error {
+    let suggestions: Vec<String> = suggest(index, symbol)
+        .into_iter()
+        .map(|s| s.value)
+        .collect();
+    if suggestions.is_empty() {
+        anyhow::anyhow!("Symbol not found: {}", symbol)
+    } else {
+        anyhow::anyhow!(
+            "Symbol not found: {} (did you mean {}?)",
+            symbol,
+            suggestions.join(", ")
+        )
+    }
+}
+
+/// The definition named exactly `qualified`, or with that GUID (see
+/// [`rocketindex::db::symbol_guid`]), that passes `filter`, if any
+fn find_exact_definition(
+    index: &SqliteIndex,
+    qualified: &str,
+    filter: &SearchFilter,
+) -> Option<rocketindex::Symbol> {
+    if rocketindex::db::is_symbol_guid(qualified) {
+        return index
+            .find_by_guid(qualified)
+            .ok()
+            .flatten()
+            .filter(|sym| filter.matches(sym));
+    }
+    if filter.path.is_none() {
+        return index.find_by_qualified(qualified).ok().flatten();
+    }
+    index
+        .find_all_by_qualified(qualified)
+        .ok()?
+        .into_iter()
+        .find(|sym| filter.matches(sym))
+}
+
+/// Like [`find_definition_with`], but an exact match in an attached
+/// repository (see `attach` in .rocketindex.toml), or with `rails` set the
+/// model a table name stands for, beats a partial match here. The second
+/// value names the attached repository the definition came from.
+///
+/// Attached repositories are skipped when `filter` has a path, which names
+/// files in this workspace.
+fn resolve_definition(
+    index: &SqliteIndex,
+    symbol: &str,
+    filter: &SearchFilter,
+) -> std::result::Result<(rocketindex::Symbol, Option<String>), Vec<rocketindex::fuzzy::Suggestion>>
+{
+    if let Some(sym) = find_exact_definition(index, symbol, filter) {
+        return Ok((sym, None));
+    }
+    if let Ok(cwd) = std::env::current_dir() {
+        let config = Config::load(&cwd);
+        if filter.path.is_none() {
+            for attached in federation::open_attached(&config, &cwd) {
+                if let Ok(Some(sym)) = attached.find_by_qualified(symbol) {
+                    return Ok((sym, Some(attached.name)));
+                }
+            }
+        }
+        // A table or association name stands for its model
+        if config.rails {
+            let model = rails::model_name(symbol);
+            if let Some(sym) = model.and_then(|model| find_exact_definition(index, &model, filter))
+            {
+                return Ok((sym, None));
+            }
+        }
+    }
+    find_definition_with(index, symbol, filter).map(|sym| (sym, None))
+}
+
+/// Add the attached repository a result came from, if any, to its JSON.
+fn note_source(output: &mut serde_json::Value, source: Option<&str>) {
+    if let Some(source) = source {
+        output["source"] = serde_json::Value::String(source.to_string());
+    }
+}
+
+/// Add whether a conditionally compiled symbol is part of the build.
+fn note_compilation(output: &mut serde_json::Value, compilation: Option<&Compilation>) {
+    if let Some(compilation) = compilation {
+        output["condition"] = compilation.condition.clone().into();
+        output["compiled"] = compilation.compiled.into();
+    }
+}
+
+/// Whether `sym` is compiled with the defines the index was built with and
+/// those in `.rocketindex.toml`, if it is conditional at all.
+fn compilation_of(index: &SqliteIndex, sym: &rocketindex::Symbol) -> Option<Compilation> {
+    let mut defines: Vec<String> = index
+        .get_metadata(conditional::DEFINES_KEY)
+        .ok()
+        .flatten()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    if let Ok(cwd) = std::env::current_dir() {
+        defines.extend(Config::load(&cwd).defines);
+    }
+    conditional::compilation(sym, &defines)
+}
+
+/// Remember the defines an index was built with, for [`compilation_of`].
+fn record_defines(root: &Path, defines: &[String]) -> Result<()> {
+    let db_path = root.join(".rocketindex").join(DEFAULT_DB_NAME);
+    let index = SqliteIndex::open(&db_path).context("Failed to open index")?;
+    index.set_metadata(conditional::DEFINES_KEY, &serde_json::to_string(defines)?)?;
+    Ok(())
+}
+
+/// JSON for a symbol that resolved to nothing
+fn not_found_json(
+    error: &str,
+    symbol: &str,
+    suggestions: &[rocketindex::fuzzy::Suggestion],
+) -> serde_json::Value {
+    let suggestion_strs: Vec<&str> = suggestions.iter().map(|s| s.value.as_str()).collect();
+    serde_json::json!({
+        "error": error,
+        "symbol": symbol,
+        "suggestions": suggestion_strs
+    })
+}
+
+/// JSON for `rkt def`
+fn location_json(
     sym: &rocketindex::Symbol,
     context: bool,
     git: bool,
-    format: OutputFormat,
-    quiet: bool,
     concise: bool,
-) -> Result<()> {
+    sandbox: Option<&Path>,
+) -> serde_json::Value {
     let loc = &sym.location;
 
     // Get git info if requested
@@ -1310,40 +3706,72 @@ fn output_location(
         None
     };
 
-    if format == OutputFormat::Json {
-        let output = if concise {
-            // Concise mode: minimal fields only
-            let mut output = serde_json::json!({
-                "file": loc.file.display().to_string(),
-                "line": loc.line,
-                "column": loc.column,
-            });
-            if let Some(info) = git_info {
-                output["git"] = serde_json::json!(info);
-            }
-            output
-        } else {
-            // Full mode: all fields
-            let mut output = serde_json::json!({
-                "file": loc.file.display().to_string(),
-                "line": loc.line,
-                "column": loc.column,
-                "name": sym.name,
-                "qualified": sym.qualified,
-                "kind": format!("{}", sym.kind),
-            });
+    if concise {
+        // Concise mode: minimal fields only
+        let mut output = serde_json::json!({
+            "file": loc.file.display().to_string(),
+            "line": loc.line,
+            "column": loc.column,
+        });
+        if let Some(info) = git_info {
+            output["git"] = serde_json::json!(info);
+        }
+        output
+    } else {
+        // Full mode: all fields
+        let mut output = serde_json::json!({
+            "file": loc.file.display().to_string(),
+            "line": loc.line,
+            "column": loc.column,
+            "name": sym.name,
+            "qualified": sym.qualified,
+            "kind": format!("{}", sym.kind),
+            "guid": sym.guid(),
+        });
 
-            if context {
-                if let Some(line_content) = get_line_content(&loc.file, loc.line as usize) {
-                    output["context"] = serde_json::Value::String(line_content);
-                }
-            }
-            if let Some(info) = git_info {
-                output["git"] = serde_json::json!(info);
+        if context {
+            if let Some(line_content) = get_line_content(&loc.file, loc.line as usize, sandbox) {
+                output["context"] = serde_json::Value::String(line_content);
             }
-            output
-        };
+        }
+        if let Some(info) = git_info {
+            output["git"] = serde_json::json!(info);
+        }
+        output
+    }
+}
+
+/// Print where `sym` is defined; `renamed_from` is the old name it was
+/// looked up by and `source` the attached repository it's from, if any.
+/// `views` are the templates a Rails controller action renders.
+#[allow(clippy::too_many_arguments)]
+fn output_location(
+    sym: &rocketindex::Symbol,
+    context: bool,
+    git: bool,
+    renamed_from: Option<&str>,
+    source: Option<&str>,
+    views: &[PathBuf],
+    compilation: Option<&Compilation>,
+    format: OutputFormat,
+    quiet: bool,
+    concise: bool,
+) -> Result<()> {
+    let loc = &sym.location;
 
+    if format == OutputFormat::Editor {
+        jump::print([loc]);
+        return Ok(());
+    }
+    if format == OutputFormat::Json {
+        let mut output = location_json(sym, context, git, concise, None);
+        note_renamed(&mut output, renamed_from, &sym.qualified);
+        note_source(&mut output, source);
+        note_compilation(&mut output, compilation);
+        if !views.is_empty() {
+            output["views"] = serde_json::json!(views);
+        }
+        let output = annotate_freshness(output);
         println!(
             "{}",
             if concise {
@@ -1353,9 +3781,40 @@ fn output_location(
             }
         );
     } else if !quiet {
-        println!("{}:{}:{}", loc.file.display(), loc.line, loc.column);
+        if let Some(old) = renamed_from {
+            report_renamed(old, &sym.qualified);
+        }
+        // Get git info if requested
+        let git_info = if git {
+            // Assume running from workspace root, so relative path works
+            git::get_blame(&loc.file, loc.line).ok()
+        } else {
+            None
+        };
+
+        match source {
+            Some(source) => println!(
+                "{}:{}:{} [{}]",
+                loc.file.display(),
+                loc.line,
+                loc.column,
+                source
+            ),
+            None => println!("{}:{}:{}", loc.file.display(), loc.line, loc.column),
+        }
+        for view in views {
+            println!("    View: {}", view.display());
+        }
+        match compilation {
+            Some(c) if c.compiled => println!("    Compiled only when: {}", c.condition),
+            Some(c) => println!(
+                "    Not compiled: needs {} (see rkt index --define)",
+                c.condition
+            ),
+            None => {}
+        }
         if context {
-            if let Some(line_content) = get_line_content(&loc.file, loc.line as usize) {
+            if let Some(line_content) = get_line_content(&loc.file, loc.line as usize, None) {
                 println!("    {}", line_content.trim());
             }
         }
@@ -1377,11 +3836,16 @@ fn output_location(
 }
 
 /// Find references to a symbol or list references in a file
+#[allow(clippy::too_many_arguments)]
 fn cmd_refs(
     file: Option<&Path>,
     symbol: Option<&str>,
     path_filter: Option<&Path>,
     context_lines: usize,
+    min_confidence: Confidence,
+    kinds: &[ReferenceKind],
+    grep: bool,
+    diff_baseline: Option<&Path>,
     format: OutputFormat,
     quiet: bool,
     concise: bool,
@@ -1396,12 +3860,16 @@ fn cmd_refs(
             sym,
             path_filter,
             context_lines,
+            min_confidence,
+            kinds,
+            grep,
+            diff_baseline,
             format,
             quiet,
             concise,
         ),
         // File mode: list all references in a file
-        (Some(f), None) => cmd_refs_file(&index, f, path_filter, format, quiet, concise),
+        (Some(f), None) => cmd_refs_file(&index, f, path_filter, kinds, format, quiet, concise),
         // Neither specified
         (None, None) => {
             anyhow::bail!("Either --file or --symbol must be specified");
@@ -1414,40 +3882,54 @@ fn cmd_refs(
 }
 
 /// Find all uses of a symbol across the codebase
+#[allow(clippy::too_many_arguments)]
 fn cmd_refs_symbol(
     index: &rocketindex::db::SqliteIndex,
     symbol: &str,
     path_filter: Option<&Path>,
     context_lines: usize,
+    min_confidence: Confidence,
+    kinds: &[ReferenceKind],
+    grep: bool,
+    diff_baseline: Option<&Path>,
     format: OutputFormat,
     quiet: bool,
     concise: bool,
 ) -> Result<u8> {
-    let all_references = index
-        .find_references(symbol)
-        .context("Failed to find references")?;
-
-    // Filter by path if specified
-    let references: Vec<_> = if let Some(filter_path) = path_filter {
-        // Canonicalize the filter path to handle relative paths
-        let abs_filter = if filter_path.is_absolute() {
-            filter_path.to_path_buf()
-        } else {
-            std::env::current_dir()
-                .unwrap_or_default()
-                .join(filter_path)
-        };
-        all_references
-            .into_iter()
-            .filter(|r| r.location.file.starts_with(&abs_filter))
-            .collect()
+    let renamed = index.follow_alias(symbol)?;
+    if let Some(new) = &renamed {
+        report_renamed(symbol, new);
+    }
+    let symbol = renamed.as_deref().unwrap_or(symbol);
+    let mut references = find_symbol_references(index, symbol, path_filter, min_confidence)?;
+    references.retain(|(r, _)| kinds.is_empty() || kinds.contains(&r.kind));
+    let text_matches = if grep {
+        grep_references(index, symbol, &references, path_filter)?
     } else {
-        all_references
+        Vec::new()
     };
 
-    if references.is_empty() {
+    if let Some(baseline) = diff_baseline {
+        let mut current = refs_json(&references, 0, None);
+        if grep {
+            for r in &mut current {
+                r["provenance"] = "index".into();
+            }
+            current.extend(text_matches_json(symbol, &text_matches, 0));
+        }
+        return print_refs_diff(
+            symbol,
+            baseline::read(baseline)?,
+            current,
+            format,
+            quiet,
+            concise,
+        );
+    }
+
+    if references.is_empty() && text_matches.is_empty() {
         if format == OutputFormat::Json {
-            println!("[]");
+            println!("{}", output_budget().list(Vec::new()));
         } else if !quiet {
             if let Some(filter) = &path_filter {
                 eprintln!(
@@ -1462,30 +3944,27 @@ fn cmd_refs_symbol(
         return Ok(exit_codes::NOT_FOUND);
     }
 
-    if format == OutputFormat::Json {
-        let refs: Vec<_> = references
+    if format == OutputFormat::Editor {
+        let text_locations: Vec<Location> = text_matches
             .iter()
-            .map(|r| {
-                let mut obj = serde_json::json!({
-                    "name": r.name,
-                    "file": r.location.file.display().to_string(),
-                    "line": r.location.line,
-                    "column": r.location.column,
-                });
-
-                // Add context if requested
-                if context_lines > 0 {
-                    if let Ok(context) =
-                        get_context_lines(&r.location.file, r.location.line, context_lines)
-                    {
-                        obj["context"] = serde_json::Value::String(context);
-                    }
-                }
-
-                obj
-            })
+            .map(|m| Location::new(m.file.clone(), m.line, m.column))
             .collect();
-
+        jump::print(
+            references
+                .iter()
+                .map(|(r, _)| &r.location)
+                .chain(&text_locations),
+        );
+    } else if format == OutputFormat::Json {
+        let mut refs = refs_json(&references, context_lines, None);
+        if grep {
+            // Say where each result came from once text matches are mixed in
+            for r in &mut refs {
+                r["provenance"] = "index".into();
+            }
+            refs.extend(text_matches_json(symbol, &text_matches, context_lines));
+        }
+        let refs = annotate_freshness(output_budget().list(refs));
         println!(
             "{}",
             if concise {
@@ -1495,28 +3974,45 @@ fn cmd_refs_symbol(
             }
         );
     } else if !quiet {
-        println!("References to '{}' ({} found):", symbol, references.len());
+        println!(
+            "References to '{}' ({} found):",
+            symbol,
+            references.len() + text_matches.len()
+        );
         println!();
 
-        for reference in &references {
+        let text_locations = text_matches.iter().map(|m| {
+            let location = Location::new(m.file.clone(), m.line, m.column);
+            (location, " (text match)")
+        });
+        let locations: Vec<_> = references
+            .iter()
+            .map(|(reference, confidence)| {
+                let note = match confidence {
+                    Confidence::StringMatch => " (string literal)",
+                    Confidence::Heuristic => " (name match)",
+                    Confidence::Qualified | Confidence::Exact => "",
+                };
+                (reference.location.clone(), note)
+            })
+            .chain(text_locations)
+            .collect();
+        for (location, note) in &locations {
             println!(
-                "  {}:{}:{}",
-                reference.location.file.display(),
-                reference.location.line,
-                reference.location.column
+                "  {}:{}:{}{}",
+                location.file.display(),
+                location.line,
+                location.column,
+                note
             );
 
             if context_lines > 0 {
-                if let Ok(context) = get_context_lines(
-                    &reference.location.file,
-                    reference.location.line,
-                    context_lines,
-                ) {
+                if let Ok(context) = get_context_lines(&location.file, location.line, context_lines)
+                {
                     for (i, line) in context.lines().enumerate() {
-                        let line_num =
-                            reference.location.line as i64 - context_lines as i64 + i as i64;
+                        let line_num = location.line as i64 - context_lines as i64 + i as i64;
                         if line_num > 0 {
-                            let marker = if line_num == reference.location.line as i64 {
+                            let marker = if line_num == location.line as i64 {
                                 ">"
                             } else {
                                 " "
@@ -1533,32 +4029,232 @@ fn cmd_refs_symbol(
     Ok(exit_codes::SUCCESS)
 }
 
-/// List all references in a file
-fn cmd_refs_file(
-    index: &rocketindex::db::SqliteIndex,
-    file: &Path,
-    _path_filter: Option<&Path>, // Not used for file mode
+/// `rkt refs --diff-baseline`: the references of `symbol` added and
+/// removed since `baseline` (see [`baseline`])
+fn print_refs_diff(
+    symbol: &str,
+    baseline: Vec<serde_json::Value>,
+    current: Vec<serde_json::Value>,
     format: OutputFormat,
     quiet: bool,
     concise: bool,
 ) -> Result<u8> {
-    let file = file.canonicalize().context("Failed to resolve file path")?;
-
-    let references = index
-        .references_in_file(&file)
-        .context("Failed to get references")?;
+    let (baseline_count, current_count) = (baseline.len(), current.len());
+    let diff = baseline::diff(baseline, current, &std::env::current_dir()?);
+    let location = |r: &serde_json::Value| {
+        Location::new(
+            PathBuf::from(r["file"].as_str().unwrap_or_default()),
+            r["line"].as_u64().unwrap_or_default() as u32,
+            r["column"].as_u64().unwrap_or_default() as u32,
+        )
+    };
 
-    if format == OutputFormat::Json {
-        let refs: Vec<_> = references
-            .iter()
-            .map(|r| {
-                serde_json::json!({
-                    "name": r.name,
+    if format == OutputFormat::Editor {
+        let added: Vec<Location> = diff.added.iter().map(location).collect();
+        jump::print(&added);
+    } else if format == OutputFormat::Json {
+        let output = serde_json::json!({
+            "symbol": symbol,
+            "baseline": baseline_count,
+            "current": current_count,
+            "added": diff.added,
+            "removed": diff.removed,
+        });
+        println!(
+            "{}",
+            if concise {
+                serde_json::to_string(&output)?
+            } else {
+                serde_json::to_string_pretty(&output)?
+            }
+        );
+    } else if !quiet {
+        println!(
+            "References to '{}' since baseline: {} -> {} (+{}, -{})",
+            symbol,
+            baseline_count,
+            current_count,
+            diff.added.len(),
+            diff.removed.len()
+        );
+        for (sign, references) in [("+", &diff.added), ("-", &diff.removed)] {
+            for r in references {
+                let location = location(r);
+                println!(
+                    "  {} {}:{}:{}",
+                    sign,
+                    location.file.display(),
+                    location.line,
+                    location.column
+                );
+            }
+        }
+    }
+
+    Ok(exit_codes::SUCCESS)
+}
+
+/// Uses of `symbol` at or above `min_confidence`, under `path_filter` if given
+fn find_symbol_references(
+    index: &SqliteIndex,
+    symbol: &str,
+    path_filter: Option<&Path>,
+    min_confidence: Confidence,
+) -> Result<Vec<(rocketindex::index::Reference, Confidence)>> {
+    // Includes string-literal matches (only present when indexed with --string-refs)
+    // at the lowest confidence, so callers can filter them out
+    let all_references = index
+        .find_references_with_confidence(symbol, min_confidence)
+        .context("Failed to find references")?;
+
+    // Filter by path if specified
+    let references: Vec<_> = if let Some(filter_path) = path_filter {
+        // Canonicalize the filter path to handle relative paths
+        let abs_filter = if filter_path.is_absolute() {
+            filter_path.to_path_buf()
+        } else {
+            std::env::current_dir()
+                .unwrap_or_default()
+                .join(filter_path)
+        };
+        all_references
+            .into_iter()
+            .filter(|(r, _)| r.location.file.starts_with(&abs_filter))
+            .collect()
+    } else {
+        all_references
+    };
+
+    Ok(references)
+}
+
+/// JSON for `rkt refs <symbol>`
+fn refs_json(
+    references: &[(rocketindex::index::Reference, Confidence)],
+    context_lines: usize,
+    sandbox: Option<&Path>,
+) -> Vec<serde_json::Value> {
+    references
+        .iter()
+        .map(|(r, confidence)| {
+            let mut obj = serde_json::json!({
+                "name": r.name,
+                "file": r.location.file.display().to_string(),
+                "line": r.location.line,
+                "column": r.location.column,
+                "kind": r.kind,
+                "confidence": confidence,
+            });
+            if *confidence == Confidence::StringMatch {
+                obj["string_literal"] = serde_json::Value::Bool(true);
+            }
+
+            // Add context if requested
+            if context_lines > 0 {
+                if let Ok(context) =
+                    context_lines_within(&r.location.file, r.location.line, context_lines, sandbox)
+                {
+                    obj["context"] = serde_json::Value::String(context);
+                }
+            }
+
+            obj
+        })
+        .collect()
+}
+
+/// Whole-word matches of `symbol`'s name in the workspace's source files
+/// that aren't already an indexed reference or a definition, for
+/// `rkt refs --grep`
+fn grep_references(
+    index: &SqliteIndex,
+    symbol: &str,
+    references: &[(rocketindex::index::Reference, Confidence)],
+    path_filter: Option<&Path>,
+) -> Result<Vec<TextMatch>> {
+    let word = symbol.rsplit(['.', ':', '#']).next().unwrap_or(symbol);
+    let root = std::env::current_dir()?;
+    let mut files = workspace_source_files(&root, &Config::load(&root))?;
+    if let Some(filter) = path_filter {
+        let filter = root.join(filter);
+        files.retain(|file| file.starts_with(&filter));
+    }
+
+    let mut known: std::collections::HashSet<(PathBuf, u32)> = references
+        .iter()
+        .map(|(r, _)| (r.location.file.clone(), r.location.line))
+        .collect();
+    known.extend(
+        index
+            .search(word, 1000, None)?
+            .into_iter()
+            .filter(|s| s.name == word)
+            .map(|s| (s.location.file, s.location.line)),
+    );
+
+    let mut matches = grep::find_word(&files, word);
+    matches.retain(|m| !known.contains(&(m.file.clone(), m.line)));
+    Ok(matches)
+}
+
+fn text_matches_json(
+    symbol: &str,
+    matches: &[TextMatch],
+    context_lines: usize,
+) -> Vec<serde_json::Value> {
+    matches
+        .iter()
+        .map(|m| {
+            let mut obj = serde_json::json!({
+                "name": symbol,
+                "file": m.file.display().to_string(),
+                "line": m.line,
+                "column": m.column,
+                "text": m.text,
+                "provenance": "grep",
+            });
+            if context_lines > 0 {
+                if let Ok(context) = get_context_lines(&m.file, m.line, context_lines) {
+                    obj["context"] = serde_json::Value::String(context);
+                }
+            }
+            obj
+        })
+        .collect()
+}
+
+/// List all references in a file
+fn cmd_refs_file(
+    index: &rocketindex::db::SqliteIndex,
+    file: &Path,
+    _path_filter: Option<&Path>, // Not used for file mode
+    kinds: &[ReferenceKind],
+    format: OutputFormat,
+    quiet: bool,
+    concise: bool,
+) -> Result<u8> {
+    let file = file.canonicalize().context("Failed to resolve file path")?;
+
+    let mut references = index
+        .references_in_file(&file)
+        .context("Failed to get references")?;
+    references.retain(|r| kinds.is_empty() || kinds.contains(&r.kind));
+
+    if format == OutputFormat::Editor {
+        jump::print(references.iter().map(|r| &r.location));
+    } else if format == OutputFormat::Json {
+        let refs: Vec<_> = references
+            .iter()
+            .map(|r| {
+                serde_json::json!({
+                    "name": r.name,
                     "line": r.location.line,
                     "column": r.location.column,
+                    "kind": r.kind,
                 })
             })
             .collect();
+        let refs = annotate_freshness(output_budget().list(refs));
         println!(
             "{}",
             if concise {
@@ -1587,108 +4283,232 @@ fn cmd_refs_file(
     Ok(exit_codes::SUCCESS)
 }
 
+/// `rkt opens`: the files opening `module`, or the opens of `file` (or of
+/// every file, with `unused`) and whether they're used
+fn cmd_opens(
+    module: Option<&str>,
+    file: Option<&Path>,
+    unused: bool,
+    format: OutputFormat,
+    quiet: bool,
+) -> Result<u8> {
+    warn_if_no_session(quiet);
+    let index = load_sqlite_index()?;
+
+    if let Some(module) = module {
+        let sites = index.files_opening(module)?;
+        let lines = open_lines(&sites);
+        if format == OutputFormat::Json {
+            let files: Vec<_> = sites
+                .iter()
+                .zip(&lines)
+                .map(|(site, line)| {
+                    serde_json::json!({
+                        "file": site.file.display().to_string(),
+                        "line": line,
+                    })
+                })
+                .collect();
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "module": module,
+                    "files": files,
+                    "count": sites.len(),
+                }))?
+            );
+        } else if !quiet {
+            if sites.is_empty() {
+                println!("No files open {}", module);
+            } else {
+                println!("Files that open {} ({}):", module, sites.len());
+                for (site, line) in sites.iter().zip(&lines) {
+                    match line {
+                        Some(line) => println!("  {}:{}", site.file.display(), line),
+                        None => println!("  {}", site.file.display()),
+                    }
+                }
+            }
+        }
+        return Ok(if sites.is_empty() {
+            exit_codes::NOT_FOUND
+        } else {
+            exit_codes::SUCCESS
+        });
+    }
+
+    let files = match file {
+        Some(file) => vec![file.canonicalize().context("Failed to resolve file path")?],
+        None => index.files_with_opens()?,
+    };
+    let mut usage = Vec::new();
+    for file in &files {
+        usage.extend(index.open_usage(file)?);
+    }
+    let lines = open_lines(&usage.iter().map(|u| u.site.clone()).collect::<Vec<_>>());
+    let mut opens: Vec<_> = usage.iter().zip(lines).collect();
+    if unused {
+        opens.retain(|(u, _)| u.status == OpenStatus::Unused);
+    }
+
+    if format == OutputFormat::Json {
+        let list: Vec<_> = opens
+            .iter()
+            .map(|(u, line)| {
+                serde_json::json!({
+                    "file": u.site.file.display().to_string(),
+                    "module": u.site.module,
+                    "line": line,
+                    "status": u.status,
+                    "uses": u.uses,
+                })
+            })
+            .collect();
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "opens": list,
+                "count": opens.len(),
+            }))?
+        );
+    } else if !quiet {
+        if opens.is_empty() {
+            println!("No {}opens found", if unused { "unused " } else { "" });
+        }
+        for (u, line) in &opens {
+            let location = match line {
+                Some(line) => format!("{}:{}", u.site.file.display(), line),
+                None => u.site.file.display().to_string(),
+            };
+            let status = match u.status {
+                OpenStatus::Used => format!("used {}x", u.uses),
+                OpenStatus::Unused => "unused".to_string(),
+                OpenStatus::External => "external".to_string(),
+            };
+            println!("{:<40} {:<30} {}", location, u.site.module, status);
+        }
+    }
+
+    Ok(exit_codes::SUCCESS)
+}
+
+/// Source lines of opens. The index records where each falls among its
+/// file's opens; the line is the next one, in order, naming its module.
+fn open_lines(sites: &[OpenSite]) -> Vec<Option<u32>> {
+    let mut sources: std::collections::HashMap<&Path, Option<String>> =
+        std::collections::HashMap::new();
+    let mut lines = Vec::with_capacity(sites.len());
+    let mut previous: Option<(&Path, usize)> = None;
+    for site in sites {
+        let source = sources
+            .entry(&site.file)
+            .or_insert_with(|| std::fs::read_to_string(&site.file).ok());
+        let after = match previous {
+            Some((file, line)) if file == site.file => line,
+            _ => 0,
+        };
+        let found = source.as_deref().and_then(|source| {
+            source
+                .lines()
+                .enumerate()
+                .skip(after)
+                .find(|(_, line)| line.contains(site.module.as_str()))
+                .map(|(i, _)| i + 1)
+        });
+        if let Some(line) = found {
+            previous = Some((&site.file, line));
+        }
+        lines.push(found.map(|line| line as u32));
+    }
+    lines
+}
+
 /// Get context lines around a specific line in a file
 fn get_context_lines(file: &Path, line: u32, context: usize) -> Result<String> {
-    let content = std::fs::read_to_string(file)?;
-    let lines: Vec<&str> = content.lines().collect();
+    context_lines_within(file, line, context, None)
+}
+
+/// Context lines around `line`, reading `file` through the MCP sandbox when
+/// `sandbox` names the workspace root (for requests from the network).
+///
+/// A line past the end of the file (a stale index) is an error rather than
+/// a panic.
+fn context_lines_within(
+    file: &Path,
+    line: u32,
+    context: usize,
+    sandbox: Option<&Path>,
+) -> Result<String> {
+    let lines = read_source_lines(file, sandbox)
+        .with_context(|| format!("Cannot read {}", file.display()))?;
 
     let line_idx = line.saturating_sub(1) as usize;
+    if line_idx >= lines.len() {
+        anyhow::bail!("Line {} is past the end of {}", line, file.display());
+    }
     let start = line_idx.saturating_sub(context);
-    let end = (line_idx + context + 1).min(lines.len());
+    let end = line_idx
+        .saturating_add(context)
+        .saturating_add(1)
+        .min(lines.len());
 
     Ok(lines[start..end].join("\n"))
 }
 
+/// The lines of `file`; with a `sandbox` root, only if the sandbox allows
+/// reading it (inside the root, a regular file, not oversized).
+fn read_source_lines(file: &Path, sandbox: Option<&Path>) -> Option<Vec<String>> {
+    match sandbox {
+        Some(root) => mcp::sandbox::read_lines(file, root),
+        None => std::fs::read_to_string(file)
+            .ok()
+            .map(|content| content.lines().map(str::to_string).collect()),
+    }
+}
+
 /// Spider from an entry point
+#[allow(clippy::too_many_arguments)]
 fn cmd_spider(
     symbol: &str,
     depth: usize,
     reverse: bool,
+    min_confidence: Confidence,
     format: OutputFormat,
     quiet: bool,
     concise: bool,
 ) -> Result<u8> {
     warn_if_no_session(quiet);
-    // Spider still uses CodeIndex for now since it has complex resolution logic
-    // TODO: Update spider to use SqliteIndex
-    let index = load_code_index()?;
-
-    // First try to find the entry point
-    let entry_qualified = if index.get(symbol).is_some() {
-        symbol.to_string()
-    } else {
-        // Try to find it via search
-        let matches = index.search(symbol);
-        if let Some(first) = matches.first() {
-            first.qualified.clone()
-        } else {
-            // Get fuzzy suggestions from all symbol names (short and qualified)
-            let all_names = index.all_names_for_fuzzy();
-            let suggestions = rocketindex::fuzzy::find_similar(
-                symbol,
-                all_names.iter().map(|s| s.as_str()),
-                rocketindex::fuzzy::DEFAULT_MAX_DISTANCE,
-                rocketindex::fuzzy::DEFAULT_MAX_SUGGESTIONS,
-            );
-
-            if format == OutputFormat::Json {
-                let suggestion_strs: Vec<&str> =
-                    suggestions.iter().map(|s| s.value.as_str()).collect();
-                println!(
-                    "{}",
-                    serde_json::json!({
-                        "error": "Entry point not found",
-                        "symbol": symbol,
-                        "suggestions": suggestion_strs
-                    })
-                );
-            } else {
-                eprintln!("Entry point not found: {}", symbol);
-                if !suggestions.is_empty() {
-                    eprintln!("Did you mean:");
-                    for s in &suggestions {
-                        eprintln!("  {} (distance: {})", s.value, s.distance);
-                    }
-                }
-            }
+    let renamed = renamed_to(symbol);
+    let query = daemon::Query::Spider {
+        symbol: renamed.clone().unwrap_or_else(|| symbol.to_string()),
+        depth,
+        reverse,
+        min_confidence,
+    };
+    let (result, workspace_root) = match query_code_index(&query)? {
+        daemon::Answer::Spider {
+            result,
+            workspace_root,
+            ..
+        } => (result, workspace_root),
+        daemon::Answer::NotFound { suggestions } => {
+            print_not_found("Entry point not found", symbol, &suggestions, format);
             return Ok(exit_codes::NOT_FOUND);
         }
+        other => anyhow::bail!("Unexpected answer to a spider query: {:?}", other),
     };
 
-    let result = if reverse {
-        reverse_spider(&index, &entry_qualified, depth)
-    } else {
-        spider(&index, &entry_qualified, depth)
-    };
+    if let Some(new) = &renamed {
+        report_renamed(symbol, new);
+    }
 
     if format == OutputFormat::Json {
-        let nodes: Vec<_> = result
-            .nodes
-            .iter()
-            .map(|n| {
-                if concise {
-                    // Concise mode: minimal fields
-                    serde_json::json!({
-                        "qualified": n.symbol.qualified,
-                        "depth": n.depth,
-                    })
-                } else {
-                    serde_json::json!({
-                        "name": n.symbol.name,
-                        "qualified": n.symbol.qualified,
-                        "file": n.symbol.location.file.display().to_string(),
-                        "line": n.symbol.location.line,
-                        "column": n.symbol.location.column,
-                        "depth": n.depth,
-                    })
-                }
-            })
-            .collect();
-
-        let output = serde_json::json!({
-            "nodes": nodes,
-            "unresolved": result.unresolved,
-        });
+        let mut output = spider_json(&result, concise);
+        output_budget().trim_graph(&mut output);
+        if let Some(new) = &renamed {
+            note_renamed(&mut output, Some(symbol), new);
+        }
+        let output = annotate_freshness(output);
         println!(
             "{}",
             if concise {
@@ -1697,6 +4517,10 @@ fn cmd_spider(
                 serde_json::to_string_pretty(&output)?
             }
         );
+    } else if format == OutputFormat::Dot {
+        print!("{}", spider_to_dot(&result, workspace_root.as_deref()));
+    } else if format == OutputFormat::Mermaid {
+        print!("{}", spider_to_mermaid(&result, workspace_root.as_deref()));
     } else if !quiet {
         print!("{}", format_spider_result(&result));
     }
@@ -1705,84 +4529,46 @@ fn cmd_spider(
 }
 
 /// Find direct callers of a symbol (single-level reverse spider)
-fn cmd_callers(symbol: &str, format: OutputFormat, quiet: bool, concise: bool) -> Result<u8> {
+fn cmd_callers(
+    symbol: &str,
+    min_confidence: Confidence,
+    format: OutputFormat,
+    quiet: bool,
+    concise: bool,
+) -> Result<u8> {
     warn_if_no_session(quiet);
-    let index = load_code_index()?;
-
-    // First try to find the symbol
-    let qualified = if index.get(symbol).is_some() {
-        symbol.to_string()
-    } else {
-        let matches = index.search(symbol);
-        if let Some(first) = matches.first() {
-            first.qualified.clone()
-        } else {
-            // Get fuzzy suggestions (short and qualified names)
-            let all_names = index.all_names_for_fuzzy();
-            let suggestions = rocketindex::fuzzy::find_similar(
-                symbol,
-                all_names.iter().map(|s| s.as_str()),
-                rocketindex::fuzzy::DEFAULT_MAX_DISTANCE,
-                rocketindex::fuzzy::DEFAULT_MAX_SUGGESTIONS,
-            );
-
-            if format == OutputFormat::Json {
-                let suggestion_strs: Vec<&str> =
-                    suggestions.iter().map(|s| s.value.as_str()).collect();
-                println!(
-                    "{}",
-                    serde_json::json!({
-                        "error": "Symbol not found",
-                        "symbol": symbol,
-                        "suggestions": suggestion_strs
-                    })
-                );
-            } else {
-                eprintln!("Symbol not found: {}", symbol);
-                if !suggestions.is_empty() {
-                    eprintln!("Did you mean:");
-                    for s in &suggestions {
-                        eprintln!("  {} (distance: {})", s.value, s.distance);
-                    }
-                }
-            }
+    let renamed = renamed_to(symbol);
+    // Single-level reverse spider
+    let query = daemon::Query::Spider {
+        symbol: renamed.clone().unwrap_or_else(|| symbol.to_string()),
+        depth: 1,
+        reverse: true,
+        min_confidence,
+    };
+    let (qualified, result) = match query_code_index(&query)? {
+        daemon::Answer::Spider { entry, result, .. } => (entry, result),
+        daemon::Answer::NotFound { suggestions } => {
+            print_not_found("Symbol not found", symbol, &suggestions, format);
             return Ok(exit_codes::NOT_FOUND);
         }
+        other => anyhow::bail!("Unexpected answer to a callers query: {:?}", other),
     };
 
-    // Use reverse_spider with depth=1 for single-level callers
-    let result = reverse_spider(&index, &qualified, 1);
-
-    // Filter to only show callers (depth=1), not the symbol itself (depth=0)
-    let callers: Vec<_> = result.nodes.iter().filter(|n| n.depth == 1).collect();
-
-    if format == OutputFormat::Json {
-        let caller_list: Vec<_> = callers
-            .iter()
-            .map(|n| {
-                if concise {
-                    serde_json::json!({
-                        "qualified": n.symbol.qualified,
-                        "file": n.symbol.location.file.display().to_string(),
-                        "line": n.symbol.location.line,
-                    })
-                } else {
-                    serde_json::json!({
-                        "name": n.symbol.name,
-                        "qualified": n.symbol.qualified,
-                        "kind": format!("{}", n.symbol.kind),
-                        "file": n.symbol.location.file.display().to_string(),
-                        "line": n.symbol.location.line,
-                        "column": n.symbol.location.column,
-                    })
-                }
-            })
-            .collect();
-
-        let output = serde_json::json!({
-            "symbol": qualified,
-            "callers": caller_list,
-        });
+    if format == OutputFormat::Editor {
+        jump::print(
+            result
+                .nodes
+                .iter()
+                .filter(|n| n.depth == 1)
+                .map(|n| &n.symbol.location),
+        );
+    } else if format == OutputFormat::Json {
+        let mut output = callers_json(&qualified, &result, concise);
+        output_budget().trim_field(&mut output, "callers");
+        if let Some(new) = &renamed {
+            note_renamed(&mut output, Some(symbol), new);
+        }
+        let output = annotate_freshness(output);
         println!(
             "{}",
             if concise {
@@ -1792,16 +4578,26 @@ fn cmd_callers(symbol: &str, format: OutputFormat, quiet: bool, concise: bool) -
             }
         );
     } else if !quiet {
+        if let Some(new) = &renamed {
+            report_renamed(symbol, new);
+        }
+        // Only the callers (depth=1), not the symbol itself (depth=0)
+        let callers: Vec<_> = result.nodes.iter().filter(|n| n.depth == 1).collect();
         if callers.is_empty() {
             println!("No callers found for: {}", qualified);
         } else {
             println!("Callers of {}:", qualified);
             for caller in callers {
                 println!(
-                    "  {} ({}:{})",
+                    "  {} ({}:{}){}",
                     caller.symbol.qualified,
                     caller.symbol.location.file.display(),
-                    caller.symbol.location.line
+                    caller.symbol.location.line,
+                    if caller.confidence < Confidence::Qualified {
+                        " (name match)"
+                    } else {
+                        ""
+                    }
                 );
             }
         }
@@ -1810,17 +4606,203 @@ fn cmd_callers(symbol: &str, format: OutputFormat, quiet: bool, concise: bool) -
     Ok(exit_codes::SUCCESS)
 }
 
-/// Find classes that inherit from a parent class
-fn cmd_subclasses(parent: &str, format: OutputFormat, quiet: bool, concise: bool) -> Result<u8> {
-    warn_if_no_session(quiet);
-    let cwd = std::env::current_dir()?;
-    let db_path = cwd.join(".rocketindex").join(DEFAULT_DB_NAME);
-    if !db_path.exists() {
-        if format == OutputFormat::Json {
-            println!(
-                "{}",
-                serde_json::json!({
-                    "error": "IndexNotFound",
+/// JSON for `rkt spider`
+fn spider_json(result: &SpiderResult, concise: bool) -> serde_json::Value {
+    let nodes: Vec<_> = result
+        .nodes
+        .iter()
+        .map(|n| {
+            if concise {
+                // Concise mode: minimal fields
+                serde_json::json!({
+                    "qualified": n.symbol.qualified,
+                    "depth": n.depth,
+                    "parent": n.parent,
+                })
+            } else {
+                serde_json::json!({
+                    "name": n.symbol.name,
+                    "qualified": n.symbol.qualified,
+                    "file": n.symbol.location.file.display().to_string(),
+                    "line": n.symbol.location.line,
+                    "column": n.symbol.location.column,
+                    "depth": n.depth,
+                    "confidence": n.confidence,
+                    "parent": n.parent,
+                    "source": n.source,
+                })
+            }
+        })
+        .collect();
+    let edges: Vec<_> = result
+        .edges
+        .iter()
+        .map(|e| {
+            if concise {
+                serde_json::json!({ "from": e.from, "to": e.to })
+            } else {
+                let sites: Vec<_> = e
+                    .sites
+                    .iter()
+                    .map(|site| {
+                        serde_json::json!({
+                            "file": site.file.display().to_string(),
+                            "line": site.line,
+                            "column": site.column,
+                        })
+                    })
+                    .collect();
+                serde_json::json!({ "from": e.from, "to": e.to, "sites": sites })
+            }
+        })
+        .collect();
+
+    serde_json::json!({
+        "nodes": nodes,
+        "edges": edges,
+        "external_nodes": external_nodes_json(result, concise),
+        "truncated": result.truncated,
+    })
+}
+
+/// Unresolved references grouped by the package they're attributed to
+/// (`null` for names no package claims).
+fn external_nodes_json(result: &SpiderResult, concise: bool) -> serde_json::Value {
+    let groups: Vec<_> = result
+        .external_by_package()
+        .into_iter()
+        .map(|group| {
+            let symbols: Vec<_> = group
+                .nodes
+                .iter()
+                .map(|node| {
+                    if concise {
+                        serde_json::json!(node.name)
+                    } else {
+                        serde_json::json!({
+                            "name": node.name,
+                            "referenced_from": node.referenced_from,
+                        })
+                    }
+                })
+                .collect();
+            serde_json::json!({ "package": group.package, "symbols": symbols })
+        })
+        .collect();
+    serde_json::Value::Array(groups)
+}
+
+/// JSON for `rkt callers`: the depth-1 nodes of a reverse spider from `qualified`
+fn callers_json(qualified: &str, result: &SpiderResult, concise: bool) -> serde_json::Value {
+    let caller_list: Vec<_> = result
+        .nodes
+        .iter()
+        .filter(|n| n.depth == 1)
+        .map(|n| {
+            if concise {
+                serde_json::json!({
+                    "qualified": n.symbol.qualified,
+                    "file": n.symbol.location.file.display().to_string(),
+                    "line": n.symbol.location.line,
+                })
+            } else {
+                serde_json::json!({
+                    "name": n.symbol.name,
+                    "qualified": n.symbol.qualified,
+                    "kind": format!("{}", n.symbol.kind),
+                    "file": n.symbol.location.file.display().to_string(),
+                    "line": n.symbol.location.line,
+                    "column": n.symbol.location.column,
+                    "confidence": n.confidence,
+                })
+            }
+        })
+        .collect();
+
+    serde_json::json!({
+        "symbol": qualified,
+        "callers": caller_list,
+    })
+}
+
+/// The name `symbol` was renamed to (see `rkt alias`). Checked without
+/// refreshing the index, so it stays cheap next to a daemon query.
+fn renamed_to(symbol: &str) -> Option<String> {
+    load_sqlite_index_with_refresh(false)
+        .ok()?
+        .follow_alias(symbol)
+        .ok()
+        .flatten()
+}
+
+/// What to tell someone who looked a symbol up by its old name
+fn renamed_note(old: &str, new: &str) -> String {
+    format!("'{}' was renamed to '{}'", old, new)
+}
+
+/// Tell someone who looked a symbol up by its old name what it's called now
+fn report_renamed(old: &str, new: &str) {
+    notice::info("SymbolRenamed", renamed_note(old, new))
+        .with("old_name", old)
+        .with("new_name", new)
+        .emit();
+}
+
+/// Add a `deprecated` note to a JSON answer looked up by the old name `old`.
+fn note_renamed(output: &mut serde_json::Value, old: Option<&str>, new: &str) {
+    if let Some(old) = old {
+        output["deprecated"] = serde_json::json!({
+            "old_name": old,
+            "new_name": new,
+            "note": renamed_note(old, new),
+        });
+    }
+}
+
+/// Answer `query` through the daemon serving this workspace, or from the
+/// index on disk when there is none (or `--project` narrows the index).
+fn query_code_index(query: &daemon::Query) -> Result<daemon::Answer> {
+    if PROJECT.get().is_none() {
+        let cwd = std::env::current_dir()?;
+        if let Some(answer) = daemon::ask(&cwd, query) {
+            return Ok(answer);
+        }
+    }
+    let index = load_code_index()?;
+    Ok(daemon::answer(&index, query))
+}
+
+/// Report a symbol that resolved to nothing, with "did you mean" suggestions.
+fn print_not_found(
+    error: &str,
+    symbol: &str,
+    suggestions: &[rocketindex::fuzzy::Suggestion],
+    format: OutputFormat,
+) {
+    if format == OutputFormat::Json {
+        println!("{}", not_found_json(error, symbol, suggestions));
+    } else {
+        eprintln!("{}: {}", error, symbol);
+        if !suggestions.is_empty() {
+            eprintln!("Did you mean:");
+            for s in suggestions {
+                eprintln!("  {} (distance: {})", s.value, s.distance);
+            }
+        }
+    }
+}
+
+/// Find classes that inherit from a parent class
+fn cmd_subclasses(parent: &str, format: OutputFormat, quiet: bool, concise: bool) -> Result<u8> {
+    warn_if_no_session(quiet);
+    let cwd = std::env::current_dir()?;
+    let db_path = cwd.join(".rocketindex").join(DEFAULT_DB_NAME);
+    if !db_path.exists() {
+        if format == OutputFormat::Json {
+            println!(
+                "{}",
+                serde_json::json!({
+                    "error": "IndexNotFound",
                     "message": "No index found. Run 'rkt index' first."
                 })
             );
@@ -1830,10 +4812,13 @@ fn cmd_subclasses(parent: &str, format: OutputFormat, quiet: bool, concise: bool
         return Ok(exit_codes::ERROR);
     }
 
-    let db = SqliteIndex::open(&db_path)?;
+    let db = open_index(&db_path, &cwd)?;
+    apply_project_scope(&db)?;
     let subclasses = db.find_subclasses(parent)?;
 
-    if format == OutputFormat::Json {
+    if format == OutputFormat::Editor {
+        jump::print(subclasses.iter().map(|s| &s.location));
+    } else if format == OutputFormat::Json {
         let subclass_list: Vec<_> = subclasses
             .iter()
             .map(|s| {
@@ -1893,6 +4878,66 @@ fn cmd_subclasses(parent: &str, format: OutputFormat, quiet: bool, concise: bool
     }
 }
 
+/// Show the ancestors and descendants of a type
+fn cmd_hierarchy(
+    type_name: &str,
+    depth: usize,
+    format: OutputFormat,
+    quiet: bool,
+    concise: bool,
+) -> Result<u8> {
+    warn_if_no_session(quiet);
+    let cwd = std::env::current_dir()?;
+    let db_path = cwd.join(".rocketindex").join(DEFAULT_DB_NAME);
+    if !db_path.exists() {
+        if format == OutputFormat::Json {
+            println!(
+                "{}",
+                serde_json::json!({
+                    "error": "IndexNotFound",
+                    "message": "No index found. Run 'rkt index' first."
+                })
+            );
+        } else {
+            eprintln!("No index found. Run 'rkt index' first.");
+        }
+        return Ok(exit_codes::ERROR);
+    }
+
+    let db = open_index(&db_path, &cwd)?;
+    apply_project_scope(&db)?;
+    let Some(hierarchy) = rocketindex::hierarchy::type_hierarchy(&db, type_name, depth)? else {
+        print_not_found(
+            "Type not found",
+            type_name,
+            &suggest(&db, type_name),
+            format,
+        );
+        return Ok(exit_codes::NOT_FOUND);
+    };
+
+    if format == OutputFormat::Json {
+        let output = serde_json::to_value(&hierarchy)?;
+        println!(
+            "{}",
+            if concise {
+                serde_json::to_string(&output)?
+            } else {
+                serde_json::to_string_pretty(&output)?
+            }
+        );
+    } else if format == OutputFormat::Mermaid {
+        print!(
+            "{}",
+            rocketindex::hierarchy::hierarchy_to_mermaid(&hierarchy)
+        );
+    } else if !quiet {
+        print!("{}", rocketindex::hierarchy::format_hierarchy(&hierarchy));
+    }
+
+    Ok(exit_codes::SUCCESS)
+}
+
 /// Find types that implement an interface
 fn cmd_implements(interface: &str, format: OutputFormat, quiet: bool, concise: bool) -> Result<u8> {
     warn_if_no_session(quiet);
@@ -1913,10 +4958,13 @@ fn cmd_implements(interface: &str, format: OutputFormat, quiet: bool, concise: b
         return Ok(exit_codes::ERROR);
     }
 
-    let db = SqliteIndex::open(&db_path)?;
+    let db = open_index(&db_path, &cwd)?;
+    apply_project_scope(&db)?;
     let implementers = db.find_implementers(interface)?;
 
-    if format == OutputFormat::Json {
+    if format == OutputFormat::Editor {
+        jump::print(implementers.iter().map(|s| &s.location));
+    } else if format == OutputFormat::Json {
         let implementer_list: Vec<_> = implementers
             .iter()
             .map(|s| {
@@ -1980,115 +5028,85 @@ fn cmd_implements(interface: &str, format: OutputFormat, quiet: bool, concise: b
     }
 }
 
-/// Search for symbols matching a pattern
-fn cmd_symbols(
-    pattern: &str,
-    language: Option<&str>,
-    fuzzy: bool,
+/// Report module dependency cycles
+fn cmd_cycles(
+    max_size: Option<usize>,
     format: OutputFormat,
     quiet: bool,
     concise: bool,
 ) -> Result<u8> {
     warn_if_no_session(quiet);
-    let index = load_sqlite_index()?;
+    load_sqlite_index()?;
+    let index = load_code_index()?;
 
-    if fuzzy {
-        // Fuzzy search mode - find symbols within edit distance
-        let matches = index.fuzzy_search(
-            pattern,
-            rocketindex::fuzzy::DEFAULT_MAX_DISTANCE,
-            100,
-            language,
-        )?;
+    let cycles: Vec<_> = ModuleGraph::build(&index)
+        .cycles()
+        .into_iter()
+        .filter(|c| max_size.is_none_or(|max| c.modules.len() <= max))
+        .collect();
 
-        if format == OutputFormat::Json {
-            let symbols: Vec<_> = matches
+    let cwd = std::env::current_dir()?;
+    match format {
+        OutputFormat::Dot => print!("{}", cycles_to_dot(&cycles)),
+        OutputFormat::Json => {
+            let cycle_list: Vec<_> = cycles
                 .iter()
-                .map(|(s, distance)| {
-                    if concise {
-                        serde_json::json!({
-                            "qualified": s.qualified,
-                            "file": s.location.file.display().to_string(),
-                            "line": s.location.line,
-                        })
-                    } else {
-                        serde_json::json!({
-                            "name": s.name,
-                            "qualified": s.qualified,
-                            "kind": format!("{}", s.kind),
-                            "file": s.location.file.display().to_string(),
-                            "line": s.location.line,
-                            "column": s.location.column,
-                            "distance": distance,
+                .map(|c| {
+                    let edges: Vec<_> = c
+                        .edges
+                        .iter()
+                        .map(|e| {
+                            serde_json::json!({
+                                "from": e.from,
+                                "to": e.to,
+                                "references": e.references,
+                                "file": workspace_relative_path(&e.example.file, &cwd),
+                                "line": e.example.line,
+                            })
                         })
-                    }
-                })
-                .collect();
-            println!(
-                "{}",
-                if concise {
-                    serde_json::to_string(&symbols)?
-                } else {
-                    serde_json::to_string_pretty(&symbols)?
-                }
-            );
-        } else if !quiet {
-            for (sym, distance) in matches {
-                println!(
-                    "{:<40} {}:{}:{:<8} {} (distance: {})",
-                    sym.qualified,
-                    sym.location.file.display(),
-                    sym.location.line,
-                    sym.location.column,
-                    sym.kind,
-                    distance
-                );
-            }
-        }
-    } else {
-        // Standard pattern search
-        let matches = index.search(pattern, 100, language)?;
-
-        if format == OutputFormat::Json {
-            let symbols: Vec<_> = matches
-                .iter()
-                .map(|s| {
+                        .collect();
                     if concise {
-                        serde_json::json!({
-                            "qualified": s.qualified,
-                            "file": s.location.file.display().to_string(),
-                            "line": s.location.line,
-                        })
+                        serde_json::json!({ "modules": c.modules })
                     } else {
-                        serde_json::json!({
-                            "name": s.name,
-                            "qualified": s.qualified,
-                            "kind": format!("{}", s.kind),
-                            "file": s.location.file.display().to_string(),
-                            "line": s.location.line,
-                            "column": s.location.column,
-                        })
+                        serde_json::json!({ "modules": c.modules, "edges": edges })
                     }
                 })
                 .collect();
+            let output = serde_json::json!({
+                "cycles": cycle_list,
+                "count": cycles.len(),
+            });
             println!(
                 "{}",
                 if concise {
-                    serde_json::to_string(&symbols)?
+                    serde_json::to_string(&output)?
                 } else {
-                    serde_json::to_string_pretty(&symbols)?
+                    serde_json::to_string_pretty(&output)?
                 }
             );
-        } else if !quiet {
-            for sym in matches {
+        }
+        _ if quiet => {}
+        _ => {
+            if cycles.is_empty() {
+                println!("No dependency cycles found");
+            }
+            for (i, cycle) in cycles.iter().enumerate() {
                 println!(
-                    "{:<40} {}:{}:{:<8} {}",
-                    sym.qualified,
-                    sym.location.file.display(),
-                    sym.location.line,
-                    sym.location.column,
-                    sym.kind
+                    "Cycle {} ({} modules): {}",
+                    i + 1,
+                    cycle.modules.len(),
+                    cycle.modules.join(", ")
                 );
+                for edge in &cycle.edges {
+                    println!(
+                        "  {} -> {} ({} refs, e.g. {}:{})",
+                        edge.from,
+                        edge.to,
+                        edge.references,
+                        workspace_relative_path(&edge.example.file, &cwd),
+                        edge.example.line
+                    );
+                }
             }
         }
     }
@@ -2096,53 +5114,447 @@ fn cmd_symbols(
     Ok(exit_codes::SUCCESS)
 }
 
-/// Watch for file changes
-fn cmd_watch(root: &Path, format: OutputFormat, quiet: bool) -> Result<u8> {
-    use rocketindex::pidfile::PidFileError;
-    use rocketindex::watch::{DebouncedFileWatcher, DEFAULT_DEBOUNCE_DURATION};
-    use std::sync::atomic::{AtomicBool, Ordering};
-    use std::sync::Arc;
+/// Report references that resolve to nothing
+fn cmd_check(
+    min_severity: Severity,
+    fail_on: Severity,
+    format: OutputFormat,
+    quiet: bool,
+    concise: bool,
+) -> Result<u8> {
+    warn_if_no_session(quiet);
+    load_sqlite_index()?;
+    let index = load_code_index()?;
 
-    let root = root
-        .canonicalize()
-        .context("Failed to resolve root directory")?;
+    let mut report = check::check(&index);
+    report.retain_at_least(min_severity);
+    let failed = report.worst().is_some_and(|worst| worst >= fail_on);
 
-    // Acquire PID file lock to ensure only one watch process runs per directory
-    let _pid_guard: PidFileGuard = match acquire_watch_lock(&root) {
-        Ok(guard) => guard,
-        Err(PidFileError::AlreadyRunning(pid)) => {
-            if format == OutputFormat::Json {
+    if format == OutputFormat::Editor {
+        let locations: Vec<Location> = report
+            .files
+            .iter()
+            .flat_map(|file| {
+                file.findings
+                    .iter()
+                    .map(|f| Location::new(file.file.clone(), f.line, f.column))
+            })
+            .collect();
+        jump::print(&locations);
+    } else if format == OutputFormat::Json {
+        let mut output = serde_json::to_value(&report)?;
+        for severity in Severity::ALL {
+            output[format!("{}s", severity)] = report.count(severity).into();
+        }
+        println!(
+            "{}",
+            if concise {
+                serde_json::to_string(&output)?
+            } else {
+                serde_json::to_string_pretty(&output)?
+            }
+        );
+    } else if !quiet {
+        for file in &report.files {
+            println!("{}", file.file.display());
+            for finding in &file.findings {
+                let hint = match (finding.problem, &finding.suggestion) {
+                    (check::Problem::Typo, Some(name)) => format!(" (did you mean {}?)", name),
+                    (check::Problem::MissingOpen, Some(module)) => {
+                        format!(" (defined in {}, which isn't opened)", module)
+                    }
+                    _ => String::new(),
+                };
                 println!(
-                    "{}",
-                    serde_json::json!({
-                        "error": "AlreadyRunning",
-                        "pid": pid,
-                        "message": format!("Watch mode is already running (pid {})", pid)
-                    })
+                    "  {}:{} {}: {}{}",
+                    finding.line, finding.column, finding.severity, finding.name, hint
                 );
-            } else {
-                eprintln!("Watch mode is already running (pid {})", pid);
             }
-            return Ok(exit_codes::ERROR);
         }
-        Err(e) => {
-            anyhow::bail!("Failed to acquire watch lock: {}", e);
-        }
-    };
+        println!(
+            "{} references checked: {} errors, {} warnings, {} info",
+            report.checked,
+            report.count(Severity::Error),
+            report.count(Severity::Warning),
+            report.count(Severity::Info)
+        );
+    }
 
-    // First, ensure index exists (incremental if already exists)
+    Ok(if failed {
+        exit_codes::NOT_FOUND
+    } else {
+        exit_codes::SUCCESS
+    })
+}
+
+/// Report complexity metrics by file or module
+fn cmd_metrics(
+    by: Grouping,
+    top: usize,
+    format: OutputFormat,
+    quiet: bool,
+    concise: bool,
+) -> Result<u8> {
+    warn_if_no_session(quiet);
+    let index = load_sqlite_index()?;
+    let root = match index.get_metadata("workspace_root")? {
+        Some(root) => PathBuf::from(root),
+        None => std::env::current_dir()?,
+    };
+
+    let files = index.file_stats()?;
+    let mut report = metrics::report(&files, &index.file_dependencies()?, &root, by);
+    let total = report.len();
+    if top > 0 {
+        report.truncate(top);
+    }
+
+    if format == OutputFormat::Json {
+        let output = serde_json::json!({
+            "by": if by == Grouping::Module { "module" } else { "file" },
+            "total": total,
+            "metrics": report,
+        });
+        println!(
+            "{}",
+            if concise {
+                serde_json::to_string(&output)?
+            } else {
+                serde_json::to_string_pretty(&output)?
+            }
+        );
+    } else if !quiet {
+        if files.is_empty() {
+            println!("No metrics recorded; run 'rkt index --rebuild' to measure the workspace");
+            return Ok(exit_codes::NOT_FOUND);
+        }
+        println!(
+            "{:>10}  {:>7}  {:>7}  {:>6}  {:>7}  {}",
+            "COMPLEXITY",
+            "NESTING",
+            "SYMBOLS",
+            "FAN-IN",
+            "FAN-OUT",
+            if by == Grouping::Module {
+                "MODULE"
+            } else {
+                "FILE"
+            }
+        );
+        for row in &report {
+            println!(
+                "{:>10}  {:>7}  {:>7}  {:>6}  {:>7}  {}",
+                row.complexity, row.max_nesting, row.symbols, row.fan_in, row.fan_out, row.name
+            );
+        }
+        if report.len() < total {
+            println!("({} of {}; --top 0 for all)", report.len(), total);
+        }
+    }
+
+    Ok(if files.is_empty() {
+        exit_codes::NOT_FOUND
+    } else {
+        exit_codes::SUCCESS
+    })
+}
+
+/// Rank files or modules by churn, complexity and fan-in
+fn cmd_hotspots(
+    since: &str,
+    by: Grouping,
+    top: usize,
+    format: OutputFormat,
+    quiet: bool,
+    concise: bool,
+) -> Result<u8> {
+    warn_if_no_session(quiet);
+    let index = load_sqlite_index()?;
+    let root = match index.get_metadata("workspace_root")? {
+        Some(root) => PathBuf::from(root),
+        None => std::env::current_dir()?,
+    };
+
+    let files = index.file_stats()?;
+    let changes = git::numstat(&root, (since != "all").then_some(since))?;
+    let metrics = metrics::report(&files, &index.file_dependencies()?, &root, by);
+    let mut hotspots = rocketindex::hotspots::rank(&metrics, &changes, &root, by);
+    let total = hotspots.len();
+    if top > 0 {
+        hotspots.truncate(top);
+    }
+
+    if format == OutputFormat::Json {
+        let output = serde_json::json!({
+            "since": since,
+            "by": if by == Grouping::Module { "module" } else { "file" },
+            "total": total,
+            "hotspots": hotspots,
+        });
+        println!(
+            "{}",
+            if concise {
+                serde_json::to_string(&output)?
+            } else {
+                serde_json::to_string_pretty(&output)?
+            }
+        );
+    } else if !quiet {
+        if files.is_empty() {
+            println!("No metrics recorded; run 'rkt index --rebuild' to measure the workspace");
+        } else if hotspots.is_empty() {
+            println!("Nothing indexed changed since {}", since);
+        } else {
+            println!(
+                "{:>8}  {:>7}  {:>10}  {:>6}  {}",
+                "SCORE",
+                "COMMITS",
+                "COMPLEXITY",
+                "FAN-IN",
+                if by == Grouping::Module {
+                    "MODULE"
+                } else {
+                    "FILE"
+                }
+            );
+            for hotspot in &hotspots {
+                println!(
+                    "{:>8}  {:>7}  {:>10}  {:>6}  {}",
+                    hotspot.score,
+                    hotspot.commits,
+                    hotspot.complexity,
+                    hotspot.fan_in,
+                    hotspot.name
+                );
+            }
+            if hotspots.len() < total {
+                println!("({} of {}; --top 0 for all)", hotspots.len(), total);
+            }
+        }
+    }
+
+    Ok(if hotspots.is_empty() {
+        exit_codes::NOT_FOUND
+    } else {
+        exit_codes::SUCCESS
+    })
+}
+
+/// Search for symbols matching a pattern
+fn cmd_symbols(
+    pattern: &str,
+    filter: &SearchFilter,
+    fuzzy: bool,
+    format: OutputFormat,
+    quiet: bool,
+    concise: bool,
+) -> Result<u8> {
+    warn_if_no_session(quiet);
+    let index = load_sqlite_index()?;
+
+    if fuzzy {
+        // Fuzzy search mode - abbreviations and typos, best match first
+        let mut matches = index.fuzzy_search(
+            pattern,
+            rocketindex::fuzzy::DEFAULT_MAX_DISTANCE,
+            100,
+            filter.language.as_deref(),
+        )?;
+        matches.retain(|m| filter.matches(&m.symbol));
+
+        if format == OutputFormat::Editor {
+            jump::print(matches.iter().map(|m| &m.symbol.location));
+        } else if format == OutputFormat::Json {
+            let symbols =
+                annotate_freshness(output_budget().list(fuzzy_symbols_json(&matches, concise)));
+            println!(
+                "{}",
+                if concise {
+                    serde_json::to_string(&symbols)?
+                } else {
+                    serde_json::to_string_pretty(&symbols)?
+                }
+            );
+        } else if !quiet {
+            for m in matches {
+                println!(
+                    "{:<40} {}:{}:{:<8} {} (score: {})",
+                    m.symbol.qualified,
+                    m.symbol.location.file.display(),
+                    m.symbol.location.line,
+                    m.symbol.location.column,
+                    m.symbol.kind,
+                    m.score
+                );
+            }
+        }
+    } else {
+        // Standard pattern search
+        let matches = index.search_with(pattern, 100, filter)?;
+
+        if format == OutputFormat::Editor {
+            jump::print(matches.iter().map(|sym| &sym.location));
+        } else if format == OutputFormat::Json {
+            let symbols = annotate_freshness(output_budget().list(symbols_json(&matches, concise)));
+            println!(
+                "{}",
+                if concise {
+                    serde_json::to_string(&symbols)?
+                } else {
+                    serde_json::to_string_pretty(&symbols)?
+                }
+            );
+        } else if !quiet {
+            for sym in matches {
+                println!(
+                    "{:<40} {}:{}:{:<8} {}",
+                    sym.qualified,
+                    sym.location.file.display(),
+                    sym.location.line,
+                    sym.location.column,
+                    sym.kind
+                );
+            }
+        }
+    }
+
+    Ok(exit_codes::SUCCESS)
+}
+
+/// JSON for `rkt symbols`
+fn symbols_json(matches: &[rocketindex::Symbol], concise: bool) -> Vec<serde_json::Value> {
+    matches
+        .iter()
+        .map(|s| {
+            if concise {
+                serde_json::json!({
+                    "qualified": s.qualified,
+                    "file": s.location.file.display().to_string(),
+                    "line": s.location.line,
+                })
+            } else {
+                let mut output = serde_json::json!({
+                    "name": s.name,
+                    "qualified": s.qualified,
+                    "kind": format!("{}", s.kind),
+                    "file": s.location.file.display().to_string(),
+                    "line": s.location.line,
+                    "column": s.location.column,
+                    "guid": s.guid(),
+                    "visibility": s.visibility.to_string(),
+                });
+                if s.is_constructor() {
+                    output["constructor"] = true.into();
+                }
+                output
+            }
+        })
+        .collect()
+}
+
+/// JSON for `rkt symbols --fuzzy`: matches with their edit distance
+fn fuzzy_symbols_json(
+    matches: &[rocketindex::fuzzy::FuzzyMatch],
+    concise: bool,
+) -> Vec<serde_json::Value> {
+    matches
+        .iter()
+        .map(|m| {
+            let s = &m.symbol;
+            if concise {
+                serde_json::json!({
+                    "qualified": s.qualified,
+                    "file": s.location.file.display().to_string(),
+                    "line": s.location.line,
+                    "score": m.score,
+                })
+            } else {
+                serde_json::json!({
+                    "name": s.name,
+                    "qualified": s.qualified,
+                    "kind": format!("{}", s.kind),
+                    "file": s.location.file.display().to_string(),
+                    "line": s.location.line,
+                    "column": s.location.column,
+                    "guid": s.guid(),
+                    "score": m.score,
+                    "distance": m.distance,
+                })
+            }
+        })
+        .collect()
+}
+
+/// Watch mode; with `serve_queries`, also run the query daemon (see [`daemon`]).
+fn cmd_watch(
+    root: &Path,
+    serve_queries: bool,
+    dependencies: bool,
+    format: OutputFormat,
+    quiet: bool,
+) -> Result<u8> {
+    use rocketindex::pidfile::PidFileError;
+    use rocketindex::watch::{DebouncedFileWatcher, DEFAULT_DEBOUNCE_DURATION};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    let root = root
+        .canonicalize()
+        .context("Failed to resolve root directory")?;
+
+    // Acquire PID file lock to ensure only one watch process runs per directory
+    let _pid_guard: PidFileGuard = match acquire_watch_lock(&root) {
+        Ok(guard) => guard,
+        Err(PidFileError::AlreadyRunning(pid)) => {
+            if format == OutputFormat::Json {
+                println!(
+                    "{}",
+                    serde_json::json!({
+                        "error": "AlreadyRunning",
+                        "pid": pid,
+                        "message": format!("Watch mode is already running (pid {})", pid)
+                    })
+                );
+            } else {
+                eprintln!("Watch mode is already running (pid {})", pid);
+            }
+            return Ok(exit_codes::ERROR);
+        }
+        Err(e) => {
+            anyhow::bail!("Failed to acquire watch lock: {}", e);
+        }
+    };
+
+    // First, ensure index exists (incremental if already exists)
     if !quiet {
         println!("Building initial index...");
     }
-    cmd_index(&root, false, 1000, false, format, quiet)?;
+    cmd_index(&root, false, 1000, false, false, false, format, quiet)?;
 
     // Load config for recursion depth
     let config = Config::load(&root);
     let max_depth = config.max_recursion_depth;
+    let dependencies = dependencies || config.index_dependencies;
 
     // Open SQLite index for batch processing
     let db_path = root.join(".rocketindex").join(DEFAULT_DB_NAME);
-    let index = SqliteIndex::open(&db_path).context("Failed to open index")?;
+    let index = open_index(&db_path, &root)?;
+    if dependencies {
+        index_watched_dependencies(&index, &root, max_depth, format, quiet);
+    }
+
+    // The daemon answers from memory and rebuilds after every batch
+    let served = if serve_queries {
+        let code_index = build_code_index(&index, root.clone())?;
+        let shared = Arc::new(std::sync::RwLock::new(code_index));
+        let guard = daemon::serve(&root, Arc::clone(&shared))?;
+        if !quiet {
+            println!("Serving queries on {}", daemon::address(&root));
+        }
+        Some((shared, guard))
+    } else {
+        None
+    };
 
     let mut watcher = DebouncedFileWatcher::new(&root, DEFAULT_DEBOUNCE_DURATION)
         .context("Failed to create file watcher")?;
@@ -2179,9 +5591,39 @@ fn cmd_watch(root: &Path, format: OutputFormat, quiet: bool) -> Result<u8> {
         if batch.should_flush() {
             match batch.flush(&index) {
                 Ok(stats) => {
-                    if !quiet && (stats.files_updated > 0 || stats.files_deleted > 0) {
+                    let mut changed = stats.files_updated > 0 || stats.files_deleted > 0;
+                    if !quiet && changed {
                         print_batch_stats(&stats, format);
                     }
+                    // New imports may lead into packages not indexed yet
+                    if changed && dependencies {
+                        changed |=
+                            index_watched_dependencies(&index, &root, max_depth, format, quiet);
+                    }
+                    if changed && index.needs_maintenance().unwrap_or(false) {
+                        match index.maintain() {
+                            Ok(report) if !quiet => {
+                                if format == OutputFormat::Json {
+                                    let mut event = serde_json::to_value(&report)?;
+                                    event["event"] = "index_maintained".into();
+                                    println!("{}", event);
+                                } else {
+                                    print_maintenance_report(&report);
+                                }
+                            }
+                            Ok(_) => {}
+                            Err(e) => tracing::warn!("Index maintenance failed: {}", e),
+                        }
+                    }
+                    if let (true, Some((shared, _))) = (changed, &served) {
+                        // Build outside the lock so queries keep being answered
+                        match build_code_index(&index, root.clone()) {
+                            Ok(fresh) => {
+                                *shared.write().expect("daemon index lock poisoned") = fresh
+                            }
+                            Err(e) => tracing::warn!("Failed to reload daemon index: {}", e),
+                        }
+                    }
                 }
                 Err(e) => {
                     tracing::warn!("Batch flush failed: {}", e);
@@ -2207,95 +5649,588 @@ fn cmd_watch(root: &Path, format: OutputFormat, quiet: bool) -> Result<u8> {
     Ok(exit_codes::SUCCESS)
 }
 
-/// Print batch processing statistics
-fn print_batch_stats(stats: &BatchStats, format: OutputFormat) {
-    if format == OutputFormat::Json {
-        println!(
-            "{}",
-            serde_json::json!({
-                "event": "batch_processed",
-                "files_updated": stats.files_updated,
-                "files_deleted": stats.files_deleted,
-                "symbols_inserted": stats.symbols_inserted,
-                "references_inserted": stats.references_inserted,
-                "duration_ms": stats.duration.as_millis()
-            })
-        );
-    } else {
-        if stats.files_updated > 0 {
-            println!(
-                "Updated {} file(s): {} symbols, {} references ({}ms)",
-                stats.files_updated,
-                stats.symbols_inserted,
-                stats.references_inserted,
-                stats.duration.as_millis()
-            );
-        }
-        if stats.files_deleted > 0 {
-            println!("Deleted {} file(s) from index", stats.files_deleted);
-        }
-    }
-}
+/// Log of sessions started with `--background`, inside `.rocketindex/`
+const WATCH_LOG: &str = "logs/watch.log";
 
-/// Load the SQLite index from disk
-fn load_sqlite_index() -> Result<SqliteIndex> {
-    load_sqlite_index_with_refresh(true)
+/// Run `command` out of the terminal's process group, so Ctrl+C there
+/// doesn't reach it.
+fn detach(command: &mut std::process::Command) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const DETACHED_PROCESS: u32 = 0x0000_0008;
+        const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+        command.creation_flags(DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP);
+    }
 }
 
-fn load_sqlite_index_with_refresh(auto_refresh: bool) -> Result<SqliteIndex> {
-    let cwd = std::env::current_dir()?;
-    let db_path = cwd.join(".rocketindex").join(DEFAULT_DB_NAME);
+/// Start watch mode (or the daemon) as a detached process logging to
+/// [`WATCH_LOG`], and return once it holds the PID file.
+fn cmd_watch_background(
+    root: &Path,
+    serve_queries: bool,
+    dependencies: bool,
+    format: OutputFormat,
+    quiet: bool,
+) -> Result<u8> {
+    use std::time::Duration;
 
-    if !db_path.exists() {
+    let root = root
+        .canonicalize()
+        .context("Failed to resolve root directory")?;
+
+    if let Some(pid) = find_watch_process(&root) {
+        if format == OutputFormat::Json {
+            println!(
+                "{}",
+                serde_json::json!({
+                    "status": "already_running",
+                    "pid": pid,
+                    "message": "Watch mode is already running"
+                })
+            );
+        } else if !quiet {
+            println!("Watch mode is already running (pid {})", pid);
+        }
+        return Ok(exit_codes::SUCCESS);
+    }
+
+    let log_path = root.join(".rocketindex").join(WATCH_LOG);
+    if let Some(parent) = log_path.parent() {
+        std::fs::create_dir_all(parent).context("Failed to create log directory")?;
+    }
+    let log = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&log_path)
+        .with_context(|| format!("Failed to open {}", log_path.display()))?;
+
+    let mut command = std::process::Command::new(std::env::current_exe()?);
+    command
+        .arg(if serve_queries { "daemon" } else { "watch" })
+        .arg("--root")
+        .arg(&root)
+        .args(["--format", "text"])
+        .current_dir(&root)
+        .stdin(std::process::Stdio::null())
+        .stdout(log.try_clone()?)
+        .stderr(log);
+    if dependencies {
+        command.arg("--dependencies");
+    }
+    detach(&mut command);
+    let mut child = command
+        .spawn()
+        .context("Failed to start background session")?;
+    let pid = child.id();
+
+    // Callers (agents, scripts) can rely on the session once this returns
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while find_watch_process(&root) != Some(pid) {
+        if let Some(status) = child.try_wait()? {
+            anyhow::bail!(
+                "Background session exited ({}); see {}",
+                status,
+                log_path.display()
+            );
+        }
+        if Instant::now() > deadline {
+            anyhow::bail!(
+                "Background session (pid {}) didn't start within 10s; see {}",
+                pid,
+                log_path.display()
+            );
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+
+    if format == OutputFormat::Json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "status": "started",
+                "pid": pid,
+                "log": log_path.display().to_string(),
+            }))?
+        );
+    } else if !quiet {
+        println!(
+            "{} running in the background (pid {}), logging to {}",
+            if serve_queries {
+                "Daemon"
+            } else {
+                "Watch mode"
+            },
+            pid,
+            log_path.display()
+        );
+        println!("Stop it with 'rkt stop'.");
+    }
+    Ok(exit_codes::SUCCESS)
+}
+
+/// Stop the watch session for the current directory and wait for it to exit.
+fn cmd_stop(format: OutputFormat, quiet: bool) -> Result<u8> {
+    use std::time::Duration;
+
+    let cwd = std::env::current_dir()?;
+    let Some(pid) = stop_watch_process(&cwd).context("Failed to stop watch mode")? else {
+        if format == OutputFormat::Json {
+            println!(
+                "{}",
+                serde_json::json!({
+                    "status": "not_running",
+                    "message": "No watch session is running"
+                })
+            );
+        } else if !quiet {
+            println!("No watch session is running");
+        }
+        return Ok(exit_codes::SUCCESS);
+    };
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while find_watch_process(&cwd) == Some(pid) {
+        if Instant::now() > deadline {
+            anyhow::bail!("Watch mode (pid {}) didn't stop within 10s", pid);
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+
+    if format == OutputFormat::Json {
+        println!(
+            "{}",
+            serde_json::json!({
+                "status": "stopped",
+                "pid": pid,
+            })
+        );
+    } else if !quiet {
+        println!("Stopped watch mode (pid {})", pid);
+    }
+    Ok(exit_codes::SUCCESS)
+}
+
+/// Install or uninstall the daemon as a user service (see [`service`]).
+fn cmd_daemon_service(action: DaemonAction, format: OutputFormat, quiet: bool) -> Result<u8> {
+    let Some(manager) = service::Manager::current() else {
+        anyhow::bail!(
+            "No supported service manager on this platform; run 'rkt daemon --background'"
+        );
+    };
+    let (DaemonAction::Install { root } | DaemonAction::Uninstall { root }) = &action;
+    let root = root
+        .canonicalize()
+        .context("Failed to resolve root directory")?;
+    let exe = std::env::current_exe()?;
+    let service = service::Service::new(manager, root.clone(), exe);
+
+    match action {
+        DaemonAction::Install { .. } => {
+            if !root.join(".rocketindex").join(DEFAULT_DB_NAME).exists() {
+                anyhow::bail!(
+                    "No index found in {}. Run 'rkt index' first.",
+                    root.display()
+                );
+            }
+            // The service would keep failing to take the PID file
+            if let Some(pid) = find_watch_process(&root) {
+                anyhow::bail!(
+                    "Watch mode is already running here (pid {}); run 'rkt stop' first",
+                    pid
+                );
+            }
+            let path = service
+                .install()
+                .context("Failed to install the daemon service")?;
+            if format == OutputFormat::Json {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&serde_json::json!({
+                        "status": "installed",
+                        "service": service.name(),
+                        "path": path.map(|p| p.display().to_string()),
+                        "log": service.log.display().to_string(),
+                    }))?
+                );
+            } else if !quiet {
+                println!("Installed and started {}", service.name());
+                if let Some(path) = path {
+                    println!("  Definition: {}", path.display());
+                }
+                println!("  Log: {}", service.log.display());
+                println!("Remove it with 'rkt daemon uninstall'.");
+            }
+        }
+        DaemonAction::Uninstall { .. } => {
+            let removed = service
+                .uninstall()
+                .context("Failed to uninstall the daemon service")?;
+            if format == OutputFormat::Json {
+                println!(
+                    "{}",
+                    serde_json::json!({
+                        "status": if removed { "uninstalled" } else { "not_installed" },
+                        "service": service.name(),
+                    })
+                );
+            } else if !quiet {
+                if removed {
+                    println!("Stopped and removed {}", service.name());
+                } else {
+                    println!("No daemon service is installed for {}", root.display());
+                }
+            }
+        }
+    }
+    Ok(exit_codes::SUCCESS)
+}
+
+/// Bring the indexed dependency sources in line with the workspace's
+/// imports (see [`rocketindex::dependencies`]). Returns whether the index
+/// changed.
+fn index_watched_dependencies(
+    index: &SqliteIndex,
+    root: &Path,
+    max_depth: usize,
+    format: OutputFormat,
+    quiet: bool,
+) -> bool {
+    let report = index
+        .lock_for_writing(DEFAULT_LOCK_TIMEOUT)
+        .map_err(anyhow::Error::from)
+        .and_then(|_lock| Ok(index_dependencies(index, root, max_depth)?));
+    let report = match report {
+        Ok(report) => report,
+        Err(e) => {
+            notice::warning(
+                "DependencyIndexFailed",
+                format!("Failed to index dependencies: {}", e),
+            )
+            .with("reason", e.to_string())
+            .emit();
+            return false;
+        }
+    };
+    if quiet || !report.changed() {
+        return report.changed();
+    }
+    if format == OutputFormat::Json {
+        let mut event = serde_json::to_value(&report).unwrap_or_default();
+        event["event"] = "dependencies_indexed".into();
+        println!("{}", event);
+    } else {
+        if !report.indexed.is_empty() {
+            let names: Vec<&str> = report.indexed.iter().map(|d| d.name.as_str()).collect();
+            println!(
+                "Indexed {} dependenc{} ({} public symbols): {}",
+                names.len(),
+                if names.len() == 1 { "y" } else { "ies" },
+                report.symbols,
+                names.join(", ")
+            );
+        }
+        if !report.removed.is_empty() {
+            let names: Vec<&str> = report.removed.iter().map(|d| d.name.as_str()).collect();
+            println!(
+                "Removed dependencies no longer imported: {}",
+                names.join(", ")
+            );
+        }
+    }
+    true
+}
+
+/// Print batch processing statistics
+fn print_batch_stats(stats: &BatchStats, format: OutputFormat) {
+    if format == OutputFormat::Json {
+        println!(
+            "{}",
+            serde_json::json!({
+                "event": "batch_processed",
+                "files_updated": stats.files_updated,
+                "files_deleted": stats.files_deleted,
+                "symbols_inserted": stats.symbols_inserted,
+                "references_inserted": stats.references_inserted,
+                "duration_ms": stats.duration.as_millis()
+            })
+        );
+    } else {
+        if stats.files_updated > 0 {
+            println!(
+                "Updated {} file(s): {} symbols, {} references ({}ms)",
+                stats.files_updated,
+                stats.symbols_inserted,
+                stats.references_inserted,
+                stats.duration.as_millis()
+            );
+        }
+        if stats.files_deleted > 0 {
+            println!("Deleted {} file(s) from index", stats.files_deleted);
+        }
+    }
+}
+
+/// Source files of the workspace: everything under `root`, or only what is
+/// under the project roots when the config declares `roots`.
+fn workspace_source_files(root: &Path, config: &Config) -> Result<Vec<PathBuf>> {
+    let exclude_dirs = config.excluded_dirs();
+    if config.roots.is_empty() {
+        return find_source_files_with_config(root, &exclude_dirs, config.respect_gitignore)
+            .context("Failed to find source files");
+    }
+
+    let mut files = Vec::new();
+    for project in config.projects(root) {
+        files.extend(
+            find_source_files_with_config(&project.root, &exclude_dirs, config.respect_gitignore)
+                .with_context(|| format!("Failed to find source files in {}", project.name))?,
+        );
+    }
+    // Nested roots find the same files twice
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+fn project_names(projects: &[rocketindex::config::Project]) -> Vec<&str> {
+    projects.iter().map(|p| p.name.as_str()).collect()
+}
+
+/// Restrict `index` to the project given with `--project`, if any.
+fn apply_project_scope(index: &SqliteIndex) -> Result<()> {
+    if let Some(project) = PROJECT.get() {
+        if let Err(e) = index.scope_to_project(project) {
+            let known = index.projects().unwrap_or_default();
+            if known.is_empty() {
+                anyhow::bail!(
+                    "{}: --project needs `roots` in .rocketindex.toml (then re-run 'rkt index')",
+                    e
+                );
+            }
+            anyhow::bail!("{} (known projects: {})", e, known.join(", "));
+        }
+    }
+    Ok(())
+}
+
+/// Load the SQLite index from disk
+fn load_sqlite_index() -> Result<SqliteIndex> {
+    load_sqlite_index_with_refresh(true)
+}
+
+fn load_sqlite_index_with_refresh(auto_refresh: bool) -> Result<SqliteIndex> {
+    let cwd = std::env::current_dir()?;
+    let db_path = cwd.join(".rocketindex").join(DEFAULT_DB_NAME);
+
+    if !db_path.exists() {
+        // Checkouts using a distributed pack never write, so never refresh
+        if let Some(index) = open_workspace_pack(&cwd)? {
+            apply_project_scope(&index)?;
+            return Ok(index);
+        }
         anyhow::bail!("Index not found. Run 'rkt index' first.");
     }
 
-    let index = SqliteIndex::open(&db_path).context("Failed to open SQLite index")?;
+    let index = open_index(&db_path, &cwd)?;
 
     if auto_refresh {
         ensure_index_fresh(&index, &cwd)?;
     }
+    apply_project_scope(&index)?;
 
     Ok(index)
 }
 
+/// Open the index at `db_path`, migrating an older schema in place.
+///
+/// An index that can't be migrated (older than migrations go back, written
+/// by a newer rkt, or a failed upgrade step) is rebuilt from `root` instead.
+fn open_index(db_path: &Path, root: &Path) -> Result<SqliteIndex> {
+    match SqliteIndex::open(db_path) {
+        Err(e) if e.needs_rebuild() => {
+            report_schema_rebuild(&e);
+            cmd_index(
+                root,
+                false,
+                1000,
+                true,
+                false,
+                false,
+                OutputFormat::Text,
+                true,
+            )?;
+            SqliteIndex::open(db_path).context("Failed to open rebuilt index")
+        }
+        result => result.context("Failed to open SQLite index"),
+    }
+}
+
+/// Tell the user that the index is being rebuilt because of its schema.
+fn report_schema_rebuild(error: &rocketindex::IndexError) {
+    notice::info("IndexRebuilt", format!("{}. Rebuilding the index.", error))
+        .with("reason", error.to_string())
+        .with("schema_version", rocketindex::db::SCHEMA_VERSION)
+        .emit();
+}
+
+/// Open `.rocketindex/pack` read-only, if the workspace has one.
+fn open_workspace_pack(cwd: &Path) -> Result<Option<SqliteIndex>> {
+    let pack_dir = cwd.join(".rocketindex").join("pack");
+    if !pack::is_pack(&pack_dir) {
+        return Ok(None);
+    }
+    let (index, _manifest) = pack::open_pack(&pack_dir).context("Failed to open packed index")?;
+    Ok(Some(index))
+}
+
 /// Check for stale files and reindex them if needed.
 /// Targets <100ms for typical projects.
+///
+/// With `--refresh-budget`, stops reindexing once the budget is spent and
+/// records what was left stale for the query's output.
+#[tracing::instrument(name = "auto_refresh", skip_all, fields(stale))]
 fn ensure_index_fresh(index: &SqliteIndex, workspace_root: &Path) -> Result<()> {
+    let started = Instant::now();
+    let budget = REFRESH_BUDGET.get().copied();
+    let note_freshness = |stale: &[(PathBuf, &str)]| {
+        if budget.is_some() {
+            let db_path = workspace_root.join(".rocketindex").join(DEFAULT_DB_NAME);
+            let stale: Vec<PathBuf> = stale.iter().map(|(path, _)| path.clone()).collect();
+            let _ = FRESHNESS.set(freshness::Freshness::measure(
+                &db_path,
+                workspace_root,
+                &stale,
+            ));
+        }
+    };
+
     // Load config to get source files
     let config = Config::load(workspace_root);
-    let exclude_dirs = config.excluded_dirs();
-
-    let files =
-        find_source_files_with_config(workspace_root, &exclude_dirs, config.respect_gitignore)
-            .context("Failed to find source files")?;
+    let files = workspace_source_files(workspace_root, &config)?;
+
+    // Check for stale files; while a quick index is being completed, files
+    // not yet indexed are left to it
+    let quick_pending = quick::pending(index);
+    let find_stale = || -> Result<Vec<(PathBuf, &'static str)>> {
+        let mut stale = index.find_stale_files(&files)?;
+        if quick_pending {
+            stale.retain(|(_, reason)| *reason != "new");
+        }
+        Ok(stale)
+    };
+    let stale = find_stale()?;
+    if stale.is_empty() {
+        tracing::Span::current().record("stale", 0);
+        note_freshness(&stale);
+        return Ok(());
+    }
 
-    // Check for stale files
-    let stale = index.find_stale_files(&files)?;
+    // Another rkt may be refreshing the same files; wait for it, then look
+    // again so they're only reindexed once. If it takes too long, answer
+    // from the index as it is
+    let timeout = budget.map_or(DEFAULT_LOCK_TIMEOUT, |budget| {
+        budget
+            .saturating_sub(started.elapsed())
+            .min(DEFAULT_LOCK_TIMEOUT)
+    });
+    let _lock = match index.lock_for_writing(timeout) {
+        Ok(lock) => lock,
+        Err(e) => {
+            notice::warning(
+                "RefreshSkipped",
+                format!(
+                    "Answering from an index {} file(s) behind: {}",
+                    stale.len(),
+                    e
+                ),
+            )
+            .with("stale", stale.len())
+            .emit();
+            note_freshness(&stale);
+            return Ok(());
+        }
+    };
+    let stale = find_stale()?;
 
+    tracing::Span::current().record("stale", stale.len());
     if stale.is_empty() {
+        note_freshness(&stale);
+        return Ok(());
+    }
+
+    let Some(budget) = budget else {
+        reindex_files(index, &stale, config.max_recursion_depth);
+        note_refreshed(stale.len());
         return Ok(());
+    };
+
+    // Reindex a chunk at a time while the budget lasts
+    let mut remaining = &stale[..];
+    while !remaining.is_empty() && started.elapsed() < budget {
+        let (chunk, rest) = remaining.split_at(remaining.len().min(BUDGETED_REFRESH_CHUNK));
+        reindex_files(index, chunk, config.max_recursion_depth);
+        remaining = rest;
+    }
+    note_refreshed(stale.len() - remaining.len());
+    if !remaining.is_empty() {
+        notice::warning(
+            "RefreshBudgetSpent",
+            format!(
+                "Refresh budget spent; answering with {} of {} file(s) still stale",
+                remaining.len(),
+                stale.len()
+            ),
+        )
+        .with("stale", remaining.len())
+        .with("total", stale.len())
+        .emit();
+    }
+    note_freshness(remaining);
+    Ok(())
+}
+
+/// Say that a query reindexed `files` changed files before answering.
+fn note_refreshed(files: usize) {
+    if files > 0 {
+        notice::info(
+            "IndexRefreshed",
+            format!("Refreshed {} stale file(s)", files),
+        )
+        .with("files", files)
+        .emit();
     }
+}
 
-    tracing::info!("Auto-refreshing {} stale file(s)", stale.len());
+/// Files reindexed between checks of the refresh budget
+const BUDGETED_REFRESH_CHUNK: usize = 16;
 
+/// Reindex `files` ("modified", "new" or "deleted", as from
+/// `find_stale_files`) and record their mtimes. The caller holds the
+/// index's write lock.
+fn reindex_files(index: &SqliteIndex, files: &[(PathBuf, &str)], max_depth: usize) {
     // Use batch processor for efficient update
-    let mut batch = rocketindex::batch::BatchProcessor::with_defaults(config.max_recursion_depth);
+    let mut batch = rocketindex::batch::BatchProcessor::with_defaults(max_depth);
 
-    for (path, reason) in &stale {
+    for (path, reason) in files {
         match *reason {
             "deleted" => {
-                batch.add_event(rocketindex::watch::WatchEvent::Deleted(path.clone()));
+                batch.add_event(WatchEvent::Deleted(path.clone()));
             }
             "modified" | "new" => {
-                batch.add_event(rocketindex::watch::WatchEvent::Modified(path.clone()));
+                batch.add_event(WatchEvent::Modified(path.clone()));
             }
             _ => {}
         }
     }
 
     // Flush the batch
-    if let Ok(stats) = batch.flush(index) {
+    if let Ok(stats) = batch.flush_locked(index) {
         tracing::debug!(
             "Refreshed {} files, {} symbols in {:?}",
             stats.files_updated,
@@ -2305,7 +6240,7 @@ fn ensure_index_fresh(index: &SqliteIndex, workspace_root: &Path) -> Result<()>
     }
 
     // Update mtimes for refreshed files
-    for (path, reason) in &stale {
+    for (path, reason) in files {
         if *reason == "deleted" {
             let _ = index.delete_file_mtime(path);
         } else if let Ok(metadata) = std::fs::metadata(path) {
@@ -2318,8 +6253,6 @@ fn ensure_index_fresh(index: &SqliteIndex, workspace_root: &Path) -> Result<()>
             }
         }
     }
-
-    Ok(())
 }
 
 /// Load the CodeIndex from SQLite (for spider compatibility)
@@ -2328,18 +6261,28 @@ fn load_code_index() -> Result<CodeIndex> {
     let cwd = std::env::current_dir()?;
     let db_path = cwd.join(".rocketindex").join(DEFAULT_DB_NAME);
 
-    if !db_path.exists() {
+    let (sqlite_index, workspace_root) = if db_path.exists() {
+        let sqlite_index = open_index(&db_path, &cwd)?;
+
+        // Get workspace root from metadata
+        let workspace_root = sqlite_index
+            .get_metadata("workspace_root")?
+            .map(PathBuf::from)
+            .unwrap_or_else(|| cwd.clone());
+        (sqlite_index, workspace_root)
+    } else if let Some(sqlite_index) = open_workspace_pack(&cwd)? {
+        // Packed paths are relative, so the pack belongs to whichever checkout it's in
+        (sqlite_index, cwd.clone())
+    } else {
         anyhow::bail!("Index not found. Run 'rkt index' first.");
-    }
-
-    let sqlite_index = SqliteIndex::open(&db_path).context("Failed to open SQLite index")?;
+    };
+    apply_project_scope(&sqlite_index)?;
 
-    // Get workspace root from metadata
-    let workspace_root = sqlite_index
-        .get_metadata("workspace_root")?
-        .map(PathBuf::from)
-        .unwrap_or_else(|| cwd.clone());
+    build_code_index(&sqlite_index, workspace_root)
+}
 
+/// Read every symbol, reference and open of `sqlite_index` into a `CodeIndex`.
+fn build_code_index(sqlite_index: &SqliteIndex, workspace_root: PathBuf) -> Result<CodeIndex> {
     let mut code_index = CodeIndex::with_root(workspace_root.clone());
 
     // Load file order if available
@@ -2349,6 +6292,24 @@ fn load_code_index() -> Result<CodeIndex> {
         }
     }
 
+    // .NET packages, to attribute unresolved names to
+    let packages = sqlite_index.packages().unwrap_or_default();
+    if !packages.is_empty() {
+        code_index.set_external_index(index_workspace_assemblies(&workspace_root, &packages));
+    }
+
+    // Sibling repositories, loaded after the workspace so its own
+    // definitions win
+    if let Some(root) = code_index.workspace_root().map(Path::to_path_buf) {
+        let config = Config::load(&root);
+        code_index.set_rails_conventions(config.rails);
+        for attached in federation::open_attached(&config, &root) {
+            attached
+                .load_into(&mut code_index)
+                .with_context(|| format!("Failed to load attached index '{}'", attached.name))?;
+        }
+    }
+
     // Load symbols
     for file in sqlite_index.list_files()? {
         let symbols = sqlite_index.symbols_in_file(&file)?;
@@ -2365,121 +6326,1436 @@ fn load_code_index() -> Result<CodeIndex> {
         for open in opens {
             code_index.add_open(file.clone(), open);
         }
+
+        for alias in sqlite_index.aliases_for_file(&file)? {
+            code_index.add_alias(file.clone(), alias);
+        }
+    }
+
+    Ok(code_index)
+}
+
+/// Get a specific line from a file
+fn get_line_content(file: &Path, line: usize, sandbox: Option<&Path>) -> Option<String> {
+    read_source_lines(file, sandbox)?
+        .into_iter()
+        .nth(line.checked_sub(1)?)
+}
+
+/// Show git blame for a symbol or file location
+fn cmd_blame(target: &str, format: OutputFormat, quiet: bool, _concise: bool) -> Result<u8> {
+    warn_if_no_session(quiet);
+    // Check if target is file:line
+    let (file, line) = if let Some((f, l)) = target.rsplit_once(':') {
+        if let Ok(line_num) = l.parse::<u32>() {
+            (PathBuf::from(f), line_num)
+        } else {
+            // Not a line number, treat as symbol
+            resolve_symbol_location(target)?
+        }
+    } else {
+        // Treat as symbol
+        resolve_symbol_location(target)?
+    };
+
+    let info = git::get_blame(&file, line)?;
+
+    if format == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&info)?);
+    } else if !quiet {
+        println!("Blame for {}:{}", file.display(), line);
+        // Prioritize "why" and "when" over "who"
+        let type_str = info
+            .commit_type
+            .as_ref()
+            .map(|t| format!(" [{}]", t))
+            .unwrap_or_default();
+        println!("  Message: {}{}", info.message, type_str);
+        println!("  When:    {} ({})", info.date_relative, info.date);
+        println!("  Commit:  {}", info.commit);
+        println!("  Author:  {}", info.author);
+    }
+
+    Ok(exit_codes::SUCCESS)
+}
+
+fn resolve_symbol_location(symbol: &str) -> Result<(PathBuf, u32)> {
+    let index = load_sqlite_index()?;
+
+    // Try exact match
+    if let Ok(Some(sym)) = index.find_by_qualified(symbol) {
+        return Ok((sym.location.file.clone(), sym.location.line));
+    }
+
+    // Try partial match
+    if let Ok(matches) = index.search(symbol, 1, None) {
+        if let Some(sym) = matches.first() {
+            return Ok((sym.location.file.clone(), sym.location.line));
+        }
+    }
+
+    Err(symbol_not_found(&index, symbol))
+}
+
+/// Show git history for a symbol
+fn cmd_history(symbol: &str, format: OutputFormat, quiet: bool, _concise: bool) -> Result<u8> {
+    warn_if_no_session(quiet);
+    let index = load_sqlite_index()?;
+
+    let sym = if let Ok(Some(s)) = index.find_by_qualified(symbol) {
+        s
+    } else if let Some(s) = index
+        .search(symbol, 1, None)
+        .ok()
+        .and_then(|matches| matches.into_iter().next())
+    {
+        s
+    } else {
+        return Err(symbol_not_found(&index, symbol));
+    };
+
+    let history = git::get_history(&sym.location.file, sym.location.line, sym.location.end_line)?;
+
+    if format == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&history)?);
+    } else if !quiet {
+        println!(
+            "History for {} ({}:{}):",
+            sym.qualified,
+            sym.location.file.display(),
+            sym.location.line
+        );
+        for info in history {
+            // Truncate commit hash to 7 chars
+            let short_hash = if info.commit.len() > 7 {
+                &info.commit[..7]
+            } else {
+                &info.commit
+            };
+            // Format: why | when | reference (author omitted - often "Claude" now)
+            let type_prefix = info
+                .commit_type
+                .as_ref()
+                .map(|t| format!("[{}] ", t))
+                .unwrap_or_default();
+            println!(
+                "  {} | {} | {}{}",
+                short_hash, info.date_relative, type_prefix, info.message
+            );
+        }
+    }
+
+    Ok(exit_codes::SUCCESS)
+}
+
+/// The repository's CODEOWNERS, if it has one (best effort)
+fn load_codeowners() -> Option<CodeOwners> {
+    let root = git::repo_root().or_else(|_| std::env::current_dir()).ok()?;
+    CodeOwners::load(&root).ok().flatten()
+}
+
+/// Show a symbol's CODEOWNERS rule and the authors of its lines
+fn cmd_owner(symbol: &str, format: OutputFormat, quiet: bool, concise: bool) -> Result<u8> {
+    warn_if_no_session(quiet);
+    let index = load_sqlite_index()?;
+    let sym = match find_definition(&index, symbol) {
+        Ok(sym) => sym,
+        Err(suggestions) => {
+            report_not_found(symbol, &suggestions, format, quiet, concise)?;
+            return Ok(exit_codes::NOT_FOUND);
+        }
+    };
+    let ownership = owners::ownership(load_codeowners().as_ref(), &index, &sym);
+
+    if format == OutputFormat::Json {
+        let output = serde_json::json!({
+            "symbol": sym.qualified,
+            "file": sym.location.file,
+            "line": sym.location.line,
+            "rule": ownership.rule,
+            "contributors": ownership.contributors,
+        });
+        println!(
+            "{}",
+            if concise {
+                serde_json::to_string(&output)?
+            } else {
+                serde_json::to_string_pretty(&output)?
+            }
+        );
+    } else if !quiet {
+        println!(
+            "{} ({}:{})",
+            sym.qualified,
+            sym.location.file.display(),
+            sym.location.line
+        );
+        match &ownership.rule {
+            Some(rule) if rule.owners.is_empty() => {
+                println!("  Owners: none ({} {})", rule.source, rule.pattern)
+            }
+            Some(rule) => println!(
+                "  Owners: {} ({} {})",
+                rule.owners.join(" "),
+                rule.source,
+                rule.pattern
+            ),
+            None => println!("  Owners: no CODEOWNERS rule"),
+        }
+        if ownership.contributors.is_empty() {
+            println!("  Contributors: none committed");
+        } else {
+            println!("  Contributors:");
+            for c in &ownership.contributors {
+                println!(
+                    "    {} <{}>  {} line{}",
+                    c.author,
+                    c.email,
+                    c.lines,
+                    if c.lines == 1 { "" } else { "s" }
+                );
+            }
+        }
+    }
+
+    Ok(exit_codes::SUCCESS)
+}
+
+/// Rename a symbol and its references across the workspace
+fn cmd_rename(
+    symbol: &str,
+    new_name: &str,
+    dry_run: bool,
+    strings: bool,
+    format: OutputFormat,
+    quiet: bool,
+) -> Result<u8> {
+    warn_if_no_session(quiet);
+    // Refresh stale files first so edits line up with the working tree
+    let sqlite_index = load_sqlite_index()?;
+    let index = load_code_index()?;
+
+    let string_refs = if strings {
+        sqlite_index
+            .find_string_references(symbol)
+            .context("Failed to find string references")?
+    } else {
+        Vec::new()
+    };
+    let plan = rename::plan_rename_with(&index, symbol, new_name, &string_refs, |path| {
+        std::fs::read_to_string(path)
+    });
+    let plan = match plan {
+        Ok(plan) => plan,
+        Err(rocketindex::IndexError::SymbolNotFound(_)) => {
+            report_not_found(symbol, &index.suggest(symbol), format, quiet, true)?;
+            return Ok(exit_codes::NOT_FOUND);
+        }
+        Err(e) => return Err(e).context("Failed to plan rename"),
+    };
+
+    let backup_dir = if dry_run || plan.files.is_empty() {
+        None
+    } else {
+        let stamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let dir = std::env::current_dir()?
+            .join(".rocketindex")
+            .join("backups")
+            .join(format!("rename-{}", stamp));
+        plan.apply(&dir).context("Failed to apply rename")?;
+        // Don't rely on mtimes: the files may have been indexed this same second
+        let edited: Vec<(PathBuf, &str)> = plan
+            .files
+            .iter()
+            .map(|f| (f.file.clone(), "modified"))
+            .collect();
+        let config = Config::load(&std::env::current_dir()?);
+        let _lock = sqlite_index.lock_for_writing(DEFAULT_LOCK_TIMEOUT)?;
+        reindex_files(&sqlite_index, &edited, config.max_recursion_depth);
+        Some(dir)
+    };
+
+    // Queries for the old name keep working while callers elsewhere migrate
+    let alias = if backup_dir.is_some() {
+        let new_qualified = plan.new_qualified();
+        sqlite_index
+            .add_alias(&plan.symbol, &new_qualified)
+            .context("Failed to record alias")?;
+        Some(new_qualified)
+    } else {
+        None
+    };
+
+    let cwd = std::env::current_dir()?;
+    if format == OutputFormat::Json {
+        let files: Vec<_> = plan
+            .files
+            .iter()
+            .map(|f| {
+                let mut file = serde_json::json!({
+                    "file": workspace_relative_path(&f.file, &cwd),
+                    "edits": f.edits,
+                });
+                if !f.string_edits.is_empty() {
+                    file["string_edits"] = serde_json::json!(f.string_edits);
+                }
+                file
+            })
+            .collect();
+        let mut output = serde_json::json!({
+            "symbol": plan.symbol,
+            "old_name": plan.old_name,
+            "new_name": plan.new_name,
+            "dry_run": dry_run,
+            "edit_count": plan.edit_count(),
+            "string_edit_count": plan.string_edit_count(),
+            "files": files,
+            "skipped": plan.skipped,
+        });
+        if let Some(dir) = &backup_dir {
+            output["backup"] = serde_json::json!(dir.display().to_string());
+        }
+        if let Some(new) = &alias {
+            output["alias"] = serde_json::json!({ "old": plan.symbol, "new": new });
+        }
+        if dry_run {
+            output["diff"] = serde_json::json!(plan.unified_diff());
+        }
+        println!("{}", serde_json::to_string_pretty(&output)?);
+    } else if dry_run {
+        print!("{}", plan.unified_diff());
+    } else if !quiet {
+        let in_strings = match plan.string_edit_count() {
+            0 => String::new(),
+            n => format!(" ({} in string literals)", n),
+        };
+        println!(
+            "Renamed {} to {}: {} edits in {} files{}",
+            plan.symbol,
+            plan.new_name,
+            plan.edit_count(),
+            plan.files.len(),
+            in_strings
+        );
+        if let Some(dir) = &backup_dir {
+            println!("Backup: {}", dir.display());
+        }
+        if alias.is_some() {
+            println!(
+                "Queries for {} now find the new name ('rkt alias remove {}' to stop)",
+                plan.symbol, plan.symbol
+            );
+        }
+    }
+
+    if !quiet && format != OutputFormat::Json {
+        if dry_run && plan.string_edit_count() > 0 {
+            eprintln!(
+                "Check these {} string-literal edits by hand; the name may not mean {} there:",
+                plan.string_edit_count(),
+                plan.symbol
+            );
+            for file in &plan.files {
+                for edit in &file.string_edits {
+                    eprintln!(
+                        "  {}:{}:{}",
+                        workspace_relative_path(&file.file, &cwd),
+                        edit.line,
+                        edit.column
+                    );
+                }
+            }
+        }
+        for skipped in &plan.skipped {
+            eprintln!(
+                "Skipped {}:{}:{} (file doesn't match the index)",
+                workspace_relative_path(&skipped.file, &cwd),
+                skipped.line,
+                skipped.column
+            );
+        }
+    }
+
+    Ok(exit_codes::SUCCESS)
+}
+
+/// Add, remove or list rename aliases
+fn cmd_alias(action: AliasAction, format: OutputFormat, quiet: bool, concise: bool) -> Result<u8> {
+    let index = load_sqlite_index_with_refresh(false)?;
+    match action {
+        AliasAction::Add { old, new } => {
+            index
+                .add_alias(&old, &new)
+                .context("Failed to record alias")?;
+            if format == OutputFormat::Json {
+                println!(
+                    "{}",
+                    serde_json::json!({ "status": "added", "old": old, "new": new })
+                );
+            } else if !quiet {
+                println!("Queries for {} now find {}", old, new);
+            }
+        }
+        AliasAction::Remove { old } => {
+            let removed = index.remove_alias(&old).context("Failed to remove alias")?;
+            if format == OutputFormat::Json {
+                println!(
+                    "{}",
+                    serde_json::json!({
+                        "status": if removed { "removed" } else { "not_found" },
+                        "old": old,
+                    })
+                );
+            } else if !quiet {
+                if removed {
+                    println!("Removed alias for {}", old);
+                } else {
+                    eprintln!("No alias for {}", old);
+                }
+            }
+            if !removed {
+                return Ok(exit_codes::NOT_FOUND);
+            }
+        }
+        AliasAction::List => {
+            let aliases = index.aliases()?;
+            if format == OutputFormat::Json {
+                println!(
+                    "{}",
+                    if concise {
+                        serde_json::to_string(&aliases)?
+                    } else {
+                        serde_json::to_string_pretty(&aliases)?
+                    }
+                );
+            } else if !quiet {
+                if aliases.is_empty() {
+                    println!("No aliases");
+                }
+                for alias in &aliases {
+                    println!("{:<40} -> {}", alias.old, alias.new);
+                }
+            }
+        }
+    }
+    Ok(exit_codes::SUCCESS)
+}
+
+/// Export or import metadata (see [`rocketindex::meta`])
+fn cmd_meta(action: MetaAction, format: OutputFormat, quiet: bool, concise: bool) -> Result<u8> {
+    let index = load_sqlite_index_with_refresh(false)?;
+    let default_file = || PathBuf::from(rocketindex::meta::META_FILE);
+    match action {
+        MetaAction::Export { output } => {
+            let file = output.unwrap_or_else(default_file);
+            let meta = rocketindex::meta::export(&index)?;
+            rocketindex::meta::write(&file, &meta)?;
+            if format == OutputFormat::Json {
+                let output = serde_json::json!({
+                    "status": "exported",
+                    "file": file.display().to_string(),
+                    "aliases": meta.aliases.len(),
+                    "notes": meta.notes.len(),
+                    "watchlists": meta.watchlists.len(),
+                    "layers": meta.layers.len(),
+                });
+                println!(
+                    "{}",
+                    if concise {
+                        serde_json::to_string(&output)?
+                    } else {
+                        serde_json::to_string_pretty(&output)?
+                    }
+                );
+            } else if !quiet {
+                println!(
+                    "Exported {} alias(es), {} note(s), {} watched symbol(s) and {} layer rule(s) to {}",
+                    meta.aliases.len(),
+                    meta.notes.len(),
+                    meta.watchlists.len(),
+                    meta.layers.len(),
+                    file.display()
+                );
+            }
+        }
+        MetaAction::Import { file } => {
+            let file = file.unwrap_or_else(default_file);
+            let meta = rocketindex::meta::read(&file)?;
+            let report = rocketindex::meta::import(&index, &meta)?;
+            for symbol in &report.unresolved {
+                notice::warning(
+                    "MetaUnresolved",
+                    format!("{} isn't indexed; its entries were kept as-is", symbol),
+                )
+                .with("symbol", symbol)
+                .emit();
+            }
+            if format == OutputFormat::Json {
+                let output = serde_json::json!({
+                    "status": "imported",
+                    "file": file.display().to_string(),
+                    "added": report.added,
+                    "unchanged": report.unchanged,
+                    "unresolved": report.unresolved,
+                });
+                println!(
+                    "{}",
+                    if concise {
+                        serde_json::to_string(&output)?
+                    } else {
+                        serde_json::to_string_pretty(&output)?
+                    }
+                );
+            } else if !quiet {
+                println!(
+                    "Imported {} entries from {} ({} already present)",
+                    report.added,
+                    file.display(),
+                    report.unchanged
+                );
+            }
+        }
+    }
+    Ok(exit_codes::SUCCESS)
+}
+
+/// Print `value` as JSON, compact with --concise
+fn print_json(value: &impl serde::Serialize, concise: bool) -> Result<()> {
+    println!(
+        "{}",
+        if concise {
+            serde_json::to_string(value)?
+        } else {
+            serde_json::to_string_pretty(value)?
+        }
+    );
+    Ok(())
+}
+
+/// Add, remove or list notes on symbols
+fn cmd_note(action: NoteAction, format: OutputFormat, quiet: bool, concise: bool) -> Result<u8> {
+    let index = load_sqlite_index_with_refresh(false)?;
+    match action {
+        NoteAction::Add { symbol, text } => {
+            let renamed = index.follow_alias(&symbol)?;
+            let sym = match find_definition(&index, renamed.as_deref().unwrap_or(&symbol)) {
+                Ok(sym) => sym,
+                Err(suggestions) => {
+                    report_not_found(&symbol, &suggestions, format, quiet, concise)?;
+                    return Ok(exit_codes::NOT_FOUND);
+                }
+            };
+            let id = index
+                .add_note(&sym.guid(), &sym.qualified, &text)
+                .context("Failed to record note")?;
+            if format == OutputFormat::Json {
+                println!(
+                    "{}",
+                    serde_json::json!({
+                        "status": "added",
+                        "id": id,
+                        "symbol": sym.qualified,
+                        "guid": sym.guid(),
+                    })
+                );
+            } else if !quiet {
+                println!("Added note {} on {}", id, sym.qualified);
+            }
+        }
+        NoteAction::Remove { id } => {
+            let removed = index.remove_note(id).context("Failed to remove note")?;
+            if format == OutputFormat::Json {
+                println!(
+                    "{}",
+                    serde_json::json!({
+                        "status": if removed { "removed" } else { "not_found" },
+                        "id": id,
+                    })
+                );
+            } else if !quiet {
+                if removed {
+                    println!("Removed note {}", id);
+                } else {
+                    eprintln!("No note {}", id);
+                }
+            }
+            if !removed {
+                return Ok(exit_codes::NOT_FOUND);
+            }
+        }
+        NoteAction::List { symbol } => {
+            let notes = match symbol {
+                Some(symbol) => {
+                    let renamed = index.follow_alias(&symbol)?;
+                    let sym = match find_definition(&index, renamed.as_deref().unwrap_or(&symbol)) {
+                        Ok(sym) => sym,
+                        Err(suggestions) => {
+                            report_not_found(&symbol, &suggestions, format, quiet, concise)?;
+                            return Ok(exit_codes::NOT_FOUND);
+                        }
+                    };
+                    index.notes_for(&sym.guid(), &sym.qualified)?
+                }
+                None => index.notes()?,
+            };
+            if format == OutputFormat::Json {
+                print_json(&notes, concise)?;
+            } else if !quiet {
+                if notes.is_empty() {
+                    println!("No notes");
+                }
+                for note in &notes {
+                    println!("[{}] {}: {}", note.id, note.qualified, note.text);
+                }
+            }
+        }
+    }
+    Ok(exit_codes::SUCCESS)
+}
+
+/// Add symbols to, remove them from, or show watchlists
+fn cmd_watchlist(
+    action: WatchlistAction,
+    format: OutputFormat,
+    quiet: bool,
+    concise: bool,
+) -> Result<u8> {
+    let index = load_sqlite_index_with_refresh(false)?;
+    match action {
+        WatchlistAction::Add { list, symbol } => {
+            let renamed = index.follow_alias(&symbol)?;
+            let sym = match find_definition(&index, renamed.as_deref().unwrap_or(&symbol)) {
+                Ok(sym) => sym,
+                Err(suggestions) => {
+                    report_not_found(&symbol, &suggestions, format, quiet, concise)?;
+                    return Ok(exit_codes::NOT_FOUND);
+                }
+            };
+            let added = index
+                .watch_symbol(&list, &sym.guid(), &sym.qualified)
+                .context("Failed to update watchlist")?;
+            if format == OutputFormat::Json {
+                println!(
+                    "{}",
+                    serde_json::json!({
+                        "status": if added { "added" } else { "unchanged" },
+                        "list": list,
+                        "symbol": sym.qualified,
+                        "guid": sym.guid(),
+                    })
+                );
+            } else if !quiet {
+                if added {
+                    println!("Watching {} on {}", sym.qualified, list);
+                } else {
+                    println!("{} is already on {}", sym.qualified, list);
+                }
+            }
+        }
+        WatchlistAction::Remove { list, symbol } => {
+            let removed = index
+                .unwatch_symbol(&list, &symbol)
+                .context("Failed to update watchlist")?;
+            if format == OutputFormat::Json {
+                println!(
+                    "{}",
+                    serde_json::json!({
+                        "status": if removed { "removed" } else { "not_found" },
+                        "list": list,
+                        "symbol": symbol,
+                    })
+                );
+            } else if !quiet {
+                if removed {
+                    println!("Removed {} from {}", symbol, list);
+                } else {
+                    eprintln!("{} isn't on {}", symbol, list);
+                }
+            }
+            if !removed {
+                return Ok(exit_codes::NOT_FOUND);
+            }
+        }
+        WatchlistAction::Show { list } => {
+            let watched = index.watchlists(list.as_deref())?;
+            if format == OutputFormat::Json {
+                print_json(&watched, concise)?;
+            } else if !quiet {
+                if watched.is_empty() {
+                    println!("No watched symbols");
+                }
+                for entry in &watched {
+                    println!("{:<20} {}", entry.list, entry.qualified);
+                }
+            }
+        }
+    }
+    Ok(exit_codes::SUCCESS)
+}
+
+/// Add, remove, list or check layering rules
+fn cmd_layers(
+    action: LayersAction,
+    format: OutputFormat,
+    quiet: bool,
+    concise: bool,
+) -> Result<u8> {
+    let index = load_sqlite_index_with_refresh(false)?;
+    match action {
+        LayersAction::Add { from, to } => {
+            let rule = rocketindex::db::LayerRule { from, to };
+            let added = index
+                .add_layer_rule(&rule)
+                .context("Failed to record layer rule")?;
+            if format == OutputFormat::Json {
+                println!(
+                    "{}",
+                    serde_json::json!({
+                        "status": if added { "added" } else { "unchanged" },
+                        "from": rule.from,
+                        "to": rule.to,
+                    })
+                );
+            } else if !quiet {
+                println!("{} must not depend on {}", rule.from, rule.to);
+            }
+        }
+        LayersAction::Remove { from, to } => {
+            let rule = rocketindex::db::LayerRule { from, to };
+            let removed = index
+                .remove_layer_rule(&rule)
+                .context("Failed to remove layer rule")?;
+            if format == OutputFormat::Json {
+                println!(
+                    "{}",
+                    serde_json::json!({
+                        "status": if removed { "removed" } else { "not_found" },
+                        "from": rule.from,
+                        "to": rule.to,
+                    })
+                );
+            } else if !quiet {
+                if removed {
+                    println!("Removed rule {} -> {}", rule.from, rule.to);
+                } else {
+                    eprintln!("No rule {} -> {}", rule.from, rule.to);
+                }
+            }
+            if !removed {
+                return Ok(exit_codes::NOT_FOUND);
+            }
+        }
+        LayersAction::List => {
+            let rules = index.layer_rules()?;
+            if format == OutputFormat::Json {
+                print_json(&rules, concise)?;
+            } else if !quiet {
+                if rules.is_empty() {
+                    println!("No layer rules");
+                }
+                for rule in &rules {
+                    println!("{:<40} must not depend on {}", rule.from, rule.to);
+                }
+            }
+        }
+        LayersAction::Check => {
+            let root = match index.get_metadata("workspace_root")? {
+                Some(root) => PathBuf::from(root),
+                None => std::env::current_dir()?,
+            };
+            let violations = rocketindex::layers::check(&index, &root)?;
+            if format == OutputFormat::Json {
+                print_json(&violations, concise)?;
+            } else if !quiet {
+                if violations.is_empty() {
+                    println!("No layering violations");
+                }
+                for violation in &violations {
+                    println!(
+                        "{} -> {} (rule: {} must not depend on {})",
+                        violation.from.display(),
+                        violation.to.display(),
+                        violation.rule.from,
+                        violation.rule.to
+                    );
+                }
+            }
+            if !violations.is_empty() {
+                return Ok(exit_codes::NOT_FOUND);
+            }
+        }
+    }
+    Ok(exit_codes::SUCCESS)
+}
+
+/// List symbols in a file, or (without a file) those enclosing uncommitted changes
+fn cmd_symbols_in(
+    file: Option<&Path>,
+    staged: bool,
+    format: OutputFormat,
+    quiet: bool,
+    concise: bool,
+) -> Result<u8> {
+    warn_if_no_session(quiet);
+    let index = load_sqlite_index()?;
+    let cwd = std::env::current_dir()?;
+
+    // (symbol, changed lines that mapped to it)
+    let results: Vec<(rocketindex::Symbol, Vec<u32>)> = if let Some(file) = file {
+        let mut symbols = indexed_symbols_in(&index, &cwd.join(file))?;
+        symbols.sort_by_key(|s| (s.location.line, s.location.column));
+        symbols.into_iter().map(|s| (s, Vec::new())).collect()
+    } else {
+        changed_symbols(&index, staged)?
+    };
+
+    if format == OutputFormat::Json {
+        let symbols: Vec<_> = results
+            .iter()
+            .map(|(s, lines)| {
+                let file = s
+                    .location
+                    .file
+                    .strip_prefix(&cwd)
+                    .unwrap_or(&s.location.file)
+                    .display()
+                    .to_string();
+                if concise {
+                    serde_json::json!({
+                        "qualified": s.qualified,
+                        "file": file,
+                        "line": s.location.line,
+                    })
+                } else {
+                    let mut value = serde_json::json!({
+                        "name": s.name,
+                        "qualified": s.qualified,
+                        "kind": format!("{}", s.kind),
+                        "file": file,
+                        "line": s.location.line,
+                        "column": s.location.column,
+                    });
+                    if !lines.is_empty() {
+                        value["changed_lines"] = serde_json::json!(lines);
+                    }
+                    value
+                }
+            })
+            .collect();
+        println!(
+            "{}",
+            if concise {
+                serde_json::to_string(&symbols)?
+            } else {
+                serde_json::to_string_pretty(&symbols)?
+            }
+        );
+    } else if !quiet {
+        for (sym, _) in &results {
+            println!(
+                "{:<40} {}:{}:{:<8} {}",
+                sym.qualified,
+                sym.location
+                    .file
+                    .strip_prefix(&cwd)
+                    .unwrap_or(&sym.location.file)
+                    .display(),
+                sym.location.line,
+                sym.location.column,
+                sym.kind
+            );
+        }
+    }
+
+    if results.is_empty() {
+        Ok(exit_codes::NOT_FOUND)
+    } else {
+        Ok(exit_codes::SUCCESS)
+    }
+}
+
+/// List tests affected by uncommitted changes, or a runner filter selecting them
+#[allow(clippy::too_many_arguments)]
+fn cmd_affected_tests(
+    depth: usize,
+    staged: bool,
+    runner: Option<TestRunner>,
+    min_confidence: Confidence,
+    format: OutputFormat,
+    quiet: bool,
+    concise: bool,
+) -> Result<u8> {
+    warn_if_no_session(quiet);
+    // Loading the SQLite index first refreshes stale files, so the changed
+    // spans and the reference graph both reflect the working tree
+    let sqlite_index = load_sqlite_index()?;
+    let changed = changed_symbols(&sqlite_index, staged)?;
+    let index = load_code_index()?;
+
+    let changed_names: Vec<&str> = changed.iter().map(|(s, _)| s.qualified.as_str()).collect();
+    let tests = affected_tests(&index, &changed_names, depth, min_confidence);
+
+    if format == OutputFormat::Json {
+        let test_list: Vec<_> = tests
+            .iter()
+            .map(|t| {
+                if concise {
+                    serde_json::json!({
+                        "qualified": t.symbol.as_ref().map(|s| &s.qualified),
+                        "file": t.file.display().to_string(),
+                        "line": t.line,
+                    })
+                } else {
+                    serde_json::json!({
+                        "name": t.symbol.as_ref().map(|s| &s.name),
+                        "qualified": t.symbol.as_ref().map(|s| &s.qualified),
+                        "file": t.file.display().to_string(),
+                        "line": t.line,
+                        "reaches": t.reaches,
+                        "depth": t.depth,
+                    })
+                }
+            })
+            .collect();
+        let mut output = serde_json::json!({
+            "changed": changed_names,
+            "tests": test_list,
+        });
+        if let Some(runner) = runner {
+            output["runner"] = serde_json::json!(runner.as_str());
+            output["filter"] = serde_json::json!(runner.filter(&tests));
+        }
+        println!(
+            "{}",
+            if concise {
+                serde_json::to_string(&output)?
+            } else {
+                serde_json::to_string_pretty(&output)?
+            }
+        );
+    } else if !quiet {
+        if let Some(runner) = runner {
+            // Bare filter so it can be spliced into the runner's command line
+            println!("{}", runner.filter(&tests));
+        } else if tests.is_empty() {
+            println!(
+                "No affected tests ({} changed symbols)",
+                changed_names.len()
+            );
+        } else {
+            for test in &tests {
+                let name = test
+                    .symbol
+                    .as_ref()
+                    .map_or("(file)", |s| s.qualified.as_str());
+                println!(
+                    "{:<40} {}:{}  reaches {} (depth {})",
+                    name,
+                    test.file.display(),
+                    test.line,
+                    test.reaches,
+                    test.depth
+                );
+            }
+        }
+    }
+
+    if tests.is_empty() {
+        Ok(exit_codes::NOT_FOUND)
+    } else {
+        Ok(exit_codes::SUCCESS)
+    }
+}
+
+/// List the tests that reach `symbol` through its callers
+fn cmd_tests_for(
+    symbol: &str,
+    depth: usize,
+    min_confidence: Confidence,
+    format: OutputFormat,
+    quiet: bool,
+    concise: bool,
+) -> Result<u8> {
+    use rocketindex::test_map::tests_for;
+
+    warn_if_no_session(quiet);
+    // Refresh stale files first so the reference graph matches the working tree
+    load_sqlite_index()?;
+    let index = load_code_index()?;
+
+    if index.get(symbol).is_none() {
+        report_not_found(symbol, &index.suggest(symbol), format, quiet, true)?;
+        return Ok(exit_codes::NOT_FOUND);
+    }
+
+    let tests = tests_for(&index, symbol, depth, min_confidence);
+
+    if format == OutputFormat::Json {
+        let test_list: Vec<_> = tests
+            .iter()
+            .map(|t| {
+                if concise {
+                    serde_json::json!({
+                        "qualified": t.symbol.as_ref().map(|s| &s.qualified),
+                        "file": t.file.display().to_string(),
+                        "line": t.line,
+                    })
+                } else {
+                    serde_json::json!({
+                        "name": t.symbol.as_ref().map(|s| &s.name),
+                        "qualified": t.symbol.as_ref().map(|s| &s.qualified),
+                        "file": t.file.display().to_string(),
+                        "line": t.line,
+                        "depth": t.depth,
+                    })
+                }
+            })
+            .collect();
+        let output = serde_json::json!({
+            "symbol": symbol,
+            "tests": test_list,
+        });
+        println!(
+            "{}",
+            if concise {
+                serde_json::to_string(&output)?
+            } else {
+                serde_json::to_string_pretty(&output)?
+            }
+        );
+    } else if !quiet {
+        if tests.is_empty() {
+            println!("No tests reach {}", symbol);
+        } else {
+            for test in &tests {
+                let name = test
+                    .symbol
+                    .as_ref()
+                    .map_or("(file)", |s| s.qualified.as_str());
+                println!(
+                    "{:<40} {}:{}  (depth {})",
+                    name,
+                    test.file.display(),
+                    test.line,
+                    test.depth
+                );
+            }
+        }
+    }
+
+    if tests.is_empty() {
+        Ok(exit_codes::NOT_FOUND)
+    } else {
+        Ok(exit_codes::SUCCESS)
+    }
+}
+
+/// Report the blast radius of the working tree's changes since `base`
+fn cmd_impact(
+    base: Option<&str>,
+    depth: usize,
+    min_confidence: Confidence,
+    format: OutputFormat,
+    quiet: bool,
+    concise: bool,
+) -> Result<u8> {
+    use rocketindex::impact::{analyze_impact, changed_symbols, ImpactedSymbol};
+
+    warn_if_no_session(quiet);
+    // Refresh stale files first so spans and references match the working tree
+    load_sqlite_index()?;
+    let index = load_code_index()?;
+
+    // Use the index's spelling of the root so diff paths line up with indexed ones
+    let repo_root = git::repo_root()?;
+    let root = match index.workspace_root() {
+        Some(ws) if ws.canonicalize().ok() == repo_root.canonicalize().ok() => ws.to_path_buf(),
+        _ => repo_root,
+    };
+    let changes = git::changed_ranges(base)?;
+    let changed = changed_symbols(&index, &root, &changes);
+    let found = !changed.is_empty();
+    let impact = analyze_impact(&index, changed, depth, min_confidence);
+
+    if format == OutputFormat::Json {
+        let location = |s: &rocketindex::Symbol| {
+            serde_json::json!({
+                "qualified": s.qualified,
+                "file": s.location.file.display().to_string(),
+                "line": s.location.line,
+            })
+        };
+        let impacted = |list: &[ImpactedSymbol]| -> Vec<serde_json::Value> {
+            list.iter()
+                .map(|i| {
+                    let mut value = location(&i.symbol);
+                    if !concise {
+                        value["kind"] = serde_json::json!(i.symbol.kind.to_string());
+                        value["reaches"] = serde_json::json!(i.reaches);
+                        value["depth"] = serde_json::json!(i.depth);
+                    }
+                    value
+                })
+                .collect()
+        };
+        let changed: Vec<_> = impact
+            .changed
+            .iter()
+            .map(|c| {
+                let mut value = location(&c.symbol);
+                if !concise {
+                    value["kind"] = serde_json::json!(c.symbol.kind.to_string());
+                    value["changed_lines"] = serde_json::json!(c.lines);
+                }
+                value
+            })
+            .collect();
+        let tests: Vec<_> = impact
+            .tests
+            .iter()
+            .map(|t| {
+                let mut value = serde_json::json!({
+                    "qualified": t.symbol.as_ref().map(|s| &s.qualified),
+                    "file": t.file.display().to_string(),
+                    "line": t.line,
+                });
+                if !concise {
+                    value["reaches"] = serde_json::json!(t.reaches);
+                    value["depth"] = serde_json::json!(t.depth);
+                }
+                value
+            })
+            .collect();
+
+        let gateways: Vec<_> = impact
+            .gateways
+            .iter()
+            .map(|g| {
+                let mut value = location(&g.symbol);
+                value["dominates"] = serde_json::json!(g.dominates);
+                value
+            })
+            .collect();
+
+        let output = serde_json::json!({
+            "base": base,
+            "changed": changed,
+            "gateways": gateways,
+            "callers": impacted(&impact.callers),
+            "entry_points": impacted(&impact.entry_points),
+            "tests": tests,
+        });
+        println!(
+            "{}",
+            if concise {
+                serde_json::to_string(&output)?
+            } else {
+                serde_json::to_string_pretty(&output)?
+            }
+        );
+    } else if !quiet {
+        if !found {
+            println!(
+                "No changed symbols{}",
+                base.map(|b| format!(" since {}", b)).unwrap_or_default()
+            );
+            return Ok(exit_codes::NOT_FOUND);
+        }
+
+        println!("Changed ({}):", impact.changed.len());
+        for c in &impact.changed {
+            println!(
+                "  {:<40} {}:{}",
+                c.symbol.qualified,
+                c.symbol.location.file.display(),
+                c.symbol.location.line
+            );
+        }
+        if !impact.gateways.is_empty() {
+            let behind: usize = impact.gateways.iter().map(|g| g.dominates + 1).sum();
+            println!(
+                "\nGateways ({}): {} of {} callers are reached only through these",
+                impact.gateways.len(),
+                behind,
+                impact.callers.len()
+            );
+            for g in &impact.gateways {
+                println!(
+                    "  {:<40} {}:{}  dominates {}",
+                    g.symbol.qualified,
+                    g.symbol.location.file.display(),
+                    g.symbol.location.line,
+                    g.dominates
+                );
+            }
+        }
+        for (title, list) in [
+            ("Callers", &impact.callers),
+            ("Entry points", &impact.entry_points),
+        ] {
+            println!("\n{} ({}):", title, list.len());
+            for i in list {
+                println!(
+                    "  {:<40} {}:{}  reaches {} (depth {})",
+                    i.symbol.qualified,
+                    i.symbol.location.file.display(),
+                    i.symbol.location.line,
+                    i.reaches,
+                    i.depth
+                );
+            }
+        }
+        println!("\nTests ({}):", impact.tests.len());
+        for test in &impact.tests {
+            let name = test
+                .symbol
+                .as_ref()
+                .map_or("(file)", |s| s.qualified.as_str());
+            println!(
+                "  {:<40} {}:{}  reaches {} (depth {})",
+                name,
+                test.file.display(),
+                test.line,
+                test.reaches,
+                test.depth
+            );
+        }
     }
 
-    Ok(code_index)
+    if found {
+        Ok(exit_codes::SUCCESS)
+    } else {
+        Ok(exit_codes::NOT_FOUND)
+    }
 }
 
-/// Get a specific line from a file
-fn get_line_content(file: &PathBuf, line: usize) -> Option<String> {
-    let content = std::fs::read_to_string(file).ok()?;
-    content.lines().nth(line - 1).map(|s| s.to_string())
+/// Index `rev` in memory from git, with the files `rkt index` would pick.
+fn snapshot_revision(
+    rev: &str,
+    root: &Path,
+    config: &Config,
+) -> Result<rocketindex::compare::Snapshot> {
+    let excluded = config.excluded_dirs();
+    let files = git::files_at_rev(rev, |path| {
+        rocketindex::watch::is_supported_file(path)
+            && !path.components().any(|c| {
+                let c = c.as_os_str().to_string_lossy();
+                c.starts_with('.') || excluded.contains(&c.as_ref())
+            })
+    })?;
+    Ok(rocketindex::compare::Snapshot::new(
+        root,
+        files,
+        config.max_recursion_depth,
+    ))
 }
 
-/// Show git blame for a symbol or file location
-fn cmd_blame(target: &str, format: OutputFormat, quiet: bool, _concise: bool) -> Result<u8> {
-    warn_if_no_session(quiet);
-    // Check if target is file:line
-    let (file, line) = if let Some((f, l)) = target.rsplit_once(':') {
-        if let Ok(line_num) = l.parse::<u32>() {
-            (PathBuf::from(f), line_num)
-        } else {
-            // Not a line number, treat as symbol
-            resolve_symbol_location(target)?
-        }
-    } else {
-        // Treat as symbol
-        resolve_symbol_location(target)?
-    };
+fn cmd_compare_branches(
+    base: &str,
+    head: &str,
+    depth: usize,
+    min_confidence: Confidence,
+    format: OutputFormat,
+    quiet: bool,
+    concise: bool,
+) -> Result<u8> {
+    use rocketindex::compare::compare;
 
-    let info = git::get_blame(&file, line)?;
+    let root = git::repo_root()?;
+    let config = Config::load(&root);
+    let before = snapshot_revision(base, &root, &config)?;
+    let after = snapshot_revision(head, &root, &config)?;
+    let comparison = compare(&before, &after, depth, min_confidence);
+    let found = !comparison.is_empty();
 
     if format == OutputFormat::Json {
-        println!("{}", serde_json::to_string_pretty(&info)?);
-    } else if !quiet {
-        println!("Blame for {}:{}", file.display(), line);
-        // Prioritize "why" and "when" over "who"
-        let type_str = info
-            .commit_type
-            .as_ref()
-            .map(|t| format!(" [{}]", t))
-            .unwrap_or_default();
-        println!("  Message: {}{}", info.message, type_str);
-        println!("  When:    {} ({})", info.date_relative, info.date);
-        println!("  Commit:  {}", info.commit);
-        println!("  Author:  {}", info.author);
-    }
-
-    Ok(exit_codes::SUCCESS)
-}
-
-fn resolve_symbol_location(symbol: &str) -> Result<(PathBuf, u32)> {
-    let index = load_sqlite_index()?;
+        let location = |s: &rocketindex::Symbol| {
+            let mut value = serde_json::json!({
+                "qualified": s.qualified,
+                "file": s.location.file.display().to_string(),
+                "line": s.location.line,
+            });
+            if !concise {
+                value["kind"] = serde_json::json!(s.kind.to_string());
+            }
+            value
+        };
+        let changed: Vec<_> = comparison
+            .changed
+            .iter()
+            .map(|c| {
+                let mut value = location(&c.after);
+                value["changes"] = serde_json::json!(c.changes);
+                if !concise && c.before.signature != c.after.signature {
+                    value["signature_before"] = serde_json::json!(c.before.signature);
+                    value["signature_after"] = serde_json::json!(c.after.signature);
+                }
+                value
+            })
+            .collect();
+        let callers: Vec<_> = comparison
+            .callers
+            .iter()
+            .map(|i| {
+                let mut value = location(&i.symbol);
+                value["reaches"] = serde_json::json!(i.reaches);
+                if !concise {
+                    value["depth"] = serde_json::json!(i.depth);
+                }
+                value
+            })
+            .collect();
 
-    // Try exact match
-    if let Ok(Some(sym)) = index.find_by_qualified(symbol) {
-        return Ok((sym.location.file.clone(), sym.location.line));
-    }
+        let output = serde_json::json!({
+            "base": base,
+            "head": head,
+            "summary": {
+                "added": comparison.added.len(),
+                "removed": comparison.removed.len(),
+                "changed": comparison.changed.len(),
+                "callers": comparison.callers.len(),
+            },
+            "added": comparison.added.iter().map(location).collect::<Vec<_>>(),
+            "removed": comparison.removed.iter().map(location).collect::<Vec<_>>(),
+            "changed": changed,
+            "callers": callers,
+        });
+        println!(
+            "{}",
+            if concise {
+                serde_json::to_string(&output)?
+            } else {
+                serde_json::to_string_pretty(&output)?
+            }
+        );
+    } else if !quiet {
+        if !found {
+            println!("No symbol differences between {} and {}", base, head);
+            return Ok(exit_codes::NOT_FOUND);
+        }
 
-    // Try partial match
-    if let Ok(matches) = index.search(symbol, 1, None) {
-        if let Some(sym) = matches.first() {
-            return Ok((sym.location.file.clone(), sym.location.line));
+        println!(
+            "{}..{}: {} added, {} removed, {} changed, {} callers affected",
+            base,
+            head,
+            comparison.added.len(),
+            comparison.removed.len(),
+            comparison.changed.len(),
+            comparison.callers.len()
+        );
+        for (title, marker, list) in [
+            ("Added", '+', &comparison.added),
+            ("Removed", '-', &comparison.removed),
+        ] {
+            if list.is_empty() {
+                continue;
+            }
+            println!("\n{} ({}):", title, list.len());
+            for s in list {
+                println!(
+                    "  {} {:<40} {}:{}",
+                    marker,
+                    s.qualified,
+                    s.location.file.display(),
+                    s.location.line
+                );
+            }
+        }
+        if !comparison.changed.is_empty() {
+            println!("\nChanged ({}):", comparison.changed.len());
+            for c in &comparison.changed {
+                let changes: Vec<String> = c
+                    .changes
+                    .iter()
+                    .map(|change| format!("{:?}", change).to_lowercase())
+                    .collect();
+                println!(
+                    "  ~ {:<40} {}:{}  ({})",
+                    c.after.qualified,
+                    c.after.location.file.display(),
+                    c.after.location.line,
+                    changes.join(", ")
+                );
+            }
+        }
+        if !comparison.callers.is_empty() {
+            println!("\nCallers affected ({}):", comparison.callers.len());
+            for i in &comparison.callers {
+                println!(
+                    "    {:<40} {}:{}  reaches {} (depth {})",
+                    i.symbol.qualified,
+                    i.symbol.location.file.display(),
+                    i.symbol.location.line,
+                    i.reaches,
+                    i.depth
+                );
+            }
         }
     }
 
-    anyhow::bail!("Symbol not found: {}", symbol)
+    if found {
+        Ok(exit_codes::SUCCESS)
+    } else {
+        Ok(exit_codes::NOT_FOUND)
+    }
 }
 
-/// Show git history for a symbol
-fn cmd_history(symbol: &str, format: OutputFormat, quiet: bool, _concise: bool) -> Result<u8> {
-    warn_if_no_session(quiet);
-    let index = load_sqlite_index()?;
-
-    let sym = if let Ok(Some(s)) = index.find_by_qualified(symbol) {
-        s
-    } else if let Ok(matches) = index.search(symbol, 1, None) {
-        if let Some(s) = matches.first() {
-            s.clone()
-        } else {
-            anyhow::bail!("Symbol not found: {}", symbol);
+/// Map each uncommitted change to the symbol defined closest above it.
+///
+/// Returns each touched symbol with the changed lines that mapped to it.
+fn changed_symbols(
+    index: &SqliteIndex,
+    staged: bool,
+) -> Result<Vec<(rocketindex::Symbol, Vec<u32>)>> {
+    let mut results = Vec::new();
+    let repo_root = git::repo_root()?;
+    for changed in git::changed_files(staged)? {
+        let mut symbols = indexed_symbols_in(index, &repo_root.join(&changed.path))?;
+        if symbols.is_empty() {
+            continue;
         }
-    } else {
-        anyhow::bail!("Symbol not found: {}", symbol);
-    };
+        symbols.sort_by_key(|s| (s.location.line, s.location.column));
 
-    let history = git::get_history(&sym.location.file, sym.location.line, sym.location.end_line)?;
+        let mut touched: Vec<(usize, u32)> = Vec::new();
+        for (start, end) in &changed.ranges {
+            // The symbol defined closest above the hunk encloses its first line...
+            if let Some(i) = symbols.iter().rposition(|s| s.location.line <= *start) {
+                touched.push((i, *start));
+            }
+            // ...and any definition inside the hunk was itself edited
+            for (i, s) in symbols.iter().enumerate() {
+                if s.location.line > *start && s.location.line <= *end {
+                    touched.push((i, s.location.line));
+                }
+            }
+        }
 
-    if format == OutputFormat::Json {
-        println!("{}", serde_json::to_string_pretty(&history)?);
-    } else if !quiet {
-        println!(
-            "History for {} ({}:{}):",
-            sym.qualified,
-            sym.location.file.display(),
-            sym.location.line
-        );
-        for info in history {
-            // Truncate commit hash to 7 chars
-            let short_hash = if info.commit.len() > 7 {
-                &info.commit[..7]
-            } else {
-                &info.commit
-            };
-            // Format: why | when | reference (author omitted - often "Claude" now)
-            let type_prefix = info
-                .commit_type
-                .as_ref()
-                .map(|t| format!("[{}] ", t))
-                .unwrap_or_default();
-            println!(
-                "  {} | {} | {}{}",
-                short_hash, info.date_relative, type_prefix, info.message
-            );
+        let mut lines_by_symbol: Vec<Vec<u32>> = vec![Vec::new(); symbols.len()];
+        for (i, line) in touched {
+            lines_by_symbol[i].push(line);
+        }
+        for (symbol, lines) in symbols.into_iter().zip(lines_by_symbol) {
+            if !lines.is_empty() {
+                results.push((symbol, lines));
+            }
         }
     }
+    Ok(results)
+}
 
-    Ok(exit_codes::SUCCESS)
+/// Symbols defined in `path`, trying the canonical path if the given one isn't indexed.
+fn indexed_symbols_in(index: &SqliteIndex, path: &Path) -> Result<Vec<rocketindex::Symbol>> {
+    let symbols = index.symbols_in_file(path)?;
+    if !symbols.is_empty() {
+        return Ok(symbols);
+    }
+    match path.canonicalize() {
+        Ok(canonical) if canonical != path => Ok(index.symbols_in_file(&canonical)?),
+        _ => Ok(symbols),
+    }
 }
 
 /// Check RocketIndex health and configuration
@@ -2502,12 +7778,38 @@ fn cmd_doctor(format: OutputFormat, quiet: bool) -> Result<u8> {
 
     // Check 2: Symbol and file counts (if index exists)
     let (symbol_count, file_count) = if index_exists {
-        if let Ok(index) = SqliteIndex::open(&db_path) {
-            let symbols = index.count_symbols().unwrap_or(0);
-            let files = index.list_files().map(|f| f.len()).unwrap_or(0);
-            (symbols, files)
-        } else {
-            (0, 0)
+        match SqliteIndex::open(&db_path) {
+            Ok(index) => {
+                let symbols = index.count_symbols().unwrap_or(0);
+                let files = index.list_files().map(|f| f.len()).unwrap_or(0);
+                if let Ok(Some(built_with)) = index.get_metadata("tool_version") {
+                    if built_with != env!("RKT_VERSION") {
+                        checks.push((
+                            "Index version",
+                            false,
+                            format!(
+                                "Built by rkt {}, this is {}",
+                                built_with,
+                                env!("RKT_VERSION")
+                            ),
+                        ));
+                        suggestions.push(
+                            "Run 'rkt index --rebuild' to reindex with this version".to_string(),
+                        );
+                    }
+                }
+                (symbols, files)
+            }
+            Err(e) => {
+                if e.needs_rebuild() {
+                    checks.push(("Schema", false, e.to_string()));
+                    suggestions.push(
+                        "Run 'rkt index --rebuild' (other commands rebuild it on first use)"
+                            .to_string(),
+                    );
+                }
+                (0, 0)
+            }
         }
     } else {
         (0, 0)
@@ -2570,8 +7872,8 @@ fn cmd_doctor(format: OutputFormat, quiet: bool) -> Result<u8> {
     if index_exists {
         if let Ok(index) = SqliteIndex::open(&db_path) {
             if let Ok(files) = index.list_files() {
-                let mut languages: std::collections::HashSet<&str> =
-                    std::collections::HashSet::new();
+                let mut languages: std::collections::BTreeSet<&str> =
+                    std::collections::BTreeSet::new();
                 for file in &files {
                     if let Some(ext) = file.extension().and_then(|e| e.to_str()) {
                         match ext {
@@ -2596,51 +7898,278 @@ fn cmd_doctor(format: OutputFormat, quiet: bool) -> Result<u8> {
         }
     }
 
-    // Output results
-    if format == OutputFormat::Json {
-        let check_list: Vec<_> = checks
-            .iter()
-            .map(|(name, ok, msg)| {
-                serde_json::json!({
-                    "check": name,
-                    "ok": ok,
-                    "message": msg
-                })
-            })
-            .collect();
+    // Output results
+    if format == OutputFormat::Json {
+        let check_list: Vec<_> = checks
+            .iter()
+            .map(|(name, ok, msg)| {
+                serde_json::json!({
+                    "check": name,
+                    "ok": ok,
+                    "message": msg
+                })
+            })
+            .collect();
+
+        let output = serde_json::json!({
+            "checks": check_list,
+            "suggestions": suggestions,
+            "healthy": checks.iter().all(|(_, ok, _)| *ok)
+        });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+    } else if !quiet {
+        println!("RocketIndex Health Check\n");
+
+        for (name, ok, msg) in &checks {
+            let status = if *ok { "✓" } else { "✗" };
+            println!("  {} {}: {}", status, name, msg);
+        }
+
+        if !suggestions.is_empty() {
+            println!("\nSuggestions:");
+            for suggestion in &suggestions {
+                println!("  • {}", suggestion);
+            }
+        }
+
+        println!();
+        if checks.iter().all(|(_, ok, _)| *ok) {
+            println!("All checks passed!");
+        } else {
+            println!("Some checks failed. See suggestions above.");
+        }
+    }
+
+    Ok(exit_codes::SUCCESS)
+}
+
+/// Show the index's metadata and provenance
+fn cmd_info(format: OutputFormat, quiet: bool) -> Result<u8> {
+    let db_path = std::env::current_dir()?
+        .join(".rocketindex")
+        .join(DEFAULT_DB_NAME);
+    if !db_path.exists() {
+        anyhow::bail!("Index not found. Run 'rkt index' first.");
+    }
+    let index = SqliteIndex::open(&db_path).context("Failed to open SQLite index")?;
+
+    let tool_version = index.get_metadata("tool_version")?;
+    let created_at: Option<u64> = index
+        .get_metadata("created_at")?
+        .and_then(|value| value.parse().ok());
+    let workspace_root = index.get_metadata("workspace_root")?;
+    let git_commit = index.get_metadata(GIT_HEAD_KEY)?;
+    let languages = index.language_counts()?;
+    // The write-ahead log holds recent writes until it's checkpointed
+    let size_bytes: u64 = ["", "-wal", "-shm"]
+        .iter()
+        .filter_map(|suffix| {
+            let mut path = db_path.clone().into_os_string();
+            path.push(suffix);
+            std::fs::metadata(path).ok()
+        })
+        .map(|metadata| metadata.len())
+        .sum();
+    let version_mismatch = tool_version.as_deref() != Some(env!("RKT_VERSION"));
+
+    if format == OutputFormat::Json {
+        let output = serde_json::json!({
+            "path": db_path,
+            "schema_version": index.get_schema_version()?,
+            "created_at": created_at,
+            "tool_version": tool_version,
+            "current_version": env!("RKT_VERSION"),
+            "version_mismatch": version_mismatch,
+            "workspace_root": workspace_root,
+            "git_commit": git_commit,
+            "languages": languages,
+            "size_bytes": size_bytes,
+        });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+    } else if !quiet {
+        let unknown = || "unknown".to_string();
+        println!("Index:          {}", db_path.display());
+        println!("Schema version: {}", index.get_schema_version()?);
+        println!(
+            "Created:        {}",
+            created_at.map_or_else(unknown, format_age)
+        );
+        println!(
+            "Tool version:   {}{}",
+            tool_version.clone().unwrap_or_else(unknown),
+            if version_mismatch {
+                format!(" (this rkt is {})", env!("RKT_VERSION"))
+            } else {
+                String::new()
+            }
+        );
+        println!("Workspace root: {}", workspace_root.unwrap_or_else(unknown));
+        println!("Git commit:     {}", git_commit.unwrap_or_else(unknown));
+        println!("Size on disk:   {} bytes", size_bytes);
+        println!("Languages:");
+        for count in &languages {
+            println!(
+                "  {:<12} {} files, {} symbols",
+                count.language, count.files, count.symbols
+            );
+        }
+    }
+
+    Ok(exit_codes::SUCCESS)
+}
+
+/// How long ago a Unix timestamp was, e.g. "3 days ago".
+fn format_age(timestamp: u64) -> String {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let seconds = now.saturating_sub(timestamp);
+    let (amount, unit) = match seconds {
+        0..=59 => return "just now".to_string(),
+        60..=3599 => (seconds / 60, "minute"),
+        3600..=86_399 => (seconds / 3600, "hour"),
+        _ => (seconds / 86_400, "day"),
+    };
+    format!(
+        "{} {}{} ago",
+        amount,
+        unit,
+        if amount == 1 { "" } else { "s" }
+    )
+}
+
+/// Name of an output format as accepted by `--format`
+fn format_name(format: OutputFormat) -> String {
+    use clap::ValueEnum;
+    format
+        .to_possible_value()
+        .map(|v| v.get_name().to_string())
+        .unwrap_or_default()
+}
+
+/// Export the index to a format consumed by other tools
+fn cmd_export(output: Option<&Path>, format: OutputFormat, quiet: bool) -> Result<u8> {
+    use rocketindex::export::{ScipIndex, Tags};
+
+    let (default_output, bytes, summary) = match format {
+        OutputFormat::Scip => {
+            let index = load_code_index()?;
+            let scip = ScipIndex::from_index(&index, env!("RKT_VERSION"));
+            let summary = format!(
+                "{} documents, {} symbols, {} occurrences",
+                scip.documents.len(),
+                scip.symbol_count(),
+                scip.occurrence_count()
+            );
+            ("index.scip", scip.encode(), summary)
+        }
+        OutputFormat::Ctags | OutputFormat::Etags => {
+            let index = load_sqlite_index()?;
+            // Editors resolve tag paths relative to the tags file itself
+            let tags_dir = match output.and_then(Path::parent) {
+                Some(dir) if !dir.as_os_str().is_empty() => dir
+                    .canonicalize()
+                    .with_context(|| format!("Failed to resolve {}", dir.display()))?,
+                _ => std::env::current_dir()?,
+            };
+            let tags = Tags::from_sqlite(&index, &tags_dir)?;
+            let summary = format!("{} tags", tags.entries.len());
+            if format == OutputFormat::Ctags {
+                let ctags = tags.to_ctags(env!("RKT_VERSION"));
+                ("tags", ctags.into_bytes(), summary)
+            } else {
+                ("TAGS", tags.to_etags(&tags_dir).into_bytes(), summary)
+            }
+        }
+        _ => anyhow::bail!(
+            "Unsupported export format '{}'. Use --format scip, ctags or etags",
+            format_name(format)
+        ),
+    };
+
+    let output = output.unwrap_or(Path::new(default_output));
+    std::fs::write(output, bytes)
+        .with_context(|| format!("Failed to write {}", output.display()))?;
+
+    if !quiet {
+        println!("Exported {} to {}", summary, output.display());
+    }
+
+    Ok(exit_codes::SUCCESS)
+}
+
+fn cmd_pack_index(
+    output: Option<&Path>,
+    force: bool,
+    format: OutputFormat,
+    quiet: bool,
+) -> Result<u8> {
+    let index = load_sqlite_index()?;
+    let cwd = std::env::current_dir()?;
+    let out_dir = output.map_or_else(|| cwd.join(".rocketindex").join("pack"), Path::to_path_buf);
+
+    if pack::is_pack(&out_dir) {
+        if !force {
+            anyhow::bail!(
+                "A pack already exists at {}. Use --force to replace it.",
+                out_dir.display()
+            );
+        }
+        std::fs::remove_file(out_dir.join(pack::PACK_DB_NAME))?;
+        std::fs::remove_file(out_dir.join(pack::MANIFEST_NAME))?;
+    }
+
+    let manifest = pack::pack_index(&index, &out_dir, env!("RKT_VERSION"))
+        .with_context(|| format!("Failed to pack index into {}", out_dir.display()))?;
 
-        let output = serde_json::json!({
-            "checks": check_list,
-            "suggestions": suggestions,
-            "healthy": checks.iter().all(|(_, ok, _)| *ok)
-        });
-        println!("{}", serde_json::to_string_pretty(&output)?);
+    if format == OutputFormat::Json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "path": out_dir.display().to_string(),
+                "manifest": manifest,
+            }))?
+        );
     } else if !quiet {
-        println!("RocketIndex Health Check\n");
+        println!(
+            "Packed {} symbols from {} files into {} ({} bytes)",
+            manifest.symbols,
+            manifest.files,
+            out_dir.display(),
+            manifest.size_bytes
+        );
+    }
 
-        for (name, ok, msg) in &checks {
-            let status = if *ok { "✓" } else { "✗" };
-            println!("  {} {}: {}", status, name, msg);
-        }
+    Ok(exit_codes::SUCCESS)
+}
 
-        if !suggestions.is_empty() {
-            println!("\nSuggestions:");
-            for suggestion in &suggestions {
-                println!("  • {}", suggestion);
-            }
-        }
+fn cmd_maintain(format: OutputFormat, quiet: bool) -> Result<u8> {
+    let db_path = std::env::current_dir()?
+        .join(".rocketindex")
+        .join(DEFAULT_DB_NAME);
+    if !db_path.exists() {
+        anyhow::bail!("Index not found. Run 'rkt index' first.");
+    }
+    let index = open_index(&db_path, &std::env::current_dir()?)?;
+    let report = index.maintain().context("Failed to maintain index")?;
 
-        println!();
-        if checks.iter().all(|(_, ok, _)| *ok) {
-            println!("All checks passed!");
-        } else {
-            println!("Some checks failed. See suggestions above.");
-        }
+    if format == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else if !quiet {
+        print_maintenance_report(&report);
     }
 
     Ok(exit_codes::SUCCESS)
 }
 
+fn print_maintenance_report(report: &MaintenanceReport) {
+    println!(
+        "Index compacted from {} to {} bytes ({} orphaned references, {} orphaned opens removed)",
+        report.size_before, report.size_after, report.orphaned_references, report.orphaned_opens
+    );
+}
+
 /// Show documentation for a symbol
 fn cmd_doc(symbol: &str, format: OutputFormat, quiet: bool) -> Result<u8> {
     warn_if_no_session(quiet);
@@ -2649,35 +8178,14 @@ fn cmd_doc(symbol: &str, format: OutputFormat, quiet: bool) -> Result<u8> {
     // Try exact match first
     let sym = if let Ok(Some(s)) = index.find_by_qualified(symbol) {
         s
-    } else if let Ok(matches) = index.search(symbol, 1, None) {
-        if let Some(s) = matches.first() {
-            s.clone()
-        } else {
-            if format == OutputFormat::Json {
-                println!(
-                    "{}",
-                    serde_json::json!({
-                        "error": "Symbol not found",
-                        "symbol": symbol
-                    })
-                );
-            } else {
-                eprintln!("Symbol not found: {}", symbol);
-            }
-            return Ok(exit_codes::NOT_FOUND);
-        }
+    } else if let Some(s) = index
+        .search(symbol, 1, None)
+        .ok()
+        .and_then(|matches| matches.into_iter().next())
+    {
+        s
     } else {
-        if format == OutputFormat::Json {
-            println!(
-                "{}",
-                serde_json::json!({
-                    "error": "Symbol not found",
-                    "symbol": symbol
-                })
-            );
-        } else {
-            eprintln!("Symbol not found: {}", symbol);
-        }
+        print_not_found("Symbol not found", symbol, &suggest(&index, symbol), format);
         return Ok(exit_codes::NOT_FOUND);
     };
 
@@ -2700,9 +8208,25 @@ fn cmd_doc(symbol: &str, format: OutputFormat, quiet: bool) -> Result<u8> {
     Ok(exit_codes::SUCCESS)
 }
 
+/// Caller depth searched for tests by `rkt tests-for` and `rkt enrich --tests`
+const TESTS_FOR_DEPTH: usize = 5;
+
+/// What `rkt enrich` reports beyond the symbol itself
+struct EnrichOptions {
+    callers_depth: usize,
+    deps_depth: usize,
+    tests: bool,
+    routes: bool,
+}
+
 /// Enrich a symbol with debugging context (callers, dependencies, blame, docs)
 /// Designed for stacktrace analysis workflows
-fn cmd_enrich(symbol: &str, format: OutputFormat, quiet: bool) -> Result<u8> {
+fn cmd_enrich(
+    symbol: &str,
+    options: &EnrichOptions,
+    format: OutputFormat,
+    quiet: bool,
+) -> Result<u8> {
     warn_if_no_session(quiet);
 
     let sqlite_index = load_sqlite_index()?;
@@ -2711,130 +8235,149 @@ fn cmd_enrich(symbol: &str, format: OutputFormat, quiet: bool) -> Result<u8> {
     // Find the symbol
     let sym = if let Ok(Some(s)) = sqlite_index.find_by_qualified(symbol) {
         s
-    } else if let Ok(matches) = sqlite_index.search(symbol, 1, None) {
-        if let Some(s) = matches.first() {
-            s.clone()
-        } else {
-            if format == OutputFormat::Json {
-                println!(
-                    "{}",
-                    serde_json::json!({
-                        "error": "Symbol not found",
-                        "symbol": symbol
-                    })
-                );
-            } else {
-                eprintln!("Symbol not found: {}", symbol);
-            }
-            return Ok(exit_codes::NOT_FOUND);
-        }
+    } else if let Some(s) = sqlite_index
+        .search(symbol, 1, None)
+        .ok()
+        .and_then(|matches| matches.into_iter().next())
+    {
+        s
     } else {
-        if format == OutputFormat::Json {
-            println!(
-                "{}",
-                serde_json::json!({
-                    "error": "Symbol not found",
-                    "symbol": symbol
-                })
-            );
-        } else {
-            eprintln!("Symbol not found: {}", symbol);
-        }
+        print_not_found(
+            "Symbol not found",
+            symbol,
+            &suggest(&sqlite_index, symbol),
+            format,
+        );
         return Ok(exit_codes::NOT_FOUND);
     };
 
-    // Get callers (reverse spider depth=1)
-    let callers_result = reverse_spider(&code_index, &sym.qualified, 1);
-    let callers: Vec<_> = callers_result
-        .nodes
-        .iter()
-        .filter(|n| n.depth == 1)
-        .collect();
-
-    // Get dependencies (spider depth=1)
-    let deps_result = spider(&code_index, &sym.qualified, 1);
-    let dependencies: Vec<_> = deps_result.nodes.iter().filter(|n| n.depth == 1).collect();
+    // Callers and dependencies, nearest first
+    let related = |mut result: SpiderResult| -> Vec<String> {
+        result.nodes.sort_by_key(|n| n.depth);
+        result
+            .nodes
+            .into_iter()
+            .filter(|n| n.depth > 0)
+            .map(|n| n.symbol.qualified)
+            .collect()
+    };
+    let callers = related(reverse_spider(
+        &code_index,
+        &sym.qualified,
+        options.callers_depth,
+    ));
+    let dependencies = related(spider(&code_index, &sym.qualified, options.deps_depth));
 
     // Get blame info (best effort)
-    let blame = git::get_blame(&sym.location.file, sym.location.line).ok();
-
-    if format == OutputFormat::Json {
-        let caller_names: Vec<&str> = callers
-            .iter()
-            .map(|c| c.symbol.qualified.as_str())
-            .collect();
-        let dep_names: Vec<&str> = dependencies
-            .iter()
-            .map(|d| d.symbol.qualified.as_str())
-            .collect();
-
-        let mut output = serde_json::json!({
-            "symbol": sym.qualified,
-            "kind": format!("{}", sym.kind),
-            "file": sym.location.file.display().to_string(),
-            "line": sym.location.line,
-            "callers_count": callers.len(),
-            "callers": caller_names,
-            "dependencies_count": dependencies.len(),
-            "dependencies": dep_names,
+    let blame = git::get_blame(&sym.location.file, sym.location.line)
+        .ok()
+        .map(|b| enrich::Blame {
+            commit: b.commit,
+            author: b.author,
+            date_relative: b.date_relative,
+            message: b.message,
         });
 
-        if let Some(doc) = &sym.doc {
-            output["doc"] = serde_json::json!(doc);
-        }
-        if let Some(sig) = &sym.signature {
-            output["signature"] = serde_json::json!(sig);
-        }
-        if let Some(b) = &blame {
-            output["blame"] = serde_json::json!({
-                "commit": b.commit,
-                "author": b.author,
-                "date_relative": b.date_relative,
-                "message": b.message,
-            });
-        }
+    let tests = options.tests.then(|| {
+        rocketindex::test_map::tests_for(
+            &code_index,
+            &sym.qualified,
+            TESTS_FOR_DEPTH,
+            Confidence::StringMatch,
+        )
+        .into_iter()
+        .map(|t| enrich::Test {
+            qualified: t.symbol.map(|s| s.qualified),
+            file: t.file.display().to_string(),
+            line: t.line,
+            depth: t.depth,
+        })
+        .collect::<Vec<_>>()
+    });
+
+    let output = enrich::Enrichment {
+        schema_version: enrich::VERSION,
+        symbol: sym.qualified.clone(),
+        guid: sym.guid(),
+        kind: sym.kind.to_string(),
+        file: sym.location.file.display().to_string(),
+        line: sym.location.line,
+        signature: sym.signature.clone(),
+        doc: sym.doc.clone(),
+        callers_depth: options.callers_depth,
+        callers_count: callers.len(),
+        callers,
+        dependencies_depth: options.deps_depth,
+        dependencies_count: dependencies.len(),
+        dependencies,
+        blame,
+        tests,
+        routes: options.routes.then(|| sym.routes()),
+        owner: Some(owners::ownership(
+            load_codeowners().as_ref(),
+            &sqlite_index,
+            &sym,
+        ))
+        .filter(|o| o.rule.is_some() || !o.contributors.is_empty()),
+    };
 
+    if format == OutputFormat::Json {
         println!("{}", serde_json::to_string_pretty(&output)?);
     } else if !quiet {
-        println!("{} ({})", sym.qualified, sym.kind);
-        println!(
-            "  Location: {}:{}",
-            sym.location.file.display(),
-            sym.location.line
-        );
+        println!("{} ({})", output.symbol, output.kind);
+        println!("  Location: {}:{}", output.file, output.line);
 
-        if let Some(sig) = &sym.signature {
+        if let Some(sig) = &output.signature {
             println!("  Signature: {}", sig);
         }
 
-        println!("  Callers: {} call sites", callers.len());
-        if !callers.is_empty() {
-            for c in callers.iter().take(5) {
-                println!("    - {}", c.symbol.qualified);
-            }
-            if callers.len() > 5 {
-                println!("    ... and {} more", callers.len() - 5);
-            }
+        println!("  Callers: {} call sites", output.callers_count);
+        for c in output.callers.iter().take(5) {
+            println!("    - {}", c);
+        }
+        if output.callers_count > 5 {
+            println!("    ... and {} more", output.callers_count - 5);
+        }
+
+        println!("  Dependencies: {} symbols", output.dependencies_count);
+        for d in output.dependencies.iter().take(5) {
+            println!("    - {}", d);
+        }
+        if output.dependencies_count > 5 {
+            println!("    ... and {} more", output.dependencies_count - 5);
         }
 
-        println!("  Dependencies: {} symbols", dependencies.len());
-        if !dependencies.is_empty() {
-            for d in dependencies.iter().take(5) {
-                println!("    - {}", d.symbol.qualified);
+        if let Some(tests) = &output.tests {
+            println!("  Tests: {}", tests.len());
+            for t in tests.iter().take(5) {
+                let name = t.qualified.as_deref().unwrap_or("(file-level)");
+                println!("    - {} ({}:{})", name, t.file, t.line);
             }
-            if dependencies.len() > 5 {
-                println!("    ... and {} more", dependencies.len() - 5);
+        }
+
+        if let Some(routes) = &output.routes {
+            for r in routes {
+                println!("  Route: {} {}", r.method.as_deref().unwrap_or("*"), r.path);
             }
         }
 
-        if let Some(b) = &blame {
+        if let Some(b) = &output.blame {
             println!(
                 "  Last change: {} ({}) - {}",
                 b.date_relative, b.author, b.message
             );
         }
 
-        if let Some(doc) = &sym.doc {
+        if let Some(owner) = &output.owner {
+            if let Some(rule) = owner.rule.as_ref().filter(|r| !r.owners.is_empty()) {
+                println!("  Owners: {}", rule.owners.join(" "));
+            }
+            if let Some(top) = owner.contributors.first() {
+                println!("  Top contributor: {} <{}>", top.author, top.email);
+            }
+        }
+
+        if let Some(doc) = &output.doc {
             let truncated = if doc.len() > 100 {
                 format!("{}...", &doc[..100])
             } else {
@@ -2847,18 +8390,62 @@ fn cmd_enrich(symbol: &str, format: OutputFormat, quiet: bool) -> Result<u8> {
     Ok(exit_codes::SUCCESS)
 }
 
-/// Analyze a stacktrace and enrich each frame with code context
+/// Emit a symbol's context bundle: source, docs, dependencies, call sites
+fn cmd_context(
+    symbol: &str,
+    options: &ContextOptions,
+    format: OutputFormat,
+    quiet: bool,
+    concise: bool,
+) -> Result<u8> {
+    warn_if_no_session(quiet);
+    let sqlite_index = load_sqlite_index()?;
+    let sym = match find_definition(&sqlite_index, symbol) {
+        Ok(sym) => sym,
+        Err(suggestions) => {
+            report_not_found(symbol, &suggestions, format, quiet, concise)?;
+            return Ok(exit_codes::NOT_FOUND);
+        }
+    };
+    let code_index = load_code_index()?;
+    let sym = code_index.get(&sym.qualified).cloned().unwrap_or(sym);
+    let bundle = build_context(&code_index, &sym, options, |path| {
+        std::fs::read_to_string(path)
+    });
+
+    if format == OutputFormat::Json {
+        if concise {
+            println!("{}", serde_json::to_string(&bundle)?);
+        } else {
+            println!("{}", serde_json::to_string_pretty(&bundle)?);
+        }
+    } else {
+        print!("{}", bundle.to_markdown());
+    }
+
+    Ok(exit_codes::SUCCESS)
+}
+
+/// Analyze a stacktrace, or the error-tracker event `event` names, and
+/// enrich each frame with code context
 fn cmd_analyze(
     stacktrace: Option<&str>,
+    event: Option<&str>,
     user_only: bool,
+    sourcemap_dirs: Option<&[PathBuf]>,
     format: OutputFormat,
     quiet: bool,
 ) -> Result<u8> {
-    use rocketindex::parse_stacktrace;
     use std::io::Read;
 
     warn_if_no_session(quiet);
 
+    if let Some(event) = event {
+        let trace = rocketindex::error_events::parse_event(&read_error_event(event)?)
+            .with_context(|| format!("Failed to read a stacktrace from {}", event))?;
+        return report_trace_analysis(trace, user_only, sourcemap_dirs, format);
+    }
+
     // Get stacktrace text from argument or stdin
     let trace_text = match stacktrace {
         Some(text) => text.to_string(),
@@ -2881,41 +8468,246 @@ fn cmd_analyze(
         } else {
             eprintln!("No stacktrace provided. Pipe stacktrace text to stdin or pass as argument.");
         }
-        return Ok(exit_codes::ERROR);
+        return Ok(exit_codes::ERROR);
+    }
+
+    report_trace_analysis(
+        rocketindex::parse_stacktrace(&trace_text),
+        user_only,
+        sourcemap_dirs,
+        format,
+    )
+}
+
+/// The JSON of the error-tracker event `source` names: fetched from the
+/// Sentry API for a URL, else read from a file or stdin (`-`).
+fn read_error_event(source: &str) -> Result<String> {
+    use std::io::Read;
+
+    if source.contains("://") {
+        let url = rocketindex::error_events::sentry_event_url(source).with_context(|| {
+            format!(
+                "{} isn't a Sentry issue URL (.../organizations/ORG/issues/ID/)",
+                source
+            )
+        })?;
+        let sentry_url = std::env::var("SENTRY_URL").ok();
+        if !rocketindex::error_events::token_allowed_for(&url, sentry_url.as_deref()) {
+            anyhow::bail!(
+                "Won't send SENTRY_AUTH_TOKEN to {}: use an https:// URL on sentry.io, \
+                 or set SENTRY_URL to your self-hosted Sentry",
+                url
+            );
+        }
+        let token = std::env::var("SENTRY_AUTH_TOKEN")
+            .context("Set SENTRY_AUTH_TOKEN to fetch events from Sentry")?;
+        let response = network::agent("fetch the Sentry event")?
+            .get(&url)
+            .set("Authorization", &format!("Bearer {}", token))
+            .call()
+            .with_context(|| format!("Failed to fetch {}", url))?;
+        return Ok(response.into_string()?);
+    }
+    if source == "-" {
+        let mut buffer = String::new();
+        std::io::stdin().read_to_string(&mut buffer)?;
+        return Ok(buffer);
+    }
+    std::fs::read_to_string(source).with_context(|| format!("Failed to read {}", source))
+}
+
+/// Enrich `trace` from the index and print it, as `rkt analyze` does.
+fn report_trace_analysis(
+    trace: rocketindex::StacktraceResult,
+    user_only: bool,
+    sourcemap_dirs: Option<&[PathBuf]>,
+    format: OutputFormat,
+) -> Result<u8> {
+    // Try to load index for enrichment
+    let sqlite_index = load_sqlite_index().ok();
+    let code_index = load_code_index().ok();
+
+    let analysis = analyze_trace(
+        trace,
+        user_only,
+        sourcemap_dirs,
+        sqlite_index.as_ref().zip(code_index.as_ref()),
+    );
+
+    if analysis.result.frames.is_empty() {
+        if format == OutputFormat::Json {
+            println!(
+                "{}",
+                serde_json::json!({
+                    "error": "No stack frames found",
+                    "unparsed_lines": analysis.result.unparsed_lines.len()
+                })
+            );
+        } else {
+            eprintln!("No stack frames found in input.");
+        }
+        return Ok(exit_codes::NOT_FOUND);
+    }
+
+    if format == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&analysis.output)?);
+    } else {
+        print_trace_analysis(&analysis);
+    }
+
+    Ok(exit_codes::SUCCESS)
+}
+
+/// How long a followed log may go quiet before the trace being read is
+/// taken to be complete
+const FOLLOW_QUIET_PERIOD: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// `rkt analyze --follow`: read a log (`file`, or stdin) as it's written
+/// and report each stacktrace in it, with `--format json` as one object
+/// per line. A file is read from its end, as `tail -f` does.
+fn cmd_analyze_follow(
+    file: Option<&Path>,
+    user_only: bool,
+    sourcemap_dirs: Option<&[PathBuf]>,
+    format: OutputFormat,
+    quiet: bool,
+) -> Result<u8> {
+    use std::io::BufRead;
+    use std::sync::mpsc::{self, RecvTimeoutError};
+
+    warn_if_no_session(quiet);
+    let sqlite_index = load_sqlite_index().ok();
+    let code_index = load_code_index().ok();
+
+    let (sender, lines) = mpsc::channel::<String>();
+    match file {
+        Some(path) => {
+            let start = std::fs::metadata(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?
+                .len();
+            let path = path.to_path_buf();
+            std::thread::spawn(move || tail_lines(&path, start, &sender));
+        }
+        None => {
+            std::thread::spawn(move || {
+                for line in std::io::stdin().lock().lines().map_while(Result::ok) {
+                    if sender.send(line).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+    }
+
+    let mut splitter = rocketindex::TraceSplitter::new();
+    loop {
+        let (block, done) = match lines.recv_timeout(FOLLOW_QUIET_PERIOD) {
+            Ok(line) => (splitter.push(&line), false),
+            Err(RecvTimeoutError::Timeout) => (splitter.flush(), false),
+            Err(RecvTimeoutError::Disconnected) => (splitter.flush(), true),
+        };
+        if let Some(block) = block {
+            let analysis = analyze_trace(
+                rocketindex::parse_stacktrace(&block.text),
+                user_only,
+                sourcemap_dirs,
+                sqlite_index.as_ref().zip(code_index.as_ref()),
+            );
+            if !analysis.result.frames.is_empty() {
+                if format == OutputFormat::Json {
+                    let mut event = analysis.output.clone();
+                    event["event"] = "trace".into();
+                    event["line"] = block.line.into();
+                    println!("{}", serde_json::to_string(&event)?);
+                } else {
+                    println!("--- Stacktrace at line {} ---", block.line);
+                    print_trace_analysis(&analysis);
+                    println!();
+                }
+            }
+        }
+        if done {
+            break;
+        }
     }
 
-    // Parse the stacktrace
-    let result = parse_stacktrace(&trace_text);
+    Ok(exit_codes::SUCCESS)
+}
 
-    if result.frames.is_empty() {
-        if format == OutputFormat::Json {
-            println!(
-                "{}",
-                serde_json::json!({
-                    "error": "No stack frames found",
-                    "unparsed_lines": result.unparsed_lines.len()
-                })
-            );
-        } else {
-            eprintln!("No stack frames found in input.");
+/// Send the lines appended to `path` after byte `start`, polling for more.
+/// A file that shrinks (truncated, or replaced by log rotation) is read
+/// again from its start.
+fn tail_lines(path: &Path, mut start: u64, sender: &std::sync::mpsc::Sender<String>) {
+    use std::io::{Read, Seek, SeekFrom};
+
+    let mut partial = Vec::new();
+    loop {
+        if let Ok(mut file) = std::fs::File::open(path) {
+            let len = file.metadata().map_or(0, |metadata| metadata.len());
+            if len < start {
+                start = 0;
+                partial.clear();
+            }
+            if len > start && file.seek(SeekFrom::Start(start)).is_ok() {
+                let read = file
+                    .take(len - start)
+                    .read_to_end(&mut partial)
+                    .unwrap_or(0);
+                start += read as u64;
+                while let Some(end) = partial.iter().position(|&b| b == b'\n') {
+                    let line: Vec<u8> = partial.drain(..=end).collect();
+                    let line = String::from_utf8_lossy(&line);
+                    if sender
+                        .send(line.trim_end_matches(['\n', '\r']).to_string())
+                        .is_err()
+                    {
+                        return;
+                    }
+                }
+            }
         }
-        return Ok(exit_codes::NOT_FOUND);
+        std::thread::sleep(std::time::Duration::from_millis(250));
     }
+}
 
-    // Filter frames if user_only
-    let frames: Vec<_> = if user_only {
-        result.frames.iter().filter(|f| f.is_user_code).collect()
-    } else {
-        result.frames.iter().collect()
+/// What `rkt analyze` reports about one trace.
+struct TraceAnalysis {
+    result: rocketindex::StacktraceResult,
+    /// Where each frame pointed before a source map moved it
+    generated: Vec<Option<rocketindex::GeneratedPosition>>,
+    /// Indices of the frames reported (user code only with --user-only)
+    shown: Vec<usize>,
+    /// The JSON answer
+    output: serde_json::Value,
+}
+
+/// Map a trace's bundled frames through source maps when
+/// `sourcemap_dirs` is given, and enrich the frames from `indexes`.
+fn analyze_trace(
+    mut result: rocketindex::StacktraceResult,
+    user_only: bool,
+    sourcemap_dirs: Option<&[PathBuf]>,
+    indexes: Option<(&SqliteIndex, &CodeIndex)>,
+) -> TraceAnalysis {
+    // Map bundled frames to their sources
+    let generated = match sourcemap_dirs {
+        Some(dirs) => result.apply_sourcemaps(dirs),
+        None => vec![None; result.frames.len()],
     };
 
-    // Try to load index for enrichment
-    let sqlite_index = load_sqlite_index().ok();
-    let code_index = load_code_index().ok();
+    // Filter frames if user_only, keeping their index in the trace
+    let shown: Vec<usize> = result
+        .frames
+        .iter()
+        .enumerate()
+        .filter(|(_, f)| !user_only || f.is_user_code)
+        .map(|(index, _)| index)
+        .collect();
 
     // Build enriched output
     let mut enriched_frames = Vec::new();
-    for frame in &frames {
+    for &index in &shown {
+        let frame = &result.frames[index];
         let mut enriched = serde_json::json!({
             "symbol": frame.symbol,
             "file": frame.file,
@@ -2923,111 +8715,368 @@ fn cmd_analyze(
             "column": frame.column,
             "is_user_code": frame.is_user_code,
             "language": frame.language.map(|l| l.to_string()),
+            "chain_index": result.chain_index(index),
         });
+        if let Some(generated) = &generated[index] {
+            enriched["generated"] = serde_json::json!(generated);
+        }
+
+        // Try to resolve symbol in index: by name, then (for lambdas, closures
+        // and generated names) by the definition enclosing the frame's line.
+        // Names in minified code mean nothing, so mapped frames go by location.
+        if let Some((sqlite, code_idx)) = indexes {
+            let by_location = || {
+                let (file, line) = (frame.file.as_ref()?, frame.line?);
+                let sym = sqlite.enclosing_symbol(file, line).ok()??;
+                Some((sym, "location"))
+            };
+            let resolved = if generated[index].is_some() {
+                by_location()
+            } else {
+                let by_name = match sqlite.find_by_qualified(&frame.symbol) {
+                    Ok(Some(sym)) => Some(sym),
+                    _ => sqlite
+                        .search(&frame.symbol, 1, None)
+                        .ok()
+                        .and_then(|matches| matches.into_iter().next()),
+                };
+                by_name.map(|sym| (sym, "name")).or_else(by_location)
+            };
 
-        // Try to resolve symbol in index
-        if let (Some(ref sqlite), Some(ref code_idx)) = (&sqlite_index, &code_index) {
-            if let Ok(Some(sym)) = sqlite.find_by_qualified(&frame.symbol) {
-                // Add resolved location
+            if let Some((sym, matched_by)) = resolved {
                 enriched["resolved"] = serde_json::json!({
+                    "qualified": sym.qualified,
                     "file": sym.location.file.display().to_string(),
                     "line": sym.location.line,
                     "kind": sym.kind.to_string(),
+                    "matched_by": matched_by,
                 });
 
                 // Get callers count
                 let callers = reverse_spider(code_idx, &sym.qualified, 1);
                 let caller_count = callers.nodes.iter().filter(|n| n.depth == 1).count();
                 enriched["callers_count"] = serde_json::json!(caller_count);
-            } else if let Ok(matches) = sqlite.search(&frame.symbol, 1, None) {
-                if let Some(sym) = matches.first() {
-                    enriched["resolved"] = serde_json::json!({
-                        "file": sym.location.file.display().to_string(),
-                        "line": sym.location.line,
-                        "kind": sym.kind.to_string(),
-                    });
-
-                    let callers = reverse_spider(code_idx, &sym.qualified, 1);
-                    let caller_count = callers.nodes.iter().filter(|n| n.depth == 1).count();
-                    enriched["callers_count"] = serde_json::json!(caller_count);
-                }
             }
         }
 
         enriched_frames.push(enriched);
     }
 
-    // Output result
+    // Where the exception type is defined and raised, for traces whose
+    // frames are inlined or cut off before reaching the origin
+    let exception = result.exception.as_ref().map(|name| {
+        let mut exception = serde_json::json!({ "type": name });
+        if let Some((sqlite, code_idx)) = indexes {
+            exception["definition"] = exception_definition(sqlite, name)
+                .map(|sym| {
+                    let file = match code_idx.workspace_root() {
+                        Some(root) => workspace_relative_path(&sym.location.file, root),
+                        None => sym.location.file.display().to_string(),
+                    };
+                    serde_json::json!({
+                        "qualified": sym.qualified,
+                        "file": file,
+                        "line": sym.location.line,
+                    })
+                })
+                .into();
+            exception["throw_sites"] = throw_sites(sqlite, code_idx, name).into();
+        }
+        exception
+    });
+
+    // The exceptions the trace chains, outermost first
+    let chain: Vec<_> = result
+        .chain
+        .iter()
+        .map(|link| {
+            serde_json::json!({
+                "type": link.exception,
+                "message": link.message,
+                "frames": link.frames.len(),
+            })
+        })
+        .collect();
+
     let output = serde_json::json!({
+        "exception": exception,
+        "chain": chain,
         "frames": enriched_frames,
         "summary": {
             "total_frames": result.frames.len(),
             "user_frames": result.frames.iter().filter(|f| f.is_user_code).count(),
-            "displayed_frames": frames.len(),
+            "displayed_frames": shown.len(),
             "resolved_frames": enriched_frames.iter().filter(|f| f.get("resolved").is_some()).count(),
+            "mapped_frames": generated.iter().flatten().count(),
             "detected_language": result.detected_language.map(|l| l.to_string()),
         }
     });
 
-    if format == OutputFormat::Json {
-        println!("{}", serde_json::to_string_pretty(&output)?);
-    } else {
-        // Text format
-        println!("Stacktrace Analysis");
-        println!("===================");
-        if let Some(lang) = result.detected_language {
-            println!("Language: {}", lang);
-        }
-        println!(
-            "Frames: {} total, {} user code\n",
-            result.frames.len(),
-            result.frames.iter().filter(|f| f.is_user_code).count()
+    TraceAnalysis {
+        result,
+        generated,
+        shown,
+        output,
+    }
+}
+
+/// Print a [`TraceAnalysis`] as text.
+fn print_trace_analysis(analysis: &TraceAnalysis) {
+    let result = &analysis.result;
+    println!("Stacktrace Analysis");
+    println!("===================");
+    if let Some(lang) = result.detected_language {
+        println!("Language: {}", lang);
+    }
+    println!(
+        "Frames: {} total, {} user code\n",
+        result.frames.len(),
+        result.frames.iter().filter(|f| f.is_user_code).count()
+    );
+    let exception = &analysis.output["exception"];
+    if !exception.is_null() {
+        print!(
+            "Exception: {}",
+            exception["type"].as_str().unwrap_or_default()
         );
+        match exception["definition"].as_object() {
+            Some(def) => println!(
+                " (defined at {}:{})",
+                def["file"].as_str().unwrap_or_default(),
+                def["line"]
+            ),
+            None => println!(),
+        }
+        let sites = exception["throw_sites"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default();
+        if !sites.is_empty() {
+            println!("Raised at:");
+            for site in sites {
+                println!(
+                    "  {}:{}",
+                    site["file"].as_str().unwrap_or_default(),
+                    site["line"]
+                );
+            }
+        }
+        println!();
+    }
 
-        for (i, frame) in frames.iter().enumerate() {
-            let marker = if frame.is_user_code { "→" } else { " " };
-            let location = match (&frame.file, frame.line) {
-                (Some(f), Some(l)) => format!("{}:{}", f.display(), l),
-                (Some(f), None) => f.display().to_string(),
-                _ => "unknown".to_string(),
-            };
-            println!("{} {}. {} ({})", marker, i + 1, frame.symbol, location);
+    let mut link = None;
+    for (i, &index) in analysis.shown.iter().enumerate() {
+        let frame = &result.frames[index];
+        let frame_link = result.chain_index(index);
+        if frame_link != link {
+            if let Some(cause) = frame_link.filter(|&l| l > 0).map(|l| &result.chain[l]) {
+                println!(
+                    "  Caused by: {}{}",
+                    cause.exception.as_deref().unwrap_or("(unknown)"),
+                    cause
+                        .message
+                        .as_ref()
+                        .map(|m| format!(": {}", m))
+                        .unwrap_or_default()
+                );
+            }
+            link = frame_link;
+        }
+        let marker = if frame.is_user_code { "→" } else { " " };
+        let location = match (&frame.file, frame.line) {
+            (Some(f), Some(l)) => format!("{}:{}", f.display(), l),
+            (Some(f), None) => f.display().to_string(),
+            _ => "unknown".to_string(),
+        };
+        println!("{} {}. {} ({})", marker, i + 1, frame.symbol, location);
+        if let Some(generated) = &analysis.generated[index] {
+            println!(
+                "       mapped from {}:{}:{}",
+                generated.file.display(),
+                generated.line,
+                generated.column.unwrap_or(1)
+            );
+        }
+        let resolved = &analysis.output["frames"][i]["resolved"];
+        if resolved["matched_by"] == "location" {
+            println!(
+                "       in {}",
+                resolved["qualified"].as_str().unwrap_or_default()
+            );
         }
     }
+}
 
-    Ok(exit_codes::SUCCESS)
+/// The definition of an exception type named in a stacktrace header.
+fn exception_definition(sqlite: &SqliteIndex, name: &str) -> Option<rocketindex::Symbol> {
+    if let Ok(Some(sym)) = sqlite.find_by_qualified(name) {
+        return Some(sym);
+    }
+    let short = short_type_name(name);
+    sqlite
+        .search(short, 20, None)
+        .ok()?
+        .into_iter()
+        .find(|sym| sym.name == short)
+}
+
+/// References to an exception type on lines that raise or throw it.
+fn throw_sites(sqlite: &SqliteIndex, code_idx: &CodeIndex, name: &str) -> Vec<serde_json::Value> {
+    let mut sources: std::collections::HashMap<PathBuf, Option<Vec<String>>> =
+        std::collections::HashMap::new();
+    let mut sites: Vec<(String, u32)> = sqlite
+        .find_references(short_type_name(name))
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|reference| {
+            let lines = sources
+                .entry(reference.location.file.clone())
+                .or_insert_with(|| {
+                    let path = code_idx.make_location_absolute(&reference.location).file;
+                    let text = std::fs::read_to_string(path).ok()?;
+                    Some(text.lines().map(str::to_string).collect())
+                });
+            lines
+                .as_ref()
+                .and_then(|lines| lines.get(reference.location.line.checked_sub(1)? as usize))
+                .is_some_and(|line| rocketindex::stacktrace::is_throw_site(line))
+        })
+        .map(|reference| {
+            let file = match code_idx.workspace_root() {
+                Some(root) => workspace_relative_path(&reference.location.file, root),
+                None => reference.location.file.display().to_string(),
+            };
+            (file, reference.location.line)
+        })
+        .collect();
+    sites.sort();
+    sites.dedup();
+    sites
+        .into_iter()
+        .map(|(file, line)| serde_json::json!({ "file": file, "line": line }))
+        .collect()
+}
+
+/// `PaymentError` from `com.example.PaymentError` or `Billing::PaymentError`.
+fn short_type_name(name: &str) -> &str {
+    name.rsplit(['.', ':']).next().unwrap_or(name)
 }
 
 /// Set up editor integrations
-fn cmd_setup(editor: &str, format: OutputFormat, quiet: bool) -> Result<u8> {
+fn cmd_setup(
+    editor: &str,
+    options: &SetupOptions,
+    format: OutputFormat,
+    quiet: bool,
+) -> Result<u8> {
     let cwd = std::env::current_dir()?;
+    let editor = editor.to_lowercase();
+
+    if !matches!(editor.as_str(), "claude" | "claude-code") {
+        if options.pack.is_some() {
+            anyhow::bail!("--pack is only supported by 'rkt setup claude'");
+        }
+        if options.agents.is_some() {
+            anyhow::bail!("--agents is only supported by 'rkt setup claude'");
+        }
+    }
 
-    match editor.to_lowercase().as_str() {
-        "claude" | "claude-code" => setup_claude_code(&cwd, format, quiet),
-        "cursor" => setup_cursor(&cwd, format, quiet),
-        "copilot" | "github-copilot" => setup_copilot(&cwd, format, quiet),
-        "zed" => setup_zed(&cwd, format, quiet),
-        "gemini" | "gemini-cli" => setup_gemini(&cwd, format, quiet),
+    match editor.as_str() {
+        "claude" | "claude-code" => setup_claude_code(&cwd, options, format, quiet),
+        "claude-desktop" => setup_claude_desktop(&cwd, format, quiet),
+        "cursor" => setup_cursor(&cwd, options, format, quiet),
+        "copilot" | "github-copilot" => setup_copilot(&cwd, options, format, quiet),
+        "zed" => setup_zed(&cwd, options, format, quiet),
+        "gemini" | "gemini-cli" => setup_gemini(&cwd, options, format, quiet),
         _ => {
             if format == OutputFormat::Json {
                 println!(
                     "{}",
                     serde_json::json!({
                         "error": "Unknown editor",
-                        "supported": ["claude", "cursor", "copilot", "zed", "gemini"]
+                        "supported": ["claude", "claude-desktop", "cursor", "copilot", "zed", "gemini"]
                     })
                 );
             } else {
                 eprintln!("Unknown editor: {}", editor);
-                eprintln!("Supported editors: claude, cursor, copilot, zed, gemini");
+                eprintln!(
+                    "Supported editors: claude, claude-desktop, cursor, copilot, zed, gemini"
+                );
             }
             Ok(exit_codes::ERROR)
         }
     }
 }
 
+/// Remove what `rkt setup` added to the current project
+fn cmd_uninstall(dry_run: bool, format: OutputFormat, quiet: bool) -> Result<u8> {
+    let cwd = std::env::current_dir()?;
+    let changes = uninstall::plan(&cwd);
+
+    if !dry_run && !changes.is_empty() {
+        if let Some(pid) = find_watch_process(&cwd) {
+            anyhow::bail!(
+                "rkt watch is running for this project (PID {}); run 'rkt stop' before uninstalling",
+                pid
+            );
+        }
+        uninstall::apply(&cwd, &changes)?;
+    }
+
+    let relative = |path: &Path| {
+        path.strip_prefix(&cwd)
+            .unwrap_or(path)
+            .display()
+            .to_string()
+    };
+    if format == OutputFormat::Json {
+        let list: Vec<_> = changes
+            .iter()
+            .map(|c| serde_json::json!({ "action": c.action(), "path": relative(c.path()) }))
+            .collect();
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "dry_run": dry_run,
+                "changes": list,
+            }))?
+        );
+    } else if !quiet {
+        if changes.is_empty() {
+            println!("Nothing to remove: RocketIndex is not set up here.");
+        } else {
+            if dry_run {
+                println!("Would make these changes (dry run):");
+            } else {
+                println!("Removed RocketIndex from {}:", cwd.display());
+            }
+            for change in &changes {
+                let suffix = if matches!(change, uninstall::Change::RemoveDir(_)) {
+                    "/"
+                } else {
+                    ""
+                };
+                println!(
+                    "  {:<7} {}{}",
+                    change.action(),
+                    relative(change.path()),
+                    suffix
+                );
+            }
+        }
+    }
+
+    Ok(exit_codes::SUCCESS)
+}
+
 /// Start a coding session - runs setup if needed, then starts watch mode
-fn cmd_start(agent: &str, format: OutputFormat, quiet: bool) -> Result<u8> {
+fn cmd_start(
+    agent: &str,
+    options: &SetupOptions,
+    background: bool,
+    format: OutputFormat,
+    quiet: bool,
+) -> Result<u8> {
     let cwd = std::env::current_dir()?;
     let db_path = cwd.join(".rocketindex").join(DEFAULT_DB_NAME);
 
@@ -3084,7 +9133,7 @@ fn cmd_start(agent: &str, format: OutputFormat, quiet: bool) -> Result<u8> {
         if !quiet {
             println!("First time setup - running wizard...\n");
         }
-        cmd_setup(agent, format, quiet)?;
+        cmd_setup(agent, options, format, quiet)?;
     } else {
         // Index exists, just ensure it's fresh and start watch
         if !quiet {
@@ -3093,7 +9142,11 @@ fn cmd_start(agent: &str, format: OutputFormat, quiet: bool) -> Result<u8> {
     }
 
     // Start watch mode (this will also rebuild/update index if needed)
-    cmd_watch(&cwd, format, quiet)
+    if background {
+        cmd_watch_background(&cwd, false, false, format, quiet)
+    } else {
+        cmd_watch(&cwd, false, false, format, quiet)
+    }
 }
 
 /// Warn if no active session (watch mode) is running
@@ -3109,13 +9162,15 @@ fn warn_if_no_session(quiet: bool) {
     };
 
     if find_watch_process(&cwd).is_none() {
-        eprintln!("Warning: No active session. Run 'rkt start <agent>' in a separate terminal for live index updates.");
-        eprintln!();
+        notice::warning(
+            "NoActiveSession",
+            "No active session. Run 'rkt start <agent>' in a separate terminal for live index updates.",
+        )
+        .emit();
     }
 }
 
 /// Detect the primary programming language of a project by counting file extensions
-#[allow(dead_code)]
 fn detect_primary_language(cwd: &Path) -> Option<String> {
     use std::collections::HashMap;
 
@@ -3166,15 +9221,15 @@ fn detect_primary_language(cwd: &Path) -> Option<String> {
         }
     }
 
+    // Ties go to the alphabetically first language
     language_counts
         .into_iter()
-        .max_by_key(|(_, count)| *count)
+        .max_by_key(|(lang, count)| (*count, std::cmp::Reverse(*lang)))
         .filter(|(_, count)| *count >= 3) // Require at least 3 files
         .map(|(lang, _)| lang.to_string())
 }
 
 /// Recursively count file extensions up to a certain depth
-#[allow(dead_code)]
 fn count_extensions(
     path: &Path,
     counts: &mut std::collections::HashMap<&'static str, usize>,
@@ -3298,7 +9353,7 @@ Estimated time: ~1-3 seconds per 1,000 files
     let started = Instant::now();
     println!("\nIndexing codebase...");
 
-    match cmd_index(cwd, false, 1000, false, format, quiet) {
+    match cmd_index(cwd, false, 1000, false, false, false, format, quiet) {
         Ok(code) if code == exit_codes::SUCCESS => {
             if !quiet {
                 println!("Indexed in {:.1?}", started.elapsed());
@@ -3310,6 +9365,124 @@ Estimated time: ~1-3 seconds per 1,000 files
     }
 }
 
+/// Values substituted into agent and skill templates
+fn setup_template_vars(cwd: &Path) -> skills::TemplateVars {
+    skills::TemplateVars {
+        project: cwd
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "this project".to_string()),
+        language: detect_primary_language(cwd).unwrap_or_else(|| "multiple languages".to_string()),
+        rkt_version: env!("RKT_VERSION").to_string(),
+    }
+}
+
+/// Screen 3: Agents - install selected agent and skill definitions
+fn setup_screen_agents(
+    cwd: &Path,
+    definitions: &[skills::Definition],
+    created_files: &mut Vec<String>,
+) -> Result<()> {
+    use dialoguer::MultiSelect;
+
+    if definitions.is_empty() {
+        return Ok(());
+    }
+
+    println!(
+        r#"
+Agents
+──────
+
+Agents and skills teach Claude Code when to reach for RocketIndex.
+They are installed into .claude/ and can be edited afterwards.
+"#
+    );
+
+    let items: Vec<String> = definitions
+        .iter()
+        .map(|d| {
+            let kind = match d.kind {
+                skills::DefinitionKind::Agent => "agent",
+                skills::DefinitionKind::Skill => "skill",
+            };
+            format!("{} ({}, {}) - {}", d.name, kind, d.source, d.description)
+        })
+        .collect();
+    let defaults = vec![true; items.len()];
+
+    let Some(selected) = MultiSelect::new()
+        .with_prompt("Install which? (space to toggle, enter to confirm)")
+        .items(&items)
+        .defaults(&defaults)
+        .interact_opt()?
+    else {
+        println!("\nSkipping agents.\n");
+        return Ok(());
+    };
+
+    let selected: Vec<skills::Definition> = selected
+        .into_iter()
+        .map(|i| definitions[i].clone())
+        .collect();
+    install_definitions(cwd, &selected, created_files)
+}
+
+/// Install agent and skill definitions, reporting each one
+fn install_definitions(
+    cwd: &Path,
+    definitions: &[skills::Definition],
+    created_files: &mut Vec<String>,
+) -> Result<()> {
+    let vars = setup_template_vars(cwd);
+    for definition in definitions {
+        match definition.install(cwd, &vars)? {
+            Some(path) => {
+                println!("  * {}", definition.install_path().display());
+                created_files.push(path.display().to_string());
+            }
+            None => println!(
+                "  - {} already exists, left unchanged",
+                definition.install_path().display()
+            ),
+        }
+    }
+    Ok(())
+}
+
+/// Pick definitions by name for `--agents` ("none" selects nothing)
+fn select_definitions(
+    definitions: &[skills::Definition],
+    names: &[String],
+) -> Result<Vec<skills::Definition>> {
+    let names: Vec<&str> = names
+        .iter()
+        .map(|n| n.trim())
+        .filter(|n| !n.is_empty() && *n != "none")
+        .collect();
+    names
+        .iter()
+        .map(|name| {
+            definitions
+                .iter()
+                .find(|d| d.name == *name)
+                .cloned()
+                .ok_or_else(|| {
+                    let available: Vec<&str> =
+                        definitions.iter().map(|d| d.name.as_str()).collect();
+                    anyhow::anyhow!(
+                        "Unknown agent '{}' (available: {})",
+                        name,
+                        available.join(", ")
+                    )
+                })
+        })
+        .collect()
+}
+
+/// Pointer to the command reference, inserted into existing CLAUDE.md and copilot instructions
+const ROCKETINDEX_NOTE: &str = "**Note**: This project uses [RocketIndex](https://github.com/rocket-tycoon/rocket-index) for code navigation.\n   For definitions, callers, and dependencies use `rkt`. See `.rocketindex/AGENTS.md` for commands.\n";
+
 const AGENT_INSTRUCTIONS: &str = r#"## RocketIndex Code Navigation
 
 This project uses [RocketIndex](https://github.com/rocket-tycoon/rocket-index) for fast code navigation.
@@ -3376,7 +9549,7 @@ Creating project configuration...
     let claude_md_path = cwd.join("CLAUDE.md");
     if claude_md_path.exists() {
         let claude_content = std::fs::read_to_string(&claude_md_path).unwrap_or_default();
-        let rocketindex_note = "**Note**: This project uses [RocketIndex](https://github.com/rocket-tycoon/rocket-index) for code navigation.\n   For definitions, callers, and dependencies use `rkt`. See `.rocketindex/AGENTS.md` for commands.\n";
+        let rocketindex_note = ROCKETINDEX_NOTE;
 
         if !claude_content.contains("RocketIndex") {
             let updated = if let Some(pos) = claude_content.find("\n\n") {
@@ -3398,7 +9571,7 @@ Creating project configuration...
     let copilot_path = cwd.join(".github").join("copilot-instructions.md");
     if copilot_path.exists() {
         let copilot_content = std::fs::read_to_string(&copilot_path).unwrap_or_default();
-        let rocketindex_note = "**Note**: This project uses [RocketIndex](https://github.com/rocket-tycoon/rocket-index) for code navigation.\n   For definitions, callers, and dependencies use `rkt`. See `.rocketindex/AGENTS.md` for commands.\n";
+        let rocketindex_note = ROCKETINDEX_NOTE;
 
         if !copilot_content.contains("RocketIndex") {
             let updated = if let Some(pos) = copilot_content.find("\n\n") {
@@ -3431,6 +9604,30 @@ Creating project configuration...
     Ok(())
 }
 
+/// Offer to register `rkt serve` in the project's .mcp.json
+fn setup_screen_mcp(cwd: &Path, options: &SetupOptions) -> Result<()> {
+    use dialoguer::Confirm;
+
+    let register = options.mcp
+        || Confirm::new()
+            .with_prompt(
+                "Also register the rkt MCP server in .mcp.json? (not needed with the plugin)",
+            )
+            .default(false)
+            .interact()?;
+    if register {
+        if let Some(path) = mcp_clients::register(mcp_clients::Client::ClaudeCode, cwd)? {
+            let relative = path
+                .strip_prefix(cwd)
+                .unwrap_or(&path)
+                .display()
+                .to_string();
+            println!("  * {:<26}Registered rkt serve", relative);
+        }
+    }
+    Ok(())
+}
+
 /// Screen 5: Complete
 fn setup_screen_complete() {
     println!(
@@ -3477,7 +9674,7 @@ fn ensure_initial_index(cwd: &Path, format: OutputFormat, quiet: bool) -> Result
         println!("Building initial RocketIndex index (rkt index)...");
     }
 
-    match cmd_index(cwd, false, 1000, false, format, quiet) {
+    match cmd_index(cwd, false, 1000, false, false, false, format, quiet) {
         Ok(code) if code == exit_codes::SUCCESS => {
             if show_feedback {
                 println!(
@@ -3493,23 +9690,54 @@ fn ensure_initial_index(cwd: &Path, format: OutputFormat, quiet: bool) -> Result
 }
 
 /// Set up Claude Code with 5-screen wizard flow
-fn setup_claude_code(cwd: &Path, format: OutputFormat, quiet: bool) -> Result<u8> {
-    let is_interactive = !quiet && dialoguer::console::Term::stderr().is_term();
+fn setup_claude_code(
+    cwd: &Path,
+    options: &SetupOptions,
+    format: OutputFormat,
+    quiet: bool,
+) -> Result<u8> {
+    let is_interactive = !quiet && !options.yes && dialoguer::console::Term::stderr().is_term();
+
+    // Load the pack and check --agents up front so mistakes fail before anything is written
+    let pack_definitions = options.pack.as_deref().map(skills::load_pack).transpose()?;
+    let mut definitions = skills::embedded();
+    definitions.extend(pack_definitions.clone().unwrap_or_default());
+    let chosen = options
+        .agents
+        .as_deref()
+        .map(|names| select_definitions(&definitions, names))
+        .transpose()?;
 
     // For non-interactive mode, use legacy behavior
     if !is_interactive {
-        return setup_claude_code_non_interactive(cwd, format, quiet);
+        // --yes takes the wizard's defaults (every agent offered); plain
+        // non-interactive runs only install what a pack brings
+        let install = match chosen {
+            Some(chosen) => chosen,
+            None if options.yes => definitions,
+            None => pack_definitions.unwrap_or_default(),
+        };
+        return setup_claude_code_non_interactive(cwd, install, options, format, quiet);
     }
 
     // Screen 1: Welcome
     setup_screen_welcome()?;
 
     // Screen 2: Code Indexing
-    setup_screen_indexing(cwd, format, quiet)?;
+    if !options.no_index {
+        setup_screen_indexing(cwd, format, quiet)?;
+    }
 
-    // Screen 3: Configuration Files
+    // Screen 3: Agents
     let mut created_files = Vec::new();
+    match chosen {
+        Some(chosen) => install_definitions(cwd, &chosen, &mut created_files)?,
+        None => setup_screen_agents(cwd, &definitions, &mut created_files)?,
+    }
+
+    // Screen 4: Configuration Files
     setup_screen_configuration(cwd, format, quiet, &mut created_files)?;
+    setup_screen_mcp(cwd, options)?;
 
     // Screen 5: Complete
     setup_screen_complete();
@@ -3518,11 +9746,26 @@ fn setup_claude_code(cwd: &Path, format: OutputFormat, quiet: bool) -> Result<u8
 }
 
 /// Non-interactive setup for CI/scripts (legacy behavior)
-fn setup_claude_code_non_interactive(cwd: &Path, format: OutputFormat, quiet: bool) -> Result<u8> {
+fn setup_claude_code_non_interactive(
+    cwd: &Path,
+    definitions: Vec<skills::Definition>,
+    options: &SetupOptions,
+    format: OutputFormat,
+    quiet: bool,
+) -> Result<u8> {
     let mut created_files = Vec::new();
 
     // Ensure index exists
-    ensure_initial_index(cwd, format, quiet)?;
+    if !options.no_index {
+        ensure_initial_index(cwd, format, quiet)?;
+    }
+
+    let vars = setup_template_vars(cwd);
+    for definition in definitions {
+        if let Some(path) = definition.install(cwd, &vars)? {
+            created_files.push(path.display().to_string());
+        }
+    }
 
     // Create AGENTS.md
     let agents_md_path = cwd.join(".rocketindex").join("AGENTS.md");
@@ -3546,7 +9789,7 @@ fn setup_claude_code_non_interactive(cwd: &Path, format: OutputFormat, quiet: bo
     let claude_md_path = cwd.join("CLAUDE.md");
     if claude_md_path.exists() {
         let claude_content = std::fs::read_to_string(&claude_md_path).unwrap_or_default();
-        let rocketindex_note = "**Note**: This project uses [RocketIndex](https://github.com/rocket-tycoon/rocket-index) for code navigation.\n   For definitions, callers, and dependencies use `rkt`. See `.rocketindex/AGENTS.md` for commands.\n";
+        let rocketindex_note = ROCKETINDEX_NOTE;
 
         if !claude_content.contains("RocketIndex") {
             let updated = if let Some(pos) = claude_content.find("\n\n") {
@@ -3567,7 +9810,7 @@ fn setup_claude_code_non_interactive(cwd: &Path, format: OutputFormat, quiet: bo
     let copilot_path = cwd.join(".github").join("copilot-instructions.md");
     if copilot_path.exists() {
         let copilot_content = std::fs::read_to_string(&copilot_path).unwrap_or_default();
-        let rocketindex_note = "**Note**: This project uses [RocketIndex](https://github.com/rocket-tycoon/rocket-index) for code navigation.\n   For definitions, callers, and dependencies use `rkt`. See `.rocketindex/AGENTS.md` for commands.\n";
+        let rocketindex_note = ROCKETINDEX_NOTE;
 
         if !copilot_content.contains("RocketIndex") {
             let updated = if let Some(pos) = copilot_content.find("\n\n") {
@@ -3584,6 +9827,8 @@ fn setup_claude_code_non_interactive(cwd: &Path, format: OutputFormat, quiet: bo
         }
     }
 
+    let mcp_config = setup_mcp_client(mcp_clients::Client::ClaudeCode, cwd, options)?;
+
     // JSON output for non-interactive
     if format == OutputFormat::Json {
         println!(
@@ -3591,6 +9836,7 @@ fn setup_claude_code_non_interactive(cwd: &Path, format: OutputFormat, quiet: bo
             serde_json::json!({
                 "editor": "claude-code",
                 "created": created_files,
+                "mcp_config": mcp_config,
                 "usage": "See .rocketindex/AGENTS.md for detailed instructions"
             })
         );
@@ -3599,9 +9845,69 @@ fn setup_claude_code_non_interactive(cwd: &Path, format: OutputFormat, quiet: bo
     Ok(exit_codes::SUCCESS)
 }
 
+/// Register `rkt serve` with `client` if `--mcp` was given; returns the config written
+fn setup_mcp_client(
+    client: mcp_clients::Client,
+    cwd: &Path,
+    options: &SetupOptions,
+) -> Result<Option<String>> {
+    if !options.mcp {
+        return Ok(None);
+    }
+    let path = match mcp_clients::register(client, cwd)? {
+        Some(path) => path,
+        // Already registered: still report where
+        None => client.config_path(cwd).unwrap_or_default(),
+    };
+    Ok(Some(path.display().to_string()))
+}
+
+fn print_mcp_registration(config: Option<&str>) {
+    if let Some(config) = config {
+        println!("  MCP server: {}", config);
+    }
+}
+
+/// Register `rkt serve` with Claude Desktop (a per-user config; there is no project guidance)
+fn setup_claude_desktop(cwd: &Path, format: OutputFormat, quiet: bool) -> Result<u8> {
+    let client = mcp_clients::Client::ClaudeDesktop;
+    let registered = mcp_clients::register(client, cwd)?;
+    let path = client.config_path(cwd).unwrap_or_default();
+
+    if format == OutputFormat::Json {
+        println!(
+            "{}",
+            serde_json::json!({
+                "editor": "claude-desktop",
+                "mcp_config": path.display().to_string(),
+                "updated": registered.is_some(),
+                "usage": "Restart Claude Desktop to load the rocket-index MCP server"
+            })
+        );
+    } else if !quiet {
+        if registered.is_some() {
+            println!("Claude Desktop setup complete!");
+            println!("  Updated: {}", path.display());
+        } else {
+            println!("Claude Desktop already has the rocket-index MCP server.");
+        }
+        println!();
+        println!("Restart Claude Desktop to load it, then register projects with `rkt serve add`.");
+    }
+
+    Ok(exit_codes::SUCCESS)
+}
+
 /// Set up Cursor rules
-fn setup_cursor(cwd: &Path, format: OutputFormat, quiet: bool) -> Result<u8> {
-    ensure_initial_index(cwd, format, quiet)?;
+fn setup_cursor(
+    cwd: &Path,
+    options: &SetupOptions,
+    format: OutputFormat,
+    quiet: bool,
+) -> Result<u8> {
+    if !options.no_index {
+        ensure_initial_index(cwd, format, quiet)?;
+    }
 
     let rules_path = cwd.join(".cursor").join("rules");
     std::fs::create_dir_all(rules_path.parent().unwrap())?;
@@ -3630,6 +9936,7 @@ Tips:
 "#;
 
     std::fs::write(&rules_path, rules_content)?;
+    let mcp_config = setup_mcp_client(mcp_clients::Client::Cursor, cwd, options)?;
 
     if format == OutputFormat::Json {
         println!(
@@ -3637,12 +9944,14 @@ Tips:
             serde_json::json!({
                 "editor": "cursor",
                 "created": [rules_path.display().to_string()],
+                "mcp_config": mcp_config,
                 "usage": "Cursor will now see RocketIndex guidance in .cursor/rules"
             })
         );
     } else if !quiet {
         println!("Cursor setup complete!");
         println!("  Created: {}", rules_path.display());
+        print_mcp_registration(mcp_config.as_deref());
         println!();
         println!("Cursor will now see RocketIndex guidance in .cursor/rules");
     }
@@ -3651,10 +9960,17 @@ Tips:
 }
 
 /// Set up GitHub Copilot instructions
-fn setup_copilot(cwd: &Path, format: OutputFormat, quiet: bool) -> Result<u8> {
+fn setup_copilot(
+    cwd: &Path,
+    options: &SetupOptions,
+    format: OutputFormat,
+    quiet: bool,
+) -> Result<u8> {
     let mut created_files = Vec::new();
 
-    ensure_initial_index(cwd, format, quiet)?;
+    if !options.no_index {
+        ensure_initial_index(cwd, format, quiet)?;
+    }
 
     let copilot_path = cwd.join(".github").join("copilot-instructions.md");
     if let Some(parent) = copilot_path.parent() {
@@ -3700,6 +10016,8 @@ This project uses [RocketIndex](https://github.com/rocket-tycoon/rocket-index) f
         }
     }
 
+    let mcp_config = setup_mcp_client(mcp_clients::Client::Copilot, cwd, options)?;
+
     if format == OutputFormat::Json {
         println!(
             "{}",
@@ -3707,6 +10025,7 @@ This project uses [RocketIndex](https://github.com/rocket-tycoon/rocket-index) f
                 "editor": "copilot",
                 "file": copilot_path.display().to_string(),
                 "created": created_files,
+                "mcp_config": mcp_config,
                 "updated": copilot_content.is_empty() || !copilot_content.contains("RocketIndex"),
                 "usage": "GitHub Copilot will now see RocketIndex guidance"
             })
@@ -3721,6 +10040,7 @@ This project uses [RocketIndex](https://github.com/rocket-tycoon/rocket-index) f
             println!("GitHub Copilot setup complete!");
             println!("  Updated: {}", copilot_path.display());
         }
+        print_mcp_registration(mcp_config.as_deref());
         println!();
         println!("GitHub Copilot will now see RocketIndex guidance.");
     }
@@ -3731,10 +10051,12 @@ This project uses [RocketIndex](https://github.com/rocket-tycoon/rocket-index) f
 /// Set up Zed editor configuration
 /// Zed reads rules from multiple files: .rules, CLAUDE.md, AGENTS.md, etc.
 /// We create .rules (Zed's primary format) and update CLAUDE.md if it exists.
-fn setup_zed(cwd: &Path, format: OutputFormat, quiet: bool) -> Result<u8> {
+fn setup_zed(cwd: &Path, options: &SetupOptions, format: OutputFormat, quiet: bool) -> Result<u8> {
     let mut created_files = Vec::new();
 
-    ensure_initial_index(cwd, format, quiet)?;
+    if !options.no_index {
+        ensure_initial_index(cwd, format, quiet)?;
+    }
 
     // Create AGENTS.md in .rocketindex/ (shared with other editors)
     let agents_md_path = cwd.join(".rocketindex").join("AGENTS.md");
@@ -3801,7 +10123,7 @@ This project uses [RocketIndex](https://github.com/rocket-tycoon/rocket-index) f
     let claude_md_path = cwd.join("CLAUDE.md");
     if claude_md_path.exists() {
         let claude_content = std::fs::read_to_string(&claude_md_path).unwrap_or_default();
-        let rocketindex_note = "**Note**: This project uses [RocketIndex](https://github.com/rocket-tycoon/rocket-index) for code navigation.\n   For definitions, callers, and dependencies use `rkt`. See `.rocketindex/AGENTS.md` for commands.\n";
+        let rocketindex_note = ROCKETINDEX_NOTE;
 
         if !claude_content.contains("RocketIndex") {
             let updated = if let Some(pos) = claude_content.find("\n\n") {
@@ -3818,6 +10140,8 @@ This project uses [RocketIndex](https://github.com/rocket-tycoon/rocket-index) f
         }
     }
 
+    let mcp_config = setup_mcp_client(mcp_clients::Client::Zed, cwd, options)?;
+
     if format == OutputFormat::Json {
         println!(
             "{}",
@@ -3825,6 +10149,7 @@ This project uses [RocketIndex](https://github.com/rocket-tycoon/rocket-index) f
                 "editor": "zed",
                 "rules_file": rules_path.display().to_string(),
                 "created": created_files,
+                "mcp_config": mcp_config,
                 "usage": "Zed will now see RocketIndex guidance in .rules file",
                 "tip": "Use Cmd+Alt+L (Mac) or Ctrl+Alt+L (Linux) to access Zed's Rules Library for global rules"
             })
@@ -3842,6 +10167,7 @@ This project uses [RocketIndex](https://github.com/rocket-tycoon/rocket-index) f
         if created_files.iter().any(|f| f.contains("AGENTS.md")) {
             println!("  Created: {}", agents_md_path.display());
         }
+        print_mcp_registration(mcp_config.as_deref());
         println!();
         println!("Zed will now see RocketIndex guidance in .rules file.");
         println!();
@@ -3854,10 +10180,17 @@ This project uses [RocketIndex](https://github.com/rocket-tycoon/rocket-index) f
 
 /// Set up Gemini CLI configuration
 /// Gemini CLI uses GEMINI.md as its default context file (similar to CLAUDE.md)
-fn setup_gemini(cwd: &Path, format: OutputFormat, quiet: bool) -> Result<u8> {
+fn setup_gemini(
+    cwd: &Path,
+    options: &SetupOptions,
+    format: OutputFormat,
+    quiet: bool,
+) -> Result<u8> {
     let mut created_files = Vec::new();
 
-    ensure_initial_index(cwd, format, quiet)?;
+    if !options.no_index {
+        ensure_initial_index(cwd, format, quiet)?;
+    }
 
     // Create AGENTS.md in .rocketindex/ (shared with other editors)
     let agents_md_path = cwd.join(".rocketindex").join("AGENTS.md");
@@ -3920,6 +10253,8 @@ This project uses [RocketIndex](https://github.com/rocket-tycoon/rocket-index) f
         }
     }
 
+    let mcp_config = setup_mcp_client(mcp_clients::Client::Gemini, cwd, options)?;
+
     if format == OutputFormat::Json {
         println!(
             "{}",
@@ -3927,6 +10262,7 @@ This project uses [RocketIndex](https://github.com/rocket-tycoon/rocket-index) f
                 "editor": "gemini",
                 "gemini_md": gemini_md_path.display().to_string(),
                 "created": created_files,
+                "mcp_config": mcp_config,
                 "usage": "Gemini CLI will now see RocketIndex guidance in GEMINI.md"
             })
         );
@@ -3943,6 +10279,7 @@ This project uses [RocketIndex](https://github.com/rocket-tycoon/rocket-index) f
         if created_files.iter().any(|f| f.contains("AGENTS.md")) {
             println!("  Created: {}", agents_md_path.display());
         }
+        print_mcp_registration(mcp_config.as_deref());
         println!();
         println!("Gemini CLI will now see RocketIndex guidance in GEMINI.md.");
     }
@@ -17,6 +17,37 @@ pub struct McpConfig {
     /// Debounce duration for file watching (milliseconds)
     #[serde(default = "default_debounce_ms")]
     pub debounce_ms: u64,
+
+    /// Tool calls that may run at once across all clients
+    #[serde(default = "default_max_concurrent_queries")]
+    pub max_concurrent_queries: usize,
+
+    /// Tool calls that may run at once for a single client. Each stdio server
+    /// process has one client, so this caps the process
+    #[serde(default = "default_max_queries_per_client")]
+    pub max_queries_per_client: usize,
+
+    /// How long a tool call may wait for a free slot (seconds)
+    #[serde(default = "default_queue_timeout_secs")]
+    pub queue_timeout_secs: u64,
+
+    /// How long a tool call may run before it is reported as timed out (seconds)
+    #[serde(default = "default_query_timeout_secs")]
+    pub query_timeout_secs: u64,
+
+    /// Offer tools that propose edits (rename_symbol, organize_opens).
+    /// They return edits for the client to apply and never write files.
+    #[serde(default)]
+    pub editing_tools: bool,
+
+    /// Projects whose indexes are kept open; the least recently used one
+    /// is closed when another has to be opened
+    #[serde(default = "default_max_open_projects")]
+    pub max_open_projects: usize,
+
+    /// How often an open project is checked for changed files (milliseconds)
+    #[serde(default = "default_refresh_interval_ms")]
+    pub refresh_interval_ms: u64,
 }
 
 impl Default for McpConfig {
@@ -25,6 +56,13 @@ impl Default for McpConfig {
             projects: Vec::new(),
             auto_watch: default_auto_watch(),
             debounce_ms: default_debounce_ms(),
+            max_concurrent_queries: default_max_concurrent_queries(),
+            max_queries_per_client: default_max_queries_per_client(),
+            queue_timeout_secs: default_queue_timeout_secs(),
+            query_timeout_secs: default_query_timeout_secs(),
+            editing_tools: false,
+            max_open_projects: default_max_open_projects(),
+            refresh_interval_ms: default_refresh_interval_ms(),
         }
     }
 }
@@ -37,6 +75,32 @@ fn default_debounce_ms() -> u64 {
     200
 }
 
+fn default_max_concurrent_queries() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4)
+}
+
+fn default_max_queries_per_client() -> usize {
+    4
+}
+
+fn default_queue_timeout_secs() -> u64 {
+    10
+}
+
+fn default_query_timeout_secs() -> u64 {
+    30
+}
+
+fn default_max_open_projects() -> usize {
+    8
+}
+
+fn default_refresh_interval_ms() -> u64 {
+    1000
+}
+
 impl McpConfig {
     /// Get the config file path
     pub fn config_path() -> PathBuf {
@@ -0,0 +1,210 @@
+//! Concurrency caps and timeouts for tool calls.
+//!
+//! The rate limiter in the server bounds how often calls arrive; this bounds
+//! how much work runs at once. Each call first takes a permit for its client
+//! and then one from the global pool, waiting at most the queue timeout for
+//! both. Clients are told apart by the name they give when connecting; an
+//! MCP server speaks stdio to a single client, so there the per-client cap
+//! limits the whole process. A call that runs past the query timeout gets an
+//! error response, but keeps its permits until the work actually finishes, so
+//! a runaway spider can't be "timed out" into unbounded background load.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::{Mutex, Semaphore};
+use tokio::time::timeout;
+
+use super::config::McpConfig;
+
+/// Limits applied to tool calls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueryLimits {
+    /// Calls running at once across all clients
+    pub max_concurrent: usize,
+    /// Calls running at once for a single client
+    pub max_per_client: usize,
+    /// How long a call may wait for a free slot before being rejected
+    pub queue_timeout: Duration,
+    /// How long a call may run before the client gets a timeout error
+    pub query_timeout: Duration,
+}
+
+impl QueryLimits {
+    pub fn from_config(config: &McpConfig) -> Self {
+        Self {
+            max_concurrent: config.max_concurrent_queries.max(1),
+            max_per_client: config.max_queries_per_client.max(1),
+            queue_timeout: Duration::from_secs(config.queue_timeout_secs),
+            query_timeout: Duration::from_secs(config.query_timeout_secs),
+        }
+    }
+}
+
+/// Why a call was not answered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LimitError {
+    /// No slot freed up within the queue timeout
+    Busy,
+    /// The call ran longer than the query timeout
+    TimedOut(Duration),
+    /// The call panicked
+    Failed(String),
+}
+
+impl std::fmt::Display for LimitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LimitError::Busy => write!(
+                f,
+                "Server busy: too many queries in flight. Wait for earlier queries to finish."
+            ),
+            LimitError::TimedOut(limit) => write!(
+                f,
+                "Query timed out after {}s. Try a smaller depth or a more specific symbol.",
+                limit.as_secs()
+            ),
+            LimitError::Failed(reason) => write!(f, "Query failed: {}", reason),
+        }
+    }
+}
+
+/// Admits tool calls under [`QueryLimits`].
+pub struct QueryLimiter {
+    limits: QueryLimits,
+    global: Arc<Semaphore>,
+    per_client: Mutex<HashMap<String, Arc<Semaphore>>>,
+}
+
+impl QueryLimiter {
+    pub fn new(limits: QueryLimits) -> Self {
+        Self {
+            limits,
+            global: Arc::new(Semaphore::new(limits.max_concurrent)),
+            per_client: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Run `query` for `client` once both its client and the global pool have room.
+    pub async fn run<F, T>(&self, client: &str, query: F) -> Result<T, LimitError>
+    where
+        F: Future<Output = T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let client_slots = self
+            .per_client
+            .lock()
+            .await
+            .entry(client.to_string())
+            .or_insert_with(|| Arc::new(Semaphore::new(self.limits.max_per_client)))
+            .clone();
+
+        // Take the client's own slot first so one client's backlog never
+        // holds global slots while it waits
+        let client_permit = timeout(self.limits.queue_timeout, client_slots.acquire_owned())
+            .await
+            .map_err(|_| LimitError::Busy)?
+            .map_err(|_| LimitError::Busy)?;
+        let global_permit = timeout(
+            self.limits.queue_timeout,
+            self.global.clone().acquire_owned(),
+        )
+        .await
+        .map_err(|_| LimitError::Busy)?
+        .map_err(|_| LimitError::Busy)?;
+
+        let handle = tokio::spawn(async move {
+            let _permits = (client_permit, global_permit);
+            query.await
+        });
+
+        match timeout(self.limits.query_timeout, handle).await {
+            Ok(Ok(value)) => Ok(value),
+            Ok(Err(e)) => Err(LimitError::Failed(e.to_string())),
+            Err(_) => Err(LimitError::TimedOut(self.limits.query_timeout)),
+        }
+    }
+
+    /// Calls currently running across all clients.
+    #[cfg(test)]
+    pub fn in_flight(&self) -> usize {
+        self.limits.max_concurrent - self.global.available_permits()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(max_concurrent: usize, max_per_client: usize) -> QueryLimits {
+        QueryLimits {
+            max_concurrent,
+            max_per_client,
+            queue_timeout: Duration::from_millis(50),
+            query_timeout: Duration::from_millis(200),
+        }
+    }
+
+    async fn sleep_for(ms: u64) -> u64 {
+        tokio::time::sleep(Duration::from_millis(ms)).await;
+        ms
+    }
+
+    #[tokio::test]
+    async fn runaway_client_is_capped_without_blocking_others() {
+        let limiter = Arc::new(QueryLimiter::new(limits(3, 1)));
+
+        let busy = {
+            let limiter = limiter.clone();
+            tokio::spawn(async move { limiter.run("agent-a", sleep_for(150)).await })
+        };
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        // agent-a's second call can't get a slot in time...
+        assert_eq!(
+            limiter.run("agent-a", sleep_for(1)).await,
+            Err(LimitError::Busy)
+        );
+        // ...but agent-b still gets through
+        assert_eq!(limiter.run("agent-b", sleep_for(1)).await, Ok(1));
+
+        assert_eq!(busy.await.unwrap(), Ok(150));
+        assert_eq!(limiter.in_flight(), 0);
+    }
+
+    #[tokio::test]
+    async fn global_pool_is_shared_by_all_clients() {
+        let limiter = Arc::new(QueryLimiter::new(limits(1, 4)));
+
+        let busy = {
+            let limiter = limiter.clone();
+            tokio::spawn(async move { limiter.run("agent-a", sleep_for(150)).await })
+        };
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        assert_eq!(
+            limiter.run("agent-b", sleep_for(1)).await,
+            Err(LimitError::Busy)
+        );
+        assert_eq!(busy.await.unwrap(), Ok(150));
+    }
+
+    #[tokio::test]
+    async fn slow_queries_time_out_but_hold_their_slot() {
+        let limiter = QueryLimiter::new(limits(1, 1));
+
+        let result = limiter.run("agent-a", sleep_for(400)).await;
+        assert_eq!(
+            result,
+            Err(LimitError::TimedOut(Duration::from_millis(200)))
+        );
+        // The timed-out work is still running and still counted
+        assert_eq!(limiter.in_flight(), 1);
+        assert_eq!(
+            limiter.run("agent-b", sleep_for(1)).await,
+            Err(LimitError::Busy)
+        );
+    }
+}
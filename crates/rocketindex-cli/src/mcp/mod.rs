@@ -7,7 +7,9 @@ mod tests;
 
 pub mod config;
 pub mod format;
+pub mod limits;
 pub mod project_manager;
+pub mod sandbox;
 pub mod server;
 pub mod tools;
 pub mod watcher_pool;
@@ -1,16 +1,27 @@
 //! Multi-project state management for the MCP server.
+//!
+//! Registered projects are opened on first use and kept open up to
+//! `max_open_projects`, closing the least recently used one beyond that.
+//! Each project has its own lock: calls to different projects run side by
+//! side, while calls to the same project wait for a load or refresh in
+//! progress instead of repeating it. An open project is refreshed like the
+//! CLI does (stale source files are reindexed by mtime) at most every
+//! `refresh_interval_ms`, and reloaded when another process (`rkt index`,
+//! `rkt watch`) has written to its database.
 
 use anyhow::{Context, Result};
 use rayon::prelude::*;
 use rocketindex::config::Config;
+use rocketindex::external_index::index_workspace_assemblies;
 use rocketindex::watch::find_source_files_with_config;
 use rocketindex::{CodeIndex, SqliteIndex};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use std::sync::{Arc, Mutex};
-use std::time::Instant;
-use tokio::sync::RwLock;
-use tracing::{info, warn};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::{Mutex, OwnedMutexGuard, RwLock};
+use tracing::{debug, info, warn};
 
 use super::config::McpConfig;
 
@@ -25,6 +36,10 @@ pub struct ProjectState {
     /// Whether the project has active watchers (managed by WatcherPool)
     #[allow(dead_code)]
     pub watching: bool,
+    /// Modification time of the database when it was loaded
+    db_modified: Option<SystemTime>,
+    /// When source files were last checked for changes
+    checked: Instant,
 }
 
 impl ProjectState {
@@ -37,12 +52,14 @@ impl ProjectState {
             Self::create_index(&root)?;
         }
 
+        let db_modified = db_modified(&root);
         let sqlite = SqliteIndex::open(&db_path)
             .with_context(|| format!("Failed to open index at {}", db_path.display()))?;
 
         // Load CodeIndex from SQLite
         let mut code_index = CodeIndex::new();
         code_index.set_workspace_root(root.clone());
+        code_index.set_rails_conventions(Config::load(&root).rails);
 
         // Load symbols into CodeIndex for resolution
         Self::load_code_index(&sqlite, &mut code_index)?;
@@ -52,6 +69,8 @@ impl ProjectState {
             sqlite,
             code_index,
             watching: false,
+            db_modified,
+            checked: Instant::now(),
         })
     }
 
@@ -116,6 +135,7 @@ impl ProjectState {
         let mut all_symbols = Vec::new();
         let mut all_references: Vec<(PathBuf, rocketindex::index::Reference)> = Vec::new();
         let mut all_opens: Vec<(PathBuf, String, u32)> = Vec::new();
+        let mut all_aliases: Vec<(PathBuf, rocketindex::ModuleAlias, u32)> = Vec::new();
 
         for (file, parse_result) in parse_results {
             all_symbols.extend(parse_result.symbols);
@@ -127,6 +147,10 @@ impl ProjectState {
             for (line, open) in parse_result.opens.into_iter().enumerate() {
                 all_opens.push((file.clone(), open, line as u32 + 1));
             }
+
+            for (line, alias) in parse_result.aliases.into_iter().enumerate() {
+                all_aliases.push((file.clone(), alias, line as u32 + 1));
+            }
         }
 
         let symbol_count = all_symbols.len();
@@ -154,6 +178,26 @@ impl ProjectState {
             .insert_opens(&open_tuples)
             .context("Failed to insert opens")?;
 
+        // Batch insert module aliases
+        let alias_tuples: Vec<_> = all_aliases
+            .iter()
+            .map(|(f, a, l)| (f.as_path(), a, *l))
+            .collect();
+        index
+            .insert_aliases(&alias_tuples)
+            .context("Failed to insert module aliases")?;
+
+        // Record mtimes so the first refresh doesn't reindex everything
+        for file in &files {
+            if let Some(mtime) = std::fs::metadata(file)
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            {
+                let _ = index.set_file_mtime(file, mtime.as_secs());
+            }
+        }
+
         let duration = start.elapsed();
         info!(
             "Auto-indexed {} files, {} symbols in {:.2}s",
@@ -167,6 +211,12 @@ impl ProjectState {
 
     /// Load symbols from SQLite into the in-memory CodeIndex
     fn load_code_index(sqlite: &SqliteIndex, code_index: &mut CodeIndex) -> Result<()> {
+        let packages = sqlite.packages()?;
+        if let (false, Some(root)) = (packages.is_empty(), code_index.workspace_root()) {
+            let external = index_workspace_assemblies(root, &packages);
+            code_index.set_external_index(external);
+        }
+
         // Get all files in the index
         let files = sqlite.list_files()?;
 
@@ -188,6 +238,11 @@ impl ProjectState {
             for module in opens {
                 code_index.add_open(file.clone(), module);
             }
+
+            // Load module aliases for this file
+            for alias in sqlite.aliases_for_file(&file)? {
+                code_index.add_alias(file.clone(), alias);
+            }
         }
 
         Ok(())
@@ -196,21 +251,71 @@ impl ProjectState {
     /// Reload the index from SQLite
     pub fn reload(&mut self) -> Result<()> {
         let db_path = self.root.join(".rocketindex").join("index.db");
+        self.db_modified = db_modified(&self.root);
         self.sqlite = SqliteIndex::open(&db_path)?;
         self.code_index = CodeIndex::new();
         self.code_index.set_workspace_root(self.root.clone());
+        self.code_index
+            .set_rails_conventions(Config::load(&self.root).rails);
         Self::load_code_index(&self.sqlite, &mut self.code_index)?;
         Ok(())
     }
+
+    /// Reindex changed source files and reload if the database changed,
+    /// unless the project was checked less than `interval` ago.
+    fn refresh_if_stale(&mut self, interval: Duration) -> Result<()> {
+        if self.checked.elapsed() < interval {
+            return Ok(());
+        }
+        self.checked = Instant::now();
+        crate::ensure_index_fresh(&self.sqlite, &self.root)?;
+        if db_modified(&self.root) != self.db_modified {
+            self.reload()?;
+        }
+        Ok(())
+    }
+}
+
+/// Last write to a project's database. Writes land in the WAL until a
+/// checkpoint, so that counts too.
+fn db_modified(root: &Path) -> Option<SystemTime> {
+    let index_dir = root.join(".rocketindex");
+    ["index.db", "index.db-wal"]
+        .iter()
+        .filter_map(|name| {
+            std::fs::metadata(index_dir.join(name))
+                .ok()?
+                .modified()
+                .ok()
+        })
+        .max()
+}
+
+/// A registered project, open or not
+struct ProjectSlot {
+    /// `None` until first used, and again once closed to make room
+    state: Arc<Mutex<Option<ProjectState>>>,
+    /// Tick of the last use, for closing the least recently used
+    last_used: AtomicU64,
+}
+
+impl ProjectSlot {
+    fn new(state: Option<ProjectState>) -> Arc<Self> {
+        Arc::new(Self {
+            state: Arc::new(Mutex::new(state)),
+            last_used: AtomicU64::new(0),
+        })
+    }
 }
 
 /// Multi-project state manager
 pub struct ProjectManager {
-    /// Active projects keyed by canonical root path
-    /// Each ProjectState is wrapped in Mutex for thread-safe access
-    projects: Arc<RwLock<HashMap<PathBuf, Mutex<ProjectState>>>>,
+    /// Registered projects keyed by canonical root path
+    projects: Arc<RwLock<HashMap<PathBuf, Arc<ProjectSlot>>>>,
     /// Configuration
     config: Arc<RwLock<McpConfig>>,
+    /// Incremented on every project access, for LRU ordering
+    clock: AtomicU64,
 }
 
 impl ProjectManager {
@@ -220,6 +325,7 @@ impl ProjectManager {
         let manager = Self {
             projects: Arc::new(RwLock::new(HashMap::new())),
             config: Arc::new(RwLock::new(config)),
+            clock: AtomicU64::new(0),
         };
 
         // Load registered projects from config
@@ -228,26 +334,28 @@ impl ProjectManager {
         Ok(manager)
     }
 
-    /// Load all registered projects from config
+    /// Add all registered projects from config; each is opened on first use
     async fn load_registered_projects(&self) -> Result<()> {
         let config = self.config.read().await;
         let project_paths: Vec<PathBuf> = config.projects.clone();
         drop(config);
 
+        let mut projects = self.projects.write().await;
         for path in project_paths {
             // Skip non-existent paths (stale entries from previous runs)
-            if !path.exists() {
+            let Ok(canonical) = path.canonicalize() else {
                 continue;
-            }
-            if let Err(e) = self.register(path.clone()).await {
-                warn!("Failed to load project {}: {}", path.display(), e);
-            }
+            };
+            projects
+                .entry(canonical)
+                .or_insert_with(|| ProjectSlot::new(None));
         }
 
         Ok(())
     }
 
     /// Register a new project (or return existing)
+    #[allow(dead_code)]
     pub async fn register(&self, root: PathBuf) -> Result<()> {
         let canonical = root
             .canonicalize()
@@ -262,19 +370,14 @@ impl ProjectManager {
             }
         }
 
-        // Load the project
+        // Load the project now, so registering reports a broken one
         let state = ProjectState::load(canonical.clone())?;
         info!(
             "Registered project: {} ({} symbols)",
             canonical.display(),
             state.code_index.symbol_count()
         );
-
-        // Add to active projects
-        {
-            let mut projects = self.projects.write().await;
-            projects.insert(canonical.clone(), Mutex::new(state));
-        }
+        self.insert_open(canonical.clone(), state).await;
 
         // Update config
         {
@@ -293,8 +396,7 @@ impl ProjectManager {
             .canonicalize()
             .with_context(|| format!("Failed to canonicalize path: {}", root.display()))?;
         let state = ProjectState::load(canonical.clone())?;
-        let mut projects = self.projects.write().await;
-        projects.insert(canonical.clone(), Mutex::new(state));
+        self.insert_open(canonical, state).await;
         Ok(())
     }
 
@@ -304,6 +406,7 @@ impl ProjectManager {
         Ok(Self {
             projects: Arc::new(RwLock::new(HashMap::new())),
             config: Arc::new(RwLock::new(McpConfig::default())),
+            clock: AtomicU64::new(0),
         })
     }
 
@@ -349,17 +452,15 @@ impl ProjectManager {
         None
     }
 
-    /// Get access to a project's state (locks the project's Mutex)
+    /// Get access to a project's state (locks the project, opening or
+    /// refreshing it first as needed)
     pub async fn with_project<F, R>(&self, root: &Path, f: F) -> Option<R>
     where
         F: FnOnce(&ProjectState) -> R,
     {
         let canonical = root.canonicalize().ok()?;
-        let projects = self.projects.read().await;
-        projects.get(&canonical).map(|mutex| {
-            let state = mutex.lock().expect("ProjectState mutex poisoned");
-            f(&state)
-        })
+        let guard = self.open(&canonical).await?;
+        guard.as_ref().map(f)
     }
 
     /// Get mutable access to a project's state (locks the project's Mutex)
@@ -369,11 +470,103 @@ impl ProjectManager {
         F: FnOnce(&mut ProjectState) -> R,
     {
         let canonical = root.canonicalize().ok()?;
+        let mut guard = self.open(&canonical).await?;
+        guard.as_mut().map(f)
+    }
+
+    /// Lock a registered project, opening it if closed (and closing the
+    /// least recently used one beyond `max_open_projects`), or refreshing
+    /// it if open.
+    async fn open(&self, canonical: &Path) -> Option<OwnedMutexGuard<Option<ProjectState>>> {
+        let slot = self.projects.read().await.get(canonical).cloned()?;
+        let tick = self.clock.fetch_add(1, Ordering::Relaxed);
+        slot.last_used.store(tick, Ordering::Relaxed);
+
+        let (max_open, interval) = {
+            let config = self.config.read().await;
+            (
+                config.max_open_projects,
+                Duration::from_millis(config.refresh_interval_ms),
+            )
+        };
+
+        let mut guard = slot.state.clone().lock_owned().await;
+        match guard.as_mut() {
+            Some(state) => {
+                // A failed refresh still leaves the last good index to answer from
+                if let Err(e) = state.refresh_if_stale(interval) {
+                    warn!("Failed to refresh {}: {}", canonical.display(), e);
+                }
+            }
+            None => match ProjectState::load(canonical.to_path_buf()) {
+                Ok(state) => {
+                    info!(
+                        "Opened project: {} ({} symbols)",
+                        canonical.display(),
+                        state.code_index.symbol_count()
+                    );
+                    *guard = Some(state);
+                    self.close_least_recently_used(canonical, max_open).await;
+                }
+                Err(e) => {
+                    warn!("Failed to load project {}: {}", canonical.display(), e);
+                    return None;
+                }
+            },
+        }
+        Some(guard)
+    }
+
+    /// Add a project that is already open
+    async fn insert_open(&self, canonical: PathBuf, state: ProjectState) {
+        let slot = ProjectSlot::new(Some(state));
+        slot.last_used.store(
+            self.clock.fetch_add(1, Ordering::Relaxed),
+            Ordering::Relaxed,
+        );
+        self.projects.write().await.insert(canonical.clone(), slot);
+        let max_open = self.config.read().await.max_open_projects;
+        self.close_least_recently_used(&canonical, max_open).await;
+    }
+
+    /// Close open projects, least recently used first, until at most
+    /// `max_open` are open. `keep` and projects in use are left alone.
+    async fn close_least_recently_used(&self, keep: &Path, max_open: usize) {
         let projects = self.projects.read().await;
-        projects.get(&canonical).map(|mutex| {
-            let mut state = mutex.lock().expect("ProjectState mutex poisoned");
-            f(&mut state)
-        })
+        let mut busy = 0;
+        let mut idle = Vec::new();
+        for (root, slot) in projects.iter() {
+            if root == keep {
+                continue;
+            }
+            match slot.state.clone().try_lock_owned() {
+                Ok(guard) if guard.is_some() => {
+                    idle.push((slot.last_used.load(Ordering::Relaxed), root, guard));
+                }
+                Ok(_) => {}
+                Err(_) => busy += 1,
+            }
+        }
+
+        idle.sort_by_key(|(last_used, _, _)| *last_used);
+        let mut open = idle.len() + busy + 1;
+        for (_, root, mut guard) in idle {
+            if open <= max_open.max(1) {
+                break;
+            }
+            *guard = None;
+            open -= 1;
+            debug!("Closed least recently used project: {}", root.display());
+        }
+    }
+
+    /// Whether a project's index is currently open
+    #[cfg(test)]
+    async fn is_open(&self, root: &Path) -> bool {
+        let canonical = root.canonicalize().unwrap();
+        let slot = self.projects.read().await.get(&canonical).cloned().unwrap();
+        let open = slot.state.lock().await.is_some();
+        open
     }
 
     /// Get all projects for symbol search (when no specific project is known)
@@ -389,11 +582,15 @@ impl ProjectManager {
         pattern: &str,
         limit: usize,
     ) -> Vec<(PathBuf, Vec<rocketindex::Symbol>)> {
-        let projects = self.projects.read().await;
         let mut results = Vec::new();
 
-        for (root, mutex) in projects.iter() {
-            let state = mutex.lock().expect("ProjectState mutex poisoned");
+        for root in self.all_projects().await {
+            let Some(guard) = self.open(&root).await else {
+                continue;
+            };
+            let Some(state) = guard.as_ref() else {
+                continue;
+            };
             if let Ok(symbols) = state.sqlite.search(pattern, limit, None) {
                 if !symbols.is_empty() {
                     results.push((root.clone(), symbols));
@@ -410,15 +607,19 @@ impl ProjectManager {
         pattern: &str,
         limit: usize,
     ) -> Vec<(PathBuf, Vec<rocketindex::Symbol>)> {
-        let projects = self.projects.read().await;
         let mut results = Vec::new();
 
-        for (root, mutex) in projects.iter() {
-            let state = mutex.lock().expect("ProjectState mutex poisoned");
+        for root in self.all_projects().await {
+            let Some(guard) = self.open(&root).await else {
+                continue;
+            };
+            let Some(state) = guard.as_ref() else {
+                continue;
+            };
             // Distance 3 allows for typos
             if let Ok(fuzzy_results) = state.sqlite.fuzzy_search(pattern, 3, limit, None) {
                 if !fuzzy_results.is_empty() {
-                    let symbols = fuzzy_results.into_iter().map(|(s, _)| s).collect();
+                    let symbols = fuzzy_results.into_iter().map(|m| m.symbol).collect();
                     results.push((root.clone(), symbols));
                 }
             }
@@ -429,14 +630,27 @@ impl ProjectManager {
 
     /// Find definition across all projects
     pub async fn find_definition_all(&self, symbol: &str) -> Vec<(PathBuf, rocketindex::Symbol)> {
-        let projects = self.projects.read().await;
         let mut results = Vec::new();
 
-        for (root, mutex) in projects.iter() {
-            let state = mutex.lock().expect("ProjectState mutex poisoned");
+        for root in self.all_projects().await {
+            let Some(guard) = self.open(&root).await else {
+                continue;
+            };
+            let Some(state) = guard.as_ref() else {
+                continue;
+            };
             // Try exact match first
             if let Ok(Some(sym)) = state.sqlite.find_by_qualified(symbol) {
                 results.push((root.clone(), sym));
+            } else if let Some(sym) = state
+                .sqlite
+                .follow_alias(symbol)
+                .ok()
+                .flatten()
+                .and_then(|new| state.sqlite.find_by_qualified(&new).ok().flatten())
+            {
+                // Renamed; the new name answers for the old one
+                results.push((root.clone(), sym));
             } else if let Ok(symbols) = state.sqlite.search(symbol, 10, None) {
                 // Fall back to search
                 for sym in symbols {
@@ -551,6 +765,83 @@ mod tests {
         );
     }
 
+    fn python_project(source: &str) -> TempDir {
+        let temp = TempDir::new().unwrap();
+        std::fs::write(temp.path().join("app.py"), source).unwrap();
+        temp
+    }
+
+    #[tokio::test]
+    async fn test_least_recently_used_project_is_closed() {
+        let first = python_project("def first():\n    pass\n");
+        let second = python_project("def second():\n    pass\n");
+
+        let manager = ProjectManager::new_empty().await.unwrap();
+        manager.config.write().await.max_open_projects = 1;
+        manager
+            .register_in_memory(first.path().into())
+            .await
+            .unwrap();
+        manager
+            .register_in_memory(second.path().into())
+            .await
+            .unwrap();
+        assert!(!manager.is_open(first.path()).await);
+        assert!(manager.is_open(second.path()).await);
+
+        // Using a closed project reopens it and closes the other
+        let found = manager
+            .with_project(first.path(), |state| {
+                state.code_index.get("first").is_some()
+            })
+            .await;
+        assert_eq!(found, Some(true));
+        assert!(manager.is_open(first.path()).await);
+        assert!(!manager.is_open(second.path()).await);
+    }
+
+    #[tokio::test]
+    async fn test_changed_files_are_refreshed() {
+        let project = python_project("def old_name():\n    pass\n");
+        let manager = Arc::new(ProjectManager::new_empty().await.unwrap());
+        manager.config.write().await.refresh_interval_ms = 0;
+        manager
+            .register_in_memory(project.path().into())
+            .await
+            .unwrap();
+
+        let file = project.path().join("app.py");
+        std::fs::write(&file, "def new_name():\n    pass\n").unwrap();
+        // mtimes are compared in whole seconds
+        std::fs::File::options()
+            .write(true)
+            .open(&file)
+            .unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(1))
+            .unwrap();
+
+        // Concurrent calls refresh once and all see the change
+        let calls: Vec<_> = (0..4)
+            .map(|_| {
+                let manager = manager.clone();
+                let root = project.path().to_path_buf();
+                tokio::spawn(async move {
+                    manager
+                        .with_project(&root, |state| {
+                            (
+                                state.code_index.get("new_name").is_some(),
+                                state.code_index.get("old_name").is_some(),
+                            )
+                        })
+                        .await
+                })
+            })
+            .collect();
+        for call in calls {
+            assert_eq!(call.await.unwrap(), Some((true, false)));
+        }
+    }
+
     #[tokio::test]
     async fn test_resolve_projects_unregistered_path_rejected() {
         let manager = ProjectManager::new().await.unwrap();
@@ -0,0 +1,119 @@
+//! Path sandboxing for file reads made while answering queries.
+//!
+//! Tools read source text (context lines, staleness checks) from the file
+//! paths stored in a project's index. Those paths come from the repository,
+//! which may be untrusted: a checked-in symlink to `~/.ssh/id_rsa` or a
+//! crafted `../../` location would otherwise be read and returned to the
+//! client. Every read goes through [`resolve_within`], which canonicalizes
+//! the path (following symlinks) and rejects anything outside the registered
+//! project root, and then reads the canonical path rather than the original.
+
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+
+use tracing::warn;
+
+/// Largest file tools will read for context; bigger files are almost
+/// certainly generated or binary and would blow up response sizes.
+pub const MAX_READ_BYTES: u64 = 8 * 1024 * 1024;
+
+/// Resolve `file` to its canonical path if it lies inside `project_root`.
+///
+/// Relative paths (e.g. from packed indexes) are taken relative to the root.
+/// Returns `None`, logging a warning, for paths that escape the root via
+/// `..` or symlinks, and for anything that isn't a regular file.
+pub fn resolve_within(file: &Path, project_root: &Path) -> Option<PathBuf> {
+    let canonical_root = project_root.canonicalize().ok()?;
+    let candidate = if file.is_relative() {
+        canonical_root.join(file)
+    } else {
+        file.to_path_buf()
+    };
+    let canonical_file = candidate.canonicalize().ok()?;
+
+    if !canonical_file.starts_with(&canonical_root) {
+        warn!(
+            "Rejected file read outside project boundary: {} (project: {})",
+            file.display(),
+            project_root.display()
+        );
+        return None;
+    }
+
+    // FIFOs and devices could block the server or stream forever
+    if !canonical_file.metadata().ok()?.is_file() {
+        warn!("Rejected read of non-regular file: {}", file.display());
+        return None;
+    }
+
+    Some(canonical_file)
+}
+
+/// Read the lines of `file`, if it is inside `project_root`.
+pub fn read_lines(file: &Path, project_root: &Path) -> Option<Vec<String>> {
+    let path = resolve_within(file, project_root)?;
+    let f = std::fs::File::open(&path).ok()?;
+    if f.metadata().ok()?.len() > MAX_READ_BYTES {
+        warn!("Skipped reading oversized file: {}", path.display());
+        return None;
+    }
+
+    let reader = BufReader::new(f.take(MAX_READ_BYTES));
+    Some(reader.lines().map_while(Result::ok).collect())
+}
+
+/// Metadata for `file`, if it is inside `project_root`.
+pub fn metadata_within(file: &Path, project_root: &Path) -> Option<std::fs::Metadata> {
+    resolve_within(file, project_root)?.metadata().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn project() -> (TempDir, PathBuf) {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path().join("project");
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("src/lib.rs"), "fn a() {}\nfn b() {}\n").unwrap();
+        std::fs::write(dir.path().join("secret.txt"), "hunter2\n").unwrap();
+        (dir, root)
+    }
+
+    #[test]
+    fn reads_files_inside_the_root() {
+        let (_dir, root) = project();
+
+        let lines = read_lines(&root.join("src/lib.rs"), &root).unwrap();
+        assert_eq!(lines, vec!["fn a() {}", "fn b() {}"]);
+        // Relative paths resolve against the root
+        assert!(read_lines(Path::new("src/lib.rs"), &root).is_some());
+    }
+
+    #[test]
+    fn rejects_traversal_and_directories() {
+        let (_dir, root) = project();
+
+        assert!(read_lines(&root.join("../secret.txt"), &root).is_none());
+        assert!(read_lines(Path::new("../secret.txt"), &root).is_none());
+        assert!(read_lines(&root.join("src"), &root).is_none());
+        assert!(metadata_within(&root.join("../secret.txt"), &root).is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_symlinks_that_escape_the_root() {
+        let (dir, root) = project();
+        std::os::unix::fs::symlink(dir.path().join("secret.txt"), root.join("src/link.rs"))
+            .unwrap();
+        std::os::unix::fs::symlink(dir.path(), root.join("outside")).unwrap();
+
+        assert!(read_lines(&root.join("src/link.rs"), &root).is_none());
+        assert!(read_lines(&root.join("outside/secret.txt"), &root).is_none());
+
+        // Links that stay inside the root are fine
+        std::os::unix::fs::symlink(root.join("src/lib.rs"), root.join("alias.rs")).unwrap();
+        assert!(read_lines(&root.join("alias.rs"), &root).is_some());
+    }
+}
@@ -14,9 +14,10 @@ use std::collections::VecDeque;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::Mutex;
-use tracing::{info, warn};
+use tracing::{info, info_span, warn, Instrument};
 
 use super::config::McpConfig;
+use super::limits::{QueryLimiter, QueryLimits};
 use super::tools;
 use super::watcher_pool::WatcherPool;
 use super::ProjectManager;
@@ -94,6 +95,9 @@ impl RateLimiter {
 pub struct RocketIndexServer {
     manager: Arc<ProjectManager>,
     rate_limiter: RateLimiter,
+    query_limiter: QueryLimiter,
+    /// Whether the edit-proposing tools are offered
+    editing_tools: bool,
 }
 
 impl RocketIndexServer {
@@ -102,21 +106,100 @@ impl RocketIndexServer {
     /// Rate limit window duration
     const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(1);
 
-    /// Create a new RocketIndex MCP server
-    pub fn new(manager: Arc<ProjectManager>) -> Self {
+    /// Create a server that caps concurrent tool calls at `limits`
+    pub fn with_limits(manager: Arc<ProjectManager>, limits: QueryLimits) -> Self {
         Self {
             manager,
             rate_limiter: RateLimiter::new(Self::RATE_LIMIT_REQUESTS, Self::RATE_LIMIT_WINDOW),
+            query_limiter: QueryLimiter::new(limits),
+            editing_tools: false,
+        }
+    }
+
+    /// Offer the tools that propose edits (`rename_symbol`, `organize_opens`)
+    pub fn with_editing_tools(mut self, enabled: bool) -> Self {
+        self.editing_tools = enabled;
+        self
+    }
+
+    /// Parse arguments for and run the named tool
+    async fn dispatch(
+        manager: Arc<ProjectManager>,
+        name: String,
+        args: serde_json::Value,
+        editing_tools: bool,
+    ) -> Result<CallToolResult, McpError> {
+        match name.as_str() {
+            "find_definition" => {
+                let input: tools::FindDefinitionInput = serde_json::from_value(args)
+                    .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+                Ok(tools::find_definition(manager, input).await)
+            }
+
+            "find_callers" => {
+                let input: tools::FindCallersInput = serde_json::from_value(args)
+                    .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+                Ok(tools::find_callers(manager, input).await)
+            }
+
+            "find_references" => {
+                let input: tools::FindReferencesInput = serde_json::from_value(args)
+                    .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+                Ok(tools::find_references(manager, input).await)
+            }
+
+            "search_symbols" => {
+                let input: tools::SearchSymbolsInput = serde_json::from_value(args)
+                    .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+                Ok(tools::search_symbols(manager, input).await)
+            }
+
+            "analyze_dependencies" => {
+                let input: tools::AnalyzeDepsInput = serde_json::from_value(args)
+                    .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+                Ok(tools::analyze_dependencies(manager, input).await)
+            }
+
+            "describe_project" => {
+                let input: tools::DescribeProjectInput = serde_json::from_value(args)
+                    .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+                Ok(tools::describe_project(manager, input).await)
+            }
+
+            "rename_symbol" | "organize_opens" if !editing_tools => {
+                Ok(CallToolResult::error(vec![Content::text(format!(
+                    "{} is disabled. Set \"editing_tools\": true in {} to enable it.",
+                    name,
+                    McpConfig::config_path().display()
+                ))]))
+            }
+
+            "rename_symbol" => {
+                let input: tools::RenameSymbolInput = serde_json::from_value(args)
+                    .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+                Ok(tools::rename_symbol(manager, input).await)
+            }
+
+            "organize_opens" => {
+                let input: tools::OrganizeOpensInput = serde_json::from_value(args)
+                    .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+                Ok(tools::organize_opens(manager, input).await)
+            }
+
+            _ => Ok(CallToolResult::error(vec![Content::text(format!(
+                "Unknown tool: {}",
+                name
+            ))])),
         }
     }
 
-    /// Build the list of available tools
     /// Build the list of available tools.
     ///
     /// Tools are ordered by value-add: highest-value tools that grep cannot replicate
     /// are listed first, so AI assistants discover them before falling back to grep.
-    fn tools() -> Vec<Tool> {
-        vec![
+    /// The editing tools come last, and only when enabled.
+    fn tools(editing_tools: bool) -> Vec<Tool> {
+        let mut tools = vec![
             // === HIGH VALUE: Unique capabilities grep cannot provide ===
             tool(
                 "find_callers",
@@ -128,6 +211,11 @@ impl RocketIndexServer {
                             "type": "string",
                             "description": "The fully qualified name of the symbol to find callers for (e.g., 'MyModule.MyFunction')."
                         },
+                        "min_confidence": {
+                            "type": "string",
+                            "enum": ["exact", "qualified", "heuristic", "string-match"],
+                            "description": "Optional minimum confidence. Use 'qualified' to drop callers matched by short name only."
+                        },
                         "project_root": {
                             "type": "string",
                             "description": "Optional path to the project root. If omitted, uses the current project context."
@@ -154,6 +242,11 @@ impl RocketIndexServer {
                             "type": "boolean",
                             "description": "If true, finds what calls this symbol (incoming edges). If false (default), finds what this symbol calls (outgoing edges)."
                         },
+                        "min_confidence": {
+                            "type": "string",
+                            "enum": ["exact", "qualified", "heuristic", "string-match"],
+                            "description": "Optional minimum edge confidence. Higher values trade recall for precision."
+                        },
                         "project_root": {
                             "type": "string",
                             "description": "Optional path to the project root."
@@ -260,6 +353,57 @@ impl RocketIndexServer {
                     "required": ["pattern"]
                 }),
             ),
+        ];
+
+        if editing_tools {
+            tools.extend(Self::editing_tools());
+        }
+        tools
+    }
+
+    /// Tools that propose edits as workspace-edit JSON for the client to apply
+    fn editing_tools() -> Vec<Tool> {
+        vec![
+            tool(
+                "rename_symbol",
+                "Proposes renaming a symbol everywhere it is defined and referenced. Returns a workspace edit (`changes` keyed by file, each a list of `{range, newText}` with 0-indexed lines and columns) plus a unified diff; no files are modified. Prefer this over hand-written find-and-replace: only references that resolve to this symbol are edited, so unrelated symbols with the same name are left alone.",
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "symbol": {
+                            "type": "string",
+                            "description": "The qualified name of the symbol to rename (e.g., 'MyModule.myFunction')."
+                        },
+                        "new_name": {
+                            "type": "string",
+                            "description": "The new short name (e.g., 'myRenamedFunction')."
+                        },
+                        "project_root": {
+                            "type": "string",
+                            "description": "Optional path to the project root."
+                        }
+                    },
+                    "required": ["symbol", "new_name"]
+                }),
+            ),
+            tool(
+                "organize_opens",
+                "Proposes sorting a file's `open` statements (shallower modules first, then alphabetically). Returns a workspace edit replacing the block of opens, or no changes if they are already in order; the file is not modified.",
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "file": {
+                            "type": "string",
+                            "description": "The file to organize, relative to the project root or absolute."
+                        },
+                        "project_root": {
+                            "type": "string",
+                            "description": "Optional path to the project root."
+                        }
+                    },
+                    "required": ["file"]
+                }),
+            ),
         ]
     }
 }
@@ -303,7 +447,7 @@ impl ServerHandler for RocketIndexServer {
     ) -> impl std::future::Future<Output = Result<ListToolsResult, McpError>> + Send + '_ {
         async move {
             Ok(ListToolsResult {
-                tools: Self::tools(),
+                tools: Self::tools(self.editing_tools),
                 next_cursor: None,
             })
         }
@@ -312,10 +456,12 @@ impl ServerHandler for RocketIndexServer {
     fn call_tool(
         &self,
         request: CallToolRequestParam,
-        _context: RequestContext<RoleServer>,
+        context: RequestContext<RoleServer>,
     ) -> impl std::future::Future<Output = Result<CallToolResult, McpError>> + Send + '_ {
         let manager = self.manager.clone();
+        let editing_tools = self.editing_tools;
         let rate_limiter = &self.rate_limiter;
+        let query_limiter = &self.query_limiter;
         async move {
             // SECURITY: Rate limiting to prevent DoS via tool spam
             if !rate_limiter.check().await {
@@ -325,56 +471,51 @@ impl ServerHandler for RocketIndexServer {
                 )]));
             }
 
-            let name = request.name.as_ref();
+            let name = request.name.to_string();
             let args = request
                 .arguments
                 .map(serde_json::Value::Object)
                 .unwrap_or(serde_json::Value::Object(Default::default()));
 
-            info!("Calling tool: {} with args: {}", name, args);
-
-            match name {
-                "find_definition" => {
-                    let input: tools::FindDefinitionInput = serde_json::from_value(args)
-                        .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
-                    Ok(tools::find_definition(manager, input).await)
+            // SECURITY: Concurrency caps so one runaway client can't starve the
+            // machine. Over stdio a server process only ever has one client, so
+            // its per-client cap is in effect a cap for the process.
+            let client = context
+                .peer
+                .peer_info()
+                .map(|info| info.client_info.name.clone())
+                .unwrap_or_else(|| "unknown".to_string());
+
+            // Exported as an OpenTelemetry server span when export is enabled
+            let span = info_span!(
+                "tool_call",
+                tool = %name,
+                client = %client,
+                otel.kind = "server",
+                otel.status_code = tracing::field::Empty,
+            );
+            info!(parent: &span, "Calling tool: {} with args: {}", name, args);
+
+            let query =
+                Self::dispatch(manager, name.clone(), args, editing_tools).instrument(span.clone());
+            let result = match query_limiter
+                .run(&client, query)
+                .instrument(span.clone())
+                .await
+            {
+                Ok(result) => result,
+                Err(e) => {
+                    warn!(parent: &span, "Tool call {} from {} not completed: {}", name, client, e);
+                    Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
                 }
-
-                "find_callers" => {
-                    let input: tools::FindCallersInput = serde_json::from_value(args)
-                        .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
-                    Ok(tools::find_callers(manager, input).await)
-                }
-
-                "find_references" => {
-                    let input: tools::FindReferencesInput = serde_json::from_value(args)
-                        .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
-                    Ok(tools::find_references(manager, input).await)
-                }
-
-                "search_symbols" => {
-                    let input: tools::SearchSymbolsInput = serde_json::from_value(args)
-                        .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
-                    Ok(tools::search_symbols(manager, input).await)
-                }
-
-                "analyze_dependencies" => {
-                    let input: tools::AnalyzeDepsInput = serde_json::from_value(args)
-                        .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
-                    Ok(tools::analyze_dependencies(manager, input).await)
-                }
-
-                "describe_project" => {
-                    let input: tools::DescribeProjectInput = serde_json::from_value(args)
-                        .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
-                    Ok(tools::describe_project(manager, input).await)
-                }
-
-                _ => Ok(CallToolResult::error(vec![Content::text(format!(
-                    "Unknown tool: {}",
-                    name
-                ))])),
-            }
+            };
+
+            let failed = match &result {
+                Ok(output) => output.is_error == Some(true),
+                Err(_) => true,
+            };
+            span.record("otel.status_code", if failed { "error" } else { "ok" });
+            result
         }
     }
 }
@@ -400,7 +541,8 @@ pub async fn run_server(manager: Arc<ProjectManager>) -> anyhow::Result<()> {
         None
     };
 
-    let server = RocketIndexServer::new(manager);
+    let server = RocketIndexServer::with_limits(manager, QueryLimits::from_config(&config))
+        .with_editing_tools(config.editing_tools);
     let transport = rmcp::transport::stdio();
 
     info!("Starting RocketIndex MCP server...");
@@ -1,5 +1,8 @@
 use super::*;
 use crate::mcp::tools::definition::{find_definition, FindDefinitionInput};
+use crate::mcp::tools::edit::{
+    organize_opens, rename_symbol, OrganizeOpensInput, RenameSymbolInput,
+};
 use crate::mcp::tools::structure::{describe_project, DescribeProjectInput};
 use rocketindex::{Location, SqliteIndex, Symbol, SymbolKind, Visibility};
 use std::sync::Arc;
@@ -361,3 +364,114 @@ async fn test_mcp_tools_use_relative_paths() {
         json
     );
 }
+
+#[tokio::test]
+async fn test_rename_symbol_proposes_workspace_edit() {
+    let _guard = CWD_MUTEX.lock().await;
+
+    let dir = TempDir::new().unwrap();
+    let file_path = dir.path().join("billing.py");
+    let source = "def charge():\n    pass\n\ncharge()\n";
+    std::fs::write(&file_path, source).unwrap();
+
+    let db_path = dir.path().join(".rocketindex").join("index.db");
+    std::fs::create_dir_all(db_path.parent().unwrap()).unwrap();
+    let index = SqliteIndex::create(&db_path).unwrap();
+    index
+        .insert_symbol(&Symbol::new(
+            "charge".to_string(),
+            "billing.charge".to_string(),
+            SymbolKind::Function,
+            Location::new(file_path.clone(), 1, 5),
+            Visibility::Public,
+            "python".to_string(),
+        ))
+        .unwrap();
+    index
+        .insert_reference(
+            &file_path,
+            &rocketindex::Reference {
+                name: "charge".to_string(),
+                location: Location::new(file_path.clone(), 4, 1),
+                kind: rocketindex::ReferenceKind::Read,
+            },
+        )
+        .unwrap();
+    drop(index);
+
+    let manager = ProjectManager::new_empty().await.unwrap();
+    manager
+        .register_in_memory(dir.path().to_path_buf())
+        .await
+        .unwrap();
+
+    let result = rename_symbol(
+        Arc::new(manager),
+        RenameSymbolInput {
+            symbol: "billing.charge".to_string(),
+            new_name: "bill".to_string(),
+            project_root: Some(dir.path().to_str().unwrap().to_string()),
+        },
+    )
+    .await;
+    assert_ne!(result.is_error, Some(true));
+    let text = &result.content[0].as_text().unwrap().text;
+    let json: serde_json::Value = serde_json::from_str(text).unwrap();
+
+    assert_eq!(json["new_symbol"], "billing.bill");
+    assert_eq!(json["edit_count"], 2);
+    let edits = json["changes"]["billing.py"].as_array().unwrap();
+    assert_eq!(
+        edits[0],
+        serde_json::json!({
+            "range": {
+                "start": { "line": 0, "character": 4 },
+                "end": { "line": 0, "character": 10 }
+            },
+            "newText": "bill"
+        })
+    );
+    assert_eq!(edits[1]["range"]["start"]["line"], 3);
+    assert!(json["diff"].as_str().unwrap().contains("+bill()"));
+    // Only proposed, never applied
+    assert_eq!(std::fs::read_to_string(&file_path).unwrap(), source);
+}
+
+#[tokio::test]
+async fn test_organize_opens_proposes_sorted_block() {
+    let _guard = CWD_MUTEX.lock().await;
+
+    let (dir, manager) = setup_project().await;
+    let root = dir.path().to_str().unwrap().to_string();
+    std::fs::write(
+        dir.path().join("src").join("Program.fs"),
+        "module Program\n\nopen System.IO\nopen Zebra\nopen Apple\n\nlet x = 1\n",
+    )
+    .unwrap();
+
+    let result = organize_opens(
+        manager.clone(),
+        OrganizeOpensInput {
+            file: "src/Program.fs".to_string(),
+            project_root: Some(root.clone()),
+        },
+    )
+    .await;
+    let text = &result.content[0].as_text().unwrap().text;
+    let json: serde_json::Value = serde_json::from_str(text).unwrap();
+    let edit = &json["changes"]["src/Program.fs"][0];
+    assert_eq!(edit["newText"], "open Apple\nopen Zebra\nopen System.IO");
+    assert_eq!(edit["range"]["start"]["line"], 2);
+    assert_eq!(edit["range"]["end"]["line"], 4);
+
+    // Files outside the project are refused
+    let result = organize_opens(
+        manager,
+        OrganizeOpensInput {
+            file: "../outside.fs".to_string(),
+            project_root: Some(root),
+        },
+    )
+    .await;
+    assert_eq!(result.is_error, Some(true));
+}
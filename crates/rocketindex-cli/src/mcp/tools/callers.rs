@@ -4,6 +4,8 @@ use rmcp::model::{CallToolResult, Content};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
+use rocketindex::Confidence;
+
 use crate::mcp::format::to_relative_path;
 use crate::mcp::ProjectManager;
 
@@ -12,6 +14,9 @@ use crate::mcp::ProjectManager;
 pub struct FindCallersInput {
     /// Symbol to find callers for (qualified name preferred)
     pub symbol: String,
+    /// Only return callers at or above this confidence (default: all)
+    #[serde(default)]
+    pub min_confidence: Option<Confidence>,
     /// Optional project root
     pub project_root: Option<String>,
 }
@@ -23,6 +28,7 @@ pub struct CallerInfo {
     pub file: String,
     pub line: u32,
     pub column: u32,
+    pub confidence: Confidence,
 }
 
 /// Output for find_callers tool
@@ -49,13 +55,18 @@ pub async fn find_callers(manager: Arc<ProjectManager>, input: FindCallersInput)
 
     let mut all_results = Vec::new();
 
-    for root in project_roots {
+    for root in project_roots.clone() {
         let result = manager
             .with_project(&root, |state| {
                 // Use spider with reverse=true and depth=1 to find callers
-                use rocketindex::spider::reverse_spider;
+                use rocketindex::spider::reverse_spider_with_confidence;
 
-                let tree = reverse_spider(&state.code_index, &input.symbol, 1);
+                let tree = reverse_spider_with_confidence(
+                    &state.code_index,
+                    &input.symbol,
+                    1,
+                    input.min_confidence.unwrap_or(Confidence::StringMatch),
+                );
                 let mut callers = Vec::new();
                 for node in tree.nodes {
                     if node.depth == 1 {
@@ -64,6 +75,7 @@ pub async fn find_callers(manager: Arc<ProjectManager>, input: FindCallersInput)
                             file: to_relative_path(&node.symbol.location.file, &root),
                             line: node.symbol.location.line,
                             column: node.symbol.location.column,
+                            confidence: node.confidence,
                         });
                     }
                 }
@@ -84,8 +96,9 @@ pub async fn find_callers(manager: Arc<ProjectManager>, input: FindCallersInput)
 
     if all_results.is_empty() {
         return CallToolResult::success(vec![Content::text(format!(
-            "No callers found for '{}'. This symbol may not be called anywhere, or the index may need updating.",
-            input.symbol
+            "No callers found for '{}'. This symbol may not be called anywhere, or the index may need updating.{}",
+            input.symbol,
+            super::did_you_mean(&manager, &project_roots, &input.symbol).await
         ))]);
     }
 
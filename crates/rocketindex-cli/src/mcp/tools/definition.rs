@@ -5,6 +5,7 @@ use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
 use crate::mcp::format::to_relative_path;
+use crate::mcp::sandbox;
 use crate::mcp::ProjectManager;
 
 /// Input for find_definition tool
@@ -44,6 +45,9 @@ pub struct DefinitionResult {
     pub confidence: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub warning: Option<String>,
+    /// Set when the symbol was found through a rename alias
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deprecated: Option<String>,
 }
 
 /// Execute the find_definition tool
@@ -75,15 +79,20 @@ pub async fn find_definition(
         // Search specific project
         let (res, fuzzy) = manager
             .with_project(&root, |state| {
-                // Try exact match first
+                // Try exact match first, then the new name of a renamed symbol
                 if let Ok(Some(sym)) = state.sqlite.find_by_qualified(&input.symbol) {
                     return (vec![sym], false);
                 }
+                if let Ok(Some(new)) = state.sqlite.follow_alias(&input.symbol) {
+                    if let Ok(Some(sym)) = state.sqlite.find_by_qualified(&new) {
+                        return (vec![sym], false);
+                    }
+                }
                 // Fall back to fuzzy search
                 let fuzzy_results = state
                     .sqlite
                     .fuzzy_search(&input.symbol, 3, 5, None)
-                    .map(|res| res.into_iter().map(|(s, _)| s).collect())
+                    .map(|res| res.into_iter().map(|m| m.symbol).collect())
                     .unwrap_or_default();
                 (fuzzy_results, true)
             })
@@ -117,9 +126,15 @@ pub async fn find_definition(
     };
 
     if results.is_empty() {
+        let roots = if project_roots.is_empty() {
+            manager.all_projects().await
+        } else {
+            project_roots
+        };
         return CallToolResult::error(vec![Content::text(format!(
-            "Symbol '{}' not found. Try using `search_symbols` to find similar symbols.",
-            input.symbol
+            "Symbol '{}' not found.{} Try using `search_symbols` to find similar symbols.",
+            input.symbol,
+            super::did_you_mean(&manager, &roots, &input.symbol).await
         ))]);
     }
 
@@ -134,6 +149,9 @@ pub async fn find_definition(
 
         // Check for staleness
         let warning = check_staleness(&sym.location.file, &root);
+        // Exact matches under another name came through an alias
+        let deprecated = (!is_fuzzy && sym.qualified != input.symbol && sym.name != input.symbol)
+            .then(|| format!("'{}' was renamed to '{}'", input.symbol, sym.qualified));
 
         output_results.push(DefinitionResult {
             qualified: sym.qualified,
@@ -154,6 +172,7 @@ pub async fn find_definition(
             }),
             confidence: Some(if is_fuzzy { 0.5 } else { 1.0 }),
             warning,
+            deprecated,
         });
     }
 
@@ -163,44 +182,26 @@ pub async fn find_definition(
 
 /// Read a single line from a file for context
 ///
-/// SECURITY: Validates that the file is within the project boundary before reading.
+/// SECURITY: Reads go through the sandbox, which rejects files outside the project.
 /// This prevents reading arbitrary files if symbol locations are manipulated.
 fn read_context_line(
     file: &std::path::Path,
     line: usize,
     project_root: &std::path::Path,
 ) -> Option<String> {
-    use std::io::BufRead;
-
-    // SECURITY: Validate file is within project boundary
-    let canonical_file = file.canonicalize().ok()?;
-    let canonical_root = project_root.canonicalize().ok()?;
-
-    if !canonical_file.starts_with(&canonical_root) {
-        // File is outside project boundary - reject for security
-        tracing::warn!(
-            "Rejected file read outside project boundary: {} (project: {})",
-            file.display(),
-            project_root.display()
-        );
-        return None;
-    }
-
-    let f = std::fs::File::open(file).ok()?;
-    let reader = std::io::BufReader::new(f);
-    reader
-        .lines()
+    sandbox::read_lines(file, project_root)?
+        .into_iter()
         .nth(line.saturating_sub(1))
-        .and_then(|l| l.ok())
 }
 
 /// Check if a file is newer than the index database
 fn check_staleness(file: &std::path::Path, project_root: &std::path::Path) -> Option<String> {
     let index_path = project_root.join(".rocketindex").join("index.db");
 
-    if let (Ok(file_meta), Ok(index_meta)) =
-        (std::fs::metadata(file), std::fs::metadata(index_path))
-    {
+    if let (Some(file_meta), Ok(index_meta)) = (
+        sandbox::metadata_within(file, project_root),
+        std::fs::metadata(index_path),
+    ) {
         if let (Ok(file_time), Ok(index_time)) = (file_meta.modified(), index_meta.modified()) {
             if file_time > index_time {
                 // Approximate time diff? For now just warned.
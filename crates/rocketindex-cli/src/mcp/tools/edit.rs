@@ -0,0 +1,236 @@
+//! rename_symbol and organize_opens tools - propose edits without applying them
+//!
+//! Both return LSP-style workspace edits (`changes` keyed by file, each a list
+//! of `{range, newText}` with 0-indexed positions) for the client to review
+//! and apply. Nothing is written to disk. The tools are only listed when
+//! `editing_tools` is enabled in the MCP config.
+
+use rmcp::model::{CallToolResult, Content};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use rocketindex::organize::organize_opens as organize;
+use rocketindex::rename::{plan_rename, SkippedLocation};
+
+use crate::mcp::format::to_relative_path;
+use crate::mcp::sandbox;
+use crate::mcp::ProjectManager;
+
+/// Input for rename_symbol tool
+#[derive(Debug, Deserialize)]
+pub struct RenameSymbolInput {
+    /// Qualified name of the symbol to rename
+    pub symbol: String,
+    /// New short name
+    pub new_name: String,
+    /// Optional project root
+    pub project_root: Option<String>,
+}
+
+/// Input for organize_opens tool
+#[derive(Debug, Deserialize)]
+pub struct OrganizeOpensInput {
+    /// File to organize, relative to the project root or absolute
+    pub file: String,
+    /// Optional project root
+    pub project_root: Option<String>,
+}
+
+/// A 0-indexed position; `character` is a byte offset into the line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Position {
+    pub line: u32,
+    pub character: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Range {
+    pub start: Position,
+    pub end: Position,
+}
+
+/// Replace `range` with `new_text`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextEdit {
+    pub range: Range,
+    pub new_text: String,
+}
+
+/// Output for rename_symbol tool
+#[derive(Debug, Serialize)]
+pub struct RenameResult {
+    pub symbol: String,
+    pub new_symbol: String,
+    /// Edits per file, paths relative to the project root
+    pub changes: BTreeMap<String, Vec<TextEdit>>,
+    pub edit_count: usize,
+    /// Index locations that no longer match the file (stale index)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<SkippedLocation>,
+    /// The same edits as a unified diff
+    pub diff: String,
+    pub project_root: String,
+}
+
+/// Output for organize_opens tool
+#[derive(Debug, Serialize)]
+pub struct OrganizeOpensResult {
+    /// Empty when the opens are already in order
+    pub changes: BTreeMap<String, Vec<TextEdit>>,
+    pub project_root: String,
+}
+
+/// Execute the rename_symbol tool
+pub async fn rename_symbol(
+    manager: Arc<ProjectManager>,
+    input: RenameSymbolInput,
+) -> CallToolResult {
+    let project_roots = manager
+        .resolve_projects(input.project_root.as_deref(), None)
+        .await;
+
+    if project_roots.is_empty() {
+        return CallToolResult::error(vec![Content::text(
+            "No projects registered. Use `register_project` to add a project first.",
+        )]);
+    }
+
+    let mut last_error = None;
+    for root in project_roots.clone() {
+        let planned = manager
+            .with_project(&root, |state| {
+                plan_rename(&state.code_index, &input.symbol, &input.new_name)
+            })
+            .await;
+
+        let mut plan = match planned {
+            Some(Ok(plan)) => plan,
+            // Reported below, with suggestions, if no project has it
+            Some(Err(rocketindex::IndexError::SymbolNotFound(_))) => continue,
+            Some(Err(e)) => {
+                last_error = Some(e.to_string());
+                continue;
+            }
+            None => continue,
+        };
+
+        // SECURITY: only propose edits to files inside the project
+        let (files, outside): (Vec<_>, Vec<_>) = plan
+            .files
+            .into_iter()
+            .partition(|file| sandbox::resolve_within(&file.file, &root).is_some());
+        plan.files = files;
+        for file in outside {
+            plan.skipped
+                .extend(file.edits.iter().map(|edit| SkippedLocation {
+                    file: file.file.clone(),
+                    line: edit.line,
+                    column: edit.column,
+                }));
+        }
+
+        let changes = plan
+            .files
+            .iter()
+            .map(|file| {
+                let edits = file
+                    .edits
+                    .iter()
+                    .map(|edit| TextEdit {
+                        range: Range {
+                            start: Position {
+                                line: edit.line - 1,
+                                character: edit.column - 1,
+                            },
+                            end: Position {
+                                line: edit.line - 1,
+                                character: edit.end_column - 1,
+                            },
+                        },
+                        new_text: plan.new_name.clone(),
+                    })
+                    .collect();
+                (to_relative_path(&file.file, &root), edits)
+            })
+            .collect();
+
+        let result = RenameResult {
+            symbol: plan.symbol.clone(),
+            new_symbol: plan.new_qualified(),
+            changes,
+            edit_count: plan.edit_count(),
+            skipped: plan.skipped.clone(),
+            diff: plan.unified_diff(),
+            project_root: root.display().to_string(),
+        };
+        let json = serde_json::to_string(&result).unwrap_or_default();
+        return CallToolResult::success(vec![Content::text(json)]);
+    }
+
+    let message = match last_error {
+        Some(error) => error,
+        None => format!(
+            "Symbol '{}' not found.{} Use search_symbols to find its qualified name.",
+            input.symbol,
+            super::did_you_mean(&manager, &project_roots, &input.symbol).await
+        ),
+    };
+    CallToolResult::error(vec![Content::text(message)])
+}
+
+/// Execute the organize_opens tool
+pub async fn organize_opens(
+    manager: Arc<ProjectManager>,
+    input: OrganizeOpensInput,
+) -> CallToolResult {
+    let project_roots = manager
+        .resolve_projects(input.project_root.as_deref(), Some(&input.file))
+        .await;
+
+    let Some(root) = project_roots.into_iter().next() else {
+        return CallToolResult::error(vec![Content::text(
+            "No projects registered. Use `register_project` to add a project first.",
+        )]);
+    };
+
+    let Some(lines) = sandbox::read_lines(Path::new(&input.file), &root) else {
+        return CallToolResult::error(vec![Content::text(format!(
+            "Cannot read '{}' in project {}",
+            input.file,
+            root.display()
+        ))]);
+    };
+
+    let mut changes = BTreeMap::new();
+    if let Some(organized) = organize(&lines.join("\n")) {
+        let file = sandbox::resolve_within(Path::new(&input.file), &root)
+            .map(|path| to_relative_path(&path, &root))
+            .unwrap_or_else(|| input.file.clone());
+        changes.insert(
+            file,
+            vec![TextEdit {
+                range: Range {
+                    start: Position {
+                        line: organized.start_line as u32,
+                        character: 0,
+                    },
+                    end: Position {
+                        line: organized.end_line as u32,
+                        character: organized.end_column as u32,
+                    },
+                },
+                new_text: organized.text,
+            }],
+        );
+    }
+
+    let result = OrganizeOpensResult {
+        changes,
+        project_root: root.display().to_string(),
+    };
+    let json = serde_json::to_string(&result).unwrap_or_default();
+    CallToolResult::success(vec![Content::text(json)])
+}
@@ -4,6 +4,7 @@
 
 pub mod callers;
 pub mod definition;
+pub mod edit;
 pub mod references;
 pub mod spider;
 pub mod structure;
@@ -11,7 +12,51 @@ pub mod symbols;
 
 pub use callers::*;
 pub use definition::*;
+pub use edit::*;
 pub use references::*;
 pub use spider::*;
 pub use structure::*;
 pub use symbols::*;
+
+use std::path::PathBuf;
+
+use crate::mcp::ProjectManager;
+
+/// " Did you mean: a, b?" for a symbol none of `roots` define, or an empty
+/// string, to end a tool's "not found" message with.
+pub(crate) async fn did_you_mean(
+    manager: &ProjectManager,
+    roots: &[PathBuf],
+    symbol: &str,
+) -> String {
+    let mut suggestions: Vec<String> = Vec::new();
+    for root in roots {
+        let found = manager
+            .with_project(root, |state| {
+                let index = &state.code_index;
+                if index.get(symbol).is_some() || !index.search(symbol).is_empty() {
+                    None
+                } else {
+                    Some(index.suggest(symbol))
+                }
+            })
+            .await;
+        let found = match found {
+            Some(Some(found)) => found,
+            // Defined here, so the name isn't the problem
+            Some(None) => return String::new(),
+            None => continue,
+        };
+        for suggestion in found {
+            if !suggestions.contains(&suggestion.value) {
+                suggestions.push(suggestion.value);
+            }
+        }
+    }
+    suggestions.truncate(rocketindex::fuzzy::DEFAULT_MAX_SUGGESTIONS);
+    if suggestions.is_empty() {
+        String::new()
+    } else {
+        format!(" Did you mean: {}?", suggestions.join(", "))
+    }
+}
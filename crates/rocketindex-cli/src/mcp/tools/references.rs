@@ -5,6 +5,7 @@ use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
 use crate::mcp::format::to_relative_path;
+use crate::mcp::sandbox;
 use crate::mcp::ProjectManager;
 
 /// Input for find_references tool
@@ -56,7 +57,7 @@ pub async fn find_references(
 
     let mut all_results = Vec::new();
 
-    for root in project_roots {
+    for root in project_roots.clone() {
         let context_lines = input.context_lines;
         let root_for_context = root.clone();
         let result = manager
@@ -101,8 +102,9 @@ pub async fn find_references(
 
     if all_results.is_empty() {
         return CallToolResult::success(vec![Content::text(format!(
-            "No references found for '{}'. The symbol may not be used anywhere, or try a different name.",
-            input.symbol
+            "No references found for '{}'. The symbol may not be used anywhere, or try a different name.{}",
+            input.symbol,
+            super::did_you_mean(&manager, &project_roots, &input.symbol).await
         ))]);
     }
 
@@ -112,31 +114,14 @@ pub async fn find_references(
 
 /// Read context lines around a specific line
 ///
-/// SECURITY: Validates that the file is within the project boundary before reading.
+/// SECURITY: Reads go through the sandbox, which rejects files outside the project.
 fn read_context(
     file: &std::path::Path,
     line: usize,
     context: usize,
     project_root: &std::path::Path,
 ) -> Option<String> {
-    use std::io::BufRead;
-
-    // SECURITY: Validate file is within project boundary
-    let canonical_file = file.canonicalize().ok()?;
-    let canonical_root = project_root.canonicalize().ok()?;
-
-    if !canonical_file.starts_with(&canonical_root) {
-        tracing::warn!(
-            "Rejected file read outside project boundary: {} (project: {})",
-            file.display(),
-            project_root.display()
-        );
-        return None;
-    }
-
-    let f = std::fs::File::open(file).ok()?;
-    let reader = std::io::BufReader::new(f);
-    let lines: Vec<String> = reader.lines().map_while(Result::ok).collect();
+    let lines = sandbox::read_lines(file, project_root)?;
 
     let start = line.saturating_sub(context + 1);
     let end = (line + context).min(lines.len());
@@ -4,6 +4,8 @@ use rmcp::model::{CallToolResult, Content};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
+use rocketindex::Confidence;
+
 use crate::mcp::format::to_relative_path;
 use crate::mcp::ProjectManager;
 
@@ -18,6 +20,9 @@ pub struct AnalyzeDepsInput {
     /// Reverse direction (find what leads TO this symbol instead of FROM)
     #[serde(default)]
     pub reverse: bool,
+    /// Only follow edges at or above this confidence (default: follow all)
+    #[serde(default)]
+    pub min_confidence: Option<Confidence>,
     /// Optional project root
     pub project_root: Option<String>,
 }
@@ -34,6 +39,15 @@ pub struct DependencyNode {
     pub file: String,
     pub line: u32,
     pub depth: usize,
+    pub confidence: Confidence,
+}
+
+/// Unresolved names attributed to one package
+#[derive(Debug, Serialize)]
+pub struct ExternalDependencies {
+    /// `None` for names no package claims
+    pub package: Option<String>,
+    pub symbols: Vec<String>,
 }
 
 /// Output for analyze_dependencies tool
@@ -43,7 +57,9 @@ pub struct DependencyGraph {
     pub direction: String,
     pub max_depth: usize,
     pub nodes: Vec<DependencyNode>,
-    pub unresolved: Vec<String>,
+    pub external_nodes: Vec<ExternalDependencies>,
+    /// Whether the traversal stopped at its node cap with more left to visit
+    pub truncated: bool,
     pub project_root: String,
 }
 
@@ -65,17 +81,36 @@ pub async fn analyze_dependencies(
 
     let mut all_results = Vec::new();
 
-    for root in project_roots {
+    for root in project_roots.clone() {
         let result = manager
             .with_project(&root, |state| {
-                use rocketindex::spider::{reverse_spider, spider};
+                use rocketindex::spider::{reverse_spider_with_confidence, spider_with_confidence};
 
+                let min_confidence = input.min_confidence.unwrap_or(Confidence::StringMatch);
                 let tree = if input.reverse {
-                    reverse_spider(&state.code_index, &input.symbol, input.depth)
+                    reverse_spider_with_confidence(
+                        &state.code_index,
+                        &input.symbol,
+                        input.depth,
+                        min_confidence,
+                    )
                 } else {
-                    spider(&state.code_index, &input.symbol, input.depth)
+                    spider_with_confidence(
+                        &state.code_index,
+                        &input.symbol,
+                        input.depth,
+                        min_confidence,
+                    )
                 };
 
+                let external_nodes = tree
+                    .external_by_package()
+                    .into_iter()
+                    .map(|group| ExternalDependencies {
+                        package: group.package.map(str::to_string),
+                        symbols: group.nodes.iter().map(|n| n.name.clone()).collect(),
+                    })
+                    .collect();
                 let nodes: Vec<DependencyNode> = tree
                     .nodes
                     .into_iter()
@@ -85,6 +120,7 @@ pub async fn analyze_dependencies(
                         file: to_relative_path(&n.symbol.location.file, &root),
                         line: n.symbol.location.line,
                         depth: n.depth,
+                        confidence: n.confidence,
                     })
                     .collect();
 
@@ -97,14 +133,15 @@ pub async fn analyze_dependencies(
                     },
                     max_depth: input.depth,
                     nodes,
-                    unresolved: tree.unresolved,
+                    external_nodes,
+                    truncated: tree.truncated,
                     project_root: root.display().to_string(),
                 }
             })
             .await;
 
         if let Some(graph) = result {
-            if !graph.nodes.is_empty() || !graph.unresolved.is_empty() {
+            if !graph.nodes.is_empty() || !graph.external_nodes.is_empty() {
                 all_results.push(graph);
             }
         }
@@ -112,13 +149,14 @@ pub async fn analyze_dependencies(
 
     if all_results.is_empty() {
         return CallToolResult::success(vec![Content::text(format!(
-            "No dependencies found for '{}'. The symbol may not exist or have no {}.",
+            "No dependencies found for '{}'. The symbol may not exist or have no {}.{}",
             input.symbol,
             if input.reverse {
                 "callers"
             } else {
                 "dependencies"
-            }
+            },
+            super::did_you_mean(&manager, &project_roots, &input.symbol).await
         ))]);
     }
 
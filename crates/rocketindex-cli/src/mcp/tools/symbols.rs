@@ -58,13 +58,14 @@ pub async fn search_symbols(
         let result = manager
             .with_project(&root, |state| {
                 if input.fuzzy {
-                    // Fuzzy search returns (Symbol, score)
+                    // Fuzzy search returns matches best first
                     state
                         .sqlite
                         .fuzzy_search(&input.pattern, 2, input.limit, input.language.as_deref())
                         .unwrap_or_default()
                         .into_iter()
-                        .map(|(s, _score)| SymbolInfo {
+                        .map(|m| m.symbol)
+                        .map(|s| SymbolInfo {
                             qualified: s.qualified,
                             name: s.name,
                             kind: format!("{:?}", s.kind),
@@ -0,0 +1,211 @@
+//! Registering `rkt serve` with MCP clients.
+//!
+//! Each client keeps its MCP servers under a different key of a JSON config
+//! file; most of them read a project-level file, Claude Desktop only a
+//! per-user one. Registration adds a `rocket-index` entry and leaves every
+//! other setting alone.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde_json::{json, Map, Value};
+
+/// Name the server is registered under
+pub const SERVER_NAME: &str = "rocket-index";
+
+/// An MCP client `rkt setup` knows how to configure
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Client {
+    ClaudeCode,
+    ClaudeDesktop,
+    Cursor,
+    Zed,
+    Gemini,
+    Copilot,
+}
+
+impl Client {
+    /// Clients configured through a file in the project
+    pub const PROJECT: [Client; 5] = [
+        Client::ClaudeCode,
+        Client::Cursor,
+        Client::Zed,
+        Client::Gemini,
+        Client::Copilot,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Client::ClaudeCode => "Claude Code",
+            Client::ClaudeDesktop => "Claude Desktop",
+            Client::Cursor => "Cursor",
+            Client::Zed => "Zed",
+            Client::Gemini => "Gemini CLI",
+            Client::Copilot => "GitHub Copilot",
+        }
+    }
+
+    /// The config file to register in; `None` if the user config directory is unknown
+    pub fn config_path(self, root: &Path) -> Option<PathBuf> {
+        match self {
+            Client::ClaudeCode => Some(root.join(".mcp.json")),
+            Client::ClaudeDesktop => {
+                dirs::config_dir().map(|d| d.join("Claude").join("claude_desktop_config.json"))
+            }
+            Client::Cursor => Some(root.join(".cursor").join("mcp.json")),
+            Client::Zed => Some(root.join(".zed").join("settings.json")),
+            Client::Gemini => Some(root.join(".gemini").join("settings.json")),
+            Client::Copilot => Some(root.join(".vscode").join("mcp.json")),
+        }
+    }
+
+    fn servers_key(self) -> &'static str {
+        match self {
+            Client::Zed => "context_servers",
+            Client::Copilot => "servers",
+            _ => "mcpServers",
+        }
+    }
+
+    fn entry(self) -> Value {
+        match self {
+            Client::Copilot => json!({ "type": "stdio", "command": "rkt", "args": ["serve"] }),
+            _ => json!({ "command": "rkt", "args": ["serve"] }),
+        }
+    }
+}
+
+/// Add `rkt serve` to `client`'s config under `root`.
+///
+/// Returns the config path if it was written, `None` if the server was
+/// already registered.
+pub fn register(client: Client, root: &Path) -> Result<Option<PathBuf>> {
+    let path = client
+        .config_path(root)
+        .with_context(|| format!("Can't locate the {} config directory", client.name()))?;
+    let mut config = read_config(&path)?;
+
+    let Some(object) = config.as_object_mut() else {
+        anyhow::bail!("{} is not a JSON object", path.display());
+    };
+    let servers = object
+        .entry(client.servers_key())
+        .or_insert_with(|| Value::Object(Map::new()));
+    let Some(servers) = servers.as_object_mut() else {
+        anyhow::bail!(
+            "\"{}\" in {} is not an object",
+            client.servers_key(),
+            path.display()
+        );
+    };
+    if servers.contains_key(SERVER_NAME) {
+        return Ok(None);
+    }
+    servers.insert(SERVER_NAME.to_string(), client.entry());
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, serde_json::to_string_pretty(&config)? + "\n")
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(Some(path))
+}
+
+/// `content` without the `rkt serve` registration, or `None` if it has none.
+///
+/// Returns an empty string when nothing else was configured, so the caller
+/// can remove the file.
+pub fn unregister(client: Client, content: &str) -> Option<String> {
+    let mut config: Value = serde_json::from_str(content).ok()?;
+    let object = config.as_object_mut()?;
+    let servers = object.get_mut(client.servers_key())?.as_object_mut()?;
+    servers.remove(SERVER_NAME)?;
+    if servers.is_empty() {
+        object.remove(client.servers_key());
+    }
+    if object.is_empty() {
+        return Some(String::new());
+    }
+    Some(serde_json::to_string_pretty(&config).ok()? + "\n")
+}
+
+fn read_config(path: &Path) -> Result<Value> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) if !content.trim().is_empty() => content,
+        Ok(_) => return Ok(Value::Object(Map::new())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Value::Object(Map::new())),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    // Zed settings allow comments; don't risk rewriting a file we can't round-trip
+    serde_json::from_str(&content).with_context(|| {
+        format!(
+            "Can't parse {} as plain JSON; add \"{}\": {{ \"command\": \"rkt\", \"args\": [\"serve\"] }} by hand",
+            path.display(),
+            SERVER_NAME
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_register_keeps_existing_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".zed/settings.json");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(
+            &path,
+            r#"{ "tab_size": 4, "context_servers": { "other": {} } }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            register(Client::Zed, dir.path()).unwrap(),
+            Some(path.clone())
+        );
+        let config: Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(config["tab_size"], 4);
+        assert!(config["context_servers"]["other"].is_object());
+        assert_eq!(config["context_servers"][SERVER_NAME]["command"], "rkt");
+
+        // Registering twice is a no-op
+        assert_eq!(register(Client::Zed, dir.path()).unwrap(), None);
+    }
+
+    #[test]
+    fn test_register_creates_client_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = register(Client::Copilot, dir.path()).unwrap().unwrap();
+        assert_eq!(path, dir.path().join(".vscode/mcp.json"));
+        let config: Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(config["servers"][SERVER_NAME]["type"], "stdio");
+    }
+
+    #[test]
+    fn test_register_refuses_unparseable_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".zed/settings.json");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, "// my settings\n{}\n").unwrap();
+        assert!(register(Client::Zed, dir.path()).is_err());
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "// my settings\n{}\n"
+        );
+    }
+
+    #[test]
+    fn test_unregister() {
+        let only_ours = r#"{ "mcpServers": { "rocket-index": { "command": "rkt" } } }"#;
+        assert_eq!(unregister(Client::Cursor, only_ours).unwrap(), "");
+
+        let shared = r#"{ "mcpServers": { "rocket-index": {}, "other": {} } }"#;
+        let rest: Value =
+            serde_json::from_str(&unregister(Client::Cursor, shared).unwrap()).unwrap();
+        assert_eq!(rest, json!({ "mcpServers": { "other": {} } }));
+
+        assert!(unregister(Client::Cursor, r#"{ "mcpServers": {} }"#).is_none());
+    }
+}
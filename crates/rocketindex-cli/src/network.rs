@@ -0,0 +1,48 @@
+//! Network access, as limited by `rkt --offline` and `rkt --timeout`.
+//!
+//! Update checks, `rkt update`, pulling and publishing prebuilt indexes over
+//! HTTP and fetching agent packs all go through here. Offline, each fails
+//! with an error naming what needed the network, and the update notice is
+//! skipped, so nothing leaves the machine in an air-gapped environment.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use anyhow::Result;
+
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+static TIMEOUT: OnceLock<Duration> = OnceLock::new();
+
+/// Turn off all network access for the rest of the run.
+pub fn set_offline(offline: bool) {
+    OFFLINE.store(offline, Ordering::Relaxed);
+}
+
+pub fn is_offline() -> bool {
+    OFFLINE.load(Ordering::Relaxed)
+}
+
+/// Limit each HTTP request to `timeout`. Only the first call has an effect.
+pub fn set_timeout(timeout: Duration) {
+    let _ = TIMEOUT.set(timeout);
+}
+
+/// Fail if running offline; `purpose` says what needed the network.
+pub fn ensure_online(purpose: &str) -> Result<()> {
+    if is_offline() {
+        anyhow::bail!("Cannot {} with --offline", purpose);
+    }
+    Ok(())
+}
+
+/// An HTTP agent with the timeout applied, or an error if running offline.
+pub fn agent(purpose: &str) -> Result<ureq::Agent> {
+    ensure_online(purpose)?;
+    let mut builder = ureq::AgentBuilder::new().user_agent("rocketindex-cli");
+    if let Some(timeout) = TIMEOUT.get() {
+        builder = builder.timeout(*timeout);
+    }
+    Ok(builder.build())
+}
@@ -0,0 +1,134 @@
+//! Notices: warnings, hints and status that aren't part of a command's
+//! result.
+//!
+//! Results go to stdout and notices to stderr, so an agent reading stdout
+//! never has to pick one out of the other. With `--format json` each notice
+//! is a JSON object on a line of its own:
+//!
+//! ```text
+//! {"files":2,"level":"info","message":"Refreshed 2 stale file(s)","notice":"IndexRefreshed"}
+//! ```
+//!
+//! `notice` names what happened and stays stable; `level` is `info` or
+//! `warning`; `message` is what text output prints, as `Note: ...` or
+//! `Warning: ...`. Other fields depend on the notice. `--quiet` silences
+//! them all.
+
+use std::sync::OnceLock;
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// Whether this run prints notices as JSON
+static JSON: OnceLock<bool> = OnceLock::new();
+
+/// Whether this run prints no notices (`--quiet`)
+static QUIET: OnceLock<bool> = OnceLock::new();
+
+/// Set how this run prints notices, once, before any are emitted.
+pub fn init(json: bool, quiet: bool) {
+    let _ = JSON.set(json);
+    let _ = QUIET.set(quiet);
+}
+
+/// How much a notice matters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Info,
+    Warning,
+}
+
+/// A notice with nothing to act on.
+pub fn info(kind: &'static str, message: impl Into<String>) -> Notice {
+    Notice::new(kind, Level::Info, message.into())
+}
+
+/// A notice about something that may make the result incomplete or wrong.
+pub fn warning(kind: &'static str, message: impl Into<String>) -> Notice {
+    Notice::new(kind, Level::Warning, message.into())
+}
+
+/// One notice, built up and then emitted.
+#[derive(Debug, Clone)]
+#[must_use = "a notice does nothing until emitted"]
+pub struct Notice {
+    kind: &'static str,
+    level: Level,
+    message: String,
+    fields: Map<String, Value>,
+}
+
+impl Notice {
+    fn new(kind: &'static str, level: Level, message: String) -> Self {
+        Self {
+            kind,
+            level,
+            message,
+            fields: Map::new(),
+        }
+    }
+
+    /// Add a field to the JSON form.
+    pub fn with(mut self, key: &str, value: impl Serialize) -> Self {
+        self.fields.insert(
+            key.to_string(),
+            serde_json::to_value(value).unwrap_or(Value::Null),
+        );
+        self
+    }
+
+    /// Print to stderr as this run prints notices.
+    pub fn emit(self) {
+        if QUIET.get() == Some(&true) {
+            return;
+        }
+        if JSON.get() == Some(&true) {
+            eprintln!("{}", self.to_json());
+        } else {
+            eprintln!("{}", self.to_text());
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        let mut object = Map::new();
+        object.insert("notice".to_string(), self.kind.into());
+        object.insert(
+            "level".to_string(),
+            serde_json::to_value(self.level).unwrap_or(Value::Null),
+        );
+        object.insert("message".to_string(), self.message.clone().into());
+        for (key, value) in &self.fields {
+            object.insert(key.clone(), value.clone());
+        }
+        Value::Object(object)
+    }
+
+    fn to_text(&self) -> String {
+        match self.level {
+            Level::Info => format!("Note: {}", self.message),
+            Level::Warning => format!("Warning: {}", self.message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_as_json_line_or_prefixed_text() {
+        let notice = warning("RefreshBudgetSpent", "3 of 5 file(s) still stale")
+            .with("stale", 3)
+            .with("total", 5);
+        assert_eq!(
+            notice.to_json().to_string(),
+            r#"{"level":"warning","message":"3 of 5 file(s) still stale","notice":"RefreshBudgetSpent","stale":3,"total":5}"#
+        );
+        assert_eq!(notice.to_text(), "Warning: 3 of 5 file(s) still stale");
+        assert_eq!(
+            info("IndexRefreshed", "Refreshed 1 stale file(s)").to_text(),
+            "Note: Refreshed 1 stale file(s)"
+        );
+    }
+}
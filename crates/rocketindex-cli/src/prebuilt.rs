@@ -0,0 +1,175 @@
+//! Prebuilt indexes: `rkt index --publish <url>` and `rkt index --pull <url>`.
+//!
+//! A published index is a compressed pack named after the commit it was
+//! built at, with a [`PublishedIndex`] beside it (see [`rocketindex::pack`]).
+//! Locations are `http(s)://` URLs, written with PUT and read with GET, or
+//! directories (optionally as `file://` URLs) such as a shared cache mount.
+//!
+//! Pulling looks for HEAD's index first, then its first-parent ancestors, so
+//! a clone a few commits ahead of the last published build still starts
+//! warm; the caller then reindexes what changed since that commit.
+
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use rocketindex::db::{DEFAULT_DB_NAME, SCHEMA_VERSION};
+use rocketindex::pack::{self, PublishedIndex};
+use rocketindex::watch::is_supported_file;
+use rocketindex::{git, SqliteIndex};
+use rocketindex_cli::network;
+
+/// How many commits back from HEAD `--pull` looks for a published index.
+pub const MAX_ANCESTORS: usize = 50;
+
+/// An index restored by [`pull`].
+pub struct Pulled {
+    pub index: SqliteIndex,
+    pub published: PublishedIndex,
+    /// Commits between the one the index was built at and HEAD
+    pub behind: usize,
+}
+
+/// Publish the index of the checkout at `root` for its HEAD commit.
+///
+/// The working tree must be clean, or the index wouldn't match the commit
+/// it's published for.
+pub fn publish(root: &Path, location: &str) -> Result<PublishedIndex> {
+    let db_path = root.join(".rocketindex").join(DEFAULT_DB_NAME);
+    let index = SqliteIndex::open(&db_path).context("Failed to open index")?;
+    let commit = git::head_commit(root)?;
+    let dirty = git::working_tree_changes(root)?
+        .into_iter()
+        .filter(|path| is_supported_file(path))
+        .count();
+    if dirty > 0 {
+        anyhow::bail!(
+            "{} source files have uncommitted changes; publish from a clean checkout so the index matches {}",
+            dirty,
+            commit
+        );
+    }
+
+    let staging = root
+        .join(".rocketindex")
+        .join(format!("publish-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&staging);
+    let packed = pack::pack_index(&index, &staging, env!("RKT_VERSION"))
+        .and_then(|_| pack::compress_pack(&staging, &commit));
+    let _ = std::fs::remove_dir_all(&staging);
+    let (artifact, published) = packed.context("Failed to pack index")?;
+
+    // The manifest goes last, so readers never find one without its artifact
+    store(location, &pack::artifact_name(&commit), &artifact)?;
+    store(
+        location,
+        &pack::published_manifest_name(&commit),
+        &serde_json::to_vec_pretty(&published)?,
+    )?;
+    Ok(published)
+}
+
+/// Restore the newest index published for HEAD or one of its ancestors into
+/// the checkout at `root`. `None` when there isn't one to use.
+///
+/// The caller holds the write lock for the index.
+pub fn pull(root: &Path, location: &str) -> Result<Option<Pulled>> {
+    let db_path = root.join(".rocketindex").join(DEFAULT_DB_NAME);
+    for (behind, commit) in git::recent_commits(root, MAX_ANCESTORS)?
+        .into_iter()
+        .enumerate()
+    {
+        let Some(manifest) = fetch(location, &pack::published_manifest_name(&commit))? else {
+            continue;
+        };
+        let published: PublishedIndex = serde_json::from_slice(&manifest)
+            .with_context(|| format!("Invalid index manifest for {}", commit))?;
+        // The manifest names the commit later handed to git, so it has to be
+        // the one it was fetched for
+        if published.commit != commit {
+            anyhow::bail!(
+                "Index manifest for {} claims to be for commit '{}'",
+                commit,
+                published.commit
+            );
+        }
+        if published.pack.schema_version != SCHEMA_VERSION {
+            tracing::warn!(
+                "Skipping index published for {}: schema v{} (this version uses v{})",
+                commit,
+                published.pack.schema_version,
+                SCHEMA_VERSION
+            );
+            continue;
+        }
+
+        let artifact = fetch(location, &pack::artifact_name(&commit))?
+            .with_context(|| format!("Index artifact for {} is missing", commit))?;
+        std::fs::create_dir_all(root.join(".rocketindex"))?;
+        let index = pack::restore_published(&artifact, &published, &db_path, root)
+            .with_context(|| format!("Failed to restore index published for {}", commit))?;
+        return Ok(Some(Pulled {
+            index,
+            published,
+            behind,
+        }));
+    }
+    Ok(None)
+}
+
+/// Directory a location names, unless it's an HTTP URL.
+fn as_directory(location: &str) -> Option<PathBuf> {
+    if location.starts_with("http://") || location.starts_with("https://") {
+        return None;
+    }
+    Some(PathBuf::from(
+        location.strip_prefix("file://").unwrap_or(location),
+    ))
+}
+
+fn url_of(location: &str, name: &str) -> String {
+    format!("{}/{}", location.trim_end_matches('/'), name)
+}
+
+/// Read `name` from a location; `None` when it doesn't exist there.
+fn fetch(location: &str, name: &str) -> Result<Option<Vec<u8>>> {
+    if let Some(dir) = as_directory(location) {
+        let path = dir.join(name);
+        return match std::fs::read(&path) {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+    }
+
+    let url = url_of(location, name);
+    match network::agent("pull a prebuilt index")?.get(&url).call() {
+        Ok(response) => {
+            let mut bytes = Vec::new();
+            response.into_reader().read_to_end(&mut bytes)?;
+            Ok(Some(bytes))
+        }
+        Err(ureq::Error::Status(404, _)) => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("Failed to download {}", url)),
+    }
+}
+
+/// Write `name` to a location.
+fn store(location: &str, name: &str, bytes: &[u8]) -> Result<()> {
+    if let Some(dir) = as_directory(location) {
+        std::fs::create_dir_all(&dir)?;
+        // Write then rename, so a concurrent pull never reads half a file
+        let partial = dir.join(format!("{}.partial", name));
+        std::fs::write(&partial, bytes)
+            .and_then(|_| std::fs::rename(&partial, dir.join(name)))
+            .with_context(|| format!("Failed to write {}", dir.join(name).display()))?;
+        return Ok(());
+    }
+
+    let url = url_of(location, name);
+    network::agent("publish the index")?
+        .put(&url)
+        .send_bytes(bytes)
+        .with_context(|| format!("Failed to upload {}", url))?;
+    Ok(())
+}
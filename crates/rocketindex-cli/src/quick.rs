@@ -0,0 +1,149 @@
+//! Quick bootstrap for large repositories: `rkt index --quick`.
+//!
+//! Indexes the files touched by the last N commits and the working tree,
+//! plus the files they import, so queries about the code being worked on
+//! answer within seconds. A background `rkt index` then indexes the rest
+//! through the batch processor, a chunk per transaction, so the index fills
+//! in while it's used. Until that finishes, queries refresh the files
+//! already indexed but leave the rest to it.
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+use rocketindex::{git, pidfile, SqliteIndex};
+
+/// Metadata key holding the PID of the background process completing a
+/// quick index; absent once the index is complete
+pub const PENDING_KEY: &str = "quick_pending";
+
+/// Log of the background process, under `.rocketindex/`
+pub const LOG: &str = "logs/index.log";
+
+/// Most files a single import may pull in; names like `utils` or `index`
+/// match too many files to say which one is meant
+const MAX_FILES_PER_IMPORT: usize = 5;
+
+/// Files to index first: those among `all_files` changed by the last
+/// `commits` commits or in the working tree, and the files they import.
+pub fn sample(
+    root: &Path,
+    all_files: &[PathBuf],
+    commits: usize,
+    max_depth: usize,
+) -> Vec<PathBuf> {
+    let mut touched: Vec<PathBuf> = git::working_tree_changes(root).unwrap_or_default();
+    if let Some(oldest) = git::recent_commits(root, commits)
+        .ok()
+        .and_then(|commits| commits.last().cloned())
+    {
+        // Diff from the oldest commit's parent, so its own changes count;
+        // the first commit in a repository has none
+        let changed = git::changed_since(root, &format!("{}^", oldest))
+            .or_else(|_| git::changed_since(root, &oldest));
+        touched.extend(changed.unwrap_or_default());
+    }
+
+    let workspace: BTreeSet<&PathBuf> = all_files.iter().collect();
+    let mut sample: BTreeSet<PathBuf> = touched
+        .into_iter()
+        .filter(|path| workspace.contains(path))
+        .collect();
+
+    let imports: Vec<String> = sample
+        .iter()
+        .filter_map(|file| {
+            let source = std::fs::read_to_string(file).ok()?;
+            Some(rocketindex::extract_symbols(file, &source, max_depth).opens)
+        })
+        .flatten()
+        .collect();
+    sample.extend(imported_files(&imports, all_files));
+    sample.into_iter().collect()
+}
+
+/// Files `imports` probably name, matching the last two segments of each
+/// (`MyApp.Utils`, `./utils`, `crate::utils::parse`) against file stems.
+fn imported_files(imports: &[String], all_files: &[PathBuf]) -> Vec<PathBuf> {
+    let mut by_stem: HashMap<String, Vec<&PathBuf>> = HashMap::new();
+    for file in all_files {
+        if let Some(stem) = file.file_stem().and_then(|s| s.to_str()) {
+            by_stem.entry(stem.to_lowercase()).or_default().push(file);
+        }
+    }
+
+    let mut files = BTreeSet::new();
+    for import in imports {
+        let segments: Vec<String> = import
+            .split(['.', '/', '\\', ':'])
+            .filter(|segment| !segment.is_empty() && *segment != "*")
+            .map(str::to_lowercase)
+            .collect();
+        for segment in segments.iter().rev().take(2) {
+            match by_stem.get(segment) {
+                Some(matches) if matches.len() <= MAX_FILES_PER_IMPORT => {
+                    files.extend(matches.iter().map(|file| (*file).clone()));
+                }
+                _ => {}
+            }
+        }
+    }
+    files.into_iter().collect()
+}
+
+/// Whether a background process is still completing the quick index
+/// behind `index`.
+pub fn pending(index: &SqliteIndex) -> bool {
+    index
+        .get_metadata(PENDING_KEY)
+        .ok()
+        .flatten()
+        .and_then(|pid| pid.parse::<u32>().ok())
+        .is_some_and(pidfile::is_process_alive)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn imports_match_file_stems() {
+        let all_files: Vec<PathBuf> = [
+            "/ws/src/Utils.fs",
+            "/ws/src/billing/helpers.py",
+            "/ws/web/util.ts",
+            "/ws/crates/core/src/db.rs",
+            "/ws/src/App.fs",
+        ]
+        .into_iter()
+        .map(PathBuf::from)
+        .collect();
+        let imports: Vec<String> = [
+            "MyApp.Utils",
+            "helpers",
+            "./util",
+            "crate::db::Index",
+            "System",
+        ]
+        .into_iter()
+        .map(String::from)
+        .collect();
+
+        assert_eq!(
+            imported_files(&imports, &all_files),
+            vec![
+                PathBuf::from("/ws/crates/core/src/db.rs"),
+                PathBuf::from("/ws/src/Utils.fs"),
+                PathBuf::from("/ws/src/billing/helpers.py"),
+                PathBuf::from("/ws/web/util.ts"),
+            ]
+        );
+    }
+
+    #[test]
+    fn common_names_pull_in_nothing() {
+        let all_files: Vec<PathBuf> = (0..=MAX_FILES_PER_IMPORT)
+            .map(|i| PathBuf::from(format!("/ws/pkg{}/index.ts", i)))
+            .collect();
+        assert!(imported_files(&["./index".to_string()], &all_files).is_empty());
+    }
+}
@@ -0,0 +1,373 @@
+//! `rkt daemon install|uninstall`: keep the daemon running across reboots.
+//!
+//! The daemon is registered with the user's own service manager: a launchd
+//! agent on macOS, a systemd user unit on Linux and a logon task in Task
+//! Scheduler on Windows. (A real Windows service would run outside the user's
+//! session, under another account; a logon task is the per-user equivalent.)
+//! Each workspace gets its own service, named after its directory plus a hash
+//! of its path, so several checkouts can each have one.
+//!
+//! A clean exit (`rkt stop`) leaves the service stopped until the next login;
+//! launchd and systemd restart it only when it crashes.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{Context, Result};
+
+/// Service managers `rkt daemon install` can register with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Manager {
+    Launchd,
+    Systemd,
+    TaskScheduler,
+}
+
+impl Manager {
+    /// The service manager of this platform, if it has one we support
+    pub fn current() -> Option<Self> {
+        if cfg!(target_os = "macos") {
+            Some(Manager::Launchd)
+        } else if cfg!(target_os = "linux") {
+            Some(Manager::Systemd)
+        } else if cfg!(windows) {
+            Some(Manager::TaskScheduler)
+        } else {
+            None
+        }
+    }
+}
+
+/// The daemon service for one workspace
+#[derive(Debug, Clone)]
+pub struct Service {
+    pub manager: Manager,
+    pub root: PathBuf,
+    /// The `rkt` binary the service runs
+    pub exe: PathBuf,
+    /// Where the daemon's output goes (launchd and systemd only)
+    pub log: PathBuf,
+}
+
+impl Service {
+    /// The daemon service for the workspace at `root` (absolute), running `exe`.
+    pub fn new(manager: Manager, root: PathBuf, exe: PathBuf) -> Self {
+        let log = root.join(".rocketindex").join(crate::WATCH_LOG);
+        Self {
+            manager,
+            root,
+            exe,
+            log,
+        }
+    }
+
+    /// Name the service is registered under
+    pub fn name(&self) -> String {
+        let dir = self
+            .root
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let slug: String = dir
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_lowercase()
+                } else {
+                    '-'
+                }
+            })
+            .collect();
+        let id = format!("{}-{:08x}", slug.trim_matches('-'), path_hash(&self.root));
+        match self.manager {
+            Manager::Launchd => format!("dev.rocketindex.daemon.{}", id),
+            Manager::Systemd => format!("rocketindex-daemon-{}.service", id),
+            Manager::TaskScheduler => format!("RocketIndex\\daemon-{}", id),
+        }
+    }
+
+    /// The command line the service runs
+    fn args(&self) -> Vec<String> {
+        vec![
+            self.exe.display().to_string(),
+            "daemon".to_string(),
+            "--root".to_string(),
+            self.root.display().to_string(),
+            "--format".to_string(),
+            "text".to_string(),
+        ]
+    }
+
+    /// File the service is defined in; Task Scheduler keeps tasks itself.
+    pub fn definition_path(&self) -> Result<Option<PathBuf>> {
+        Ok(match self.manager {
+            Manager::Launchd => Some(
+                dirs::home_dir()
+                    .context("Could not determine home directory")?
+                    .join("Library/LaunchAgents")
+                    .join(format!("{}.plist", self.name())),
+            ),
+            Manager::Systemd => Some(
+                dirs::config_dir()
+                    .context("Could not determine config directory")?
+                    .join("systemd/user")
+                    .join(self.name()),
+            ),
+            Manager::TaskScheduler => None,
+        })
+    }
+
+    /// launchd agent definition
+    pub fn launchd_plist(&self) -> String {
+        let args: String = self
+            .args()
+            .iter()
+            .map(|arg| format!("        <string>{}</string>\n", xml_escape(arg)))
+            .collect();
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{label}</string>
+    <key>ProgramArguments</key>
+    <array>
+{args}    </array>
+    <key>WorkingDirectory</key>
+    <string>{root}</string>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>KeepAlive</key>
+    <dict>
+        <key>SuccessfulExit</key>
+        <false/>
+    </dict>
+    <key>StandardOutPath</key>
+    <string>{log}</string>
+    <key>StandardErrorPath</key>
+    <string>{log}</string>
+</dict>
+</plist>
+"#,
+            label = xml_escape(&self.name()),
+            args = args,
+            root = xml_escape(&self.root.display().to_string()),
+            log = xml_escape(&self.log.display().to_string()),
+        )
+    }
+
+    /// systemd user unit
+    pub fn systemd_unit(&self) -> String {
+        let exec: Vec<String> = self.args().iter().map(|arg| systemd_quote(arg)).collect();
+        format!(
+            "[Unit]\n\
+             Description=RocketIndex daemon for {root}\n\
+             \n\
+             [Service]\n\
+             ExecStart={exec}\n\
+             WorkingDirectory={root}\n\
+             Restart=on-failure\n\
+             StandardOutput=append:{log}\n\
+             StandardError=append:{log}\n\
+             \n\
+             [Install]\n\
+             WantedBy=default.target\n",
+            root = self.root.display().to_string().replace('%', "%%"),
+            exec = exec.join(" "),
+            log = self.log.display().to_string().replace('%', "%%"),
+        )
+    }
+
+    /// Write the service definition, register it and start it. Returns the
+    /// definition file, if the manager uses one.
+    pub fn install(&self) -> Result<Option<PathBuf>> {
+        if let Some(dir) = self.log.parent() {
+            std::fs::create_dir_all(dir).context("Failed to create log directory")?;
+        }
+        let path = self.definition_path()?;
+        let name = self.name();
+        match self.manager {
+            Manager::Launchd => {
+                let path = path.as_deref().expect("launchd agents live in a plist");
+                write_definition(path, &self.launchd_plist())?;
+                // Reinstalling replaces a loaded agent
+                let _ = run("launchctl", &["unload".as_ref(), path.as_os_str()]);
+                run(
+                    "launchctl",
+                    &["load".as_ref(), "-w".as_ref(), path.as_os_str()],
+                )?;
+            }
+            Manager::Systemd => {
+                let path = path.as_deref().expect("systemd units live in a file");
+                write_definition(path, &self.systemd_unit())?;
+                run("systemctl", &["--user", "daemon-reload"])?;
+                run("systemctl", &["--user", "enable", &name])?;
+                // Starts it, or picks up a changed unit when reinstalling
+                run("systemctl", &["--user", "restart", &name])?;
+            }
+            Manager::TaskScheduler => {
+                let command: Vec<String> = self
+                    .args()
+                    .iter()
+                    .map(|arg| format!("\"{}\"", arg))
+                    .collect();
+                run(
+                    "schtasks",
+                    &[
+                        "/Create",
+                        "/F",
+                        "/SC",
+                        "ONLOGON",
+                        "/RL",
+                        "LIMITED",
+                        "/TN",
+                        &name,
+                        "/TR",
+                        &command.join(" "),
+                    ],
+                )?;
+                run("schtasks", &["/Run", "/TN", &name])?;
+            }
+        }
+        Ok(path)
+    }
+
+    /// Stop and unregister the service. `false` when it wasn't installed.
+    pub fn uninstall(&self) -> Result<bool> {
+        let name = self.name();
+        match self.manager {
+            Manager::Launchd | Manager::Systemd => {
+                let path = self
+                    .definition_path()?
+                    .expect("launchd and systemd use definition files");
+                if !path.exists() {
+                    return Ok(false);
+                }
+                if self.manager == Manager::Launchd {
+                    run(
+                        "launchctl",
+                        &["unload".as_ref(), "-w".as_ref(), path.as_os_str()],
+                    )?;
+                } else {
+                    run("systemctl", &["--user", "disable", "--now", &name])?;
+                }
+                std::fs::remove_file(&path)
+                    .with_context(|| format!("Failed to remove {}", path.display()))?;
+                if self.manager == Manager::Systemd {
+                    run("systemctl", &["--user", "daemon-reload"])?;
+                }
+            }
+            Manager::TaskScheduler => {
+                if run("schtasks", &["/Query", "/TN", &name]).is_err() {
+                    return Ok(false);
+                }
+                // Not running is fine
+                let _ = run("schtasks", &["/End", "/TN", &name]);
+                run("schtasks", &["/Delete", "/F", "/TN", &name])?;
+            }
+        }
+        Ok(true)
+    }
+}
+
+fn write_definition(path: &Path, content: &str) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    std::fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Run a service manager command, failing with its output if it fails.
+fn run<S: AsRef<std::ffi::OsStr>>(program: &str, args: &[S]) -> Result<()> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .with_context(|| format!("Failed to run {}", program))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+/// FNV-1a of the path: stable across builds, unlike `DefaultHasher`
+fn path_hash(path: &Path) -> u32 {
+    path.to_string_lossy()
+        .bytes()
+        .fold(0x811c_9dc5, |hash, byte| {
+            (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+        })
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Quote a word for a systemd `ExecStart=` line
+fn systemd_quote(s: &str) -> String {
+    format!(
+        "\"{}\"",
+        s.replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('%', "%%")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn service(manager: Manager) -> Service {
+        Service::new(
+            manager,
+            PathBuf::from("/home/dev/Big Mono&Repo"),
+            PathBuf::from("/usr/local/bin/rkt"),
+        )
+    }
+
+    #[test]
+    fn test_names_are_per_workspace() {
+        let a = service(Manager::Systemd);
+        assert!(a.name().starts_with("rocketindex-daemon-big-mono-repo-"));
+        assert!(a.name().ends_with(".service"));
+
+        let mut b = a.clone();
+        b.root = PathBuf::from("/home/other/Big Mono&Repo");
+        assert_ne!(a.name(), b.name());
+        // Stable for the same path
+        assert_eq!(a.name(), service(Manager::Systemd).name());
+    }
+
+    #[test]
+    fn test_launchd_plist() {
+        let plist = service(Manager::Launchd).launchd_plist();
+        assert!(plist.contains("<string>/home/dev/Big Mono&amp;Repo</string>"));
+        assert!(plist.contains("<string>daemon</string>"));
+        assert!(plist.contains("<key>SuccessfulExit</key>"));
+        assert!(plist.contains("Mono&amp;Repo/.rocketindex/logs/watch.log</string>"));
+    }
+
+    #[test]
+    fn test_systemd_unit() {
+        let unit = service(Manager::Systemd).systemd_unit();
+        assert!(unit.contains(
+            "ExecStart=\"/usr/local/bin/rkt\" \"daemon\" \"--root\" \"/home/dev/Big Mono&Repo\""
+        ));
+        // A path setting, not a command line: bare, with `%` escaped
+        assert!(unit.contains("WorkingDirectory=/home/dev/Big Mono&Repo\n"));
+        assert!(unit.contains("Restart=on-failure\n"));
+        assert!(unit.contains("WantedBy=default.target\n"));
+    }
+
+    #[test]
+    fn test_systemd_quote() {
+        assert_eq!(systemd_quote(r#"a "b" 100%"#), r#""a \"b\" 100%%""#);
+    }
+}
@@ -0,0 +1,469 @@
+//! Agent and skill definitions installed by `rkt setup`.
+//!
+//! RocketIndex ships a small set of embedded agents. Organizations can add
+//! their own role packs with `rkt setup claude --pack <dir-or-git-url>`; a
+//! pack is a directory laid out the way Claude Code expects:
+//!
+//! ```text
+//! agents/<name>.md          -> .claude/agents/<name>.md
+//! skills/<name>/SKILL.md    -> .claude/skills/<name>/SKILL.md
+//! ```
+//!
+//! Definitions are templates: `{{project}}`, `{{language}}` and
+//! `{{rkt_version}}` are replaced when they are installed. Installed paths
+//! are recorded in [`MANIFEST`] so `rkt uninstall` can remove them.
+
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{Context, Result};
+use rocketindex::subprocess;
+use rocketindex_cli::network;
+
+/// Definitions installed into a project, one relative path per line
+pub const MANIFEST: &str = ".rocketindex/installed-definitions";
+
+/// An embedded agent definition
+pub struct EmbeddedAgent {
+    /// File stem of the installed definition
+    pub name: &'static str,
+    /// One-line summary for the selection UI
+    pub description: &'static str,
+    /// Full definition, including frontmatter
+    pub content: &'static str,
+}
+
+/// Agents that ship with RocketIndex
+pub const AGENTS: &[EmbeddedAgent] = &[EmbeddedAgent {
+    name: "code-navigator",
+    description: "Answers \"where is / who calls / what breaks\" questions with rkt",
+    content: r#"---
+name: code-navigator
+description: Answers questions about where code lives in {{project}}, who calls it and what a change would break. Use before editing unfamiliar code.
+tools: Bash, Read
+---
+
+You navigate the {{project}} codebase ({{language}}) with RocketIndex (`rkt`, v{{rkt_version}}).
+
+Prefer `rkt` over grep for anything structural:
+- `rkt def "Symbol"` to find a definition
+- `rkt callers "Symbol"` for direct callers, `rkt spider "Symbol" --reverse` for everything upstream
+- `rkt refs "Symbol"` for all usages
+- `rkt symbols "pattern*"` when you only know part of a name
+
+Answer with file:line locations and keep excerpts short.
+"#,
+}];
+
+/// What a definition is installed as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DefinitionKind {
+    Agent,
+    Skill,
+}
+
+/// An agent or skill ready to be installed
+#[derive(Debug, Clone)]
+pub struct Definition {
+    pub kind: DefinitionKind,
+    pub name: String,
+    pub description: String,
+    /// Where the definition came from ("embedded" or the pack location)
+    pub source: String,
+    pub content: String,
+}
+
+impl Definition {
+    /// Path of the installed definition, relative to the project root
+    pub fn install_path(&self) -> PathBuf {
+        match self.kind {
+            DefinitionKind::Agent => {
+                PathBuf::from(".claude/agents").join(format!("{}.md", self.name))
+            }
+            DefinitionKind::Skill => PathBuf::from(".claude/skills")
+                .join(&self.name)
+                .join("SKILL.md"),
+        }
+    }
+
+    /// Render the template and write it under `root`.
+    ///
+    /// Returns the written path, or `None` if a definition is already there.
+    pub fn install(&self, root: &Path, vars: &TemplateVars) -> Result<Option<PathBuf>> {
+        let path = root.join(self.install_path());
+        if path.exists() {
+            return Ok(None);
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, vars.render(&self.content))
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        record_installed(root, &self.install_path())?;
+        Ok(Some(path))
+    }
+}
+
+fn record_installed(root: &Path, installed: &Path) -> Result<()> {
+    let manifest = root.join(MANIFEST);
+    let mut paths = std::fs::read_to_string(&manifest).unwrap_or_default();
+    let line = installed.to_string_lossy().replace('\\', "/");
+    if !paths.lines().any(|l| l == line) {
+        paths.push_str(&line);
+        paths.push('\n');
+        if let Some(parent) = manifest.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&manifest, paths)?;
+    }
+    Ok(())
+}
+
+/// Definitions installed under `root`: those in the manifest plus any
+/// embedded agent found where setup would have put it, if it still reads as
+/// setup wrote it.
+///
+/// The manifest can be committed along with the project, so only paths of
+/// definitions (under `.claude/agents/` or `.claude/skills/`) are taken from
+/// it.
+pub fn installed(root: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = std::fs::read_to_string(root.join(MANIFEST))
+        .unwrap_or_default()
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(PathBuf::from)
+        .filter(|p| is_definition_path(p))
+        .collect();
+    for definition in embedded() {
+        let path = definition.install_path();
+        if paths.contains(&path) {
+            continue;
+        }
+        let content = std::fs::read_to_string(root.join(&path)).unwrap_or_default();
+        if matches_template(&definition.content, &content) {
+            paths.push(path);
+        }
+    }
+    paths.retain(|p| root.join(p).is_file());
+    paths
+}
+
+/// Whether `path` is relative, made only of plain names, and inside one of
+/// the directories definitions are installed to
+fn is_definition_path(path: &Path) -> bool {
+    let mut parts = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => parts.push(part),
+            _ => return false,
+        }
+    }
+    parts.len() >= 3 && parts[0] == ".claude" && (parts[1] == "agents" || parts[1] == "skills")
+}
+
+/// Whether `content` could be `template` rendered with some [`TemplateVars`]
+fn matches_template(template: &str, content: &str) -> bool {
+    let literals: Vec<&str> = template
+        .split("{{project}}")
+        .flat_map(|s| s.split("{{language}}"))
+        .flat_map(|s| s.split("{{rkt_version}}"))
+        .collect();
+    let [first, middle @ .., last] = literals.as_slice() else {
+        return content == template;
+    };
+    let Some(mut rest) = content.strip_prefix(first) else {
+        return false;
+    };
+    for literal in middle {
+        match rest.find(literal) {
+            Some(at) => rest = &rest[at + literal.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+/// Values substituted into definitions
+#[derive(Debug, Clone)]
+pub struct TemplateVars {
+    pub project: String,
+    pub language: String,
+    pub rkt_version: String,
+}
+
+impl TemplateVars {
+    /// Replace `{{name}}` placeholders; unknown placeholders are left alone.
+    pub fn render(&self, template: &str) -> String {
+        template
+            .replace("{{project}}", &self.project)
+            .replace("{{language}}", &self.language)
+            .replace("{{rkt_version}}", &self.rkt_version)
+    }
+}
+
+/// The embedded agents as definitions
+pub fn embedded() -> Vec<Definition> {
+    AGENTS
+        .iter()
+        .map(|agent| Definition {
+            kind: DefinitionKind::Agent,
+            name: agent.name.to_string(),
+            description: agent.description.to_string(),
+            source: "embedded".to_string(),
+            content: agent.content.to_string(),
+        })
+        .collect()
+}
+
+/// Load the definitions in a pack, fetching it first if `location` is a git URL.
+pub fn load_pack(location: &str) -> Result<Vec<Definition>> {
+    let dir = if is_git_url(location) {
+        fetch_git_pack(location)?
+    } else {
+        PathBuf::from(location)
+    };
+    if !dir.is_dir() {
+        anyhow::bail!("Agent pack not found: {}", dir.display());
+    }
+
+    let mut definitions = Vec::new();
+
+    let agents_dir = dir.join("agents");
+    for path in sorted_entries(&agents_dir)? {
+        if path.extension().is_some_and(|e| e == "md") {
+            let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+            definitions.push(read_definition(
+                &path,
+                DefinitionKind::Agent,
+                &stem,
+                location,
+            )?);
+        }
+    }
+
+    let skills_dir = dir.join("skills");
+    for path in sorted_entries(&skills_dir)? {
+        let skill_file = path.join("SKILL.md");
+        if skill_file.is_file() {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            definitions.push(read_definition(
+                &skill_file,
+                DefinitionKind::Skill,
+                &name,
+                location,
+            )?);
+        }
+    }
+
+    if definitions.is_empty() {
+        anyhow::bail!(
+            "No agents or skills in {} (expected agents/*.md or skills/*/SKILL.md)",
+            location
+        );
+    }
+    Ok(definitions)
+}
+
+fn sorted_entries(dir: &Path) -> Result<Vec<PathBuf>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut entries = std::fs::read_dir(dir)?
+        .map(|e| e.map(|e| e.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    entries.sort();
+    Ok(entries)
+}
+
+fn read_definition(
+    path: &Path,
+    kind: DefinitionKind,
+    name: &str,
+    source: &str,
+) -> Result<Definition> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let description = frontmatter_field(&content, "description").unwrap_or_default();
+    Ok(Definition {
+        kind,
+        name: name.to_string(),
+        description,
+        source: source.to_string(),
+        content,
+    })
+}
+
+/// Value of a `key: value` line in a leading `---` frontmatter block
+fn frontmatter_field(content: &str, key: &str) -> Option<String> {
+    let mut lines = content.lines();
+    if lines.next()?.trim() != "---" {
+        return None;
+    }
+    lines
+        .take_while(|line| line.trim() != "---")
+        .find_map(|line| {
+            let (k, v) = line.split_once(':')?;
+            (k.trim() == key).then(|| v.trim().trim_matches('"').to_string())
+        })
+}
+
+fn is_git_url(location: &str) -> bool {
+    ["https://", "http://", "ssh://", "git@", "git://"]
+        .iter()
+        .any(|prefix| location.starts_with(prefix))
+        || (location.ends_with(".git") && !Path::new(location).exists())
+}
+
+/// Clone (or fast-forward) a pack repository into the cache directory
+fn fetch_git_pack(url: &str) -> Result<PathBuf> {
+    let slug: String = url
+        .trim_end_matches(".git")
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    let dir = dirs::cache_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("rocketindex")
+        .join("packs")
+        .join(slug);
+
+    network::ensure_online("fetch an agent pack")?;
+    let status = if dir.join(".git").exists() {
+        subprocess::status(Command::new("git").arg("-C").arg(&dir).args([
+            "pull",
+            "--ff-only",
+            "--quiet",
+        ]))
+    } else {
+        std::fs::create_dir_all(dir.parent().unwrap_or(&dir))?;
+        subprocess::status(
+            Command::new("git")
+                // `--` so a URL starting with `-` can't be taken as an option
+                .args(["clone", "--depth", "1", "--quiet", "--", url])
+                .arg(&dir),
+        )
+    }
+    .context("Failed to run git")?;
+
+    if !status.success() {
+        anyhow::bail!("Failed to fetch agent pack from {}", url);
+    }
+    Ok(dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars() -> TemplateVars {
+        TemplateVars {
+            project: "shop".to_string(),
+            language: "Rust".to_string(),
+            rkt_version: "1.0.0".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_load_pack_and_install() {
+        let pack = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(pack.path().join("agents")).unwrap();
+        std::fs::create_dir_all(pack.path().join("skills/triage")).unwrap();
+        std::fs::write(
+            pack.path().join("agents/reviewer.md"),
+            "---\nname: reviewer\ndescription: \"Reviews {{project}} PRs\"\n---\nReview {{language}} code in {{project}}. {{unknown}}\n",
+        )
+        .unwrap();
+        std::fs::write(pack.path().join("agents/notes.txt"), "ignored").unwrap();
+        std::fs::write(pack.path().join("skills/triage/SKILL.md"), "Triage bugs\n").unwrap();
+
+        let definitions = load_pack(pack.path().to_str().unwrap()).unwrap();
+        assert_eq!(definitions.len(), 2);
+        assert_eq!(definitions[0].kind, DefinitionKind::Agent);
+        assert_eq!(definitions[0].name, "reviewer");
+        assert_eq!(definitions[0].description, "Reviews {{project}} PRs");
+        assert_eq!(definitions[1].kind, DefinitionKind::Skill);
+        assert_eq!(
+            definitions[1].install_path(),
+            PathBuf::from(".claude/skills/triage/SKILL.md")
+        );
+
+        let project = tempfile::tempdir().unwrap();
+        let written = definitions[0].install(project.path(), &vars()).unwrap();
+        let written = written.expect("installed");
+        let content = std::fs::read_to_string(written).unwrap();
+        assert!(content.contains("Review Rust code in shop. {{unknown}}"));
+
+        // Existing definitions are left alone
+        assert!(definitions[0]
+            .install(project.path(), &vars())
+            .unwrap()
+            .is_none());
+
+        // Pack definitions are found again through the manifest
+        assert_eq!(
+            installed(project.path()),
+            vec![PathBuf::from(".claude/agents/reviewer.md")]
+        );
+    }
+
+    #[test]
+    fn test_installed_only_trusts_definition_paths() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path();
+        std::fs::create_dir_all(root.join(".rocketindex")).unwrap();
+        std::fs::create_dir_all(root.join(".claude/agents")).unwrap();
+        std::fs::write(root.join("keep.txt"), "mine").unwrap();
+        std::fs::write(root.join(".claude/agents/reviewer.md"), "pack").unwrap();
+        std::fs::write(
+            root.join(MANIFEST),
+            format!(
+                "{}\n../keep.txt\n.claude/agents/../../keep.txt\nkeep.txt\n.claude/agents/reviewer.md\n",
+                root.join("keep.txt").display()
+            ),
+        )
+        .unwrap();
+
+        // A hand-written agent that happens to share an embedded name is kept
+        let navigator = embedded()[0].install_path();
+        std::fs::write(
+            root.join(&navigator),
+            "---\nname: code-navigator\n---\nMine\n",
+        )
+        .unwrap();
+        assert_eq!(
+            installed(root),
+            vec![PathBuf::from(".claude/agents/reviewer.md")]
+        );
+
+        // One setup rendered is found without the manifest
+        std::fs::write(root.join(&navigator), vars().render(&embedded()[0].content)).unwrap();
+        assert_eq!(
+            installed(root),
+            vec![PathBuf::from(".claude/agents/reviewer.md"), navigator]
+        );
+    }
+
+    #[test]
+    fn test_empty_pack_is_an_error() {
+        let pack = tempfile::tempdir().unwrap();
+        assert!(load_pack(pack.path().to_str().unwrap()).is_err());
+        assert!(load_pack("/does/not/exist").is_err());
+    }
+
+    #[test]
+    fn test_git_url_detection() {
+        assert!(is_git_url("https://github.com/acme/agents"));
+        assert!(is_git_url("git@github.com:acme/agents.git"));
+        assert!(!is_git_url("./packs/acme"));
+    }
+
+    #[test]
+    fn test_embedded_agents_have_frontmatter() {
+        for agent in embedded() {
+            assert_eq!(
+                frontmatter_field(&agent.content, "name").as_deref(),
+                Some(agent.name.as_str())
+            );
+        }
+    }
+}
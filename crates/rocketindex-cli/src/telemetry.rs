@@ -0,0 +1,134 @@
+//! Optional OpenTelemetry export of tracing spans.
+//!
+//! Indexing, batch flushes and MCP tool calls are recorded as tracing spans.
+//! When built with the `otel` feature and an OTLP endpoint is configured
+//! through the standard `OTEL_EXPORTER_OTLP_ENDPOINT` (or
+//! `OTEL_EXPORTER_OTLP_TRACES_ENDPOINT`) variable, those spans are exported
+//! over OTLP/HTTP so a centrally run server or watcher can be monitored for
+//! latency and error rates. `OTEL_SERVICE_NAME` overrides the service name.
+//! `rkt --offline` turns export off along with all other network access.
+
+use tracing::Subscriber;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+/// Layer type returned by [`init`].
+pub type BoxedLayer<S> = Box<dyn Layer<S> + Send + Sync + 'static>;
+
+/// Environment variables that turn on export.
+const ENDPOINT_VARS: [&str; 2] = [
+    "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT",
+    "OTEL_EXPORTER_OTLP_ENDPOINT",
+];
+
+/// Flushes exported spans when dropped; keep it alive until the process exits.
+#[derive(Default)]
+pub struct TelemetryGuard {
+    #[cfg(feature = "otel")]
+    provider: Option<opentelemetry_sdk::trace::SdkTracerProvider>,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        #[cfg(feature = "otel")]
+        if let Some(provider) = self.provider.take() {
+            if let Err(e) = provider.shutdown() {
+                eprintln!("Failed to flush OpenTelemetry spans: {}", e);
+            }
+        }
+    }
+}
+
+/// Whether an OTLP endpoint is configured in the environment.
+pub fn export_requested() -> bool {
+    ENDPOINT_VARS
+        .iter()
+        .any(|var| std::env::var_os(var).is_some_and(|v| !v.is_empty()))
+}
+
+/// Build the export layer, if export is requested and compiled in and the
+/// run isn't `offline`.
+///
+/// Errors setting up the exporter are reported on stderr and leave export
+/// off rather than failing the command.
+pub fn init<S>(offline: bool) -> (Option<BoxedLayer<S>>, TelemetryGuard)
+where
+    S: Subscriber + for<'a> LookupSpan<'a> + Send + Sync,
+{
+    if offline || !export_requested() {
+        return (None, TelemetryGuard::default());
+    }
+
+    #[cfg(feature = "otel")]
+    {
+        match otlp::layer() {
+            Ok((layer, provider)) => (
+                Some(layer),
+                TelemetryGuard {
+                    provider: Some(provider),
+                },
+            ),
+            Err(e) => {
+                eprintln!("OpenTelemetry export disabled: {}", e);
+                (None, TelemetryGuard::default())
+            }
+        }
+    }
+
+    #[cfg(not(feature = "otel"))]
+    {
+        eprintln!(
+            "OpenTelemetry export requested, but this build of rkt was compiled without the `otel` feature"
+        );
+        (None, TelemetryGuard::default())
+    }
+}
+
+#[cfg(feature = "otel")]
+mod otlp {
+    use super::BoxedLayer;
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry_sdk::trace::SdkTracerProvider;
+    use opentelemetry_sdk::Resource;
+    use tracing::Subscriber;
+    use tracing_subscriber::filter::{LevelFilter, Targets};
+    use tracing_subscriber::registry::LookupSpan;
+    use tracing_subscriber::Layer;
+
+    pub(super) fn layer<S>() -> anyhow::Result<(BoxedLayer<S>, SdkTracerProvider)>
+    where
+        S: Subscriber + for<'a> LookupSpan<'a> + Send + Sync,
+    {
+        // Endpoint, headers and timeouts come from the standard OTEL_* variables
+        let exporter = opentelemetry_otlp::SpanExporter::builder()
+            .with_http()
+            .build()?;
+
+        let service_name =
+            std::env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| "rocketindex".to_string());
+        let provider = SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_resource(
+                Resource::builder()
+                    .with_service_name(service_name)
+                    // The release as `git describe` names it
+                    .with_attribute(opentelemetry::KeyValue::new(
+                        "service.version",
+                        env!("RKT_VERSION"),
+                    ))
+                    .build(),
+            )
+            .build();
+
+        // Only our own spans, independent of the log level shown on stderr
+        let targets = Targets::new()
+            .with_target("rocketindex", LevelFilter::INFO)
+            .with_target("rkt", LevelFilter::INFO);
+        let layer = tracing_opentelemetry::layer()
+            .with_tracer(provider.tracer("rocketindex"))
+            .with_filter(targets)
+            .boxed();
+
+        Ok((layer, provider))
+    }
+}
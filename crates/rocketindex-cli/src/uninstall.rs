@@ -0,0 +1,277 @@
+//! Undo `rkt setup`.
+//!
+//! Files setup created are deleted; files it edited (CLAUDE.md, an existing
+//! `.rules` or copilot-instructions.md) have the RocketIndex section or note
+//! cut back out, so anything written around it is kept, and project MCP
+//! configs lose their `rocket-index` server. The `.rocketindex/` directory
+//! goes as a whole.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::mcp_clients::{self, Client};
+use crate::skills;
+
+/// Heading of the section setup appends to rules and instruction files
+const SECTION_HEADING: &str = "## RocketIndex Code Navigation";
+
+/// Headings setup writes when it creates a file for its section
+const CREATED_HEADINGS: [&str; 3] = [
+    "# Copilot Instructions",
+    "# Project Rules",
+    "# Project Instructions",
+];
+
+/// Files setup appends [`SECTION_HEADING`] to
+const SECTION_FILES: [&str; 3] = [".github/copilot-instructions.md", ".rules", "GEMINI.md"];
+
+/// Files setup inserts [`crate::ROCKETINDEX_NOTE`] into
+const NOTE_FILES: [&str; 2] = ["CLAUDE.md", ".github/copilot-instructions.md"];
+
+const GITIGNORE_ENTRY: &str = ".rocketindex/index.db";
+
+/// One step of an uninstall
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    RemoveDir(PathBuf),
+    RemoveFile(PathBuf),
+    Rewrite { path: PathBuf, content: String },
+}
+
+impl Change {
+    pub fn path(&self) -> &Path {
+        match self {
+            Change::RemoveDir(path) | Change::RemoveFile(path) => path,
+            Change::Rewrite { path, .. } => path,
+        }
+    }
+
+    pub fn action(&self) -> &'static str {
+        match self {
+            Change::RemoveDir(_) | Change::RemoveFile(_) => "remove",
+            Change::Rewrite { .. } => "edit",
+        }
+    }
+
+    fn apply(&self) -> Result<()> {
+        match self {
+            Change::RemoveDir(path) => std::fs::remove_dir_all(path),
+            Change::RemoveFile(path) => std::fs::remove_file(path),
+            Change::Rewrite { path, content } => std::fs::write(path, content),
+        }
+        .with_context(|| format!("Failed to {} {}", self.action(), self.path().display()))
+    }
+}
+
+/// Everything setup left in `root`, in the order it should be undone.
+pub fn plan(root: &Path) -> Vec<Change> {
+    let mut changes = Vec::new();
+
+    for definition in skills::installed(root) {
+        changes.push(Change::RemoveFile(root.join(definition)));
+    }
+
+    let cursor_rules = root.join(".cursor").join("rules");
+    if let Ok(content) = std::fs::read_to_string(&cursor_rules) {
+        if content.starts_with("# RocketIndex Code Navigation") {
+            changes.push(Change::RemoveFile(cursor_rules));
+        }
+    }
+
+    let mut edited: Vec<(PathBuf, String, String)> = Vec::new();
+    for file in SECTION_FILES.iter().chain(NOTE_FILES.iter()) {
+        let path = root.join(file);
+        if edited.iter().any(|(p, _, _)| *p == path) {
+            continue;
+        }
+        let Ok(original) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        let mut content = original.clone();
+        if SECTION_FILES.contains(file) {
+            content = strip_section(&content).unwrap_or(content);
+        }
+        if NOTE_FILES.contains(file) {
+            content = strip_note(&content).unwrap_or(content);
+        }
+        edited.push((path, original, content));
+    }
+    for (path, original, content) in edited {
+        if content == original {
+            continue;
+        }
+        let rest = content.trim();
+        if rest.is_empty() || CREATED_HEADINGS.contains(&rest) {
+            changes.push(Change::RemoveFile(path));
+        } else {
+            changes.push(Change::Rewrite { path, content });
+        }
+    }
+
+    for client in Client::PROJECT {
+        let Some(path) = client.config_path(root) else {
+            continue;
+        };
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        match mcp_clients::unregister(client, &content) {
+            Some(rest) if rest.is_empty() => changes.push(Change::RemoveFile(path)),
+            Some(rest) => changes.push(Change::Rewrite {
+                path,
+                content: rest,
+            }),
+            None => {}
+        }
+    }
+
+    let gitignore = root.join(".gitignore");
+    if let Ok(content) = std::fs::read_to_string(&gitignore) {
+        if let Some(updated) = strip_gitignore_entry(&content) {
+            changes.push(Change::Rewrite {
+                path: gitignore,
+                content: updated,
+            });
+        }
+    }
+
+    let index_dir = root.join(".rocketindex");
+    if index_dir.is_dir() {
+        changes.push(Change::RemoveDir(index_dir));
+    }
+
+    changes
+}
+
+/// Apply `changes`, then remove directories under `root` they left empty.
+pub fn apply(root: &Path, changes: &[Change]) -> Result<()> {
+    for change in changes {
+        change.apply()?;
+    }
+    for change in changes {
+        if let Change::RemoveFile(path) = change {
+            let mut dir = path.parent();
+            while let Some(d) = dir.filter(|d| *d != root && d.starts_with(root)) {
+                // Fails (and stops) on the first directory that isn't empty
+                if std::fs::remove_dir(d).is_err() {
+                    break;
+                }
+                dir = d.parent();
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Cut the RocketIndex section (up to the next heading of the same level or higher).
+fn strip_section(content: &str) -> Option<String> {
+    let start = content.find(SECTION_HEADING)?;
+    let body_start = start + SECTION_HEADING.len();
+    let end = content[body_start..]
+        .match_indices('\n')
+        .map(|(i, _)| body_start + i + 1)
+        .find(|&i| content[i..].starts_with("# ") || content[i..].starts_with("## "))
+        .unwrap_or(content.len());
+
+    let before = content[..start].trim_end();
+    let after = content[end..].trim_start();
+    let mut stripped = before.to_string();
+    if !after.is_empty() {
+        if !stripped.is_empty() {
+            stripped.push_str("\n\n");
+        }
+        stripped.push_str(after);
+    }
+    if !stripped.ends_with('\n') {
+        stripped.push('\n');
+    }
+    Some(stripped)
+}
+
+/// Cut the one-paragraph note setup inserts after a file's first paragraph.
+fn strip_note(content: &str) -> Option<String> {
+    let start = content.find(crate::ROCKETINDEX_NOTE)?;
+    let end = start + crate::ROCKETINDEX_NOTE.len();
+    Some(if content[end..].starts_with('\n') {
+        // Inserted between paragraphs as "note\n"
+        format!("{}{}", &content[..start], &content[end + 1..])
+    } else {
+        // Appended as "\n\nnote"
+        format!(
+            "{}{}",
+            content[..start]
+                .strip_suffix("\n\n")
+                .unwrap_or(&content[..start]),
+            &content[end..]
+        )
+    })
+}
+
+fn strip_gitignore_entry(content: &str) -> Option<String> {
+    if !content.lines().any(|l| l.trim() == GITIGNORE_ENTRY) {
+        return None;
+    }
+    let mut kept: String = content
+        .lines()
+        .filter(|l| l.trim() != GITIGNORE_ENTRY)
+        .collect::<Vec<_>>()
+        .join("\n");
+    if !kept.is_empty() {
+        kept.push('\n');
+    }
+    Some(kept)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_section_keeps_surrounding_content() {
+        let content = format!(
+            "# Rules\n\nBe nice.\n\n{}\n\nUse rkt.\n\n**Tips:**\n- Index often\n\n## Style\n\nTabs.\n",
+            SECTION_HEADING
+        );
+        assert_eq!(
+            strip_section(&content).unwrap(),
+            "# Rules\n\nBe nice.\n\n## Style\n\nTabs.\n"
+        );
+
+        let appended = format!("# Rules\n\nBe nice.\n\n{}\n\nUse rkt.\n", SECTION_HEADING);
+        assert_eq!(strip_section(&appended).unwrap(), "# Rules\n\nBe nice.\n");
+        assert!(strip_section("# Rules\n").is_none());
+    }
+
+    #[test]
+    fn test_strip_note_restores_original() {
+        let note = crate::ROCKETINDEX_NOTE;
+        let inserted = format!("# My Project\n\n{}\nSome content.\n", note);
+        assert_eq!(
+            strip_note(&inserted).unwrap(),
+            "# My Project\n\nSome content.\n"
+        );
+
+        let appended = format!("# My Project\n\n{}", note);
+        assert_eq!(strip_note(&appended).unwrap(), "# My Project");
+    }
+
+    #[test]
+    fn test_strip_gitignore_entry() {
+        assert_eq!(
+            strip_gitignore_entry("target/\n.rocketindex/index.db\n*.log\n").unwrap(),
+            "target/\n*.log\n"
+        );
+        assert!(strip_gitignore_entry("target/\n").is_none());
+    }
+
+    #[test]
+    fn test_plan_leaves_unrelated_files_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join(".cursor")).unwrap();
+        std::fs::write(root.join(".cursor/rules"), "# Team rules\n").unwrap();
+        std::fs::write(root.join("CLAUDE.md"), "# Project\n").unwrap();
+        assert!(plan(root).is_empty());
+    }
+}
@@ -1,27 +1,36 @@
 //! Version check and self-update module.
 //!
 //! Queries GitHub releases API and caches results for 24 hours.
-//! Can auto-update for non-Homebrew installations.
+//! Can auto-update for non-Homebrew installations, from either the stable or
+//! the beta channel, verifying release signatures and keeping the replaced
+//! binary around for `rkt update --rollback`.
 
 use anyhow::{Context, Result};
 use semver::Version;
 use serde::{Deserialize, Serialize};
+use std::fmt;
 use std::path::PathBuf;
+use std::str::FromStr;
 use std::time::{Duration, SystemTime};
 
+use crate::network;
+
 /// Cache TTL: 24 hours
 const CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
 
-/// GitHub API URL for releases (includes prereleases)
+/// GitHub API URL for releases (includes prereleases).
+///
+/// Enough releases are requested that a stable one is found even after a run
+/// of betas.
 const GITHUB_API_URL: &str =
-    "https://api.github.com/repos/rocket-tycoon/rocket-index/releases?per_page=1";
+    "https://api.github.com/repos/rocket-tycoon/rocket-index/releases?per_page=30";
 
 /// Get the GitHub API URL, allowing override via environment variable for testing.
 fn github_api_url() -> String {
     std::env::var("ROCKETINDEX_GITHUB_API")
         .map(|base| {
             format!(
-                "{}/repos/rocket-tycoon/rocket-index/releases?per_page=1",
+                "{}/repos/rocket-tycoon/rocket-index/releases?per_page=30",
                 base
             )
         })
@@ -31,17 +40,90 @@ fn github_api_url() -> String {
 /// Current version from git tags (matches release versions)
 pub const CURRENT_VERSION: &str = env!("RKT_VERSION");
 
+/// Hex-encoded ed25519 key release archives are signed with (zipsign).
+///
+/// Baked in at build time; builds without it refuse to self-update unless
+/// told to accept unsigned archives.
+const UPDATE_VERIFYING_KEY: Option<&str> = option_env!("RKT_UPDATE_VERIFYING_KEY");
+
+/// Release channel to look for updates on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Channel {
+    /// Releases not marked as prerelease
+    Stable,
+    /// The newest release, prerelease or not
+    Beta,
+}
+
+impl Channel {
+    pub const ALL: [Channel; 2] = [Channel::Stable, Channel::Beta];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Channel::Stable => "stable",
+            Channel::Beta => "beta",
+        }
+    }
+
+    /// The channel a version was released on: prereleases come from beta.
+    pub fn of_version(version: &str) -> Channel {
+        match parse_version(version) {
+            Some(v) if v.pre.is_empty() => Channel::Stable,
+            _ => Channel::Beta,
+        }
+    }
+
+    fn includes(self, release: &GitHubRelease) -> bool {
+        !release.draft && (self == Channel::Beta || !release.prerelease)
+    }
+}
+
+impl fmt::Display for Channel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Channel {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Channel::ALL
+            .into_iter()
+            .find(|c| c.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown channel '{}' (expected stable or beta)", s))
+    }
+}
+
 /// Cached version check result
 #[derive(Debug, Serialize, Deserialize)]
 struct VersionCache {
     latest_version: String,
     checked_at: u64, // Unix timestamp
+    /// Channel the version was looked up on; older caches are beta lookups
+    #[serde(default = "default_cache_channel")]
+    channel: String,
+}
+
+fn default_cache_channel() -> String {
+    Channel::Beta.as_str().to_string()
 }
 
 /// GitHub release response (minimal fields we need)
 #[derive(Debug, Deserialize)]
 struct GitHubRelease {
     tag_name: String,
+    #[serde(default)]
+    prerelease: bool,
+    #[serde(default)]
+    draft: bool,
+}
+
+/// Metadata stored next to the binary kept for rollback
+#[derive(Debug, Serialize, Deserialize)]
+struct RollbackInfo {
+    version: String,
+    saved_at: u64, // Unix timestamp
 }
 
 /// Returns the path to the version cache file
@@ -52,8 +134,8 @@ fn cache_path() -> PathBuf {
         .join("version_cache.json")
 }
 
-/// Load cached version if still valid
-fn load_cache() -> Option<String> {
+/// Load cached version for a channel if still valid
+fn load_cache(channel: Channel) -> Option<String> {
     let path = cache_path();
     let contents = std::fs::read_to_string(&path).ok()?;
     let cache: VersionCache = serde_json::from_str(&contents).ok()?;
@@ -64,7 +146,9 @@ fn load_cache() -> Option<String> {
         .as_secs();
 
     // Check if cache is still valid
-    if now.saturating_sub(cache.checked_at) < CACHE_TTL.as_secs() {
+    if cache.channel == channel.as_str()
+        && now.saturating_sub(cache.checked_at) < CACHE_TTL.as_secs()
+    {
         Some(cache.latest_version)
     } else {
         None
@@ -78,7 +162,7 @@ pub fn clear_cache() {
 }
 
 /// Save version to cache
-fn save_cache(version: &str) -> Result<()> {
+fn save_cache(channel: Channel, version: &str) -> Result<()> {
     let path = cache_path();
     if let Some(parent) = path.parent() {
         std::fs::create_dir_all(parent)?;
@@ -89,6 +173,7 @@ fn save_cache(version: &str) -> Result<()> {
         checked_at: SystemTime::now()
             .duration_since(SystemTime::UNIX_EPOCH)?
             .as_secs(),
+        channel: channel.as_str().to_string(),
     };
 
     let contents = serde_json::to_string_pretty(&cache)?;
@@ -96,29 +181,45 @@ fn save_cache(version: &str) -> Result<()> {
     Ok(())
 }
 
-/// Fetch latest version from GitHub API
+/// Fetch latest version from GitHub API, including prereleases
 ///
 /// This function can be tested by setting `ROCKETINDEX_GITHUB_API` env var
 /// to point to a mock server.
 #[doc(hidden)]
 pub fn fetch_latest_version() -> Result<String> {
-    let releases: Vec<GitHubRelease> = ureq::get(&github_api_url())
-        .set("User-Agent", "rocketindex-cli")
+    fetch_latest_version_on(Channel::Beta)
+}
+
+/// Fetch the latest version published on a channel.
+#[doc(hidden)]
+pub fn fetch_latest_version_on(channel: Channel) -> Result<String> {
+    let tag = fetch_latest_tag(channel)?;
+    Ok(version_of_tag(&tag).to_string())
+}
+
+/// Tag name of the latest release on a channel.
+fn fetch_latest_tag(channel: Channel) -> Result<String> {
+    let releases: Vec<GitHubRelease> = network::agent("check for updates")?
+        .get(&github_api_url())
         .set("Accept", "application/vnd.github.v3+json")
         .call()?
         .into_json()?;
 
-    let release = releases
+    if releases.is_empty() {
+        anyhow::bail!("No releases found");
+    }
+
+    // The API lists releases newest first
+    releases
         .into_iter()
-        .next()
-        .ok_or_else(|| anyhow::anyhow!("No releases found"))?;
-
-    // Strip 'v' prefix if present
-    let version = release
-        .tag_name
-        .strip_prefix('v')
-        .unwrap_or(&release.tag_name);
-    Ok(version.to_string())
+        .find(|r| channel.includes(r))
+        .map(|r| r.tag_name)
+        .ok_or_else(|| anyhow::anyhow!("No releases found on the {} channel", channel))
+}
+
+/// Strip 'v' prefix if present
+fn version_of_tag(tag: &str) -> &str {
+    tag.strip_prefix('v').unwrap_or(tag)
 }
 
 /// Parse version string, handling pre-release versions
@@ -132,14 +233,18 @@ fn parse_version(version_str: &str) -> Option<Version> {
 /// `None` if current version is up-to-date or check fails.
 ///
 /// Results are cached for 24 hours to avoid hitting the API repeatedly.
+/// Stable installs are only told about stable releases. Offline, only the
+/// cache is consulted.
 pub fn check_for_update() -> Option<(String, String)> {
+    let channel = Channel::of_version(CURRENT_VERSION);
+
     // Try cache first
-    let latest = match load_cache() {
+    let latest = match load_cache(channel) {
         Some(v) => v,
         None => {
             // Fetch from GitHub (ignore errors silently - don't block on network issues)
-            let fetched = fetch_latest_version().ok()?;
-            let _ = save_cache(&fetched);
+            let fetched = fetch_latest_version_on(channel).ok()?;
+            let _ = save_cache(channel, &fetched);
             fetched
         }
     };
@@ -190,11 +295,83 @@ fn is_scoop_install() -> bool {
         .unwrap_or(false)
 }
 
+/// Parse the hex-encoded verifying key baked into the build.
+fn parse_verifying_key(hex: &str) -> Result<[u8; 32]> {
+    let hex = hex.trim();
+    if hex.len() != 64 || !hex.is_ascii() {
+        anyhow::bail!("expected 64 hex characters, got {}", hex.len());
+    }
+    let mut key = [0u8; 32];
+    for (i, byte) in key.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16)
+            .with_context(|| format!("invalid hex at position {}", i * 2))?;
+    }
+    Ok(key)
+}
+
+/// Keys update archives must be signed with, from the build's `baked` key.
+///
+/// Without one there is nothing to check a download against, so this fails
+/// unless `allow_unsigned` (`rkt update --allow-unsigned`) returns no keys.
+fn verifying_keys(baked: Option<&str>, allow_unsigned: bool) -> Result<Vec<[u8; 32]>> {
+    let key = baked
+        .filter(|hex| !hex.trim().is_empty())
+        .map(parse_verifying_key)
+        .transpose()
+        .context("RKT_UPDATE_VERIFYING_KEY baked into this build is malformed")?;
+    match key {
+        Some(key) => Ok(vec![key]),
+        None if allow_unsigned => Ok(Vec::new()),
+        None => anyhow::bail!(
+            "This build of rkt has no update signing key, so it can't verify a downloaded \
+             release. Reinstall from a release, or pass --allow-unsigned to update anyway."
+        ),
+    }
+}
+
+/// Directory holding the binary replaced by the last update
+fn rollback_dir() -> PathBuf {
+    dirs::data_local_dir()
+        .or_else(dirs::config_dir)
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("rocketindex")
+        .join("rollback")
+}
+
+fn rollback_binary(dir: &std::path::Path) -> PathBuf {
+    dir.join(format!("rkt-previous{}", std::env::consts::EXE_SUFFIX))
+}
+
+/// Keep a copy of the running binary so a bad update can be rolled back.
+fn save_for_rollback() -> Result<()> {
+    let exe = std::env::current_exe().context("Failed to locate the running binary")?;
+    let dir = rollback_dir();
+    std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    std::fs::copy(&exe, rollback_binary(&dir))
+        .with_context(|| format!("Failed to back up {}", exe.display()))?;
+
+    let info = RollbackInfo {
+        version: CURRENT_VERSION.to_string(),
+        saved_at: SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)?
+            .as_secs(),
+    };
+    std::fs::write(
+        dir.join("rollback.json"),
+        serde_json::to_string_pretty(&info)?,
+    )?;
+    Ok(())
+}
+
 /// Perform self-update.
 ///
-/// Downloads the latest release from GitHub and replaces the current binary.
-/// For Homebrew/Scoop installations, prints instructions instead.
-pub fn self_update() -> Result<()> {
+/// Downloads the latest release on `channel` from GitHub and replaces the
+/// current binary; without a channel, stable installs stay on stable and
+/// prerelease installs follow beta. The archive's signature is checked
+/// against the key baked into the build (a build without one refuses unless
+/// `allow_unsigned`), and the replaced binary is kept for [`rollback`]. For
+/// Homebrew/Scoop installations, prints instructions instead.
+pub fn self_update(channel: Option<Channel>, allow_unsigned: bool) -> Result<()> {
     // Check for package manager installations
     if is_homebrew_install() {
         println!("RocketIndex is installed via Homebrew.");
@@ -208,11 +385,16 @@ pub fn self_update() -> Result<()> {
         return Ok(());
     }
 
+    let keys = verifying_keys(UPDATE_VERIFYING_KEY, allow_unsigned)?;
+    network::ensure_online("update")?;
+    let channel = channel.unwrap_or_else(|| Channel::of_version(CURRENT_VERSION));
+
     // Check if update is available
-    println!("Checking for updates...");
+    println!("Checking for updates on the {} channel...", channel);
 
-    let latest = fetch_latest_version().context("Failed to check for updates")?;
-    let _ = save_cache(&latest);
+    let tag = fetch_latest_tag(channel).context("Failed to check for updates")?;
+    let latest = version_of_tag(&tag).to_string();
+    let _ = save_cache(channel, &latest);
 
     let current = parse_version(CURRENT_VERSION);
     let latest_parsed = parse_version(&latest);
@@ -221,6 +403,15 @@ pub fn self_update() -> Result<()> {
         (Some(curr), Some(lat)) if lat > curr => {
             println!("Updating from v{} to v{}...", CURRENT_VERSION, latest);
         }
+        // Switching from beta to stable can mean going back a version
+        (Some(curr), Some(lat))
+            if lat < curr && Channel::of_version(CURRENT_VERSION) != channel =>
+        {
+            println!(
+                "Switching to the {} channel: v{} -> v{}...",
+                channel, CURRENT_VERSION, latest
+            );
+        }
         (Some(_), Some(_)) => {
             println!("Already up to date (v{})", CURRENT_VERSION);
             return Ok(());
@@ -230,6 +421,14 @@ pub fn self_update() -> Result<()> {
         }
     }
 
+    if keys.is_empty() {
+        eprintln!(
+            "\x1b[33mWarning: --allow-unsigned: the download will not be signature-checked\x1b[0m"
+        );
+    }
+
+    save_for_rollback().context("Failed to keep the current binary for rollback")?;
+
     // Perform the update
     let status = self_update::backends::github::Update::configure()
         .repo_owner("rocket-tycoon")
@@ -237,6 +436,8 @@ pub fn self_update() -> Result<()> {
         .bin_name("rkt")
         .show_download_progress(true)
         .current_version(CURRENT_VERSION)
+        .target_version_tag(&tag)
+        .verifying_keys(keys)
         .build()
         .context("Failed to configure updater")?
         .update()
@@ -248,12 +449,42 @@ pub fn self_update() -> Result<()> {
         }
         self_update::Status::Updated(v) => {
             println!("\x1b[32m✓ Updated to v{}\x1b[0m", v);
+            println!("  If it misbehaves, run: rkt update --rollback");
         }
     }
 
     Ok(())
 }
 
+/// Restore the binary replaced by the last `rkt update`.
+pub fn rollback() -> Result<()> {
+    let dir = rollback_dir();
+    let binary = rollback_binary(&dir);
+    if !binary.exists() {
+        anyhow::bail!(
+            "No previous version to roll back to (nothing in {})",
+            dir.display()
+        );
+    }
+    let info: Option<RollbackInfo> = std::fs::read_to_string(dir.join("rollback.json"))
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok());
+
+    self_update::self_replace::self_replace(&binary)
+        .context("Failed to restore the previous binary")?;
+
+    // One step only: the restored binary is now the running one
+    let _ = std::fs::remove_file(&binary);
+    let _ = std::fs::remove_file(dir.join("rollback.json"));
+    clear_cache();
+
+    match info {
+        Some(info) => println!("\x1b[32m✓ Rolled back to v{}\x1b[0m", info.version),
+        None => println!("\x1b[32m✓ Rolled back to the previous version\x1b[0m"),
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -309,11 +540,73 @@ mod tests {
         let cache = VersionCache {
             latest_version: "0.1.0-beta.28".to_string(),
             checked_at: 1703500000,
+            channel: "stable".to_string(),
         };
         let json = serde_json::to_string(&cache).unwrap();
         let parsed: VersionCache = serde_json::from_str(&json).unwrap();
         assert_eq!(parsed.latest_version, "0.1.0-beta.28");
         assert_eq!(parsed.checked_at, 1703500000);
+        assert_eq!(parsed.channel, "stable");
+    }
+
+    #[test]
+    fn test_cache_without_channel_is_beta() {
+        let json = r#"{"latest_version": "0.1.0-beta.28", "checked_at": 1703500000}"#;
+        let parsed: VersionCache = serde_json::from_str(json).unwrap();
+        assert_eq!(parsed.channel, "beta");
+    }
+
+    #[test]
+    fn test_channel_parse_and_default() {
+        assert_eq!("stable".parse::<Channel>(), Ok(Channel::Stable));
+        assert_eq!("BETA".parse::<Channel>(), Ok(Channel::Beta));
+        assert!("nightly".parse::<Channel>().is_err());
+
+        assert_eq!(Channel::of_version("1.2.0"), Channel::Stable);
+        assert_eq!(Channel::of_version("0.1.0-beta.28"), Channel::Beta);
+    }
+
+    #[test]
+    fn test_channel_includes_releases() {
+        let json = r#"[
+            {"tag_name": "v0.3.0", "draft": true},
+            {"tag_name": "v0.2.0-beta.1", "prerelease": true},
+            {"tag_name": "v0.1.0"}
+        ]"#;
+        let releases: Vec<GitHubRelease> = serde_json::from_str(json).unwrap();
+        let first = |channel: Channel| {
+            releases
+                .iter()
+                .find(|r| channel.includes(r))
+                .map(|r| r.tag_name.as_str())
+        };
+        assert_eq!(first(Channel::Beta), Some("v0.2.0-beta.1"));
+        assert_eq!(first(Channel::Stable), Some("v0.1.0"));
+    }
+
+    #[test]
+    fn test_parse_verifying_key() {
+        let hex = "00ff".repeat(16);
+        let key = parse_verifying_key(&hex).unwrap();
+        assert_eq!(key[0], 0x00);
+        assert_eq!(key[1], 0xff);
+        assert_eq!(key[31], 0xff);
+
+        assert!(parse_verifying_key("abcd").is_err());
+        assert!(parse_verifying_key(&"zz".repeat(32)).is_err());
+    }
+
+    #[test]
+    fn test_unsigned_updates_need_opting_in() {
+        let error = verifying_keys(None, false).unwrap_err().to_string();
+        assert!(error.contains("--allow-unsigned"), "{}", error);
+        assert!(verifying_keys(Some(" "), false).is_err());
+        assert!(verifying_keys(None, true).unwrap().is_empty());
+
+        let hex = "ab".repeat(32);
+        assert_eq!(verifying_keys(Some(&hex), false).unwrap(), vec![[0xab; 32]]);
+        // A malformed key is never waved through
+        assert!(verifying_keys(Some("abcd"), true).is_err());
     }
 
     #[test]
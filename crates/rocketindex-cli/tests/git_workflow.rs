@@ -1,6 +1,7 @@
 #![allow(deprecated)] // cargo_bin is deprecated in assert_cmd but replacement not yet stable
 
 use assert_cmd::Command;
+use predicates::prelude::*;
 use predicates::str::contains;
 use std::error::Error;
 use std::fs;
@@ -156,3 +157,557 @@ fn def_git_flag_shows_provenance() -> TestResult {
 
     Ok(())
 }
+
+#[test]
+fn symbols_in_changed_maps_diff_to_enclosing_symbols() -> TestResult {
+    let workspace = GitWorkspace::new()?;
+
+    workspace.commit_file(
+        "src/App.fs",
+        "module App\n\nlet hello() =\n    \"world\"\n\nlet goodbye() =\n    \"bye\"\n",
+        "Initial commit",
+    )?;
+
+    Command::cargo_bin("rkt")?
+        .current_dir(workspace.root())
+        .args(["index", "--root", "."])
+        .assert()
+        .success();
+
+    // Unstaged edit to the body of goodbye only
+    fs::write(
+        workspace.root().join("src/App.fs"),
+        "module App\n\nlet hello() =\n    \"world\"\n\nlet goodbye() =\n    \"see you\"\n",
+    )?;
+
+    Command::cargo_bin("rkt")?
+        .current_dir(workspace.root())
+        .args(["symbols-in", "--changed", "--format", "text"])
+        .assert()
+        .success()
+        .stdout(contains("App.goodbye"))
+        .stdout(contains("App.hello").not());
+
+    // Nothing is staged yet
+    Command::cargo_bin("rkt")?
+        .current_dir(workspace.root())
+        .args(["symbols-in", "--changed", "--staged", "--format", "text"])
+        .assert()
+        .code(1);
+
+    Ok(())
+}
+
+#[test]
+fn index_changed_updates_only_what_git_reports() -> TestResult {
+    let workspace = GitWorkspace::new()?;
+    workspace.commit_file("src/App.fs", "module App\n\nlet hello() = 1\n", "Add app")?;
+    workspace.commit_file(
+        "src/Legacy.fs",
+        "module Legacy\n\nlet old() = 1\n",
+        "Add legacy",
+    )?;
+
+    Command::cargo_bin("rkt")?
+        .current_dir(workspace.root())
+        .args(["index", "--root", "."])
+        .assert()
+        .success();
+
+    // Sleep so the edit's mtime differs (filesystem granularity can be 1 second)
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+    // A commit since the last index, an unstaged edit, a deletion and an
+    // untracked file
+    workspace.commit_file(
+        "src/Billing.fs",
+        "module Billing\n\nlet charge() = 1\n",
+        "Add billing",
+    )?;
+    fs::write(
+        workspace.root().join("src/App.fs"),
+        "module App\n\nlet hello() = 1\n\nlet greet() = 2\n",
+    )?;
+    fs::remove_file(workspace.root().join("src/Legacy.fs"))?;
+    fs::write(
+        workspace.root().join("src/Draft.fs"),
+        "module Draft\n\nlet draft() = 1\n",
+    )?;
+
+    let output = Command::cargo_bin("rkt")?
+        .current_dir(workspace.root())
+        .args(["index", "--root", ".", "--changed", "--format", "json"])
+        .output()?;
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(json["changed"], true);
+    assert_eq!(json["files_updated"], 3);
+    assert_eq!(json["files_deleted"], 1);
+
+    for symbol in ["App.greet", "Billing.charge", "Draft.draft"] {
+        Command::cargo_bin("rkt")?
+            .current_dir(workspace.root())
+            .args(["def", symbol])
+            .assert()
+            .success();
+    }
+    Command::cargo_bin("rkt")?
+        .current_dir(workspace.root())
+        .args(["def", "Legacy.old"])
+        .assert()
+        .code(1);
+
+    // Files already reindexed are skipped next time
+    let output = Command::cargo_bin("rkt")?
+        .current_dir(workspace.root())
+        .args(["index", "--root", ".", "--changed", "--format", "json"])
+        .output()?;
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(json["files_updated"], 0);
+
+    Ok(())
+}
+
+#[test]
+fn affected_tests_prints_runner_filter_for_changed_code() -> TestResult {
+    let workspace = GitWorkspace::new()?;
+
+    workspace.commit_file(
+        "app/billing.py",
+        "def charge(amount):\n    return amount\n\n\ndef refund(amount):\n    return -amount\n",
+        "Add billing",
+    )?;
+    workspace.commit_file(
+        "tests/test_billing.py",
+        "from app.billing import charge, refund\n\n\ndef test_charge():\n    assert charge(1) == 1\n\n\ndef test_refund():\n    assert refund(1) == -1\n",
+        "Add billing tests",
+    )?;
+
+    Command::cargo_bin("rkt")?
+        .current_dir(workspace.root())
+        .args(["index", "--root", "."])
+        .assert()
+        .success();
+
+    // Change charge only
+    fs::write(
+        workspace.root().join("app/billing.py"),
+        "def charge(amount):\n    return amount * 2\n\n\ndef refund(amount):\n    return -amount\n",
+    )?;
+
+    Command::cargo_bin("rkt")?
+        .current_dir(workspace.root())
+        .args(["affected-tests", "--runner", "pytest", "--format", "text"])
+        .assert()
+        .success()
+        .stdout(contains("tests/test_billing.py::test_charge"))
+        .stdout(contains("test_refund").not());
+
+    Ok(())
+}
+
+#[test]
+fn impact_reports_callers_entry_points_and_tests_since_base() -> TestResult {
+    let workspace = GitWorkspace::new()?;
+
+    workspace.commit_file(
+        "app/billing.py",
+        "def charge(amount):\n    return amount\n\n\ndef refund(amount):\n    return -amount\n",
+        "Add billing",
+    )?;
+    workspace.commit_file(
+        "app/api.py",
+        "from app.billing import charge\n\n\ndef checkout(amount):\n    return charge(amount)\n",
+        "Add api",
+    )?;
+    workspace.commit_file(
+        "tests/test_api.py",
+        "from app.api import checkout\n\n\ndef test_checkout():\n    assert checkout(1) == 1\n",
+        "Add api tests",
+    )?;
+    StdCommand::new("git")
+        .args(["branch", "base"])
+        .current_dir(workspace.root())
+        .output()?;
+
+    // Change charge in a commit on top of the base branch
+    workspace.commit_file(
+        "app/billing.py",
+        "def charge(amount):\n    return amount * 2\n\n\ndef refund(amount):\n    return -amount\n",
+        "Double charges",
+    )?;
+
+    Command::cargo_bin("rkt")?
+        .current_dir(workspace.root())
+        .args(["index", "--root", "."])
+        .assert()
+        .success();
+
+    // Nothing is uncommitted
+    Command::cargo_bin("rkt")?
+        .current_dir(workspace.root())
+        .args(["impact", "--format", "text"])
+        .assert()
+        .code(1);
+
+    let output = Command::cargo_bin("rkt")?
+        .current_dir(workspace.root())
+        .args(["impact", "--base", "base", "--format", "json"])
+        .output()?;
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    let names = |key: &str| -> Vec<String> {
+        json[key]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| v["qualified"].as_str().unwrap_or_default().to_string())
+            .collect()
+    };
+    assert_eq!(names("changed").len(), 1);
+    assert!(names("changed")[0].ends_with("charge"));
+    assert!(names("callers").iter().any(|n| n.ends_with("checkout")));
+    assert!(names("entry_points")
+        .iter()
+        .any(|n| n.ends_with("checkout")));
+    assert!(names("tests").iter().any(|n| n.ends_with("test_checkout")));
+    assert!(!names("callers").iter().any(|n| n.ends_with("refund")));
+    // checkout's only caller is a test, so it dominates no other caller
+    assert_eq!(json["gateways"], serde_json::json!([]));
+
+    Ok(())
+}
+
+#[test]
+fn published_index_is_pulled_and_caught_up_to_head() -> TestResult {
+    let workspace = GitWorkspace::new()?;
+    let artifacts = TempDir::new()?;
+    let store = artifacts.path().to_string_lossy().to_string();
+
+    workspace.commit_file(
+        "src/App.fs",
+        "module App\n\nlet hello() = \"world\"\n",
+        "Initial commit",
+    )?;
+    let head = |root: &Path| -> TestResult<String> {
+        let output = StdCommand::new("git")
+            .args(["rev-parse", "HEAD"])
+            .current_dir(root)
+            .output()?;
+        Ok(String::from_utf8(output.stdout)?.trim().to_string())
+    };
+    let published_at = head(workspace.root())?;
+
+    Command::cargo_bin("rkt")?
+        .current_dir(workspace.root())
+        .args(["index", "--root", ".", "--publish", &store])
+        .assert()
+        .success()
+        .stderr(contains("IndexPublished"));
+    assert!(artifacts
+        .path()
+        .join(format!("{}.db.gz", published_at))
+        .exists());
+    assert!(artifacts
+        .path()
+        .join(format!("{}.json", published_at))
+        .exists());
+
+    // A fresh checkout one commit ahead of the published build
+    fs::remove_dir_all(workspace.root().join(".rocketindex"))?;
+    workspace.commit_file(
+        "src/App.fs",
+        "module App\n\nlet hello() = \"world\"\n\nlet goodbye() = \"moon\"\n",
+        "Add goodbye",
+    )?;
+
+    let output = Command::cargo_bin("rkt")?
+        .current_dir(workspace.root())
+        .args(["index", "--root", ".", "--pull", &store, "--format", "json"])
+        .output()?;
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr)?;
+    let notice: serde_json::Value = serde_json::from_str(
+        stderr
+            .lines()
+            .find(|l| l.contains("IndexPulled"))
+            .expect("pull notice"),
+    )?;
+    assert_eq!(notice["commit"], published_at.as_str());
+    assert_eq!(notice["behind"], 1);
+
+    // The file changed since the build was reindexed
+    Command::cargo_bin("rkt")?
+        .current_dir(workspace.root())
+        .args(["def", "App.goodbye"])
+        .assert()
+        .success()
+        .stdout(contains("App.fs"));
+
+    // A manifest naming some other commit is refused before git sees it
+    let manifest_path = artifacts.path().join(format!("{}.json", published_at));
+    let mut manifest: serde_json::Value = serde_json::from_slice(&fs::read(&manifest_path)?)?;
+    let clobbered = artifacts.path().join("clobbered");
+    manifest["commit"] = format!("--output={}", clobbered.display()).into();
+    fs::write(&manifest_path, serde_json::to_vec(&manifest)?)?;
+    Command::cargo_bin("rkt")?
+        .current_dir(workspace.root())
+        .args(["index", "--root", ".", "--pull", &store])
+        .assert()
+        .failure()
+        .stderr(contains("claims to be for commit"));
+    assert!(!clobbered.exists());
+
+    // Publishing needs a clean checkout
+    fs::write(
+        workspace.root().join("src/App.fs"),
+        "module App\n\nlet hello() = 1\n",
+    )?;
+    Command::cargo_bin("rkt")?
+        .current_dir(workspace.root())
+        .args(["index", "--root", ".", "--publish", &store])
+        .assert()
+        .failure()
+        .stderr(contains("uncommitted changes"));
+
+    Ok(())
+}
+
+#[test]
+fn compare_branches_reports_symbol_changes_and_callers() -> TestResult {
+    let workspace = GitWorkspace::new()?;
+
+    workspace.commit_file(
+        "app/billing.py",
+        "def charge(amount):\n    return amount\n\n\ndef legacy():\n    return 0\n",
+        "Add billing",
+    )?;
+    workspace.commit_file(
+        "app/api.py",
+        "from app.billing import charge\n\n\ndef checkout(amount):\n    return charge(amount)\n",
+        "Add api",
+    )?;
+    StdCommand::new("git")
+        .args(["branch", "base"])
+        .current_dir(workspace.root())
+        .output()?;
+    workspace.commit_file(
+        "app/billing.py",
+        "def charge(amount):\n    return amount * 2\n\n\ndef refund(amount):\n    return -amount\n",
+        "Rework billing",
+    )?;
+    // Uncommitted edits aren't part of either revision
+    fs::write(
+        workspace.root().join("app/api.py"),
+        "def unrelated():\n    pass\n",
+    )?;
+
+    let output = Command::cargo_bin("rkt")?
+        .current_dir(workspace.root())
+        .args(["compare-branches", "base", "HEAD", "--format", "json"])
+        .output()?;
+    assert!(output.status.success(), "{:?}", output);
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    let names = |key: &str| -> Vec<String> {
+        json[key]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| v["qualified"].as_str().unwrap_or_default().to_string())
+            .collect()
+    };
+    assert_eq!(json["summary"]["added"], 1);
+    assert!(names("added")[0].ends_with("refund"));
+    assert!(names("removed")[0].ends_with("legacy"));
+    assert!(names("changed")[0].ends_with("charge"));
+    assert_eq!(json["changed"][0]["changes"][0], "body");
+    assert!(names("callers").iter().any(|n| n.ends_with("checkout")));
+    assert_eq!(json["added"][0]["file"], "app/billing.py");
+
+    Command::cargo_bin("rkt")?
+        .current_dir(workspace.root())
+        .args(["compare-branches", "base", "HEAD", "--format", "text"])
+        .assert()
+        .success()
+        .stdout(contains("1 added, 1 removed, 1 changed"));
+
+    Command::cargo_bin("rkt")?
+        .current_dir(workspace.root())
+        .args(["compare-branches", "HEAD", "HEAD", "--format", "text"])
+        .assert()
+        .code(1)
+        .stdout(contains("No symbol differences"));
+
+    Command::cargo_bin("rkt")?
+        .current_dir(workspace.root())
+        .args(["compare-branches", "base", "no-such-branch"])
+        .assert()
+        .code(2)
+        .stderr(contains("no-such-branch"));
+
+    Ok(())
+}
+
+#[test]
+fn quick_index_answers_for_recent_work_and_completes_in_background() -> TestResult {
+    let workspace = GitWorkspace::new()?;
+    workspace.commit_file(
+        "app/helpers.py",
+        "def format_total(amount):\n    return str(amount)\n",
+        "Add helpers",
+    )?;
+    workspace.commit_file(
+        "app/legacy.py",
+        "def old_report():\n    return 1\n",
+        "Add legacy report",
+    )?;
+    workspace.commit_file(
+        "app/billing.py",
+        "from helpers import format_total\n\ndef charge(amount):\n    return format_total(amount)\n",
+        "Add billing",
+    )?;
+
+    // The last commit touched billing.py, which imports helpers.py
+    let output = Command::cargo_bin("rkt")?
+        .current_dir(workspace.root())
+        .args(["index", "--quick", "1", "--format", "json"])
+        .output()?;
+    assert!(output.status.success(), "{:?}", output);
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(json["files"], 3);
+    assert_eq!(json["files_indexed"], 2);
+    assert_eq!(json["remaining"], 1);
+    assert!(json["background_pid"].is_u64());
+
+    Command::cargo_bin("rkt")?
+        .current_dir(workspace.root())
+        .args(["def", "format_total", "--format", "text"])
+        .assert()
+        .success();
+
+    // The background process indexes the rest
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(30);
+    loop {
+        let found = Command::cargo_bin("rkt")?
+            .current_dir(workspace.root())
+            .args(["def", "old_report", "--format", "text"])
+            .output()?
+            .status
+            .success();
+        if found {
+            break;
+        }
+        assert!(
+            std::time::Instant::now() < deadline,
+            "background indexing didn't finish"
+        );
+        std::thread::sleep(std::time::Duration::from_millis(200));
+    }
+
+    // With an index in place, --quick is an ordinary incremental index
+    Command::cargo_bin("rkt")?
+        .current_dir(workspace.root())
+        .args(["index", "--quick", "--format", "text"])
+        .assert()
+        .success()
+        .stdout(contains("up to date"));
+
+    Ok(())
+}
+
+#[test]
+fn hotspots_rank_files_by_churn_complexity_and_fan_in() -> TestResult {
+    let workspace = GitWorkspace::new()?;
+    workspace.commit_file(
+        "pricing.py",
+        "def price(order):\n    return 1\n",
+        "Add pricing",
+    )?;
+    workspace.commit_file(
+        "pricing.py",
+        "def price(order):\n    if order.rush:\n        return 2\n    return 1\n",
+        "Rush pricing",
+    )?;
+    workspace.commit_file(
+        "pricing.py",
+        "def price(order):\n    if order.rush:\n        return 2\n    if order.bulk and order.size > 10:\n        return 0\n    return 1\n",
+        "Bulk pricing",
+    )?;
+    workspace.commit_file(
+        "checkout.py",
+        "from pricing import price\n\ndef checkout(order):\n    return price(order)\n",
+        "Add checkout",
+    )?;
+    workspace.commit_file(
+        "stable.py",
+        "def untouched(x):\n    if x:\n        return 1\n    return 0\n",
+        "Add stable",
+    )?;
+
+    Command::cargo_bin("rkt")?
+        .current_dir(workspace.root())
+        .args(["index", "--format", "text"])
+        .assert()
+        .success();
+
+    let output = Command::cargo_bin("rkt")?
+        .current_dir(workspace.root())
+        .args(["hotspots", "--since", "all", "--format", "json"])
+        .output()?;
+    assert!(output.status.success(), "{:?}", output);
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    let top = &json["hotspots"][0];
+    assert_eq!(top["name"], "pricing.py");
+    assert_eq!(top["commits"], 3);
+    assert_eq!(top["fan_in"], 1);
+
+    Command::cargo_bin("rkt")?
+        .current_dir(workspace.root())
+        .args(["hotspots", "--since", "all", "--by", "module"])
+        .args(["--format", "text"])
+        .assert()
+        .success()
+        .stdout(contains("SCORE").and(contains("MODULE")));
+
+    Ok(())
+}
+
+#[test]
+fn owner_reports_codeowners_rule_and_contributors() -> TestResult {
+    let workspace = GitWorkspace::new()?;
+    workspace.commit_file(
+        ".github/CODEOWNERS",
+        "*          @acme/core\n/billing/  @acme/billing\n",
+        "Add CODEOWNERS",
+    )?;
+    workspace.commit_file(
+        "billing/charge.py",
+        "def charge(amount):\n    total = amount\n    return total\n",
+        "Add charge",
+    )?;
+
+    Command::cargo_bin("rkt")?
+        .current_dir(workspace.root())
+        .args(["index", "--format", "text"])
+        .assert()
+        .success();
+
+    let output = Command::cargo_bin("rkt")?
+        .current_dir(workspace.root())
+        .args(["owner", "charge", "--format", "json"])
+        .output()?;
+    assert!(output.status.success(), "{:?}", output);
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(json["rule"]["owners"][0], "@acme/billing");
+    assert_eq!(json["rule"]["source"], ".github/CODEOWNERS:2");
+    assert_eq!(json["contributors"][0]["author"], "Test User");
+    assert_eq!(json["contributors"][0]["lines"], 3);
+
+    Command::cargo_bin("rkt")?
+        .current_dir(workspace.root())
+        .args(["enrich", "charge", "--format", "json"])
+        .assert()
+        .success()
+        .stdout(contains("\"@acme/billing\"").and(contains("test@example.com")));
+
+    Ok(())
+}
@@ -87,6 +87,117 @@ fn setup_claude_creates_correct_files() -> TestResult {
     Ok(())
 }
 
+#[test]
+fn setup_claude_installs_agent_pack() -> TestResult {
+    let workspace = SetupWorkspace::new()?;
+    let pack = TempDir::new()?;
+    fs::create_dir_all(pack.path().join("agents"))?;
+    fs::create_dir_all(pack.path().join("skills/release-notes"))?;
+    fs::write(
+        pack.path().join("agents/security-reviewer.md"),
+        "---\nname: security-reviewer\ndescription: Reviews changes\n---\nReview {{project}} with rkt {{rkt_version}}.\n",
+    )?;
+    fs::write(
+        pack.path().join("skills/release-notes/SKILL.md"),
+        "Write release notes.\n",
+    )?;
+
+    Command::cargo_bin("rkt")?
+        .current_dir(workspace.root())
+        .args(["setup", "claude", "--quiet", "--pack"])
+        .arg(pack.path())
+        .assert()
+        .success();
+
+    let agent = workspace.read_file(".claude/agents/security-reviewer.md")?;
+    let project = workspace.root().file_name().unwrap().to_string_lossy();
+    assert!(
+        agent.contains(&format!("Review {} with rkt ", project)),
+        "template should be rendered: {}",
+        agent
+    );
+    assert!(!agent.contains("{{"));
+    workspace.assert_exists(".claude/skills/release-notes/SKILL.md");
+    // Embedded agents are only offered interactively
+    workspace.assert_not_exists(".claude/agents/code-navigator.md");
+
+    // Packs are a Claude Code feature
+    Command::cargo_bin("rkt")?
+        .current_dir(workspace.root())
+        .args(["setup", "cursor", "--quiet", "--pack"])
+        .arg(pack.path())
+        .assert()
+        .failure();
+
+    Ok(())
+}
+
+#[test]
+fn setup_claude_scripted_with_agents_and_no_index() -> TestResult {
+    let workspace = SetupWorkspace::new()?;
+
+    Command::cargo_bin("rkt")?
+        .current_dir(workspace.root())
+        .args([
+            "setup",
+            "claude",
+            "--yes",
+            "--no-index",
+            "--agents",
+            "code-navigator",
+        ])
+        .assert()
+        .success();
+
+    workspace.assert_not_exists(".rocketindex/index.db");
+    workspace.assert_exists(".rocketindex/AGENTS.md");
+    let agent = workspace.read_file(".claude/agents/code-navigator.md")?;
+    assert!(!agent.contains("{{"), "template should be rendered");
+
+    // Unknown names fail before anything is written
+    Command::cargo_bin("rkt")?
+        .current_dir(workspace.root())
+        .args(["setup", "claude", "--yes", "--agents", "nope"])
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("code-navigator"));
+    workspace.assert_not_exists(".rocketindex/index.db");
+
+    // --yes alone takes the wizard's defaults: index and every agent
+    let other = SetupWorkspace::new()?;
+    Command::cargo_bin("rkt")?
+        .current_dir(other.root())
+        .args(["setup", "claude", "--yes", "--quiet"])
+        .assert()
+        .success();
+    other.assert_exists(".rocketindex/index.db");
+    other.assert_exists(".claude/agents/code-navigator.md");
+
+    Ok(())
+}
+
+#[test]
+fn setup_other_editors_honor_no_index() -> TestResult {
+    let workspace = SetupWorkspace::new()?;
+
+    Command::cargo_bin("rkt")?
+        .current_dir(workspace.root())
+        .args(["setup", "cursor", "--yes", "--no-index", "--quiet"])
+        .assert()
+        .success();
+
+    workspace.assert_not_exists(".rocketindex/index.db");
+
+    // Agent selection is a Claude Code feature
+    Command::cargo_bin("rkt")?
+        .current_dir(workspace.root())
+        .args(["setup", "zed", "--agents", "code-navigator"])
+        .assert()
+        .failure();
+
+    Ok(())
+}
+
 #[test]
 fn setup_claude_updates_existing_copilot_instructions() -> TestResult {
     let workspace = SetupWorkspace::new()?;
@@ -622,3 +733,130 @@ fn start_gemini_is_recognized() -> TestResult {
 
     Ok(())
 }
+
+#[test]
+fn uninstall_removes_what_setup_added() -> TestResult {
+    let workspace = SetupWorkspace::new()?;
+    let claude_md = "# My Project\n\nSome existing content.\n";
+    let gitignore = "target/\n";
+    fs::write(workspace.path("CLAUDE.md"), claude_md)?;
+    fs::write(workspace.path(".gitignore"), gitignore)?;
+
+    for editor in ["claude", "cursor", "zed", "gemini"] {
+        Command::cargo_bin("rkt")?
+            .current_dir(workspace.root())
+            .args(["setup", editor, "--yes", "--no-index", "--quiet"])
+            .assert()
+            .success();
+    }
+    fs::write(workspace.path(".rocketindex/index.db"), "")?;
+    fs::write(
+        workspace.path(".gitignore"),
+        ".rocketindex/index.db\ntarget/\n",
+    )?;
+    assert_ne!(workspace.read_file("CLAUDE.md")?, claude_md);
+
+    // A dry run lists the changes and touches nothing
+    Command::cargo_bin("rkt")?
+        .current_dir(workspace.root())
+        .args(["uninstall", "--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicates::str::contains(
+            ".claude/agents/code-navigator.md",
+        ))
+        .stdout(predicates::str::contains("GEMINI.md"));
+    workspace.assert_exists(".rocketindex/index.db");
+
+    Command::cargo_bin("rkt")?
+        .current_dir(workspace.root())
+        .args(["teardown", "--quiet"])
+        .assert()
+        .success();
+
+    for path in [".rocketindex", ".claude", ".cursor", ".rules", "GEMINI.md"] {
+        workspace.assert_not_exists(path);
+    }
+    assert_eq!(workspace.read_file("CLAUDE.md")?, claude_md);
+    assert_eq!(workspace.read_file(".gitignore")?, gitignore);
+
+    // Nothing left to do
+    Command::cargo_bin("rkt")?
+        .current_dir(workspace.root())
+        .args(["uninstall", "--format", "text"])
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("Nothing to remove"));
+
+    Ok(())
+}
+
+#[test]
+fn setup_mcp_registers_server_per_editor() -> TestResult {
+    let workspace = SetupWorkspace::new()?;
+    fs::create_dir_all(workspace.path(".zed"))?;
+    fs::write(
+        workspace.path(".zed/settings.json"),
+        "{ \"tab_size\": 2 }\n",
+    )?;
+
+    for editor in ["claude", "cursor", "zed", "gemini", "copilot"] {
+        Command::cargo_bin("rkt")?
+            .current_dir(workspace.root())
+            .args(["setup", editor, "--yes", "--no-index", "--mcp", "--quiet"])
+            .assert()
+            .success();
+    }
+
+    for (path, key) in [
+        (".mcp.json", "mcpServers"),
+        (".cursor/mcp.json", "mcpServers"),
+        (".zed/settings.json", "context_servers"),
+        (".gemini/settings.json", "mcpServers"),
+        (".vscode/mcp.json", "servers"),
+    ] {
+        let config: serde_json::Value = serde_json::from_str(&workspace.read_file(path)?)?;
+        assert_eq!(config[key]["rocket-index"]["command"], "rkt", "{}", path);
+    }
+    let zed: serde_json::Value = serde_json::from_str(&workspace.read_file(".zed/settings.json")?)?;
+    assert_eq!(zed["tab_size"], 2, "existing settings are kept");
+
+    // Without --mcp nothing is registered
+    let plain = SetupWorkspace::new()?;
+    Command::cargo_bin("rkt")?
+        .current_dir(plain.root())
+        .args(["setup", "cursor", "--yes", "--no-index", "--quiet"])
+        .assert()
+        .success();
+    plain.assert_not_exists(".cursor/mcp.json");
+
+    // Claude Desktop is a per-user config
+    let home = TempDir::new()?;
+    let output = Command::cargo_bin("rkt")?
+        .current_dir(workspace.root())
+        .env("HOME", home.path())
+        .env("XDG_CONFIG_HOME", home.path().join(".config"))
+        .env("APPDATA", home.path().join("AppData"))
+        .args(["setup", "claude-desktop", "--format", "json"])
+        .output()?;
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    let desktop = json["mcp_config"].as_str().expect("config path");
+    assert!(desktop.starts_with(&home.path().display().to_string()));
+    let config: serde_json::Value = serde_json::from_str(&fs::read_to_string(desktop)?)?;
+    assert_eq!(config["mcpServers"]["rocket-index"]["args"][0], "serve");
+
+    // Uninstall takes the registrations back out
+    Command::cargo_bin("rkt")?
+        .current_dir(workspace.root())
+        .args(["uninstall", "--quiet"])
+        .assert()
+        .success();
+    for path in [".mcp.json", ".cursor", ".gemini", ".vscode"] {
+        workspace.assert_not_exists(path);
+    }
+    let zed: serde_json::Value = serde_json::from_str(&workspace.read_file(".zed/settings.json")?)?;
+    assert_eq!(zed, serde_json::json!({ "tab_size": 2 }));
+
+    Ok(())
+}
@@ -234,3 +234,55 @@ async fn test_check_for_update_with_older_version() {
     // Should be None since the "latest" is actually older
     assert!(result.is_none());
 }
+
+#[tokio::test]
+#[serial]
+async fn test_offline_never_contacts_the_api() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/repos/rocket-tycoon/rocket-index/releases"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([
+            {"tag_name": "v99.0.0"}
+        ])))
+        .expect(0)
+        .mount(&mock_server)
+        .await;
+
+    setup_test_env(&mock_server);
+    rocketindex_cli::network::set_offline(true);
+
+    let result = rocketindex_cli::version_check::fetch_latest_version();
+
+    rocketindex_cli::network::set_offline(false);
+    cleanup_test_env();
+
+    assert!(result.unwrap_err().to_string().contains("--offline"));
+}
+
+#[tokio::test]
+#[serial]
+async fn test_fetch_gives_up_after_timeout() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/repos/rocket-tycoon/rocket-index/releases"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(serde_json::json!([{"tag_name": "v99.0.0"}]))
+                .set_delay(std::time::Duration::from_secs(10)),
+        )
+        .mount(&mock_server)
+        .await;
+
+    setup_test_env(&mock_server);
+    rocketindex_cli::network::set_timeout(std::time::Duration::from_secs(1));
+
+    let started = std::time::Instant::now();
+    let result = rocketindex_cli::version_check::fetch_latest_version();
+
+    cleanup_test_env();
+
+    assert!(result.is_err());
+    assert!(started.elapsed() < std::time::Duration::from_secs(5));
+}
@@ -0,0 +1,193 @@
+//! Call hierarchy support.
+//!
+//! Incoming calls come from [`reverse_spider`] and outgoing calls from
+//! [`spider`], both one level deep; the editor expands the tree lazily by
+//! asking again for each item. Items carry their qualified name in `data` so
+//! follow-up requests don't need to re-resolve the symbol.
+
+use std::collections::HashMap;
+
+use rocketindex::spider::{find_containing_symbol, reverse_spider, spider};
+use rocketindex::{CodeIndex, Reference, Symbol};
+use tower_lsp::lsp_types::{
+    CallHierarchyIncomingCall, CallHierarchyItem, CallHierarchyOutgoingCall, Range,
+};
+
+use crate::{lsp_symbol_kind, to_lsp_location};
+
+/// Build the item for `symbol`.
+pub fn to_item(index: &CodeIndex, symbol: &Symbol) -> CallHierarchyItem {
+    let location = to_lsp_location(&index.make_location_absolute(&symbol.location));
+    CallHierarchyItem {
+        name: symbol.name.clone(),
+        kind: lsp_symbol_kind(symbol),
+        tags: None,
+        detail: Some(symbol.qualified.clone()),
+        uri: location.uri,
+        range: location.range,
+        selection_range: location.range,
+        data: Some(serde_json::Value::String(symbol.qualified.clone())),
+    }
+}
+
+/// Qualified name of an item created by [`to_item`].
+pub fn item_qualified_name(item: &CallHierarchyItem) -> &str {
+    item.data
+        .as_ref()
+        .and_then(|data| data.as_str())
+        .or(item.detail.as_deref())
+        .unwrap_or(&item.name)
+}
+
+/// Callers of `qualified`, each with the ranges of its calls.
+pub fn incoming_calls(index: &CodeIndex, qualified: &str) -> Vec<CallHierarchyIncomingCall> {
+    let callers = reverse_spider(index, qualified, 1);
+
+    let mut ranges: HashMap<&str, Vec<Range>> = HashMap::new();
+    for reference in index.find_references(qualified) {
+        if let Some(caller) = find_containing_symbol(index, reference) {
+            ranges
+                .entry(caller.qualified.as_str())
+                .or_default()
+                .push(reference_range(index, reference));
+        }
+    }
+
+    callers
+        .at_depth(1)
+        .into_iter()
+        .filter_map(|node| {
+            let from_ranges = ranges.remove(node.symbol.qualified.as_str())?;
+            Some(CallHierarchyIncomingCall {
+                from: to_item(index, &node.symbol),
+                from_ranges,
+            })
+        })
+        .collect()
+}
+
+/// Symbols called from within `qualified`, each with the ranges of the calls.
+pub fn outgoing_calls(index: &CodeIndex, qualified: &str) -> Vec<CallHierarchyOutgoingCall> {
+    let Some(symbol) = index.get(qualified) else {
+        return Vec::new();
+    };
+
+    // spider follows every reference in the defining file; keep only the
+    // ones inside this symbol's body
+    let body: Vec<&Reference> = index
+        .references_in_file(&symbol.location.file)
+        .iter()
+        .filter(|reference| {
+            find_containing_symbol(index, reference)
+                .is_some_and(|container| container.qualified == symbol.qualified)
+        })
+        .collect();
+
+    spider(index, qualified, 1)
+        .at_depth(1)
+        .into_iter()
+        .filter_map(|node| {
+            let callee_refs = index.find_references(&node.symbol.qualified);
+            let from_ranges: Vec<Range> = body
+                .iter()
+                .filter(|reference| callee_refs.iter().any(|r| std::ptr::eq(*r, **reference)))
+                .map(|reference| reference_range(index, reference))
+                .collect();
+            (!from_ranges.is_empty()).then(|| CallHierarchyOutgoingCall {
+                to: to_item(index, &node.symbol),
+                from_ranges,
+            })
+        })
+        .collect()
+}
+
+fn reference_range(index: &CodeIndex, reference: &Reference) -> Range {
+    to_lsp_location(&index.make_location_absolute(&reference.location)).range
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocketindex::{Location, ReferenceKind, SymbolKind, Visibility};
+    use std::path::PathBuf;
+
+    fn function(name: &str, line: u32) -> Symbol {
+        Symbol::new(
+            name.to_string(),
+            format!("App.{}", name),
+            SymbolKind::Function,
+            Location::with_end(
+                PathBuf::from("src/App.fs"),
+                line,
+                5,
+                line,
+                5 + name.len() as u32,
+            ),
+            Visibility::Public,
+            "fsharp".to_string(),
+        )
+    }
+
+    fn call(name: &str, line: u32) -> Reference {
+        Reference {
+            name: name.to_string(),
+            location: Location::with_end(
+                PathBuf::from("src/App.fs"),
+                line,
+                5,
+                line,
+                5 + name.len() as u32,
+            ),
+            kind: ReferenceKind::Call,
+        }
+    }
+
+    /// `main` calls `helper` twice and `log` once; `helper` calls `log`.
+    fn index() -> CodeIndex {
+        let mut index = CodeIndex::with_root(PathBuf::from("/ws"));
+        index.add_symbol(function("log", 1));
+        index.add_symbol(function("helper", 3));
+        index.add_symbol(function("main", 6));
+        index.add_reference(PathBuf::from("src/App.fs"), call("log", 4));
+        index.add_reference(PathBuf::from("src/App.fs"), call("helper", 7));
+        index.add_reference(PathBuf::from("src/App.fs"), call("log", 8));
+        index.add_reference(PathBuf::from("src/App.fs"), call("helper", 9));
+        index
+    }
+
+    #[test]
+    fn incoming_calls_group_ranges_by_caller() {
+        let index = index();
+
+        let mut calls = incoming_calls(&index, "App.log");
+        calls.sort_by(|a, b| a.from.name.cmp(&b.from.name));
+
+        let callers: Vec<(&str, usize)> = calls
+            .iter()
+            .map(|c| (c.from.name.as_str(), c.from_ranges.len()))
+            .collect();
+        assert_eq!(callers, vec![("helper", 1), ("main", 1)]);
+        assert_eq!(calls[1].from_ranges[0].start.line, 7);
+        assert_eq!(item_qualified_name(&calls[0].from), "App.helper");
+        assert_eq!(calls[0].from.uri.path(), "/ws/src/App.fs");
+    }
+
+    #[test]
+    fn outgoing_calls_only_include_the_symbols_body() {
+        let index = index();
+
+        let mut calls = outgoing_calls(&index, "App.main");
+        calls.sort_by(|a, b| a.to.name.cmp(&b.to.name));
+
+        let callees: Vec<(&str, usize)> = calls
+            .iter()
+            .map(|c| (c.to.name.as_str(), c.from_ranges.len()))
+            .collect();
+        assert_eq!(callees, vec![("helper", 2), ("log", 1)]);
+
+        // helper's body only calls log, even though the file also calls helper
+        let calls = outgoing_calls(&index, "App.helper");
+        let callees: Vec<&str> = calls.iter().map(|c| c.to.name.as_str()).collect();
+        assert_eq!(callees, vec!["log"]);
+    }
+}
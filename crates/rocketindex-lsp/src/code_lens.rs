@@ -0,0 +1,61 @@
+//! Code lens support.
+//!
+//! Puts "N references" above each definition in a file. The counts are the
+//! ones the SQLite index maintains as files are reindexed, so a lens costs a
+//! lookup per file rather than a scan of every reference.
+
+use rocketindex::Symbol;
+use tower_lsp::lsp_types::{CodeLens, Command};
+
+use crate::to_lsp_location;
+
+/// A lens for each symbol, from [`rocketindex::SqliteIndex::reference_counts_in_file`].
+pub fn reference_lenses(counts: &[(Symbol, usize)]) -> Vec<CodeLens> {
+    counts
+        .iter()
+        .map(|(symbol, count)| CodeLens {
+            range: to_lsp_location(&symbol.location).range,
+            command: Some(Command {
+                title: match count {
+                    1 => "1 reference".to_string(),
+                    n => format!("{} references", n),
+                },
+                // Informational only; there's nothing to run
+                command: String::new(),
+                arguments: None,
+            }),
+            data: None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocketindex::{Location, SymbolKind, Visibility};
+    use std::path::PathBuf;
+
+    fn symbol(name: &str, line: u32) -> Symbol {
+        Symbol::new(
+            name.to_string(),
+            format!("Shop.{}", name),
+            SymbolKind::Function,
+            Location::new(PathBuf::from("/ws/src/Shop.fs"), line, 5),
+            Visibility::Public,
+            "fsharp".to_string(),
+        )
+    }
+
+    #[test]
+    fn lens_per_symbol_with_its_count() {
+        let lenses = reference_lenses(&[(symbol("total", 3), 1), (symbol("tax", 7), 4)]);
+        let titles: Vec<(u32, &str)> = lenses
+            .iter()
+            .map(|lens| {
+                let title = lens.command.as_ref().unwrap().title.as_str();
+                (lens.range.start.line, title)
+            })
+            .collect();
+        assert_eq!(titles, vec![(2, "1 reference"), (6, "4 references")]);
+    }
+}
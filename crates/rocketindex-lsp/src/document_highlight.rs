@@ -0,0 +1,108 @@
+//! Document highlight support.
+//!
+//! Highlights every occurrence of a symbol in one file: its definitions and
+//! the references that resolve to it, using the spans recorded when the file
+//! was indexed. Assignments are marked as writes and other uses that read the
+//! name (including calls) as reads; imports and type uses stay plain text.
+
+use std::path::Path;
+
+use rocketindex::{CodeIndex, ReferenceKind};
+use tower_lsp::lsp_types::{DocumentHighlight, DocumentHighlightKind};
+
+use crate::to_lsp_location;
+
+/// Occurrences of `qualified` in `file` (absolute), in source order.
+pub fn document_highlights(
+    index: &CodeIndex,
+    file: &Path,
+    qualified: &str,
+) -> Vec<DocumentHighlight> {
+    let definitions = index
+        .get_all(qualified)
+        .iter()
+        .map(|symbol| (&symbol.location, DocumentHighlightKind::WRITE));
+    let references = index
+        .find_references(qualified)
+        .into_iter()
+        .map(|reference| (&reference.location, highlight_kind(reference.kind)));
+
+    let mut highlights: Vec<DocumentHighlight> = definitions
+        .chain(references)
+        .map(|(location, kind)| (index.make_location_absolute(location), kind))
+        .filter(|(location, _)| location.file == file)
+        .map(|(location, kind)| DocumentHighlight {
+            range: to_lsp_location(&location).range,
+            kind: Some(kind),
+        })
+        .collect();
+    highlights.sort_by_key(|h| (h.range.start.line, h.range.start.character));
+    highlights.dedup_by_key(|h| h.range.start);
+    highlights
+}
+
+fn highlight_kind(kind: ReferenceKind) -> DocumentHighlightKind {
+    match kind {
+        ReferenceKind::Write => DocumentHighlightKind::WRITE,
+        ReferenceKind::Read | ReferenceKind::Call => DocumentHighlightKind::READ,
+        _ => DocumentHighlightKind::TEXT,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocketindex::{Location, Reference, Symbol, SymbolKind, Visibility};
+    use std::path::PathBuf;
+
+    fn reference(file: &str, line: u32, column: u32, kind: ReferenceKind) -> Reference {
+        Reference {
+            name: "total".to_string(),
+            location: Location::with_end(PathBuf::from(file), line, column, line, column + 5),
+            kind,
+        }
+    }
+
+    #[test]
+    fn highlights_definition_and_references_in_the_file() {
+        let mut index = CodeIndex::with_root(PathBuf::from("/ws"));
+        index.add_symbol(Symbol::new(
+            "total".to_string(),
+            "Shop.total".to_string(),
+            SymbolKind::Value,
+            Location::with_end(PathBuf::from("src/Shop.fs"), 3, 9, 3, 14),
+            Visibility::Public,
+            "fsharp".to_string(),
+        ));
+        for r in [
+            reference("src/Shop.fs", 7, 5, ReferenceKind::Write),
+            reference("src/Shop.fs", 5, 12, ReferenceKind::Call),
+            // Another file's use isn't highlighted here
+            reference("src/App.fs", 2, 1, ReferenceKind::Read),
+        ] {
+            let file = r.location.file.clone();
+            index.add_reference(file, r);
+        }
+
+        let highlights = document_highlights(&index, Path::new("/ws/src/Shop.fs"), "Shop.total");
+        let found: Vec<(u32, u32, u32, DocumentHighlightKind)> = highlights
+            .iter()
+            .map(|h| {
+                (
+                    h.range.start.line,
+                    h.range.start.character,
+                    h.range.end.character,
+                    h.kind.unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (2, 8, 13, DocumentHighlightKind::WRITE),
+                (4, 11, 16, DocumentHighlightKind::READ),
+                (6, 4, 9, DocumentHighlightKind::WRITE),
+            ]
+        );
+    }
+}
@@ -148,6 +148,11 @@ impl DocumentStore {
         // Fall back to reading from disk (async I/O)
         tokio::fs::read_to_string(path).await.ok()
     }
+
+    /// Copies of every open document, keyed by path.
+    pub async fn snapshot(&self) -> HashMap<PathBuf, Document> {
+        self.documents.read().await.clone()
+    }
 }
 
 impl Clone for DocumentStore {
@@ -0,0 +1,201 @@
+//! Document symbol (outline) support.
+//!
+//! Builds hierarchical `DocumentSymbol` trees from the symbols indexed for a
+//! file. Nesting follows qualified names: a symbol becomes a child of the
+//! longest other symbol in the same file whose qualified name is a prefix of
+//! its own (module → type → member).
+
+use std::path::Path;
+
+use rocketindex::{CodeIndex, Symbol};
+use tower_lsp::lsp_types::{DocumentSymbol, Position, Range};
+
+use crate::lsp_symbol_kind;
+
+/// Separators between qualified-name segments across supported languages.
+const SEPARATORS: [&str; 4] = ["::", ".", "#", "\\"];
+
+/// Build the outline for `file` (absolute or workspace-relative).
+pub fn document_symbols(index: &CodeIndex, file: &Path) -> Vec<DocumentSymbol> {
+    let relative = index
+        .workspace_root()
+        .and_then(|root| file.strip_prefix(root).ok())
+        .unwrap_or(file);
+
+    let mut symbols: Vec<&Symbol> = index
+        .symbols_in_file(file)
+        .into_iter()
+        // symbols_in_file returns every definition of a name, including
+        // overloads that live in other files
+        .filter(|s| s.location.file == relative || s.location.file == file)
+        .collect();
+    symbols.sort_by_key(|s| (s.location.line, s.location.column));
+    symbols.dedup_by(|a, b| std::ptr::eq(*a, *b));
+
+    build_tree(&symbols)
+}
+
+/// Nest `symbols` (sorted by position) by qualified name.
+fn build_tree(symbols: &[&Symbol]) -> Vec<DocumentSymbol> {
+    let parents: Vec<Option<usize>> = symbols
+        .iter()
+        .enumerate()
+        .map(|(i, symbol)| {
+            symbols
+                .iter()
+                .enumerate()
+                .filter(|(j, candidate)| {
+                    *j != i && is_enclosing(&candidate.qualified, &symbol.qualified)
+                })
+                .max_by_key(|(_, candidate)| candidate.qualified.len())
+                .map(|(j, _)| j)
+        })
+        .collect();
+
+    (0..symbols.len())
+        .filter(|&i| parents[i].is_none())
+        .map(|i| to_document_symbol(symbols, &parents, i))
+        .collect()
+}
+
+fn to_document_symbol(
+    symbols: &[&Symbol],
+    parents: &[Option<usize>],
+    index: usize,
+) -> DocumentSymbol {
+    let symbol = symbols[index];
+    let children: Vec<DocumentSymbol> = (0..symbols.len())
+        .filter(|&i| parents[i] == Some(index))
+        .map(|i| to_document_symbol(symbols, parents, i))
+        .collect();
+
+    let selection_range = to_range(&symbol.location);
+    // Symbols only record the span of their name, so widen the full range to
+    // cover the children; clients expect children inside their parent's range
+    let mut range = selection_range;
+    for child in &children {
+        if (child.range.end.line, child.range.end.character) > (range.end.line, range.end.character)
+        {
+            range.end = child.range.end;
+        }
+    }
+
+    #[allow(deprecated)]
+    DocumentSymbol {
+        name: symbol.name.clone(),
+        detail: symbol.signature.clone(),
+        kind: lsp_symbol_kind(symbol),
+        tags: None,
+        deprecated: None,
+        range,
+        selection_range,
+        children: (!children.is_empty()).then_some(children),
+    }
+}
+
+/// Whether `outer` is a proper qualified-name prefix of `inner`.
+fn is_enclosing(outer: &str, inner: &str) -> bool {
+    inner.strip_prefix(outer).is_some_and(|rest| {
+        SEPARATORS
+            .iter()
+            .any(|sep| rest.len() > sep.len() && rest.starts_with(sep))
+    })
+}
+
+fn to_range(location: &rocketindex::Location) -> Range {
+    let start = Position {
+        line: location.line.saturating_sub(1),
+        character: location.column.saturating_sub(1),
+    };
+    let end = Position {
+        line: location.end_line.saturating_sub(1),
+        character: location.end_column.saturating_sub(1),
+    };
+    // Locations without an end (e.g. loaded from older indexes) collapse to the start
+    Range {
+        start,
+        end: if (end.line, end.character) < (start.line, start.character) {
+            start
+        } else {
+            end
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocketindex::{Location, SymbolKind, Visibility};
+    use std::path::PathBuf;
+
+    fn symbol(name: &str, qualified: &str, kind: SymbolKind, file: &str, line: u32) -> Symbol {
+        Symbol::new(
+            name.to_string(),
+            qualified.to_string(),
+            kind,
+            Location::with_end(PathBuf::from(file), line, 5, line, 5 + name.len() as u32),
+            Visibility::Public,
+            "fsharp".to_string(),
+        )
+    }
+
+    #[test]
+    fn nests_members_under_types_under_modules() {
+        let mut index = CodeIndex::with_root(PathBuf::from("/ws"));
+        index.add_symbol(symbol("Shop", "Shop", SymbolKind::Module, "src/Shop.fs", 1));
+        index.add_symbol(symbol(
+            "Order",
+            "Shop.Order",
+            SymbolKind::Record,
+            "src/Shop.fs",
+            3,
+        ));
+        index.add_symbol(symbol(
+            "Id",
+            "Shop.Order.Id",
+            SymbolKind::Member,
+            "src/Shop.fs",
+            4,
+        ));
+        index.add_symbol(symbol(
+            "total",
+            "Shop.total",
+            SymbolKind::Function,
+            "src/Shop.fs",
+            9,
+        ));
+        // Same short name in another file must not leak into this outline
+        index.add_symbol(symbol(
+            "total",
+            "Other.total",
+            SymbolKind::Function,
+            "src/Other.fs",
+            2,
+        ));
+
+        let outline = document_symbols(&index, Path::new("/ws/src/Shop.fs"));
+
+        assert_eq!(outline.len(), 1);
+        let module = &outline[0];
+        assert_eq!(module.name, "Shop");
+        // The module's range grows to include its last child
+        assert_eq!(module.range.end.line, 8);
+
+        let children = module.children.as_ref().unwrap();
+        let names: Vec<&str> = children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["Order", "total"]);
+
+        let fields = children[0].children.as_ref().unwrap();
+        assert_eq!(fields.len(), 1);
+        assert_eq!(fields[0].name, "Id");
+        assert!(fields[0].children.is_none());
+    }
+
+    #[test]
+    fn prefix_must_end_at_a_segment_boundary() {
+        assert!(is_enclosing("geo::Point", "geo::Point::new"));
+        assert!(is_enclosing("App", "App.run"));
+        assert!(!is_enclosing("App", "Application.run"));
+        assert!(!is_enclosing("App", "App"));
+    }
+}
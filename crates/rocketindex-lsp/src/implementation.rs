@@ -0,0 +1,127 @@
+//! Go to implementation support.
+//!
+//! From an interface or trait, jumps to every type that implements or
+//! derives it, matched by bare name so `IComparable` finds
+//! `System.IComparable<T>` in any language. From a member of an interface,
+//! jumps to the members of the same name on those types.
+
+use rocketindex::{CodeIndex, Symbol, SymbolKind};
+use tower_lsp::lsp_types::Location;
+
+use crate::to_lsp_location;
+
+/// Implementations of `symbol`, in location order.
+pub fn implementation_locations(index: &CodeIndex, symbol: &Symbol) -> Vec<Location> {
+    let implementations: Vec<&Symbol> = match interface_of_member(index, symbol) {
+        Some((interface, separator)) => index
+            .find_implementers(&interface.qualified)
+            .into_iter()
+            .flat_map(|implementer| {
+                let member = format!("{}{}{}", implementer.qualified, separator, symbol.name);
+                index.get_all(&member)
+            })
+            .collect(),
+        None => index.find_implementers(&symbol.qualified),
+    };
+    implementations
+        .into_iter()
+        .map(|symbol| to_lsp_location(&index.make_location_absolute(&symbol.location)))
+        .collect()
+}
+
+/// The interface `symbol` is a member of, and the separator between their
+/// names (`.`, `::`, `#`).
+fn interface_of_member<'a>(index: &'a CodeIndex, symbol: &Symbol) -> Option<(&'a Symbol, &'a str)> {
+    let owner = symbol.qualified.strip_suffix(&symbol.name)?;
+    let separator = ["::", ".", "#"]
+        .into_iter()
+        .find(|separator| owner.ends_with(separator))?;
+    let interface = index.get(&owner[..owner.len() - separator.len()])?;
+    (interface.kind == SymbolKind::Interface).then_some((interface, separator))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocketindex::{Location as IndexLocation, Visibility};
+    use std::path::PathBuf;
+
+    fn symbol(qualified: &str, kind: SymbolKind, file: &str, implements: &[&str]) -> Symbol {
+        let name = qualified.rsplit(['.', ':']).next().unwrap().to_string();
+        let mut symbol = Symbol::new(
+            name,
+            qualified.to_string(),
+            kind,
+            IndexLocation::new(PathBuf::from(file), 1, 1),
+            Visibility::Public,
+            "rust".to_string(),
+        );
+        if !implements.is_empty() {
+            symbol.implements = Some(implements.iter().map(|s| s.to_string()).collect());
+        }
+        symbol
+    }
+
+    fn index() -> CodeIndex {
+        let mut index = CodeIndex::with_root(PathBuf::from("/ws"));
+        for symbol in [
+            symbol("shapes::Area", SymbolKind::Interface, "src/shapes.rs", &[]),
+            symbol(
+                "shapes::Area::area",
+                SymbolKind::Function,
+                "src/shapes.rs",
+                &[],
+            ),
+            symbol(
+                "Square",
+                SymbolKind::Class,
+                "src/square.rs",
+                &["shapes::Area"],
+            ),
+            symbol("Square::area", SymbolKind::Function, "src/square.rs", &[]),
+            symbol(
+                "Circle",
+                SymbolKind::Class,
+                "src/circle.rs",
+                &["Area", "Debug"],
+            ),
+            symbol("Circle::area", SymbolKind::Function, "src/circle.rs", &[]),
+            symbol("Line", SymbolKind::Class, "src/line.rs", &["Debug"]),
+        ] {
+            index.add_symbol(symbol);
+        }
+        index
+    }
+
+    fn files(locations: Vec<Location>) -> Vec<String> {
+        locations
+            .into_iter()
+            .map(|l| l.uri.path().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn interface_jumps_to_implementing_types() {
+        let index = index();
+        let area = index.get("shapes::Area").unwrap();
+        assert_eq!(
+            files(implementation_locations(&index, area)),
+            vec!["/ws/src/circle.rs", "/ws/src/square.rs"]
+        );
+    }
+
+    #[test]
+    fn interface_member_jumps_to_implementing_members() {
+        let index = index();
+        let area = index.get("shapes::Area::area").unwrap();
+        let locations = implementation_locations(&index, area);
+        assert_eq!(
+            files(locations),
+            vec!["/ws/src/circle.rs", "/ws/src/square.rs"]
+        );
+
+        // Members of a type that isn't an interface have no implementations
+        let area = index.get("Square::area").unwrap();
+        assert!(implementation_locations(&index, area).is_empty());
+    }
+}
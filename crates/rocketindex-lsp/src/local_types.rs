@@ -0,0 +1,440 @@
+//! Shallow type inference for let-bound locals.
+//!
+//! Locals aren't indexed and rarely appear in the type cache, so hover would
+//! show nothing for them. Instead we find the nearest `let`/`use` binding of
+//! the name above the cursor and look at its right-hand side:
+//!
+//! - an explicit annotation (`let total: decimal = ...`) wins;
+//! - literals have their F# type (`42L` is `int64`, `"a"` is `string`);
+//! - lists, arrays and tuples of literals combine their element types;
+//! - `new Order(...)` or calling a type (`Order(...)`) gives that type;
+//! - a record expression gives the record declaring all of its fields;
+//! - calling a function gives what's left of its type (from the type cache,
+//!   else its signature) after the arguments passed.
+//!
+//! Anything else stays unknown rather than guessed.
+
+use std::path::Path;
+
+use rocketindex::{CodeIndex, SymbolKind};
+
+/// The type of local `name` as seen from zero-based `line` of `content`.
+pub fn infer_local_type(
+    index: &CodeIndex,
+    content: &str,
+    file: &Path,
+    name: &str,
+    line: u32,
+) -> Option<String> {
+    let lines: Vec<&str> = content.lines().collect();
+    let last = (line as usize).min(lines.len().checked_sub(1)?);
+    for i in (0..=last).rev() {
+        let Some((annotation, expr)) = binding_of(lines[i], name) else {
+            continue;
+        };
+        if let Some(annotation) = annotation {
+            return Some(annotation.to_string());
+        }
+        // `let x =` with the value on the following line
+        let expr = if expr.is_empty() {
+            lines.get(i + 1).map(|l| l.trim()).unwrap_or_default()
+        } else {
+            expr
+        };
+        return infer_expr(index, file, expr);
+    }
+    None
+}
+
+/// The annotation and right-hand side of a binding of `name` on `line`.
+fn binding_of<'a>(line: &'a str, name: &str) -> Option<(Option<&'a str>, &'a str)> {
+    let rest = line.trim_start();
+    let rest = ["let!", "let", "use!", "use"]
+        .iter()
+        .find_map(|keyword| rest.strip_prefix(keyword))
+        .filter(|rest| rest.starts_with(char::is_whitespace))?
+        .trim_start();
+    let rest = rest.strip_prefix("mutable ").unwrap_or(rest).trim_start();
+    let rest = rest.strip_prefix(name)?;
+    if rest.starts_with(|c: char| c.is_alphanumeric() || c == '_' || c == '\'') {
+        return None;
+    }
+
+    let (head, expr) = rest.split_once('=')?;
+    let head = head.trim();
+    let annotation = match head.strip_prefix(':') {
+        Some(ty) => Some(ty.trim()).filter(|ty| !ty.is_empty()),
+        // Anything else before `=` means parameters: a local function
+        None if head.is_empty() => None,
+        None => return None,
+    };
+    Some((annotation, expr.trim()))
+}
+
+fn infer_expr(index: &CodeIndex, file: &Path, expr: &str) -> Option<String> {
+    let expr = expr.trim();
+    if expr.is_empty() {
+        return None;
+    }
+    if let Some(ty) = literal_type(expr) {
+        return Some(ty);
+    }
+    if let Some(inner) = strip_delimiters(expr, "[|", "|]") {
+        return Some(format!("{} array", element_type(index, file, inner)?));
+    }
+    if let Some(inner) = strip_delimiters(expr, "[", "]") {
+        return Some(format!("{} list", element_type(index, file, inner)?));
+    }
+    if let Some(inner) = strip_delimiters(expr, "{", "}") {
+        return record_type(index, inner);
+    }
+    if let Some(inner) = strip_delimiters(expr, "(", ")") {
+        let items = split_top_level(inner, ',');
+        if items.len() > 1 {
+            let types = items
+                .iter()
+                .map(|item| infer_expr(index, file, item))
+                .collect::<Option<Vec<_>>>()?;
+            return Some(types.join(" * "));
+        }
+        return infer_expr(index, file, inner);
+    }
+    application_type(index, file, expr.strip_prefix("new ").unwrap_or(expr))
+}
+
+/// F# type of a literal, including numeric suffixes.
+fn literal_type(expr: &str) -> Option<String> {
+    let ty = match expr {
+        "true" | "false" => "bool",
+        "()" => "unit",
+        _ if is_string_literal(expr) => "string",
+        _ if expr.len() >= 3 && expr.starts_with('\'') && expr.ends_with('\'') => "char",
+        _ if expr.starts_with(|c: char| c.is_ascii_digit())
+            || (expr.starts_with('-') && expr[1..].starts_with(|c: char| c.is_ascii_digit())) =>
+        {
+            number_type(expr.trim_start_matches('-'))?
+        }
+        _ => return None,
+    };
+    Some(ty.to_string())
+}
+
+fn is_string_literal(expr: &str) -> bool {
+    let body = expr.trim_start_matches(['$', '@']).trim_end_matches('B');
+    body.len() >= 2
+        && body.starts_with('"')
+        && body.ends_with('"')
+        // One literal, not `"a" + "b"` or a call on one
+        && !body[1..body.len() - 1]
+            .replace("\\\"", "")
+            .contains('"')
+}
+
+fn number_type(number: &str) -> Option<&'static str> {
+    let (digits, suffix) = match number.strip_prefix("0x") {
+        Some(hex) => {
+            let end = hex
+                .find(|c: char| !(c.is_ascii_hexdigit() || c == '_'))
+                .unwrap_or(hex.len());
+            (&hex[..end], &hex[end..])
+        }
+        None => {
+            let end = number
+                .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '_' || c == 'e'))
+                .unwrap_or(number.len());
+            number.split_at(end)
+        }
+    };
+    let is_float = !number.starts_with("0x") && (digits.contains('.') || digits.contains('e'));
+    let ty = match (suffix, is_float) {
+        ("", false) => "int",
+        ("", true) => "float",
+        ("f" | "F", _) => "float32",
+        ("m" | "M", _) => "decimal",
+        ("L", false) => "int64",
+        ("u", false) => "uint32",
+        ("UL", false) => "uint64",
+        ("y", false) => "sbyte",
+        ("uy", false) => "byte",
+        ("s", false) => "int16",
+        ("us", false) => "uint16",
+        ("n", false) => "nativeint",
+        ("I", false) => "bigint",
+        _ => return None,
+    };
+    Some(ty)
+}
+
+fn strip_delimiters<'a>(expr: &'a str, open: &str, close: &str) -> Option<&'a str> {
+    let inner = expr.strip_prefix(open)?.strip_suffix(close)?;
+    // `(a) + (b)` starts and ends with parentheses but isn't one group
+    let mut depth = 0i32;
+    for c in inner.chars() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            _ => {}
+        }
+        if depth < 0 {
+            return None;
+        }
+    }
+    Some(inner.trim())
+}
+
+fn split_top_level(text: &str, separator: char) -> Vec<&str> {
+    let mut items = Vec::new();
+    let mut depth = 0i32;
+    let mut in_string = false;
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '(' | '[' | '{' if !in_string => depth += 1,
+            ')' | ']' | '}' if !in_string => depth -= 1,
+            _ if c == separator && depth == 0 && !in_string => {
+                items.push(text[start..i].trim());
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    items.push(text[start..].trim());
+    items.retain(|item| !item.is_empty());
+    items
+}
+
+/// Type of the elements of a list or array literal, from its first element.
+fn element_type(index: &CodeIndex, file: &Path, inner: &str) -> Option<String> {
+    let first = split_top_level(inner, ';').into_iter().next()?;
+    // Ranges like `1 .. 10`
+    let first = first.split("..").next().unwrap_or(first);
+    infer_expr(index, file, first)
+}
+
+/// The record type declaring every field set in `{ A = ...; B = ... }`.
+fn record_type(index: &CodeIndex, inner: &str) -> Option<String> {
+    // `{ r with ... }` has the type of `r`, which we don't know
+    if inner.contains(" with ") {
+        return None;
+    }
+    let fields: Vec<&str> = split_top_level(inner, ';')
+        .into_iter()
+        .filter_map(|assignment| assignment.split_once('=').map(|(field, _)| field.trim()))
+        .collect();
+    if fields.is_empty() {
+        return None;
+    }
+    index
+        .symbols()
+        .filter(|symbol| symbol.kind == SymbolKind::Record)
+        .find(|symbol| {
+            symbol.signature.as_deref().is_some_and(|signature| {
+                fields
+                    .iter()
+                    .all(|field| signature.contains(&format!("{}:", field)))
+            })
+        })
+        .map(|symbol| symbol.name.clone())
+}
+
+/// Type of `callee arg1 arg2` or `callee(args)`: the type itself when the
+/// callee is a type, otherwise the callee's type after the arguments.
+fn application_type(index: &CodeIndex, file: &Path, expr: &str) -> Option<String> {
+    let callee_end = expr
+        .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.' || c == '\''))
+        .unwrap_or(expr.len());
+    let (callee, args) = expr.split_at(callee_end);
+    if callee.is_empty() || callee.ends_with('.') {
+        return None;
+    }
+    let args = args.trim();
+    // Operators and pipelines aren't followed
+    if args.starts_with(|c: char| "+-*/%<>=|&^@:".contains(c)) {
+        return None;
+    }
+
+    let resolved = index
+        .resolve(callee, file)
+        .or_else(|| index.resolve_dotted(callee, file))?;
+    let symbol = resolved.symbol;
+    match symbol.kind {
+        SymbolKind::Class | SymbolKind::Record | SymbolKind::Type | SymbolKind::Union => {
+            Some(symbol.name.clone())
+        }
+        _ => {
+            let signature = index
+                .get_symbol_type(&symbol.qualified)
+                .or(symbol.signature.as_deref())?;
+            let applied = if args.starts_with('(') {
+                1
+            } else {
+                split_args(args).len()
+            };
+            apply(signature, applied)
+        }
+    }
+}
+
+/// Arguments of a curried application: `a (f b) "c"` is three.
+fn split_args(args: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let mut depth = 0i32;
+    let mut in_string = false;
+    let mut start = None;
+    for (i, c) in args.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '(' | '[' | '{' if !in_string => depth += 1,
+            ')' | ']' | '}' if !in_string => depth -= 1,
+            _ => {}
+        }
+        if c.is_whitespace() && depth == 0 && !in_string {
+            if let Some(s) = start.take() {
+                items.push(&args[s..i]);
+            }
+        } else if start.is_none() {
+            start = Some(i);
+        }
+    }
+    if let Some(s) = start {
+        items.push(&args[s..]);
+    }
+    items
+}
+
+/// What's left of `signature` (`a -> b -> c`) after applying `applied`
+/// arguments. A signature of only parameter types can't tell us the result.
+fn apply(signature: &str, applied: usize) -> Option<String> {
+    let parts = split_arrows(signature);
+    if applied == 0 {
+        return Some(signature.trim().to_string());
+    }
+    (applied < parts.len()).then(|| parts[applied..].join(" -> "))
+}
+
+fn split_arrows(signature: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0i32;
+    let mut start = 0;
+    let bytes = signature.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'(' | b'<' => depth += 1,
+            b')' => depth -= 1,
+            b'>' if i > 0 && bytes[i - 1] == b'-' && depth == 0 => {
+                parts.push(signature[start..i - 1].trim());
+                start = i + 1;
+            }
+            b'>' => depth -= 1,
+            _ => {}
+        }
+        i += 1;
+    }
+    parts.push(signature[start..].trim());
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocketindex::{Location, Symbol, Visibility};
+    use std::path::PathBuf;
+
+    fn index() -> CodeIndex {
+        let mut index = CodeIndex::with_root(PathBuf::from("/ws"));
+        for (name, kind, signature) in [
+            (
+                "Order",
+                SymbolKind::Record,
+                Some("{ Id: int; Total: decimal }"),
+            ),
+            ("Customer", SymbolKind::Class, None),
+            ("add", SymbolKind::Function, Some("int -> int -> int")),
+            ("untyped", SymbolKind::Function, Some("int")),
+        ] {
+            index.add_symbol(
+                Symbol::new(
+                    name.to_string(),
+                    format!("Shop.{}", name),
+                    kind,
+                    Location::new(PathBuf::from("src/Shop.fs"), 1, 1),
+                    Visibility::Public,
+                    "fsharp".to_string(),
+                )
+                .with_signature(signature.map(str::to_string)),
+            );
+        }
+        index
+    }
+
+    fn infer(source: &str, name: &str) -> Option<String> {
+        let line = source.lines().count() as u32;
+        infer_local_type(&index(), source, Path::new("/ws/src/Shop.fs"), name, line)
+    }
+
+    #[test]
+    fn literals_and_collections() {
+        assert_eq!(infer("let x = 42", "x").as_deref(), Some("int"));
+        assert_eq!(infer("let x = -1.5", "x").as_deref(), Some("float"));
+        assert_eq!(infer("let x = 10L", "x").as_deref(), Some("int64"));
+        assert_eq!(infer("let x = 9.99M", "x").as_deref(), Some("decimal"));
+        assert_eq!(infer("let x = 3.5f", "x").as_deref(), Some("float32"));
+        assert_eq!(infer("let x = 0xFFu", "x").as_deref(), Some("uint32"));
+        assert_eq!(
+            infer("let mutable s = $\"hi {n}\"", "s").as_deref(),
+            Some("string")
+        );
+        assert_eq!(infer("  use c = 'c'", "c").as_deref(), Some("char"));
+        assert_eq!(
+            infer("let xs = [1; 2; 3]", "xs").as_deref(),
+            Some("int list")
+        );
+        assert_eq!(
+            infer("let xs = [| \"a\" |]", "xs").as_deref(),
+            Some("string array")
+        );
+        assert_eq!(
+            infer("let p = (1, true)", "p").as_deref(),
+            Some("int * bool")
+        );
+        // Annotations win
+        assert_eq!(infer("let n: uint8 = 1uy", "n").as_deref(), Some("uint8"));
+        // Expressions we can't type stay unknown
+        assert_eq!(infer("let x = \"a\" + \"b\"", "x"), None);
+        assert_eq!(infer("let x = y + 1", "x"), None);
+    }
+
+    #[test]
+    fn constructors_records_and_calls() {
+        assert_eq!(
+            infer("let o = { Id = 1; Total = 2M }", "o").as_deref(),
+            Some("Order")
+        );
+        assert_eq!(
+            infer("let c = new Customer(\"a\")", "c").as_deref(),
+            Some("Customer")
+        );
+        assert_eq!(
+            infer("let c = Customer()", "c").as_deref(),
+            Some("Customer")
+        );
+        assert_eq!(infer("let n = add 1 2", "n").as_deref(), Some("int"));
+        assert_eq!(
+            infer("let inc = add 1", "inc").as_deref(),
+            Some("int -> int")
+        );
+        // The signature lists only parameter types; the result is unknown
+        assert_eq!(infer("let r = untyped 1", "r"), None);
+    }
+
+    #[test]
+    fn nearest_binding_above_the_cursor() {
+        let source = "let x = 1\nlet f y =\n    let x =\n        \"shadowed\"\n    x";
+        assert_eq!(infer(source, "x").as_deref(), Some("string"));
+        // `f` is a function, not a value binding
+        assert_eq!(infer(source, "f"), None);
+        assert_eq!(infer(source, "missing"), None);
+    }
+}
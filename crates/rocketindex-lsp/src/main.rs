@@ -3,6 +3,9 @@
 //! This server provides:
 //! - Go-to-definition
 //! - Workspace symbol search
+//! - Document symbols (outline)
+//! - Call hierarchy (incoming/outgoing calls)
+//! - Signature help
 //! - Incremental file indexing on save
 //! - In-memory document tracking for unsaved changes
 //! - Syntax error diagnostics
@@ -11,8 +14,18 @@
 //! Storage: Uses SQLite database (.rocketindex/index.db) for persistence,
 //! loaded into memory as CodeIndex for fast resolution.
 
+mod call_hierarchy;
+mod code_lens;
 mod completion;
+mod document_highlight;
 mod document_store;
+mod document_symbols;
+mod implementation;
+mod local_types;
+mod references;
+mod rename;
+mod signature_help;
+mod workspace_symbols;
 
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -20,8 +33,8 @@ use std::sync::Arc;
 use anyhow::Result;
 use document_store::DocumentStore;
 use rocketindex::{
-    config::Config, db::DEFAULT_DB_NAME, extract_symbols, watch::find_source_files, CodeIndex,
-    SqliteIndex, SyntaxError,
+    config::Config, db::DEFAULT_DB_NAME, extract_symbols, watch::find_source_files,
+    write_lock::DEFAULT_LOCK_TIMEOUT, CodeIndex, SqliteIndex, SyntaxError,
 };
 use tokio::sync::RwLock;
 use tower_lsp::jsonrpc::Result as LspResult;
@@ -55,9 +68,37 @@ struct Backend {
     documents: DocumentStore,
     /// Maximum recursion depth for parsing (from config)
     max_recursion_depth: Arc<RwLock<usize>>,
+    /// Which kinds of workspace edit the client applies
+    edit_support: Arc<RwLock<rename::EditSupport>>,
 }
 
 impl Backend {
+    /// Handle `rocketindex/writeReferences`: the places the value under the
+    /// cursor is assigned or mutated, without its declaration.
+    async fn write_references(
+        &self,
+        params: TextDocumentPositionParams,
+    ) -> LspResult<Option<Vec<Location>>> {
+        let file = match params.text_document.uri.to_file_path() {
+            Ok(f) => f,
+            Err(_) => return Ok(None),
+        };
+        let word = match self.get_symbol_at_position(&file, params.position).await {
+            Some(w) => w,
+            None => return Ok(None),
+        };
+
+        info!("Finding writes of: {}", word);
+
+        let index = self.index.read().await;
+        let locations = index
+            .resolve(&word, &file)
+            .or_else(|| index.resolve_dotted(&word, &file))
+            .map(|result| references::write_locations(&index, &result.symbol.qualified))
+            .unwrap_or_default();
+        Ok((!locations.is_empty()).then_some(locations))
+    }
+
     /// Get the path to the SQLite database.
     fn get_db_path(root: &Path) -> PathBuf {
         root.join(".rocketindex").join(DEFAULT_DB_NAME)
@@ -106,6 +147,7 @@ impl Backend {
             let mut all_symbols = Vec::new();
             let mut all_references = Vec::new();
             let mut all_opens = Vec::new();
+            let mut all_aliases = Vec::new();
 
             for file in &files {
                 let symbols = sqlite_index.symbols_in_file(file)?;
@@ -120,6 +162,10 @@ impl Backend {
                 for open in opens {
                     all_opens.push((file.clone(), open));
                 }
+
+                for alias in sqlite_index.aliases_for_file(file)? {
+                    all_aliases.push((file.clone(), alias));
+                }
             }
 
             Ok::<_, anyhow::Error>((
@@ -128,16 +174,28 @@ impl Backend {
                 all_symbols,
                 all_references,
                 all_opens,
+                all_aliases,
                 files.len(),
             ))
         })
         .await??;
 
-        let (workspace_root, file_order, all_symbols, all_references, all_opens, file_count) =
-            load_result;
+        let (
+            workspace_root,
+            file_order,
+            all_symbols,
+            all_references,
+            all_opens,
+            all_aliases,
+            file_count,
+        ) = load_result;
 
         // Build CodeIndex from loaded data (non-blocking)
         let mut code_index = CodeIndex::with_root(workspace_root);
+        if let Some(root) = code_index.workspace_root() {
+            let rails = Config::load(root).rails;
+            code_index.set_rails_conventions(rails);
+        }
 
         if let Some(order) = file_order {
             code_index.set_file_order(order);
@@ -155,6 +213,10 @@ impl Backend {
             code_index.add_open(file, open);
         }
 
+        for (file, alias) in all_aliases {
+            code_index.add_alias(file, alias);
+        }
+
         let mut index = self.index.write().await;
         *index = code_index;
 
@@ -197,6 +259,7 @@ impl Backend {
 
         // Set workspace root for relative path storage
         index.set_workspace_root(root_path.clone());
+        index.set_rails_conventions(Config::load(&root_path).rails);
 
         // Index external assemblies from .fsproj files
         self.index_external_assemblies(&mut index, &root_path).await;
@@ -218,10 +281,11 @@ impl Backend {
 
     /// Index external assemblies based on .fsproj package references.
     async fn index_external_assemblies(&self, index: &mut CodeIndex, root_path: &Path) {
-        use rocketindex::external_index::index_external_assemblies;
+        use rocketindex::external_index::index_workspace_assemblies;
         use rocketindex::fsproj::{find_fsproj_files, parse_fsproj};
 
         let fsproj_files = find_fsproj_files(root_path);
+        let is_dotnet = !fsproj_files.is_empty();
 
         let mut all_packages = Vec::new();
 
@@ -235,9 +299,10 @@ impl Backend {
         all_packages.sort_by(|a, b| a.name.cmp(&b.name));
         all_packages.dedup_by(|a, b| a.name == b.name);
 
-        if !all_packages.is_empty() {
+        // Framework types are worth knowing in any .NET project, packages or not
+        if is_dotnet || !all_packages.is_empty() {
             info!("Indexing {} external packages", all_packages.len());
-            let external_index = index_external_assemblies(&all_packages);
+            let external_index = index_workspace_assemblies(root_path, &all_packages);
             index.set_external_index(external_index);
         }
     }
@@ -272,6 +337,11 @@ impl Backend {
             index.add_open(file.clone(), open);
         }
 
+        // Add module aliases
+        for alias in result.aliases {
+            index.add_alias(file.clone(), alias);
+        }
+
         Ok(())
     }
 
@@ -298,6 +368,9 @@ impl Backend {
             for open in &result.opens {
                 index.add_open(file.clone(), open.clone());
             }
+            for alias in &result.aliases {
+                index.add_alias(file.clone(), alias.clone());
+            }
         }
 
         // Update SQLite if it exists - runs in blocking task to avoid blocking async runtime
@@ -315,11 +388,21 @@ impl Backend {
                     .enumerate()
                     .map(|(i, open)| (open.clone(), i as u32 + 1))
                     .collect();
+                let aliases = result.aliases.clone();
+                let metrics = result.metrics;
 
                 // Run SQLite operations in a blocking task
                 let update_result = tokio::task::spawn_blocking(move || {
                     let sqlite_index = SqliteIndex::open(&db_path)?;
+                    let _lock = sqlite_index.lock_for_writing(DEFAULT_LOCK_TIMEOUT)?;
                     sqlite_index.update_file_data(&file_clone, &symbols, &references, &opens)?;
+                    let alias_rows: Vec<_> = aliases
+                        .iter()
+                        .enumerate()
+                        .map(|(i, alias)| (file_clone.as_path(), alias, i as u32 + 1))
+                        .collect();
+                    sqlite_index.insert_aliases(&alias_rows)?;
+                    sqlite_index.set_file_metrics(&file_clone, &metrics)?;
                     Ok::<_, anyhow::Error>(())
                 })
                 .await;
@@ -335,6 +418,64 @@ impl Backend {
         Ok(())
     }
 
+    /// String-literal references to `qualified` recorded in the SQLite index
+    /// (only there when it was built with `--string-refs`).
+    async fn string_references(&self, qualified: &str) -> Vec<rocketindex::Reference> {
+        let Some(root) = self.workspace_root.read().await.clone() else {
+            return Vec::new();
+        };
+        let db_path = Self::get_db_path(&root);
+        if !db_path.exists() {
+            return Vec::new();
+        }
+
+        let qualified = qualified.to_string();
+        let found = tokio::task::spawn_blocking(move || {
+            SqliteIndex::open(&db_path)?.find_string_references(&qualified)
+        })
+        .await;
+        match found {
+            Ok(Ok(refs)) => refs,
+            Ok(Err(e)) => {
+                warn!("Failed to read string references: {}", e);
+                Vec::new()
+            }
+            Err(e) => {
+                warn!("String reference lookup panicked: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Definitions in `file` with their reference counts, from the SQLite
+    /// index (empty when there isn't one).
+    async fn reference_counts(&self, file: &Path) -> Vec<(rocketindex::Symbol, usize)> {
+        let Some(root) = self.workspace_root.read().await.clone() else {
+            return Vec::new();
+        };
+        let db_path = Self::get_db_path(&root);
+        if !db_path.exists() {
+            return Vec::new();
+        }
+
+        let file = file.to_path_buf();
+        let found = tokio::task::spawn_blocking(move || {
+            SqliteIndex::open(&db_path)?.reference_counts_in_file(&file)
+        })
+        .await;
+        match found {
+            Ok(Ok(counts)) => counts,
+            Ok(Err(e)) => {
+                warn!("Failed to read reference counts: {}", e);
+                Vec::new()
+            }
+            Err(e) => {
+                warn!("Reference count lookup panicked: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
     /// Publish diagnostics for a file based on syntax errors.
     async fn publish_diagnostics(&self, uri: &Url, errors: Vec<SyntaxError>) {
         let diagnostics: Vec<Diagnostic> = errors
@@ -419,6 +560,15 @@ impl Backend {
     }
 }
 
+/// The LSP SymbolKind of `symbol`: its kind, or CONSTRUCTOR for constructors.
+fn lsp_symbol_kind(symbol: &rocketindex::Symbol) -> SymbolKind {
+    if symbol.is_constructor() {
+        SymbolKind::CONSTRUCTOR
+    } else {
+        to_lsp_symbol_kind(symbol.kind)
+    }
+}
+
 /// Convert our SymbolKind to LSP SymbolKind.
 fn to_lsp_symbol_kind(kind: rocketindex::SymbolKind) -> SymbolKind {
     match kind {
@@ -438,74 +588,18 @@ fn to_lsp_symbol_kind(kind: rocketindex::SymbolKind) -> SymbolKind {
 ///
 /// Returns the sorted opens as a string and the range to replace, or None if no opens found.
 fn compute_organize_opens(content: &str) -> Option<(String, Range)> {
-    let lines: Vec<&str> = content.lines().collect();
-
-    // Find all open statements and their locations
-    let mut opens: Vec<(usize, &str)> = Vec::new();
-    let mut first_open_line: Option<usize> = None;
-    let mut last_open_line: Option<usize> = None;
-
-    for (i, line) in lines.iter().enumerate() {
-        let trimmed = line.trim();
-        if trimmed.starts_with("open ") {
-            if first_open_line.is_none() {
-                first_open_line = Some(i);
-            }
-            last_open_line = Some(i);
-
-            // Extract the module name
-            let module_name = trimmed.strip_prefix("open ").unwrap_or("").trim();
-            opens.push((i, module_name));
-        }
-    }
-
-    // Need at least 2 opens to organize
-    if opens.len() < 2 {
-        return None;
-    }
-
-    let first_line = first_open_line?;
-    let last_line = last_open_line?;
-
-    // Sort the module names
-    let mut module_names: Vec<&str> = opens.iter().map(|(_, name)| *name).collect();
-    module_names.sort_by(|a, b| {
-        // Sort by depth first (fewer dots = higher priority)
-        let a_depth = a.matches('.').count();
-        let b_depth = b.matches('.').count();
-        if a_depth != b_depth {
-            return a_depth.cmp(&b_depth);
-        }
-        // Then alphabetically
-        a.to_lowercase().cmp(&b.to_lowercase())
-    });
-
-    // Check if already sorted
-    let current_order: Vec<&str> = opens.iter().map(|(_, name)| *name).collect();
-    if current_order == module_names {
-        return None;
-    }
-
-    // Build the sorted opens string
-    let sorted_text = module_names
-        .iter()
-        .map(|name| format!("open {}", name))
-        .collect::<Vec<_>>()
-        .join("\n");
-
-    // Calculate the range to replace
+    let organized = rocketindex::organize::organize_opens(content)?;
     let range = Range {
         start: Position {
-            line: first_line as u32,
+            line: organized.start_line as u32,
             character: 0,
         },
         end: Position {
-            line: last_line as u32,
-            character: lines[last_line].len() as u32,
+            line: organized.end_line as u32,
+            character: organized.end_column as u32,
         },
     };
-
-    Some((sorted_text, range))
+    Some((organized.text, range))
 }
 
 /// Find potential missing open statements for unresolved symbols.
@@ -718,6 +812,50 @@ fn resolve_expression_type(
     None
 }
 
+/// Hover text for `expr.Member` when `Member` belongs to a type the index
+/// only knows the members of (type cache or framework types), e.g.
+/// `Console.WriteLine` or `name.Length`.
+fn member_hover(index: &rocketindex::CodeIndex, word: &str, from_file: &Path) -> Option<String> {
+    let (expr, member_name) = word.rsplit_once('.')?;
+    let type_name = resolve_expression_type(index, expr, from_file)?;
+    let member = index.get_type_member(&type_name, member_name)?;
+    Some(format!(
+        "**{}** `{}`\n\n**Type:** `{}`\n\n*Member of* `{}`",
+        member.kind, member.member, member.member_type, member.type_name
+    ))
+}
+
+/// Resolve `receiver.member` to the member defined on the receiver's type.
+///
+/// The receiver's type comes from the type cache or, for a local, from its
+/// binding in `content`. Returns `None` when the type can't be worked out,
+/// rather than picking any member that happens to share the name.
+fn resolve_receiver_member<'a>(
+    index: &'a CodeIndex,
+    content: Option<&str>,
+    from_file: &Path,
+    word: &str,
+    line: u32,
+) -> Option<rocketindex::ResolveResult<'a>> {
+    let (receiver, member) = word.rsplit_once('.')?;
+    let type_name = resolve_expression_type(index, receiver, from_file)
+        .or_else(|| local_types::infer_local_type(index, content?, from_file, receiver, line))?;
+    let owner = index.resolve(extract_simple_type_name(&type_name), from_file)?;
+
+    let symbol = [".", "::", "#"].iter().find_map(|separator| {
+        index.get(&format!(
+            "{}{}{}",
+            owner.symbol.qualified, separator, member
+        ))
+    })?;
+    Some(rocketindex::ResolveResult {
+        symbol,
+        resolution_path: rocketindex::resolve::ResolutionPath::ViaMemberAccess {
+            type_name: owner.symbol.qualified.clone(),
+        },
+    })
+}
+
 /// Extract simple type name from a type signature.
 ///
 /// Handles F# type syntax:
@@ -755,6 +893,23 @@ fn extract_simple_type_name(type_sig: &str) -> &str {
     trimmed
 }
 
+/// Error for a request about a symbol the index doesn't know, with "did you
+/// mean" suggestions in the message and as `data.suggestions`.
+fn symbol_not_found(index: &CodeIndex, word: &str) -> tower_lsp::jsonrpc::Error {
+    let suggestions: Vec<String> = index.suggest(word).into_iter().map(|s| s.value).collect();
+    let mut error = tower_lsp::jsonrpc::Error::invalid_params(if suggestions.is_empty() {
+        format!("Symbol not found: {}", word)
+    } else {
+        format!(
+            "Symbol not found: {} (did you mean {}?)",
+            word,
+            suggestions.join(", ")
+        )
+    });
+    error.data = Some(serde_json::json!({ "suggestions": suggestions }));
+    error
+}
+
 /// Convert our Location to LSP Location.
 fn to_lsp_location(loc: &rocketindex::Location) -> Location {
     Location {
@@ -776,6 +931,15 @@ fn to_lsp_location(loc: &rocketindex::Location) -> Location {
 #[tower_lsp::async_trait]
 impl LanguageServer for Backend {
     async fn initialize(&self, params: InitializeParams) -> LspResult<InitializeResult> {
+        if let Some(edits) = params
+            .capabilities
+            .workspace
+            .as_ref()
+            .and_then(|w| w.workspace_edit.as_ref())
+        {
+            *self.edit_support.write().await = rename::EditSupport::from_capabilities(edits);
+        }
+
         // Store workspace root
         if let Some(root_uri) = params.root_uri {
             if let Ok(path) = root_uri.to_file_path() {
@@ -795,15 +959,27 @@ impl LanguageServer for Backend {
                     TextDocumentSyncKind::INCREMENTAL,
                 )),
                 definition_provider: Some(OneOf::Left(true)),
+                implementation_provider: Some(ImplementationProviderCapability::Simple(true)),
                 hover_provider: Some(HoverProviderCapability::Simple(true)),
                 references_provider: Some(OneOf::Left(true)),
+                document_highlight_provider: Some(OneOf::Left(true)),
                 workspace_symbol_provider: Some(OneOf::Left(true)),
+                document_symbol_provider: Some(OneOf::Left(true)),
+                call_hierarchy_provider: Some(CallHierarchyServerCapability::Simple(true)),
+                signature_help_provider: Some(SignatureHelpOptions {
+                    trigger_characters: Some(vec!["(".to_string(), ",".to_string()]),
+                    retrigger_characters: None,
+                    work_done_progress_options: Default::default(),
+                }),
                 completion_provider: Some(CompletionOptions {
                     trigger_characters: Some(vec![".".to_string()]),
                     resolve_provider: Some(false),
                     ..Default::default()
                 }),
                 code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+                code_lens_provider: Some(CodeLensOptions {
+                    resolve_provider: Some(false),
+                }),
                 rename_provider: Some(OneOf::Left(true)),
                 ..Default::default()
             },
@@ -886,6 +1062,37 @@ impl LanguageServer for Backend {
         Ok(None)
     }
 
+    async fn goto_implementation(
+        &self,
+        params: request::GotoImplementationParams,
+    ) -> LspResult<Option<request::GotoImplementationResponse>> {
+        let uri = params.text_document_position_params.text_document.uri;
+        let pos = params.text_document_position_params.position;
+
+        let file = match uri.to_file_path() {
+            Ok(f) => f,
+            Err(_) => return Ok(None),
+        };
+
+        let word = match self.get_symbol_at_position(&file, pos).await {
+            Some(w) => w,
+            None => return Ok(None),
+        };
+
+        info!("Finding implementations of: {}", word);
+
+        let index = self.index.read().await;
+        let Some(result) = index
+            .resolve(&word, &file)
+            .or_else(|| index.resolve_dotted(&word, &file))
+        else {
+            return Ok(None);
+        };
+
+        let locations = implementation::implementation_locations(&index, result.symbol);
+        Ok((!locations.is_empty()).then_some(GotoDefinitionResponse::Array(locations)))
+    }
+
     async fn hover(&self, params: HoverParams) -> LspResult<Option<Hover>> {
         let uri = params.text_document_position_params.text_document.uri;
         let pos = params.text_document_position_params.position;
@@ -945,8 +1152,34 @@ impl LanguageServer for Backend {
                 range: None,
             }));
         }
+        if let Some(content) = member_hover(&index, &word, &file) {
+            return Ok(Some(Hover {
+                contents: HoverContents::Markup(MarkupContent {
+                    kind: MarkupKind::Markdown,
+                    value: content,
+                }),
+                range: None,
+            }));
+        }
+        drop(index);
 
-        Ok(None)
+        // Not an indexed symbol: maybe a local we can type from its binding
+        let content = match self.documents.get_content(&file).await {
+            Some(content) => content,
+            None => return Ok(None),
+        };
+        let index = self.index.read().await;
+        let Some(ty) = local_types::infer_local_type(&index, &content, &file, &word, pos.line)
+        else {
+            return Ok(None);
+        };
+        Ok(Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: format!("**local** `{}`\n\n**Type:** `{}` *(inferred)*", word, ty),
+            }),
+            range: None,
+        }))
     }
 
     async fn references(&self, params: ReferenceParams) -> LspResult<Option<Vec<Location>>> {
@@ -974,18 +1207,11 @@ impl LanguageServer for Backend {
             .or_else(|| index.resolve_dotted(&word, &file));
 
         if let Some(result) = resolved {
-            let qualified_name = &result.symbol.qualified;
-
-            // Find all references to this symbol
-            let references = index.find_references(qualified_name);
-
-            let locations: Vec<Location> = references
-                .into_iter()
-                .map(|reference| {
-                    let abs_location = index.make_location_absolute(&reference.location);
-                    to_lsp_location(&abs_location)
-                })
-                .collect();
+            let locations = references::reference_locations(
+                &index,
+                &result.symbol.qualified,
+                params.context.include_declaration,
+            );
 
             if !locations.is_empty() {
                 return Ok(Some(locations));
@@ -995,38 +1221,145 @@ impl LanguageServer for Backend {
         Ok(None)
     }
 
+    async fn document_highlight(
+        &self,
+        params: DocumentHighlightParams,
+    ) -> LspResult<Option<Vec<DocumentHighlight>>> {
+        let pos = params.text_document_position_params.position;
+        let file = match params
+            .text_document_position_params
+            .text_document
+            .uri
+            .to_file_path()
+        {
+            Ok(f) => f,
+            Err(_) => return Ok(None),
+        };
+
+        let word = match self.get_symbol_at_position(&file, pos).await {
+            Some(w) => w,
+            None => return Ok(None),
+        };
+
+        let index = self.index.read().await;
+        let Some(result) = index
+            .resolve(&word, &file)
+            .or_else(|| index.resolve_dotted(&word, &file))
+        else {
+            return Ok(None);
+        };
+
+        let highlights =
+            document_highlight::document_highlights(&index, &file, &result.symbol.qualified);
+        Ok((!highlights.is_empty()).then_some(highlights))
+    }
+
     async fn symbol(
         &self,
         params: WorkspaceSymbolParams,
     ) -> LspResult<Option<Vec<SymbolInformation>>> {
-        let query = &params.query;
+        let index = self.index.read().await;
+        Ok(Some(workspace_symbols::workspace_symbols(
+            &index,
+            &params.query,
+        )))
+    }
 
-        if query.is_empty() {
-            return Ok(Some(Vec::new()));
-        }
+    async fn document_symbol(
+        &self,
+        params: DocumentSymbolParams,
+    ) -> LspResult<Option<DocumentSymbolResponse>> {
+        let file = match params.text_document.uri.to_file_path() {
+            Ok(f) => f,
+            Err(_) => return Ok(None),
+        };
 
         let index = self.index.read().await;
+        let symbols = document_symbols::document_symbols(&index, &file);
 
-        #[allow(deprecated)]
-        let matches: Vec<SymbolInformation> = index
-            .search(query)
-            .into_iter()
-            .take(50) // Limit results
-            .map(|sym| {
-                // Convert relative path to absolute for LSP
-                let abs_location = index.make_location_absolute(&sym.location);
-                SymbolInformation {
-                    name: sym.name.clone(),
-                    kind: to_lsp_symbol_kind(sym.kind),
-                    location: to_lsp_location(&abs_location),
-                    container_name: sym.qualified.rsplit_once('.').map(|(c, _)| c.to_string()),
-                    tags: None,
-                    deprecated: None,
-                }
-            })
-            .collect();
+        Ok(Some(DocumentSymbolResponse::Nested(symbols)))
+    }
+
+    async fn code_lens(&self, params: CodeLensParams) -> LspResult<Option<Vec<CodeLens>>> {
+        let file = match params.text_document.uri.to_file_path() {
+            Ok(f) => f,
+            Err(_) => return Ok(None),
+        };
+
+        let counts = self.reference_counts(&file).await;
+        Ok((!counts.is_empty()).then(|| code_lens::reference_lenses(&counts)))
+    }
+
+    async fn prepare_call_hierarchy(
+        &self,
+        params: CallHierarchyPrepareParams,
+    ) -> LspResult<Option<Vec<CallHierarchyItem>>> {
+        let uri = params.text_document_position_params.text_document.uri;
+        let pos = params.text_document_position_params.position;
+
+        let file = match uri.to_file_path() {
+            Ok(f) => f,
+            Err(_) => return Ok(None),
+        };
+
+        let word = match self.get_symbol_at_position(&file, pos).await {
+            Some(w) => w,
+            None => return Ok(None),
+        };
+
+        let index = self.index.read().await;
+
+        let resolved = index
+            .resolve(&word, &file)
+            .or_else(|| index.resolve_dotted(&word, &file));
+
+        Ok(resolved.map(|result| vec![call_hierarchy::to_item(&index, result.symbol)]))
+    }
+
+    async fn incoming_calls(
+        &self,
+        params: CallHierarchyIncomingCallsParams,
+    ) -> LspResult<Option<Vec<CallHierarchyIncomingCall>>> {
+        let qualified = call_hierarchy::item_qualified_name(&params.item);
+        let index = self.index.read().await;
 
-        Ok(Some(matches))
+        Ok(Some(call_hierarchy::incoming_calls(&index, qualified)))
+    }
+
+    async fn outgoing_calls(
+        &self,
+        params: CallHierarchyOutgoingCallsParams,
+    ) -> LspResult<Option<Vec<CallHierarchyOutgoingCall>>> {
+        let qualified = call_hierarchy::item_qualified_name(&params.item);
+        let index = self.index.read().await;
+
+        Ok(Some(call_hierarchy::outgoing_calls(&index, qualified)))
+    }
+
+    async fn signature_help(
+        &self,
+        params: SignatureHelpParams,
+    ) -> LspResult<Option<SignatureHelp>> {
+        let uri = params.text_document_position_params.text_document.uri;
+        let pos = params.text_document_position_params.position;
+
+        let file = match uri.to_file_path() {
+            Ok(f) => f,
+            Err(_) => return Ok(None),
+        };
+
+        let content = match self.documents.get_content(&file).await {
+            Some(c) => c,
+            None => return Ok(None),
+        };
+
+        let context = match signature_help::find_call_context(&content, pos) {
+            Some(c) => c,
+            None => return Ok(None),
+        };
+
+        let index = self.index.read().await;
+        Ok(signature_help::signature_help(&index, &context, &file))
     }
 
     async fn completion(&self, params: CompletionParams) -> LspResult<Option<CompletionResponse>> {
@@ -1205,98 +1538,61 @@ impl LanguageServer for Backend {
 
         info!("Renaming symbol: {} to {}", word, new_name);
 
+        let content = self.documents.get_content(&file).await;
         let index = self.index.read().await;
 
-        // Try to resolve the symbol to get its qualified name and definition
+        // Try to resolve the symbol to get its qualified name and definition.
+        // For field access through a value (e.g. `person.Name`) the receiver
+        // isn't a symbol, so look the member up on the receiver's type.
         let resolved = index
             .resolve(&word, &file)
-            .or_else(|| index.resolve_dotted(&word, &file));
-
-        if let Some(result) = resolved {
-            let sym = &result.symbol;
-            let qualified_name = &sym.qualified;
-            let short_name = &sym.name;
-
-            // Collect all locations to rename: definition + references
-            let mut locations = Vec::new();
-
-            // Add definition location
-            locations.push(sym.location.clone());
-
-            // Add all reference locations
-            let references = index.find_references(qualified_name);
-            for reference in references {
-                locations.push(reference.location.clone());
-            }
-
-            // Create text edits for each location
-            let mut changes: std::collections::HashMap<Url, Vec<TextEdit>> =
-                std::collections::HashMap::new();
-
-            for location in locations {
-                let abs_location = index.make_location_absolute(&location);
-                let lsp_location = to_lsp_location(&abs_location);
-                let file_uri = lsp_location.uri;
-
-                // Get the document content to find the exact text to replace
-                let content = if let Some(c) = self.documents.get_content(&abs_location.file).await
-                {
-                    c
-                } else {
-                    // Fallback to reading from disk (async I/O)
-                    match tokio::fs::read_to_string(&abs_location.file).await {
-                        Ok(c) => c,
-                        Err(_) => continue,
-                    }
-                };
-
-                // Find the text at the location
-                let lines: Vec<&str> = content.lines().collect();
-                if abs_location.line as usize >= lines.len() {
-                    continue;
-                }
-                let line = lines[abs_location.line as usize - 1]; // 1-indexed to 0-indexed
-
-                // Extract the word at the position
-                let start_col = abs_location.column as usize - 1; // 1-indexed to 0-indexed
-                let end_col = abs_location.end_column as usize - 1;
-
-                if start_col >= line.len() || end_col > line.len() {
-                    continue;
-                }
+            .or_else(|| index.resolve_dotted(&word, &file))
+            .or_else(|| {
+                resolve_receiver_member(&index, content.as_deref(), &file, &word, pos.line)
+            });
 
-                let current_text = &line[start_col..end_col];
-
-                // Determine what to replace
-                let replacement_text = if current_text == short_name {
-                    new_name.clone()
-                } else if current_text.ends_with(&format!(".{}", short_name)) {
-                    current_text.replace(&format!(".{}", short_name), &format!(".{}", new_name))
-                } else {
-                    // Fallback: replace the short name if it appears
-                    current_text.replace(short_name, &new_name)
-                };
-
-                let range = lsp_location.range;
-                let edit = TextEdit {
-                    range,
-                    new_text: replacement_text,
-                };
+        let Some(result) = resolved else {
+            return Err(symbol_not_found(&index, &word));
+        };
 
-                changes.entry(file_uri).or_default().push(edit);
-            }
+        // Names in string literals are only offered to clients that can ask
+        // the user to confirm them
+        let edit_support = *self.edit_support.read().await;
+        let strings = if edit_support.change_annotations {
+            self.string_references(&result.symbol.qualified).await
+        } else {
+            Vec::new()
+        };
 
-            if changes.is_empty() {
-                Ok(None)
-            } else {
-                Ok(Some(WorkspaceEdit {
-                    changes: Some(changes),
-                    ..Default::default()
-                }))
+        // Plan against unsaved buffers, so edits line up with what the
+        // client will apply them to
+        let open = self.documents.snapshot().await;
+        let plan = rocketindex::rename::plan_rename_with(
+            &index,
+            &result.symbol.qualified,
+            &new_name,
+            &strings,
+            |path| match open.get(path) {
+                Some(doc) => Ok(doc.content.clone()),
+                None => std::fs::read_to_string(path),
+            },
+        );
+        let plan = match plan {
+            Ok(plan) if plan.files.is_empty() => return Ok(None),
+            Ok(plan) => plan,
+            Err(e) => {
+                return Err(tower_lsp::jsonrpc::Error::invalid_params(e.to_string()));
             }
-        } else {
-            Ok(None)
+        };
+        if !plan.skipped.is_empty() {
+            warn!(
+                "Rename skipped {} stale locations of {}",
+                plan.skipped.len(),
+                plan.symbol
+            );
         }
+
+        Ok(Some(rename::workspace_edit(&plan, &open, edit_support)))
     }
 
     async fn did_open(&self, params: DidOpenTextDocumentParams) {
@@ -1387,13 +1683,16 @@ async fn main() {
     let stdin = tokio::io::stdin();
     let stdout = tokio::io::stdout();
 
-    let (service, socket) = LspService::new(|client| Backend {
+    let (service, socket) = LspService::build(|client| Backend {
         client,
         index: Arc::new(RwLock::new(CodeIndex::new())),
         workspace_root: Arc::new(RwLock::new(None)),
         documents: DocumentStore::new(),
         max_recursion_depth: Arc::new(RwLock::new(500)), // Default, updated on init
-    });
+        edit_support: Arc::new(RwLock::new(rename::EditSupport::default())),
+    })
+    .custom_method("rocketindex/writeReferences", Backend::write_references)
+    .finish();
 
     Server::new(stdin, stdout, socket).serve(service).await;
 }
@@ -1683,6 +1982,46 @@ let x = 1"#;
         assert_eq!(result, Some("User".to_string()));
     }
 
+    #[test]
+    fn test_framework_members_complete_and_hover() {
+        let mut index = rocketindex::CodeIndex::new();
+        index.set_external_index(rocketindex::external_index::index_external_assemblies(&[]));
+
+        let type_name = resolve_expression_type(&index, "Console", Path::new("test.fs"));
+        assert_eq!(type_name.as_deref(), Some("Console"));
+        assert!(index
+            .get_type_members("Console")
+            .unwrap()
+            .iter()
+            .any(|member| member.member == "WriteLine"));
+
+        let hover = member_hover(&index, "System.String.Length", Path::new("test.fs")).unwrap();
+        assert!(hover.contains("**property** `Length`"), "{}", hover);
+        assert!(hover.contains("`int`"), "{}", hover);
+        assert!(member_hover(&index, "Console.Nope", Path::new("test.fs")).is_none());
+    }
+
+    #[test]
+    fn test_resolve_receiver_member_uses_the_receiver_type() {
+        let mut index = rocketindex::CodeIndex::new();
+        let file = Path::new("people.fs");
+        let source =
+            "module People\n\ntype Person = { Name: string }\ntype Pet = { Name: string }\n";
+        let result = extract_symbols(file, source, 100);
+        for symbol in result.symbols {
+            index.add_symbol(symbol);
+        }
+
+        let content = "let rex: Pet = load ()\nlet n = rex.Name\n";
+        let resolved = resolve_receiver_member(&index, Some(content), file, "rex.Name", 1).unwrap();
+        assert_eq!(resolved.symbol.qualified, "People.Pet.Name");
+
+        // An untyped receiver declines instead of guessing by member name
+        let content = "let rex = load ()\nlet n = rex.Name\n";
+        assert!(resolve_receiver_member(&index, Some(content), file, "rex.Name", 1).is_none());
+        assert!(resolve_receiver_member(&index, None, file, "rex.Name", 1).is_none());
+    }
+
     #[test]
     fn test_resolve_expression_type_not_found() {
         let index = rocketindex::CodeIndex::new();
@@ -1803,4 +2142,24 @@ let x = 1
         assert_eq!(pos.line, 0);
         assert_eq!(pos.character, 0);
     }
+
+    #[test]
+    fn test_symbol_not_found_suggests_close_names() {
+        let mut index = CodeIndex::new();
+        index.add_symbol(rocketindex::Symbol::new(
+            "charge".to_string(),
+            "Billing.charge".to_string(),
+            rocketindex::SymbolKind::Function,
+            rocketindex::Location::new(PathBuf::from("src/Billing.fs"), 1, 5),
+            rocketindex::Visibility::Public,
+            "fsharp".to_string(),
+        ));
+
+        let error = symbol_not_found(&index, "chrage");
+        assert_eq!(
+            error.message,
+            "Symbol not found: chrage (did you mean charge?)"
+        );
+        assert_eq!(error.data.unwrap()["suggestions"][0], "charge");
+    }
 }
@@ -0,0 +1,190 @@
+//! Find references support.
+//!
+//! Results come back grouped by file, so editors can render each group as
+//! it arrives: files with the most references first (ties by path), and
+//! positions in source order within a file. Definitions are included only
+//! when the client's `ReferenceContext` asks for them; otherwise references
+//! recorded at a definition (an F# `let mutable x = 0` is also a write of
+//! `x`) are left out too.
+//!
+//! The custom `rocketindex/writeReferences` request takes the same
+//! `TextDocumentPositionParams` as go to definition and returns only the
+//! places a value is assigned, grouped the same way.
+
+use std::collections::BTreeMap;
+
+use rocketindex::{CodeIndex, Reference, ReferenceKind};
+use tower_lsp::lsp_types::{Location, Url};
+
+use crate::to_lsp_location;
+
+/// Locations referring to `qualified`, grouped by file.
+pub fn reference_locations(
+    index: &CodeIndex,
+    qualified: &str,
+    include_declaration: bool,
+) -> Vec<Location> {
+    locations_where(index, qualified, include_declaration, |_| true)
+}
+
+/// Locations assigning to `qualified`, grouped by file.
+pub fn write_locations(index: &CodeIndex, qualified: &str) -> Vec<Location> {
+    locations_where(index, qualified, false, |reference| {
+        reference.kind == ReferenceKind::Write
+    })
+}
+
+fn locations_where(
+    index: &CodeIndex,
+    qualified: &str,
+    include_declaration: bool,
+    wanted: impl Fn(&Reference) -> bool,
+) -> Vec<Location> {
+    let declarations = index.get_all(qualified);
+    let at_declaration = |reference: &Reference| {
+        declarations.iter().any(|symbol| {
+            symbol.location.file == reference.location.file
+                && symbol.location.line == reference.location.line
+                && symbol.location.column == reference.location.column
+        })
+    };
+    let definitions = declarations
+        .iter()
+        .filter(|_| include_declaration)
+        .map(|symbol| &symbol.location);
+    let references = index
+        .find_references(qualified)
+        .into_iter()
+        .filter(|reference| wanted(reference))
+        .filter(|reference| include_declaration || !at_declaration(reference))
+        .map(|reference| &reference.location);
+
+    let mut by_file: BTreeMap<Url, Vec<Location>> = BTreeMap::new();
+    for location in definitions.chain(references) {
+        let location = to_lsp_location(&index.make_location_absolute(location));
+        by_file
+            .entry(location.uri.clone())
+            .or_default()
+            .push(location);
+    }
+
+    let mut groups: Vec<Vec<Location>> = by_file
+        .into_values()
+        .map(|mut locations| {
+            locations.sort_by_key(|l| (l.range.start.line, l.range.start.character));
+            locations.dedup_by_key(|l| l.range.start);
+            locations
+        })
+        .collect();
+    // Stable, so equally sized groups stay in path order
+    groups.sort_by_key(|locations| std::cmp::Reverse(locations.len()));
+    groups.into_iter().flatten().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocketindex::{Location as IndexLocation, Symbol};
+    use rocketindex::{SymbolKind, Visibility};
+    use std::path::PathBuf;
+
+    fn index() -> CodeIndex {
+        let mut index = CodeIndex::with_root(PathBuf::from("/ws"));
+        index.add_symbol(Symbol::new(
+            "save".to_string(),
+            "Store.save".to_string(),
+            SymbolKind::Function,
+            IndexLocation::new(PathBuf::from("src/Store.fs"), 3, 5),
+            Visibility::Public,
+            "fsharp".to_string(),
+        ));
+        for (file, line) in [
+            ("src/Store.fs", 9),
+            ("src/App.fs", 7),
+            ("src/App.fs", 2),
+            ("src/Cli.fs", 4),
+        ] {
+            index.add_reference(
+                PathBuf::from(file),
+                Reference {
+                    name: "save".to_string(),
+                    location: IndexLocation::new(PathBuf::from(file), line, 5),
+                    kind: ReferenceKind::Call,
+                },
+            );
+        }
+        index
+    }
+
+    fn positions(locations: &[Location]) -> Vec<(String, u32)> {
+        locations
+            .iter()
+            .map(|l| {
+                let path = l.uri.path();
+                (
+                    path.rsplit('/').next().unwrap().to_string(),
+                    l.range.start.line,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn groups_by_file_with_the_most_references_first() {
+        let locations = reference_locations(&index(), "Store.save", false);
+        assert_eq!(
+            positions(&locations),
+            vec![
+                ("App.fs".to_string(), 1),
+                ("App.fs".to_string(), 6),
+                ("Cli.fs".to_string(), 3),
+                ("Store.fs".to_string(), 8),
+            ]
+        );
+    }
+
+    #[test]
+    fn writes_only_and_never_the_declaration() {
+        let mut index = index();
+        for (line, column, kind) in [
+            (3, 5, ReferenceKind::Write),
+            (12, 1, ReferenceKind::Write),
+            (13, 1, ReferenceKind::Read),
+        ] {
+            index.add_reference(
+                PathBuf::from("src/Store.fs"),
+                Reference {
+                    name: "save".to_string(),
+                    location: IndexLocation::new(PathBuf::from("src/Store.fs"), line, column),
+                    kind,
+                },
+            );
+        }
+
+        assert_eq!(
+            positions(&write_locations(&index, "Store.save")),
+            vec![("Store.fs".to_string(), 11)]
+        );
+        // The write recorded at the definition counts as the declaration
+        let all = reference_locations(&index, "Store.save", false);
+        assert!(!positions(&all).contains(&("Store.fs".to_string(), 2)));
+        let all = reference_locations(&index, "Store.save", true);
+        assert!(positions(&all).contains(&("Store.fs".to_string(), 2)));
+    }
+
+    #[test]
+    fn declaration_only_when_asked() {
+        let locations = reference_locations(&index(), "Store.save", true);
+        assert_eq!(locations.len(), 5);
+        // The definition joins its file's group, which now ties with App.fs
+        assert_eq!(
+            positions(&locations)[..4],
+            [
+                ("App.fs".to_string(), 1),
+                ("App.fs".to_string(), 6),
+                ("Store.fs".to_string(), 2),
+                ("Store.fs".to_string(), 8),
+            ]
+        );
+    }
+}
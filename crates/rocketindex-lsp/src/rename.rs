@@ -0,0 +1,256 @@
+//! Rename support.
+//!
+//! Edits are planned by [`rocketindex::rename`], the same engine behind
+//! `rkt rename --dry-run`, against the open buffers where there are any.
+//! That covers references written through the symbol (`Utils.run` when
+//! renaming the module `Utils`) in files that don't `open` it.
+//!
+//! Clients that support `documentChanges` get one versioned edit per file, so
+//! the rename is applied to every file or, if a buffer changed since it was
+//! planned, to none of them. Clients that also support change annotations
+//! get the symbol's string-literal matches (DI registrations, reflection) as
+//! edits the user has to confirm.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use rocketindex::rename::{RenameEdit, RenamePlan};
+use tower_lsp::lsp_types::{
+    AnnotatedTextEdit, ChangeAnnotation, DocumentChanges, OneOf,
+    OptionalVersionedTextDocumentIdentifier, Position, Range, TextDocumentEdit, TextEdit, Url,
+    WorkspaceEdit, WorkspaceEditClientCapabilities,
+};
+
+use crate::document_store::Document;
+
+/// Annotation attached to string-literal edits.
+const STRING_LITERAL: &str = "string-literal";
+
+/// The kinds of workspace edit a client applies.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EditSupport {
+    /// Versioned `documentChanges` instead of a plain `changes` map
+    pub document_changes: bool,
+    /// Edits annotated as needing the user's confirmation
+    pub change_annotations: bool,
+}
+
+impl EditSupport {
+    pub fn from_capabilities(capabilities: &WorkspaceEditClientCapabilities) -> Self {
+        let document_changes = capabilities.document_changes.unwrap_or(false);
+        Self {
+            document_changes,
+            change_annotations: document_changes
+                && capabilities.change_annotation_support.is_some(),
+        }
+    }
+}
+
+/// The plan as a workspace edit; `open` holds the buffers it was planned
+/// against, whose versions the edit is pinned to.
+///
+/// String-literal edits are only included where they can be annotated.
+pub fn workspace_edit(
+    plan: &RenamePlan,
+    open: &HashMap<PathBuf, Document>,
+    support: EditSupport,
+) -> WorkspaceEdit {
+    let text_edit = |edit: &RenameEdit| TextEdit {
+        range: Range {
+            start: Position::new(edit.line - 1, edit.column - 1),
+            end: Position::new(edit.line - 1, edit.end_column - 1),
+        },
+        new_text: plan.new_name.clone(),
+    };
+    let files = plan.files.iter().filter_map(|file| {
+        let uri = Url::from_file_path(&file.file).ok()?;
+        let version = open.get(&file.file).map(|doc| doc.version);
+        Some((uri, version, file))
+    });
+
+    if !support.document_changes {
+        let changes = files
+            .map(|(uri, _, file)| (uri, file.edits.iter().map(text_edit).collect()))
+            .filter(|(_, edits): &(Url, Vec<TextEdit>)| !edits.is_empty())
+            .collect();
+        return WorkspaceEdit {
+            changes: Some(changes),
+            ..Default::default()
+        };
+    }
+
+    let mut confirm = false;
+    let edits: Vec<TextDocumentEdit> = files
+        .filter_map(|(uri, version, file)| {
+            let mut edits: Vec<OneOf<TextEdit, AnnotatedTextEdit>> = file
+                .edits
+                .iter()
+                .map(|e| OneOf::Left(text_edit(e)))
+                .collect();
+            if support.change_annotations {
+                edits.extend(file.string_edits.iter().map(|e| {
+                    OneOf::Right(AnnotatedTextEdit {
+                        text_edit: text_edit(e),
+                        annotation_id: STRING_LITERAL.to_string(),
+                    })
+                }));
+                confirm |= !file.string_edits.is_empty();
+            }
+            (!edits.is_empty()).then_some(TextDocumentEdit {
+                text_document: OptionalVersionedTextDocumentIdentifier { uri, version },
+                edits,
+            })
+        })
+        .collect();
+
+    let annotations = confirm.then(|| {
+        HashMap::from([(
+            STRING_LITERAL.to_string(),
+            ChangeAnnotation {
+                label: "Rename in string literal".to_string(),
+                needs_confirmation: Some(true),
+                description: Some(format!(
+                    "The name appears in a string, which may not mean {}",
+                    plan.symbol
+                )),
+            },
+        )])
+    });
+    WorkspaceEdit {
+        document_changes: Some(DocumentChanges::Edits(edits)),
+        change_annotations: annotations,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocketindex::rename::plan_rename_with;
+    use rocketindex::{CodeIndex, Location, Reference, ReferenceKind, Symbol};
+    use rocketindex::{SymbolKind, Visibility};
+
+    const UTILS: &str = "module Utils\n\nlet run x = x\n";
+    const APP: &str = "module App\n\nlet main () =\n    Utils.run 1\n";
+    const WIRING: &str = "module Wiring\n\nlet m = load \"Utils\"\n";
+
+    const ALL: EditSupport = EditSupport {
+        document_changes: true,
+        change_annotations: true,
+    };
+
+    fn plan(open: &HashMap<PathBuf, Document>) -> RenamePlan {
+        let mut index = CodeIndex::with_root(PathBuf::from("/ws"));
+        for (name, qualified, kind, line, column) in [
+            ("Utils", "Utils", SymbolKind::Module, 1, 8),
+            ("run", "Utils.run", SymbolKind::Function, 3, 5),
+        ] {
+            index.add_symbol(Symbol::new(
+                name.to_string(),
+                qualified.to_string(),
+                kind,
+                Location::new(PathBuf::from("src/Utils.fs"), line, column),
+                Visibility::Public,
+                "fsharp".to_string(),
+            ));
+        }
+        index.add_reference(
+            PathBuf::from("src/App.fs"),
+            Reference {
+                name: "Utils.run".to_string(),
+                location: Location::new(PathBuf::from("src/App.fs"), 4, 5),
+                kind: ReferenceKind::Call,
+            },
+        );
+
+        let strings = [Reference {
+            name: "Utils".to_string(),
+            location: Location::with_end(PathBuf::from("/ws/src/Wiring.fs"), 3, 15, 3, 20),
+            kind: ReferenceKind::Read,
+        }];
+
+        plan_rename_with(&index, "Utils", "Helpers", &strings, |path| {
+            Ok(match open.get(path) {
+                Some(doc) => doc.content.clone(),
+                None if path.ends_with("Utils.fs") => UTILS.to_string(),
+                None if path.ends_with("Wiring.fs") => WIRING.to_string(),
+                None => APP.to_string(),
+            })
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn versioned_edits_for_open_buffers() {
+        let mut open = HashMap::new();
+        open.insert(
+            PathBuf::from("/ws/src/App.fs"),
+            Document::new(APP.to_string(), 7),
+        );
+
+        let edit = workspace_edit(&plan(&open), &open, ALL);
+        let Some(DocumentChanges::Edits(files)) = edit.document_changes else {
+            panic!("expected document edits");
+        };
+        let versions: Vec<(&str, Option<i32>)> = files
+            .iter()
+            .map(|f| (f.text_document.uri.path(), f.text_document.version))
+            .collect();
+        assert_eq!(
+            versions,
+            vec![
+                ("/ws/src/App.fs", Some(7)),
+                ("/ws/src/Utils.fs", None),
+                ("/ws/src/Wiring.fs", None)
+            ]
+        );
+        assert_eq!(
+            files[0].edits,
+            vec![OneOf::Left(TextEdit {
+                range: Range::new(Position::new(3, 4), Position::new(3, 9)),
+                new_text: "Helpers".to_string(),
+            })]
+        );
+    }
+
+    #[test]
+    fn string_literal_edits_need_confirmation() {
+        let open = HashMap::new();
+        let edit = workspace_edit(&plan(&open), &open, ALL);
+        let Some(DocumentChanges::Edits(files)) = edit.document_changes else {
+            panic!("expected document edits");
+        };
+        let OneOf::Right(wiring) = &files[2].edits[0] else {
+            panic!("expected an annotated edit");
+        };
+        assert_eq!(wiring.annotation_id, STRING_LITERAL);
+        assert_eq!(
+            wiring.text_edit.range,
+            Range::new(Position::new(2, 14), Position::new(2, 19))
+        );
+        let annotations = edit.change_annotations.unwrap();
+        assert_eq!(annotations[STRING_LITERAL].needs_confirmation, Some(true));
+
+        // Without annotations there's no way to ask, so they're left out
+        let support = EditSupport {
+            change_annotations: false,
+            ..ALL
+        };
+        let edit = workspace_edit(&plan(&open), &open, support);
+        let Some(DocumentChanges::Edits(files)) = edit.document_changes else {
+            panic!("expected document edits");
+        };
+        assert_eq!(files.len(), 2);
+        assert!(edit.change_annotations.is_none());
+    }
+
+    #[test]
+    fn plain_changes_without_document_changes_support() {
+        let open = HashMap::new();
+        let edit = workspace_edit(&plan(&open), &open, EditSupport::default());
+        assert!(edit.document_changes.is_none());
+        let changes = edit.changes.unwrap();
+        assert_eq!(changes.len(), 2);
+        assert!(changes.values().all(|edits| edits.len() == 1));
+    }
+}
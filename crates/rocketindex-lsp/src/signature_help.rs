@@ -0,0 +1,356 @@
+//! Signature help support.
+//!
+//! Finds the call the cursor is inside (`processPayment(amount, |`), resolves
+//! the callee, and describes its parameters. Parameters come from the type
+//! cache when it has them, otherwise from the symbol's extracted signature.
+
+use std::path::Path;
+
+use rocketindex::type_cache::ParameterInfo;
+use rocketindex::CodeIndex;
+use tower_lsp::lsp_types::{
+    Documentation, MarkupContent, MarkupKind, ParameterInformation, ParameterLabel, Position,
+    SignatureHelp, SignatureInformation,
+};
+
+/// How far back to look for the opening parenthesis of the enclosing call.
+const MAX_LOOKBACK: usize = 4096;
+
+/// The call enclosing a cursor position.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallContext {
+    /// Callee expression before the `(`, e.g. `PaymentService.processPayment`
+    pub callee: String,
+    /// Zero-based index of the argument the cursor is in
+    pub active_parameter: u32,
+}
+
+/// Find the innermost unclosed call before `pos`.
+pub fn find_call_context(content: &str, pos: Position) -> Option<CallContext> {
+    let offset = offset_of(content, pos)?;
+    let before = &content[..offset];
+    let start = before.len().saturating_sub(MAX_LOOKBACK);
+    let start = (start..before.len())
+        .find(|&i| before.is_char_boundary(i))
+        .unwrap_or(before.len());
+    let window = &before[start..];
+
+    // Walk backwards to the unmatched '(' counting top-level commas
+    let mut depth = 0usize;
+    let mut commas = 0u32;
+    let mut open = None;
+    for (i, c) in window.char_indices().rev() {
+        match c {
+            ')' | ']' | '}' => depth += 1,
+            '(' | '[' | '{' if depth > 0 => depth -= 1,
+            '(' => {
+                open = Some(i);
+                break;
+            }
+            // An unclosed list or record literal: not a call argument list
+            '[' | '{' => return None,
+            ',' if depth == 0 => commas += 1,
+            _ => {}
+        }
+    }
+
+    let callee_end = window[..open?].trim_end();
+    // Just past the last character that can't be part of a callee, which may
+    // be more than one byte long
+    let callee_start = callee_end
+        .char_indices()
+        .rev()
+        .find(|&(_, c)| !c.is_alphanumeric() && c != '_' && c != '.' && c != ':')
+        .map_or(0, |(i, c)| i + c.len_utf8());
+    let callee = callee_end[callee_start..].trim_matches(|c| c == '.' || c == ':');
+    if callee.is_empty() || callee.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+
+    Some(CallContext {
+        callee: callee.to_string(),
+        active_parameter: commas,
+    })
+}
+
+/// Signature help for the call described by `context`, resolved from `file`.
+pub fn signature_help(
+    index: &CodeIndex,
+    context: &CallContext,
+    file: &Path,
+) -> Option<SignatureHelp> {
+    let result = index
+        .resolve(&context.callee, file)
+        .or_else(|| index.resolve_dotted(&context.callee, file))?;
+    let symbol = result.symbol;
+
+    let cached: &[ParameterInfo] = index
+        .type_cache()
+        .and_then(|cache| cache.get_symbol(&symbol.qualified))
+        .map(|typed| typed.parameters.as_slice())
+        .unwrap_or_default();
+
+    // Parameter labels must be substrings of the signature label, so when the
+    // type cache supplies them, the label is built from them
+    let (label, parameters) = if !cached.is_empty() {
+        let parameters: Vec<String> = cached
+            .iter()
+            .map(|p| format!("{}: {}", p.name, p.type_signature))
+            .collect();
+        (
+            format!("{}({})", symbol.name, parameters.join(", ")),
+            parameters,
+        )
+    } else {
+        let signature = symbol
+            .signature
+            .clone()
+            .unwrap_or_else(|| symbol.name.clone());
+        let parameters = split_parameters(&signature);
+        (signature, parameters)
+    };
+
+    let documentation = symbol
+        .doc
+        .clone()
+        // The label no longer shows the written signature, so show it here
+        .or_else(|| symbol.signature.clone().filter(|_| !cached.is_empty()))
+        .map(|value| {
+            Documentation::MarkupContent(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value,
+            })
+        });
+
+    let active_parameter =
+        (!parameters.is_empty()).then(|| context.active_parameter.min(parameters.len() as u32 - 1));
+
+    Some(SignatureHelp {
+        signatures: vec![SignatureInformation {
+            label,
+            documentation,
+            parameters: Some(
+                parameters
+                    .into_iter()
+                    .map(|p| ParameterInformation {
+                        label: ParameterLabel::Simple(p),
+                        documentation: None,
+                    })
+                    .collect(),
+            ),
+            active_parameter,
+        }],
+        active_signature: Some(0),
+        active_parameter,
+    })
+}
+
+/// Parameters written in a signature.
+///
+/// `f(a: int, b: string)` has two parameters split on top-level commas; an
+/// F# curried signature `let f (a: int) (b: string) =` has one per group.
+fn split_parameters(signature: &str) -> Vec<String> {
+    let mut groups: Vec<&str> = Vec::new();
+    let mut depth = 0usize;
+    let mut group_start = 0;
+    for (i, c) in signature.char_indices() {
+        match c {
+            '(' => {
+                if depth == 0 {
+                    group_start = i + 1;
+                }
+                depth += 1;
+            }
+            ')' if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    groups.push(&signature[group_start..i]);
+                }
+            }
+            // Return types and bodies can contain parentheses of their own
+            '=' | '{' | ':' if depth == 0 && !groups.is_empty() => break,
+            '-' if depth == 0 && signature[i..].starts_with("->") && !groups.is_empty() => break,
+            _ => {}
+        }
+    }
+
+    let parameters: Vec<&str> = match groups.as_slice() {
+        [] => Vec::new(),
+        [single] => split_top_level(single),
+        curried => curried.to_vec(),
+    };
+
+    parameters
+        .into_iter()
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Split on commas that aren't nested inside brackets.
+fn split_top_level(text: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '(' | '[' | '{' | '<' => depth += 1,
+            ')' | ']' | '}' | '>' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                parts.push(&text[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+/// Byte offset of `pos` in `content`. `character` counts UTF-16 code units,
+/// the LSP default encoding; a position inside a surrogate pair or past the
+/// end of the line falls back to the end of that character or line.
+fn offset_of(content: &str, pos: Position) -> Option<usize> {
+    let mut line_start = 0;
+    for _ in 0..pos.line {
+        line_start += content[line_start..].find('\n')? + 1;
+    }
+    let line = content[line_start..].split('\n').next().unwrap_or("");
+    let mut units = 0;
+    let column = line
+        .char_indices()
+        .find(|&(_, c)| {
+            let reached = units >= pos.character as usize;
+            units += c.len_utf16();
+            reached
+        })
+        .map_or(line.len(), |(i, _)| i);
+    Some(line_start + column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocketindex::type_cache::{TypeCache, TypeCacheSchema, TypedSymbol};
+    use rocketindex::{Location, Symbol, SymbolKind, Visibility};
+    use std::path::PathBuf;
+
+    fn context(content: &str) -> Option<CallContext> {
+        // Cursor goes where the '|' marker is
+        let offset = content.find('|').unwrap();
+        let before = &content[..offset];
+        let line = before.matches('\n').count() as u32;
+        let character = before.rsplit('\n').next().unwrap().encode_utf16().count() as u32;
+        find_call_context(&content.replace('|', ""), Position { line, character })
+    }
+
+    #[test]
+    fn finds_callee_and_active_argument() {
+        assert_eq!(
+            context("let x = Payments.processPayment(amount, |"),
+            Some(CallContext {
+                callee: "Payments.processPayment".to_string(),
+                active_parameter: 1,
+            })
+        );
+        // Commas in nested calls and literals don't count
+        assert_eq!(
+            context("charge(total(items, tax), [1, 2], |)")
+                .unwrap()
+                .active_parameter,
+            2
+        );
+        assert_eq!(
+            context("outer(a,\n  inner(|").unwrap().callee,
+            "inner".to_string()
+        );
+        assert_eq!(context("let xs = [f(a), |"), None);
+        assert_eq!(context("(1 + |"), None);
+    }
+
+    #[test]
+    fn handles_non_ascii_before_the_call() {
+        assert_eq!(context("x = «foo(|").unwrap().callee, "foo".to_string());
+        assert_eq!(context("// →bar(a, |").unwrap().callee, "bar".to_string());
+        // The emoji is two UTF-16 units; counted as one, the cursor would
+        // land past the comma
+        assert_eq!(
+            context("let s = \"😀\"; charge(a, b|, c)")
+                .unwrap()
+                .active_parameter,
+            1
+        );
+    }
+
+    #[test]
+    fn splits_parameters_from_signatures() {
+        assert_eq!(
+            split_parameters("fn charge(amount: Decimal, card: Card<'a, T>) -> Result<(), E>"),
+            vec!["amount: Decimal", "card: Card<'a, T>"]
+        );
+        assert_eq!(
+            split_parameters("let processPayment (amount: decimal) (card: Card) ="),
+            vec!["amount: decimal", "card: Card"]
+        );
+        assert!(split_parameters("val count: int").is_empty());
+    }
+
+    #[test]
+    fn prefers_type_cache_parameters() {
+        let mut index = CodeIndex::new();
+        index.add_symbol(
+            Symbol::new(
+                "processPayment".to_string(),
+                "Payments.processPayment".to_string(),
+                SymbolKind::Function,
+                Location::new(PathBuf::from("src/Payments.fs"), 3, 5),
+                Visibility::Public,
+                "fsharp".to_string(),
+            )
+            .with_signature(Some("let processPayment amount card =".to_string())),
+        );
+        index.set_type_cache(TypeCache::from_schema(TypeCacheSchema {
+            version: 1,
+            extracted_at: String::new(),
+            project: String::new(),
+            symbols: vec![TypedSymbol {
+                name: "processPayment".to_string(),
+                qualified: "Payments.processPayment".to_string(),
+                type_signature: "decimal -> Card -> Result<Receipt, string>".to_string(),
+                file: "src/Payments.fs".to_string(),
+                line: 3,
+                parameters: vec![
+                    ParameterInfo {
+                        name: "amount".to_string(),
+                        type_signature: "decimal".to_string(),
+                    },
+                    ParameterInfo {
+                        name: "card".to_string(),
+                        type_signature: "Card".to_string(),
+                    },
+                ],
+            }],
+            members: Vec::new(),
+        }));
+
+        let help = signature_help(
+            &index,
+            &CallContext {
+                callee: "Payments.processPayment".to_string(),
+                active_parameter: 5,
+            },
+            Path::new("src/App.fs"),
+        )
+        .unwrap();
+
+        let signature = &help.signatures[0];
+        assert_eq!(
+            signature.label,
+            "processPayment(amount: decimal, card: Card)"
+        );
+        assert_eq!(signature.parameters.as_ref().unwrap().len(), 2);
+        // Extra arguments clamp to the last parameter
+        assert_eq!(help.active_parameter, Some(1));
+    }
+}
@@ -0,0 +1,168 @@
+//! Workspace symbol search.
+//!
+//! Queries are abbreviations scored with [`fuzzy_score`] against each
+//! symbol's short name (and, at a discount, its qualified name), so `usvc`
+//! finds `UserService`. As in many editors, a leading `#` limits the results
+//! to types and `@` to functions and members.
+//!
+//! tower-lsp's `workspace/symbol` handler must answer with
+//! `SymbolInformation`, which carries the same fields clients show for a
+//! `WorkspaceSymbol`: container name, tags and location.
+
+use rocketindex::fuzzy::fuzzy_score;
+use rocketindex::{CodeIndex, Symbol, SymbolKind};
+use tower_lsp::lsp_types::{SymbolInformation, SymbolTag};
+
+use crate::{lsp_symbol_kind, to_lsp_location};
+
+/// Most results returned for one query.
+pub const MAX_RESULTS: usize = 100;
+
+/// Symbols matching `query`, best first.
+pub fn workspace_symbols(index: &CodeIndex, query: &str) -> Vec<SymbolInformation> {
+    let (filter, text) = parse_query(query);
+    if text.is_empty() {
+        return Vec::new();
+    }
+
+    let mut scored: Vec<(i64, &Symbol)> = index
+        .symbols()
+        .filter(|symbol| filter.is_none_or(|f| f.accepts(symbol.kind)))
+        .filter_map(|symbol| {
+            let by_name = fuzzy_score(text, &symbol.name);
+            let by_qualified = fuzzy_score(text, &symbol.qualified).map(|s| s / 2);
+            Some((by_name.max(by_qualified)?, symbol))
+        })
+        .collect();
+    scored.sort_by(|(a, x), (b, y)| {
+        b.cmp(a)
+            .then_with(|| x.name.len().cmp(&y.name.len()))
+            .then_with(|| x.qualified.cmp(&y.qualified))
+            .then_with(|| x.location.cmp(&y.location))
+    });
+    scored.truncate(MAX_RESULTS);
+
+    scored
+        .into_iter()
+        .map(|(_, symbol)| to_symbol_information(index, symbol))
+        .collect()
+}
+
+/// Kinds a `#` or `@` prefix narrows the search to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum KindFilter {
+    Types,
+    Callables,
+}
+
+impl KindFilter {
+    fn accepts(self, kind: SymbolKind) -> bool {
+        match self {
+            KindFilter::Types => matches!(
+                kind,
+                SymbolKind::Type
+                    | SymbolKind::Record
+                    | SymbolKind::Union
+                    | SymbolKind::Interface
+                    | SymbolKind::Class
+            ),
+            KindFilter::Callables => kind.is_callable(),
+        }
+    }
+}
+
+fn parse_query(query: &str) -> (Option<KindFilter>, &str) {
+    let query = query.trim();
+    if let Some(rest) = query.strip_prefix('#') {
+        (Some(KindFilter::Types), rest.trim_start())
+    } else if let Some(rest) = query.strip_prefix('@') {
+        (Some(KindFilter::Callables), rest.trim_start())
+    } else {
+        (None, query)
+    }
+}
+
+fn to_symbol_information(index: &CodeIndex, symbol: &Symbol) -> SymbolInformation {
+    let container_name = symbol
+        .qualified
+        .strip_suffix(symbol.name.as_str())
+        .map(|parent| parent.trim_end_matches(['.', ':', '#', '\\']))
+        .filter(|parent| !parent.is_empty())
+        .map(str::to_string);
+
+    #[allow(deprecated)]
+    SymbolInformation {
+        name: symbol.name.clone(),
+        kind: lsp_symbol_kind(symbol),
+        tags: symbol.is_deprecated().then(|| vec![SymbolTag::DEPRECATED]),
+        deprecated: None,
+        location: to_lsp_location(&index.make_location_absolute(&symbol.location)),
+        container_name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocketindex::{Location, Visibility};
+    use std::path::PathBuf;
+
+    fn index() -> CodeIndex {
+        let mut index = CodeIndex::with_root(PathBuf::from("/ws"));
+        for (name, qualified, kind, attributes) in [
+            ("UserService", "App.UserService", SymbolKind::Class, None),
+            (
+                "updateStatus",
+                "App.UserService.updateStatus",
+                SymbolKind::Member,
+                Some(vec!["Obsolete".to_string()]),
+            ),
+            ("Users", "App.Users", SymbolKind::Module, None),
+            ("unusedValue", "App.unusedValue", SymbolKind::Value, None),
+        ] {
+            index.add_symbol(
+                Symbol::new(
+                    name.to_string(),
+                    qualified.to_string(),
+                    kind,
+                    Location::new(PathBuf::from("src/App.fs"), 1, 1),
+                    Visibility::Public,
+                    "fsharp".to_string(),
+                )
+                .with_attributes(attributes),
+            );
+        }
+        index
+    }
+
+    fn names(results: &[SymbolInformation]) -> Vec<&str> {
+        results.iter().map(|s| s.name.as_str()).collect()
+    }
+
+    #[test]
+    fn abbreviations_match_word_starts() {
+        let results = workspace_symbols(&index(), "usvc");
+        // Members of a matching type match through their qualified name
+        assert_eq!(names(&results), vec!["UserService", "updateStatus"]);
+        assert_eq!(results[0].container_name.as_deref(), Some("App"));
+
+        assert!(workspace_symbols(&index(), "").is_empty());
+        assert!(workspace_symbols(&index(), "zz").is_empty());
+    }
+
+    #[test]
+    fn prefixes_filter_by_kind() {
+        assert_eq!(
+            names(&workspace_symbols(&index(), "#us")),
+            vec!["UserService"]
+        );
+
+        let callables = workspace_symbols(&index(), "@us");
+        assert_eq!(names(&callables), vec!["updateStatus"]);
+        assert_eq!(callables[0].tags, Some(vec![SymbolTag::DEPRECATED]));
+        assert_eq!(
+            callables[0].container_name.as_deref(),
+            Some("App.UserService")
+        );
+    }
+}
@@ -0,0 +1,364 @@
+//! Targeted test selection.
+//!
+//! Maps a set of changed symbols to the tests that (transitively) exercise
+//! them by walking callers with [`reverse_spider_with_confidence`], and
+//! renders the selection as a filter for common test runners.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::spider::reverse_spider_with_confidence;
+use crate::{CodeIndex, Confidence, Symbol, SymbolKind};
+
+/// Directory names that hold tests across the supported ecosystems.
+const TEST_DIRS: [&str; 6] = ["test", "tests", "spec", "specs", "__tests__", "testing"];
+
+/// A test reached from a changed symbol.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AffectedTest {
+    /// Test file (as stored in the index)
+    pub file: PathBuf,
+    /// The test function or method, when the file defines one that reaches the change.
+    /// `None` for files whose tests aren't indexed as symbols (e.g. rspec `it` blocks).
+    pub symbol: Option<Symbol>,
+    /// Line of the test symbol, or of the first reference in a file-level match
+    pub line: u32,
+    /// The changed symbol this test reaches
+    pub reaches: String,
+    /// Number of call edges between the test and the changed symbol
+    pub depth: usize,
+}
+
+/// Test runner whose filter syntax to produce.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TestRunner {
+    /// `dotnet test --filter "FullyQualifiedName~A|FullyQualifiedName~B"`
+    Dotnet,
+    /// `rspec spec/a_spec.rb:12 spec/b_spec.rb:40`
+    Rspec,
+    /// `pytest tests/test_a.py::test_x tests/test_b.py::TestB::test_y`
+    Pytest,
+}
+
+impl TestRunner {
+    pub const ALL: [TestRunner; 3] = [TestRunner::Dotnet, TestRunner::Rspec, TestRunner::Pytest];
+
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TestRunner::Dotnet => "dotnet",
+            TestRunner::Rspec => "rspec",
+            TestRunner::Pytest => "pytest",
+        }
+    }
+
+    /// Render `tests` as arguments for this runner.
+    ///
+    /// ```
+    /// use rocketindex::affected::{AffectedTest, TestRunner};
+    ///
+    /// let test = AffectedTest {
+    ///     file: "spec/user_spec.rb".into(),
+    ///     symbol: None,
+    ///     line: 12,
+    ///     reaches: "User.save".to_string(),
+    ///     depth: 1,
+    /// };
+    /// assert_eq!(TestRunner::Rspec.filter(&[test]), "spec/user_spec.rb:12");
+    /// ```
+    #[must_use]
+    pub fn filter(self, tests: &[AffectedTest]) -> String {
+        let mut seen = HashSet::new();
+        let args: Vec<String> = tests
+            .iter()
+            .map(|test| self.filter_arg(test))
+            .filter(|arg| seen.insert(arg.clone()))
+            .collect();
+
+        match self {
+            TestRunner::Dotnet => args.join("|"),
+            TestRunner::Rspec | TestRunner::Pytest => args.join(" "),
+        }
+    }
+
+    fn filter_arg(self, test: &AffectedTest) -> String {
+        let file = test.file.to_string_lossy().replace('\\', "/");
+        match (self, &test.symbol) {
+            (TestRunner::Dotnet, Some(symbol)) => {
+                format!("FullyQualifiedName~{}", symbol.qualified)
+            }
+            // Test classes are conventionally named after their file
+            (TestRunner::Dotnet, None) => format!(
+                "FullyQualifiedName~{}",
+                test.file.file_stem().unwrap_or_default().to_string_lossy()
+            ),
+            // rspec runs the example or group enclosing a line
+            (TestRunner::Rspec, _) => format!("{}:{}", file, test.line),
+            (TestRunner::Pytest, Some(symbol)) => match enclosing_class(symbol) {
+                Some(class) => format!("{}::{}::{}", file, class, symbol.name),
+                None => format!("{}::{}", file, symbol.name),
+            },
+            (TestRunner::Pytest, None) => file,
+        }
+    }
+}
+
+impl std::fmt::Display for TestRunner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for TestRunner {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        TestRunner::ALL
+            .into_iter()
+            .find(|r| r.as_str() == s)
+            .ok_or_else(|| {
+                format!(
+                    "unknown test runner '{}' (expected dotnet, rspec or pytest)",
+                    s
+                )
+            })
+    }
+}
+
+/// Whether `path` looks like a test file by directory or naming convention.
+///
+/// Covers `tests/`, `spec/`, `MyApp.Tests/` directories and `test_*.py`,
+/// `*_test.go`, `*_spec.rb`, `*Tests.cs`, `*.test.ts`-style file names.
+#[must_use]
+pub fn is_test_file(path: &Path) -> bool {
+    let in_test_dir = path.parent().is_some_and(|dir| {
+        dir.components().any(|c| {
+            let name = c.as_os_str().to_string_lossy();
+            TEST_DIRS.contains(&name.to_lowercase().as_str())
+                || name.ends_with(".Tests")
+                || name.ends_with(".Test")
+        })
+    });
+    if in_test_dir {
+        return true;
+    }
+
+    let Some(stem) = path.file_stem().map(|s| s.to_string_lossy()) else {
+        return false;
+    };
+    stem.starts_with("test_")
+        || ["_test", "_spec", ".test", ".spec", "Test", "Tests", "Spec"]
+            .iter()
+            .any(|suffix| stem.len() > suffix.len() && stem.ends_with(suffix))
+}
+
+/// Tests affected by changes to `changed` (qualified names).
+///
+/// Walks callers up to `max_depth` edges from each changed symbol. Callable
+/// symbols in test files are reported directly; test files that only
+/// reference an affected symbol from non-symbol code (rspec/jest blocks) are
+/// reported at file level. Results are ordered by file, then line.
+#[must_use]
+pub fn affected_tests(
+    index: &CodeIndex,
+    changed: &[&str],
+    max_depth: usize,
+    min_confidence: Confidence,
+) -> Vec<AffectedTest> {
+    tests_reaching(index, changed, max_depth, min_confidence, |symbol| {
+        is_test_file(&symbol.location.file) && is_test_case(symbol)
+    })
+}
+
+/// Callers of `changed` for which `is_test` holds, plus file-level matches.
+pub(crate) fn tests_reaching(
+    index: &CodeIndex,
+    changed: &[&str],
+    max_depth: usize,
+    min_confidence: Confidence,
+    is_test: impl Fn(&Symbol) -> bool,
+) -> Vec<AffectedTest> {
+    let mut tests: Vec<AffectedTest> = Vec::new();
+    let mut seen_symbols: HashSet<String> = HashSet::new();
+    let mut file_level: Vec<AffectedTest> = Vec::new();
+    let mut seen_files: HashSet<PathBuf> = HashSet::new();
+    // Test files whose references were already attributed to a caller symbol
+    let mut covered: HashSet<PathBuf> = HashSet::new();
+
+    for changed in changed {
+        let result = reverse_spider_with_confidence(index, changed, max_depth, min_confidence);
+
+        for node in &result.nodes {
+            let symbol = &node.symbol;
+            if node.depth > 0 && is_test_file(&symbol.location.file) {
+                covered.insert(symbol.location.file.clone());
+            }
+            if is_test(symbol) {
+                if seen_symbols.insert(symbol.qualified.clone()) {
+                    tests.push(AffectedTest {
+                        file: symbol.location.file.clone(),
+                        symbol: Some(symbol.clone()),
+                        line: symbol.location.line,
+                        reaches: changed.to_string(),
+                        depth: node.depth,
+                    });
+                }
+                continue;
+            }
+
+            for reference in index.find_references(&symbol.qualified) {
+                let file = &reference.location.file;
+                if is_test_file(file) && seen_files.insert(file.clone()) {
+                    file_level.push(AffectedTest {
+                        file: file.clone(),
+                        symbol: None,
+                        line: reference.location.line,
+                        reaches: changed.to_string(),
+                        depth: node.depth + 1,
+                    });
+                }
+            }
+        }
+    }
+
+    // A file-level entry adds nothing once one of the file's tests was found
+    covered.extend(tests.iter().map(|t| t.file.clone()));
+    tests.extend(
+        file_level
+            .into_iter()
+            .filter(|t| !covered.contains(&t.file)),
+    );
+
+    tests.sort_by(|a, b| a.file.cmp(&b.file).then_with(|| a.line.cmp(&b.line)));
+    tests
+}
+
+/// Test cases are functions and methods; helpers types and modules are not run directly.
+fn is_test_case(symbol: &Symbol) -> bool {
+    matches!(symbol.kind, SymbolKind::Function | SymbolKind::Member)
+}
+
+/// The class segment of a method's qualified name (`tests.test_user.TestUser.test_save` → `TestUser`).
+fn enclosing_class(symbol: &Symbol) -> Option<&str> {
+    if symbol.kind != SymbolKind::Member {
+        return None;
+    }
+    let prefix = symbol.qualified.strip_suffix(symbol.name.as_str())?;
+    let prefix = prefix
+        .strip_suffix('.')
+        .or_else(|| prefix.strip_suffix("::"))?;
+    prefix
+        .rsplit(['.', ':'])
+        .next()
+        .filter(|class| !class.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Location, Reference, ReferenceKind, Visibility};
+
+    fn symbol(qualified: &str, kind: SymbolKind, file: &str, line: u32) -> Symbol {
+        let name = qualified.rsplit('.').next().unwrap();
+        Symbol::new(
+            name.to_string(),
+            qualified.to_string(),
+            kind,
+            Location::new(PathBuf::from(file), line, 5),
+            Visibility::Public,
+            "python".to_string(),
+        )
+    }
+
+    fn reference(name: &str, file: &str, line: u32) -> Reference {
+        Reference {
+            name: name.to_string(),
+            location: Location::new(PathBuf::from(file), line, 9),
+            kind: ReferenceKind::Read,
+        }
+    }
+
+    #[test]
+    fn test_is_test_file() {
+        for path in [
+            "tests/test_user.py",
+            "app/user_test.go",
+            "spec/models/user_spec.rb",
+            "src/MyApp.Tests/UserTests.fs",
+            "src/UserTests.cs",
+            "web/user.test.ts",
+            "web/__tests__/user.js",
+        ] {
+            assert!(is_test_file(Path::new(path)), "{}", path);
+        }
+        for path in ["src/user.py", "src/Contest.cs", "src/Latest.fs", "Test.cs"] {
+            assert!(!is_test_file(Path::new(path)), "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_affected_tests_follow_callers_into_test_files() {
+        let mut index = CodeIndex::new();
+        index.add_symbol(symbol(
+            "app.user.save",
+            SymbolKind::Function,
+            "app/user.py",
+            1,
+        ));
+        index.add_symbol(symbol(
+            "app.api.create",
+            SymbolKind::Function,
+            "app/api.py",
+            1,
+        ));
+        index.add_symbol(symbol(
+            "tests.test_api.TestApi.test_create",
+            SymbolKind::Member,
+            "tests/test_api.py",
+            4,
+        ));
+        index.add_symbol(symbol(
+            "app.other.unrelated",
+            SymbolKind::Function,
+            "app/other.py",
+            1,
+        ));
+        // create -> save, test_create -> create, a spec block -> save
+        index.add_reference(
+            PathBuf::from("app/api.py"),
+            reference("save", "app/api.py", 2),
+        );
+        index.add_reference(
+            PathBuf::from("tests/test_api.py"),
+            reference("create", "tests/test_api.py", 5),
+        );
+        index.add_reference(
+            PathBuf::from("spec/user_spec.rb"),
+            reference("save", "spec/user_spec.rb", 7),
+        );
+
+        let tests = affected_tests(&index, &["app.user.save"], 3, Confidence::StringMatch);
+
+        assert_eq!(tests.len(), 2);
+        assert_eq!(tests[0].file, PathBuf::from("spec/user_spec.rb"));
+        assert!(tests[0].symbol.is_none());
+        assert_eq!(tests[0].line, 7);
+        assert_eq!(tests[1].depth, 2);
+        assert_eq!(tests[1].reaches, "app.user.save");
+
+        assert_eq!(
+            TestRunner::Pytest.filter(&tests),
+            "spec/user_spec.rb tests/test_api.py::TestApi::test_create"
+        );
+        assert_eq!(
+            TestRunner::Dotnet.filter(&tests[1..]),
+            "FullyQualifiedName~tests.test_api.TestApi.test_create"
+        );
+        assert!(
+            affected_tests(&index, &["app.other.unrelated"], 3, Confidence::StringMatch).is_empty()
+        );
+    }
+}
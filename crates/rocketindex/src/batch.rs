@@ -8,9 +8,14 @@ use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
 
-use crate::db::{symbol_kind_to_str, visibility_to_str, SqliteIndex};
+use crate::db::{
+    apply_visibility_rules, referenced_names_in_file, refresh_ref_counts, symbol_guid, symbol_id,
+    symbol_kind_to_str, visibility_to_str, SqliteIndex,
+};
+use crate::test_map::is_test_symbol;
 use crate::watch::WatchEvent;
-use crate::{extract_symbols, IndexError};
+use crate::write_lock::DEFAULT_LOCK_TIMEOUT;
+use crate::{extract_symbols, IndexError, ModuleAlias};
 
 /// Default batch interval (how long to wait before flushing)
 pub const DEFAULT_BATCH_INTERVAL: Duration = Duration::from_millis(100);
@@ -157,8 +162,32 @@ impl BatchProcessor {
 
     /// Flush the batch, processing all pending changes in a single transaction.
     ///
+    /// Waits for the index's write lock first (see [`crate::write_lock`]), so
+    /// concurrent writers don't process the same changes twice.
+    ///
     /// Returns statistics about the flush operation.
     pub fn flush(&mut self, index: &SqliteIndex) -> Result<BatchStats, IndexError> {
+        if self.is_empty() {
+            return Ok(BatchStats::default());
+        }
+
+        // On timeout the events stay pending for the next flush
+        let _lock = index.lock_for_writing(DEFAULT_LOCK_TIMEOUT)?;
+        self.flush_locked(index)
+    }
+
+    /// [`flush`](Self::flush) for a caller that already holds the index's
+    /// write lock.
+    #[tracing::instrument(
+        name = "batch_flush",
+        skip_all,
+        fields(
+            updates = self.pending_updates.len(),
+            deletes = self.pending_deletes.len(),
+            symbols_inserted,
+        )
+    )]
+    pub fn flush_locked(&mut self, index: &SqliteIndex) -> Result<BatchStats, IndexError> {
         let flush_start = Instant::now();
         let mut stats = BatchStats::default();
 
@@ -193,9 +222,12 @@ impl BatchProcessor {
 
         // Now process everything in a single transaction
         index.with_transaction(|tx| {
+            // Names whose reference counts this batch changes
+            let mut touched = HashSet::new();
+
             // Process deletes first (in case a file was renamed)
             for path in &deletes {
-                if let Err(e) = Self::clear_file_in_tx(tx, path) {
+                if let Err(e) = Self::clear_file_in_tx(tx, path, &mut touched) {
                     tracing::warn!("Failed to clear file {:?}: {}", path, e);
                 } else {
                     stats.files_deleted += 1;
@@ -205,7 +237,7 @@ impl BatchProcessor {
             // Process updates
             for (path, result) in &parsed_files {
                 // Clear existing data for this file
-                if let Err(e) = Self::clear_file_in_tx(tx, path) {
+                if let Err(e) = Self::clear_file_in_tx(tx, path, &mut touched) {
                     tracing::warn!("Failed to clear file {:?}: {}", path, e);
                     continue;
                 }
@@ -216,15 +248,22 @@ impl BatchProcessor {
                         tracing::warn!("Failed to insert symbol {}: {}", symbol.name, e);
                     } else {
                         stats.symbols_inserted += 1;
+                        touched.insert(symbol.name.clone());
+                        touched.insert(symbol.qualified.clone());
                     }
                 }
 
+                if let Err(e) = apply_visibility_rules(tx, Some(path)) {
+                    tracing::warn!("Failed to apply visibility rules to {:?}: {}", path, e);
+                }
+
                 // Insert references
                 for reference in &result.references {
                     if let Err(e) = Self::insert_reference_in_tx(tx, path, reference) {
                         tracing::warn!("Failed to insert reference: {}", e);
                     } else {
                         stats.references_inserted += 1;
+                        touched.insert(reference.name.clone());
                     }
                 }
 
@@ -235,18 +274,44 @@ impl BatchProcessor {
                     }
                 }
 
+                // Insert module aliases
+                for (line, alias) in result.aliases.iter().enumerate() {
+                    if let Err(e) = Self::insert_alias_in_tx(tx, path, alias, line as u32 + 1) {
+                        tracing::warn!("Failed to insert module alias: {}", e);
+                    }
+                }
+
+                if let Err(e) = tx.execute(
+                    "INSERT OR REPLACE INTO file_metrics (file, complexity, max_nesting)
+                     VALUES (?1, ?2, ?3)",
+                    rusqlite::params![
+                        path.to_string_lossy().as_ref(),
+                        result.metrics.complexity,
+                        result.metrics.max_nesting
+                    ],
+                ) {
+                    tracing::warn!("Failed to record file metrics: {}", e);
+                }
+
                 stats.files_updated += 1;
             }
 
-            Ok(())
+            refresh_ref_counts(tx, Some(&touched))
         })?;
 
         stats.duration = flush_start.elapsed();
+        tracing::Span::current().record("symbols_inserted", stats.symbols_inserted);
         Ok(stats)
     }
 
-    /// Clear all data for a file within a transaction.
-    fn clear_file_in_tx(tx: &rusqlite::Transaction<'_>, file: &Path) -> Result<(), IndexError> {
+    /// Clear all data for a file within a transaction, adding the names its
+    /// references pointed at to `touched`.
+    fn clear_file_in_tx(
+        tx: &rusqlite::Transaction<'_>,
+        file: &Path,
+        touched: &mut HashSet<String>,
+    ) -> Result<(), IndexError> {
+        touched.extend(referenced_names_in_file(tx, file)?);
         let file_str = file.to_string_lossy();
         tx.execute(
             "DELETE FROM symbols WHERE file = ?1",
@@ -260,6 +325,10 @@ impl BatchProcessor {
             "DELETE FROM opens WHERE file = ?1",
             rusqlite::params![file_str.as_ref()],
         )?;
+        tx.execute(
+            "DELETE FROM file_metrics WHERE file = ?1",
+            rusqlite::params![file_str.as_ref()],
+        )?;
         Ok(())
     }
 
@@ -269,8 +338,8 @@ impl BatchProcessor {
         symbol: &crate::Symbol,
     ) -> Result<(), IndexError> {
         tx.execute(
-            "INSERT INTO symbols (name, qualified, kind, file, line, column, end_line, end_column, visibility, source, language, parent, mixins, attributes, implements, doc, signature)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, 'syntactic', ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
+            "INSERT INTO symbols (name, qualified, kind, file, line, column, end_line, end_column, visibility, source, language, parent, mixins, attributes, implements, doc, signature, is_test, symbol_id, guid)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, 'syntactic', ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19)",
             rusqlite::params![
                 symbol.name,
                 symbol.qualified,
@@ -288,6 +357,9 @@ impl BatchProcessor {
                 symbol.implements.as_ref().map(|v| serde_json::to_string(v).unwrap_or_default()),
                 symbol.doc,
                 symbol.signature,
+                is_test_symbol(symbol),
+                symbol_id(&symbol.qualified, symbol.kind),
+                symbol_guid(&symbol.language, &symbol.qualified, symbol.signature.as_deref()),
             ],
         )?;
         Ok(())
@@ -301,12 +373,16 @@ impl BatchProcessor {
     ) -> Result<(), IndexError> {
         let file_str = file.to_string_lossy();
         tx.execute(
-            "INSERT INTO refs (name, file, line, column) VALUES (?1, ?2, ?3, ?4)",
+            "INSERT INTO refs (name, file, line, column, end_line, end_column, kind)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
             rusqlite::params![
                 reference.name,
                 file_str.as_ref(),
                 reference.location.line,
                 reference.location.column,
+                reference.location.end_line,
+                reference.location.end_column,
+                reference.kind.as_str(),
             ],
         )?;
         Ok(())
@@ -327,6 +403,21 @@ impl BatchProcessor {
         Ok(())
     }
 
+    /// Insert a module alias within a transaction.
+    fn insert_alias_in_tx(
+        tx: &rusqlite::Transaction<'_>,
+        file: &Path,
+        alias: &ModuleAlias,
+        line: u32,
+    ) -> Result<(), IndexError> {
+        let file_str = file.to_string_lossy();
+        tx.execute(
+            "INSERT INTO opens (file, module_path, line, alias) VALUES (?1, ?2, ?3, ?4)",
+            rusqlite::params![file_str.as_ref(), alias.target, line, alias.alias],
+        )?;
+        Ok(())
+    }
+
     /// Force an immediate flush regardless of the batch interval.
     pub fn force_flush(&mut self, index: &SqliteIndex) -> Result<BatchStats, IndexError> {
         self.flush(index)
@@ -569,6 +660,39 @@ mod tests {
         assert!(stats.symbols_inserted > 0);
     }
 
+    #[test]
+    fn test_flush_keeps_reference_counts_current() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let lib = dir.path().join("lib.rs");
+        let main = dir.path().join("main.rs");
+        std::fs::write(&lib, "fn helper() {}\n").unwrap();
+        std::fs::write(&main, "fn main() {\n    helper();\n    helper();\n}\n").unwrap();
+
+        let index = SqliteIndex::in_memory().unwrap();
+        let counts = |index: &SqliteIndex| {
+            let helper = index.search("helper", 1, None).unwrap().remove(0);
+            index
+                .count_symbol_references(&helper.qualified, &helper.name)
+                .unwrap()
+        };
+
+        let mut batch = BatchProcessor::with_defaults(500);
+        batch.add_event(WatchEvent::Created(lib.clone()));
+        batch.add_event(WatchEvent::Created(main.clone()));
+        batch.flush(&index).unwrap();
+        assert_eq!(counts(&index), (1, 2));
+
+        // Only the caller changes; the callee's count follows
+        std::fs::write(&main, "fn main() {\n    helper();\n}\n").unwrap();
+        batch.add_event(WatchEvent::Modified(main.clone()));
+        batch.flush(&index).unwrap();
+        assert_eq!(counts(&index), (1, 1));
+
+        batch.add_event(WatchEvent::Deleted(main));
+        batch.flush(&index).unwrap();
+        assert_eq!(counts(&index), (0, 0));
+    }
+
     #[test]
     fn test_complex_event_sequence() {
         let mut batch = BatchProcessor::with_defaults(500);
@@ -0,0 +1,389 @@
+//! Workspace diagnostics: references that resolve to nothing.
+//!
+//! Every call, type use, base type and implemented interface in the index
+//! is resolved the way `rkt spider` resolves it. The ones that fail are
+//! reported by file, each with a severity:
+//!
+//! - `error`: the name is a slip away from a workspace symbol, so it's
+//!   likely a typo
+//! - `warning`: the name only matches a symbol in a module the file doesn't
+//!   open (F# and Python, where a bare name must be opened or imported)
+//! - `info`: nothing in the workspace is close; usually code from a library
+//!   or the standard library, sometimes a gap in extraction
+//!
+//! Reads and writes aren't checked, since most of them are locals and
+//! parameters, which aren't symbols. Names reached through an import or a
+//! package the workspace references aren't checked either.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::fuzzy::{self, Candidate};
+use crate::spider::try_resolve_reference;
+use crate::{CodeIndex, Confidence, Reference, ReferenceKind};
+
+/// How likely an unresolved reference is to be a mistake.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    /// All levels, from least to most severe.
+    pub const ALL: [Severity; 3] = [Severity::Info, Severity::Warning, Severity::Error];
+
+    /// The name used in CLI flags and JSON output.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for Severity {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Severity::ALL
+            .into_iter()
+            .find(|severity| severity.as_str() == s)
+            .ok_or_else(|| format!("unknown severity '{}' (expected error, warning or info)", s))
+    }
+}
+
+/// Why a reference didn't resolve.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Problem {
+    /// Close to the name of a workspace symbol
+    Typo,
+    /// Names a symbol in a module the file doesn't open
+    MissingOpen,
+    /// Nothing like it in the workspace
+    Unknown,
+}
+
+/// An unresolved reference.
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    pub name: String,
+    pub line: u32,
+    pub column: u32,
+    pub kind: ReferenceKind,
+    pub severity: Severity,
+    pub problem: Problem,
+    /// The name probably meant, or the module to open
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<String>,
+}
+
+/// Unresolved references in one file, in source order.
+#[derive(Debug, Clone, Serialize)]
+pub struct FileFindings {
+    pub file: PathBuf,
+    pub findings: Vec<Finding>,
+}
+
+/// Result of checking a whole index.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CheckReport {
+    /// Number of references checked
+    pub checked: usize,
+    /// Files with findings, sorted by path
+    pub files: Vec<FileFindings>,
+}
+
+impl CheckReport {
+    /// Number of findings of `severity`.
+    #[must_use]
+    pub fn count(&self, severity: Severity) -> usize {
+        self.findings()
+            .filter(|finding| finding.severity == severity)
+            .count()
+    }
+
+    /// The most severe finding's severity, if there are any.
+    #[must_use]
+    pub fn worst(&self) -> Option<Severity> {
+        self.findings().map(|finding| finding.severity).max()
+    }
+
+    /// Drop findings below `severity`, and files left without any.
+    pub fn retain_at_least(&mut self, severity: Severity) {
+        for file in &mut self.files {
+            file.findings.retain(|finding| finding.severity >= severity);
+        }
+        self.files.retain(|file| !file.findings.is_empty());
+    }
+
+    fn findings(&self) -> impl Iterator<Item = &Finding> {
+        self.files.iter().flat_map(|file| &file.findings)
+    }
+}
+
+/// Edits a name may be away from a symbol to count as a typo of it.
+const TYPO_DISTANCE: usize = 2;
+
+/// Names shorter than this are too easily close to something by chance.
+const TYPO_MIN_LENGTH: usize = 4;
+
+/// Check every call, type use, base type and implemented interface in
+/// `index`.
+#[must_use]
+pub fn check(index: &CodeIndex) -> CheckReport {
+    let names: Vec<&str> = {
+        let mut names: Vec<&str> = index.symbols().map(|s| s.name.as_str()).collect();
+        names.sort_unstable();
+        names.dedup();
+        names
+    };
+    let kinds: std::collections::HashMap<&str, crate::SymbolKind> =
+        index.symbols().map(|s| (s.name.as_str(), s.kind)).collect();
+
+    let mut files: Vec<&PathBuf> = index.files().collect();
+    files.sort();
+
+    let mut report = CheckReport::default();
+    for file in files {
+        let references = index.references_in_file(file);
+        let imported = imported_names(index, file, references);
+        let mut findings = Vec::new();
+
+        for reference in references.iter().filter(|r| is_checked(r.kind)) {
+            report.checked += 1;
+            let name = without_receiver(&reference.name);
+            let head = name.split(['.', ':']).next().unwrap_or(name);
+            if imported.contains(head) {
+                continue;
+            }
+            let opens = index.opens_for_file(file);
+            if let Some((resolved, confidence)) = try_resolve_reference(index, name, opens, file) {
+                if let Some(module) = missing_open(index, file, name, &resolved, confidence) {
+                    findings.push(finding(
+                        reference,
+                        Severity::Warning,
+                        Problem::MissingOpen,
+                        Some(module),
+                    ));
+                }
+                continue;
+            }
+            let external = index
+                .external_index()
+                .and_then(|external| external.package_of(name, opens));
+            if external.is_some() {
+                continue;
+            }
+
+            let last = name.rsplit(['.', ':']).next().unwrap_or(name);
+            let typo = (last.chars().count() >= TYPO_MIN_LENGTH)
+                .then(|| {
+                    let candidates = names.iter().map(|&value| Candidate {
+                        value,
+                        kind: kinds[value],
+                        references: 0,
+                    });
+                    fuzzy::rank_suggestions(last, candidates, TYPO_DISTANCE, 1)
+                })
+                .and_then(|suggestions| suggestions.into_iter().next());
+            findings.push(match typo {
+                Some(suggestion) => finding(
+                    reference,
+                    Severity::Error,
+                    Problem::Typo,
+                    Some(suggestion.value),
+                ),
+                None => finding(reference, Severity::Info, Problem::Unknown, None),
+            });
+        }
+
+        if !findings.is_empty() {
+            findings.sort_by_key(|f| (f.line, f.column));
+            report.files.push(FileFindings {
+                file: file.clone(),
+                findings,
+            });
+        }
+    }
+    report
+}
+
+fn finding(
+    reference: &Reference,
+    severity: Severity,
+    problem: Problem,
+    suggestion: Option<String>,
+) -> Finding {
+    Finding {
+        name: reference.name.clone(),
+        line: reference.location.line,
+        column: reference.location.column,
+        kind: reference.kind,
+        severity,
+        problem,
+        suggestion,
+    }
+}
+
+fn is_checked(kind: ReferenceKind) -> bool {
+    matches!(
+        kind,
+        ReferenceKind::Call
+            | ReferenceKind::TypeUse
+            | ReferenceKind::Inherit
+            | ReferenceKind::Implement
+    )
+}
+
+/// A member reached through `self`, `this` and the like, as its bare name.
+fn without_receiver(name: &str) -> &str {
+    ["self.", "this.", "cls.", "super.", "Self::", "self::", "@"]
+        .into_iter()
+        .find_map(|receiver| name.strip_prefix(receiver))
+        .unwrap_or(name)
+}
+
+/// Names the file brings in from elsewhere: what it imports and opens, as
+/// written and by their last segment (`from os import path` gives `path`).
+fn imported_names<'a>(
+    index: &'a CodeIndex,
+    file: &Path,
+    references: &'a [Reference],
+) -> HashSet<&'a str> {
+    references
+        .iter()
+        .filter(|r| r.kind == ReferenceKind::Import)
+        .map(|r| r.name.as_str())
+        .chain(index.opens_for_file(file).iter().map(String::as_str))
+        .flat_map(|name| [name, name.rsplit(['.', ':', '/']).next().unwrap_or(name)])
+        .collect()
+}
+
+/// The module to open when a bare `name` only resolved by matching a
+/// symbol's short name in a module the file can't see, in languages where
+/// that's a mistake.
+fn missing_open(
+    index: &CodeIndex,
+    file: &Path,
+    name: &str,
+    resolved: &str,
+    confidence: Confidence,
+) -> Option<String> {
+    let needs_open = matches!(
+        file.extension().and_then(|e| e.to_str()),
+        Some("fs" | "fsi" | "fsx" | "py")
+    );
+    if !needs_open || confidence != Confidence::Heuristic || name.contains(['.', ':']) {
+        return None;
+    }
+    let module = resolved.strip_suffix(name)?.strip_suffix('.')?;
+    // Names from the file's own modules, and their enclosing ones, are in scope
+    let in_scope = index.symbols_in_file(file).iter().any(|symbol| {
+        symbol.qualified == module || symbol.qualified.starts_with(&format!("{}.", module))
+    });
+    (!in_scope).then(|| module.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index_with(files: &[(&str, &str)]) -> CodeIndex {
+        let mut index = CodeIndex::with_root(PathBuf::from("/ws"));
+        for (file, source) in files {
+            let result = crate::extract_symbols(Path::new(file), source, 500);
+            for symbol in result.symbols {
+                index.add_symbol(symbol);
+            }
+            for reference in result.references {
+                index.add_reference(PathBuf::from(file), reference);
+            }
+            for open in result.opens {
+                index.add_open(PathBuf::from(file), open);
+            }
+        }
+        index
+    }
+
+    #[test]
+    fn reports_typos_missing_opens_and_unknown_names() {
+        let index = index_with(&[
+            (
+                "src/Billing.fs",
+                "module Billing\n\nlet charge amount = amount * 2\n",
+            ),
+            (
+                "src/App.fs",
+                "module App\n\nlet run () =\n    charge 1 |> ignore\n    Billing.chrage 2 |> ignore\n    printfn \"done\"\n",
+            ),
+        ]);
+
+        let report = check(&index);
+        assert_eq!(report.files.len(), 1);
+        let app = &report.files[0];
+        assert_eq!(app.file, PathBuf::from("src/App.fs"));
+
+        let summary: Vec<(&str, Severity, Problem, Option<&str>)> = app
+            .findings
+            .iter()
+            .map(|f| {
+                (
+                    f.name.as_str(),
+                    f.severity,
+                    f.problem,
+                    f.suggestion.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    "charge",
+                    Severity::Warning,
+                    Problem::MissingOpen,
+                    Some("Billing")
+                ),
+                (
+                    "Billing.chrage",
+                    Severity::Error,
+                    Problem::Typo,
+                    Some("charge")
+                ),
+                ("printfn", Severity::Info, Problem::Unknown, None),
+            ]
+        );
+        assert_eq!(report.worst(), Some(Severity::Error));
+    }
+
+    #[test]
+    fn names_in_scope_are_not_reported() {
+        let index = index_with(&[(
+            "billing.py",
+            "import os\nfrom helpers import format_name\n\ndef charge(amount):\n    return format_name(amount)\n\ndef checkout():\n    charge(1)\n    os.path.join('a', 'b')\n",
+        )]);
+
+        let mut report = check(&index);
+        assert!(report.checked >= 3);
+        // Only method calls on library objects (`join`) are left, as info
+        assert_eq!(report.worst(), Some(Severity::Info), "{:?}", report.files);
+        assert_eq!(report.count(Severity::Info), 1);
+
+        report.retain_at_least(Severity::Warning);
+        assert!(report.files.is_empty());
+    }
+}
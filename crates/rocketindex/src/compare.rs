@@ -0,0 +1,304 @@
+//! Symbol-level comparison of two revisions.
+//!
+//! Each revision is indexed in memory from git's object store (see
+//! [`crate::git::files_at_rev`]), so neither has to be checked out. Symbols
+//! are matched by qualified name and kind; a matched symbol has changed when
+//! the text of its definition differs, ignoring indentation. Callers affected
+//! by the changes are found with [`analyze_impact`]: for changed symbols in
+//! the newer revision, for removed ones in the older revision, where their
+//! callers were last seen.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::git::RevisionFile;
+use crate::impact::{analyze_impact, keep_closest, sorted, ChangedSymbol, ImpactedSymbol};
+use crate::{extract_symbols, CodeIndex, Confidence, Symbol};
+
+/// One revision, indexed in memory.
+pub struct Snapshot {
+    pub index: CodeIndex,
+    /// File contents by path relative to the root
+    sources: HashMap<PathBuf, String>,
+}
+
+impl Snapshot {
+    /// Index `files` (paths relative to `root`) as if checked out at `root`.
+    pub fn new(root: &Path, files: Vec<RevisionFile>, max_depth: usize) -> Self {
+        let mut index = CodeIndex::with_root(root.to_path_buf());
+        let mut sources = HashMap::with_capacity(files.len());
+        for file in files {
+            let path = root.join(&file.path);
+            let result = extract_symbols(&path, &file.content, max_depth);
+            for symbol in result.symbols {
+                index.add_symbol(symbol);
+            }
+            for reference in result.references {
+                index.add_reference(path.clone(), reference);
+            }
+            for open in result.opens {
+                index.add_open(path.clone(), open);
+            }
+            for alias in result.aliases {
+                index.add_alias(path.clone(), alias);
+            }
+            sources.insert(file.path, file.content);
+        }
+        Self { index, sources }
+    }
+
+    /// Every definition, ordered by file then position.
+    fn definitions(&self) -> Vec<&Symbol> {
+        let mut all: Vec<&Symbol> = self
+            .index
+            .all_qualified_names()
+            .iter()
+            .flat_map(|name| self.index.get_all(name))
+            .collect();
+        all.sort_by(|a, b| {
+            (&a.location.file, a.location.line, a.location.column).cmp(&(
+                &b.location.file,
+                b.location.line,
+                b.location.column,
+            ))
+        });
+        all
+    }
+
+    /// Text of a symbol's definition with indentation stripped, so moving
+    /// or re-indenting a definition doesn't count as changing it.
+    ///
+    /// Parsers that record only the name's position leave the span on one
+    /// line; those definitions run up to the next one in the file, as in
+    /// [`CodeIndex::symbols_in_range`].
+    fn body(&self, symbol: &Symbol) -> String {
+        let Some(source) = self.sources.get(&symbol.location.file) else {
+            return String::new();
+        };
+        let start = symbol.location.line.max(1) as usize - 1;
+        let end = if symbol.location.end_line > symbol.location.line {
+            symbol.location.end_line as usize
+        } else {
+            self.index
+                .symbols_in_file(&symbol.location.file)
+                .iter()
+                .map(|s| s.location.line as usize - 1)
+                .filter(|&line| line > start)
+                .min()
+                .unwrap_or(usize::MAX)
+        };
+        source
+            .lines()
+            .skip(start)
+            .take(end.saturating_sub(start))
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// What differs about a symbol present in both revisions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Change {
+    Signature,
+    Visibility,
+    Body,
+}
+
+/// A symbol whose definition differs between the revisions.
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangedDefinition {
+    pub before: Symbol,
+    pub after: Symbol,
+    pub changes: Vec<Change>,
+}
+
+/// Symbol-level differences between two revisions.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Comparison {
+    pub added: Vec<Symbol>,
+    pub removed: Vec<Symbol>,
+    pub changed: Vec<ChangedDefinition>,
+    /// Callers of changed and removed symbols, closest first
+    pub callers: Vec<ImpactedSymbol>,
+}
+
+impl Comparison {
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Compare revision `before` with `after`, walking callers of what changed
+/// up to `max_depth` edges.
+#[must_use]
+pub fn compare(
+    before: &Snapshot,
+    after: &Snapshot,
+    max_depth: usize,
+    min_confidence: Confidence,
+) -> Comparison {
+    // Overloads share a name, so match the nth definition with the nth
+    type Key<'a> = (&'a str, String);
+    fn group(symbols: Vec<&Symbol>) -> BTreeMap<Key<'_>, Vec<&Symbol>> {
+        let mut groups: BTreeMap<Key<'_>, Vec<&Symbol>> = BTreeMap::new();
+        for symbol in symbols {
+            groups
+                .entry((symbol.qualified.as_str(), symbol.kind.to_string()))
+                .or_default()
+                .push(symbol);
+        }
+        groups
+    }
+    let old = group(before.definitions());
+    let new = group(after.definitions());
+
+    let mut comparison = Comparison::default();
+    for (key, old_symbols) in &old {
+        let new_symbols = new.get(key).map(Vec::as_slice).unwrap_or_default();
+        for (i, &old_symbol) in old_symbols.iter().enumerate() {
+            let Some(&new_symbol) = new_symbols.get(i) else {
+                comparison.removed.push(old_symbol.clone());
+                continue;
+            };
+            let mut changes = Vec::new();
+            if old_symbol.signature != new_symbol.signature {
+                changes.push(Change::Signature);
+            }
+            if old_symbol.visibility != new_symbol.visibility {
+                changes.push(Change::Visibility);
+            }
+            if before.body(old_symbol) != after.body(new_symbol) {
+                changes.push(Change::Body);
+            }
+            if !changes.is_empty() {
+                comparison.changed.push(ChangedDefinition {
+                    before: old_symbol.clone(),
+                    after: new_symbol.clone(),
+                    changes,
+                });
+            }
+        }
+    }
+    for (key, new_symbols) in &new {
+        let known = old.get(key).map_or(0, Vec::len);
+        comparison
+            .added
+            .extend(new_symbols.iter().skip(known).map(|&s| s.clone()));
+    }
+
+    let by_position = |a: &Symbol, b: &Symbol| {
+        (&a.location.file, a.location.line).cmp(&(&b.location.file, b.location.line))
+    };
+    comparison.added.sort_by(by_position);
+    comparison.removed.sort_by(by_position);
+    comparison
+        .changed
+        .sort_by(|a, b| by_position(&a.after, &b.after));
+
+    let as_changed = |symbol: &Symbol| ChangedSymbol {
+        symbol: symbol.clone(),
+        lines: vec![symbol.location.line],
+    };
+    let changed: Vec<ChangedSymbol> = comparison
+        .changed
+        .iter()
+        .map(|c| as_changed(&c.after))
+        .collect();
+    let removed: Vec<ChangedSymbol> = comparison.removed.iter().map(as_changed).collect();
+
+    // A caller that is itself added, removed or changed is reported as such
+    let listed: HashSet<&str> = comparison
+        .added
+        .iter()
+        .chain(&comparison.removed)
+        .chain(comparison.changed.iter().map(|c| &c.after))
+        .map(|s| s.qualified.as_str())
+        .collect();
+    let mut callers: HashMap<String, ImpactedSymbol> = HashMap::new();
+    for impacted in analyze_impact(&after.index, changed, max_depth, min_confidence)
+        .callers
+        .into_iter()
+        .chain(analyze_impact(&before.index, removed, max_depth, min_confidence).callers)
+    {
+        if listed.contains(impacted.symbol.qualified.as_str()) {
+            continue;
+        }
+        keep_closest(&mut callers, impacted);
+    }
+    comparison.callers = sorted(callers);
+    comparison
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(files: &[(&str, &str)]) -> Snapshot {
+        let files = files
+            .iter()
+            .map(|(path, content)| RevisionFile {
+                path: PathBuf::from(path),
+                content: content.to_string(),
+            })
+            .collect();
+        Snapshot::new(Path::new("/repo"), files, 100)
+    }
+
+    #[test]
+    fn test_compare_reports_added_removed_changed_and_callers() {
+        let before = snapshot(&[
+            (
+                "billing.py",
+                "def charge(amount):\n    return amount\n\n\ndef legacy():\n    return 0\n",
+            ),
+            (
+                "api.py",
+                "def checkout(amount):\n    return charge(amount)\n\n\ndef report():\n    return legacy()\n",
+            ),
+        ]);
+        let after = snapshot(&[
+            (
+                "billing.py",
+                "def charge(amount):\n    return amount * 2\n\n\ndef refund(amount):\n    return -amount\n",
+            ),
+            (
+                "api.py",
+                "def checkout(amount):\n    return charge(amount)\n\n\ndef report():\n    return legacy()\n",
+            ),
+        ]);
+
+        let comparison = compare(&before, &after, 3, Confidence::StringMatch);
+        let names = |symbols: &[Symbol]| -> Vec<String> {
+            symbols.iter().map(|s| s.name.clone()).collect()
+        };
+        assert_eq!(names(&comparison.added), vec!["refund"]);
+        assert_eq!(names(&comparison.removed), vec!["legacy"]);
+        assert_eq!(comparison.changed.len(), 1);
+        assert_eq!(comparison.changed[0].after.name, "charge");
+        assert_eq!(comparison.changed[0].changes, vec![Change::Body]);
+
+        let callers: Vec<&str> = comparison
+            .callers
+            .iter()
+            .map(|c| c.symbol.name.as_str())
+            .collect();
+        assert_eq!(callers, vec!["checkout", "report"]);
+        assert_eq!(
+            comparison.callers[1].reaches,
+            comparison.removed[0].qualified
+        );
+    }
+
+    #[test]
+    fn test_moving_a_definition_is_not_a_change() {
+        let before = snapshot(&[("a.py", "def f():\n    return 1\n")]);
+        let after = snapshot(&[("a.py", "import os\n\n\ndef f():\n    return 1\n")]);
+        assert!(compare(&before, &after, 3, Confidence::StringMatch).is_empty());
+    }
+}
@@ -0,0 +1,442 @@
+//! Conditional compilation: `#if` regions (F#, C#) and `#[cfg]` items (Rust).
+//!
+//! Symbols defined inside a conditional region carry its condition as an
+//! attribute: `#if DEBUG` for F# and C#, `cfg(feature = "serde")` for Rust
+//! (the form Rust attributes are already recorded in). Each enclosing
+//! region adds one attribute, so a symbol under `#if A` and `#if B` has
+//! both. Whether the symbol is compiled depends on the symbols defined for
+//! the build, which `rkt index --define` records in the index metadata under
+//! [`DEFINES_KEY`].
+
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::parse::{node_to_location, ParseResult};
+use crate::Symbol;
+
+/// Metadata key holding the JSON list of defines the index was built with.
+pub const DEFINES_KEY: &str = "defines";
+
+/// A span of source compiled only under `attribute`'s condition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Region {
+    /// First (line, column) inside the region, 1-indexed
+    pub start: (u32, u32),
+    /// Last (line, column) inside the region, 1-indexed
+    pub end: (u32, u32),
+    /// `#if DEBUG` or `cfg(test)`
+    pub attribute: String,
+}
+
+/// Whether a conditionally compiled symbol is part of the build.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Compilation {
+    /// All of the symbol's conditions, combined: `DEBUG && !TRACE`,
+    /// `all(unix, feature = "serde")`
+    pub condition: String,
+    /// Whether the condition holds for the defines the index was built with
+    pub compiled: bool,
+}
+
+/// The conditions `symbol` is compiled under, outermost first.
+pub fn conditions(symbol: &Symbol) -> Vec<&str> {
+    symbol
+        .attributes
+        .iter()
+        .flatten()
+        .filter_map(|attribute| {
+            attribute.strip_prefix("#if ").or_else(|| {
+                attribute
+                    .strip_prefix("cfg(")
+                    .and_then(|inner| inner.strip_suffix(')'))
+            })
+        })
+        .map(str::trim)
+        .collect()
+}
+
+/// Whether `symbol` is compiled with `defines`, or `None` if it isn't
+/// conditional at all.
+pub fn compilation(symbol: &Symbol, defines: &[String]) -> Option<Compilation> {
+    let conditions = conditions(symbol);
+    let condition = match conditions.as_slice() {
+        [] => return None,
+        [condition] => condition.to_string(),
+        _ if symbol.language == "rust" => format!("all({})", conditions.join(", ")),
+        _ => conditions
+            .iter()
+            .map(|c| wrap(c))
+            .collect::<Vec<_>>()
+            .join(" && "),
+    };
+    let compiled = conditions.iter().all(|c| holds(c, defines));
+    Some(Compilation {
+        condition,
+        compiled,
+    })
+}
+
+/// Whether `condition` holds when exactly `defines` are defined.
+///
+/// Understands `#if` expressions (`DEBUG && !(TRACE || NETSTANDARD)`) and
+/// `cfg` predicates (`all(unix, not(test))`, `feature = "serde"`). A
+/// `key = "value"` predicate holds when `key=value` is defined. Conditions
+/// that can't be parsed are assumed to hold.
+pub fn holds(condition: &str, defines: &[String]) -> bool {
+    let defines: Vec<String> = defines.iter().map(|d| normalize(d)).collect();
+    let tokens = tokenize(condition);
+    let mut parser = Parser {
+        tokens: &tokens,
+        at: 0,
+        defines: &defines,
+    };
+    match parser.or() {
+        Some(value) if parser.at == tokens.len() => value,
+        _ => true,
+    }
+}
+
+/// `feature = "serde"` and `feature=serde` both become `feature=serde`.
+fn normalize(define: &str) -> String {
+    define
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '"')
+        .collect()
+}
+
+fn wrap(condition: &str) -> String {
+    if condition.contains(' ') {
+        format!("({})", condition)
+    } else {
+        condition.to_string()
+    }
+}
+
+fn negate(condition: &str) -> String {
+    format!("!{}", wrap(condition))
+}
+
+fn tokenize(condition: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut chars = condition.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '&' | '|' => {
+                chars.next_if_eq(&c);
+                tokens.push(format!("{c}{c}"));
+            }
+            '"' => {
+                let mut text = String::from('"');
+                for c in chars.by_ref() {
+                    if c == '"' {
+                        break;
+                    }
+                    text.push(c);
+                }
+                tokens.push(text);
+            }
+            c if c.is_alphanumeric() || c == '_' || c == '.' => {
+                let mut word = String::from(c);
+                while let Some(c) = chars.next_if(|c| c.is_alphanumeric() || *c == '_' || *c == '.')
+                {
+                    word.push(c);
+                }
+                tokens.push(word);
+            }
+            c => tokens.push(c.to_string()),
+        }
+    }
+    tokens
+}
+
+struct Parser<'a> {
+    tokens: &'a [String],
+    at: usize,
+    defines: &'a [String],
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.at).map(String::as_str)
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        let matched = self.peek() == Some(token);
+        if matched {
+            self.at += 1;
+        }
+        matched
+    }
+
+    fn or(&mut self) -> Option<bool> {
+        let mut value = self.and()?;
+        while self.eat("||") {
+            value |= self.and()?;
+        }
+        Some(value)
+    }
+
+    fn and(&mut self) -> Option<bool> {
+        let mut value = self.unary()?;
+        while self.eat("&&") {
+            value &= self.unary()?;
+        }
+        Some(value)
+    }
+
+    fn unary(&mut self) -> Option<bool> {
+        if self.eat("!") {
+            return Some(!self.unary()?);
+        }
+        if self.eat("(") {
+            let value = self.or()?;
+            return self.eat(")").then_some(value);
+        }
+        let word = self.peek()?.to_string();
+        if !word.starts_with(|c: char| c.is_alphanumeric() || c == '_') {
+            return None;
+        }
+        self.at += 1;
+
+        // cfg predicates: not(..), all(..), any(..)
+        if self.eat("(") {
+            let mut values = Vec::new();
+            while !self.eat(")") {
+                values.push(self.or()?);
+                if !self.eat(",") && self.peek() != Some(")") {
+                    return None;
+                }
+            }
+            return match word.as_str() {
+                "not" if values.len() == 1 => Some(!values[0]),
+                "all" => Some(values.iter().all(|v| *v)),
+                "any" => Some(values.iter().any(|v| *v)),
+                _ => None,
+            };
+        }
+        if self.eat("=") {
+            let value = self.peek()?.strip_prefix('"')?.to_string();
+            self.at += 1;
+            let define = format!("{}={}", word, value);
+            return Some(self.defines.contains(&define));
+        }
+        Some(match word.as_str() {
+            "true" => true,
+            "false" => false,
+            _ => self.defines.contains(&word),
+        })
+    }
+}
+
+/// `#if`/`#elif`/`#else`/`#endif` regions of F# or C# source, by line.
+///
+/// Each branch is a region of its own: after `#if A`, `#elif B` covers
+/// `!A && B` and `#else` covers `!A && !B`.
+pub(crate) fn preprocessor_regions(source: &str) -> Vec<Region> {
+    struct Open {
+        /// First line of the current branch
+        start: u32,
+        /// Conditions of the earlier branches of this `#if`
+        earlier: Vec<String>,
+        /// The current branch's own condition, empty for `#else`
+        own: String,
+    }
+
+    impl Open {
+        fn condition(&self) -> String {
+            let mut parts: Vec<String> = self.earlier.iter().map(|c| negate(c)).collect();
+            if !self.own.is_empty() {
+                parts.push(if parts.is_empty() {
+                    self.own.clone()
+                } else {
+                    wrap(&self.own)
+                });
+            }
+            parts.join(" && ")
+        }
+
+        fn close(&self, line: u32, regions: &mut Vec<Region>) {
+            if line > self.start {
+                regions.push(Region {
+                    start: (self.start, 1),
+                    end: (line - 1, u32::MAX),
+                    attribute: format!("#if {}", self.condition()),
+                });
+            }
+        }
+    }
+
+    let mut regions = Vec::new();
+    let mut open: Vec<Open> = Vec::new();
+    for (i, text) in source.lines().enumerate() {
+        let line = i as u32 + 1;
+        let Some(directive) = text.trim_start().strip_prefix('#') else {
+            continue;
+        };
+        let (keyword, rest) = directive
+            .split_once(char::is_whitespace)
+            .unwrap_or((directive, ""));
+        let rest = rest.split("//").next().unwrap_or_default().trim();
+        match (keyword, open.last_mut()) {
+            ("if", _) => open.push(Open {
+                start: line + 1,
+                earlier: Vec::new(),
+                own: rest.to_string(),
+            }),
+            ("elif" | "else", Some(branch)) => {
+                branch.close(line, &mut regions);
+                let own = std::mem::take(&mut branch.own);
+                branch.earlier.push(own);
+                if keyword == "elif" {
+                    branch.own = rest.to_string();
+                }
+                branch.start = line + 1;
+            }
+            ("endif", Some(_)) => {
+                if let Some(branch) = open.pop() {
+                    branch.close(line, &mut regions);
+                }
+            }
+            _ => {}
+        }
+    }
+    // Outermost first, as nested regions close before the ones around them
+    regions.sort_by_key(|region| region.start);
+    regions
+}
+
+/// `#[cfg(...)]` items of Rust source, and the items an inner
+/// `#![cfg(...)]` applies to.
+pub(crate) fn cfg_regions(root: &tree_sitter::Node, source: &[u8], file: &Path) -> Vec<Region> {
+    let span = |node: &tree_sitter::Node| {
+        let location = node_to_location(file, node);
+        (
+            (location.line, location.column),
+            (location.end_line, location.end_column),
+        )
+    };
+    let mut regions = Vec::new();
+    let mut cursor = root.walk();
+    'walk: loop {
+        let node = cursor.node();
+        let target = match node.kind() {
+            "attribute_item" => {
+                let mut next = node.next_named_sibling();
+                while let Some(sibling) = next.filter(|s| {
+                    matches!(
+                        s.kind(),
+                        "attribute_item" | "line_comment" | "block_comment"
+                    )
+                }) {
+                    next = sibling.next_named_sibling();
+                }
+                next
+            }
+            "inner_attribute_item" => node.parent(),
+            _ => None,
+        };
+        if let (Some(target), Ok(text)) = (target, node.utf8_text(source)) {
+            let attribute = text
+                .trim_start_matches("#!")
+                .trim_start_matches("#[")
+                .trim_end_matches(']');
+            if attribute.starts_with("cfg(") {
+                let (start, end) = span(&target);
+                regions.push(Region {
+                    start,
+                    end,
+                    attribute: attribute.to_string(),
+                });
+            }
+        }
+        if cursor.goto_first_child() {
+            continue;
+        }
+        while !cursor.goto_next_sibling() {
+            if !cursor.goto_parent() {
+                break 'walk;
+            }
+        }
+    }
+    regions
+}
+
+/// Add each region's condition to the symbols defined inside it.
+pub(crate) fn tag_symbols(result: &mut ParseResult, regions: &[Region]) {
+    for symbol in &mut result.symbols {
+        let at = (symbol.location.line, symbol.location.column);
+        for region in regions.iter().filter(|r| r.start <= at && at <= r.end) {
+            let attributes = symbol.attributes.get_or_insert_with(Vec::new);
+            if !attributes.contains(&region.attribute) {
+                attributes.push(region.attribute.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract_symbols;
+
+    fn defines(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn evaluates_if_and_cfg_conditions() {
+        let debug = defines(&["DEBUG", "feature = \"serde\""]);
+        assert!(holds("DEBUG", &debug));
+        assert!(!holds("TRACE", &debug));
+        assert!(holds("DEBUG && !TRACE", &debug));
+        assert!(!holds("!(DEBUG || TRACE)", &debug));
+        assert!(holds("feature = \"serde\"", &debug));
+        assert!(!holds("feature = \"json\"", &debug));
+        assert!(holds("all(feature = \"serde\", not(test))", &debug));
+        assert!(!holds("any(test, unix)", &debug));
+        // Unparseable conditions don't hide anything
+        assert!(holds("DEBUG &&", &debug));
+    }
+
+    #[test]
+    fn tags_symbols_in_if_regions() {
+        let source = "module App\n\n#if DEBUG\nlet trace x = x\n#if VERBOSE\nlet dump x = x\n#endif\n#elif TRACE\nlet tracing x = x\n#else\nlet quiet x = x\n#endif\n\nlet run () = ()\n";
+        let result = extract_symbols(Path::new("App.fs"), source, 100);
+        let symbol = |name: &str| result.symbols.iter().find(|s| s.name == name).unwrap();
+
+        assert_eq!(conditions(symbol("trace")), vec!["DEBUG"]);
+        assert_eq!(conditions(symbol("dump")), vec!["DEBUG", "VERBOSE"]);
+        assert_eq!(conditions(symbol("tracing")), vec!["!DEBUG && TRACE"]);
+        assert_eq!(conditions(symbol("quiet")), vec!["!DEBUG && !TRACE"]);
+        assert!(conditions(symbol("run")).is_empty());
+
+        let dump = compilation(symbol("dump"), &defines(&["DEBUG"])).unwrap();
+        assert_eq!(dump.condition, "DEBUG && VERBOSE");
+        assert!(!dump.compiled);
+        assert!(compilation(symbol("quiet"), &[]).unwrap().compiled);
+        assert!(compilation(symbol("run"), &[]).is_none());
+    }
+
+    #[test]
+    fn tags_items_inside_cfg_modules() {
+        let source = "#[cfg(feature = \"serde\")]\npub fn to_json() {}\n\n#[cfg(test)]\nmod tests {\n    fn helper() {}\n}\n\npub fn run() {}\n";
+        let result = extract_symbols(Path::new("lib.rs"), source, 100);
+        let symbol = |name: &str| result.symbols.iter().find(|s| s.name == name).unwrap();
+
+        assert_eq!(conditions(symbol("to_json")), vec!["feature = \"serde\""]);
+        assert_eq!(conditions(symbol("tests")), vec!["test"]);
+        assert_eq!(conditions(symbol("helper")), vec!["test"]);
+        assert!(conditions(symbol("run")).is_empty());
+
+        let helper = compilation(symbol("helper"), &[]).unwrap();
+        assert_eq!(
+            helper,
+            Compilation {
+                condition: "test".to_string(),
+                compiled: false,
+            }
+        );
+    }
+}
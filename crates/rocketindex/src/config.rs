@@ -8,7 +8,10 @@ use figment::{
     Figment,
 };
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::Visibility;
 
 /// Default directories to exclude from indexing.
 ///
@@ -39,6 +42,125 @@ pub struct Config {
     /// Whether to respect .gitignore files when indexing (default: true).
     #[serde(default = "default_respect_gitignore")]
     pub respect_gitignore: bool,
+
+    /// Whether to scan string literals for qualified symbol names (default: false).
+    ///
+    /// Catches reflection and DI registrations at the cost of some noise;
+    /// matches are reported as low-confidence references.
+    #[serde(default)]
+    pub string_references: bool,
+
+    /// Project roots of a multi-root workspace, relative to the config file
+    /// (default: none, the whole directory is one project).
+    ///
+    /// A `*` in a path segment matches any directory name, so
+    /// `["services/*", "libs/common"]` makes each service its own project.
+    /// Only files under a root are indexed.
+    #[serde(default)]
+    pub roots: Vec<String>,
+
+    /// Other repositories whose indexes are searched, read-only, for symbols
+    /// this workspace doesn't define (default: none). Keys name the
+    /// repository in results; values are its checkout relative to the config
+    /// file, e.g. `[attach]` / `shared = "../shared-lib"`.
+    /// See [`crate::federation`].
+    #[serde(default)]
+    pub attach: BTreeMap<String, String>,
+
+    /// Whether to follow Ruby on Rails naming conventions (default: false).
+    ///
+    /// Resolves `before_action` callbacks to inherited controller methods
+    /// and association or table names (`:line_items`) to their models, and
+    /// lists the views a controller action renders in `rkt def`. See
+    /// [`crate::languages::ruby::rails`].
+    #[serde(default)]
+    pub rails: bool,
+
+    /// Conditional compilation symbols defined for the build (default:
+    /// none), as passed to `rkt index --define`: `["DEBUG", "feature=serde"]`.
+    /// `rkt def` uses them to tell whether a symbol inside `#if` or
+    /// `#[cfg]` is compiled. See [`crate::conditional`].
+    #[serde(default)]
+    pub defines: Vec<String>,
+
+    /// Rules overriding the visibility parsers infer (default: none), as
+    /// `[[visibility]]` tables. See [`VisibilityRule`].
+    #[serde(default)]
+    pub visibility: Vec<VisibilityRule>,
+
+    /// Whether watch mode indexes the dependencies the workspace imports
+    /// (default: false), as `rkt watch --dependencies` does: the public
+    /// symbols of `node_modules` packages, vendored gems and NuGet packages
+    /// that ship sources. See [`crate::dependencies`].
+    #[serde(default)]
+    pub index_dependencies: bool,
+}
+
+/// A `[[visibility]]` rule: symbols it matches get `visibility` whatever
+/// their modifiers or naming conventions say.
+///
+/// `language`, `name` and `path` are all optional and all must match.
+/// `name` is matched against a symbol's short name and `path` against its
+/// file relative to the workspace root, with `*` matching any run of
+/// characters. The first matching rule wins, so put exceptions first:
+///
+/// ```toml
+/// [[visibility]]
+/// language = "python"
+/// name = "__*__"
+/// visibility = "public"
+///
+/// [[visibility]]
+/// language = "python"
+/// name = "_*"
+/// visibility = "private"
+/// ```
+///
+/// Rules apply as files are indexed; `rkt index` reindexes everything
+/// when they change.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct VisibilityRule {
+    /// Language the rule is for, e.g. `python` (default: all)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Pattern for the symbol's name (default: any)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Pattern for the file, relative to the workspace root (default: any)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// `public`, `internal` or `private`
+    #[serde(with = "visibility_name")]
+    pub visibility: Visibility,
+}
+
+/// (De)serialize a [`Visibility`] by its lowercase name, as
+/// `rkt symbols --visibility` takes it.
+mod visibility_name {
+    use crate::Visibility;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        visibility: &Visibility,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(visibility)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Visibility, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
+
+/// One root of a multi-root workspace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Project {
+    /// The root's path relative to the workspace, with `/` separators
+    pub name: String,
+    /// Absolute path of the root
+    pub root: PathBuf,
 }
 
 impl Default for Config {
@@ -47,6 +169,13 @@ impl Default for Config {
             exclude_dirs: Vec::new(),
             max_recursion_depth: default_recursion_depth(),
             respect_gitignore: default_respect_gitignore(),
+            string_references: false,
+            roots: Vec::new(),
+            attach: BTreeMap::new(),
+            rails: false,
+            defines: Vec::new(),
+            visibility: Vec::new(),
+            index_dependencies: false,
         }
     }
 }
@@ -103,6 +232,86 @@ impl Config {
         }
         dirs
     }
+
+    /// The projects `roots` expands to under `workspace`, sorted by name.
+    ///
+    /// Empty when no roots are configured. Patterns that match no directory
+    /// are skipped.
+    pub fn projects(&self, workspace: &Path) -> Vec<Project> {
+        let mut projects: Vec<Project> = Vec::new();
+        for pattern in &self.roots {
+            let segments: Vec<&str> = pattern
+                .split('/')
+                .filter(|s| !s.is_empty() && *s != ".")
+                .collect();
+            for (name, root) in expand_root(workspace, String::new(), &segments) {
+                if !projects.iter().any(|p| p.name == name) {
+                    projects.push(Project { name, root });
+                }
+            }
+        }
+        projects.sort_by(|a, b| a.name.cmp(&b.name));
+        projects
+    }
+}
+
+/// Directories under `dir` matching `segments`, as (relative name, path) pairs.
+fn expand_root(dir: &Path, name: String, segments: &[&str]) -> Vec<(String, PathBuf)> {
+    let Some((segment, rest)) = segments.split_first() else {
+        return if name.is_empty() {
+            Vec::new()
+        } else {
+            vec![(name, dir.to_path_buf())]
+        };
+    };
+    let join = |child: &str| {
+        if name.is_empty() {
+            child.to_string()
+        } else {
+            format!("{}/{}", name, child)
+        }
+    };
+
+    if !segment.contains('*') {
+        let path = dir.join(segment);
+        return if path.is_dir() {
+            expand_root(&path, join(segment), rest)
+        } else {
+            Vec::new()
+        };
+    }
+
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut matches = Vec::new();
+    for entry in entries.flatten() {
+        let child = entry.file_name().to_string_lossy().into_owned();
+        if entry.path().is_dir() && !child.starts_with('.') && wildcard_match(segment, &child) {
+            matches.extend(expand_root(&entry.path(), join(&child), rest));
+        }
+    }
+    matches
+}
+
+/// Match `name` against a pattern where `*` stands for any run of characters.
+pub(crate) fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if parts.len() == 1 {
+        return pattern == name;
+    }
+    if name.len() < first.len() + last.len() || !name.starts_with(first) || !name.ends_with(last) {
+        return false;
+    }
+    let mut rest = &name[first.len()..name.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    true
 }
 
 #[cfg(test)]
@@ -185,5 +394,91 @@ respect_gitignore = false
         assert!(!config.respect_gitignore); // from config
         assert_eq!(config.max_recursion_depth, 500); // from defaults
         assert!(config.exclude_dirs.is_empty()); // from defaults
+        assert!(!config.string_references); // from defaults
+        assert!(!config.rails); // from defaults
+    }
+
+    #[test]
+    fn test_load_config_with_string_references() {
+        let temp = TempDir::new().unwrap();
+        std::fs::write(
+            temp.path().join(".rocketindex.toml"),
+            "string_references = true\n",
+        )
+        .unwrap();
+
+        let config = Config::load(temp.path());
+        assert!(config.string_references);
+    }
+
+    #[test]
+    fn test_load_config_with_visibility_rules() {
+        let temp = TempDir::new().unwrap();
+        std::fs::write(
+            temp.path().join(".rocketindex.toml"),
+            "[[visibility]]\nlanguage = \"python\"\nname = \"_*\"\nvisibility = \"private\"\n\n\
+             [[visibility]]\npath = \"src/internal/*\"\nvisibility = \"Internal\"\n",
+        )
+        .unwrap();
+
+        let config = Config::load(temp.path());
+        assert_eq!(
+            config.visibility,
+            vec![
+                VisibilityRule {
+                    language: Some("python".to_string()),
+                    name: Some("_*".to_string()),
+                    path: None,
+                    visibility: Visibility::Private,
+                },
+                VisibilityRule {
+                    language: None,
+                    name: None,
+                    path: Some("src/internal/*".to_string()),
+                    visibility: Visibility::Internal,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_projects_expand_roots() {
+        let temp = TempDir::new().unwrap();
+        for dir in [
+            "services/api",
+            "services/billing",
+            "services/.cache",
+            "libs/common",
+        ] {
+            std::fs::create_dir_all(temp.path().join(dir)).unwrap();
+        }
+        std::fs::write(temp.path().join("services/README.md"), "").unwrap();
+        std::fs::write(
+            temp.path().join(".rocketindex.toml"),
+            "roots = [\"services/*/\", \"libs/common\", \"libs/missing\"]\n",
+        )
+        .unwrap();
+
+        let config = Config::load(temp.path());
+        let projects = config.projects(temp.path());
+        let names: Vec<&str> = projects.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["libs/common", "services/api", "services/billing"]
+        );
+        assert_eq!(projects[1].root, temp.path().join("services").join("api"));
+
+        assert!(Config::default().projects(temp.path()).is_empty());
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("*", "api"));
+        assert!(wildcard_match("svc-*", "svc-api"));
+        assert!(wildcard_match("*-svc", "api-svc"));
+        assert!(wildcard_match("a*b*c", "axbyc"));
+        assert!(!wildcard_match("svc-*", "api"));
+        assert!(!wildcard_match("a*a", "a"));
+        assert!(!wildcard_match("api", "apis"));
     }
 }
@@ -0,0 +1,453 @@
+//! Self-contained context for one symbol, for `rkt context`.
+//!
+//! A [`ContextBundle`] holds what an agent needs to work on a symbol without
+//! opening files: its source (from the definition's start/end lines), its
+//! doc comment, the signatures of the symbols it depends on, and its call
+//! sites with a few lines around each.
+//!
+//! Bundles are capped to a byte budget. The source is always kept (cut at a
+//! line boundary if it alone is over budget), then dependencies and call
+//! sites are added in order until the next one doesn't fit, so the same
+//! index and budget always give the same bundle.
+
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::spider::spider;
+use crate::{CodeIndex, Symbol, SymbolKind};
+
+/// Bytes per token when a budget is given in tokens.
+pub const BYTES_PER_TOKEN: usize = 4;
+
+/// How much to put in a bundle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContextOptions {
+    /// Lines shown above and below each call site
+    pub call_site_lines: usize,
+    /// Cap on the bundle's text: source, docs, signatures and snippets
+    pub max_bytes: usize,
+}
+
+impl Default for ContextOptions {
+    fn default() -> Self {
+        Self {
+            call_site_lines: 3,
+            max_bytes: 4000 * BYTES_PER_TOKEN,
+        }
+    }
+}
+
+/// A symbol the bundled one depends on, by signature.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Dependency {
+    pub qualified: String,
+    pub kind: SymbolKind,
+    pub signature: Option<String>,
+    pub file: PathBuf,
+    pub line: u32,
+}
+
+/// A reference to the bundled symbol, with the lines around it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CallSite {
+    /// Innermost symbol the call is made from, if any
+    pub caller: Option<String>,
+    pub file: PathBuf,
+    pub line: u32,
+    /// Line number of the snippet's first line
+    pub start_line: u32,
+    pub snippet: String,
+}
+
+/// Everything about one symbol that fits in the budget.
+#[derive(Debug, Clone, Serialize)]
+pub struct ContextBundle {
+    pub symbol: String,
+    pub kind: SymbolKind,
+    pub language: String,
+    /// Workspace-relative path of the definition
+    pub file: PathBuf,
+    pub start_line: u32,
+    pub end_line: u32,
+    pub signature: Option<String>,
+    pub doc: Option<String>,
+    /// Lines `start_line..=end_line` of the definition
+    pub source: String,
+    pub dependencies: Vec<Dependency>,
+    pub call_sites: Vec<CallSite>,
+    /// Whether anything was cut to fit the budget
+    pub truncated: bool,
+    pub omitted_dependencies: usize,
+    pub omitted_call_sites: usize,
+}
+
+/// Build the context bundle for `symbol`, reading sources through `read`
+/// (given absolute paths). Files that can't be read contribute no source
+/// or snippets.
+pub fn build_context(
+    index: &CodeIndex,
+    symbol: &Symbol,
+    options: &ContextOptions,
+    mut read: impl FnMut(&Path) -> io::Result<String>,
+) -> ContextBundle {
+    let mut lines_of = |file: &Path| -> Vec<String> {
+        read(&index.to_absolute(file))
+            .map(|text| text.lines().map(str::to_string).collect())
+            .unwrap_or_default()
+    };
+
+    let start_line = symbol.location.line;
+    let end_line = symbol.location.end_line.max(start_line);
+    let definition = lines_of(&symbol.location.file);
+
+    let mut budget = options.max_bytes;
+    let mut truncated = false;
+    let mut source = String::new();
+    for line in slice(&definition, start_line, end_line) {
+        if line.len() + 1 > budget {
+            truncated = true;
+            break;
+        }
+        budget -= line.len() + 1;
+        source.push_str(line);
+        source.push('\n');
+    }
+    let doc = symbol
+        .doc
+        .clone()
+        .filter(|doc| take(&mut budget, doc.len()));
+    truncated |= symbol.doc.is_some() && doc.is_none();
+
+    let mut dependencies = Vec::new();
+    let mut omitted_dependencies = 0;
+    let callees = spider(index, &symbol.qualified, 1).nodes;
+    for node in callees.into_iter().filter(|node| node.depth == 1) {
+        let dependency = Dependency {
+            file: index.to_relative(&node.symbol.location.file),
+            line: node.symbol.location.line,
+            kind: node.symbol.kind,
+            signature: node.symbol.signature,
+            qualified: node.symbol.qualified,
+        };
+        let size =
+            dependency.qualified.len() + dependency.signature.as_ref().map_or(0, String::len);
+        if omitted_dependencies == 0 && take(&mut budget, size) {
+            dependencies.push(dependency);
+        } else {
+            omitted_dependencies += 1;
+        }
+    }
+
+    let sites: BTreeSet<(PathBuf, u32)> = index
+        .find_references(&symbol.qualified)
+        .into_iter()
+        .map(|reference| (reference.location.file.clone(), reference.location.line))
+        .collect();
+    let mut call_sites = Vec::new();
+    let mut omitted_call_sites = 0;
+    let mut current: Option<(PathBuf, Vec<String>)> = None;
+    for (file, line) in sites {
+        if omitted_call_sites > 0 {
+            omitted_call_sites += 1;
+            continue;
+        }
+        if current.as_ref().is_none_or(|(open, _)| *open != file) {
+            current = Some((file.clone(), lines_of(&file)));
+        }
+        let lines = current
+            .as_ref()
+            .map(|(_, lines)| lines.as_slice())
+            .unwrap_or_default();
+        let context = options.call_site_lines as u32;
+        let first = line.saturating_sub(context).max(1);
+        let snippet: String = slice(lines, first, line + context)
+            .map(|text| format!("{}\n", text))
+            .collect();
+        if !take(&mut budget, snippet.len()) {
+            omitted_call_sites += 1;
+            continue;
+        }
+        let caller = enclosing_caller(index, &file, line).map(|caller| caller.qualified.clone());
+        call_sites.push(CallSite {
+            caller,
+            file: index.to_relative(&file),
+            line,
+            start_line: first,
+            snippet,
+        });
+    }
+
+    ContextBundle {
+        symbol: symbol.qualified.clone(),
+        kind: symbol.kind,
+        language: symbol.language.clone(),
+        file: index.to_relative(&symbol.location.file),
+        start_line,
+        end_line,
+        signature: symbol.signature.clone(),
+        doc,
+        source,
+        dependencies,
+        call_sites,
+        truncated: truncated || omitted_dependencies > 0 || omitted_call_sites > 0,
+        omitted_dependencies,
+        omitted_call_sites,
+    }
+}
+
+impl ContextBundle {
+    /// The bundle as a markdown document.
+    pub fn to_markdown(&self) -> String {
+        let fence = fence_language(&self.language);
+        let mut out = String::new();
+        let _ = writeln!(out, "# {} ({})\n", self.symbol, self.kind);
+        let _ = writeln!(
+            out,
+            "`{}:{}-{}`\n",
+            self.file.display(),
+            self.start_line,
+            self.end_line
+        );
+        if let Some(doc) = &self.doc {
+            let _ = writeln!(out, "{}\n", doc.trim());
+        }
+        let _ = writeln!(out, "```{}\n{}```\n", fence, self.source);
+
+        if !self.dependencies.is_empty() || self.omitted_dependencies > 0 {
+            let _ = writeln!(out, "## Dependencies\n");
+            for dep in &self.dependencies {
+                match &dep.signature {
+                    Some(signature) => {
+                        let _ = writeln!(out, "- `{}`: `{}`", dep.qualified, signature);
+                    }
+                    None => {
+                        let _ = writeln!(out, "- `{}` ({})", dep.qualified, dep.kind);
+                    }
+                }
+            }
+            if self.omitted_dependencies > 0 {
+                let _ = writeln!(out, "- ... {} more", self.omitted_dependencies);
+            }
+            out.push('\n');
+        }
+
+        if !self.call_sites.is_empty() || self.omitted_call_sites > 0 {
+            let _ = writeln!(out, "## Call sites\n");
+            for site in &self.call_sites {
+                let caller = site
+                    .caller
+                    .as_ref()
+                    .map(|caller| format!(" in `{}`", caller))
+                    .unwrap_or_default();
+                let _ = writeln!(
+                    out,
+                    "`{}:{}`{}\n\n```{}\n{}```\n",
+                    site.file.display(),
+                    site.line,
+                    caller,
+                    fence,
+                    site.snippet
+                );
+            }
+            if self.omitted_call_sites > 0 {
+                let _ = writeln!(
+                    out,
+                    "... {} more call sites over budget\n",
+                    self.omitted_call_sites
+                );
+            }
+        }
+        out
+    }
+}
+
+/// The innermost definition spanning `line` that code runs in. Local
+/// values (`let user = ...` inside a function) don't count as callers, and
+/// definitions recorded without a span run up to the next one.
+fn enclosing_caller<'a>(index: &'a CodeIndex, file: &Path, line: u32) -> Option<&'a Symbol> {
+    let mut symbols: Vec<&Symbol> = index
+        .symbols_in_file(file)
+        .into_iter()
+        .filter(|s| s.kind != SymbolKind::Value)
+        .collect();
+    symbols.sort_by_key(|s| (s.location.line, s.location.column));
+    let ends: Vec<u32> = symbols
+        .iter()
+        .enumerate()
+        .map(|(i, s)| {
+            if s.location.end_line > s.location.line {
+                return s.location.end_line;
+            }
+            symbols[i + 1..]
+                .iter()
+                .find(|next| next.location.line > s.location.line)
+                .map_or(u32::MAX, |next| next.location.line - 1)
+        })
+        .collect();
+    symbols
+        .into_iter()
+        .zip(ends)
+        .filter(|(s, end)| s.location.line <= line && line <= *end)
+        .min_by_key(|(s, end)| (end - s.location.line, std::cmp::Reverse(s.location.column)))
+        .map(|(s, _)| s)
+}
+
+/// Lines `first..=last` (1-indexed) of `lines`, clamped to what's there.
+fn slice(lines: &[String], first: u32, last: u32) -> impl Iterator<Item = &str> {
+    let start = (first.max(1) - 1) as usize;
+    let end = (last as usize).min(lines.len());
+    lines
+        .get(start..end.max(start))
+        .unwrap_or_default()
+        .iter()
+        .map(String::as_str)
+}
+
+/// Spend `size` bytes of the budget, if there are that many left.
+fn take(budget: &mut usize, size: usize) -> bool {
+    let fits = size <= *budget;
+    if fits {
+        *budget -= size;
+    }
+    fits
+}
+
+/// Info string for a fenced code block in `language`.
+fn fence_language(language: &str) -> &str {
+    match language {
+        "csharp" => "cs",
+        "javascript" => "js",
+        "typescript" => "ts",
+        "cpp" => "cpp",
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Location, Reference, ReferenceKind, Visibility};
+
+    const STORE: &str = "module Store\n\n/// Saves an order\nlet save order =\n    validate order\n    write order\n\nlet validate order = order\n";
+    const APP: &str =
+        "module App\n\nlet main () =\n    let order = load ()\n    Store.save order\n    0\n";
+
+    fn index() -> CodeIndex {
+        let mut index = CodeIndex::with_root(PathBuf::from("/ws"));
+        let mut save = Symbol::new(
+            "save".to_string(),
+            "Store.save".to_string(),
+            SymbolKind::Function,
+            Location::with_end(PathBuf::from("src/Store.fs"), 4, 5, 6, 16),
+            Visibility::Public,
+            "fsharp".to_string(),
+        );
+        save.doc = Some("Saves an order".to_string());
+        index.add_symbol(save);
+        let mut validate = Symbol::new(
+            "validate".to_string(),
+            "Store.validate".to_string(),
+            SymbolKind::Function,
+            Location::with_end(PathBuf::from("src/Store.fs"), 8, 5, 8, 26),
+            Visibility::Public,
+            "fsharp".to_string(),
+        );
+        validate.signature = Some("'a -> 'a".to_string());
+        index.add_symbol(validate);
+        index.add_symbol(Symbol::new(
+            "main".to_string(),
+            "App.main".to_string(),
+            SymbolKind::Function,
+            Location::with_end(PathBuf::from("src/App.fs"), 3, 5, 6, 5),
+            Visibility::Public,
+            "fsharp".to_string(),
+        ));
+        for (file, name, line) in [
+            ("src/Store.fs", "validate", 5),
+            ("src/App.fs", "Store.save", 5),
+        ] {
+            index.add_reference(
+                PathBuf::from(file),
+                Reference {
+                    name: name.to_string(),
+                    location: Location::new(PathBuf::from(file), line, 5),
+                    kind: ReferenceKind::Call,
+                },
+            );
+        }
+        index
+    }
+
+    fn bundle_with_budget(max_bytes: usize) -> ContextBundle {
+        let index = index();
+        let save = index.get("Store.save").unwrap().clone();
+        let options = ContextOptions {
+            call_site_lines: 1,
+            max_bytes,
+        };
+        build_context(&index, &save, &options, |path| {
+            Ok(if path.ends_with("Store.fs") {
+                STORE
+            } else {
+                APP
+            }
+            .to_string())
+        })
+    }
+
+    #[test]
+    fn test_bundle_has_source_dependencies_and_call_sites() {
+        let bundle = bundle_with_budget(ContextOptions::default().max_bytes);
+        assert_eq!(bundle.file, PathBuf::from("src/Store.fs"));
+        assert_eq!(
+            bundle.source,
+            "let save order =\n    validate order\n    write order\n"
+        );
+        assert_eq!(bundle.doc.as_deref(), Some("Saves an order"));
+        assert_eq!(bundle.dependencies.len(), 1);
+        assert_eq!(bundle.dependencies[0].qualified, "Store.validate");
+        assert_eq!(
+            bundle.dependencies[0].signature.as_deref(),
+            Some("'a -> 'a")
+        );
+
+        assert_eq!(bundle.call_sites.len(), 1);
+        let site = &bundle.call_sites[0];
+        assert_eq!(site.caller.as_deref(), Some("App.main"));
+        assert_eq!(
+            (site.file.as_path(), site.line),
+            (Path::new("src/App.fs"), 5)
+        );
+        assert_eq!(site.start_line, 4);
+        assert_eq!(
+            site.snippet,
+            "    let order = load ()\n    Store.save order\n    0\n"
+        );
+        assert!(!bundle.truncated);
+
+        let markdown = bundle.to_markdown();
+        assert!(markdown.contains("# Store.save (Function)"));
+        assert!(markdown.contains("```fsharp\nlet save order ="));
+        assert!(markdown.contains("- `Store.validate`: `'a -> 'a`"));
+        assert!(markdown.contains("`src/App.fs:5` in `App.main`"));
+    }
+
+    #[test]
+    fn test_budget_keeps_source_and_drops_the_rest() {
+        let bundle = bundle_with_budget(60);
+        assert!(bundle.source.starts_with("let save order ="));
+        assert!(bundle.call_sites.is_empty());
+        assert_eq!(bundle.omitted_call_sites, 1);
+        assert!(bundle.truncated);
+
+        // Over budget on its own, the source is cut at a line
+        let bundle = bundle_with_budget(20);
+        assert_eq!(bundle.source, "let save order =\n");
+        assert!(bundle.doc.is_none());
+        assert!(bundle.truncated);
+    }
+}
@@ -0,0 +1,394 @@
+//! Module-level dependency cycles.
+//!
+//! Builds a graph whose nodes are modules and whose edges are "some code in
+//! module A refers to a symbol in module B" (from resolved references and
+//! `open`/import statements), then reports its strongly connected components.
+//! Every component with more than one module is a dependency cycle, which in
+//! a layered architecture is usually a layering violation.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::{CodeIndex, Location, Symbol, SymbolKind};
+
+/// Evidence for a dependency between two modules.
+#[derive(Debug, Clone, Serialize)]
+pub struct ModuleEdge {
+    pub from: String,
+    pub to: String,
+    /// Number of references (and opens) backing this edge
+    pub references: usize,
+    /// The first reference found, to start investigating from
+    pub example: Location,
+}
+
+/// Dependencies between modules.
+#[derive(Debug, Default)]
+pub struct ModuleGraph {
+    edges: BTreeMap<(String, String), ModuleEdge>,
+}
+
+/// A set of modules that all (transitively) depend on each other.
+#[derive(Debug, Clone, Serialize)]
+pub struct Cycle {
+    /// Modules in the cycle, sorted
+    pub modules: Vec<String>,
+    /// Edges between modules of the cycle
+    pub edges: Vec<ModuleEdge>,
+}
+
+impl ModuleGraph {
+    /// Build the module graph from an index.
+    ///
+    /// A symbol belongs to its nearest enclosing `Module` symbol; files in
+    /// languages without modules count as one module named after the file.
+    #[must_use]
+    pub fn build(index: &CodeIndex) -> Self {
+        let mut graph = Self::default();
+        let mut files: Vec<&Path> = index.files().map(PathBuf::as_path).collect();
+        files.sort();
+
+        let module_symbols: BTreeSet<&str> = files
+            .iter()
+            .flat_map(|file| index.symbols_in_file(file))
+            .filter(|s| s.kind == SymbolKind::Module)
+            .map(|s| s.qualified.as_str())
+            .collect();
+
+        for file in files {
+            let mut symbols = index.symbols_in_file(file);
+            symbols.sort_by_key(|s| (s.location.line, s.location.column));
+
+            for reference in index.references_in_file(file) {
+                let Some(target) = index
+                    .resolve(&reference.name, file)
+                    .or_else(|| index.resolve_dotted(&reference.name, file))
+                    .map(|r| r.symbol)
+                    .or_else(|| index.get(&reference.name))
+                else {
+                    continue;
+                };
+
+                // The module of the closest definition above the reference
+                let from = symbols
+                    .iter()
+                    .rev()
+                    .find(|s| s.location.line <= reference.location.line)
+                    .or(symbols.first())
+                    .map_or_else(|| file_module(file), |s| module_of(s, &module_symbols));
+                let to = module_of(target, &module_symbols);
+                graph.add_edge(from, to, &index.make_location_absolute(&reference.location));
+            }
+
+            // `open X` makes the file's module depend on X even if nothing
+            // from X is referenced
+            let Some(first) = symbols.first() else {
+                continue;
+            };
+            let from = module_of(first, &module_symbols);
+            for open in index.opens_for_file(file) {
+                if module_symbols.contains(open.as_str()) {
+                    let location = index.make_location_absolute(&first.location);
+                    graph.add_edge(from.clone(), open.clone(), &location);
+                }
+            }
+        }
+
+        graph
+    }
+
+    fn add_edge(&mut self, from: String, to: String, location: &Location) {
+        if from == to {
+            return;
+        }
+        self.edges
+            .entry((from.clone(), to.clone()))
+            .and_modify(|e| e.references += 1)
+            .or_insert_with(|| ModuleEdge {
+                from,
+                to,
+                references: 1,
+                example: location.clone(),
+            });
+    }
+
+    /// All edges, ordered by source then target module.
+    pub fn edges(&self) -> impl Iterator<Item = &ModuleEdge> {
+        self.edges.values()
+    }
+
+    /// Dependency cycles: strongly connected components with at least two modules.
+    ///
+    /// Larger cycles come first.
+    #[must_use]
+    pub fn cycles(&self) -> Vec<Cycle> {
+        let mut adjacency: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for (from, to) in self.edges.keys() {
+            adjacency.entry(from).or_default().push(to);
+            adjacency.entry(to).or_default();
+        }
+
+        let mut cycles: Vec<Cycle> = strongly_connected_components(&adjacency)
+            .into_iter()
+            .filter(|component| component.len() > 1)
+            .map(|mut modules| {
+                modules.sort_unstable();
+                let members: BTreeSet<&str> = modules.iter().copied().collect();
+                let edges = self
+                    .edges
+                    .values()
+                    .filter(|e| {
+                        members.contains(e.from.as_str()) && members.contains(e.to.as_str())
+                    })
+                    .cloned()
+                    .collect();
+                Cycle {
+                    modules: modules.into_iter().map(str::to_string).collect(),
+                    edges,
+                }
+            })
+            .collect();
+
+        cycles.sort_by(|a, b| {
+            b.modules
+                .len()
+                .cmp(&a.modules.len())
+                .then_with(|| a.modules.cmp(&b.modules))
+        });
+        cycles
+    }
+}
+
+/// Render cycles as a Graphviz DOT digraph, one cluster per cycle.
+#[must_use]
+pub fn cycles_to_dot(cycles: &[Cycle]) -> String {
+    let mut out = String::from("digraph cycles {\n    rankdir=LR;\n    node [shape=box];\n");
+    for (i, cycle) in cycles.iter().enumerate() {
+        out.push_str(&format!(
+            "    subgraph cluster_{} {{\n        label=\"cycle {} ({} modules)\";\n",
+            i + 1,
+            i + 1,
+            cycle.modules.len()
+        ));
+        for module in &cycle.modules {
+            out.push_str(&format!("        {};\n", dot_id(module)));
+        }
+        for edge in &cycle.edges {
+            out.push_str(&format!(
+                "        {} -> {} [label=\"{}\", tooltip={}];\n",
+                dot_id(&edge.from),
+                dot_id(&edge.to),
+                edge.references,
+                dot_id(&format!(
+                    "{}:{}",
+                    edge.example.file.display(),
+                    edge.example.line
+                ))
+            ));
+        }
+        out.push_str("    }\n");
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// Quote a string as a DOT identifier.
+fn dot_id(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// The module a symbol belongs to.
+fn module_of(symbol: &Symbol, modules: &BTreeSet<&str>) -> String {
+    if symbol.kind == SymbolKind::Module {
+        return symbol.qualified.clone();
+    }
+    let mut prefix = symbol.qualified.as_str();
+    while let Some(end) = prefix.rfind(['.', ':']) {
+        prefix = prefix[..end].trim_end_matches(':');
+        if modules.contains(prefix) {
+            return prefix.to_string();
+        }
+    }
+    file_module(&symbol.location.file)
+}
+
+fn file_module(file: &Path) -> String {
+    file.to_string_lossy().replace('\\', "/")
+}
+
+/// Tarjan's algorithm, iteratively so deep graphs can't overflow the stack.
+fn strongly_connected_components<'a>(graph: &BTreeMap<&'a str, Vec<&'a str>>) -> Vec<Vec<&'a str>> {
+    struct NodeState {
+        index: usize,
+        lowlink: usize,
+        on_stack: bool,
+    }
+
+    let mut state: HashMap<&str, NodeState> = HashMap::new();
+    let mut stack: Vec<&str> = Vec::new();
+    let mut components = Vec::new();
+    let mut next_index = 0;
+
+    for &root in graph.keys() {
+        if state.contains_key(root) {
+            continue;
+        }
+        // (node, position of the next successor to visit)
+        let mut work: Vec<(&str, usize)> = vec![(root, 0)];
+        while let Some((node, next)) = work.pop() {
+            if next == 0 {
+                state.insert(
+                    node,
+                    NodeState {
+                        index: next_index,
+                        lowlink: next_index,
+                        on_stack: true,
+                    },
+                );
+                next_index += 1;
+                stack.push(node);
+            }
+
+            let successors = &graph[node];
+            if let Some(&successor) = successors.get(next) {
+                work.push((node, next + 1));
+                match state.get(successor) {
+                    None => work.push((successor, 0)),
+                    Some(s) if s.on_stack => {
+                        let index = s.index;
+                        let entry = state.get_mut(node).expect("visited");
+                        entry.lowlink = entry.lowlink.min(index);
+                    }
+                    Some(_) => {}
+                }
+                continue;
+            }
+
+            // All successors done: propagate lowlink to the parent
+            let lowlink = state[node].lowlink;
+            if let Some(&(parent, _)) = work.last() {
+                let entry = state.get_mut(parent).expect("visited");
+                entry.lowlink = entry.lowlink.min(lowlink);
+            }
+
+            if lowlink == state[node].index {
+                let mut component = Vec::new();
+                while let Some(member) = stack.pop() {
+                    state.get_mut(member).expect("visited").on_stack = false;
+                    component.push(member);
+                    if member == node {
+                        break;
+                    }
+                }
+                components.push(component);
+            }
+        }
+    }
+
+    components
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Reference, ReferenceKind, Visibility};
+
+    fn add(index: &mut CodeIndex, qualified: &str, kind: SymbolKind, file: &str, line: u32) {
+        let name = qualified.rsplit('.').next().unwrap();
+        index.add_symbol(Symbol::new(
+            name.to_string(),
+            qualified.to_string(),
+            kind,
+            Location::new(PathBuf::from(file), line, 5),
+            Visibility::Public,
+            "fsharp".to_string(),
+        ));
+    }
+
+    fn reference(index: &mut CodeIndex, name: &str, file: &str, line: u32) {
+        index.add_reference(
+            PathBuf::from(file),
+            Reference {
+                name: name.to_string(),
+                location: Location::new(PathBuf::from(file), line, 5),
+                kind: ReferenceKind::Read,
+            },
+        );
+    }
+
+    #[test]
+    fn test_finds_cycles_between_modules() {
+        let mut index = CodeIndex::new();
+        // Domain -> Services -> Data -> Domain, and Api -> Domain (no cycle)
+        for (module, file) in [
+            ("App.Domain", "Domain.fs"),
+            ("App.Services", "Services.fs"),
+            ("App.Data", "Data.fs"),
+            ("App.Api", "Api.fs"),
+        ] {
+            add(&mut index, module, SymbolKind::Module, file, 1);
+            add(
+                &mut index,
+                &format!("{}.run", module),
+                SymbolKind::Function,
+                file,
+                3,
+            );
+        }
+        reference(&mut index, "App.Services.run", "Domain.fs", 4);
+        reference(&mut index, "App.Data.run", "Services.fs", 4);
+        reference(&mut index, "App.Data.run", "Services.fs", 5);
+        reference(&mut index, "App.Domain.run", "Data.fs", 4);
+        reference(&mut index, "App.Domain.run", "Api.fs", 4);
+        // Same-module references are not edges
+        reference(&mut index, "App.Api.run", "Api.fs", 5);
+
+        let graph = ModuleGraph::build(&index);
+        assert_eq!(graph.edges().count(), 4);
+
+        let cycles = graph.cycles();
+        assert_eq!(cycles.len(), 1);
+        assert_eq!(
+            cycles[0].modules,
+            vec!["App.Data", "App.Domain", "App.Services"]
+        );
+        assert_eq!(cycles[0].edges.len(), 3);
+        let services_to_data = cycles[0]
+            .edges
+            .iter()
+            .find(|e| e.from == "App.Services")
+            .unwrap();
+        assert_eq!(services_to_data.references, 2);
+
+        let dot = cycles_to_dot(&cycles);
+        assert!(dot.starts_with("digraph cycles {"));
+        assert!(dot.contains("\"App.Services\" -> \"App.Data\" [label=\"2\""));
+        assert!(!dot.contains("App.Api"));
+    }
+
+    #[test]
+    fn test_scc_handles_nested_and_separate_components() {
+        let graph: BTreeMap<&str, Vec<&str>> = BTreeMap::from([
+            ("a", vec!["b"]),
+            ("b", vec!["c", "d"]),
+            ("c", vec!["a"]),
+            ("d", vec!["e"]),
+            ("e", vec!["d"]),
+            ("f", vec![]),
+        ]);
+        let mut components: Vec<Vec<&str>> = strongly_connected_components(&graph)
+            .into_iter()
+            .map(|mut c| {
+                c.sort_unstable();
+                c
+            })
+            .collect();
+        components.sort();
+        assert_eq!(
+            components,
+            vec![vec!["a", "b", "c"], vec!["d", "e"], vec!["f"]]
+        );
+    }
+}
@@ -48,25 +48,403 @@
 //! assert_eq!(results.len(), 1);
 //! ```
 
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
-use std::sync::{Mutex, MutexGuard};
-
-use rusqlite::{params, Connection, OptionalExtension};
-
-use crate::index::Reference;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, Instant};
+
+use rusqlite::backup::{Backup, StepResult};
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension};
+
+use crate::config::{Project, VisibilityRule};
+use crate::fsproj::PackageReference;
+use crate::fuzzy::FuzzyMatch;
+use crate::index::{ModuleAlias, Reference, ReferenceKind};
+use crate::metrics::{FileMetrics, FileStats};
+use crate::test_map::is_test_symbol;
 use crate::type_cache::{MemberKind, TypeMember};
-use crate::{IndexError, Location, Result, Symbol, SymbolKind, Visibility};
+use crate::write_lock::{self, WriteLock};
+use crate::{Confidence, IndexError, Location, Result, Symbol, SymbolKind, Visibility};
 
 /// Current schema version. Increment when making breaking changes.
-pub const SCHEMA_VERSION: u32 = 4;
+pub const SCHEMA_VERSION: u32 = 18;
+
+/// Release recorded as `tool_version` in an index's metadata when it is
+/// created, so an index built by another release can be spotted.
+static TOOL_VERSION: OnceLock<&'static str> = OnceLock::new();
+
+/// Record `version` as the release that builds indexes from now on; `rkt`
+/// passes its own release. Only the first call has an effect.
+pub fn set_tool_version(version: &'static str) {
+    let _ = TOOL_VERSION.set(version);
+}
+
+/// The release set with [`set_tool_version`], or this crate's version.
+pub fn tool_version() -> &'static str {
+    TOOL_VERSION
+        .get()
+        .copied()
+        .unwrap_or(env!("CARGO_PKG_VERSION"))
+}
+
+/// Oldest schema [`SqliteIndex::open`] can migrate; older indexes are rebuilt.
+pub const MIN_MIGRATABLE_VERSION: u32 = 3;
+
+/// One step of the schema upgrade path (see [`SqliteIndex::open`]).
+struct Migration {
+    /// Schema version after this step
+    to: u32,
+    description: &'static str,
+    apply: fn(&SqliteIndex) -> Result<()>,
+}
+
+/// Upgrade steps, oldest first. Steps check before altering tables so that
+/// they're safe to rerun on databases an older build partially migrated.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        to: 4,
+        description: "file mtimes",
+        apply: SqliteIndex::migrate_to_v4,
+    },
+    Migration {
+        to: 5,
+        description: "string-literal references",
+        apply: SqliteIndex::migrate_to_v5,
+    },
+    Migration {
+        to: 6,
+        description: "reference confidence",
+        apply: SqliteIndex::migrate_to_v6,
+    },
+    Migration {
+        to: 7,
+        description: "test classification",
+        apply: SqliteIndex::migrate_to_v7,
+    },
+    Migration {
+        to: 8,
+        description: "projects of a multi-root workspace",
+        apply: SqliteIndex::migrate_to_v8,
+    },
+    Migration {
+        to: 9,
+        description: "rename aliases",
+        apply: SqliteIndex::migrate_to_v9,
+    },
+    Migration {
+        to: 10,
+        description: "reference kinds; run 'rkt index --rebuild' to classify existing references",
+        apply: SqliteIndex::migrate_to_v10,
+    },
+    Migration {
+        to: 11,
+        description: "reference spans",
+        apply: SqliteIndex::migrate_to_v11,
+    },
+    Migration {
+        to: 12,
+        description: "stable symbol IDs and symbol history",
+        apply: SqliteIndex::migrate_to_v12,
+    },
+    Migration {
+        to: 13,
+        description: "trigram index for fuzzy search",
+        apply: SqliteIndex::migrate_to_v13,
+    },
+    Migration {
+        to: 14,
+        description: "per-symbol reference counts",
+        apply: SqliteIndex::migrate_to_v14,
+    },
+    Migration {
+        to: 15,
+        description: "module aliases",
+        apply: SqliteIndex::migrate_to_v15,
+    },
+    Migration {
+        to: 16,
+        description: "file metrics; run 'rkt index --rebuild' to measure existing files",
+        apply: SqliteIndex::migrate_to_v16,
+    },
+    Migration {
+        to: 17,
+        description: "symbol GUIDs",
+        apply: SqliteIndex::migrate_to_v17,
+    },
+    Migration {
+        to: 18,
+        description: "notes, watchlists and layer rules",
+        apply: SqliteIndex::migrate_to_v18,
+    },
+];
 
 /// Standard columns selected when querying symbols.
 /// Must match the order expected by `row_to_symbol`.
 const SYMBOL_COLUMNS: &str = "name, qualified, kind, file, line, column, end_line, end_column, visibility, language, parent, mixins, attributes, implements, doc, signature";
 
+/// Order of symbols that tie on everything a query ranks by: by file, then
+/// position, then name. Row ids depend on the order files were indexed in,
+/// which varies from run to run, so they never decide order.
+const SYMBOL_ORDER: &str = "file, line, column, qualified";
+
 /// Default database filename within .rocketindex/
 pub const DEFAULT_DB_NAME: &str = "index.db";
 
+/// Metadata key of the recorded `[[visibility]]` rules
+const VISIBILITY_RULES: &str = "visibility_rules";
+
+/// `index.db` -> `index.db-wal` and friends.
+fn sidecar(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// An "old name → new name" mapping left by a rename, so queries for the old
+/// name still find the symbol during a long migration.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Alias {
+    /// Qualified name the symbol used to have
+    pub old: String,
+    /// Qualified name it has now
+    pub new: String,
+}
+
+/// A note on a symbol, attached by the symbol's [`symbol_guid`].
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Note {
+    pub id: i64,
+    /// GUID of the symbol the note is on
+    pub guid: String,
+    /// Qualified name of the symbol when the note was written
+    pub qualified: String,
+    pub text: String,
+    /// Unix time the note was added
+    pub created_at: i64,
+}
+
+/// A symbol on a named watchlist, by its [`symbol_guid`].
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct WatchedSymbol {
+    /// Name of the watchlist
+    pub list: String,
+    /// GUID of the watched symbol
+    pub guid: String,
+    /// Qualified name of the symbol when it was added
+    pub qualified: String,
+}
+
+/// A layering rule: files matching `from` must not depend on files matching
+/// `to`. Both are paths relative to the workspace root, where `*` stands for
+/// any run of characters (see [`crate::layers`]).
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize)]
+pub struct LayerRule {
+    pub from: String,
+    pub to: String,
+}
+
+/// A symbol that disappeared, and where a near-identical one (same kind and
+/// name, and same signature when both have one) appeared afterwards.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SymbolMove {
+    /// [`symbol_id`] of the symbol that disappeared
+    pub symbol_id: i64,
+    pub kind: SymbolKind,
+    pub name: String,
+    /// Qualified name it had
+    pub from: String,
+    pub from_file: PathBuf,
+    pub from_line: u32,
+    /// Qualified name of the symbol that replaced it, if one has appeared
+    pub to: Option<String>,
+    pub to_file: Option<PathBuf>,
+    pub to_line: Option<u32>,
+    /// Unix time of the disappearance, or of the move once it's known
+    pub recorded_at: i64,
+}
+
+/// Stable ID of a symbol: a hash of its qualified name and kind.
+///
+/// Unlike row IDs, it survives reindexing, so it can key data kept about a
+/// symbol across runs. FNV-1a, so it doesn't change between builds.
+#[must_use]
+pub fn symbol_id(qualified: &str, kind: SymbolKind) -> i64 {
+    let hash = qualified
+        .bytes()
+        .chain(std::iter::once(0))
+        .chain(symbol_kind_to_str(kind).bytes())
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+    hash as i64
+}
+
+/// Stable GUID of a symbol for systems outside the index (dashboards,
+/// notes, watchlists): a hash of its language, qualified name and signature,
+/// formatted as a UUID.
+///
+/// It ignores the file and line, so it survives moving the symbol to
+/// another file and edits around it, as long as its qualified name stays
+/// the same. (In languages that name symbols after their file, as Python
+/// does with modules, a move renames the symbol and so changes its GUID.)
+/// Whitespace in the signature doesn't count, so reformatting keeps it.
+#[must_use]
+pub fn symbol_guid(language: &str, qualified: &str, signature: Option<&str>) -> String {
+    use sha2::{Digest, Sha256};
+
+    let signature = signature
+        .map(|signature| signature.split_whitespace().collect::<Vec<_>>().join(" "))
+        .unwrap_or_default();
+    let digest = Sha256::new()
+        .chain_update(language)
+        .chain_update([0])
+        .chain_update(qualified)
+        .chain_update([0])
+        .chain_update(signature)
+        .finalize();
+    let hex: String = digest[..16].iter().map(|b| format!("{:02x}", b)).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+/// Whether `text` has the shape of a [`symbol_guid`].
+#[must_use]
+pub fn is_symbol_guid(text: &str) -> bool {
+    text.len() == 36
+        && text.char_indices().all(|(i, c)| match i {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit(),
+        })
+}
+
+/// Share of the database file that may be free pages before watch mode runs
+/// [`SqliteIndex::maintain`] on its own.
+pub const MAINTENANCE_FREE_RATIO: f64 = 0.25;
+
+/// What [`SqliteIndex::maintain`] did.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct MaintenanceReport {
+    /// Database size in bytes before maintenance
+    pub size_before: u64,
+    /// Database size in bytes afterwards
+    pub size_after: u64,
+    /// Free pages before maintenance (vacuuming reclaims them)
+    pub free_pages_before: u64,
+    /// References removed because their file is no longer indexed
+    pub orphaned_references: usize,
+    /// Open statements removed because their file is no longer indexed
+    pub orphaned_opens: usize,
+}
+
+/// Files and symbols indexed for one language, from
+/// [`SqliteIndex::language_counts`].
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct LanguageCount {
+    pub language: String,
+    pub files: usize,
+    pub symbols: usize,
+}
+
+/// A file's `open` (import, `using`, `use`) of a module, from
+/// [`SqliteIndex::files_opening`] and [`SqliteIndex::open_usage`].
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct OpenSite {
+    pub file: PathBuf,
+    pub module: String,
+    /// Position among the file's opens, 1 for the first (not a line number)
+    pub position: u32,
+}
+
+/// Whether an open is needed, from [`SqliteIndex::open_usage`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OpenStatus {
+    /// Some reference in the file names a symbol of the module
+    Used,
+    /// The module is indexed, but nothing in the file uses it
+    Unused,
+    /// Nothing is indexed under the module (a package, the standard
+    /// library), so its use can't be checked
+    External,
+}
+
+/// An open of a file with how much the file uses it.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct OpenUsage {
+    #[serde(flatten)]
+    pub site: OpenSite,
+    pub status: OpenStatus,
+    /// References in the file that resolve to the module's symbols
+    pub uses: usize,
+}
+
+/// Separators between a module and its members across languages.
+const MODULE_SEPARATORS: [&str; 3] = [".", "::", "\\"];
+
+/// The source text of a symbol's definition, from [`SqliteIndex::get_source`].
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct SourceSnippet {
+    pub file: PathBuf,
+    /// First line of `text` (1-indexed), including context
+    pub start_line: u32,
+    /// Last line of `text`, including context
+    pub end_line: u32,
+    /// First line of the definition itself
+    pub symbol_line: u32,
+    /// Last line of the definition itself
+    pub symbol_end_line: u32,
+    pub text: String,
+}
+
+/// Filters for [`SqliteIndex::search_with`]; every one that's set must match.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchFilter {
+    pub language: Option<String>,
+    pub kind: Option<SymbolKind>,
+    pub visibility: Option<Visibility>,
+    /// Only symbols in this file or under this directory, written the way
+    /// the index stores paths (absolute, unless it's packed). With a `*`
+    /// it's a glob, where `*` (or `**`) matches any run of characters,
+    /// slashes included, and a match also takes in everything under it
+    pub path: Option<PathBuf>,
+    /// Only symbols with this attribute, annotation or decorator (see
+    /// [`Symbol::has_attribute`])
+    pub attribute: Option<String>,
+}
+
+impl SearchFilter {
+    /// A filter on language alone.
+    #[must_use]
+    pub fn language(language: Option<&str>) -> Self {
+        Self {
+            language: language.map(str::to_string),
+            ..Self::default()
+        }
+    }
+
+    /// Whether `symbol` passes every filter.
+    #[must_use]
+    pub fn matches(&self, symbol: &Symbol) -> bool {
+        self.language.as_ref().is_none_or(|l| *l == symbol.language)
+            && self.kind.is_none_or(|k| k == symbol.kind)
+            && self.visibility.is_none_or(|v| v == symbol.visibility)
+            && self
+                .path
+                .as_ref()
+                .is_none_or(|p| path_matches(p, &symbol.location.file))
+            && self
+                .attribute
+                .as_ref()
+                .is_none_or(|a| symbol.has_attribute(a))
+    }
+}
+
 /// SQLite-based index for symbol storage and querying.
 ///
 /// `SqliteIndex` provides persistent storage for extracted symbols with
@@ -144,7 +522,109 @@ impl SqliteIndex {
         Ok(index)
     }
 
-    /// Open an existing database. Fails if it doesn't exist or has incompatible schema.
+    /// Take the write lock for this database (see [`crate::write_lock`]),
+    /// waiting up to `timeout` for another writer to finish.
+    ///
+    /// `None` for in-memory databases, which nobody else can write.
+    pub fn lock_for_writing(&self, timeout: Duration) -> Result<Option<WriteLock>> {
+        let path = match self.conn().path() {
+            Some(path) if !path.is_empty() => PathBuf::from(path),
+            _ => return Ok(None),
+        };
+        write_lock::acquire(&path, timeout).map(Some)
+    }
+
+    /// Where a full rebuild of the database at `path` is built before it is
+    /// copied into the live one: `index.db` is rebuilt as `index.db.tmp`.
+    pub fn staging_path(path: &Path) -> PathBuf {
+        let mut staged = path.as_os_str().to_owned();
+        staged.push(".tmp");
+        PathBuf::from(staged)
+    }
+
+    /// Create an empty database to rebuild `path` into, leaving `path` itself
+    /// untouched so it keeps serving queries. Whatever an interrupted rebuild
+    /// left behind is discarded first.
+    ///
+    /// Finish with [`SqliteIndex::replace`].
+    pub fn create_staged(path: &Path) -> Result<Self> {
+        let staged = Self::staging_path(path);
+        for leftover in [
+            staged.clone(),
+            sidecar(&staged, "-wal"),
+            sidecar(&staged, "-shm"),
+        ] {
+            if leftover.exists() {
+                std::fs::remove_file(&leftover)?;
+            }
+        }
+        Self::create(&staged)
+    }
+
+    /// Copy a database built by [`SqliteIndex::create_staged`] into the one
+    /// at `dest`, then delete the staged file.
+    ///
+    /// The copy is a single write transaction on `dest` made with SQLite's
+    /// backup API, so readers see either the old index or the complete new
+    /// one, and connections opened before the copy read the new one from
+    /// their next query on. Hold the write lock (see
+    /// [`SqliteIndex::lock_for_writing`]) so no other writer races the copy.
+    pub fn replace(self, dest: &Path) -> Result<()> {
+        let staged = Self::staging_path(dest);
+
+        if dest.exists() {
+            let mut live = Connection::open(dest)?;
+            {
+                let source = self.conn();
+                let backup = Backup::new(&source, &mut live)?;
+                let deadline = Instant::now() + write_lock::DEFAULT_LOCK_TIMEOUT;
+                loop {
+                    match backup.step(-1)? {
+                        StepResult::Done => break,
+                        StepResult::More => {}
+                        _ if Instant::now() >= deadline => {
+                            return Err(IndexError::IndexBusy(dest.to_path_buf()));
+                        }
+                        _ => std::thread::sleep(Duration::from_millis(50)),
+                    }
+                }
+            }
+            // The copy went through the live database's WAL; fold it into
+            // the main file now rather than on some later connection's close
+            let _ = live.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);");
+            drop(self);
+        } else {
+            // Nothing can be reading a missing file, so move the staged one
+            // in whole. Closing its last connection checkpoints its WAL, and
+            // a stray `-wal` left by a deleted index must not be replayed
+            // into the new file.
+            drop(self);
+            for leftover in [sidecar(dest, "-wal"), sidecar(dest, "-shm")] {
+                if leftover.exists() {
+                    std::fs::remove_file(&leftover)?;
+                }
+            }
+            std::fs::rename(&staged, dest)?;
+        }
+
+        for leftover in [
+            staged.clone(),
+            sidecar(&staged, "-wal"),
+            sidecar(&staged, "-shm"),
+        ] {
+            if leftover.exists() {
+                std::fs::remove_file(&leftover)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Open an existing database, migrating an older schema in place.
+    ///
+    /// Fails with [`IndexError::IncompatibleSchema`] when the schema is older
+    /// than [`MIN_MIGRATABLE_VERSION`] or newer than [`SCHEMA_VERSION`], and
+    /// with [`IndexError::MigrationFailed`] when an upgrade step fails. Both
+    /// mean the index has to be rebuilt (see [`IndexError::needs_rebuild`]).
     pub fn open(path: &Path) -> Result<Self> {
         if !path.exists() {
             return Err(IndexError::IndexNotFound);
@@ -167,38 +647,206 @@ impl SqliteIndex {
 
         // Check and migrate schema if needed
         let version = index.get_schema_version()?;
+        if !(MIN_MIGRATABLE_VERSION..=SCHEMA_VERSION).contains(&version) {
+            return Err(IndexError::IncompatibleSchema {
+                found: version,
+                supported: SCHEMA_VERSION,
+            });
+        }
         if version < SCHEMA_VERSION {
             index.migrate_schema(version)?;
-        } else if version > SCHEMA_VERSION {
-            return Err(IndexError::IoError(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                format!(
-                    "Schema version {} is newer than supported version {}",
-                    version, SCHEMA_VERSION
-                ),
-            )));
         }
 
         Ok(index)
     }
 
-    /// Migrate database schema from an older version.
+    /// Migrate the database schema in place from `from_version`.
+    ///
+    /// Runs each step of [`MIGRATIONS`] newer than `from_version` in its own
+    /// transaction, bumping `schema_version` as it commits, so an
+    /// interrupted upgrade resumes from the last completed step.
     fn migrate_schema(&self, from_version: u32) -> Result<()> {
-        // Migration v3 -> v4: Add file_mtimes table
-        if from_version < 4 {
+        for migration in MIGRATIONS.iter().filter(|m| m.to > from_version) {
+            self.conn().execute_batch("BEGIN")?;
+            let applied = (migration.apply)(self)
+                .and_then(|()| self.set_metadata("schema_version", &migration.to.to_string()));
+            match applied {
+                Ok(()) => self.conn().execute_batch("COMMIT")?,
+                Err(e) => {
+                    let _ = self.conn().execute_batch("ROLLBACK");
+                    return Err(IndexError::MigrationFailed {
+                        from: from_version,
+                        to: migration.to,
+                        message: e.to_string(),
+                    });
+                }
+            }
+            tracing::info!(
+                "Migrated database schema from v{} to v{} ({})",
+                from_version,
+                migration.to,
+                migration.description
+            );
+        }
+        Ok(())
+    }
+
+    fn migrate_to_v4(&self) -> Result<()> {
+        self.conn().execute_batch(
+            "CREATE TABLE IF NOT EXISTS file_mtimes (
+                path TEXT PRIMARY KEY,
+                mtime INTEGER NOT NULL
+            );",
+        )?;
+        Ok(())
+    }
+
+    fn migrate_to_v5(&self) -> Result<()> {
+        if !self.has_column("refs", "source")? {
+            self.conn()
+                .execute_batch("ALTER TABLE refs ADD COLUMN source TEXT DEFAULT 'syntactic';")?;
+        }
+        Ok(())
+    }
+
+    fn migrate_to_v6(&self) -> Result<()> {
+        if !self.has_column("refs", "confidence")? {
+            self.conn().execute_batch(
+                "ALTER TABLE refs ADD COLUMN confidence TEXT DEFAULT 'heuristic';",
+            )?;
+        }
+        self.conn().execute_batch(
+            "UPDATE refs SET confidence = 'string-match' WHERE source = 'string';",
+        )?;
+        self.promote_exact_references()?;
+        Ok(())
+    }
+
+    fn migrate_to_v7(&self) -> Result<()> {
+        if !self.has_column("symbols", "is_test")? {
+            self.conn().execute_batch(
+                "ALTER TABLE symbols ADD COLUMN is_test INTEGER NOT NULL DEFAULT 0;",
+            )?;
+        }
+        self.conn().execute_batch(
+            "CREATE INDEX IF NOT EXISTS idx_symbols_is_test ON symbols(is_test) WHERE is_test = 1;",
+        )?;
+        self.classify_tests()
+    }
+
+    fn migrate_to_v8(&self) -> Result<()> {
+        for table in ["symbols", "refs"] {
+            if !self.has_column(table, "project")? {
+                self.conn()
+                    .execute_batch(&format!("ALTER TABLE {table} ADD COLUMN project TEXT;"))?;
+            }
+        }
+        // Tagging a row with its project mustn't touch the FTS index
+        self.conn().execute_batch(
+            "DROP TRIGGER IF EXISTS symbols_au;
+             CREATE TRIGGER symbols_au AFTER UPDATE OF name, qualified ON symbols BEGIN
+                 INSERT INTO symbols_fts(symbols_fts, rowid, name, qualified) VALUES('delete', old.id, old.name, old.qualified);
+                 INSERT INTO symbols_fts(rowid, name, qualified) VALUES (new.id, new.name, new.qualified);
+             END;",
+        )?;
+        self.conn().execute_batch(PROJECTS_SQL)?;
+        Ok(())
+    }
+
+    fn migrate_to_v9(&self) -> Result<()> {
+        self.conn().execute_batch(ALIASES_SQL)?;
+        Ok(())
+    }
+
+    fn migrate_to_v10(&self) -> Result<()> {
+        if !self.has_column("refs", "kind")? {
+            self.conn()
+                .execute_batch("ALTER TABLE refs ADD COLUMN kind TEXT;")?;
+        }
+        Ok(())
+    }
+
+    fn migrate_to_v11(&self) -> Result<()> {
+        for column in ["end_line", "end_column"] {
+            if !self.has_column("refs", column)? {
+                self.conn()
+                    .execute_batch(&format!("ALTER TABLE refs ADD COLUMN {column} INTEGER;"))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn migrate_to_v12(&self) -> Result<()> {
+        if !self.has_column("symbols", "symbol_id")? {
+            self.conn()
+                .execute_batch("ALTER TABLE symbols ADD COLUMN symbol_id INTEGER;")?;
+        }
+        self.assign_symbol_ids()?;
+        self.conn().execute_batch(
+            "CREATE INDEX IF NOT EXISTS idx_symbols_symbol_id ON symbols(symbol_id);",
+        )?;
+        self.conn().execute_batch(HISTORY_SQL)?;
+        Ok(())
+    }
+
+    fn migrate_to_v13(&self) -> Result<()> {
+        self.conn().execute_batch(TRIGRAM_SQL)?;
+        self.conn()
+            .execute_batch("INSERT INTO symbols_trigram(symbols_trigram) VALUES ('rebuild');")?;
+        Ok(())
+    }
+
+    fn migrate_to_v14(&self) -> Result<()> {
+        if !self.has_column("symbols", "ref_count")? {
             self.conn().execute_batch(
-                "CREATE TABLE IF NOT EXISTS file_mtimes (
-                    path TEXT PRIMARY KEY,
-                    mtime INTEGER NOT NULL
-                );",
+                "ALTER TABLE symbols ADD COLUMN ref_count INTEGER NOT NULL DEFAULT 0;
+                 ALTER TABLE symbols ADD COLUMN ref_files INTEGER NOT NULL DEFAULT 0;",
             )?;
-            self.set_metadata("schema_version", "4")?;
-            tracing::info!("Migrated database schema from v{} to v4", from_version);
         }
+        self.conn().execute_batch(
+            "CREATE INDEX IF NOT EXISTS idx_symbols_ref_count ON symbols(ref_count);",
+        )?;
+        refresh_ref_counts(&self.conn(), None)
+    }
+
+    fn migrate_to_v15(&self) -> Result<()> {
+        if !self.has_column("opens", "alias")? {
+            self.conn()
+                .execute_batch("ALTER TABLE opens ADD COLUMN alias TEXT;")?;
+        }
+        Ok(())
+    }
+
+    fn migrate_to_v16(&self) -> Result<()> {
+        self.conn().execute_batch(METRICS_SQL)?;
+        Ok(())
+    }
+
+    fn migrate_to_v17(&self) -> Result<()> {
+        if !self.has_column("symbols", "guid")? {
+            self.conn()
+                .execute_batch("ALTER TABLE symbols ADD COLUMN guid TEXT;")?;
+        }
+        self.assign_symbol_guids()?;
+        self.conn()
+            .execute_batch("CREATE INDEX IF NOT EXISTS idx_symbols_guid ON symbols(guid);")?;
+        Ok(())
+    }
 
+    fn migrate_to_v18(&self) -> Result<()> {
+        self.conn().execute_batch(ANNOTATIONS_SQL)?;
         Ok(())
     }
 
+    fn has_column(&self, table: &str, column: &str) -> Result<bool> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!("SELECT name FROM pragma_table_info('{table}')"))?;
+        let names = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(names.iter().any(|name| name == column))
+    }
+
     /// Open an existing database or create a new one.
     pub fn open_or_create(path: &Path) -> Result<Self> {
         if path.exists() {
@@ -222,6 +870,224 @@ impl SqliteIndex {
         Ok(index)
     }
 
+    /// Open a packed index (see [`SqliteIndex::pack`]) for reading only.
+    ///
+    /// The file is opened immutable: no locks, no WAL and no change
+    /// detection, so it can live on read-only or shared storage. Packs can't be
+    /// migrated in place, so a schema mismatch is an error.
+    pub fn open_read_only(path: &Path) -> Result<Self> {
+        let index = Self::open_query_only(path, "?immutable=1")?;
+
+        let version = index.get_schema_version()?;
+        if version != SCHEMA_VERSION {
+            return Err(IndexError::IoError(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "Packed index has schema version {}, expected {}; re-run `rkt pack-index`",
+                    version, SCHEMA_VERSION
+                ),
+            )));
+        }
+
+        Ok(index)
+    }
+
+    /// Open another workspace's index for reading only, while its own `rkt`
+    /// may still be writing to it.
+    ///
+    /// Nothing is migrated, so the schema must match this build's.
+    pub fn open_shared(path: &Path) -> Result<Self> {
+        let index = Self::open_query_only(path, "")?;
+
+        let version = index.get_schema_version()?;
+        if version != SCHEMA_VERSION {
+            return Err(IndexError::IncompatibleSchema {
+                found: version,
+                supported: SCHEMA_VERSION,
+            });
+        }
+
+        Ok(index)
+    }
+
+    /// Open `path` read-only with `query` appended to its SQLite URI.
+    fn open_query_only(path: &Path, query: &str) -> Result<Self> {
+        if !path.exists() {
+            return Err(IndexError::IndexNotFound);
+        }
+
+        // Escape characters with a meaning in SQLite URIs
+        let escaped = path
+            .to_string_lossy()
+            .replace('%', "%25")
+            .replace('?', "%3f")
+            .replace('#', "%23");
+        let conn = Connection::open_with_flags(
+            format!("file:{}{}", escaped, query),
+            OpenFlags::SQLITE_OPEN_READ_ONLY
+                | OpenFlags::SQLITE_OPEN_URI
+                | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        conn.execute_batch(
+            "PRAGMA query_only = ON;
+             PRAGMA cache_size = -64000;
+             PRAGMA mmap_size = 268435456;
+             PRAGMA temp_store = MEMORY;",
+        )?;
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Write a compact, read-only copy of this index to `dest`.
+    ///
+    /// The copy is vacuumed, analyzed and switched to a rollback journal so it
+    /// is a single self-contained file. Paths under `workspace_root` are
+    /// stored relative to it so the pack is portable between checkouts, and
+    /// file mtimes (only meaningful on the machine that indexed) are dropped.
+    pub fn pack(&self, dest: &Path) -> Result<()> {
+        if dest.exists() {
+            return Err(IndexError::IoError(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!("Pack destination already exists: {}", dest.display()),
+            )));
+        }
+
+        self.conn()
+            .execute("VACUUM INTO ?1", params![dest.to_string_lossy()])?;
+
+        let packed = Connection::open(dest)?;
+        packed.execute_batch("PRAGMA journal_mode = DELETE;")?;
+
+        let root: Option<String> = packed
+            .query_row(
+                "SELECT value FROM metadata WHERE key = 'workspace_root'",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if let Some(root) = root {
+            let prefix = format!(
+                "{}{}",
+                root.trim_end_matches(['/', '\\']),
+                std::path::MAIN_SEPARATOR
+            );
+            for table in ["symbols", "refs", "opens", "members", "file_metrics"] {
+                packed.execute(
+                    &format!(
+                        "UPDATE {table} SET file = substr(file, length(?1) + 1)
+                         WHERE substr(file, 1, length(?1)) = ?1"
+                    ),
+                    params![prefix],
+                )?;
+            }
+        }
+
+        packed.execute_batch(
+            "DELETE FROM file_mtimes;
+             INSERT OR REPLACE INTO metadata (key, value) VALUES ('packed', '1');
+             INSERT INTO symbols_fts(symbols_fts) VALUES ('optimize');
+             INSERT INTO symbols_trigram(symbols_trigram) VALUES ('optimize');
+             ANALYZE;
+             VACUUM;",
+        )?;
+
+        Ok(())
+    }
+
+    /// Undo [`pack`](Self::pack) for a checkout at `root`: packed relative
+    /// paths become absolute under it and the index is writable again.
+    ///
+    /// File mtimes aren't restored; until they're recorded again every file
+    /// looks changed to an incremental update.
+    pub fn unpack(&self, root: &Path) -> Result<()> {
+        let prefix = format!(
+            "{}{}",
+            root.to_string_lossy().trim_end_matches(['/', '\\']),
+            std::path::MAIN_SEPARATOR
+        );
+        self.with_transaction(|conn| {
+            for table in ["symbols", "refs", "opens", "members", "file_metrics"] {
+                conn.execute(
+                    &format!(
+                        "UPDATE {table} SET file = ?1 || file
+                         WHERE substr(file, 1, 1) NOT IN ('/', '\\') AND substr(file, 2, 1) != ':'"
+                    ),
+                    params![prefix],
+                )?;
+            }
+            conn.execute(
+                "INSERT OR REPLACE INTO metadata (key, value) VALUES ('workspace_root', ?1)",
+                params![root.to_string_lossy()],
+            )?;
+            conn.execute("DELETE FROM metadata WHERE key = 'packed'", [])?;
+            Ok(())
+        })
+    }
+
+    /// Compact the index after long-running incremental updates.
+    ///
+    /// Prunes references and opens of files that are no longer indexed,
+    /// rebuilds the full-text index, refreshes the query planner's statistics
+    /// and vacuums the file. Orphans are only pruned when the index tracks
+    /// file mtimes, so a packed index (which drops them) is left intact.
+    pub fn maintain(&self) -> Result<MaintenanceReport> {
+        let (pages, page_size, free_pages_before) = self.page_stats()?;
+
+        let (orphaned_references, orphaned_opens) = self.with_transaction(|conn| {
+            let tracked: bool =
+                conn.query_row("SELECT EXISTS (SELECT 1 FROM file_mtimes)", [], |row| {
+                    row.get(0)
+                })?;
+            if !tracked {
+                return Ok((0, 0));
+            }
+            let orphaned = "file NOT IN (SELECT path FROM file_mtimes)
+                            AND file NOT IN (SELECT file FROM symbols)";
+            let refs = conn.execute(&format!("DELETE FROM refs WHERE {orphaned}"), [])?;
+            let opens = conn.execute(&format!("DELETE FROM opens WHERE {orphaned}"), [])?;
+            conn.execute(&format!("DELETE FROM file_metrics WHERE {orphaned}"), [])?;
+            if refs > 0 {
+                refresh_ref_counts(conn, None)?;
+            }
+            Ok((refs, opens))
+        })?;
+
+        self.conn().execute_batch(
+            "INSERT INTO symbols_fts(symbols_fts) VALUES ('rebuild');
+             INSERT INTO symbols_trigram(symbols_trigram) VALUES ('rebuild');
+             ANALYZE;
+             PRAGMA wal_checkpoint(TRUNCATE);
+             VACUUM;",
+        )?;
+
+        let (pages_after, page_size_after, _) = self.page_stats()?;
+        Ok(MaintenanceReport {
+            size_before: pages * page_size,
+            size_after: pages_after * page_size_after,
+            free_pages_before,
+            orphaned_references,
+            orphaned_opens,
+        })
+    }
+
+    /// Whether enough of the file is free pages that [`maintain`](Self::maintain)
+    /// is worth running (see [`MAINTENANCE_FREE_RATIO`]).
+    pub fn needs_maintenance(&self) -> Result<bool> {
+        let (pages, _, free) = self.page_stats()?;
+        Ok(pages > 0 && free as f64 / pages as f64 > MAINTENANCE_FREE_RATIO)
+    }
+
+    /// Page count, page size and number of free pages of the database.
+    fn page_stats(&self) -> Result<(u64, u64, u64)> {
+        let conn = self.conn();
+        let pages: u64 = conn.query_row("PRAGMA page_count", [], |row| row.get(0))?;
+        let page_size: u64 = conn.query_row("PRAGMA page_size", [], |row| row.get(0))?;
+        let free: u64 = conn.query_row("PRAGMA freelist_count", [], |row| row.get(0))?;
+        Ok((pages, page_size, free))
+    }
+
     /// Initialize the database schema.
     fn init_schema(&self) -> Result<()> {
         // Performance tuning for write-heavy indexing
@@ -235,7 +1101,19 @@ impl SqliteIndex {
              PRAGMA locking_mode = EXCLUSIVE;",
         )?;
         self.conn().execute_batch(SCHEMA_SQL)?;
+        self.conn().execute_batch(PROJECTS_SQL)?;
+        self.conn().execute_batch(ALIASES_SQL)?;
+        self.conn().execute_batch(ANNOTATIONS_SQL)?;
+        self.conn().execute_batch(HISTORY_SQL)?;
+        self.conn().execute_batch(TRIGRAM_SQL)?;
+        self.conn().execute_batch(METRICS_SQL)?;
         self.set_metadata("schema_version", &SCHEMA_VERSION.to_string())?;
+        let created_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        self.set_metadata("created_at", &created_at.to_string())?;
+        self.set_metadata("tool_version", tool_version())?;
         Ok(())
     }
 
@@ -283,6 +1161,13 @@ impl SqliteIndex {
         Ok(value)
     }
 
+    /// Remove a metadata key, if set.
+    pub fn remove_metadata(&self, key: &str) -> Result<()> {
+        self.conn()
+            .execute("DELETE FROM metadata WHERE key = ?1", params![key])?;
+        Ok(())
+    }
+
     // =========================================================================
     // Symbol Operations
     // =========================================================================
@@ -290,8 +1175,8 @@ impl SqliteIndex {
     /// Insert a symbol into the database. Returns the inserted row ID.
     pub fn insert_symbol(&self, symbol: &Symbol) -> Result<i64> {
         self.conn().execute(
-            "INSERT INTO symbols (name, qualified, kind, file, line, column, end_line, end_column, visibility, source, language, parent, mixins, attributes, implements, doc, signature)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, 'syntactic', ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
+            "INSERT INTO symbols (name, qualified, kind, file, line, column, end_line, end_column, visibility, source, language, parent, mixins, attributes, implements, doc, signature, is_test, symbol_id, guid)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, 'syntactic', ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19)",
             params![
                 symbol.name,
                 symbol.qualified,
@@ -309,16 +1194,21 @@ impl SqliteIndex {
                 symbol.implements.as_ref().map(|v| serde_json::to_string(v).unwrap_or_default()),
                 symbol.doc,
                 symbol.signature,
+                is_test_symbol(symbol),
+                symbol_id(&symbol.qualified, symbol.kind),
+                symbol_guid(&symbol.language, &symbol.qualified, symbol.signature.as_deref()),
             ],
         )?;
-        Ok(self.conn().last_insert_rowid())
+        let id = self.conn().last_insert_rowid();
+        self.refresh_symbol_ref_counts(symbol)?;
+        Ok(id)
     }
 
     /// Insert a symbol with type signature.
     pub fn insert_symbol_with_type(&self, symbol: &Symbol, type_signature: &str) -> Result<i64> {
         self.conn().execute(
-            "INSERT INTO symbols (name, qualified, kind, type_signature, file, line, column, end_line, end_column, visibility, source, language, parent, mixins, attributes, implements, doc, signature)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, 'semantic', ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
+            "INSERT INTO symbols (name, qualified, kind, type_signature, file, line, column, end_line, end_column, visibility, source, language, parent, mixins, attributes, implements, doc, signature, is_test, symbol_id, guid)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, 'semantic', ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20)",
             params![
                 symbol.name,
                 symbol.qualified,
@@ -337,51 +1227,32 @@ impl SqliteIndex {
                 symbol.implements.as_ref().map(|v| serde_json::to_string(v).unwrap_or_default()),
                 symbol.doc,
                 symbol.signature,
+                is_test_symbol(symbol),
+                symbol_id(&symbol.qualified, symbol.kind),
+                symbol_guid(&symbol.language, &symbol.qualified, symbol.signature.as_deref()),
             ],
         )?;
-        Ok(self.conn().last_insert_rowid())
+        let id = self.conn().last_insert_rowid();
+        self.refresh_symbol_ref_counts(symbol)?;
+        Ok(id)
+    }
+
+    /// Recount references to a symbol that was just inserted.
+    fn refresh_symbol_ref_counts(&self, symbol: &Symbol) -> Result<()> {
+        let names = HashSet::from([symbol.name.clone(), symbol.qualified.clone()]);
+        refresh_ref_counts(&self.conn(), Some(&names))
     }
 
     /// Insert multiple symbols in a transaction for efficiency.
     pub fn insert_symbols(&self, symbols: &[Symbol]) -> Result<()> {
         let conn = self.conn();
         let tx = conn.unchecked_transaction()?;
-        {
-            let mut stmt = tx.prepare(
-                "INSERT INTO symbols (name, qualified, kind, file, line, column, end_line, end_column, visibility, language, source, parent, mixins, attributes, implements, doc, signature)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, 'syntactic', ?11, ?12, ?13, ?14, ?15, ?16)",
-            )?;
-
-            for symbol in symbols {
-                stmt.execute(params![
-                    symbol.name,
-                    symbol.qualified,
-                    symbol_kind_to_str(symbol.kind),
-                    symbol.location.file.to_string_lossy(),
-                    symbol.location.line,
-                    symbol.location.column,
-                    symbol.location.end_line,
-                    symbol.location.end_column,
-                    visibility_to_str(symbol.visibility),
-                    symbol.language,
-                    symbol.parent,
-                    symbol
-                        .mixins
-                        .as_ref()
-                        .map(|v| serde_json::to_string(v).unwrap_or_default()),
-                    symbol
-                        .attributes
-                        .as_ref()
-                        .map(|v| serde_json::to_string(v).unwrap_or_default()),
-                    symbol
-                        .implements
-                        .as_ref()
-                        .map(|v| serde_json::to_string(v).unwrap_or_default()),
-                    symbol.doc,
-                    symbol.signature,
-                ])?;
-            }
-        }
+        insert_symbol_rows(&tx, symbols)?;
+        let names = symbols
+            .iter()
+            .flat_map(|s| [s.name.clone(), s.qualified.clone()])
+            .collect();
+        refresh_ref_counts(&tx, Some(&names))?;
         tx.commit()?;
         Ok(())
     }
@@ -390,8 +1261,8 @@ impl SqliteIndex {
     #[must_use = "query results should not be ignored"]
     pub fn find_by_qualified(&self, qualified: &str) -> Result<Option<Symbol>> {
         let query = format!(
-            "SELECT {} FROM symbols WHERE qualified = ?1 LIMIT 1",
-            SYMBOL_COLUMNS
+            "SELECT {} FROM symbols WHERE qualified = ?1 ORDER BY ref_count DESC, {} LIMIT 1",
+            SYMBOL_COLUMNS, SYMBOL_ORDER
         );
         let symbol = self
             .conn()
@@ -400,11 +1271,25 @@ impl SqliteIndex {
         Ok(symbol)
     }
 
+    /// Find a symbol by its [`symbol_guid`]. Overloads that share a
+    /// signature share a GUID too; the most referenced wins.
+    pub fn find_by_guid(&self, guid: &str) -> Result<Option<Symbol>> {
+        let query = format!(
+            "SELECT {} FROM symbols WHERE guid = ?1 ORDER BY ref_count DESC, {} LIMIT 1",
+            SYMBOL_COLUMNS, SYMBOL_ORDER
+        );
+        let symbol = self
+            .conn()
+            .query_row(&query, params![guid], row_to_symbol)
+            .optional()?;
+        Ok(symbol)
+    }
+
     /// Find all symbols with the given qualified name (for overloads).
     pub fn find_all_by_qualified(&self, qualified: &str) -> Result<Vec<Symbol>> {
         let query = format!(
-            "SELECT {} FROM symbols WHERE qualified = ?1",
-            SYMBOL_COLUMNS
+            "SELECT {} FROM symbols WHERE qualified = ?1 ORDER BY ref_count DESC, {}",
+            SYMBOL_COLUMNS, SYMBOL_ORDER
         );
         let conn = self.conn();
         let mut stmt = conn.prepare(&query)?;
@@ -417,37 +1302,91 @@ impl SqliteIndex {
     }
 
     /// Search for symbols matching a pattern. Supports SQL LIKE wildcards (% and _).
+    ///
+    /// The most-referenced symbols come first, so a bare name resolves to
+    /// the definition the codebase actually uses.
     #[must_use = "search results should not be ignored"]
     pub fn search(
         &self,
         pattern: &str,
         limit: usize,
         language: Option<&str>,
+    ) -> Result<Vec<Symbol>> {
+        self.search_with(pattern, limit, &SearchFilter::language(language))
+    }
+
+    /// [`search`](Self::search), keeping only symbols that pass `filter`.
+    ///
+    /// Filters are applied in SQL, so a narrow filter stays fast on large
+    /// indexes; attributes are matched by substring there and checked by
+    /// name afterwards.
+    #[must_use = "search results should not be ignored"]
+    pub fn search_with(
+        &self,
+        pattern: &str,
+        limit: usize,
+        filter: &SearchFilter,
     ) -> Result<Vec<Symbol>> {
         // Convert glob-style wildcards to SQL LIKE
         let sql_pattern = pattern.replace('*', "%").replace('?', "_");
 
-        let query = if language.is_some() {
-            format!(
-                "SELECT {} FROM symbols WHERE (name LIKE ?1 OR qualified LIKE ?1) AND language = ?2 LIMIT ?3",
-                SYMBOL_COLUMNS
-            )
-        } else {
-            format!(
-                "SELECT {} FROM symbols WHERE (name LIKE ?1 OR qualified LIKE ?1) LIMIT ?2",
-                SYMBOL_COLUMNS
-            )
+        let mut query = format!(
+            "SELECT {} FROM symbols WHERE (name LIKE ?1 OR qualified LIKE ?1)",
+            SYMBOL_COLUMNS
+        );
+        let mut values: Vec<rusqlite::types::Value> = vec![sql_pattern.into()];
+        let mut bind = |value: String| {
+            values.push(value.into());
+            values.len()
         };
+        if let Some(language) = &filter.language {
+            query += &format!(" AND language = ?{}", bind(language.clone()));
+        }
+        if let Some(kind) = filter.kind {
+            query += &format!(" AND kind = ?{}", bind(symbol_kind_to_str(kind).into()));
+        }
+        if let Some(visibility) = filter.visibility {
+            let n = bind(visibility_to_str(visibility).into());
+            query += &format!(" AND COALESCE(visibility, 'public') = ?{}", n);
+        }
+        if let Some(path) = &filter.path {
+            let path = path.to_string_lossy();
+            let path = path.trim_end_matches(['/', '\\']);
+            if path.contains('*') {
+                // GLOB keeps the literal prefix indexable, like LIKE does
+                let pattern = escape_glob(path);
+                let exact = bind(pattern.clone());
+                let under = bind(format!("{}/*", pattern));
+                query += &format!(" AND (file GLOB ?{} OR file GLOB ?{})", exact, under);
+            } else {
+                let exact = bind(path.to_string());
+                let under = bind(format!("{}/%", escape_like(path)));
+                query += &format!(
+                    " AND (file = ?{} OR file LIKE ?{} ESCAPE '\\')",
+                    exact, under
+                );
+            }
+        }
+        if let Some(attribute) = &filter.attribute {
+            let name = crate::attribute_name(attribute);
+            let n = bind(format!("%{}%", escape_like(name)));
+            query += &format!(" AND attributes LIKE ?{} ESCAPE '\\'", n);
+        }
+        query += &format!(" ORDER BY ref_count DESC, {}", SYMBOL_ORDER);
+        if filter.attribute.is_none() {
+            query += &format!(" LIMIT {}", limit);
+        }
+
         let conn = self.conn();
         let mut stmt = conn.prepare(&query)?;
-
-        let symbols = if let Some(lang) = language {
-            stmt.query_map(params![sql_pattern, lang, limit as i64], row_to_symbol)?
-        } else {
-            stmt.query_map(params![sql_pattern, limit as i64], row_to_symbol)?
-        };
-
-        let symbols = symbols.collect::<std::result::Result<Vec<_>, _>>()?;
+        let symbols = stmt
+            .query_map(rusqlite::params_from_iter(values), row_to_symbol)?
+            .filter(|row| match (row, &filter.attribute) {
+                (Ok(symbol), Some(attribute)) => symbol.has_attribute(attribute),
+                _ => true,
+            })
+            .take(limit)
+            .collect::<std::result::Result<Vec<_>, _>>()?;
 
         Ok(symbols)
     }
@@ -525,12 +1464,12 @@ impl SqliteIndex {
             .join(", ");
         let query = if language.is_some() {
             format!(
-                "SELECT {} FROM symbols s JOIN symbols_fts fts ON s.id = fts.rowid WHERE symbols_fts MATCH ?1 AND s.language = ?2 ORDER BY rank LIMIT ?3",
+                "SELECT {} FROM symbols s JOIN symbols_fts fts ON s.id = fts.rowid WHERE symbols_fts MATCH ?1 AND s.language = ?2 ORDER BY rank, s.file, s.line, s.column, s.qualified LIMIT ?3",
                 prefixed_cols
             )
         } else {
             format!(
-                "SELECT {} FROM symbols s JOIN symbols_fts fts ON s.id = fts.rowid WHERE symbols_fts MATCH ?1 ORDER BY rank LIMIT ?2",
+                "SELECT {} FROM symbols s JOIN symbols_fts fts ON s.id = fts.rowid WHERE symbols_fts MATCH ?1 ORDER BY rank, s.file, s.line, s.column, s.qualified LIMIT ?2",
                 prefixed_cols
             )
         };
@@ -562,6 +1501,131 @@ impl SqliteIndex {
         Ok(symbols)
     }
 
+    /// Symbols defined in a file, in source order, each with how many times
+    /// it's referenced (see [`count_symbol_references`](Self::count_symbol_references)).
+    pub fn reference_counts_in_file(&self, file: &Path) -> Result<Vec<(Symbol, usize)>> {
+        let query = format!(
+            "SELECT {}, ref_count FROM symbols WHERE file = ?1 ORDER BY line, column",
+            SYMBOL_COLUMNS
+        );
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&query)?;
+        let counts = stmt
+            .query_map(params![file.to_string_lossy()], |row| {
+                Ok((row_to_symbol(row)?, row.get::<_, i64>(16)? as usize))
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(counts)
+    }
+
+    /// Read the source of `symbol`'s definition, with `context` lines around it.
+    ///
+    /// The span comes from the stored end position. Parsers that record
+    /// only the name's position leave it on one line; such definitions run
+    /// up to the next symbol in the file (trailing blank lines dropped).
+    pub fn get_source(&self, symbol: &Symbol, context: u32) -> Result<SourceSnippet> {
+        let loc = &symbol.location;
+        let source = std::fs::read_to_string(&loc.file)?;
+        let lines: Vec<&str> = source.lines().collect();
+        let last = lines.len() as u32;
+
+        let start = loc.line.clamp(1, last.max(1));
+        let mut end = if loc.end_line > loc.line {
+            loc.end_line
+        } else {
+            self.symbols_in_file(&loc.file)?
+                .iter()
+                .map(|s| s.location.line)
+                .filter(|&line| line > start)
+                .min()
+                .map_or(last, |next| next - 1)
+        }
+        .min(last);
+        while end > start && lines[end as usize - 1].trim().is_empty() {
+            end -= 1;
+        }
+
+        let first = start.saturating_sub(context).max(1);
+        let through = end.saturating_add(context).min(last);
+        let text = lines
+            .get(first as usize - 1..through as usize)
+            .unwrap_or_default()
+            .join("\n");
+
+        Ok(SourceSnippet {
+            file: loc.file.clone(),
+            start_line: first,
+            end_line: through,
+            symbol_line: start,
+            symbol_end_line: end,
+            text,
+        })
+    }
+
+    /// The innermost symbol whose definition spans `line` of the file named
+    /// by `path`, for stack frames whose symbol name isn't in the index
+    /// (lambdas, closures, compiler-generated names).
+    ///
+    /// `path` may be absolute, relative, or from another machine: the longest
+    /// trailing part of it that names exactly one indexed file is used. As in
+    /// [`get_source`](Self::get_source), definitions without an end position
+    /// run up to the next symbol in the file.
+    pub fn enclosing_symbol(&self, path: &Path, line: u32) -> Result<Option<Symbol>> {
+        let Some(file) = self.indexed_file_matching(path)? else {
+            return Ok(None);
+        };
+        let mut symbols = self.symbols_in_file(&file)?;
+        symbols.sort_by_key(|s| (s.location.line, s.location.column));
+
+        let starts: Vec<u32> = symbols.iter().map(|s| s.location.line).collect();
+        let end_of = |s: &Symbol| {
+            if s.location.end_line > s.location.line {
+                s.location.end_line
+            } else {
+                starts
+                    .iter()
+                    .find(|&&start| start > s.location.line)
+                    .map_or(u32::MAX, |next| next - 1)
+            }
+        };
+        Ok(symbols
+            .iter()
+            .filter(|s| s.location.line <= line && line <= end_of(s))
+            .max_by_key(|s| (s.location.line, std::cmp::Reverse(end_of(s))))
+            .cloned())
+    }
+
+    /// The indexed file that `path` names (see
+    /// [`enclosing_symbol`](Self::enclosing_symbol)), if exactly one does.
+    fn indexed_file_matching(&self, path: &Path) -> Result<Option<PathBuf>> {
+        let full = path.to_string_lossy().replace('\\', "/");
+        let parts: Vec<&str> = full
+            .split('/')
+            .filter(|p| !p.is_empty() && *p != ".")
+            .collect();
+
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT DISTINCT file FROM symbols WHERE file = ?1 OR file LIKE ?2 ESCAPE '\\' LIMIT 2",
+        )?;
+        for skip in 0..parts.len() {
+            let suffix = parts[skip..].join("/");
+            let files = stmt
+                .query_map(
+                    params![full, format!("%/{}", escape_like(&suffix))],
+                    |row| row.get::<_, String>(0),
+                )?
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            match files.as_slice() {
+                [] => continue,
+                [file] => return Ok(Some(PathBuf::from(file))),
+                // Longer suffixes found nothing, so shorter ones won't narrow it
+                _ => return Ok(None),
+            }
+        }
+        Ok(None)
+    }
+
     /// Delete all symbols in a file.
     pub fn delete_symbols_in_file(&self, file: &Path) -> Result<usize> {
         let file_str = file.to_string_lossy();
@@ -572,6 +1636,33 @@ impl SqliteIndex {
         Ok(count)
     }
 
+    /// Delete the symbols of files under `dir` that were written without
+    /// being tracked for refresh (see [`set_file_mtime`](Self::set_file_mtime)),
+    /// as dependency sources are. Returns the number deleted.
+    pub fn delete_untracked_symbols_under(&self, dir: &Path) -> Result<usize> {
+        let prefix = dir.join("").to_string_lossy().into_owned();
+        let count = self.conn().execute(
+            "DELETE FROM symbols WHERE substr(file, 1, length(?1)) = ?1
+               AND file NOT IN (SELECT path FROM file_mtimes)",
+            params![prefix],
+        )?;
+        Ok(count)
+    }
+
+    /// List every distinct qualified name with its kind.
+    pub fn qualified_names(&self) -> Result<Vec<(String, SymbolKind)>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare("SELECT DISTINCT qualified, kind FROM symbols")?;
+        let names = stmt
+            .query_map([], |row| {
+                let qualified: String = row.get(0)?;
+                let kind: String = row.get(1)?;
+                Ok((qualified, str_to_symbol_kind(&kind)))
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(names)
+    }
+
     /// Count total symbols in the index.
     pub fn count_symbols(&self) -> Result<usize> {
         let count: i64 = self
@@ -580,10 +1671,29 @@ impl SqliteIndex {
         Ok(count as usize)
     }
 
+    /// Files and symbols indexed per language, by language name.
+    pub fn language_counts(&self) -> Result<Vec<LanguageCount>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT language, COUNT(DISTINCT file), COUNT(*) FROM symbols
+             GROUP BY language ORDER BY language",
+        )?;
+        let counts = stmt
+            .query_map([], |row| {
+                Ok(LanguageCount {
+                    language: row.get(0)?,
+                    files: row.get::<_, i64>(1)? as usize,
+                    symbols: row.get::<_, i64>(2)? as usize,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(counts)
+    }
+
     /// Find similar symbol names for "did you mean?" suggestions.
     ///
-    /// Returns symbols within `max_distance` edits of the query,
-    /// sorted by edit distance (closest first).
+    /// Returns names and qualified names within `max_distance` edits of the
+    /// query, ranked by [`crate::fuzzy::rank_suggestions`].
     ///
     /// # Arguments
     ///
@@ -596,38 +1706,42 @@ impl SqliteIndex {
         max_distance: usize,
         max_suggestions: usize,
     ) -> Result<Vec<crate::fuzzy::Suggestion>> {
-        // Get all unique symbol names and qualified names
         let conn = self.conn();
-        let mut stmt = conn.prepare(
-            "SELECT DISTINCT name FROM symbols
-             UNION
-             SELECT DISTINCT qualified FROM symbols",
-        )?;
-
-        let names: Vec<String> = stmt
-            .query_map([], |row| row.get(0))?
+        let mut stmt = conn.prepare("SELECT name, qualified, kind, ref_count FROM symbols")?;
+        let rows: Vec<(String, String, String, u32)> = stmt
+            .query_map([], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })?
             .collect::<std::result::Result<Vec<_>, _>>()?;
 
-        // Run fuzzy matching
-        let suggestions = crate::fuzzy::find_similar(
+        let candidates = rows.iter().flat_map(|(name, qualified, kind, references)| {
+            let kind = str_to_symbol_kind(kind);
+            [name, qualified].map(|value| crate::fuzzy::Candidate {
+                value,
+                kind,
+                references: *references,
+            })
+        });
+        Ok(crate::fuzzy::rank_suggestions(
             query,
-            names.iter().map(String::as_str),
+            candidates,
             max_distance,
             max_suggestions,
-        );
-
-        Ok(suggestions)
+        ))
     }
 
-    /// Search for symbols using fuzzy matching (edit distance).
+    /// Search for symbols by abbreviation or with typos.
     ///
-    /// Returns symbols whose name or qualified name is within `max_distance`
-    /// edits of the query, sorted by edit distance (closest first).
+    /// Candidates come from the trigram index (names sharing a trigram with
+    /// the query, for typos), a subsequence `LIKE` (so `usrSvc` finds
+    /// `UserService`) and a name-prefix search, then are ranked by
+    /// [`combined_score`](crate::fuzzy::combined_score), best first. Queries
+    /// with a `.` or `:` are matched against qualified names as well.
     ///
     /// # Arguments
     ///
     /// * `query` - The pattern to fuzzy match against
-    /// * `max_distance` - Maximum edit distance to consider
+    /// * `max_distance` - Maximum edit distance for a typo to match
     /// * `limit` - Maximum number of results to return
     /// * `language` - Optional language filter
     pub fn fuzzy_search(
@@ -636,73 +1750,105 @@ impl SqliteIndex {
         max_distance: usize,
         limit: usize,
         language: Option<&str>,
-    ) -> Result<Vec<(Symbol, usize)>> {
-        // OPTIMIZATION: Use FTS to get candidates first, then filter by edit distance.
-        // This avoids loading all 36k+ symbols into memory for every fuzzy search.
-        // We search for prefix matches which are likely to have low edit distance.
-
-        // Generate candidate prefixes from the query (first N chars)
-        let candidate_limit = limit * 20; // Get more candidates than needed for filtering
-        let symbols = if query.len() >= 2 {
-            // Use FTS prefix search for candidate generation
-            let prefix = &query[..query.len().min(4)];
-            let fts_query = format!("{}*", prefix);
-
-            let prefixed_cols = SYMBOL_COLUMNS
-                .split(", ")
-                .map(|c| format!("s.{}", c))
-                .collect::<Vec<_>>()
-                .join(", ");
-
-            let sql = if language.is_some() {
-                format!(
-                    "SELECT {} FROM symbols s JOIN symbols_fts fts ON s.id = fts.rowid WHERE symbols_fts MATCH ?1 AND s.language = ?2 LIMIT ?3",
-                    prefixed_cols
-                )
-            } else {
-                format!(
-                    "SELECT {} FROM symbols s JOIN symbols_fts fts ON s.id = fts.rowid WHERE symbols_fts MATCH ?1 LIMIT ?2",
-                    prefixed_cols
-                )
-            };
-
-            let conn = self.conn();
-            let mut stmt = conn.prepare(&sql)?;
-            let result: Vec<Symbol> = if let Some(lang) = language {
-                stmt.query_map(
-                    params![fts_query, lang, candidate_limit as i64],
-                    row_to_symbol,
-                )?
-            } else {
-                stmt.query_map(params![fts_query, candidate_limit as i64], row_to_symbol)?
-            }
-            .collect::<std::result::Result<Vec<_>, _>>()?;
-
-            // If FTS found enough candidates, use them; otherwise fall back to full scan
-            if result.len() >= limit {
-                result
-            } else {
-                // Fall back to scanning (for very short queries or no FTS matches)
-                self.fuzzy_search_full_scan(query, language, candidate_limit)?
-            }
+    ) -> Result<Vec<FuzzyMatch>> {
+        let candidate_limit = limit * 20; // Get more candidates than needed for ranking
+        let qualified = query.contains(['.', ':']);
+        let prefixed_cols = SYMBOL_COLUMNS
+            .split(", ")
+            .map(|c| format!("s.{}", c))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let language_filter = if language.is_some() {
+            "AND s.language = ?3"
         } else {
-            // Query too short for FTS, do full scan
-            self.fuzzy_search_full_scan(query, language, candidate_limit)?
+            "AND ?3 IS NULL"
         };
 
-        // Filter by edit distance and sort
-        let mut results = Vec::new();
-        for symbol in symbols {
-            let name_dist = crate::fuzzy::levenshtein_distance(query, &symbol.name);
-            let qual_dist = crate::fuzzy::levenshtein_distance(query, &symbol.qualified);
-            let dist = std::cmp::min(name_dist, qual_dist);
+        let mut sources: Vec<(String, String)> = Vec::new();
+        if let Some(trigrams) = trigram_query(query) {
+            sources.push((
+                format!(
+                    "SELECT {prefixed_cols} FROM symbols s JOIN symbols_trigram t ON s.id = t.rowid
+                     WHERE symbols_trigram MATCH ?1 {language_filter}
+                     ORDER BY rank, s.file, s.line, s.column, s.qualified LIMIT ?2"
+                ),
+                trigrams,
+            ));
+        }
+        sources.push((
+            format!(
+                "SELECT {prefixed_cols} FROM symbols s WHERE s.{} LIKE ?1 ESCAPE '\\' {language_filter}
+                 ORDER BY s.file, s.line, s.column, s.qualified LIMIT ?2",
+                if qualified { "qualified" } else { "name" }
+            ),
+            subsequence_pattern(query),
+        ));
+        if query.len() >= 2 {
+            let prefix: String = query.chars().take(4).collect();
+            sources.push((
+                format!(
+                    "SELECT {prefixed_cols} FROM symbols s JOIN symbols_fts fts ON s.id = fts.rowid
+                     WHERE symbols_fts MATCH ?1 {language_filter}
+                     ORDER BY rank, s.file, s.line, s.column, s.qualified LIMIT ?2"
+                ),
+                format!("\"{}\"*", prefix.replace('"', "\"\"")),
+            ));
+        }
 
-            if dist <= max_distance {
-                results.push((symbol, dist));
+        let mut symbols = Vec::new();
+        {
+            let conn = self.conn();
+            for (sql, pattern) in &sources {
+                let mut stmt = conn.prepare(sql)?;
+                let found = stmt
+                    .query_map(
+                        params![pattern, candidate_limit as i64, language],
+                        row_to_symbol,
+                    )?
+                    .collect::<std::result::Result<Vec<_>, _>>();
+                // A query FTS can't parse just contributes nothing
+                match found {
+                    Ok(found) => symbols.extend(found),
+                    Err(e) if sql.contains("MATCH") => {
+                        tracing::debug!("Fuzzy candidates skipped: {}", e)
+                    }
+                    Err(e) => return Err(e.into()),
+                }
             }
         }
+        if symbols.len() < limit {
+            // Very short queries or nothing similar enough to prefilter on
+            symbols.extend(self.fuzzy_search_full_scan(query, language, candidate_limit)?);
+        }
 
-        results.sort_by_key(|(_, dist)| *dist);
+        let mut seen = HashSet::new();
+        let mut results: Vec<FuzzyMatch> = symbols
+            .into_iter()
+            .filter(|s| seen.insert((s.qualified.clone(), s.location.clone())))
+            .filter_map(|symbol| {
+                let by_name = crate::fuzzy::combined_score(query, &symbol.name, max_distance);
+                let by_qualified = qualified
+                    .then(|| crate::fuzzy::combined_score(query, &symbol.qualified, max_distance))
+                    .flatten();
+                let score = by_name.max(by_qualified)?;
+                let distance = crate::fuzzy::levenshtein_distance(query, &symbol.name)
+                    .min(crate::fuzzy::levenshtein_distance(query, &symbol.qualified));
+                Some(FuzzyMatch {
+                    symbol,
+                    score,
+                    distance,
+                })
+            })
+            .collect();
+
+        results.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then(a.distance.cmp(&b.distance))
+                .then(a.symbol.name.len().cmp(&b.symbol.name.len()))
+                .then_with(|| a.symbol.qualified.cmp(&b.symbol.qualified))
+                .then_with(|| a.symbol.location.cmp(&b.symbol.location))
+        });
         results.truncate(limit);
 
         Ok(results)
@@ -717,11 +1863,14 @@ impl SqliteIndex {
     ) -> Result<Vec<Symbol>> {
         let sql = if language.is_some() {
             format!(
-                "SELECT {} FROM symbols WHERE language = ?1 LIMIT ?2",
-                SYMBOL_COLUMNS
+                "SELECT {} FROM symbols WHERE language = ?1 ORDER BY {} LIMIT ?2",
+                SYMBOL_COLUMNS, SYMBOL_ORDER
             )
         } else {
-            format!("SELECT {} FROM symbols LIMIT ?1", SYMBOL_COLUMNS)
+            format!(
+                "SELECT {} FROM symbols ORDER BY {} LIMIT ?1",
+                SYMBOL_COLUMNS, SYMBOL_ORDER
+            )
         };
         let conn = self.conn();
         let mut stmt = conn.prepare(&sql)?;
@@ -739,45 +1888,155 @@ impl SqliteIndex {
     /// Find all classes/modules that inherit from the given parent.
     /// Uses index on parent column for exact matches, with optimized suffix matching.
     pub fn find_subclasses(&self, parent: &str) -> Result<Vec<Symbol>> {
-        // First try exact match using the index (fast path)
-        let query = format!("SELECT {} FROM symbols WHERE parent = ?1", SYMBOL_COLUMNS);
+        // Exact matches use the index on parent; also match with a leading
+        // :: (e.g., "::Common::Client::Base")
+        let query = format!(
+            "SELECT {} FROM symbols WHERE parent = ?1 OR parent = ?2 ORDER BY {}",
+            SYMBOL_COLUMNS, SYMBOL_ORDER
+        );
+        let prefixed = format!("::{}", parent);
         let conn = self.conn();
         let mut stmt = conn.prepare(&query)?;
-        let mut symbols: Vec<Symbol> = stmt
-            .query_map(params![parent], row_to_symbol)?
+        let symbols: Vec<Symbol> = stmt
+            .query_map(params![parent, prefixed], row_to_symbol)?
             .collect::<std::result::Result<Vec<_>, _>>()?;
 
-        // Also match with leading :: (e.g., "::Common::Client::Base")
-        let prefixed = format!("::{}", parent);
-        let query2 = format!("SELECT {} FROM symbols WHERE parent = ?1", SYMBOL_COLUMNS);
-        let mut stmt2 = conn.prepare(&query2)?;
-        let prefixed_symbols: Vec<Symbol> = stmt2
-            .query_map(params![prefixed], row_to_symbol)?
+        Ok(symbols)
+    }
+
+    /// Find all types that implement or derive the given interface or
+    /// trait, qualified or not: `IComparable` finds `System.IComparable<T>`.
+    pub fn find_implementers(&self, interface: &str) -> Result<Vec<Symbol>> {
+        // Narrowed by substring in SQL, then matched by bare name (see
+        // [`Symbol::implements_type`])
+        let pattern = format!("%{}%", escape_like(crate::type_name(interface)));
+        let query = format!(
+            "SELECT {} FROM symbols WHERE implements LIKE ?1 ESCAPE '\\' ORDER BY {}",
+            SYMBOL_COLUMNS, SYMBOL_ORDER
+        );
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&query)?;
+        let symbols: Vec<Symbol> = stmt
+            .query_map(params![pattern], row_to_symbol)?
+            .filter(|row| {
+                row.as_ref()
+                    .map_or(true, |symbol| symbol.implements_type(interface))
+            })
             .collect::<std::result::Result<Vec<_>, _>>()?;
-        symbols.extend(prefixed_symbols);
 
         Ok(symbols)
     }
 
-    /// Find all types that implement the given interface.
-    /// Searches the JSON implements array for matches.
-    pub fn find_implementers(&self, interface: &str) -> Result<Vec<Symbol>> {
-        // Search for interface name in the JSON array
-        // Pattern: "InterfaceName" should match ["InterfaceName"] or ["...", "InterfaceName", "..."]
-        let pattern = format!("%\"{}%", interface);
+    /// Find all classes/modules that include, extend or prepend the given
+    /// module (Ruby mixins), written with or without a leading `::`.
+    pub fn find_includers(&self, module: &str) -> Result<Vec<Symbol>> {
+        let module = module.trim_start_matches("::");
+        let pattern = format!("%{}%", escape_like(module));
         let query = format!(
-            "SELECT {} FROM symbols WHERE implements LIKE ?1",
-            SYMBOL_COLUMNS
+            "SELECT {} FROM symbols WHERE mixins LIKE ?1 ESCAPE '\\' ORDER BY {}",
+            SYMBOL_COLUMNS, SYMBOL_ORDER
         );
         let conn = self.conn();
         let mut stmt = conn.prepare(&query)?;
         let symbols: Vec<Symbol> = stmt
             .query_map(params![pattern], row_to_symbol)?
+            .filter(|row| {
+                row.as_ref().map_or(true, |symbol| {
+                    symbol
+                        .mixins
+                        .iter()
+                        .flatten()
+                        .any(|mixin| mixin.trim_start_matches("::") == module)
+                })
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(symbols)
+    }
+
+    /// Find the symbols classified as tests (see [`crate::test_map`]), by file then line.
+    pub fn find_tests(&self) -> Result<Vec<Symbol>> {
+        let query = format!(
+            "SELECT {} FROM symbols WHERE is_test = 1 ORDER BY {}",
+            SYMBOL_COLUMNS, SYMBOL_ORDER
+        );
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&query)?;
+        let symbols: Vec<Symbol> = stmt
+            .query_map([], row_to_symbol)?
             .collect::<std::result::Result<Vec<_>, _>>()?;
 
         Ok(symbols)
     }
 
+    /// Recompute `is_test` for every symbol, for indexes written before it existed.
+    fn classify_tests(&self) -> Result<()> {
+        // Part of a migration, so already inside its transaction
+        let conn = self.conn();
+        let mut select = conn.prepare(&format!("SELECT {}, id FROM symbols", SYMBOL_COLUMNS))?;
+        let tests: Vec<i64> = select
+            .query_map([], |row| Ok((row_to_symbol(row)?, row.get::<_, i64>(16)?)))?
+            .filter_map(|row| match row {
+                Ok((symbol, id)) if is_test_symbol(&symbol) => Some(Ok(id)),
+                Ok(_) => None,
+                Err(e) => Some(Err(e)),
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let mut update = conn.prepare("UPDATE symbols SET is_test = 1 WHERE id = ?1")?;
+        for id in tests {
+            update.execute(params![id])?;
+        }
+        Ok(())
+    }
+
+    /// Fill in `symbol_id` for indexes written before it existed.
+    fn assign_symbol_ids(&self) -> Result<()> {
+        // Part of a migration, so already inside its transaction
+        let conn = self.conn();
+        let mut select = conn.prepare("SELECT id, qualified, kind FROM symbols")?;
+        let ids: Vec<(i64, i64)> = select
+            .query_map([], |row| {
+                let qualified: String = row.get(1)?;
+                let kind: String = row.get(2)?;
+                Ok((
+                    row.get(0)?,
+                    symbol_id(&qualified, str_to_symbol_kind(&kind)),
+                ))
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let mut update = conn.prepare("UPDATE symbols SET symbol_id = ?2 WHERE id = ?1")?;
+        for (id, symbol_id) in ids {
+            update.execute(params![id, symbol_id])?;
+        }
+        Ok(())
+    }
+
+    /// Fill in `guid` for indexes written before it existed.
+    fn assign_symbol_guids(&self) -> Result<()> {
+        // Part of a migration, so already inside its transaction
+        let conn = self.conn();
+        let mut select = conn.prepare("SELECT id, language, qualified, signature FROM symbols")?;
+        let guids: Vec<(i64, String)> = select
+            .query_map([], |row| {
+                let language: String = row.get(1)?;
+                let qualified: String = row.get(2)?;
+                let signature: Option<String> = row.get(3)?;
+                Ok((
+                    row.get(0)?,
+                    symbol_guid(&language, &qualified, signature.as_deref()),
+                ))
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let mut update = conn.prepare("UPDATE symbols SET guid = ?2 WHERE id = ?1")?;
+        for (id, guid) in guids {
+            update.execute(params![id, guid])?;
+        }
+        Ok(())
+    }
+
     /// List all indexed files.
     pub fn list_files(&self) -> Result<Vec<PathBuf>> {
         let conn = self.conn();
@@ -793,23 +2052,406 @@ impl SqliteIndex {
         Ok(files)
     }
 
-    /// Get type signature for a symbol by qualified name.
-    pub fn get_symbol_type(&self, qualified: &str) -> Result<Option<String>> {
-        let type_sig: Option<String> = self
-            .conn()
-            .query_row(
-                "SELECT type_signature FROM symbols WHERE qualified = ?1 AND type_signature IS NOT NULL LIMIT 1",
-                params![qualified],
-                |row| row.get(0),
-            )
-            .optional()?;
-        Ok(type_sig)
-    }
+    /// Record the projects of a multi-root workspace and tag existing symbols
+    /// and references with them. Symbols inserted later are tagged on insert.
+    ///
+    /// Does nothing if the projects are unchanged; an empty slice makes the
+    /// index single-project again.
+    pub fn set_projects(&self, projects: &[Project]) -> Result<()> {
+        let rows: Vec<(String, String)> = projects
+            .iter()
+            .map(|p| {
+                let root = p.root.to_string_lossy();
+                let root = format!(
+                    "{}{}",
+                    root.trim_end_matches(['/', '\\']),
+                    std::path::MAIN_SEPARATOR
+                );
+                (p.name.clone(), root)
+            })
+            .collect();
 
-    /// Update type signature for existing symbol(s).
-    pub fn update_symbol_type(&self, qualified: &str, type_signature: &str) -> Result<usize> {
-        let count = self.conn().execute(
-            "UPDATE symbols SET type_signature = ?1, source = 'semantic' WHERE qualified = ?2",
+        let conn = self.conn();
+        let mut existing: Vec<(String, String)> = conn
+            .prepare("SELECT name, root FROM projects")?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        existing.sort();
+        let mut wanted = rows.clone();
+        wanted.sort();
+        if existing == wanted {
+            return Ok(());
+        }
+
+        let tx = conn.unchecked_transaction()?;
+        tx.execute("DELETE FROM projects", [])?;
+        for (name, root) in &rows {
+            tx.execute(
+                "INSERT INTO projects (name, root) VALUES (?1, ?2)",
+                params![name, root],
+            )?;
+        }
+        for table in ["symbols", "refs"] {
+            tx.execute(
+                &format!(
+                    "UPDATE {table} SET project = (
+                        SELECT name FROM projects WHERE substr({table}.file, 1, length(root)) = root
+                        ORDER BY length(root) DESC LIMIT 1
+                    )"
+                ),
+                [],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Names of the workspace's projects, sorted. Empty for a single-root index.
+    pub fn projects(&self) -> Result<Vec<String>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare("SELECT name FROM projects ORDER BY name")?;
+        let names = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(names)
+    }
+
+    /// Record the NuGet packages the workspace's projects reference, so
+    /// unresolved names can be attributed to them.
+    pub fn set_packages(&self, packages: &[PackageReference]) -> Result<()> {
+        self.set_metadata("packages", &serde_json::to_string(packages)?)
+    }
+
+    /// Packages recorded by [`set_packages`](Self::set_packages).
+    pub fn packages(&self) -> Result<Vec<PackageReference>> {
+        match self.get_metadata("packages")? {
+            Some(json) => Ok(serde_json::from_str(&json)?),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Record the `[[visibility]]` rules symbols are written with from now
+    /// on (see [`VisibilityRule`]). Symbols already written keep what the
+    /// old rules gave them until their files are reindexed.
+    pub fn set_visibility_rules(&self, rules: &[VisibilityRule]) -> Result<()> {
+        self.set_metadata(VISIBILITY_RULES, &serde_json::to_string(rules)?)
+    }
+
+    /// Rules recorded by [`set_visibility_rules`](Self::set_visibility_rules).
+    pub fn visibility_rules(&self) -> Result<Vec<VisibilityRule>> {
+        match self.get_metadata(VISIBILITY_RULES)? {
+            Some(json) => Ok(serde_json::from_str(&json)?),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Restrict every query on this connection to one project's symbols and
+    /// references.
+    ///
+    /// Temporary views shadow the `symbols` and `refs` tables, so a scoped
+    /// index is for reading only: writes through it fail.
+    pub fn scope_to_project(&self, name: &str) -> Result<()> {
+        if !self.projects()?.iter().any(|p| p == name) {
+            return Err(IndexError::ProjectNotFound(name.to_string()));
+        }
+        let quoted = name.replace('\'', "''");
+        self.conn().execute_batch(&format!(
+            "DROP VIEW IF EXISTS temp.symbols;
+             DROP VIEW IF EXISTS temp.refs;
+             CREATE TEMP VIEW symbols AS SELECT * FROM main.symbols WHERE project = '{quoted}';
+             CREATE TEMP VIEW refs AS SELECT * FROM main.refs WHERE project = '{quoted}';"
+        ))?;
+        Ok(())
+    }
+
+    // =========================================================================
+    // Alias Operations
+    // =========================================================================
+
+    /// Record that the symbol `old` is now called `new` (both qualified).
+    ///
+    /// Chains collapse, so every alias points at the current name: after
+    /// `A → B` and `B → C`, `A` resolves to `C` directly. Renaming back to an
+    /// old name drops its alias.
+    pub fn add_alias(&self, old: &str, new: &str) -> Result<()> {
+        let conn = self.conn();
+        let tx = conn.unchecked_transaction()?;
+        tx.execute(
+            "UPDATE aliases SET new = ?2 WHERE new = ?1",
+            params![old, new],
+        )?;
+        tx.execute(
+            "INSERT OR REPLACE INTO aliases (old, new) VALUES (?1, ?2)",
+            params![old, new],
+        )?;
+        // The new name is in use again, so nothing should redirect away from it
+        tx.execute(
+            "DELETE FROM aliases WHERE old = new OR old = ?1",
+            params![new],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Forget the alias for `old`. Returns whether there was one.
+    pub fn remove_alias(&self, old: &str) -> Result<bool> {
+        let removed = self
+            .conn()
+            .execute("DELETE FROM aliases WHERE old = ?1", params![old])?;
+        Ok(removed > 0)
+    }
+
+    /// All aliases, sorted by old name.
+    pub fn aliases(&self) -> Result<Vec<Alias>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare("SELECT old, new FROM aliases ORDER BY old")?;
+        let aliases = stmt
+            .query_map([], |row| {
+                Ok(Alias {
+                    old: row.get(0)?,
+                    new: row.get(1)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(aliases)
+    }
+
+    /// The name `qualified` was renamed to, if it was and no symbol has
+    /// taken the old name since.
+    pub fn follow_alias(&self, qualified: &str) -> Result<Option<String>> {
+        if self.find_by_qualified(qualified)?.is_some() {
+            return Ok(None);
+        }
+        let new = self
+            .conn()
+            .query_row(
+                "SELECT new FROM aliases WHERE old = ?1",
+                params![qualified],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(new)
+    }
+
+    // =========================================================================
+    // Notes, Watchlists and Layer Rules
+    // =========================================================================
+
+    /// Add a note on the symbol with `guid`, named `qualified`. Returns its id.
+    pub fn add_note(&self, guid: &str, qualified: &str, text: &str) -> Result<i64> {
+        let conn = self.conn();
+        conn.execute(
+            "INSERT INTO notes (guid, qualified, text, created_at)
+             VALUES (?1, ?2, ?3, unixepoch())",
+            params![guid, qualified, text],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// Delete the note with `id`. Returns whether there was one.
+    pub fn remove_note(&self, id: i64) -> Result<bool> {
+        let removed = self
+            .conn()
+            .execute("DELETE FROM notes WHERE id = ?1", params![id])?;
+        Ok(removed > 0)
+    }
+
+    /// Notes on the symbol with `guid`, or written on a symbol of the same
+    /// qualified name whose GUID has since changed, oldest first.
+    pub fn notes_for(&self, guid: &str, qualified: &str) -> Result<Vec<Note>> {
+        self.query_notes(
+            "WHERE guid = ?1 OR qualified = ?2 ORDER BY id",
+            params![guid, qualified],
+        )
+    }
+
+    /// All notes, by symbol and then oldest first.
+    pub fn notes(&self) -> Result<Vec<Note>> {
+        self.query_notes("ORDER BY qualified, id", [])
+    }
+
+    fn query_notes(&self, clause: &str, params: impl rusqlite::Params) -> Result<Vec<Note>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, guid, qualified, text, created_at FROM notes {clause}"
+        ))?;
+        let notes = stmt
+            .query_map(params, |row| {
+                Ok(Note {
+                    id: row.get(0)?,
+                    guid: row.get(1)?,
+                    qualified: row.get(2)?,
+                    text: row.get(3)?,
+                    created_at: row.get(4)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(notes)
+    }
+
+    /// Put the symbol with `guid`, named `qualified`, on watchlist `list`.
+    /// Returns false if it was already there.
+    pub fn watch_symbol(&self, list: &str, guid: &str, qualified: &str) -> Result<bool> {
+        let added = self.conn().execute(
+            "INSERT OR IGNORE INTO watchlists (list, guid, qualified) VALUES (?1, ?2, ?3)",
+            params![list, guid, qualified],
+        )?;
+        Ok(added > 0)
+    }
+
+    /// Take a symbol, by GUID or qualified name, off watchlist `list`.
+    /// Returns whether it was on it.
+    pub fn unwatch_symbol(&self, list: &str, symbol: &str) -> Result<bool> {
+        let removed = self.conn().execute(
+            "DELETE FROM watchlists WHERE list = ?1 AND (guid = ?2 OR qualified = ?2)",
+            params![list, symbol],
+        )?;
+        Ok(removed > 0)
+    }
+
+    /// The symbols on watchlist `list`, or on every watchlist, sorted by list
+    /// and name.
+    pub fn watchlists(&self, list: Option<&str>) -> Result<Vec<WatchedSymbol>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT list, guid, qualified FROM watchlists
+             WHERE ?1 IS NULL OR list = ?1
+             ORDER BY list, qualified",
+        )?;
+        let watched = stmt
+            .query_map(params![list], |row| {
+                Ok(WatchedSymbol {
+                    list: row.get(0)?,
+                    guid: row.get(1)?,
+                    qualified: row.get(2)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(watched)
+    }
+
+    /// Record a layering rule. Returns false if it was already recorded.
+    pub fn add_layer_rule(&self, rule: &LayerRule) -> Result<bool> {
+        let added = self.conn().execute(
+            "INSERT OR IGNORE INTO layer_rules (from_path, to_path) VALUES (?1, ?2)",
+            params![rule.from, rule.to],
+        )?;
+        Ok(added > 0)
+    }
+
+    /// Forget a layering rule. Returns whether it was recorded.
+    pub fn remove_layer_rule(&self, rule: &LayerRule) -> Result<bool> {
+        let removed = self.conn().execute(
+            "DELETE FROM layer_rules WHERE from_path = ?1 AND to_path = ?2",
+            params![rule.from, rule.to],
+        )?;
+        Ok(removed > 0)
+    }
+
+    /// All layering rules, sorted.
+    pub fn layer_rules(&self) -> Result<Vec<LayerRule>> {
+        let conn = self.conn();
+        let mut stmt =
+            conn.prepare("SELECT from_path, to_path FROM layer_rules ORDER BY from_path, to_path")?;
+        let rules = stmt
+            .query_map([], |row| {
+                Ok(LayerRule {
+                    from: row.get(0)?,
+                    to: row.get(1)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(rules)
+    }
+
+    // =========================================================================
+    // Symbol History
+    // =========================================================================
+
+    /// Where `qualified` went: the moves recorded since it disappeared,
+    /// following a symbol that moved more than once. Empty when the symbol
+    /// never disappeared.
+    ///
+    /// The last entry has no destination when the symbol was removed and
+    /// nothing near-identical has appeared since.
+    pub fn moves_of(&self, qualified: &str) -> Result<Vec<SymbolMove>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {HISTORY_COLUMNS} FROM symbol_history
+             WHERE old_qualified = ?1 ORDER BY recorded_at DESC, id DESC LIMIT 1"
+        ))?;
+
+        let mut moves: Vec<SymbolMove> = Vec::new();
+        let mut current = qualified.to_string();
+        while let Some(hop) = stmt.query_row(params![current], row_to_move).optional()? {
+            // Moved back and forth: stop before going round again
+            if moves.iter().any(|m| m.from == hop.from) {
+                break;
+            }
+            let next = hop.to.clone();
+            moves.push(hop);
+            match next {
+                Some(next) => current = next,
+                None => break,
+            }
+        }
+        Ok(moves)
+    }
+
+    /// Every completed move, oldest first.
+    pub fn symbol_moves(&self) -> Result<Vec<SymbolMove>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {HISTORY_COLUMNS} FROM symbol_history
+             WHERE new_qualified IS NOT NULL ORDER BY id"
+        ))?;
+        let moves = stmt
+            .query_map([], row_to_move)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(moves)
+    }
+
+    /// Record a move, e.g. to carry history over to a rebuilt index.
+    pub fn add_symbol_move(&self, symbol_move: &SymbolMove) -> Result<()> {
+        self.conn().execute(
+            "INSERT INTO symbol_history (symbol_id, kind, name, signature, old_qualified, old_file, old_line, new_qualified, new_file, new_line, recorded_at)
+             VALUES (?1, ?2, ?3, NULL, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                symbol_move.symbol_id,
+                symbol_kind_to_str(symbol_move.kind),
+                symbol_move.name,
+                symbol_move.from,
+                symbol_move.from_file.to_string_lossy(),
+                symbol_move.from_line,
+                symbol_move.to,
+                symbol_move
+                    .to_file
+                    .as_ref()
+                    .map(|f| f.to_string_lossy().into_owned()),
+                symbol_move.to_line,
+                symbol_move.recorded_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Get type signature for a symbol by qualified name.
+    pub fn get_symbol_type(&self, qualified: &str) -> Result<Option<String>> {
+        let type_sig: Option<String> = self
+            .conn()
+            .query_row(
+                "SELECT type_signature FROM symbols WHERE qualified = ?1 AND type_signature IS NOT NULL LIMIT 1",
+                params![qualified],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(type_sig)
+    }
+
+    /// Update type signature for existing symbol(s).
+    pub fn update_symbol_type(&self, qualified: &str, type_signature: &str) -> Result<usize> {
+        let count = self.conn().execute(
+            "UPDATE symbols SET type_signature = ?1, source = 'semantic' WHERE qualified = ?2",
             params![type_signature, qualified],
         )?;
         Ok(count)
@@ -823,35 +2465,31 @@ impl SqliteIndex {
     pub fn insert_reference(&self, file: &Path, reference: &Reference) -> Result<i64> {
         let file_str = file.to_string_lossy();
         self.conn().execute(
-            "INSERT INTO refs (name, file, line, column) VALUES (?1, ?2, ?3, ?4)",
+            "INSERT INTO refs (name, file, line, column, end_line, end_column, confidence, kind)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
             params![
                 reference.name,
                 file_str.as_ref(),
                 reference.location.line,
                 reference.location.column,
+                reference.location.end_line,
+                reference.location.end_column,
+                Confidence::Heuristic.as_str(),
+                reference.kind.as_str(),
             ],
         )?;
-        Ok(self.conn().last_insert_rowid())
+        let id = self.conn().last_insert_rowid();
+        refresh_ref_counts(&self.conn(), Some(&HashSet::from([reference.name.clone()])))?;
+        Ok(id)
     }
 
     /// Insert multiple references in a transaction for efficiency.
     pub fn insert_references(&self, refs: &[(&Path, &Reference)]) -> Result<()> {
         let conn = self.conn();
         let tx = conn.unchecked_transaction()?;
-        {
-            let mut stmt =
-                tx.prepare("INSERT INTO refs (name, file, line, column) VALUES (?1, ?2, ?3, ?4)")?;
-
-            for (file, reference) in refs {
-                let file_str = file.to_string_lossy();
-                stmt.execute(params![
-                    reference.name,
-                    file_str.as_ref(),
-                    reference.location.line,
-                    reference.location.column,
-                ])?;
-            }
-        }
+        insert_reference_rows(&tx, refs)?;
+        let names = refs.iter().map(|(_, r)| r.name.clone()).collect();
+        refresh_ref_counts(&tx, Some(&names))?;
         tx.commit()?;
         Ok(())
     }
@@ -862,59 +2500,195 @@ impl SqliteIndex {
     pub fn find_references(&self, name: &str) -> Result<Vec<Reference>> {
         let conn = self.conn();
         let mut stmt = conn.prepare(
-            "SELECT name, file, line, column FROM refs
+            "SELECT name, file, line, column, end_line, end_column, kind FROM refs
+             WHERE source = 'syntactic'
+               AND (name = ?1
+                OR name LIKE '%.' || ?1
+                OR name LIKE '%::' || ?1
+                OR name LIKE '%\\' || ?1)
+             ORDER BY file, line, column",
+        )?;
+
+        let refs = stmt
+            .query_map(params![name], row_to_reference)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(refs)
+    }
+
+    /// Find references to a name from every source, with their confidence.
+    ///
+    /// Uses the same matching as [`find_references`](Self::find_references) but also
+    /// includes string-literal references, dropping anything below `min_confidence`.
+    ///
+    /// A syntactic reference is scored with [`Confidence::of_match`] against
+    /// the indexed symbols `name` can mean, as callers and spider score theirs:
+    /// `self.save` is a heuristic match for `Order.save` however it's written.
+    pub fn find_references_with_confidence(
+        &self,
+        name: &str,
+        min_confidence: Confidence,
+    ) -> Result<Vec<(Reference, Confidence)>> {
+        let conn = self.conn();
+        let targets = conn
+            .prepare(
+                "SELECT DISTINCT qualified FROM symbols
+                 WHERE qualified = ?1
+                    OR qualified LIKE '%.' || ?1
+                    OR qualified LIKE '%::' || ?1
+                    OR qualified LIKE '%\\' || ?1
+                    OR qualified LIKE '%#' || ?1",
+            )?
+            .query_map(params![name], |row| row.get::<_, String>(0))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let score = |reference: &Reference| {
+            targets
+                .iter()
+                .map(|qualified| Confidence::of_match(&reference.name, qualified))
+                .max()
+                .unwrap_or(Confidence::Heuristic)
+        };
+
+        let mut stmt = conn.prepare(
+            "SELECT name, file, line, column, end_line, end_column, kind, confidence FROM refs
              WHERE name = ?1
                 OR name LIKE '%.' || ?1
                 OR name LIKE '%::' || ?1
-                OR name LIKE '%\\' || ?1",
+                OR name LIKE '%\\' || ?1
+             ORDER BY file, line, column",
         )?;
 
         let refs = stmt
             .query_map(params![name], |row| {
-                let name: String = row.get(0)?;
-                let file: String = row.get(1)?;
-                let line: u32 = row.get(2)?;
-                let column: u32 = row.get(3)?;
-                Ok(Reference {
-                    name,
-                    location: Location::new(PathBuf::from(file), line, column),
-                })
+                let confidence: Option<String> = row.get(7)?;
+                Ok((
+                    row_to_reference(row)?,
+                    confidence.and_then(|c| c.parse().ok()).unwrap_or_default(),
+                ))
             })?
-            .collect::<std::result::Result<Vec<_>, _>>()?;
+            .collect::<std::result::Result<Vec<(Reference, Confidence)>, _>>()?;
+
+        Ok(refs
+            .into_iter()
+            .map(|(reference, stored)| {
+                let confidence = match stored {
+                    Confidence::StringMatch => stored,
+                    _ => score(&reference),
+                };
+                (reference, confidence)
+            })
+            .filter(|(_, confidence)| *confidence >= min_confidence)
+            .collect())
+    }
 
-        Ok(refs)
+    /// Mark syntactic references whose name is an indexed qualified name as exact.
+    ///
+    /// Run after a full index, once every file's symbols are in place.
+    pub fn promote_exact_references(&self) -> Result<usize> {
+        let count = self.conn().execute(
+            "UPDATE refs SET confidence = 'exact'
+             WHERE source = 'syntactic' AND confidence != 'exact'
+               AND name IN (SELECT qualified FROM symbols)",
+            [],
+        )?;
+        Ok(count)
     }
 
     /// Get all references in a file.
     pub fn references_in_file(&self, file: &Path) -> Result<Vec<Reference>> {
         let file_str = file.to_string_lossy();
         let conn = self.conn();
-        let mut stmt = conn.prepare("SELECT name, file, line, column FROM refs WHERE file = ?1")?;
+        let mut stmt = conn.prepare(
+            "SELECT name, file, line, column, end_line, end_column, kind FROM refs
+             WHERE file = ?1 AND source = 'syntactic'
+             ORDER BY line, column",
+        )?;
+
+        let refs = stmt
+            .query_map(params![file_str.as_ref()], row_to_reference)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(refs)
+    }
+
+    /// Insert references found in string literals (see [`crate::string_refs`]).
+    ///
+    /// These are stored separately from syntactic references so that
+    /// [`find_references`](Self::find_references) and callers/spider stay precise.
+    pub fn insert_string_references(&self, refs: &[(&Path, &Reference)]) -> Result<()> {
+        let conn = self.conn();
+        let tx = conn.unchecked_transaction()?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT INTO refs (name, file, line, column, end_line, end_column, source, confidence, kind)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, 'string', 'string-match', ?7)",
+            )?;
+
+            for (file, reference) in refs {
+                let file_str = file.to_string_lossy();
+                stmt.execute(params![
+                    reference.name,
+                    file_str.as_ref(),
+                    reference.location.line,
+                    reference.location.column,
+                    reference.location.end_line,
+                    reference.location.end_column,
+                    reference.kind.as_str(),
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Find string-literal references to a name, using the same matching as
+    /// [`find_references`](Self::find_references).
+    pub fn find_string_references(&self, name: &str) -> Result<Vec<Reference>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT name, file, line, column, end_line, end_column, kind FROM refs
+             WHERE source = 'string'
+               AND (name = ?1
+                OR name LIKE '%.' || ?1
+                OR name LIKE '%::' || ?1
+                OR name LIKE '%\\' || ?1)
+             ORDER BY file, line, column",
+        )?;
 
         let refs = stmt
-            .query_map(params![file_str.as_ref()], |row| {
-                let name: String = row.get(0)?;
-                let file: String = row.get(1)?;
-                let line: u32 = row.get(2)?;
-                let column: u32 = row.get(3)?;
-                Ok(Reference {
-                    name,
-                    location: Location::new(PathBuf::from(file), line, column),
-                })
+            .query_map(params![name], |row| {
+                let mut reference = row_to_reference(row)?;
+                // Rows from before spans were recorded: the match is the name
+                let location = &mut reference.location;
+                if location.end_line == location.line && location.end_column == location.column {
+                    location.end_column += reference.name.len() as u32;
+                }
+                Ok(reference)
             })?
             .collect::<std::result::Result<Vec<_>, _>>()?;
 
         Ok(refs)
     }
 
+    /// Delete all string-literal references (they are rebuilt as a whole).
+    pub fn clear_string_references(&self) -> Result<usize> {
+        let count = self
+            .conn()
+            .execute("DELETE FROM refs WHERE source = 'string'", [])?;
+        Ok(count)
+    }
+
     /// Delete all references in a file.
     pub fn delete_references_in_file(&self, file: &Path) -> Result<usize> {
-        let file_str = file.to_string_lossy();
-        let count = self.conn().execute(
-            "DELETE FROM refs WHERE file = ?1",
-            params![file_str.as_ref()],
-        )?;
-        Ok(count)
+        self.with_transaction(|tx| {
+            let names = referenced_names_in_file(tx, file)?;
+            let count = tx.execute(
+                "DELETE FROM refs WHERE file = ?1",
+                params![file.to_string_lossy()],
+            )?;
+            refresh_ref_counts(tx, Some(&names))?;
+            Ok(count)
+        })
     }
 
     // =========================================================================
@@ -940,26 +2714,18 @@ impl SqliteIndex {
 
         let conn = self.conn();
 
-        // Query symbols with their reference counts
-        // We match refs to symbols by:
-        // - Exact name match (e.g., "User" matches "User")
-        // - Qualified name ending (e.g., "Module.User" refs match "User" symbol)
+        // Reference counts are maintained per symbol as files are indexed
+        // (see `refresh_ref_counts`), matching refs by exact name or
+        // qualified name; overloads share a qualified name and its counts.
         let mut stmt = conn.prepare(
-            // NOTE: Using exact name match only for performance.
-            // LIKE patterns ('%.' || s.name) are too slow with millions of refs.
-            // This may under-count qualified references but is 100x faster.
             r#"
             SELECT
                 s.name, s.qualified, s.kind, s.file, s.line, s.column,
                 s.end_line, s.end_column, s.visibility, s.language,
                 s.parent, s.mixins, s.attributes, s.implements, s.doc, s.signature,
-                COUNT(DISTINCT r.file) as file_diversity,
-                COUNT(r.id) as total_refs
+                MAX(s.ref_files) as file_diversity,
+                MAX(s.ref_count) as total_refs
             FROM symbols s
-            LEFT JOIN refs r ON (
-                r.name = s.name
-                OR r.name = s.qualified
-            )
             GROUP BY s.qualified
             ORDER BY
                 file_diversity DESC,
@@ -980,7 +2746,8 @@ impl SqliteIndex {
                     WHEN 'internal' THEN 2
                     ELSE 1
                 END DESC,
-                total_refs DESC
+                total_refs DESC,
+                s.file, s.line, s.qualified
             LIMIT ?1
             "#,
         )?;
@@ -1015,24 +2782,20 @@ impl SqliteIndex {
 
     /// Count references to a specific symbol.
     ///
-    /// Returns (file_diversity, total_refs) tuple.
+    /// Returns (file_diversity, total_refs) tuple, read from the counts
+    /// maintained as files are indexed; both are 0 for an unknown symbol.
     pub fn count_symbol_references(&self, qualified: &str, name: &str) -> Result<(usize, usize)> {
-        let conn = self.conn();
-        let mut stmt = conn.prepare(
-            r#"
-            SELECT
-                COUNT(DISTINCT file) as file_diversity,
-                COUNT(*) as total_refs
-            FROM refs
-            WHERE name = ?1 OR name = ?2
-            "#,
+        let counts: Option<(i64, i64)> = self.conn().query_row(
+            "SELECT MAX(ref_files), MAX(ref_count) FROM symbols
+                 WHERE qualified = ?1 AND name = ?2",
+            params![qualified, name],
+            |row| {
+                Ok(row
+                    .get::<_, Option<i64>>(0)?
+                    .zip(row.get::<_, Option<i64>>(1)?))
+            },
         )?;
-
-        let (file_diversity, total_refs): (i64, i64) = stmt
-            .query_row(params![name, qualified], |row| {
-                Ok((row.get(0)?, row.get(1)?))
-            })?;
-
+        let (file_diversity, total_refs) = counts.unwrap_or_default();
         Ok((file_diversity as usize, total_refs as usize))
     }
 
@@ -1062,10 +2825,9 @@ impl SqliteIndex {
                     s.name, s.qualified, s.kind, s.file, s.line, s.column,
                     s.end_line, s.end_column, s.visibility, s.language,
                     s.parent, s.mixins, s.attributes, s.implements, s.doc, s.signature,
-                    COUNT(DISTINCT r.file) as file_diversity,
-                    COUNT(r.id) as total_refs
+                    MAX(s.ref_files) as file_diversity,
+                    MAX(s.ref_count) as total_refs
                 FROM symbols s
-                LEFT JOIN refs r ON (r.name = s.name OR r.name = s.qualified)
                 GROUP BY s.qualified
             ),
             per_file_ranked AS (
@@ -1082,7 +2844,8 @@ impl SqliteIndex {
                                 WHEN 'union' THEN 3
                                 ELSE 1
                             END DESC,
-                            total_refs DESC
+                            total_refs DESC,
+                            line, qualified
                     ) as rank_in_file
                 FROM ranked_refs
             )
@@ -1151,8 +2914,8 @@ impl SqliteIndex {
     pub fn get_all_symbols_ordered(&self) -> Result<Vec<Symbol>> {
         let conn = self.conn();
         let mut stmt = conn.prepare(&format!(
-            "SELECT {} FROM symbols ORDER BY file, line",
-            SYMBOL_COLUMNS
+            "SELECT {} FROM symbols ORDER BY {}",
+            SYMBOL_COLUMNS, SYMBOL_ORDER
         ))?;
 
         let symbols = stmt
@@ -1176,19 +2939,11 @@ impl SqliteIndex {
         Ok(self.conn().last_insert_rowid())
     }
 
-    /// Insert multiple open statements in a transaction for efficiency.
-    pub fn insert_opens(&self, opens: &[(&Path, &str, u32)]) -> Result<()> {
-        let conn = self.conn();
-        let tx = conn.unchecked_transaction()?;
-        {
-            let mut stmt =
-                tx.prepare("INSERT INTO opens (file, module_path, line) VALUES (?1, ?2, ?3)")?;
-
-            for (file, module_path, line) in opens {
-                let file_str = file.to_string_lossy();
-                stmt.execute(params![file_str.as_ref(), *module_path, *line])?;
-            }
-        }
+    /// Insert multiple open statements in a transaction for efficiency.
+    pub fn insert_opens(&self, opens: &[(&Path, &str, u32)]) -> Result<()> {
+        let conn = self.conn();
+        let tx = conn.unchecked_transaction()?;
+        insert_open_rows(&tx, opens)?;
         tx.commit()?;
         Ok(())
     }
@@ -1197,8 +2952,9 @@ impl SqliteIndex {
     pub fn opens_for_file(&self, file: &Path) -> Result<Vec<String>> {
         let file_str = file.to_string_lossy();
         let conn = self.conn();
-        let mut stmt =
-            conn.prepare("SELECT module_path FROM opens WHERE file = ?1 ORDER BY line")?;
+        let mut stmt = conn.prepare(
+            "SELECT module_path FROM opens WHERE file = ?1 AND alias IS NULL ORDER BY line",
+        )?;
 
         let opens = stmt
             .query_map(params![file_str.as_ref()], |row| row.get(0))?
@@ -1207,7 +2963,126 @@ impl SqliteIndex {
         Ok(opens)
     }
 
-    /// Delete all opens in a file.
+    /// Insert module aliases as (file, alias, line).
+    pub fn insert_aliases(&self, aliases: &[(&Path, &ModuleAlias, u32)]) -> Result<()> {
+        let conn = self.conn();
+        let tx = conn.unchecked_transaction()?;
+        insert_alias_rows(&tx, aliases)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Get all module aliases for a file.
+    pub fn aliases_for_file(&self, file: &Path) -> Result<Vec<ModuleAlias>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT alias, module_path FROM opens WHERE file = ?1 AND alias IS NOT NULL
+             ORDER BY line",
+        )?;
+        let aliases = stmt
+            .query_map(params![file.to_string_lossy()], |row| {
+                Ok(ModuleAlias {
+                    alias: row.get(0)?,
+                    target: row.get(1)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(aliases)
+    }
+
+    /// Files that open `module`, by file.
+    pub fn files_opening(&self, module: &str) -> Result<Vec<OpenSite>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT file, module_path, line FROM opens WHERE module_path = ?1 AND alias IS NULL
+             ORDER BY file, line",
+        )?;
+        let sites = stmt
+            .query_map(params![module], row_to_open_site)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(sites)
+    }
+
+    /// Files with at least one open, sorted.
+    pub fn files_with_opens(&self) -> Result<Vec<PathBuf>> {
+        let conn = self.conn();
+        let mut stmt =
+            conn.prepare("SELECT DISTINCT file FROM opens WHERE alias IS NULL ORDER BY file")?;
+        let files = stmt
+            .query_map([], |row| Ok(PathBuf::from(row.get::<_, String>(0)?)))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(files)
+    }
+
+    /// Every module some file opens or aliases, sorted.
+    pub fn opened_modules(&self) -> Result<Vec<String>> {
+        let conn = self.conn();
+        let mut stmt =
+            conn.prepare("SELECT DISTINCT module_path FROM opens ORDER BY module_path")?;
+        let modules = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(modules)
+    }
+
+    /// The opens of `file`, in order, each with whether the file uses it.
+    ///
+    /// A reference uses an open module when the module's name followed by
+    /// the reference's (`MyApp.Domain` + `createUser`) is an indexed symbol,
+    /// or when the reference is already qualified with it.
+    pub fn open_usage(&self, file: &Path) -> Result<Vec<OpenUsage>> {
+        let references = self.references_in_file(file)?;
+        let conn = self.conn();
+        let mut sites = conn.prepare(
+            "SELECT file, module_path, line FROM opens WHERE file = ?1 AND alias IS NULL
+                 ORDER BY line",
+        )?;
+        let sites = sites
+            .query_map(params![file.to_string_lossy()], row_to_open_site)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let mut defines =
+            conn.prepare_cached("SELECT EXISTS (SELECT 1 FROM symbols WHERE qualified = ?1)")?;
+        let mut has_members = conn.prepare_cached(
+            "SELECT EXISTS (SELECT 1 FROM symbols WHERE qualified LIKE ?1 ESCAPE '\\')",
+        )?;
+        let mut usage = Vec::with_capacity(sites.len());
+        for site in sites {
+            let mut indexed = false;
+            for separator in MODULE_SEPARATORS {
+                let prefix = format!("{}%", escape_like(&format!("{}{}", site.module, separator)));
+                indexed |= has_members.query_row(params![prefix], |row| row.get::<_, bool>(0))?;
+            }
+            let mut uses = 0;
+            if indexed {
+                for reference in &references {
+                    let mut used = false;
+                    for separator in MODULE_SEPARATORS {
+                        let prefix = format!("{}{}", site.module, separator);
+                        let qualified = if reference.name.starts_with(&prefix) {
+                            reference.name.clone()
+                        } else {
+                            format!("{}{}", prefix, reference.name)
+                        };
+                        if defines.query_row(params![qualified], |row| row.get::<_, bool>(0))? {
+                            used = true;
+                            break;
+                        }
+                    }
+                    uses += usize::from(used);
+                }
+            }
+            let status = match (indexed, uses) {
+                (false, _) => OpenStatus::External,
+                (true, 0) => OpenStatus::Unused,
+                (true, _) => OpenStatus::Used,
+            };
+            usage.push(OpenUsage { site, status, uses });
+        }
+        Ok(usage)
+    }
+
+    /// Delete all opens (and module aliases) in a file.
     pub fn delete_opens_in_file(&self, file: &Path) -> Result<usize> {
         let file_str = file.to_string_lossy();
         let count = self.conn().execute(
@@ -1261,7 +3136,8 @@ impl SqliteIndex {
     pub fn get_members(&self, type_name: &str) -> Result<Vec<TypeMember>> {
         let conn = self.conn();
         let mut stmt = conn.prepare(
-            "SELECT type_name, member_name, member_type, kind FROM members WHERE type_name = ?1",
+            "SELECT type_name, member_name, member_type, kind FROM members
+             WHERE type_name = ?1 ORDER BY member_name",
         )?;
 
         let members = stmt
@@ -1318,6 +3194,16 @@ impl SqliteIndex {
         let tx = conn.unchecked_transaction()?;
         let file_str = file.to_string_lossy();
 
+        // Counts change for whatever the old and new references name, and
+        // the new symbols start from the references already there
+        let mut touched = referenced_names_in_file(&tx, file)?;
+        touched.extend(references.iter().map(|r| r.name.clone()));
+        touched.extend(
+            symbols
+                .iter()
+                .flat_map(|s| [s.name.clone(), s.qualified.clone()]),
+        );
+
         // Clear existing data
         tx.execute(
             "DELETE FROM symbols WHERE file = ?1",
@@ -1335,8 +3221,8 @@ impl SqliteIndex {
         // Insert symbols
         {
             let mut stmt = tx.prepare(
-                "INSERT INTO symbols (name, qualified, kind, file, line, column, end_line, end_column, visibility, language, source, parent, mixins, attributes, implements, doc, signature)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, 'syntactic', ?11, ?12, ?13, ?14, ?15, ?16)",
+                "INSERT INTO symbols (name, qualified, kind, file, line, column, end_line, end_column, visibility, language, source, parent, mixins, attributes, implements, doc, signature, is_test, symbol_id, guid)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, 'syntactic', ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19)",
             )?;
             for symbol in symbols {
                 stmt.execute(params![
@@ -1365,22 +3251,43 @@ impl SqliteIndex {
                         .map(|v| serde_json::to_string(v).unwrap_or_default()),
                     symbol.doc,
                     symbol.signature,
+                    is_test_symbol(symbol),
+                    symbol_id(&symbol.qualified, symbol.kind),
+                    symbol_guid(
+                        &symbol.language,
+                        &symbol.qualified,
+                        symbol.signature.as_deref()
+                    ),
                 ])?;
             }
         }
 
+        apply_visibility_rules(&tx, Some(file))?;
+
         // Insert references
         {
-            let mut stmt =
-                tx.prepare("INSERT INTO refs (name, file, line, column) VALUES (?1, ?2, ?3, ?4)")?;
+            let mut stmt = tx.prepare(
+                "INSERT INTO refs (name, file, line, column, end_line, end_column, confidence, kind)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            )?;
             for reference in references {
                 stmt.execute(params![
                     reference.name,
                     file_str.as_ref(),
                     reference.location.line,
                     reference.location.column,
+                    reference.location.end_line,
+                    reference.location.end_column,
+                    Confidence::Heuristic.as_str(),
+                    reference.kind.as_str(),
                 ])?;
             }
+            tx.execute(
+                "UPDATE refs SET confidence = 'exact'
+                 WHERE file = ?1 AND source = 'syntactic'
+                   AND name IN (SELECT qualified FROM symbols)",
+                params![file_str.as_ref()],
+            )?;
         }
 
         // Insert opens
@@ -1392,6 +3299,7 @@ impl SqliteIndex {
             }
         }
 
+        refresh_ref_counts(&tx, Some(&touched))?;
         tx.commit()?;
         Ok(())
     }
@@ -1401,6 +3309,10 @@ impl SqliteIndex {
         self.delete_symbols_in_file(file)?;
         self.delete_references_in_file(file)?;
         self.delete_opens_in_file(file)?;
+        self.conn().execute(
+            "DELETE FROM file_metrics WHERE file = ?1",
+            params![file.to_string_lossy()],
+        )?;
         Ok(())
     }
 
@@ -1417,6 +3329,26 @@ impl SqliteIndex {
         Ok(result)
     }
 
+    /// Start loading many files' data at once, as a full index build does.
+    ///
+    /// Everything written through the returned [`BulkLoad`] goes into one
+    /// transaction with reused prepared statements, and `synchronous` is off
+    /// until it is finished: an OS crash mid-load can lose the index, which
+    /// is then rebuilt. Other calls on this index block until the load is
+    /// finished or dropped (dropping it rolls back).
+    pub fn bulk_load(&self) -> Result<BulkLoad<'_>> {
+        let conn = self.conn();
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             PRAGMA synchronous = OFF;
+             BEGIN;",
+        )?;
+        Ok(BulkLoad {
+            conn,
+            finished: false,
+        })
+    }
+
     // =========================================================================
     // File Mtime Tracking (for incremental refresh)
     // =========================================================================
@@ -1455,6 +3387,66 @@ impl SqliteIndex {
         Ok(())
     }
 
+    // =========================================================================
+    // File Metrics
+    // =========================================================================
+
+    /// Record what a file's syntax tree measures.
+    pub fn set_file_metrics(&self, file: &Path, metrics: &FileMetrics) -> Result<()> {
+        insert_metrics_row(&self.conn(), file, metrics)
+    }
+
+    /// Every measured file with its metrics and symbol count, by path.
+    pub fn file_stats(&self) -> Result<Vec<FileStats>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT m.file, m.complexity, m.max_nesting,
+                    (SELECT COUNT(*) FROM symbols s WHERE s.file = m.file)
+             FROM file_metrics m ORDER BY m.file",
+        )?;
+        let stats = stmt
+            .query_map([], |row| {
+                Ok(FileStats {
+                    file: PathBuf::from(row.get::<_, String>(0)?),
+                    metrics: FileMetrics {
+                        complexity: row.get(1)?,
+                        max_nesting: row.get(2)?,
+                    },
+                    symbols: row.get::<_, i64>(3)? as usize,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(stats)
+    }
+
+    /// Pairs of (referencing file, referenced file) for references that
+    /// name a symbol defined in exactly one other file, by name or
+    /// qualified name. String-literal references don't count.
+    pub fn file_dependencies(&self) -> Result<Vec<(PathBuf, PathBuf)>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "WITH defined AS (
+                 SELECT name AS key, MIN(file) AS file FROM symbols
+                 GROUP BY name HAVING COUNT(DISTINCT file) = 1
+                 UNION
+                 SELECT qualified, MIN(file) FROM symbols
+                 GROUP BY qualified HAVING COUNT(DISTINCT file) = 1
+             )
+             SELECT DISTINCT r.file, d.file FROM refs r JOIN defined d ON d.key = r.name
+             WHERE r.file != d.file AND COALESCE(r.source, 'syntactic') != 'string'
+             ORDER BY r.file, d.file",
+        )?;
+        let pairs = stmt
+            .query_map([], |row| {
+                Ok((
+                    PathBuf::from(row.get::<_, String>(0)?),
+                    PathBuf::from(row.get::<_, String>(1)?),
+                ))
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(pairs)
+    }
+
     /// Clear all mtime records.
     pub fn clear_all_mtimes(&self) -> Result<usize> {
         let count = self.conn().execute("DELETE FROM file_mtimes", [])?;
@@ -1464,7 +3456,7 @@ impl SqliteIndex {
     /// Get all tracked file paths.
     pub fn get_tracked_files(&self) -> Result<Vec<PathBuf>> {
         let conn = self.conn();
-        let mut stmt = conn.prepare("SELECT path FROM file_mtimes")?;
+        let mut stmt = conn.prepare("SELECT path FROM file_mtimes ORDER BY path")?;
         let files = stmt
             .query_map([], |row| {
                 let path: String = row.get(0)?;
@@ -1532,6 +3524,72 @@ impl SqliteIndex {
     }
 }
 
+/// One transaction loading many files' data, from [`SqliteIndex::bulk_load`].
+pub struct BulkLoad<'a> {
+    conn: MutexGuard<'a, Connection>,
+    finished: bool,
+}
+
+impl BulkLoad<'_> {
+    /// Insert symbols.
+    pub fn insert_symbols(&mut self, symbols: &[Symbol]) -> Result<()> {
+        insert_symbol_rows(&self.conn, symbols)
+    }
+
+    /// Insert references.
+    pub fn insert_references(&mut self, refs: &[(&Path, &Reference)]) -> Result<()> {
+        insert_reference_rows(&self.conn, refs)
+    }
+
+    /// Insert opens.
+    pub fn insert_opens(&mut self, opens: &[(&Path, &str, u32)]) -> Result<()> {
+        insert_open_rows(&self.conn, opens)
+    }
+
+    /// Insert module aliases.
+    pub fn insert_aliases(&mut self, aliases: &[(&Path, &ModuleAlias, u32)]) -> Result<()> {
+        insert_alias_rows(&self.conn, aliases)
+    }
+
+    /// Record what a file's syntax tree measures.
+    pub fn set_file_metrics(&mut self, file: &Path, metrics: &FileMetrics) -> Result<()> {
+        insert_metrics_row(&self.conn, file, metrics)
+    }
+
+    /// Record the modification time of a file.
+    pub fn set_file_mtime(&mut self, file: &Path, mtime: u64) -> Result<()> {
+        self.conn
+            .prepare_cached("INSERT OR REPLACE INTO file_mtimes (path, mtime) VALUES (?1, ?2)")?
+            .execute(params![file.to_string_lossy(), mtime as i64])?;
+        Ok(())
+    }
+
+    /// Commit everything loaded and make writes durable again.
+    pub fn finish(mut self) -> Result<()> {
+        apply_visibility_rules(&self.conn, None)?;
+        refresh_ref_counts(&self.conn, None)?;
+        self.finished = true;
+        self.conn.execute_batch(
+            "COMMIT;
+             PRAGMA synchronous = NORMAL;",
+        )?;
+        Ok(())
+    }
+}
+
+impl Drop for BulkLoad<'_> {
+    fn drop(&mut self) {
+        if !self.finished {
+            if let Err(e) = self.conn.execute_batch(
+                "ROLLBACK;
+                 PRAGMA synchronous = NORMAL;",
+            ) {
+                tracing::warn!("Failed to roll back bulk load: {}", e);
+            }
+        }
+    }
+}
+
 // ============================================================================
 // Schema SQL
 // ============================================================================
@@ -1543,7 +3601,9 @@ CREATE TABLE IF NOT EXISTS metadata (
     value TEXT NOT NULL
 );
 
--- Main symbols table (syntactic + type info merged)
+-- Main symbols table (syntactic + type info merged). ref_count and ref_files
+-- count syntactic references to the symbol and the files they're in, kept
+-- current by every write (see refresh_ref_counts)
 CREATE TABLE IF NOT EXISTS symbols (
     id INTEGER PRIMARY KEY,
     name TEXT NOT NULL,
@@ -1563,7 +3623,13 @@ CREATE TABLE IF NOT EXISTS symbols (
     attributes TEXT,
     implements TEXT,
     doc TEXT,
-    signature TEXT
+    signature TEXT,
+    is_test INTEGER NOT NULL DEFAULT 0,
+    project TEXT,
+    symbol_id INTEGER,
+    guid TEXT,
+    ref_count INTEGER NOT NULL DEFAULT 0,
+    ref_files INTEGER NOT NULL DEFAULT 0
 );
 
 CREATE INDEX IF NOT EXISTS idx_symbols_qualified ON symbols(qualified);
@@ -1571,6 +3637,10 @@ CREATE INDEX IF NOT EXISTS idx_symbols_name ON symbols(name);
 CREATE INDEX IF NOT EXISTS idx_symbols_file ON symbols(file);
 CREATE INDEX IF NOT EXISTS idx_symbols_kind ON symbols(kind);
 CREATE INDEX IF NOT EXISTS idx_symbols_parent ON symbols(parent);
+CREATE INDEX IF NOT EXISTS idx_symbols_is_test ON symbols(is_test) WHERE is_test = 1;
+CREATE INDEX IF NOT EXISTS idx_symbols_symbol_id ON symbols(symbol_id);
+CREATE INDEX IF NOT EXISTS idx_symbols_guid ON symbols(guid);
+CREATE INDEX IF NOT EXISTS idx_symbols_ref_count ON symbols(ref_count);
 
 -- FTS5 virtual table for fast full-text search on symbol names
 -- Uses content= to make it an "external content" table linked to symbols
@@ -1591,7 +3661,7 @@ CREATE TRIGGER IF NOT EXISTS symbols_ad AFTER DELETE ON symbols BEGIN
     INSERT INTO symbols_fts(symbols_fts, rowid, name, qualified) VALUES('delete', old.id, old.name, old.qualified);
 END;
 
-CREATE TRIGGER IF NOT EXISTS symbols_au AFTER UPDATE ON symbols BEGIN
+CREATE TRIGGER IF NOT EXISTS symbols_au AFTER UPDATE OF name, qualified ON symbols BEGIN
     INSERT INTO symbols_fts(symbols_fts, rowid, name, qualified) VALUES('delete', old.id, old.name, old.qualified);
     INSERT INTO symbols_fts(rowid, name, qualified) VALUES (new.id, new.name, new.qualified);
 END;
@@ -1616,18 +3686,26 @@ CREATE TABLE IF NOT EXISTS refs (
     name TEXT NOT NULL,
     file TEXT NOT NULL,
     line INTEGER NOT NULL,
-    column INTEGER NOT NULL
+    column INTEGER NOT NULL,
+    end_line INTEGER,
+    end_column INTEGER,
+    source TEXT DEFAULT 'syntactic',
+    confidence TEXT DEFAULT 'heuristic',
+    project TEXT,
+    kind TEXT
 );
 
 CREATE INDEX IF NOT EXISTS idx_refs_name ON refs(name);
 CREATE INDEX IF NOT EXISTS idx_refs_file ON refs(file);
 
--- Open statements for resolution context
+-- Open statements for resolution context. Rows with an alias are module
+-- aliases (`module M = ...`, `using M = ...`) naming module_path instead
 CREATE TABLE IF NOT EXISTS opens (
     id INTEGER PRIMARY KEY,
     file TEXT NOT NULL,
     module_path TEXT NOT NULL,
-    line INTEGER NOT NULL
+    line INTEGER NOT NULL,
+    alias TEXT
 );
 
 CREATE INDEX IF NOT EXISTS idx_opens_file ON opens(file);
@@ -1639,10 +3717,454 @@ CREATE TABLE IF NOT EXISTS file_mtimes (
 );
 "#;
 
+/// Multi-root workspaces: each symbol and reference is tagged with the
+/// project whose root (stored with a trailing separator) is the longest
+/// prefix of its file. Kept apart from `SCHEMA_SQL` so migrations can reuse it.
+const PROJECTS_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS projects (
+    name TEXT PRIMARY KEY,
+    root TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_symbols_project ON symbols(project);
+CREATE INDEX IF NOT EXISTS idx_refs_project ON refs(project);
+
+CREATE TRIGGER IF NOT EXISTS symbols_project AFTER INSERT ON symbols
+WHEN new.project IS NULL AND EXISTS (SELECT 1 FROM projects) BEGIN
+    UPDATE symbols SET project = (
+        SELECT name FROM projects WHERE substr(new.file, 1, length(root)) = root
+        ORDER BY length(root) DESC LIMIT 1
+    ) WHERE id = new.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS refs_project AFTER INSERT ON refs
+WHEN new.project IS NULL AND EXISTS (SELECT 1 FROM projects) BEGIN
+    UPDATE refs SET project = (
+        SELECT name FROM projects WHERE substr(new.file, 1, length(root)) = root
+        ORDER BY length(root) DESC LIMIT 1
+    ) WHERE id = new.id;
+END;
+"#;
+
+/// Per-file complexity metrics (see [`crate::metrics`]).
+const METRICS_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS file_metrics (
+    file TEXT PRIMARY KEY,
+    complexity INTEGER NOT NULL,
+    max_nesting INTEGER NOT NULL
+);
+"#;
+
+/// Rename aliases (see [`Alias`]). Survive `rkt index --rebuild` by being
+/// copied over by the CLI; not scoped by project.
+const ALIASES_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS aliases (
+    old TEXT PRIMARY KEY,
+    new TEXT NOT NULL
+);
+"#;
+
+/// Notes, watchlists and layer rules (see [`Note`], [`WatchedSymbol`] and
+/// [`LayerRule`]). Recorded by hand, so they survive `rkt index --rebuild` by
+/// being copied over by the CLI, like aliases.
+const ANNOTATIONS_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS notes (
+    id INTEGER PRIMARY KEY,
+    guid TEXT NOT NULL,
+    qualified TEXT NOT NULL,
+    text TEXT NOT NULL,
+    created_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_notes_guid ON notes(guid);
+CREATE INDEX IF NOT EXISTS idx_notes_qualified ON notes(qualified);
+
+CREATE TABLE IF NOT EXISTS watchlists (
+    list TEXT NOT NULL,
+    guid TEXT NOT NULL,
+    qualified TEXT NOT NULL,
+    PRIMARY KEY (list, guid)
+);
+
+CREATE TABLE IF NOT EXISTS layer_rules (
+    from_path TEXT NOT NULL,
+    to_path TEXT NOT NULL,
+    PRIMARY KEY (from_path, to_path)
+);
+"#;
+
+/// Symbol history (see [`SymbolMove`]). Deleting a symbol leaves a row with
+/// no destination; inserting one either cancels the row of the symbol it
+/// replaces in the same file (the file was only reindexed) or completes the
+/// newest row for a near-identical symbol that vanished from elsewhere.
+/// Trigrams of symbol names for fuzzy search, kept in sync like `symbols_fts`.
+const TRIGRAM_SQL: &str = r#"
+CREATE VIRTUAL TABLE IF NOT EXISTS symbols_trigram USING fts5(
+    name,
+    content='symbols',
+    content_rowid='id',
+    tokenize='trigram'
+);
+
+CREATE TRIGGER IF NOT EXISTS symbols_trigram_ai AFTER INSERT ON symbols BEGIN
+    INSERT INTO symbols_trigram(rowid, name) VALUES (new.id, new.name);
+END;
+
+CREATE TRIGGER IF NOT EXISTS symbols_trigram_ad AFTER DELETE ON symbols BEGIN
+    INSERT INTO symbols_trigram(symbols_trigram, rowid, name) VALUES('delete', old.id, old.name);
+END;
+
+CREATE TRIGGER IF NOT EXISTS symbols_trigram_au AFTER UPDATE OF name ON symbols BEGIN
+    INSERT INTO symbols_trigram(symbols_trigram, rowid, name) VALUES('delete', old.id, old.name);
+    INSERT INTO symbols_trigram(rowid, name) VALUES (new.id, new.name);
+END;
+"#;
+
+const HISTORY_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS symbol_history (
+    id INTEGER PRIMARY KEY,
+    symbol_id INTEGER NOT NULL,
+    kind TEXT NOT NULL,
+    name TEXT NOT NULL,
+    signature TEXT,
+    old_qualified TEXT NOT NULL,
+    old_file TEXT NOT NULL,
+    old_line INTEGER NOT NULL,
+    new_qualified TEXT,
+    new_file TEXT,
+    new_line INTEGER,
+    recorded_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_symbol_history_old ON symbol_history(old_qualified);
+CREATE INDEX IF NOT EXISTS idx_symbol_history_pending_id
+    ON symbol_history(symbol_id, old_file) WHERE new_qualified IS NULL;
+CREATE INDEX IF NOT EXISTS idx_symbol_history_pending_name
+    ON symbol_history(name, kind) WHERE new_qualified IS NULL;
+
+CREATE TRIGGER IF NOT EXISTS symbols_history_ad AFTER DELETE ON symbols
+WHEN old.symbol_id IS NOT NULL BEGIN
+    INSERT INTO symbol_history (symbol_id, kind, name, signature, old_qualified, old_file, old_line, recorded_at)
+    VALUES (old.symbol_id, old.kind, old.name, old.signature, old.qualified, old.file, old.line, unixepoch());
+END;
+
+CREATE TRIGGER IF NOT EXISTS symbols_history_ai AFTER INSERT ON symbols
+WHEN new.symbol_id IS NOT NULL BEGIN
+    UPDATE symbol_history
+    SET new_qualified = new.qualified, new_file = new.file, new_line = new.line, recorded_at = unixepoch()
+    WHERE id = (
+        SELECT id FROM symbol_history
+        WHERE new_qualified IS NULL AND name = new.name AND kind = new.kind
+          AND (signature IS NULL OR new.signature IS NULL OR signature = new.signature)
+          AND NOT (symbol_id = new.symbol_id AND old_file = new.file)
+        ORDER BY id DESC LIMIT 1
+    )
+    AND NOT EXISTS (
+        SELECT 1 FROM symbol_history
+        WHERE new_qualified IS NULL AND symbol_id = new.symbol_id AND old_file = new.file
+    );
+    DELETE FROM symbol_history WHERE id = (
+        SELECT id FROM symbol_history
+        WHERE new_qualified IS NULL AND symbol_id = new.symbol_id AND old_file = new.file
+        ORDER BY id DESC LIMIT 1
+    );
+END;
+"#;
+
+/// Columns selected for [`row_to_move`].
+const HISTORY_COLUMNS: &str = "symbol_id, kind, name, old_qualified, old_file, old_line, new_qualified, new_file, new_line, recorded_at";
+
 // ============================================================================
 // Helper Functions
 // ============================================================================
 
+/// Recount `ref_count` and `ref_files` for the symbols whose name or
+/// qualified name is in `names`, or for every symbol when it's `None`.
+///
+/// Writers call this with the names their change added or removed
+/// references to (and the names of symbols they inserted), inside the same
+/// transaction, so the counts are never stale and never need a full scan.
+/// Override the visibility of the symbols in `file` (every file if `None`)
+/// by the recorded `[[visibility]]` rules.
+pub(crate) fn apply_visibility_rules(conn: &Connection, file: Option<&Path>) -> Result<()> {
+    let metadata = |key: &str| -> Result<Option<String>> {
+        Ok(conn
+            .query_row(
+                "SELECT value FROM metadata WHERE key = ?1",
+                params![key],
+                |row| row.get(0),
+            )
+            .optional()?)
+    };
+    let rules: Vec<VisibilityRule> = match metadata(VISIBILITY_RULES)? {
+        Some(json) => serde_json::from_str(&json)?,
+        None => return Ok(()),
+    };
+    if rules.is_empty() {
+        return Ok(());
+    }
+    let root = metadata("workspace_root")?;
+
+    // Last to first, so the first rule matching a symbol has the last word
+    for rule in rules.iter().rev() {
+        let mut query = String::from("UPDATE symbols SET visibility = ?1 WHERE 1 = 1");
+        let mut values: Vec<rusqlite::types::Value> =
+            vec![visibility_to_str(rule.visibility).to_string().into()];
+        let mut bind = |value: String| {
+            values.push(value.into());
+            values.len()
+        };
+        if let Some(file) = file {
+            query += &format!(" AND file = ?{}", bind(file.to_string_lossy().into_owned()));
+        }
+        if let Some(language) = &rule.language {
+            query += &format!(" AND language = ?{}", bind(language.clone()));
+        }
+        if let Some(name) = &rule.name {
+            query += &format!(" AND name GLOB ?{}", bind(escape_glob(name)));
+        }
+        if let Some(path) = &rule.path {
+            let path = path.trim_start_matches("./");
+            let pattern = match &root {
+                Some(root) => format!("{}/{}", root.trim_end_matches(['/', '\\']), path),
+                None => format!("*/{}", path),
+            };
+            query += &format!(" AND file GLOB ?{}", bind(escape_glob(&pattern)));
+        }
+        conn.execute(&query, rusqlite::params_from_iter(values))?;
+    }
+    Ok(())
+}
+
+pub(crate) fn refresh_ref_counts(conn: &Connection, names: Option<&HashSet<String>>) -> Result<()> {
+    const REFS: &str = "FROM refs r WHERE r.source = 'syntactic'
+                        AND (r.name = symbols.name OR r.name = symbols.qualified)";
+    let update = format!(
+        "UPDATE symbols SET ref_count = (SELECT COUNT(*) {REFS}),
+                            ref_files = (SELECT COUNT(DISTINCT r.file) {REFS})"
+    );
+    match names {
+        None => {
+            conn.execute(&update, [])?;
+        }
+        Some(names) => {
+            let mut stmt =
+                conn.prepare_cached(&format!("{update} WHERE name = ?1 OR qualified = ?1"))?;
+            for name in names {
+                stmt.execute(params![name])?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Distinct names referenced from `file`, read before its references are
+/// replaced so [`refresh_ref_counts`] can lower their counts.
+pub(crate) fn referenced_names_in_file(conn: &Connection, file: &Path) -> Result<HashSet<String>> {
+    let mut stmt = conn.prepare_cached("SELECT DISTINCT name FROM refs WHERE file = ?1")?;
+    let names = stmt
+        .query_map(params![file.to_string_lossy()], |row| row.get(0))?
+        .collect::<std::result::Result<_, _>>()?;
+    Ok(names)
+}
+
+fn insert_symbol_rows(conn: &Connection, symbols: &[Symbol]) -> Result<()> {
+    let mut stmt = conn.prepare_cached(
+        "INSERT INTO symbols (name, qualified, kind, file, line, column, end_line, end_column, visibility, language, source, parent, mixins, attributes, implements, doc, signature, is_test, symbol_id, guid)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, 'syntactic', ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19)",
+    )?;
+
+    for symbol in symbols {
+        stmt.execute(params![
+            symbol.name,
+            symbol.qualified,
+            symbol_kind_to_str(symbol.kind),
+            symbol.location.file.to_string_lossy(),
+            symbol.location.line,
+            symbol.location.column,
+            symbol.location.end_line,
+            symbol.location.end_column,
+            visibility_to_str(symbol.visibility),
+            symbol.language,
+            symbol.parent,
+            symbol
+                .mixins
+                .as_ref()
+                .map(|v| serde_json::to_string(v).unwrap_or_default()),
+            symbol
+                .attributes
+                .as_ref()
+                .map(|v| serde_json::to_string(v).unwrap_or_default()),
+            symbol
+                .implements
+                .as_ref()
+                .map(|v| serde_json::to_string(v).unwrap_or_default()),
+            symbol.doc,
+            symbol.signature,
+            is_test_symbol(symbol),
+            symbol_id(&symbol.qualified, symbol.kind),
+            symbol_guid(
+                &symbol.language,
+                &symbol.qualified,
+                symbol.signature.as_deref()
+            ),
+        ])?;
+    }
+    Ok(())
+}
+
+fn insert_reference_rows(conn: &Connection, refs: &[(&Path, &Reference)]) -> Result<()> {
+    let mut stmt = conn.prepare_cached(
+        "INSERT INTO refs (name, file, line, column, end_line, end_column, confidence, kind)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+    )?;
+
+    for (file, reference) in refs {
+        let file_str = file.to_string_lossy();
+        stmt.execute(params![
+            reference.name,
+            file_str.as_ref(),
+            reference.location.line,
+            reference.location.column,
+            reference.location.end_line,
+            reference.location.end_column,
+            Confidence::Heuristic.as_str(),
+            reference.kind.as_str(),
+        ])?;
+    }
+    Ok(())
+}
+
+fn insert_open_rows(conn: &Connection, opens: &[(&Path, &str, u32)]) -> Result<()> {
+    let mut stmt =
+        conn.prepare_cached("INSERT INTO opens (file, module_path, line) VALUES (?1, ?2, ?3)")?;
+
+    for (file, module_path, line) in opens {
+        let file_str = file.to_string_lossy();
+        stmt.execute(params![file_str.as_ref(), *module_path, *line])?;
+    }
+    Ok(())
+}
+
+fn row_to_move(row: &rusqlite::Row<'_>) -> rusqlite::Result<SymbolMove> {
+    let kind: String = row.get(1)?;
+    let from_file: String = row.get(4)?;
+    let to_file: Option<String> = row.get(7)?;
+    Ok(SymbolMove {
+        symbol_id: row.get(0)?,
+        kind: str_to_symbol_kind(&kind),
+        name: row.get(2)?,
+        from: row.get(3)?,
+        from_file: PathBuf::from(from_file),
+        from_line: row.get(5)?,
+        to: row.get(6)?,
+        to_file: to_file.map(PathBuf::from),
+        to_line: row.get(8)?,
+        recorded_at: row.get(9)?,
+    })
+}
+
+/// FTS query matching names that share any trigram with `query` (case
+/// folded, as the trigram tokenizer folds), or `None` if it's too short to
+/// have one.
+fn trigram_query(query: &str) -> Option<String> {
+    let chars: Vec<char> = query.to_lowercase().chars().collect();
+    let mut trigrams: Vec<String> = chars
+        .windows(3)
+        .map(|w| format!("\"{}\"", w.iter().collect::<String>().replace('"', "\"\"")))
+        .collect();
+    trigrams.sort();
+    trigrams.dedup();
+    (!trigrams.is_empty()).then(|| trigrams.join(" OR "))
+}
+
+/// `LIKE` pattern matching strings that contain `query` as a subsequence,
+/// e.g. `%u%s%r%S%v%c%` (LIKE ignores ASCII case).
+fn subsequence_pattern(query: &str) -> String {
+    let mut pattern = String::from("%");
+    for c in query.chars() {
+        pattern.push_str(&escape_like(c.encode_utf8(&mut [0; 4])));
+        pattern.push('%');
+    }
+    pattern
+}
+
+/// `text` with `LIKE` wildcards escaped, for use with `ESCAPE '\'`.
+fn insert_alias_rows(conn: &Connection, aliases: &[(&Path, &ModuleAlias, u32)]) -> Result<()> {
+    let mut stmt = conn.prepare_cached(
+        "INSERT INTO opens (file, module_path, line, alias) VALUES (?1, ?2, ?3, ?4)",
+    )?;
+    for (file, alias, line) in aliases {
+        stmt.execute(params![
+            file.to_string_lossy(),
+            alias.target,
+            *line,
+            alias.alias
+        ])?;
+    }
+    Ok(())
+}
+
+fn insert_metrics_row(conn: &Connection, file: &Path, metrics: &FileMetrics) -> Result<()> {
+    conn.prepare_cached(
+        "INSERT OR REPLACE INTO file_metrics (file, complexity, max_nesting) VALUES (?1, ?2, ?3)",
+    )?
+    .execute(params![
+        file.to_string_lossy(),
+        metrics.complexity,
+        metrics.max_nesting
+    ])?;
+    Ok(())
+}
+
+fn row_to_open_site(row: &rusqlite::Row<'_>) -> rusqlite::Result<OpenSite> {
+    Ok(OpenSite {
+        file: PathBuf::from(row.get::<_, String>(0)?),
+        module: row.get(1)?,
+        position: row.get(2)?,
+    })
+}
+
+fn escape_like(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// `pattern` for GLOB, with only `*` left special.
+fn escape_glob(pattern: &str) -> String {
+    let mut escaped = String::with_capacity(pattern.len());
+    for c in pattern.chars() {
+        match c {
+            '?' | '[' => {
+                escaped.push('[');
+                escaped.push(c);
+                escaped.push(']');
+            }
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Whether `file` is `path`, lies under it, or matches it as a glob (see
+/// [`SearchFilter::path`]).
+fn path_matches(path: &Path, file: &Path) -> bool {
+    let pattern = path.to_string_lossy();
+    let pattern = pattern.trim_end_matches(['/', '\\']);
+    if !pattern.contains('*') {
+        return file.starts_with(path);
+    }
+    let file = file.to_string_lossy();
+    crate::config::wildcard_match(pattern, &file)
+        || crate::config::wildcard_match(&format!("{}/*", pattern), &file)
+}
+
 fn row_to_symbol(row: &rusqlite::Row<'_>) -> rusqlite::Result<Symbol> {
     let name: String = row.get(0)?;
     let qualified: String = row.get(1)?;
@@ -1728,12 +4250,42 @@ fn str_to_symbol_kind(s: &str) -> SymbolKind {
     }
 }
 
-pub(crate) fn visibility_to_str(vis: Visibility) -> &'static str {
-    match vis {
-        Visibility::Public => "public",
-        Visibility::Internal => "internal",
-        Visibility::Private => "private",
-    }
+pub(crate) fn visibility_to_str(vis: Visibility) -> &'static str {
+    match vis {
+        Visibility::Public => "public",
+        Visibility::Internal => "internal",
+        Visibility::Private => "private",
+    }
+}
+
+/// Convert a row of `name, file, line, column, end_line, end_column, kind`
+/// into a Reference.
+///
+/// Rows from before spans were recorded end where they start.
+fn row_to_reference(row: &rusqlite::Row<'_>) -> rusqlite::Result<Reference> {
+    let name: String = row.get(0)?;
+    let file: String = row.get(1)?;
+    let line: u32 = row.get(2)?;
+    let column: u32 = row.get(3)?;
+    let end_line: Option<u32> = row.get(4)?;
+    let end_column: Option<u32> = row.get(5)?;
+    let kind: Option<String> = row.get(6)?;
+    Ok(Reference {
+        name,
+        location: Location::with_end(
+            PathBuf::from(file),
+            line,
+            column,
+            end_line.unwrap_or(line),
+            end_column.unwrap_or(column),
+        ),
+        kind: parse_reference_kind(kind),
+    })
+}
+
+/// Rows from before reference kinds were recorded have none.
+fn parse_reference_kind(kind: Option<String>) -> ReferenceKind {
+    kind.and_then(|k| k.parse().ok()).unwrap_or_default()
 }
 
 fn str_to_visibility(s: &str) -> Visibility {
@@ -1799,6 +4351,53 @@ mod tests {
         assert_eq!(index.get_schema_version().unwrap(), SCHEMA_VERSION);
     }
 
+    #[test]
+    fn test_new_database_records_provenance() {
+        let index = SqliteIndex::in_memory().unwrap();
+        assert_eq!(
+            index.get_metadata("tool_version").unwrap().as_deref(),
+            Some(tool_version())
+        );
+        let created_at: u64 = index
+            .get_metadata("created_at")
+            .unwrap()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(created_at > 0);
+    }
+
+    #[test]
+    fn test_language_counts() {
+        let index = SqliteIndex::in_memory().unwrap();
+        index
+            .insert_symbol(&make_symbol("a", "A.a", "src/A.fs", 1))
+            .unwrap();
+        index
+            .insert_symbol(&make_symbol("b", "A.b", "src/A.fs", 2))
+            .unwrap();
+        let mut ruby = make_symbol("c", "C#c", "lib/c.rb", 1);
+        ruby.language = "ruby".to_string();
+        index.insert_symbol(&ruby).unwrap();
+
+        let counts = index.language_counts().unwrap();
+        assert_eq!(
+            counts,
+            vec![
+                LanguageCount {
+                    language: "fsharp".to_string(),
+                    files: 1,
+                    symbols: 2,
+                },
+                LanguageCount {
+                    language: "ruby".to_string(),
+                    files: 1,
+                    symbols: 1,
+                },
+            ]
+        );
+    }
+
     #[test]
     fn test_metadata_operations() {
         let index = SqliteIndex::in_memory().unwrap();
@@ -1835,6 +4434,56 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_staged_rebuild_replaces_live_database() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db_path = temp_dir.path().join("index.db");
+
+        let old = SqliteIndex::create(&db_path).unwrap();
+        old.insert_symbol(&make_symbol("Old", "Old", "old.fs", 1))
+            .unwrap();
+        drop(old);
+        let reader = SqliteIndex::open(&db_path).unwrap();
+
+        // A leftover from an interrupted rebuild doesn't get in the way
+        std::fs::write(SqliteIndex::staging_path(&db_path), b"partial").unwrap();
+        let staged = SqliteIndex::create_staged(&db_path).unwrap();
+        staged
+            .insert_symbol(&make_symbol("New", "New", "new.fs", 1))
+            .unwrap();
+
+        // The live database keeps answering while the rebuild runs
+        let fresh = SqliteIndex::open(&db_path).unwrap();
+        assert!(fresh.find_by_qualified("Old").unwrap().is_some());
+        drop(fresh);
+
+        staged.replace(&db_path).unwrap();
+        assert!(!SqliteIndex::staging_path(&db_path).exists());
+
+        let swapped = SqliteIndex::open(&db_path).unwrap();
+        assert!(swapped.find_by_qualified("New").unwrap().is_some());
+        assert!(swapped.find_by_qualified("Old").unwrap().is_none());
+        // Connections from before the swap read the new index too
+        assert!(reader.find_by_qualified("New").unwrap().is_some());
+        assert!(reader.find_by_qualified("Old").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_staged_rebuild_creates_missing_database() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db_path = temp_dir.path().join("index.db");
+
+        let staged = SqliteIndex::create_staged(&db_path).unwrap();
+        staged
+            .insert_symbol(&make_symbol("New", "New", "new.fs", 1))
+            .unwrap();
+        staged.replace(&db_path).unwrap();
+        assert!(!SqliteIndex::staging_path(&db_path).exists());
+
+        let index = SqliteIndex::open(&db_path).unwrap();
+        assert!(index.find_by_qualified("New").unwrap().is_some());
+    }
+
     #[test]
     fn test_open_fails_if_not_exists() {
         let result = SqliteIndex::open(Path::new("/nonexistent/path.db"));
@@ -1877,126 +4526,861 @@ mod tests {
         assert_eq!(found.qualified, "Utils.helper");
     }
 
-    #[test]
-    fn test_find_nonexistent_symbol() {
-        let index = SqliteIndex::in_memory().unwrap();
-        let found = index.find_by_qualified("NonExistent.symbol").unwrap();
-        assert!(found.is_none());
+    #[test]
+    fn test_find_nonexistent_symbol() {
+        let index = SqliteIndex::in_memory().unwrap();
+        let found = index.find_by_qualified("NonExistent.symbol").unwrap();
+        assert!(found.is_none());
+    }
+
+    #[test]
+    fn test_find_all_by_qualified_overloads() {
+        let index = SqliteIndex::in_memory().unwrap();
+
+        // Insert two symbols with same qualified name (simulating overloads)
+        index
+            .insert_symbol(&make_symbol("parse", "Parser.parse", "src/Parser.fs", 10))
+            .unwrap();
+        index
+            .insert_symbol(&make_symbol("parse", "Parser.parse", "src/Parser.fs", 20))
+            .unwrap();
+
+        let all = index.find_all_by_qualified("Parser.parse").unwrap();
+        assert_eq!(all.len(), 2);
+    }
+
+    #[test]
+    fn test_search_symbols() {
+        let index = SqliteIndex::in_memory().unwrap();
+
+        index
+            .insert_symbol(&make_symbol(
+                "PaymentService",
+                "App.PaymentService",
+                "a.fs",
+                1,
+            ))
+            .unwrap();
+        index
+            .insert_symbol(&make_symbol(
+                "PaymentRequest",
+                "App.PaymentRequest",
+                "a.fs",
+                2,
+            ))
+            .unwrap();
+        index
+            .insert_symbol(&make_symbol("OrderService", "App.OrderService", "b.fs", 1))
+            .unwrap();
+
+        let results = index.search("Payment%", 100, None).unwrap();
+        assert_eq!(results.len(), 2);
+
+        let results = index.search("Order%", 100, None).unwrap();
+        assert_eq!(results.len(), 1);
+    }
+
+    #[test]
+    fn test_results_order_does_not_depend_on_insertion_order() {
+        let index = SqliteIndex::in_memory().unwrap();
+        // Inserted in the reverse of the documented order, as a parallel
+        // index might
+        for (file, line) in [("b.fs", 1), ("a.fs", 9), ("a.fs", 2)] {
+            index
+                .insert_symbol(&make_symbol("handle", "App.handle", file, line))
+                .unwrap();
+            let reference = Reference {
+                name: "handle".to_string(),
+                location: Location::new(PathBuf::from(file), line, 1),
+                kind: ReferenceKind::Call,
+            };
+            index.insert_reference(Path::new(file), &reference).unwrap();
+        }
+
+        let positions = |symbols: Vec<Symbol>| -> Vec<(PathBuf, u32)> {
+            symbols
+                .into_iter()
+                .map(|s| (s.location.file, s.location.line))
+                .collect()
+        };
+        let expected = vec![
+            (PathBuf::from("a.fs"), 2),
+            (PathBuf::from("a.fs"), 9),
+            (PathBuf::from("b.fs"), 1),
+        ];
+        assert_eq!(
+            positions(index.search("handle", 10, None).unwrap()),
+            expected
+        );
+        assert_eq!(
+            positions(index.find_all_by_qualified("App.handle").unwrap()),
+            expected
+        );
+        let references: Vec<(PathBuf, u32)> = index
+            .find_references("handle")
+            .unwrap()
+            .into_iter()
+            .map(|r| (r.location.file, r.location.line))
+            .collect();
+        assert_eq!(references, expected);
+    }
+
+    #[test]
+    fn test_search_with_filters() {
+        let index = SqliteIndex::in_memory().unwrap();
+        let mut service = make_symbol("UserService", "App.UserService", "/ws/src/services/a.fs", 1);
+        service.kind = SymbolKind::Class;
+        let mut save = make_symbol(
+            "saveUser",
+            "App.UserService.saveUser",
+            "/ws/src/services/a.fs",
+            2,
+        )
+        .with_attributes(Some(vec!["Obsolete(\"use store\")".to_string()]));
+        save.visibility = Visibility::Private;
+        let load = make_symbol("loadUser", "App.loadUser", "/ws/src/services_old/b.fs", 1)
+            .with_attributes(Some(vec!["ObsoleteLater".to_string()]));
+        let find = make_symbol("findUser", "App.findUser", "/ws/src/data/c.fs", 1);
+        index.insert_symbols(&[service, save, load, find]).unwrap();
+
+        let names = |filter: &SearchFilter| -> Vec<String> {
+            let mut names: Vec<String> = index
+                .search_with("*User*", 100, filter)
+                .unwrap()
+                .into_iter()
+                .map(|s| s.name)
+                .collect();
+            names.sort();
+            names
+        };
+
+        assert_eq!(names(&SearchFilter::default()).len(), 4);
+        let kind = SearchFilter {
+            kind: Some(SymbolKind::Class),
+            ..SearchFilter::default()
+        };
+        assert_eq!(names(&kind), vec!["UserService"]);
+
+        // A directory, not a path prefix: services_old is left out
+        let path = SearchFilter {
+            path: Some(PathBuf::from("/ws/src/services/")),
+            ..SearchFilter::default()
+        };
+        assert_eq!(names(&path), vec!["UserService", "saveUser"]);
+
+        // Globs match across directories and take in what's under a match
+        let glob = SearchFilter {
+            path: Some(PathBuf::from("/ws/src/services*")),
+            ..SearchFilter::default()
+        };
+        assert_eq!(names(&glob), vec!["UserService", "loadUser", "saveUser"]);
+        let glob = SearchFilter {
+            path: Some(PathBuf::from("/ws/**/c.fs")),
+            ..SearchFilter::default()
+        };
+        assert_eq!(names(&glob), vec!["findUser"]);
+        assert!(glob.matches(&make_symbol("x", "x", "/ws/src/data/c.fs", 1)));
+        assert!(!glob.matches(&make_symbol("x", "x", "/ws/src/data/c.fsx", 1)));
+        // Only `*` is special
+        let glob = SearchFilter {
+            path: Some(PathBuf::from("/ws/src/[ds]*")),
+            ..SearchFilter::default()
+        };
+        assert!(names(&glob).is_empty());
+
+        // Matched by attribute name, not substring
+        let attribute = SearchFilter {
+            attribute: Some("ObsoleteAttribute".to_string()),
+            ..SearchFilter::default()
+        };
+        assert_eq!(names(&attribute), vec!["saveUser"]);
+
+        // Filters compose
+        let public_in_services = SearchFilter {
+            visibility: Some(Visibility::Public),
+            ..path
+        };
+        assert_eq!(names(&public_in_services), vec!["UserService"]);
+        assert!(public_in_services.matches(&make_symbol("x", "x", "/ws/src/services/d.fs", 1)));
+        assert!(!public_in_services.matches(&make_symbol("x", "x", "/ws/src/data/d.fs", 1)));
+    }
+
+    #[test]
+    fn test_find_implementers_by_bare_name() {
+        let index = SqliteIndex::in_memory().unwrap();
+        let implementing = |name: &str, file: &str, implements: &[&str]| {
+            let mut symbol = make_symbol(name, name, file, 1);
+            symbol.kind = SymbolKind::Class;
+            symbol.implements = Some(implements.iter().map(|s| s.to_string()).collect());
+            symbol
+        };
+        index
+            .insert_symbols(&[
+                implementing("Order", "a.cs", &["System.IComparable<Order>"]),
+                implementing("Point", "b.rs", &["Debug", "std::fmt::Display"]),
+                implementing("Money", "c.fs", &["IComparable"]),
+                implementing("Sorter", "d.cs", &["IComparableSorter"]),
+            ])
+            .unwrap();
+
+        let names = |interface: &str| -> Vec<String> {
+            index
+                .find_implementers(interface)
+                .unwrap()
+                .into_iter()
+                .map(|s| s.name)
+                .collect()
+        };
+        assert_eq!(names("IComparable"), vec!["Order", "Money"]);
+        assert_eq!(names("System.IComparable"), vec!["Order", "Money"]);
+        assert_eq!(names("Display"), vec!["Point"]);
+        assert!(names("Displ").is_empty());
+    }
+
+    #[test]
+    fn test_enclosing_symbol_by_line() {
+        let index = SqliteIndex::in_memory().unwrap();
+        let mut class = make_symbol(
+            "UserService",
+            "App.UserService",
+            "/ws/src/UserService.java",
+            3,
+        );
+        class.kind = SymbolKind::Class;
+        class.location.end_line = 40;
+        let mut save = make_symbol(
+            "save",
+            "App.UserService.save",
+            "/ws/src/UserService.java",
+            10,
+        );
+        save.location.end_line = 20;
+        // No end recorded: runs up to the next symbol
+        let load = make_symbol(
+            "load",
+            "App.UserService.load",
+            "/ws/src/UserService.java",
+            25,
+        );
+        let other = make_symbol("run", "Other.run", "/ws/lib/Other.java", 1);
+        let twin = make_symbol("run", "Twin.run", "/ws/test/Other.java", 1);
+        index
+            .insert_symbols(&[class, save, load, other, twin])
+            .unwrap();
+
+        let at = |path: &str, line| {
+            index
+                .enclosing_symbol(Path::new(path), line)
+                .unwrap()
+                .map(|s| s.qualified)
+        };
+        // A lambda inside save, from a frame with only the file name
+        assert_eq!(
+            at("UserService.java", 14).as_deref(),
+            Some("App.UserService.save")
+        );
+        assert_eq!(
+            at("/deploy/app/src/UserService.java", 30).as_deref(),
+            Some("App.UserService.load")
+        );
+        assert_eq!(
+            at("UserService.java", 5).as_deref(),
+            Some("App.UserService")
+        );
+        assert_eq!(at("UserService.java", 1), None);
+        // Ambiguous by name alone, but not with its directory
+        assert_eq!(at("Other.java", 1), None);
+        assert_eq!(at("lib/Other.java", 1).as_deref(), Some("Other.run"));
+        assert_eq!(at("Missing.java", 1), None);
+    }
+
+    #[test]
+    fn test_symbols_in_file() {
+        let index = SqliteIndex::in_memory().unwrap();
+
+        index
+            .insert_symbol(&make_symbol("foo", "M.foo", "src/a.fs", 1))
+            .unwrap();
+        index
+            .insert_symbol(&make_symbol("bar", "M.bar", "src/a.fs", 2))
+            .unwrap();
+        index
+            .insert_symbol(&make_symbol("baz", "M.baz", "src/b.fs", 1))
+            .unwrap();
+
+        let symbols = index.symbols_in_file(Path::new("src/a.fs")).unwrap();
+        assert_eq!(symbols.len(), 2);
+    }
+
+    #[test]
+    fn test_delete_symbols_in_file() {
+        let index = SqliteIndex::in_memory().unwrap();
+
+        index
+            .insert_symbol(&make_symbol("foo", "M.foo", "src/a.fs", 1))
+            .unwrap();
+        index
+            .insert_symbol(&make_symbol("bar", "M.bar", "src/b.fs", 1))
+            .unwrap();
+
+        let deleted = index.delete_symbols_in_file(Path::new("src/a.fs")).unwrap();
+        assert_eq!(deleted, 1);
+        assert_eq!(index.count_symbols().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_insert_symbols_batch() {
+        let index = SqliteIndex::in_memory().unwrap();
+
+        let symbols = vec![
+            make_symbol("a", "M.a", "test.fs", 1),
+            make_symbol("b", "M.b", "test.fs", 2),
+            make_symbol("c", "M.c", "test.fs", 3),
+        ];
+
+        index.insert_symbols(&symbols).unwrap();
+        assert_eq!(index.count_symbols().unwrap(), 3);
+    }
+
+    #[test]
+    fn test_list_files() {
+        let index = SqliteIndex::in_memory().unwrap();
+
+        index
+            .insert_symbol(&make_symbol("a", "M.a", "src/A.fs", 1))
+            .unwrap();
+        index
+            .insert_symbol(&make_symbol("b", "M.b", "src/B.fs", 1))
+            .unwrap();
+        index
+            .insert_symbol(&make_symbol("c", "M.c", "src/A.fs", 2))
+            .unwrap();
+
+        let files = index.list_files().unwrap();
+        assert_eq!(files.len(), 2);
+        assert!(files.contains(&PathBuf::from("src/A.fs")));
+        assert!(files.contains(&PathBuf::from("src/B.fs")));
+    }
+
+    #[test]
+    fn test_find_tests_and_migrate_is_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("index.db");
+        let test_case = make_symbol(
+            "saves user",
+            "UserTests.saves user",
+            "tests/UserTests.fs",
+            3,
+        )
+        .with_attributes(Some(vec!["Fact".to_string()]));
+        {
+            let index = SqliteIndex::create(&path).unwrap();
+            index
+                .insert_symbols(&[
+                    make_symbol("save", "User.save", "src/User.fs", 1),
+                    test_case,
+                ])
+                .unwrap();
+            let tests = index.find_tests().unwrap();
+            assert_eq!(tests.len(), 1);
+            assert_eq!(tests[0].qualified, "UserTests.saves user");
+
+            // Downgrade to a v6 index, which has no is_test column
+            index
+                .conn()
+                .execute_batch(
+                    "DROP INDEX idx_symbols_is_test;
+                     ALTER TABLE symbols DROP COLUMN is_test;",
+                )
+                .unwrap();
+            index.set_metadata("schema_version", "6").unwrap();
+        }
+
+        let index = SqliteIndex::open(&path).unwrap();
+        assert_eq!(index.get_schema_version().unwrap(), SCHEMA_VERSION);
+        let tests = index.find_tests().unwrap();
+        assert_eq!(tests.len(), 1);
+        assert_eq!(tests[0].qualified, "UserTests.saves user");
+    }
+
+    #[test]
+    fn test_fuzzy_search_ranks_abbreviations_and_typos() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("index.db");
+        {
+            let index = SqliteIndex::create(&path).unwrap();
+            index
+                .insert_symbols(&[
+                    make_symbol("UserService", "App.UserService", "src/User.fs", 1),
+                    make_symbol(
+                        "UsersRecoveryServiceCache",
+                        "App.UsersRecoveryServiceCache",
+                        "src/Cache.fs",
+                        1,
+                    ),
+                    make_symbol("OrderQueue", "App.OrderQueue", "src/Order.fs", 1),
+                    make_symbol(
+                        "processPayment",
+                        "Billing.processPayment",
+                        "src/Billing.fs",
+                        1,
+                    ),
+                ])
+                .unwrap();
+
+            let found = index.fuzzy_search("usrSvc", 2, 10, None).unwrap();
+            let names: Vec<&str> = found.iter().map(|m| m.symbol.name.as_str()).collect();
+            assert_eq!(names, vec!["UserService", "UsersRecoveryServiceCache"]);
+            assert!(found[0].score > found[1].score);
+
+            let found = index.fuzzy_search("procesPayment", 2, 10, None).unwrap();
+            assert_eq!(found[0].symbol.qualified, "Billing.processPayment");
+            assert_eq!(found[0].distance, 1);
+
+            // Downgrade to a v12 index, which has no trigram table
+            index
+                .conn()
+                .execute_batch(
+                    "DROP TRIGGER symbols_trigram_ai;
+                     DROP TRIGGER symbols_trigram_ad;
+                     DROP TRIGGER symbols_trigram_au;
+                     DROP TABLE symbols_trigram;",
+                )
+                .unwrap();
+            index.set_metadata("schema_version", "12").unwrap();
+        }
+
+        // The migration fills the trigram index from existing symbols
+        let index = SqliteIndex::open(&path).unwrap();
+        let count: i64 = index
+            .conn()
+            .query_row(
+                "SELECT count(*) FROM symbols_trigram WHERE symbols_trigram MATCH 'queue'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(count, 1);
+        let found = index.fuzzy_search("OrdrQueue", 2, 10, None).unwrap();
+        assert_eq!(found[0].symbol.name, "OrderQueue");
     }
 
     #[test]
-    fn test_find_all_by_qualified_overloads() {
-        let index = SqliteIndex::in_memory().unwrap();
+    fn test_unusable_schemas_need_a_rebuild() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("index.db");
+        drop(SqliteIndex::create(&path).unwrap());
+        let set_version = |version: u32| {
+            Connection::open(&path)
+                .unwrap()
+                .execute(
+                    "UPDATE metadata SET value = ?1 WHERE key = 'schema_version'",
+                    params![version.to_string()],
+                )
+                .unwrap();
+        };
 
-        // Insert two symbols with same qualified name (simulating overloads)
-        index
-            .insert_symbol(&make_symbol("parse", "Parser.parse", "src/Parser.fs", 10))
+        set_version(SCHEMA_VERSION + 1);
+        let err = SqliteIndex::open(&path).err().unwrap();
+        assert!(
+            matches!(err, IndexError::IncompatibleSchema { found, .. } if found == SCHEMA_VERSION + 1)
+        );
+        assert!(err.needs_rebuild());
+
+        set_version(MIN_MIGRATABLE_VERSION - 1);
+        assert!(SqliteIndex::open(&path).err().unwrap().needs_rebuild());
+
+        // A failed step rolls back and leaves the version where it was
+        set_version(11);
+        Connection::open(&path)
+            .unwrap()
+            .execute_batch("DROP TABLE symbols;")
             .unwrap();
-        index
-            .insert_symbol(&make_symbol("parse", "Parser.parse", "src/Parser.fs", 20))
+        let err = SqliteIndex::open(&path).err().unwrap();
+        assert!(matches!(
+            err,
+            IndexError::MigrationFailed {
+                from: 11,
+                to: 12,
+                ..
+            }
+        ));
+        let version: String = Connection::open(&path)
+            .unwrap()
+            .query_row(
+                "SELECT value FROM metadata WHERE key = 'schema_version'",
+                [],
+                |row| row.get(0),
+            )
             .unwrap();
-
-        let all = index.find_all_by_qualified("Parser.parse").unwrap();
-        assert_eq!(all.len(), 2);
+        assert_eq!(version, "11");
     }
 
     #[test]
-    fn test_search_symbols() {
+    fn test_projects_tag_and_scope_queries() {
         let index = SqliteIndex::in_memory().unwrap();
+        let root = Path::new("ws");
+        let project = |name: &str| Project {
+            name: format!("services/{}", name),
+            root: root.join("services").join(name),
+        };
+        let file = |name: &str| {
+            root.join("services")
+                .join(name)
+                .join("Main.fs")
+                .to_string_lossy()
+                .into_owned()
+        };
 
+        // Symbols indexed before the roots were configured get tagged too
         index
-            .insert_symbol(&make_symbol(
-                "PaymentService",
-                "App.PaymentService",
-                "a.fs",
-                1,
-            ))
+            .insert_symbol(&make_symbol("charge", "Api.charge", &file("api"), 1))
             .unwrap();
         index
-            .insert_symbol(&make_symbol(
-                "PaymentRequest",
-                "App.PaymentRequest",
-                "a.fs",
-                2,
-            ))
+            .set_projects(&[project("api"), project("billing")])
             .unwrap();
         index
-            .insert_symbol(&make_symbol("OrderService", "App.OrderService", "b.fs", 1))
+            .insert_symbol(&make_symbol(
+                "charge",
+                "Billing.charge",
+                &file("billing"),
+                1,
+            ))
             .unwrap();
+        for caller in ["api", "billing"] {
+            let reference = Reference {
+                name: "charge".to_string(),
+                location: Location::new(PathBuf::from(file(caller)), 5, 3),
+                kind: ReferenceKind::Read,
+            };
+            index
+                .insert_reference(Path::new(&file(caller)), &reference)
+                .unwrap();
+        }
+        assert_eq!(
+            index.projects().unwrap(),
+            vec!["services/api", "services/billing"]
+        );
 
-        let results = index.search("Payment%", 100, None).unwrap();
-        assert_eq!(results.len(), 2);
+        index.scope_to_project("services/billing").unwrap();
+        let found: Vec<String> = index
+            .search("charge", 10, None)
+            .unwrap()
+            .into_iter()
+            .map(|s| s.qualified)
+            .collect();
+        assert_eq!(found, vec!["Billing.charge"]);
+        assert_eq!(index.search_fts("charge", 10, None).unwrap().len(), 1);
+        assert!(index.find_by_qualified("Api.charge").unwrap().is_none());
+        let refs = index.find_references("charge").unwrap();
+        assert_eq!(refs.len(), 1);
+        assert_eq!(refs[0].location.file, PathBuf::from(file("billing")));
 
-        let results = index.search("Order%", 100, None).unwrap();
-        assert_eq!(results.len(), 1);
+        assert!(matches!(
+            index.scope_to_project("services/web"),
+            Err(IndexError::ProjectNotFound(_))
+        ));
     }
 
     #[test]
-    fn test_symbols_in_file() {
+    fn test_aliases_follow_renames() {
         let index = SqliteIndex::in_memory().unwrap();
-
         index
-            .insert_symbol(&make_symbol("foo", "M.foo", "src/a.fs", 1))
+            .insert_symbol(&make_symbol("bill", "Billing.bill", "src/Billing.fs", 1))
             .unwrap();
-        index
-            .insert_symbol(&make_symbol("bar", "M.bar", "src/a.fs", 2))
+
+        index.add_alias("Billing.charge", "Billing.debit").unwrap();
+        index.add_alias("Billing.debit", "Billing.bill").unwrap();
+        assert_eq!(
+            index.aliases().unwrap(),
+            vec![
+                Alias {
+                    old: "Billing.charge".to_string(),
+                    new: "Billing.bill".to_string()
+                },
+                Alias {
+                    old: "Billing.debit".to_string(),
+                    new: "Billing.bill".to_string()
+                },
+            ]
+        );
+        assert_eq!(
+            index.follow_alias("Billing.charge").unwrap().as_deref(),
+            Some("Billing.bill")
+        );
+        // Names that still exist aren't redirected
+        assert_eq!(index.follow_alias("Billing.bill").unwrap(), None);
+
+        // Renaming back drops the alias for the name in use again
+        index.add_alias("Billing.bill", "Billing.charge").unwrap();
+        let olds: Vec<String> = index
+            .aliases()
+            .unwrap()
+            .into_iter()
+            .map(|a| a.old)
+            .collect();
+        assert_eq!(olds, vec!["Billing.bill", "Billing.debit"]);
+
+        assert!(index.remove_alias("Billing.debit").unwrap());
+        assert!(!index.remove_alias("Billing.debit").unwrap());
+    }
+
+    #[test]
+    fn test_bulk_load_commits_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let index = SqliteIndex::create(&dir.path().join("index.db")).unwrap();
+        let file = Path::new("src/Billing.fs");
+        let reference = Reference {
+            name: "charge".to_string(),
+            location: Location::new(file.to_path_buf(), 9, 5),
+            kind: ReferenceKind::Call,
+        };
+
+        let mut bulk = index.bulk_load().unwrap();
+        for chunk in [["charge", "refund"], ["invoice", "credit"]] {
+            let symbols: Vec<Symbol> = chunk
+                .iter()
+                .map(|name| make_symbol(name, &format!("Billing.{}", name), "src/Billing.fs", 1))
+                .collect();
+            bulk.insert_symbols(&symbols).unwrap();
+        }
+        bulk.insert_references(&[(file, &reference)]).unwrap();
+        bulk.insert_opens(&[(file, "Payments", 1)]).unwrap();
+        bulk.set_file_mtime(file, 42).unwrap();
+        bulk.finish().unwrap();
+
+        assert_eq!(index.count_symbols().unwrap(), 4);
+        assert_eq!(index.find_references("charge").unwrap().len(), 1);
+        assert_eq!(index.opens_for_file(file).unwrap(), vec!["Payments"]);
+        assert_eq!(index.get_file_mtime(file).unwrap(), Some(42));
+
+        // Dropping an unfinished load rolls it back
+        let mut bulk = index.bulk_load().unwrap();
+        bulk.insert_symbols(&[make_symbol("void", "Billing.void", "src/Billing.fs", 2)])
             .unwrap();
+        drop(bulk);
+        assert_eq!(index.count_symbols().unwrap(), 4);
+        assert!(index.find_by_qualified("Billing.void").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_maintain_prunes_orphans_and_vacuums() {
+        let dir = tempfile::tempdir().unwrap();
+        let index = SqliteIndex::create(&dir.path().join("index.db")).unwrap();
+        let live = Path::new("src/Billing.fs");
+        let gone = Path::new("src/Old.fs");
+        let reference = |file: &Path| Reference {
+            name: "charge".to_string(),
+            location: Location::new(file.to_path_buf(), 3, 1),
+            kind: ReferenceKind::Call,
+        };
+
+        let symbols: Vec<Symbol> = (0..2000)
+            .map(|i| make_symbol("f", &format!("Billing.f{i}"), "src/Billing.fs", i))
+            .collect();
+        index.insert_symbols(&symbols).unwrap();
+        index.set_file_mtime(live, 1).unwrap();
         index
-            .insert_symbol(&make_symbol("baz", "M.baz", "src/b.fs", 1))
+            .insert_references(&[(live, &reference(live)), (gone, &reference(gone))])
             .unwrap();
-
-        let symbols = index.symbols_in_file(Path::new("src/a.fs")).unwrap();
-        assert_eq!(symbols.len(), 2);
+        index.insert_opens(&[(gone, "Payments", 1)]).unwrap();
+        index.delete_symbols_in_file(live).unwrap();
+        index.insert_symbols(&symbols[..10]).unwrap();
+        assert!(index.needs_maintenance().unwrap());
+
+        let report = index.maintain().unwrap();
+        assert_eq!(report.orphaned_references, 1);
+        assert_eq!(report.orphaned_opens, 1);
+        assert!(report.free_pages_before > 0);
+        assert!(report.size_after < report.size_before);
+        assert!(!index.needs_maintenance().unwrap());
+        assert_eq!(index.find_references("charge").unwrap().len(), 1);
+        // The rebuilt full-text index still answers searches
+        assert_eq!(index.search_fts("f1*", 100, None).unwrap().len(), 1);
     }
 
     #[test]
-    fn test_delete_symbols_in_file() {
+    fn test_symbol_guid_survives_moves() {
         let index = SqliteIndex::in_memory().unwrap();
+        let mut charge = make_symbol("charge", "Billing.charge", "src/Billing.fs", 4);
+        charge.signature = Some("amount: decimal -> Receipt".to_string());
+        let guid = charge.guid();
+        assert!(is_symbol_guid(&guid), "{guid}");
+        index
+            .update_file_data(
+                Path::new("src/Billing.fs"),
+                std::slice::from_ref(&charge),
+                &[],
+                &[],
+            )
+            .unwrap();
 
+        // Moved to another file, further down
+        charge.location = Location::new(PathBuf::from("src/Charges.fs"), 30, 5);
+        charge.signature = Some("amount:  decimal\n    -> Receipt".to_string());
+        assert_eq!(charge.guid(), guid);
         index
-            .insert_symbol(&make_symbol("foo", "M.foo", "src/a.fs", 1))
+            .update_file_data(Path::new("src/Billing.fs"), &[], &[], &[])
             .unwrap();
         index
-            .insert_symbol(&make_symbol("bar", "M.bar", "src/b.fs", 1))
+            .update_file_data(Path::new("src/Charges.fs"), &[charge], &[], &[])
             .unwrap();
-
-        let deleted = index.delete_symbols_in_file(Path::new("src/a.fs")).unwrap();
-        assert_eq!(deleted, 1);
-        assert_eq!(index.count_symbols().unwrap(), 1);
+        let found = index.find_by_guid(&guid).unwrap().unwrap();
+        assert_eq!(found.location.file, PathBuf::from("src/Charges.fs"));
+        assert_eq!(found.location.line, 30);
+
+        // A new signature is a different symbol
+        assert_ne!(
+            symbol_guid("fsharp", "Billing.charge", Some("amount: int -> Receipt")),
+            guid
+        );
+        assert!(!is_symbol_guid("Billing.charge"));
     }
 
     #[test]
-    fn test_insert_symbols_batch() {
+    fn test_visibility_rules_override_parsed_visibility() {
         let index = SqliteIndex::in_memory().unwrap();
+        index.set_metadata("workspace_root", "/ws").unwrap();
+        let rule = |name: Option<&str>, path: Option<&str>, visibility| VisibilityRule {
+            language: Some("fsharp".to_string()),
+            name: name.map(str::to_string),
+            path: path.map(str::to_string),
+            visibility,
+        };
+        index
+            .set_visibility_rules(&[
+                rule(Some("_keep*"), None, Visibility::Public),
+                rule(Some("_*"), None, Visibility::Private),
+                rule(None, Some("src/Internal/*"), Visibility::Internal),
+            ])
+            .unwrap();
 
-        let symbols = vec![
-            make_symbol("a", "M.a", "test.fs", 1),
-            make_symbol("b", "M.b", "test.fs", 2),
-            make_symbol("c", "M.c", "test.fs", 3),
+        let symbols = [
+            make_symbol("charge", "Billing.charge", "/ws/src/Billing.fs", 3),
+            make_symbol("_round", "Billing._round", "/ws/src/Billing.fs", 5),
+            make_symbol("_keepAlive", "Billing._keepAlive", "/ws/src/Billing.fs", 7),
         ];
+        index
+            .update_file_data(Path::new("/ws/src/Billing.fs"), &symbols, &[], &[])
+            .unwrap();
+        let ledger = make_symbol("post", "Ledger.post", "/ws/src/Internal/Ledger.fs", 2);
+        let mut bulk = index.bulk_load().unwrap();
+        bulk.insert_symbols(&[ledger]).unwrap();
+        bulk.finish().unwrap();
 
-        index.insert_symbols(&symbols).unwrap();
-        assert_eq!(index.count_symbols().unwrap(), 3);
+        let visibility = |qualified: &str| {
+            index
+                .find_by_qualified(qualified)
+                .unwrap()
+                .unwrap()
+                .visibility
+        };
+        assert_eq!(visibility("Billing.charge"), Visibility::Public);
+        assert_eq!(visibility("Billing._round"), Visibility::Private);
+        // The first matching rule wins
+        assert_eq!(visibility("Billing._keepAlive"), Visibility::Public);
+        assert_eq!(visibility("Ledger.post"), Visibility::Internal);
+        assert_eq!(index.visibility_rules().unwrap().len(), 3);
     }
 
     #[test]
-    fn test_list_files() {
+    fn test_symbol_history_tracks_moves() {
         let index = SqliteIndex::in_memory().unwrap();
+        let charge = make_symbol("charge", "Billing.charge", "src/Billing.fs", 4);
+        index
+            .update_file_data(
+                Path::new("src/Billing.fs"),
+                std::slice::from_ref(&charge),
+                &[],
+                &[],
+            )
+            .unwrap();
 
+        // Reindexing a file in place isn't a move
+        let shifted = make_symbol("charge", "Billing.charge", "src/Billing.fs", 9);
         index
-            .insert_symbol(&make_symbol("a", "M.a", "src/A.fs", 1))
+            .update_file_data(Path::new("src/Billing.fs"), &[shifted], &[], &[])
+            .unwrap();
+        assert!(index.moves_of("Billing.charge").unwrap().is_empty());
+
+        // Removed, and nothing like it yet
+        index
+            .update_file_data(Path::new("src/Billing.fs"), &[], &[], &[])
             .unwrap();
+        let moves = index.moves_of("Billing.charge").unwrap();
+        assert_eq!(moves.len(), 1);
+        assert_eq!(moves[0].from_line, 9);
+        assert_eq!(moves[0].to, None);
+
+        // Reappears in another module
+        let moved = make_symbol("charge", "Payments.charge", "src/Payments.fs", 2);
         index
-            .insert_symbol(&make_symbol("b", "M.b", "src/B.fs", 1))
+            .update_file_data(Path::new("src/Payments.fs"), &[moved], &[], &[])
             .unwrap();
+        let moves = index.moves_of("Billing.charge").unwrap();
+        assert_eq!(moves.len(), 1);
+        assert_eq!(
+            moves[0].symbol_id,
+            symbol_id("Billing.charge", SymbolKind::Function)
+        );
+        assert_eq!(moves[0].to.as_deref(), Some("Payments.charge"));
+        assert_eq!(moves[0].to_file, Some(PathBuf::from("src/Payments.fs")));
+        assert_eq!(moves[0].to_line, Some(2));
+
+        // Moves are followed
         index
-            .insert_symbol(&make_symbol("c", "M.c", "src/A.fs", 2))
+            .update_file_data(Path::new("src/Payments.fs"), &[], &[], &[])
             .unwrap();
+        index
+            .update_file_data(Path::new("src/Billing.fs"), &[charge], &[], &[])
+            .unwrap();
+        let hops: Vec<Option<String>> = index
+            .moves_of("Billing.charge")
+            .unwrap()
+            .into_iter()
+            .map(|m| m.to)
+            .collect();
+        assert_eq!(
+            hops,
+            vec![
+                Some("Payments.charge".to_string()),
+                Some("Billing.charge".to_string())
+            ]
+        );
+        assert_eq!(index.symbol_moves().unwrap().len(), 2);
+    }
 
-        let files = index.list_files().unwrap();
-        assert_eq!(files.len(), 2);
-        assert!(files.contains(&PathBuf::from("src/A.fs")));
-        assert!(files.contains(&PathBuf::from("src/B.fs")));
+    #[test]
+    fn test_get_source() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("lib.py");
+        std::fs::write(
+            &file,
+            "import os\n\ndef first():\n    return 1\n\n\ndef second():\n    return 2\n",
+        )
+        .unwrap();
+        let file = file.to_string_lossy();
+        let index = SqliteIndex::in_memory().unwrap();
+        let first = make_symbol("first", "first", &file, 3);
+        let mut second = make_symbol("second", "second", &file, 7);
+        second.location.end_line = 8;
+        index
+            .insert_symbols(&[first.clone(), second.clone()])
+            .unwrap();
+
+        // Stored end position
+        let snippet = index.get_source(&second, 0).unwrap();
+        assert_eq!(snippet.text, "def second():\n    return 2");
+        assert_eq!((snippet.start_line, snippet.end_line), (7, 8));
+
+        // Single-line span runs to the next symbol, without blank lines
+        let snippet = index.get_source(&first, 0).unwrap();
+        assert_eq!(snippet.text, "def first():\n    return 1");
+        assert_eq!((snippet.symbol_line, snippet.symbol_end_line), (3, 4));
+
+        let snippet = index.get_source(&first, 2).unwrap();
+        assert_eq!((snippet.start_line, snippet.end_line), (1, 6));
+        assert!(snippet.text.starts_with("import os\n\ndef first():"));
     }
 
     // =========================================================================
@@ -2044,7 +5428,8 @@ mod tests {
 
         let reference = Reference {
             name: "helper".to_string(),
-            location: Location::new(PathBuf::from("src/Main.fs"), 10, 5),
+            location: Location::with_end(PathBuf::from("src/Main.fs"), 10, 5, 10, 11),
+            kind: ReferenceKind::Call,
         };
 
         index
@@ -2054,6 +5439,8 @@ mod tests {
         let refs = index.find_references("helper").unwrap();
         assert_eq!(refs.len(), 1);
         assert_eq!(refs[0].name, "helper");
+        assert_eq!(refs[0].location, reference.location);
+        assert_eq!(refs[0].kind, ReferenceKind::Call);
     }
 
     #[test]
@@ -2063,10 +5450,12 @@ mod tests {
         let ref1 = Reference {
             name: "foo".to_string(),
             location: Location::new(PathBuf::from("src/Main.fs"), 10, 5),
+            kind: ReferenceKind::Read,
         };
         let ref2 = Reference {
             name: "bar".to_string(),
             location: Location::new(PathBuf::from("src/Main.fs"), 20, 5),
+            kind: ReferenceKind::Read,
         };
 
         index
@@ -2080,6 +5469,105 @@ mod tests {
         assert_eq!(refs.len(), 2);
     }
 
+    #[test]
+    fn test_string_references_are_kept_separate() {
+        let index = SqliteIndex::in_memory().unwrap();
+
+        let syntactic = Reference {
+            name: "UserService".to_string(),
+            location: Location::new(PathBuf::from("src/App.cs"), 3, 9),
+            kind: ReferenceKind::Read,
+        };
+        let literal = Reference {
+            name: "MyApp.UserService".to_string(),
+            location: Location::new(PathBuf::from("src/Startup.cs"), 12, 22),
+            kind: ReferenceKind::Read,
+        };
+
+        index
+            .insert_reference(Path::new("src/App.cs"), &syntactic)
+            .unwrap();
+        index
+            .insert_string_references(&[(Path::new("src/Startup.cs"), &literal)])
+            .unwrap();
+
+        // Syntactic queries don't see string-literal references
+        assert_eq!(index.find_references("UserService").unwrap().len(), 1);
+        assert!(index
+            .references_in_file(Path::new("src/Startup.cs"))
+            .unwrap()
+            .is_empty());
+
+        let strings = index.find_string_references("UserService").unwrap();
+        assert_eq!(strings.len(), 1);
+        assert_eq!(strings[0].name, "MyApp.UserService");
+        assert_eq!(strings[0].location.end_column, 22 + 17);
+
+        assert_eq!(index.clear_string_references().unwrap(), 1);
+        assert!(index
+            .find_string_references("UserService")
+            .unwrap()
+            .is_empty());
+        assert_eq!(index.find_references("UserService").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_reference_confidence() {
+        let index = SqliteIndex::in_memory().unwrap();
+        index
+            .insert_symbol(&make_symbol(
+                "helper",
+                "App.Utils.helper",
+                "src/Utils.fs",
+                3,
+            ))
+            .unwrap();
+
+        let file = Path::new("src/Main.fs");
+        let reference = |name: &str, line| Reference {
+            name: name.to_string(),
+            location: Location::new(file.to_path_buf(), line, 1),
+            kind: ReferenceKind::Read,
+        };
+        let exact = reference("App.Utils.helper", 1);
+        let qualified = reference("Utils.helper", 2);
+        let receiver = reference("obj.helper", 3);
+        let bare = reference("helper", 4);
+        index
+            .insert_references(&[
+                (file, &exact),
+                (file, &qualified),
+                (file, &receiver),
+                (file, &bare),
+            ])
+            .unwrap();
+        index.insert_string_references(&[(file, &exact)]).unwrap();
+        assert_eq!(index.promote_exact_references().unwrap(), 1);
+
+        let all = index
+            .find_references_with_confidence("helper", Confidence::StringMatch)
+            .unwrap();
+        let mut levels: Vec<_> = all.iter().map(|(r, c)| (r.name.as_str(), *c)).collect();
+        levels.sort_by_key(|(_, c)| *c);
+        assert_eq!(
+            levels,
+            vec![
+                ("App.Utils.helper", Confidence::StringMatch),
+                ("obj.helper", Confidence::Heuristic),
+                ("helper", Confidence::Heuristic),
+                ("Utils.helper", Confidence::Qualified),
+                ("App.Utils.helper", Confidence::Exact),
+            ]
+        );
+
+        // A receiver is dotted but says nothing about which `helper` it calls
+        let precise = index
+            .find_references_with_confidence("helper", Confidence::Qualified)
+            .unwrap();
+        let names: Vec<_> = precise.iter().map(|(r, _)| r.name.as_str()).collect();
+        assert_eq!(names, vec!["App.Utils.helper", "Utils.helper"]);
+    }
+
     // =========================================================================
     // Ranking Tests
     // =========================================================================
@@ -2108,6 +5596,7 @@ mod tests {
                     &Reference {
                         name: "UserService".to_string(),
                         location: Location::new(PathBuf::from(file), line, 1),
+                        kind: ReferenceKind::Read,
                     },
                 )
                 .unwrap();
@@ -2121,6 +5610,7 @@ mod tests {
                     &Reference {
                         name: "HelperUtils".to_string(),
                         location: Location::new(PathBuf::from("src/utils.rs"), line, 1),
+                        kind: ReferenceKind::Read,
                     },
                 )
                 .unwrap();
@@ -2177,6 +5667,7 @@ mod tests {
                     &Reference {
                         name: "User".to_string(),
                         location: Location::new(PathBuf::from(file), 5, 1),
+                        kind: ReferenceKind::Read,
                     },
                 )
                 .unwrap();
@@ -2189,6 +5680,7 @@ mod tests {
                 &Reference {
                     name: "User".to_string(),
                     location: Location::new(PathBuf::from("src/main.rs"), 10, 1),
+                    kind: ReferenceKind::Read,
                 },
             )
             .unwrap();
@@ -2199,6 +5691,96 @@ mod tests {
         assert_eq!(total_refs, 3); // 3 total references
     }
 
+    #[test]
+    fn test_reference_counts_follow_updates_and_rank_search() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("index.db");
+        let call = |name: &str, file: &str, line: u32| Reference {
+            name: name.to_string(),
+            location: Location::new(PathBuf::from(file), line, 1),
+            kind: ReferenceKind::Call,
+        };
+        {
+            let index = SqliteIndex::create(&path).unwrap();
+            index
+                .update_file_data(
+                    Path::new("src/Jobs.fs"),
+                    &[make_symbol("run", "Jobs.run", "src/Jobs.fs", 1)],
+                    &[],
+                    &[],
+                )
+                .unwrap();
+            // Referenced before it's defined: the count starts at 2
+            index
+                .update_file_data(
+                    Path::new("src/App.fs"),
+                    &[],
+                    &[
+                        call("Server.run", "src/App.fs", 3),
+                        call("Server.run", "src/App.fs", 4),
+                    ],
+                    &[],
+                )
+                .unwrap();
+            index
+                .update_file_data(
+                    Path::new("src/Server.fs"),
+                    &[make_symbol("run", "Server.run", "src/Server.fs", 1)],
+                    &[],
+                    &[],
+                )
+                .unwrap();
+            assert_eq!(
+                index.count_symbol_references("Server.run", "run").unwrap(),
+                (1, 2)
+            );
+            // The more-referenced `run` wins a bare-name lookup
+            assert_eq!(
+                index.search("run", 1, None).unwrap()[0].qualified,
+                "Server.run"
+            );
+
+            index
+                .update_file_data(
+                    Path::new("src/App.fs"),
+                    &[],
+                    &[call("Jobs.run", "src/App.fs", 3)],
+                    &[],
+                )
+                .unwrap();
+            assert_eq!(
+                index.count_symbol_references("Server.run", "run").unwrap(),
+                (0, 0)
+            );
+            assert_eq!(
+                index.count_symbol_references("Jobs.run", "run").unwrap(),
+                (1, 1)
+            );
+            assert_eq!(
+                index.search("run", 1, None).unwrap()[0].qualified,
+                "Jobs.run"
+            );
+
+            // Downgrade to a v13 index, which has no counts
+            index
+                .conn()
+                .execute_batch(
+                    "DROP INDEX idx_symbols_ref_count;
+                     ALTER TABLE symbols DROP COLUMN ref_count;
+                     ALTER TABLE symbols DROP COLUMN ref_files;",
+                )
+                .unwrap();
+            index.set_metadata("schema_version", "13").unwrap();
+        }
+
+        // The migration counts existing references
+        let index = SqliteIndex::open(&path).unwrap();
+        assert_eq!(
+            index.count_symbol_references("Jobs.run", "run").unwrap(),
+            (1, 1)
+        );
+    }
+
     // =========================================================================
     // Opens Tests
     // =========================================================================
@@ -2220,10 +5802,112 @@ mod tests {
         assert_eq!(opens[1], "FSharp.Core");
     }
 
+    #[test]
+    fn test_module_aliases_are_kept_apart_from_opens() {
+        let index = SqliteIndex::in_memory().unwrap();
+        let file = Path::new("src/App.fs");
+        let alias = ModuleAlias {
+            alias: "Db".to_string(),
+            target: "MyApp.Data.Database".to_string(),
+        };
+
+        index.insert_opens(&[(file, "System", 1)]).unwrap();
+        index.insert_aliases(&[(file, &alias, 1)]).unwrap();
+
+        assert_eq!(index.opens_for_file(file).unwrap(), vec!["System"]);
+        assert_eq!(index.aliases_for_file(file).unwrap(), vec![alias]);
+        assert!(index
+            .files_opening("MyApp.Data.Database")
+            .unwrap()
+            .is_empty());
+
+        // Reindexing the file drops its aliases with its opens
+        index.update_file_data(file, &[], &[], &[]).unwrap();
+        assert!(index.aliases_for_file(file).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_migration_adds_module_aliases() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("index.db");
+        {
+            let index = SqliteIndex::create(&path).unwrap();
+            index
+                .insert_opens(&[(Path::new("src/App.fs"), "System", 1)])
+                .unwrap();
+            // Downgrade to a v14 index, which has no aliases
+            index
+                .conn()
+                .execute_batch("ALTER TABLE opens DROP COLUMN alias;")
+                .unwrap();
+            index.set_metadata("schema_version", "14").unwrap();
+        }
+
+        let index = SqliteIndex::open(&path).unwrap();
+        assert_eq!(index.get_schema_version().unwrap(), SCHEMA_VERSION);
+        let file = Path::new("src/App.fs");
+        assert_eq!(index.opens_for_file(file).unwrap(), vec!["System"]);
+        let alias = ModuleAlias {
+            alias: "Db".to_string(),
+            target: "MyApp.Data.Database".to_string(),
+        };
+        index.insert_aliases(&[(file, &alias, 2)]).unwrap();
+        assert_eq!(index.aliases_for_file(file).unwrap(), vec![alias]);
+    }
+
     // =========================================================================
     // Type Member Tests
     // =========================================================================
 
+    #[test]
+    fn test_open_usage_and_files_opening() {
+        let index = SqliteIndex::in_memory().unwrap();
+        let app = Path::new("src/App.fs");
+        index
+            .insert_symbols(&[
+                make_symbol("createUser", "MyApp.Domain.createUser", "src/Domain.fs", 3),
+                make_symbol("helper", "MyApp.Utils.helper", "src/Utils.fs", 3),
+            ])
+            .unwrap();
+        index
+            .insert_opens(&[
+                (app, "MyApp.Domain", 1),
+                (app, "MyApp.Utils", 2),
+                (app, "System.IO", 3),
+            ])
+            .unwrap();
+        index
+            .insert_reference(
+                app,
+                &Reference {
+                    name: "createUser".to_string(),
+                    location: Location::new(app.to_path_buf(), 7, 16),
+                    kind: ReferenceKind::Call,
+                },
+            )
+            .unwrap();
+
+        let usage = index.open_usage(app).unwrap();
+        let summary: Vec<(&str, OpenStatus, usize)> = usage
+            .iter()
+            .map(|u| (u.site.module.as_str(), u.status, u.uses))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("MyApp.Domain", OpenStatus::Used, 1),
+                ("MyApp.Utils", OpenStatus::Unused, 0),
+                ("System.IO", OpenStatus::External, 0),
+            ]
+        );
+
+        let sites = index.files_opening("MyApp.Utils").unwrap();
+        assert_eq!(sites.len(), 1);
+        assert_eq!(sites[0].file, app);
+        assert_eq!(sites[0].position, 2);
+        assert!(index.files_opening("MyApp").unwrap().is_empty());
+    }
+
     #[test]
     fn test_insert_and_get_members() {
         let index = SqliteIndex::in_memory().unwrap();
@@ -2316,6 +6000,7 @@ mod tests {
                 &Reference {
                     name: "bar".to_string(),
                     location: Location::new(PathBuf::from("src/Test.fs"), 5, 1),
+                    kind: ReferenceKind::Read,
                 },
             )
             .unwrap();
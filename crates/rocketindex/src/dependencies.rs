@@ -0,0 +1,394 @@
+//! Dependency sources, indexed on demand so go-to-definition lands in them.
+//!
+//! The walk that indexes a workspace skips `node_modules` and whatever
+//! `.gitignore` hides, like `vendor/bundle`, so names the workspace imports
+//! from packages resolve nowhere. With `rkt watch --dependencies` (or
+//! `index_dependencies = true`) the packages its imports lead into are
+//! indexed too, at reduced fidelity: public symbols only, with no
+//! references or opens, so `rkt refs` and the other workspace analyses
+//! don't see dependency code.
+//!
+//! An import leads into:
+//! - `node_modules/<package>` for a bare JavaScript or TypeScript
+//!   specifier (`lodash/fp` is package `lodash`, `@acme/ui/button` is
+//!   `@acme/ui`)
+//! - a gem under `vendor/bundle/ruby/*/gems` or `vendor/gems` for a
+//!   `require` naming the gem or a file in its `lib`; only `lib` is indexed
+//! - the NuGet package (`~/.nuget/packages`, or `$NUGET_PACKAGES`) of a
+//!   recorded package reference whose name prefixes an opened namespace,
+//!   when the package ships sources
+//!
+//! Indexed packages are recorded in the index metadata and indexed again
+//! only when their directory changes, as a reinstall does. Files the
+//! workspace indexes itself are left alone.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::db::SqliteIndex;
+use crate::fsproj::PackageReference;
+use crate::watch::find_source_files_with_config;
+use crate::{extract_symbols, Result, Visibility};
+
+/// Metadata key for the dependencies indexed so far
+const DEPENDENCIES_KEY: &str = "dependencies";
+
+/// Directories of a package never searched: tests, and packages nested in it
+const SKIPPED_DIRS: &[&str] = &["node_modules", "test", "tests", "__tests__", "spec"];
+
+/// Files larger than this are bundles or generated, and skipped
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+
+/// Where a dependency comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Ecosystem {
+    Npm,
+    Gem,
+    Nuget,
+}
+
+/// A dependency whose sources can be indexed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Dependency {
+    /// Package or gem name
+    pub name: String,
+    pub ecosystem: Ecosystem,
+    /// Directory indexed
+    pub dir: PathBuf,
+}
+
+/// A dependency as recorded after indexing it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Recorded {
+    #[serde(flatten)]
+    dependency: Dependency,
+    /// Modification time of its directory, in seconds
+    mtime: u64,
+}
+
+/// What [`index_dependencies`] did.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DependencyReport {
+    /// Dependencies indexed, newly or again
+    pub indexed: Vec<Dependency>,
+    /// Dependencies no longer imported or installed, whose symbols were removed
+    pub removed: Vec<Dependency>,
+    /// Public symbols written
+    pub symbols: usize,
+}
+
+impl DependencyReport {
+    /// Whether the index changed.
+    #[must_use]
+    pub fn changed(&self) -> bool {
+        !self.indexed.is_empty() || !self.removed.is_empty()
+    }
+}
+
+/// The dependencies that `modules`, the modules a workspace at `root`
+/// opens, lead into, given the NuGet `packages` its projects reference.
+#[must_use]
+pub fn find_dependencies(
+    root: &Path,
+    modules: &[String],
+    packages: &[PackageReference],
+) -> Vec<Dependency> {
+    let mut found = Vec::new();
+    let mut seen = HashSet::new();
+    let mut add = |dependency: Dependency| {
+        if seen.insert(dependency.dir.clone()) {
+            found.push(dependency);
+        }
+    };
+
+    let node_modules = root.join("node_modules");
+    if node_modules.is_dir() {
+        for package in modules.iter().filter_map(|module| npm_package(module)) {
+            let dir = node_modules.join(package);
+            if dir.is_dir() {
+                add(Dependency {
+                    name: package.to_string(),
+                    ecosystem: Ecosystem::Npm,
+                    dir,
+                });
+            }
+        }
+    }
+
+    let gems = vendored_gems(root);
+    for module in modules {
+        let first = module.split('/').next().unwrap_or_default();
+        let required = Path::new("lib").join(format!("{}.rb", module));
+        let gem = gems
+            .iter()
+            .find(|(name, _)| name == first)
+            .or_else(|| gems.iter().find(|(_, dir)| dir.join(&required).is_file()));
+        if let Some((name, dir)) = gem {
+            let lib = dir.join("lib");
+            add(Dependency {
+                name: name.clone(),
+                ecosystem: Ecosystem::Gem,
+                dir: if lib.is_dir() { lib } else { dir.clone() },
+            });
+        }
+    }
+
+    let nuget = nuget_packages_dir();
+    for package in packages {
+        let opened = modules.iter().any(|module| {
+            module
+                .strip_prefix(package.name.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+        });
+        if !opened {
+            continue;
+        }
+        let candidates = [
+            nuget.as_ref().map(|dir| {
+                dir.join(package.name.to_lowercase())
+                    .join(package.version.to_lowercase())
+            }),
+            // packages.config restores into the solution
+            Some(
+                root.join("packages")
+                    .join(format!("{}.{}", package.name, package.version)),
+            ),
+        ];
+        if let Some(dir) = candidates.into_iter().flatten().find(|dir| dir.is_dir()) {
+            add(Dependency {
+                name: package.name.clone(),
+                ecosystem: Ecosystem::Nuget,
+                dir,
+            });
+        }
+    }
+
+    found
+}
+
+/// Index the public symbols of the dependencies the workspace at `root`
+/// imports and `index` hasn't indexed (or whose directory changed since),
+/// and remove those of dependencies no longer imported or installed.
+pub fn index_dependencies(
+    index: &SqliteIndex,
+    root: &Path,
+    max_depth: usize,
+) -> Result<DependencyReport> {
+    let wanted = find_dependencies(root, &index.opened_modules()?, &index.packages()?);
+    let recorded: Vec<Recorded> = match index.get_metadata(DEPENDENCIES_KEY)? {
+        Some(json) => serde_json::from_str(&json).unwrap_or_default(),
+        None => Vec::new(),
+    };
+
+    let mut report = DependencyReport::default();
+    let mut kept = Vec::new();
+    for entry in recorded {
+        let current = wanted.contains(&entry.dependency)
+            && dir_mtime(&entry.dependency.dir) == Some(entry.mtime);
+        if current {
+            kept.push(entry);
+        } else {
+            index.delete_untracked_symbols_under(&entry.dependency.dir)?;
+            if !wanted.contains(&entry.dependency) {
+                report.removed.push(entry.dependency);
+            }
+        }
+    }
+
+    for dependency in wanted {
+        if kept.iter().any(|entry| entry.dependency == dependency) {
+            continue;
+        }
+        let Some(mtime) = dir_mtime(&dependency.dir) else {
+            continue;
+        };
+        report.symbols += index_public_symbols(index, &dependency.dir, max_depth)?;
+        kept.push(Recorded {
+            dependency: dependency.clone(),
+            mtime,
+        });
+        report.indexed.push(dependency);
+    }
+
+    if report.changed() {
+        index.set_metadata(DEPENDENCIES_KEY, &serde_json::to_string(&kept)?)?;
+    }
+    Ok(report)
+}
+
+/// Write the public symbols of the source files under `dir`, skipping
+/// files the workspace indexes itself. Returns how many were written.
+fn index_public_symbols(index: &SqliteIndex, dir: &Path, max_depth: usize) -> Result<usize> {
+    let mut symbols = Vec::new();
+    for file in find_source_files_with_config(dir, SKIPPED_DIRS, false)? {
+        let small = std::fs::metadata(&file).is_ok_and(|meta| meta.len() <= MAX_FILE_BYTES);
+        let minified = file.to_string_lossy().ends_with(".min.js");
+        if !small || minified || index.get_file_mtime(&file)?.is_some() {
+            continue;
+        }
+        let Ok(source) = std::fs::read_to_string(&file) else {
+            continue;
+        };
+        let result = extract_symbols(&file, &source, max_depth);
+        symbols.extend(
+            result
+                .symbols
+                .into_iter()
+                .filter(|symbol| symbol.visibility == Visibility::Public),
+        );
+    }
+    index.insert_symbols(&symbols)?;
+    Ok(symbols.len())
+}
+
+/// The npm package a module specifier names, if it's a bare one.
+fn npm_package(module: &str) -> Option<&str> {
+    if module.is_empty() || module.starts_with(['.', '/']) || module.contains(':') {
+        return None;
+    }
+    let mut end = module.find('/').unwrap_or(module.len());
+    if module.starts_with('@') {
+        end = module[end..]
+            .get(1..)
+            .and_then(|rest| rest.find('/'))
+            .map_or(module.len(), |slash| end + 1 + slash);
+    }
+    Some(&module[..end])
+}
+
+/// Gems installed into the workspace, by name, from directories named
+/// `<name>-<version>`.
+fn vendored_gems(root: &Path) -> Vec<(String, PathBuf)> {
+    let mut parents = vec![root.join("vendor").join("gems")];
+    if let Ok(rubies) = std::fs::read_dir(root.join("vendor").join("bundle").join("ruby")) {
+        parents.extend(rubies.flatten().map(|ruby| ruby.path().join("gems")));
+    }
+    let mut gems: Vec<(String, PathBuf)> = parents
+        .iter()
+        .filter_map(|parent| std::fs::read_dir(parent).ok())
+        .flat_map(|entries| entries.flatten())
+        .map(|entry| entry.path())
+        .filter(|dir| dir.is_dir())
+        .filter_map(|dir| {
+            let file_name = dir.file_name()?.to_str()?;
+            let name = match file_name.rsplit_once('-') {
+                Some((name, version)) if version.starts_with(|c: char| c.is_ascii_digit()) => name,
+                _ => file_name,
+            };
+            Some((name.to_string(), dir.clone()))
+        })
+        .collect();
+    // The newest version of a gem installed twice comes first
+    gems.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| b.1.cmp(&a.1)));
+    gems
+}
+
+/// Where NuGet extracts packages.
+fn nuget_packages_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os("NUGET_PACKAGES") {
+        return Some(PathBuf::from(dir));
+    }
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(|home| PathBuf::from(home).join(".nuget").join("packages"))
+}
+
+fn dir_mtime(dir: &Path) -> Option<u64> {
+    let modified = std::fs::metadata(dir).ok()?.modified().ok()?;
+    modified
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()
+        .map(|d| d.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(path: &Path, text: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, text).unwrap();
+    }
+
+    #[test]
+    fn finds_packages_imports_lead_into() {
+        assert_eq!(npm_package("lodash/fp"), Some("lodash"));
+        assert_eq!(npm_package("@acme/ui/button"), Some("@acme/ui"));
+        assert_eq!(npm_package("@acme/ui"), Some("@acme/ui"));
+        assert_eq!(npm_package("./utils"), None);
+        assert_eq!(npm_package("node:fs"), None);
+
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        write(&root.join("node_modules/lodash/debounce.js"), "");
+        write(&root.join("node_modules/@acme/ui/index.js"), "");
+        let gems = root.join("vendor/bundle/ruby/3.3.0/gems");
+        write(&gems.join("stripe-12.1.0/lib/stripe.rb"), "");
+        write(
+            &gems.join("activesupport-7.1.3/lib/active_support/core_ext.rb"),
+            "",
+        );
+
+        let modules: Vec<String> = [
+            "./utils",
+            "@acme/ui/button",
+            "active_support/core_ext",
+            "left-pad",
+            "lodash/fp",
+            "stripe",
+        ]
+        .map(String::from)
+        .to_vec();
+        let found = find_dependencies(root, &modules, &[]);
+        let names: Vec<(&str, Ecosystem)> = found
+            .iter()
+            .map(|d| (d.name.as_str(), d.ecosystem))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                ("@acme/ui", Ecosystem::Npm),
+                ("lodash", Ecosystem::Npm),
+                ("activesupport", Ecosystem::Gem),
+                ("stripe", Ecosystem::Gem),
+            ]
+        );
+        assert_eq!(found[3].dir, gems.join("stripe-12.1.0/lib"));
+    }
+
+    #[test]
+    fn indexes_public_symbols_once_and_removes_them_when_no_longer_imported() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path().canonicalize().unwrap();
+        let lib = root.join("vendor/gems/stripe-12.1.0/lib");
+        write(
+            &lib.join("stripe.rb"),
+            "module Stripe\n  def self.charge\n  end\n\n  private\n\n  def sign\n  end\nend\n",
+        );
+        write(&lib.join("spec/stripe_spec.rb"), "def helper\nend\n");
+
+        let index = SqliteIndex::in_memory().unwrap();
+        let app = root.join("app.rb");
+        index
+            .update_file_data(&app, &[], &[], &[("stripe".to_string(), 1)])
+            .unwrap();
+
+        let report = index_dependencies(&index, &root, 500).unwrap();
+        assert_eq!(report.indexed.len(), 1);
+        assert!(index.find_by_qualified("Stripe").unwrap().is_some());
+        assert!(!index.search("charge", 10, None).unwrap().is_empty());
+        assert!(index.search("sign", 10, None).unwrap().is_empty());
+        assert!(index.search("helper", 10, None).unwrap().is_empty());
+
+        // Nothing new the second time
+        assert!(!index_dependencies(&index, &root, 500).unwrap().changed());
+
+        index.update_file_data(&app, &[], &[], &[]).unwrap();
+        let report = index_dependencies(&index, &root, 500).unwrap();
+        assert_eq!(report.removed.len(), 1);
+        assert!(index.find_by_qualified("Stripe").unwrap().is_none());
+    }
+}
@@ -0,0 +1,386 @@
+//! Stacktraces from error trackers, for `rkt analyze --sentry`.
+//!
+//! Sentry events and Rollbar items carry their frames as JSON fields, so
+//! nothing has to be parsed out of text. Accepted are a Sentry event as its
+//! SDKs send it (`exception.values`) or as its API returns it (the
+//! exception under `entries`), and a Rollbar item or occurrence (`trace` or
+//! `trace_chain` under `body`), bare or wrapped in the API's `result` or a
+//! webhook's `data`.
+//!
+//! Both trackers list frames oldest call first, and Sentry lists chained
+//! exceptions cause first; they're turned around to match
+//! [`parse_stacktrace`](crate::stacktrace::parse_stacktrace): top of the
+//! stack and outermost exception first.
+
+use anyhow::{bail, Result};
+use serde_json::Value;
+
+use crate::stacktrace::{
+    is_framework_code, ChainedException, StackFrame, StacktraceLanguage, StacktraceResult,
+};
+
+/// Turn the JSON of an error-tracker event into a stacktrace.
+pub fn parse_event(json: &str) -> Result<StacktraceResult> {
+    let event: Value = serde_json::from_str(json)?;
+    if let Some(values) = sentry_exceptions(&event) {
+        return Ok(from_sentry(&event, values));
+    }
+    if let Some(data) = rollbar_data(&event) {
+        return Ok(from_rollbar(data));
+    }
+    bail!("not a Sentry event or Rollbar item: no exception or trace found")
+}
+
+/// The API URL of the event a Sentry issue or event URL shows: the latest
+/// event of an issue, or the one event an event URL names.
+///
+/// Takes the web UI's `https://sentry.io/organizations/acme/issues/42/`
+/// and `https://acme.sentry.io/issues/42/events/abc/` forms, on any host.
+#[must_use]
+pub fn sentry_event_url(url: &str) -> Option<String> {
+    let (scheme, rest) = url.split_once("://")?;
+    let (host, path) = rest.split_once('/').unwrap_or((rest, ""));
+    let path = path.split(['?', '#']).next().unwrap_or_default();
+    let parts: Vec<&str> = path.split('/').filter(|part| !part.is_empty()).collect();
+
+    let issue_at = parts.iter().position(|part| *part == "issues")?;
+    let issue = parts.get(issue_at + 1)?;
+    let org = match parts.iter().position(|part| *part == "organizations") {
+        Some(at) => parts.get(at + 1).copied()?,
+        // acme.sentry.io
+        None => host
+            .split('.')
+            .next()
+            .filter(|_| host.matches('.').count() >= 2)?,
+    };
+    let event = match parts.get(issue_at + 2) {
+        Some(&"events") => parts.get(issue_at + 3).copied().unwrap_or("latest"),
+        _ => "latest",
+    };
+    Some(format!(
+        "{}://{}/api/0/organizations/{}/issues/{}/events/{}/",
+        scheme, host, org, issue, event
+    ))
+}
+
+/// Whether a Sentry auth token may be sent with a request to `url`: only
+/// over https, and only to sentry.io or the host of `sentry_url` (the
+/// `$SENTRY_URL` of a self-hosted Sentry).
+#[must_use]
+pub fn token_allowed_for(url: &str, sentry_url: Option<&str>) -> bool {
+    let host = |url: &str| -> Option<String> {
+        let rest = url.strip_prefix("https://")?;
+        let host = rest.split('/').next().unwrap_or_default();
+        // Nothing that could make a client see a different host (`@`, `?`, `#`)
+        (!host.is_empty()
+            && host
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | ':')))
+        .then(|| host.to_ascii_lowercase())
+    };
+    let Some(target) = host(url) else {
+        return false;
+    };
+    let name = target.split(':').next().unwrap_or_default();
+    name == "sentry.io"
+        || name.ends_with(".sentry.io")
+        || sentry_url.and_then(host).is_some_and(|own| own == target)
+}
+
+/// The exceptions of a Sentry event, cause first.
+fn sentry_exceptions(event: &Value) -> Option<&Vec<Value>> {
+    let exception = event.get("exception").or_else(|| {
+        event
+            .get("entries")?
+            .as_array()?
+            .iter()
+            .find(|entry| entry["type"] == "exception")
+            .map(|entry| &entry["data"])
+    })?;
+    exception["values"]
+        .as_array()
+        .or_else(|| exception.as_array())
+        .filter(|values| !values.is_empty())
+}
+
+fn from_sentry(event: &Value, values: &[Value]) -> StacktraceResult {
+    let platform = language_of(event["platform"].as_str());
+    let mut result = StacktraceResult {
+        detected_language: platform,
+        ..StacktraceResult::default()
+    };
+    for value in values.iter().rev() {
+        let start = result.frames.len();
+        let frames = value["stacktrace"]["frames"].as_array();
+        for frame in frames.into_iter().flatten().rev() {
+            let language = language_of(frame["platform"].as_str()).or(platform);
+            let function = frame["function"].as_str();
+            let symbol = match (frame["module"].as_str(), function) {
+                // Classes and packages, as their own traces print them
+                (Some(module), Some(function))
+                    if matches!(
+                        language,
+                        Some(StacktraceLanguage::Java | StacktraceLanguage::Go)
+                    ) =>
+                {
+                    format!("{}.{}", module, function)
+                }
+                (_, Some(function)) => function.to_string(),
+                (_, None) => "<unknown>".to_string(),
+            };
+            let file = frame["abs_path"]
+                .as_str()
+                .or_else(|| frame["filename"].as_str());
+            result.frames.push(stack_frame(
+                symbol,
+                file,
+                frame["lineno"].as_u64(),
+                frame["colno"].as_u64(),
+                frame["in_app"].as_bool(),
+                language,
+            ));
+        }
+        let exception = value["type"]
+            .as_str()
+            .map(|name| match value["module"].as_str() {
+                Some(module) if !matches!(module, "builtins" | "exceptions" | "") => {
+                    format!("{}.{}", module, name)
+                }
+                _ => name.to_string(),
+            });
+        result.chain.push(ChainedException {
+            exception,
+            message: value["value"].as_str().map(str::to_string),
+            frames: start..result.frames.len(),
+        });
+    }
+    result.exception = result.chain.iter().find_map(|link| link.exception.clone());
+    result
+}
+
+/// The occurrence data of a Rollbar item: the object with its `body`.
+fn rollbar_data(event: &Value) -> Option<&Value> {
+    [
+        &event["result"]["data"],
+        &event["result"],
+        &event["data"],
+        event,
+    ]
+    .into_iter()
+    .find(|data| data["body"]["trace"].is_object() || data["body"]["trace_chain"].is_array())
+}
+
+fn from_rollbar(data: &Value) -> StacktraceResult {
+    let language = language_of(data["language"].as_str().or(data["platform"].as_str()));
+    let mut result = StacktraceResult {
+        detected_language: language,
+        ..StacktraceResult::default()
+    };
+    // The chain is outermost first already
+    let traces = match data["body"]["trace_chain"].as_array() {
+        Some(chain) => chain.iter().collect(),
+        None => vec![&data["body"]["trace"]],
+    };
+    for trace in traces {
+        let start = result.frames.len();
+        let frames = trace["frames"].as_array();
+        for frame in frames.into_iter().flatten().rev() {
+            let symbol = match (frame["class_name"].as_str(), frame["method"].as_str()) {
+                (Some(class), Some(method)) => format!("{}.{}", class, method),
+                (None, Some(method)) => method.to_string(),
+                (_, None) => "<unknown>".to_string(),
+            };
+            result.frames.push(stack_frame(
+                symbol,
+                frame["filename"].as_str(),
+                frame["lineno"].as_u64(),
+                frame["colno"].as_u64(),
+                None,
+                language,
+            ));
+        }
+        result.chain.push(ChainedException {
+            exception: trace["exception"]["class"].as_str().map(str::to_string),
+            message: trace["exception"]["message"].as_str().map(str::to_string),
+            frames: start..result.frames.len(),
+        });
+    }
+    result.exception = result.chain.iter().find_map(|link| link.exception.clone());
+    result
+}
+
+/// A frame; without the tracker's word on whether it's the application's
+/// own code, the same guess as for frames parsed from text.
+fn stack_frame(
+    symbol: String,
+    file: Option<&str>,
+    line: Option<u64>,
+    column: Option<u64>,
+    in_app: Option<bool>,
+    language: Option<StacktraceLanguage>,
+) -> StackFrame {
+    let file = file.filter(|file| !file.is_empty()).map(Into::into);
+    let is_user_code = in_app.unwrap_or_else(|| {
+        language.is_none_or(|language| !is_framework_code(&symbol, file.as_ref(), language))
+    });
+    StackFrame {
+        symbol,
+        file,
+        line: line.and_then(|line| u32::try_from(line).ok()),
+        column: column.and_then(|column| u32::try_from(column).ok()),
+        is_user_code,
+        language,
+    }
+}
+
+/// The language of a tracker's platform or language name.
+fn language_of(platform: Option<&str>) -> Option<StacktraceLanguage> {
+    match platform? {
+        "java" | "kotlin" | "scala" | "groovy" => Some(StacktraceLanguage::Java),
+        "python" => Some(StacktraceLanguage::Python),
+        "javascript" | "node" | "typescript" | "browser" => Some(StacktraceLanguage::JavaScript),
+        "ruby" => Some(StacktraceLanguage::Ruby),
+        "go" => Some(StacktraceLanguage::Go),
+        "rust" => Some(StacktraceLanguage::Rust),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::path::PathBuf;
+
+    #[test]
+    fn parses_sentry_events_and_rollbar_items() {
+        // As the SDK sends it: cause first, oldest frame first
+        let event = json!({
+            "platform": "python",
+            "exception": { "values": [
+                {
+                    "type": "KeyError",
+                    "module": "builtins",
+                    "value": "'card'",
+                    "stacktrace": { "frames": [
+                        { "function": "lookup", "abs_path": "/app/billing/cards.py", "lineno": 8, "in_app": true },
+                    ]},
+                },
+                {
+                    "type": "CardDeclined",
+                    "module": "billing.errors",
+                    "value": "no card on file",
+                    "stacktrace": { "frames": [
+                        { "function": "handle", "abs_path": "/usr/lib/python3/site-packages/flask/app.py", "lineno": 90, "in_app": false },
+                        { "function": "charge", "abs_path": "/app/billing/service.py", "lineno": 42, "in_app": true },
+                    ]},
+                },
+            ]},
+        });
+        let result = parse_event(&event.to_string()).unwrap();
+        assert_eq!(result.detected_language, Some(StacktraceLanguage::Python));
+        assert_eq!(
+            result.exception.as_deref(),
+            Some("billing.errors.CardDeclined")
+        );
+        let symbols: Vec<&str> = result.frames.iter().map(|f| f.symbol.as_str()).collect();
+        assert_eq!(symbols, ["charge", "handle", "lookup"]);
+        assert_eq!(
+            result.frames[0].file,
+            Some(PathBuf::from("/app/billing/service.py"))
+        );
+        assert_eq!(result.frames[0].line, Some(42));
+        assert!(!result.frames[1].is_user_code);
+        assert_eq!(result.chain.len(), 2);
+        assert_eq!(result.chain[1].exception.as_deref(), Some("KeyError"));
+        assert_eq!(result.chain_index(2), Some(1));
+
+        // From the API, with the exception under `entries`
+        let event = json!({
+            "platform": "java",
+            "entries": [
+                { "type": "breadcrumbs", "data": {} },
+                { "type": "exception", "data": { "values": [{
+                    "type": "PaymentException",
+                    "module": "com.example",
+                    "stacktrace": { "frames": [
+                        { "module": "com.example.PaymentService", "function": "charge", "filename": "PaymentService.java", "lineno": 12 },
+                    ]},
+                }]}},
+            ],
+        });
+        let result = parse_event(&event.to_string()).unwrap();
+        assert_eq!(result.frames[0].symbol, "com.example.PaymentService.charge");
+        assert!(result.frames[0].is_user_code);
+        assert_eq!(
+            result.exception.as_deref(),
+            Some("com.example.PaymentException")
+        );
+
+        // A Rollbar item from the API
+        let item = json!({ "err": 0, "result": { "data": {
+            "language": "ruby",
+            "body": { "trace": {
+                "exception": { "class": "NoMethodError", "message": "undefined method `total'" },
+                "frames": [
+                    { "filename": "/app/app/controllers/orders_controller.rb", "lineno": 5, "method": "create" },
+                    { "filename": "/app/app/models/order.rb", "lineno": 17, "method": "checkout" },
+                ],
+            }},
+        }}});
+        let result = parse_event(&item.to_string()).unwrap();
+        let symbols: Vec<&str> = result.frames.iter().map(|f| f.symbol.as_str()).collect();
+        assert_eq!(symbols, ["checkout", "create"]);
+        assert_eq!(result.exception.as_deref(), Some("NoMethodError"));
+
+        assert!(parse_event(r#"{"message": "hello"}"#).is_err());
+    }
+
+    #[test]
+    fn maps_sentry_issue_urls_to_the_api() {
+        assert_eq!(
+            sentry_event_url("https://sentry.io/organizations/acme/issues/42/?project=7")
+                .as_deref(),
+            Some("https://sentry.io/api/0/organizations/acme/issues/42/events/latest/")
+        );
+        assert_eq!(
+            sentry_event_url("https://acme.sentry.io/issues/42/events/abc123/").as_deref(),
+            Some("https://acme.sentry.io/api/0/organizations/acme/issues/42/events/abc123/")
+        );
+        assert_eq!(
+            sentry_event_url("https://errors.example.com/organizations/acme/issues/9/").as_deref(),
+            Some("https://errors.example.com/api/0/organizations/acme/issues/9/events/latest/")
+        );
+        assert_eq!(sentry_event_url("https://sentry.io/issues/42/"), None);
+        assert_eq!(sentry_event_url("trace.json"), None);
+    }
+
+    #[test]
+    fn sends_tokens_only_to_sentry_over_https() {
+        let api = "https://sentry.io/api/0/organizations/acme/issues/42/events/latest/";
+        assert!(token_allowed_for(api, None));
+        assert!(token_allowed_for("https://acme.sentry.io/api/0/", None));
+        assert!(!token_allowed_for("http://sentry.io/api/0/", None));
+        assert!(!token_allowed_for(
+            "https://errors.example.com/api/0/",
+            None
+        ));
+        assert!(!token_allowed_for(
+            "https://sentry.io@evil.example/api/0/",
+            None
+        ));
+        assert!(!token_allowed_for(
+            "https://evil.example?.sentry.io/api/0/",
+            None
+        ));
+
+        let own = Some("https://errors.example.com");
+        assert!(token_allowed_for("https://errors.example.com/api/0/", own));
+        assert!(!token_allowed_for("http://errors.example.com/api/0/", own));
+        assert!(!token_allowed_for("https://other.example.com/api/0/", own));
+        assert!(!token_allowed_for(
+            "https://errors.example.com/api/0/",
+            Some("http://errors.example.com")
+        ));
+    }
+}
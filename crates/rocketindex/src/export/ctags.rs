@@ -0,0 +1,309 @@
+//! Ctags and etags export.
+//!
+//! Writes the symbols in a [`SqliteIndex`] as an Exuberant Ctags compatible
+//! `tags` file (for vim and friends) or an Emacs `TAGS` file, so editors get
+//! go-to-definition without running the language server.
+//!
+//! Ctags entries use line-number addresses plus the `kind`, `scope` and
+//! `signature` extension fields. Etags entries need the text of the defining
+//! line, so the source files are read when writing that format.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use crate::db::SqliteIndex;
+use crate::{Result, Symbol, SymbolKind};
+
+/// A single tag: one symbol definition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagEntry {
+    /// Short name of the symbol
+    pub name: String,
+    /// File path as written to the tags file (relative to its directory when possible)
+    pub file: PathBuf,
+    /// 1-indexed line of the definition
+    pub line: u32,
+    /// 1-indexed column of the definition
+    pub column: u32,
+    pub kind: SymbolKind,
+    /// Enclosing scope as `(scope kind, qualified name)`, e.g. `("class", "MyApp.User")`
+    pub scope: Option<(&'static str, String)>,
+    /// Type signature, if one was extracted
+    pub signature: Option<String>,
+}
+
+/// A set of tags ready to be written as ctags or etags.
+#[derive(Debug, Clone, Default)]
+pub struct Tags {
+    /// Entries sorted by name, then file, then line
+    pub entries: Vec<TagEntry>,
+}
+
+impl Tags {
+    /// Collect tags for every symbol in the index.
+    ///
+    /// Paths are made relative to `root` (the directory the tags file is written to).
+    pub fn from_sqlite(index: &SqliteIndex, root: &Path) -> Result<Self> {
+        Ok(Self::from_symbols(&index.get_all_symbols_ordered()?, root))
+    }
+
+    /// Collect tags for `symbols`, resolving scopes against the same set.
+    #[must_use]
+    pub fn from_symbols(symbols: &[Symbol], root: &Path) -> Self {
+        let kinds: HashMap<&str, SymbolKind> = symbols
+            .iter()
+            .map(|s| (s.qualified.as_str(), s.kind))
+            .collect();
+
+        let mut entries: Vec<TagEntry> = symbols
+            .iter()
+            .map(|symbol| TagEntry {
+                name: symbol.name.clone(),
+                file: symbol
+                    .location
+                    .file
+                    .strip_prefix(root)
+                    .unwrap_or(&symbol.location.file)
+                    .to_path_buf(),
+                line: symbol.location.line,
+                column: symbol.location.column,
+                kind: symbol.kind,
+                scope: enclosing_scope(&symbol.qualified, &symbol.name).map(|scope| {
+                    let kind = kinds.get(scope).map_or("namespace", |k| kind_name(*k));
+                    (kind, scope.to_string())
+                }),
+                signature: symbol.signature.clone(),
+            })
+            .collect();
+
+        entries.sort_by(|a, b| {
+            a.name
+                .as_bytes()
+                .cmp(b.name.as_bytes())
+                .then_with(|| a.file.cmp(&b.file))
+                .then_with(|| a.line.cmp(&b.line))
+        });
+        entries.dedup();
+
+        Self { entries }
+    }
+
+    /// Render an Exuberant Ctags (format 2) tags file.
+    #[must_use]
+    pub fn to_ctags(&self, tool_version: &str) -> String {
+        let mut out = String::new();
+        out.push_str(
+            "!_TAG_FILE_FORMAT\t2\t/extended format; --format=1 will not append ;\" to lines/\n",
+        );
+        out.push_str("!_TAG_FILE_SORTED\t1\t/0=unsorted, 1=sorted, 2=foldcase/\n");
+        out.push_str("!_TAG_PROGRAM_NAME\trocketindex\t//\n");
+        let _ = writeln!(out, "!_TAG_PROGRAM_VERSION\t{}\t//", tool_version);
+
+        for entry in &self.entries {
+            let _ = write!(
+                out,
+                "{}\t{}\t{};\"\t{}\tline:{}",
+                entry.name,
+                entry.file.to_string_lossy().replace('\\', "/"),
+                entry.line,
+                kind_letter(entry.kind),
+                entry.line
+            );
+            if let Some((kind, scope)) = &entry.scope {
+                let _ = write!(out, "\t{}:{}", kind, sanitize_field(scope));
+            }
+            if let Some(signature) = &entry.signature {
+                let _ = write!(out, "\tsignature:{}", sanitize_field(signature));
+            }
+            out.push('\n');
+        }
+
+        out
+    }
+
+    /// Render an Emacs etags `TAGS` file, reading source lines from under `root`.
+    ///
+    /// Entries whose file can't be read are written with the tag name as the
+    /// search text, which Emacs still resolves by line number.
+    #[must_use]
+    pub fn to_etags(&self, root: &Path) -> String {
+        let mut by_file: Vec<(&Path, Vec<&TagEntry>)> = Vec::new();
+        let mut sorted: Vec<&TagEntry> = self.entries.iter().collect();
+        sorted.sort_by(|a, b| a.file.cmp(&b.file).then_with(|| a.line.cmp(&b.line)));
+        for entry in sorted {
+            match by_file.last_mut() {
+                Some((file, entries)) if *file == entry.file.as_path() => entries.push(entry),
+                _ => by_file.push((entry.file.as_path(), vec![entry])),
+            }
+        }
+
+        let mut out = String::new();
+        for (file, entries) in by_file {
+            let source = std::fs::read_to_string(root.join(file)).unwrap_or_default();
+            let line_starts: Vec<usize> = std::iter::once(0)
+                .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+                .collect();
+            let lines: Vec<&str> = source.lines().collect();
+
+            let mut section = String::new();
+            for entry in entries {
+                let idx = entry.line.saturating_sub(1) as usize;
+                let offset = line_starts.get(idx).copied().unwrap_or(0);
+                let text = lines
+                    .get(idx)
+                    .and_then(|line| {
+                        // Tag text runs from the start of the line through the name
+                        let at_column = entry.column.saturating_sub(1) as usize;
+                        let pos = if line.get(at_column..)?.starts_with(entry.name.as_str()) {
+                            at_column
+                        } else {
+                            line.find(entry.name.as_str())?
+                        };
+                        Some(&line[..pos + entry.name.len()])
+                    })
+                    .unwrap_or(entry.name.as_str());
+                let _ = writeln!(
+                    section,
+                    "{}\u{7f}{}\u{1}{},{}",
+                    text, entry.name, entry.line, offset
+                );
+            }
+
+            let _ = write!(
+                out,
+                "\u{c}\n{},{}\n{}",
+                file.to_string_lossy().replace('\\', "/"),
+                section.len(),
+                section
+            );
+        }
+
+        out
+    }
+}
+
+/// The enclosing scope of a qualified name, if it has one.
+fn enclosing_scope<'a>(qualified: &'a str, name: &str) -> Option<&'a str> {
+    let prefix = qualified.strip_suffix(name)?;
+    ["::", ".", "#", "\\"]
+        .iter()
+        .find_map(|sep| prefix.strip_suffix(sep))
+        .filter(|scope| !scope.is_empty())
+}
+
+/// Single-letter kind, following the letters Exuberant Ctags uses across languages.
+fn kind_letter(kind: SymbolKind) -> char {
+    match kind {
+        SymbolKind::Module => 'n',
+        SymbolKind::Function => 'f',
+        SymbolKind::Value => 'v',
+        SymbolKind::Type => 't',
+        SymbolKind::Record => 's',
+        SymbolKind::Union => 'u',
+        SymbolKind::Interface => 'i',
+        SymbolKind::Class => 'c',
+        SymbolKind::Member => 'm',
+    }
+}
+
+/// Scope field name for an enclosing symbol of the given kind.
+fn kind_name(kind: SymbolKind) -> &'static str {
+    match kind {
+        SymbolKind::Module => "namespace",
+        SymbolKind::Function => "function",
+        SymbolKind::Value => "variable",
+        SymbolKind::Type => "typedef",
+        SymbolKind::Record => "struct",
+        SymbolKind::Union => "union",
+        SymbolKind::Interface => "interface",
+        SymbolKind::Class => "class",
+        SymbolKind::Member => "member",
+    }
+}
+
+/// Extension field values can't contain tabs or newlines.
+fn sanitize_field(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Location, Visibility};
+
+    fn symbol(name: &str, qualified: &str, kind: SymbolKind, file: &str, line: u32) -> Symbol {
+        Symbol::new(
+            name.to_string(),
+            qualified.to_string(),
+            kind,
+            Location::new(PathBuf::from(file), line, 5),
+            Visibility::Public,
+            "rust".to_string(),
+        )
+    }
+
+    #[test]
+    fn writes_sorted_ctags_with_extension_fields() {
+        let symbols = vec![
+            symbol(
+                "Point",
+                "geo::Point",
+                SymbolKind::Class,
+                "/ws/src/geo.rs",
+                3,
+            ),
+            symbol(
+                "new",
+                "geo::Point::new",
+                SymbolKind::Function,
+                "/ws/src/geo.rs",
+                8,
+            )
+            .with_signature(Some("fn new(x: f64,\n\ty: f64) -> Self".to_string())),
+            symbol("geo", "geo", SymbolKind::Module, "/ws/src/geo.rs", 1),
+        ];
+
+        let tags = Tags::from_symbols(&symbols, Path::new("/ws"));
+        let ctags = tags.to_ctags("1.0.0");
+        let lines: Vec<&str> = ctags.lines().filter(|l| !l.starts_with("!_")).collect();
+
+        assert!(ctags.starts_with("!_TAG_FILE_FORMAT\t2\t"));
+        assert_eq!(
+            lines,
+            vec![
+                "Point\tsrc/geo.rs\t3;\"\tc\tline:3\tnamespace:geo",
+                "geo\tsrc/geo.rs\t1;\"\tn\tline:1",
+                "new\tsrc/geo.rs\t8;\"\tf\tline:8\tclass:geo::Point\tsignature:fn new(x: f64, y: f64) -> Self",
+            ]
+        );
+    }
+
+    #[test]
+    fn writes_etags_sections_with_byte_offsets() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("lib.rs"),
+            "// header\npub fn alpha() {}\npub struct Beta;\npub fn f() {}\n",
+        )
+        .unwrap();
+        let symbols = vec![
+            symbol("alpha", "alpha", SymbolKind::Function, "lib.rs", 2),
+            symbol("Beta", "Beta", SymbolKind::Class, "lib.rs", 3),
+            Symbol {
+                location: Location::new(PathBuf::from("lib.rs"), 4, 8),
+                ..symbol("f", "f", SymbolKind::Function, "lib.rs", 4)
+            },
+        ];
+
+        let etags = Tags::from_symbols(&symbols, dir.path()).to_etags(dir.path());
+
+        let section = "pub fn alpha\u{7f}alpha\u{1}2,10\n\
+                       pub struct Beta\u{7f}Beta\u{1}3,28\n\
+                       pub fn f\u{7f}f\u{1}4,45\n";
+        assert_eq!(
+            etags,
+            format!("\u{c}\nlib.rs,{}\n{}", section.len(), section)
+        );
+    }
+}
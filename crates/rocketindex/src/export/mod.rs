@@ -0,0 +1,15 @@
+//! Export the index to formats understood by other code intelligence tools.
+//!
+//! - [`scip`]: SCIP protobuf indexes for Sourcegraph and other SCIP tooling
+//! - [`ctags`]: `tags` / `TAGS` files for vim, emacs and other ctags consumers
+//!
+//! Symbols are identified across formats by [`moniker`], which derives a
+//! stable, globally unique symbol string from a symbol's qualified name.
+
+pub mod ctags;
+pub mod moniker;
+pub mod scip;
+
+pub use ctags::{TagEntry, Tags};
+pub use moniker::symbol_moniker;
+pub use scip::{ScipDocument, ScipIndex, ScipOccurrence, ScipSymbolInformation};
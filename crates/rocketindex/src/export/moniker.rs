@@ -0,0 +1,216 @@
+//! Moniker generation: globally unique, stable identifiers for symbols.
+//!
+//! Monikers follow the SCIP symbol grammar:
+//!
+//! ```text
+//! rocketindex <language> <package> . <descriptors>
+//! ```
+//!
+//! The package is the workspace name and the version is left as the `.`
+//! placeholder. Descriptors are derived from the qualified name: enclosing
+//! types become type descriptors (`Name#`), any other enclosing segment becomes
+//! a namespace (`Name/`), and the symbol itself gets a suffix based on its kind
+//! (`name().` for functions, `name.` for values and members).
+
+use crate::{CodeIndex, Symbol, SymbolKind};
+
+/// Scheme prefix shared by every moniker rocketindex produces.
+pub const SCHEME: &str = "rocketindex";
+
+/// Separators used between qualified-name segments across supported languages.
+const SEPARATORS: [&str; 4] = ["::", ".", "#", "\\"];
+
+/// Generate the moniker for a symbol.
+///
+/// `index` is consulted to decide whether each enclosing segment of the
+/// qualified name is a type or a namespace.
+///
+/// # Examples
+///
+/// ```
+/// use rocketindex::export::symbol_moniker;
+/// use rocketindex::{CodeIndex, Location, Symbol, SymbolKind, Visibility};
+/// use std::path::PathBuf;
+///
+/// let symbol = Symbol::new(
+///     "process".to_string(),
+///     "MyApp.Orders.process".to_string(),
+///     SymbolKind::Function,
+///     Location::new(PathBuf::from("src/Orders.fs"), 3, 5),
+///     Visibility::Public,
+///     "fsharp".to_string(),
+/// );
+///
+/// let index = CodeIndex::new();
+/// assert_eq!(
+///     symbol_moniker(&index, &symbol, "shop"),
+///     "rocketindex fsharp shop . MyApp/Orders/process()."
+/// );
+/// ```
+#[must_use]
+pub fn symbol_moniker(index: &CodeIndex, symbol: &Symbol, package: &str) -> String {
+    let segments = split_qualified(&symbol.qualified);
+    let mut descriptors = String::new();
+
+    for (i, (prefix, segment)) in segments.iter().enumerate() {
+        let name = escape_identifier(segment);
+        if i + 1 == segments.len() {
+            descriptors.push_str(&name);
+            descriptors.push_str(descriptor_suffix(symbol.kind));
+        } else if index.get(prefix).is_some_and(|s| is_type_kind(s.kind)) {
+            descriptors.push_str(&name);
+            descriptors.push('#');
+        } else {
+            descriptors.push_str(&name);
+            descriptors.push('/');
+        }
+    }
+
+    format!(
+        "{} {} {} . {}",
+        SCHEME,
+        escape_package(&symbol.language),
+        escape_package(package),
+        descriptors
+    )
+}
+
+/// Package name for monikers: the workspace root's directory name.
+#[must_use]
+pub fn package_name(index: &CodeIndex) -> String {
+    index
+        .workspace_root()
+        .and_then(|root| root.file_name())
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| ".".to_string())
+}
+
+/// Split a qualified name into `(prefix, segment)` pairs, where `prefix` is
+/// the qualified name up to and including the segment.
+fn split_qualified(qualified: &str) -> Vec<(&str, &str)> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+
+    while i < qualified.len() {
+        if let Some(sep) = SEPARATORS.iter().find(|s| qualified[i..].starts_with(**s)) {
+            if i > start {
+                parts.push((&qualified[..i], &qualified[start..i]));
+            }
+            i += sep.len();
+            start = i;
+        } else {
+            i += qualified[i..].chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    if start < qualified.len() {
+        parts.push((qualified, &qualified[start..]));
+    }
+
+    parts
+}
+
+fn is_type_kind(kind: SymbolKind) -> bool {
+    matches!(
+        kind,
+        SymbolKind::Type
+            | SymbolKind::Record
+            | SymbolKind::Union
+            | SymbolKind::Interface
+            | SymbolKind::Class
+    )
+}
+
+fn descriptor_suffix(kind: SymbolKind) -> &'static str {
+    match kind {
+        SymbolKind::Module => "/",
+        SymbolKind::Function => "().",
+        SymbolKind::Value | SymbolKind::Member => ".",
+        SymbolKind::Type
+        | SymbolKind::Record
+        | SymbolKind::Union
+        | SymbolKind::Interface
+        | SymbolKind::Class => "#",
+    }
+}
+
+/// Escape a descriptor name, backtick-quoting anything that isn't a simple identifier.
+fn escape_identifier(name: &str) -> String {
+    let simple = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '+' | '-' | '$'));
+    if simple {
+        name.to_string()
+    } else {
+        format!("`{}`", name.replace('`', "``"))
+    }
+}
+
+/// Escape a package component: spaces are doubled and empty values become `.`.
+fn escape_package(value: &str) -> String {
+    if value.is_empty() {
+        ".".to_string()
+    } else {
+        value.replace(' ', "  ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Location, Visibility};
+    use std::path::PathBuf;
+
+    fn symbol(name: &str, qualified: &str, kind: SymbolKind, language: &str) -> Symbol {
+        Symbol::new(
+            name.to_string(),
+            qualified.to_string(),
+            kind,
+            Location::new(PathBuf::from("src/lib"), 1, 1),
+            Visibility::Public,
+            language.to_string(),
+        )
+    }
+
+    #[test]
+    fn member_of_indexed_type_uses_type_descriptor() {
+        let mut index = CodeIndex::new();
+        index.add_symbol(symbol("MyApp", "MyApp", SymbolKind::Module, "fsharp"));
+        index.add_symbol(symbol(
+            "Person",
+            "MyApp.Person",
+            SymbolKind::Record,
+            "fsharp",
+        ));
+        let field = symbol("Name", "MyApp.Person.Name", SymbolKind::Member, "fsharp");
+
+        assert_eq!(
+            symbol_moniker(&index, &field, "app"),
+            "rocketindex fsharp app . MyApp/Person#Name."
+        );
+    }
+
+    #[test]
+    fn splits_rust_paths() {
+        let mut index = CodeIndex::new();
+        index.add_symbol(symbol("Point", "geo::Point", SymbolKind::Class, "rust"));
+        let method = symbol("new", "geo::Point::new", SymbolKind::Function, "rust");
+
+        assert_eq!(
+            symbol_moniker(&index, &method, "geo"),
+            "rocketindex rust geo . geo/Point#new()."
+        );
+    }
+
+    #[test]
+    fn escapes_non_identifier_names() {
+        let index = CodeIndex::new();
+        let op = symbol("(|>)", "Ops.(|>)", SymbolKind::Function, "fsharp");
+
+        assert_eq!(
+            symbol_moniker(&index, &op, "my project"),
+            "rocketindex fsharp my  project . Ops/`(|>)`()."
+        );
+    }
+}
@@ -0,0 +1,512 @@
+//! SCIP (SCIP Code Intelligence Protocol) export.
+//!
+//! Converts a [`CodeIndex`] into a SCIP index so it can be uploaded to
+//! Sourcegraph or consumed by other SCIP tooling. Definitions become
+//! occurrences with the definition role, and references are included when
+//! they resolve to an indexed symbol.
+//!
+//! The protobuf encoding is written by hand against the published
+//! `scip.proto` schema; only the fields rocketindex can populate are emitted.
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use super::moniker::{package_name, symbol_moniker};
+use crate::{CodeIndex, Location, Reference, Symbol, SymbolKind};
+
+/// `SymbolRole.Definition` from `scip.proto`.
+pub const SYMBOL_ROLE_DEFINITION: i32 = 1;
+
+/// `TextEncoding.UTF8` from `scip.proto`.
+const TEXT_ENCODING_UTF8: i32 = 1;
+
+/// `PositionEncoding.UTF8CodeUnitOffsetFromLineStart` (tree-sitter columns are byte offsets).
+const POSITION_ENCODING_UTF8: i32 = 1;
+
+/// A SCIP index ready to be encoded.
+#[derive(Debug, Clone, Default)]
+pub struct ScipIndex {
+    /// Workspace root as a `file://` URI
+    pub project_root: String,
+    /// Version of the tool that produced the index
+    pub tool_version: String,
+    /// One document per indexed file, sorted by path
+    pub documents: Vec<ScipDocument>,
+}
+
+/// A single source file in a SCIP index.
+#[derive(Debug, Clone, Default)]
+pub struct ScipDocument {
+    /// Path relative to the project root, using `/` separators
+    pub relative_path: String,
+    /// SCIP language name (e.g. "FSharp", "Rust")
+    pub language: String,
+    pub occurrences: Vec<ScipOccurrence>,
+    pub symbols: Vec<ScipSymbolInformation>,
+}
+
+/// A definition or reference of a symbol at a source range.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ScipOccurrence {
+    /// 0-based `[start_line, start_char, end_char]` or
+    /// `[start_line, start_char, end_line, end_char]`
+    pub range: Vec<i32>,
+    /// Moniker of the symbol
+    pub symbol: String,
+    /// Bitset of symbol roles (see [`SYMBOL_ROLE_DEFINITION`])
+    pub symbol_roles: i32,
+}
+
+/// Metadata for a symbol defined in a document.
+#[derive(Debug, Clone, Default)]
+pub struct ScipSymbolInformation {
+    /// Moniker of the symbol
+    pub symbol: String,
+    /// Markdown documentation (signature first, then doc comment)
+    pub documentation: Vec<String>,
+    /// `SymbolInformation.Kind` value from `scip.proto`
+    pub kind: i32,
+    /// Short name shown in UIs
+    pub display_name: String,
+    /// Monikers of interfaces/base types this symbol implements
+    pub implementations: Vec<String>,
+}
+
+impl ScipIndex {
+    /// Build a SCIP index from a code index.
+    #[must_use]
+    pub fn from_index(index: &CodeIndex, tool_version: &str) -> Self {
+        let package = package_name(index);
+        let project_root = index
+            .workspace_root()
+            .map(|root| format!("file://{}", root.display()))
+            .unwrap_or_default();
+
+        let mut files: Vec<_> = index.files().cloned().collect();
+        files.sort();
+
+        let documents = files
+            .iter()
+            .map(|file| build_document(index, file, &package))
+            .collect();
+
+        Self {
+            project_root,
+            tool_version: tool_version.to_string(),
+            documents,
+        }
+    }
+
+    /// Total number of symbols defined across all documents.
+    #[must_use]
+    pub fn symbol_count(&self) -> usize {
+        self.documents.iter().map(|d| d.symbols.len()).sum()
+    }
+
+    /// Total number of occurrences (definitions and references).
+    #[must_use]
+    pub fn occurrence_count(&self) -> usize {
+        self.documents.iter().map(|d| d.occurrences.len()).sum()
+    }
+
+    /// Encode the index as a SCIP protobuf message.
+    #[must_use]
+    pub fn encode(&self) -> Vec<u8> {
+        let mut w = ProtoWriter::default();
+
+        // Index.metadata = 1
+        w.message(1, |m| {
+            // Metadata.tool_info = 2
+            m.message(2, |t| {
+                t.string(1, "rocketindex");
+                t.string(2, &self.tool_version);
+            });
+            m.string(3, &self.project_root);
+            m.int32(4, TEXT_ENCODING_UTF8);
+        });
+
+        // Index.documents = 2
+        for doc in &self.documents {
+            w.message(2, |d| {
+                d.string(1, &doc.relative_path);
+                for occ in &doc.occurrences {
+                    d.message(2, |o| {
+                        o.packed_int32(1, &occ.range);
+                        o.string(2, &occ.symbol);
+                        o.int32(3, occ.symbol_roles);
+                    });
+                }
+                for info in &doc.symbols {
+                    d.message(3, |s| {
+                        s.string(1, &info.symbol);
+                        for line in &info.documentation {
+                            s.string(3, line);
+                        }
+                        for target in &info.implementations {
+                            s.message(4, |r| {
+                                r.string(1, target);
+                                r.bool(3, true);
+                            });
+                        }
+                        s.int32(5, info.kind);
+                        s.string(6, &info.display_name);
+                    });
+                }
+                d.string(4, &doc.language);
+                d.int32(6, POSITION_ENCODING_UTF8);
+            });
+        }
+
+        w.buf
+    }
+}
+
+fn build_document(index: &CodeIndex, file: &Path, package: &str) -> ScipDocument {
+    let mut symbols: Vec<&Symbol> = index
+        .symbols_in_file(file)
+        .into_iter()
+        .filter(|s| s.location.file == file)
+        .collect();
+    symbols.sort_by_key(|s| (s.location.line, s.location.column));
+
+    let mut seen = HashSet::new();
+    let mut occurrences = Vec::new();
+    let mut infos = Vec::new();
+    let mut language = String::new();
+
+    for symbol in &symbols {
+        let moniker = symbol_moniker(index, symbol, package);
+        language = scip_language(&symbol.language);
+
+        let occurrence = ScipOccurrence {
+            range: scip_range(&symbol.location),
+            symbol: moniker.clone(),
+            symbol_roles: SYMBOL_ROLE_DEFINITION,
+        };
+        if seen.insert(occurrence.range.clone()) {
+            occurrences.push(occurrence);
+        }
+
+        infos.push(ScipSymbolInformation {
+            symbol: moniker,
+            documentation: documentation(symbol),
+            kind: scip_kind(symbol.kind),
+            display_name: symbol.name.clone(),
+            implementations: implementations(index, symbol, package),
+        });
+    }
+
+    for reference in index.references_in_file(file) {
+        let resolved = index
+            .resolve(&reference.name, file)
+            .or_else(|| index.resolve_dotted(&reference.name, file))
+            .map(|result| result.symbol)
+            // Not every language has a resolver; fall back to exact qualified matches
+            .or_else(|| index.get(&reference.name));
+        let Some(target) = resolved else {
+            continue;
+        };
+
+        let range = scip_range(&reference_span(reference));
+        // Definitions already cover their own location
+        if !seen.insert(range.clone()) {
+            continue;
+        }
+        occurrences.push(ScipOccurrence {
+            range,
+            symbol: symbol_moniker(index, target, package),
+            symbol_roles: 0,
+        });
+    }
+
+    occurrences.sort_by(|a, b| a.range.cmp(&b.range));
+
+    ScipDocument {
+        relative_path: file.to_string_lossy().replace('\\', "/"),
+        language,
+        occurrences,
+        symbols: infos,
+    }
+}
+
+/// References loaded from SQLite only store their start position; derive the
+/// end from the referenced name.
+fn reference_span(reference: &Reference) -> Location {
+    let mut location = reference.location.clone();
+    if location.end_line <= location.line && location.end_column <= location.column {
+        location.end_line = location.line;
+        location.end_column = location.column + reference.name.len() as u32;
+    }
+    location
+}
+
+/// Convert a 1-indexed location into a 0-based SCIP range.
+fn scip_range(location: &Location) -> Vec<i32> {
+    let start_line = location.line.saturating_sub(1) as i32;
+    let start_char = location.column.saturating_sub(1) as i32;
+    let end_line = location.end_line.max(location.line).saturating_sub(1) as i32;
+    let end_char = location.end_column.saturating_sub(1) as i32;
+
+    if end_line == start_line {
+        vec![start_line, start_char, end_char.max(start_char)]
+    } else {
+        vec![start_line, start_char, end_line, end_char]
+    }
+}
+
+fn documentation(symbol: &Symbol) -> Vec<String> {
+    let mut docs = Vec::new();
+    if let Some(signature) = &symbol.signature {
+        docs.push(format!("```{}\n{}\n```", symbol.language, signature));
+    }
+    if let Some(doc) = &symbol.doc {
+        docs.push(doc.clone());
+    }
+    docs
+}
+
+/// Resolve implemented interfaces (and base types, for types) to monikers.
+fn implementations(index: &CodeIndex, symbol: &Symbol, package: &str) -> Vec<String> {
+    let mut targets: Vec<&String> = symbol.implements.iter().flatten().collect();
+    if scip_kind(symbol.kind) == KIND_CLASS {
+        targets.extend(symbol.parent.iter());
+    }
+
+    targets
+        .into_iter()
+        .filter_map(|name| {
+            index
+                .resolve(name, &symbol.location.file)
+                .or_else(|| index.resolve_dotted(name, &symbol.location.file))
+        })
+        .map(|result| symbol_moniker(index, result.symbol, package))
+        .collect()
+}
+
+// `SymbolInformation.Kind` values from scip.proto
+const KIND_CLASS: i32 = 7;
+const KIND_ENUM: i32 = 11;
+const KIND_FIELD: i32 = 15;
+const KIND_FUNCTION: i32 = 17;
+const KIND_INTERFACE: i32 = 21;
+const KIND_MODULE: i32 = 29;
+const KIND_STRUCT: i32 = 49;
+const KIND_TYPE: i32 = 54;
+const KIND_VALUE: i32 = 60;
+
+fn scip_kind(kind: SymbolKind) -> i32 {
+    match kind {
+        SymbolKind::Module => KIND_MODULE,
+        SymbolKind::Function => KIND_FUNCTION,
+        SymbolKind::Value => KIND_VALUE,
+        SymbolKind::Type => KIND_TYPE,
+        SymbolKind::Record => KIND_STRUCT,
+        SymbolKind::Union => KIND_ENUM,
+        SymbolKind::Interface => KIND_INTERFACE,
+        SymbolKind::Class => KIND_CLASS,
+        SymbolKind::Member => KIND_FIELD,
+    }
+}
+
+/// Map rocketindex language identifiers to SCIP `Language` names.
+fn scip_language(language: &str) -> String {
+    match language {
+        "c" => "C",
+        "cpp" => "CPP",
+        "csharp" => "CSharp",
+        "fsharp" => "FSharp",
+        "go" => "Go",
+        "haxe" => "Haxe",
+        "java" => "Java",
+        "javascript" => "JavaScript",
+        "kotlin" => "Kotlin",
+        "objc" => "Objective_C",
+        "php" => "PHP",
+        "python" => "Python",
+        "ruby" => "Ruby",
+        "rust" => "Rust",
+        "swift" => "Swift",
+        "typescript" => "TypeScript",
+        other => other,
+    }
+    .to_string()
+}
+
+/// Minimal protobuf wire-format writer.
+#[derive(Default)]
+struct ProtoWriter {
+    buf: Vec<u8>,
+}
+
+impl ProtoWriter {
+    fn varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.buf.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        self.buf.push(value as u8);
+    }
+
+    fn key(&mut self, field: u32, wire_type: u8) {
+        self.varint((u64::from(field) << 3) | u64::from(wire_type));
+    }
+
+    fn bytes(&mut self, field: u32, value: &[u8]) {
+        self.key(field, 2);
+        self.varint(value.len() as u64);
+        self.buf.extend_from_slice(value);
+    }
+
+    /// proto3 scalar: empty strings are omitted.
+    fn string(&mut self, field: u32, value: &str) {
+        if !value.is_empty() {
+            self.bytes(field, value.as_bytes());
+        }
+    }
+
+    /// proto3 scalar: zero is omitted.
+    fn int32(&mut self, field: u32, value: i32) {
+        if value != 0 {
+            self.key(field, 0);
+            // Negative int32 values are sign-extended to 64 bits
+            self.varint(i64::from(value) as u64);
+        }
+    }
+
+    fn bool(&mut self, field: u32, value: bool) {
+        if value {
+            self.key(field, 0);
+            self.varint(1);
+        }
+    }
+
+    fn packed_int32(&mut self, field: u32, values: &[i32]) {
+        if values.is_empty() {
+            return;
+        }
+        let mut inner = ProtoWriter::default();
+        for value in values {
+            inner.varint(i64::from(*value) as u64);
+        }
+        self.bytes(field, &inner.buf);
+    }
+
+    fn message(&mut self, field: u32, build: impl FnOnce(&mut ProtoWriter)) {
+        let mut inner = ProtoWriter::default();
+        build(&mut inner);
+        self.bytes(field, &inner.buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ReferenceKind, Visibility};
+    use std::path::PathBuf;
+
+    fn sample_index() -> CodeIndex {
+        let mut index = CodeIndex::with_root(PathBuf::from("/work/shop"));
+        index.add_symbol(
+            Symbol::new(
+                "total".to_string(),
+                "Shop.Cart.total".to_string(),
+                SymbolKind::Function,
+                Location::with_end(PathBuf::from("/work/shop/src/Cart.fs"), 3, 5, 3, 10),
+                Visibility::Public,
+                "fsharp".to_string(),
+            )
+            .with_signature(Some("Item list -> decimal".to_string())),
+        );
+        index.add_reference(
+            PathBuf::from("/work/shop/src/Cart.fs"),
+            Reference {
+                name: "total".to_string(),
+                location: Location::with_end(PathBuf::from("/work/shop/src/Cart.fs"), 7, 13, 7, 18),
+                kind: ReferenceKind::Read,
+            },
+        );
+        index.add_reference(
+            PathBuf::from("/work/shop/src/Cart.fs"),
+            Reference {
+                name: "Shop.Cart.total".to_string(),
+                location: Location::new(PathBuf::from("/work/shop/src/Cart.fs"), 9, 5),
+                kind: ReferenceKind::Read,
+            },
+        );
+        index.add_reference(
+            PathBuf::from("/work/shop/src/Cart.fs"),
+            Reference {
+                name: "List.sum".to_string(),
+                location: Location::new(PathBuf::from("/work/shop/src/Cart.fs"), 4, 5),
+                kind: ReferenceKind::Read,
+            },
+        );
+        index
+    }
+
+    #[test]
+    fn builds_documents_with_definitions_and_resolved_references() {
+        let scip = ScipIndex::from_index(&sample_index(), "1.0.0");
+
+        assert_eq!(scip.project_root, "file:///work/shop");
+        assert_eq!(scip.documents.len(), 1);
+
+        let doc = &scip.documents[0];
+        assert_eq!(doc.relative_path, "src/Cart.fs");
+        assert_eq!(doc.language, "FSharp");
+        assert_eq!(doc.symbols.len(), 1);
+        assert_eq!(doc.symbols[0].kind, KIND_FUNCTION);
+        assert_eq!(
+            doc.symbols[0].documentation,
+            vec!["```fsharp\nItem list -> decimal\n```".to_string()]
+        );
+
+        let moniker = "rocketindex fsharp shop . Shop/Cart/total().";
+        assert_eq!(
+            doc.occurrences,
+            vec![
+                ScipOccurrence {
+                    range: vec![2, 4, 9],
+                    symbol: moniker.to_string(),
+                    symbol_roles: SYMBOL_ROLE_DEFINITION,
+                },
+                // Unresolved `List.sum` reference is skipped
+                ScipOccurrence {
+                    range: vec![6, 12, 17],
+                    symbol: moniker.to_string(),
+                    symbol_roles: 0,
+                },
+                // Start-only location (as loaded from SQLite) spans the name
+                ScipOccurrence {
+                    range: vec![8, 4, 19],
+                    symbol: moniker.to_string(),
+                    symbol_roles: 0,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn encodes_protobuf_wire_format() {
+        let mut w = ProtoWriter::default();
+        w.string(1, "hi");
+        w.int32(2, 300);
+        w.packed_int32(3, &[1, 2]);
+        w.string(4, "");
+        assert_eq!(
+            w.buf,
+            vec![0x0a, 2, b'h', b'i', 0x10, 0xac, 0x02, 0x1a, 2, 1, 2]
+        );
+    }
+
+    #[test]
+    fn encoded_index_contains_document_and_symbol() {
+        let bytes = ScipIndex::from_index(&sample_index(), "1.0.0").encode();
+
+        // Index.metadata (field 1, length-delimited) comes first
+        assert_eq!(bytes[0], 0x0a);
+        let haystack = String::from_utf8_lossy(&bytes);
+        assert!(haystack.contains("src/Cart.fs"));
+        assert!(haystack.contains("rocketindex fsharp shop . Shop/Cart/total()."));
+    }
+}
@@ -1,32 +1,69 @@
 //! External assembly indexer for .NET libraries.
 //!
-//! **Current Status: Stub Implementation**
+//! Symbols and type members for code the workspace references but doesn't
+//! contain, so completion and hover work on framework types without the
+//! fsi-based type extractor:
 //!
-//! This module provides basic symbol information for common .NET BCL types.
-//! It does NOT read actual .NET assemblies - instead it provides hardcoded
-//! type definitions for the most commonly used System.* types.
+//! - A bundled manifest of the .NET BCL (`System.String`, `System.Console`,
+//!   `System.Collections.Generic.List<T>`, ...) with member signatures
+//! - Manifests exported from reference assemblies (NuGet packages, or a
+//!   fuller BCL) placed in `.rocketindex/assemblies/*.json`, one per
+//!   assembly:
 //!
-//! This enables basic completion and hover for types like:
-//! - System.String, System.Int32, System.Boolean
-//! - System.Console (WriteLine, ReadLine)
-//! - System.Collections.Generic (List, Dictionary)
-//! - System.IO (File, Path, Directory)
-//! - System.Linq (common extension methods)
+//! ```json
+//! { "assembly": "Newtonsoft.Json",
+//!   "types": [{ "name": "Newtonsoft.Json.JsonConvert",
+//!               "members": [{ "name": "SerializeObject",
+//!                             "type": "obj -> string",
+//!                             "kind": "method" }] }] }
+//! ```
 //!
-//! For full external assembly support, consider using fsautocomplete alongside
-//! fsharp-tools.
-//!
-//! ## Future Work
-//!
-//! A proper implementation would:
-//! 1. Parse .dll files using PE metadata readers
-//! 2. Extract public type and member information
-//! 3. Handle NuGet package resolution
+//! Packages without a manifest are still known by name, so unresolved
+//! names can be attributed to them (see [`ExternalIndex::package_of`]).
 
+use crate::fsproj::PackageReference;
+use crate::type_cache::{MemberKind, TypeMember};
 use crate::{Location, Symbol, SymbolKind, Visibility};
+use serde::Deserialize;
 use std::collections::HashMap;
 use std::path::Path;
 
+/// Directory under `.rocketindex/` holding exported assembly manifests
+pub const ASSEMBLY_MANIFEST_DIR: &str = "assemblies";
+
+/// Public members of a reference assembly's types, as exported from its
+/// metadata
+#[derive(Debug, Clone, Deserialize)]
+pub struct AssemblyManifest {
+    /// Assembly name (e.g., "System.Runtime")
+    pub assembly: String,
+    /// Types the assembly defines
+    #[serde(default)]
+    pub types: Vec<ManifestType>,
+}
+
+/// A type in an [`AssemblyManifest`]
+#[derive(Debug, Clone, Deserialize)]
+pub struct ManifestType {
+    /// Fully qualified name, with generic arity (e.g., "System.Collections.Generic.List`1")
+    pub name: String,
+    /// Public members
+    #[serde(default)]
+    pub members: Vec<ManifestMember>,
+}
+
+/// A member of a [`ManifestType`]
+#[derive(Debug, Clone, Deserialize)]
+pub struct ManifestMember {
+    /// Member name
+    pub name: String,
+    /// F# signature of the member
+    #[serde(rename = "type")]
+    pub member_type: String,
+    /// Kind of member
+    pub kind: MemberKind,
+}
+
 /// External symbol from a .NET assembly
 #[derive(Debug, Clone)]
 pub struct ExternalSymbol {
@@ -41,8 +78,286 @@ pub struct ExternalSymbol {
 pub struct ExternalIndex {
     /// Symbols indexed by qualified name
     symbols: HashMap<String, ExternalSymbol>,
+    /// Packages the workspace references, for [`package_of`](Self::package_of)
+    packages: Vec<PackageReference>,
+    /// Members by fully qualified type name
+    members: HashMap<String, Vec<TypeMember>>,
+    /// Other names code uses for a type (`String`, `string`, `ResizeArray`)
+    /// -> its fully qualified name
+    type_names: HashMap<String, String>,
 }
 
+/// F# abbreviations for framework types
+const FSHARP_ABBREVIATIONS: &[(&str, &str)] = &[
+    ("obj", "System.Object"),
+    ("string", "System.String"),
+    ("int", "System.Int32"),
+    ("int64", "System.Int64"),
+    ("float", "System.Double"),
+    ("float32", "System.Single"),
+    ("bool", "System.Boolean"),
+    ("char", "System.Char"),
+    ("byte", "System.Byte"),
+    ("exn", "System.Exception"),
+    ("ResizeArray", "System.Collections.Generic.List`1"),
+];
+
+/// A bundled member: (name, F# signature, kind)
+type BundledMember = (&'static str, &'static str, MemberKind);
+
+/// Bundled manifest of commonly used BCL members: (type, assembly, members).
+#[rustfmt::skip]
+const BCL_MEMBERS: &[(&str, &str, &[BundledMember])] = {
+    use MemberKind::{Method, Property};
+    &[
+        ("System.Object", "System.Runtime", &[
+            ("ToString", "unit -> string", Method),
+            ("Equals", "obj -> bool", Method),
+            ("GetHashCode", "unit -> int", Method),
+            ("GetType", "unit -> System.Type", Method),
+        ]),
+        ("System.String", "System.Runtime", &[
+            ("Length", "int", Property),
+            ("Chars", "int -> char", Property),
+            ("Contains", "string -> bool", Method),
+            ("StartsWith", "string -> bool", Method),
+            ("EndsWith", "string -> bool", Method),
+            ("IndexOf", "string -> int", Method),
+            ("LastIndexOf", "string -> int", Method),
+            ("Substring", "int * int -> string", Method),
+            ("Replace", "string * string -> string", Method),
+            ("Split", "char[] -> string[]", Method),
+            ("Trim", "unit -> string", Method),
+            ("TrimStart", "unit -> string", Method),
+            ("TrimEnd", "unit -> string", Method),
+            ("ToUpper", "unit -> string", Method),
+            ("ToLower", "unit -> string", Method),
+            ("PadLeft", "int -> string", Method),
+            ("PadRight", "int -> string", Method),
+            ("Insert", "int * string -> string", Method),
+            ("Remove", "int -> string", Method),
+            ("Equals", "string -> bool", Method),
+            ("ToCharArray", "unit -> char[]", Method),
+            ("IsNullOrEmpty", "string -> bool", Method),
+            ("IsNullOrWhiteSpace", "string -> bool", Method),
+            ("Join", "string * seq<string> -> string", Method),
+            ("Concat", "seq<string> -> string", Method),
+            ("Format", "string * obj[] -> string", Method),
+            ("Empty", "string", Property),
+        ]),
+        ("System.Int32", "System.Runtime", &[
+            ("Parse", "string -> int", Method),
+            ("TryParse", "string * byref<int> -> bool", Method),
+            ("MaxValue", "int", Property),
+            ("MinValue", "int", Property),
+            ("CompareTo", "int -> int", Method),
+        ]),
+        ("System.Double", "System.Runtime", &[
+            ("Parse", "string -> float", Method),
+            ("TryParse", "string * byref<float> -> bool", Method),
+            ("IsNaN", "float -> bool", Method),
+            ("IsInfinity", "float -> bool", Method),
+            ("MaxValue", "float", Property),
+            ("MinValue", "float", Property),
+        ]),
+        ("System.Boolean", "System.Runtime", &[
+            ("Parse", "string -> bool", Method),
+            ("TryParse", "string * byref<bool> -> bool", Method),
+        ]),
+        ("System.DateTime", "System.Runtime", &[
+            ("Now", "System.DateTime", Property),
+            ("UtcNow", "System.DateTime", Property),
+            ("Today", "System.DateTime", Property),
+            ("Year", "int", Property),
+            ("Month", "int", Property),
+            ("Day", "int", Property),
+            ("Hour", "int", Property),
+            ("Minute", "int", Property),
+            ("Second", "int", Property),
+            ("DayOfWeek", "System.DayOfWeek", Property),
+            ("Date", "System.DateTime", Property),
+            ("AddDays", "float -> System.DateTime", Method),
+            ("AddHours", "float -> System.DateTime", Method),
+            ("AddMinutes", "float -> System.DateTime", Method),
+            ("AddSeconds", "float -> System.DateTime", Method),
+            ("Subtract", "System.DateTime -> System.TimeSpan", Method),
+            ("ToString", "string -> string", Method),
+            ("Parse", "string -> System.DateTime", Method),
+            ("TryParse", "string * byref<System.DateTime> -> bool", Method),
+        ]),
+        ("System.TimeSpan", "System.Runtime", &[
+            ("TotalMilliseconds", "float", Property),
+            ("TotalSeconds", "float", Property),
+            ("TotalMinutes", "float", Property),
+            ("TotalHours", "float", Property),
+            ("TotalDays", "float", Property),
+            ("FromMilliseconds", "float -> System.TimeSpan", Method),
+            ("FromSeconds", "float -> System.TimeSpan", Method),
+            ("FromMinutes", "float -> System.TimeSpan", Method),
+            ("FromHours", "float -> System.TimeSpan", Method),
+            ("Zero", "System.TimeSpan", Property),
+        ]),
+        ("System.Guid", "System.Runtime", &[
+            ("NewGuid", "unit -> System.Guid", Method),
+            ("Parse", "string -> System.Guid", Method),
+            ("TryParse", "string * byref<System.Guid> -> bool", Method),
+            ("Empty", "System.Guid", Property),
+        ]),
+        ("System.Exception", "System.Runtime", &[
+            ("Message", "string", Property),
+            ("InnerException", "exn", Property),
+            ("StackTrace", "string", Property),
+            ("Data", "System.Collections.IDictionary", Property),
+        ]),
+        ("System.Math", "System.Runtime", &[
+            ("Abs", "float -> float", Method),
+            ("Max", "float * float -> float", Method),
+            ("Min", "float * float -> float", Method),
+            ("Round", "float -> float", Method),
+            ("Floor", "float -> float", Method),
+            ("Ceiling", "float -> float", Method),
+            ("Sqrt", "float -> float", Method),
+            ("Pow", "float * float -> float", Method),
+            ("PI", "float", Property),
+        ]),
+        ("System.Console", "System.Console", &[
+            ("WriteLine", "string -> unit", Method),
+            ("Write", "string -> unit", Method),
+            ("ReadLine", "unit -> string", Method),
+            ("ReadKey", "unit -> System.ConsoleKeyInfo", Method),
+            ("Clear", "unit -> unit", Method),
+            ("ForegroundColor", "System.ConsoleColor", Property),
+            ("Out", "System.IO.TextWriter", Property),
+            ("Error", "System.IO.TextWriter", Property),
+        ]),
+        ("System.IO.File", "System.IO", &[
+            ("ReadAllText", "string -> string", Method),
+            ("WriteAllText", "string * string -> unit", Method),
+            ("ReadAllLines", "string -> string[]", Method),
+            ("WriteAllLines", "string * seq<string> -> unit", Method),
+            ("ReadAllBytes", "string -> byte[]", Method),
+            ("AppendAllText", "string * string -> unit", Method),
+            ("Exists", "string -> bool", Method),
+            ("Delete", "string -> unit", Method),
+            ("Copy", "string * string -> unit", Method),
+            ("Move", "string * string -> unit", Method),
+            ("OpenRead", "string -> System.IO.FileStream", Method),
+        ]),
+        ("System.IO.Directory", "System.IO", &[
+            ("Exists", "string -> bool", Method),
+            ("CreateDirectory", "string -> System.IO.DirectoryInfo", Method),
+            ("Delete", "string -> unit", Method),
+            ("GetFiles", "string -> string[]", Method),
+            ("GetDirectories", "string -> string[]", Method),
+            ("GetCurrentDirectory", "unit -> string", Method),
+        ]),
+        ("System.IO.Path", "System.IO", &[
+            ("Combine", "string[] -> string", Method),
+            ("GetFileName", "string -> string", Method),
+            ("GetFileNameWithoutExtension", "string -> string", Method),
+            ("GetDirectoryName", "string -> string", Method),
+            ("GetExtension", "string -> string", Method),
+            ("GetFullPath", "string -> string", Method),
+            ("GetTempPath", "unit -> string", Method),
+            ("DirectorySeparatorChar", "char", Property),
+        ]),
+        ("System.Collections.Generic.List`1", "System.Collections", &[
+            ("Add", "'T -> unit", Method),
+            ("AddRange", "seq<'T> -> unit", Method),
+            ("Remove", "'T -> bool", Method),
+            ("RemoveAt", "int -> unit", Method),
+            ("Insert", "int * 'T -> unit", Method),
+            ("Clear", "unit -> unit", Method),
+            ("Contains", "'T -> bool", Method),
+            ("IndexOf", "'T -> int", Method),
+            ("Sort", "unit -> unit", Method),
+            ("Reverse", "unit -> unit", Method),
+            ("ToArray", "unit -> 'T[]", Method),
+            ("Count", "int", Property),
+            ("Item", "int -> 'T", Property),
+        ]),
+        ("System.Collections.Generic.Dictionary`2", "System.Collections", &[
+            ("Add", "'TKey * 'TValue -> unit", Method),
+            ("Remove", "'TKey -> bool", Method),
+            ("Clear", "unit -> unit", Method),
+            ("ContainsKey", "'TKey -> bool", Method),
+            ("ContainsValue", "'TValue -> bool", Method),
+            ("TryGetValue", "'TKey * byref<'TValue> -> bool", Method),
+            ("TryAdd", "'TKey * 'TValue -> bool", Method),
+            ("Count", "int", Property),
+            ("Keys", "Dictionary<'TKey, 'TValue>.KeyCollection", Property),
+            ("Values", "Dictionary<'TKey, 'TValue>.ValueCollection", Property),
+            ("Item", "'TKey -> 'TValue", Property),
+        ]),
+        ("System.Collections.Generic.HashSet`1", "System.Collections", &[
+            ("Add", "'T -> bool", Method),
+            ("Remove", "'T -> bool", Method),
+            ("Contains", "'T -> bool", Method),
+            ("Clear", "unit -> unit", Method),
+            ("UnionWith", "seq<'T> -> unit", Method),
+            ("IntersectWith", "seq<'T> -> unit", Method),
+            ("Count", "int", Property),
+        ]),
+        ("System.Collections.Generic.Queue`1", "System.Collections", &[
+            ("Enqueue", "'T -> unit", Method),
+            ("Dequeue", "unit -> 'T", Method),
+            ("Peek", "unit -> 'T", Method),
+            ("TryDequeue", "byref<'T> -> bool", Method),
+            ("Count", "int", Property),
+        ]),
+        ("System.Collections.Generic.Stack`1", "System.Collections", &[
+            ("Push", "'T -> unit", Method),
+            ("Pop", "unit -> 'T", Method),
+            ("Peek", "unit -> 'T", Method),
+            ("TryPop", "byref<'T> -> bool", Method),
+            ("Count", "int", Property),
+        ]),
+        ("System.Text.StringBuilder", "System.Runtime", &[
+            ("Append", "string -> System.Text.StringBuilder", Method),
+            ("AppendLine", "string -> System.Text.StringBuilder", Method),
+            ("AppendFormat", "string * obj[] -> System.Text.StringBuilder", Method),
+            ("Insert", "int * string -> System.Text.StringBuilder", Method),
+            ("Clear", "unit -> System.Text.StringBuilder", Method),
+            ("ToString", "unit -> string", Method),
+            ("Length", "int", Property),
+        ]),
+        ("System.Text.RegularExpressions.Regex", "System.Text.RegularExpressions", &[
+            ("Match", "string -> System.Text.RegularExpressions.Match", Method),
+            ("Matches", "string -> System.Text.RegularExpressions.MatchCollection", Method),
+            ("Replace", "string * string -> string", Method),
+            ("IsMatch", "string -> bool", Method),
+            ("Split", "string -> string[]", Method),
+            ("Escape", "string -> string", Method),
+        ]),
+        ("System.Threading.Tasks.Task", "System.Runtime", &[
+            ("Run", "(unit -> unit) -> System.Threading.Tasks.Task", Method),
+            ("WhenAll", "seq<System.Threading.Tasks.Task> -> System.Threading.Tasks.Task", Method),
+            ("WhenAny", "seq<System.Threading.Tasks.Task> -> System.Threading.Tasks.Task<System.Threading.Tasks.Task>", Method),
+            ("Delay", "int -> System.Threading.Tasks.Task", Method),
+            ("Wait", "unit -> unit", Method),
+            ("IsCompleted", "bool", Property),
+            ("CompletedTask", "System.Threading.Tasks.Task", Property),
+        ]),
+        ("System.Threading.Tasks.Task`1", "System.Runtime", &[
+            ("Result", "'TResult", Property),
+            ("Wait", "unit -> unit", Method),
+            ("IsCompleted", "bool", Property),
+            ("ContinueWith", "(Task<'TResult> -> unit) -> System.Threading.Tasks.Task", Method),
+        ]),
+        ("System.Net.Http.HttpClient", "System.Net.Http", &[
+            ("GetAsync", "string -> Task<HttpResponseMessage>", Method),
+            ("GetStringAsync", "string -> Task<string>", Method),
+            ("PostAsync", "string * HttpContent -> Task<HttpResponseMessage>", Method),
+            ("PutAsync", "string * HttpContent -> Task<HttpResponseMessage>", Method),
+            ("DeleteAsync", "string -> Task<HttpResponseMessage>", Method),
+            ("SendAsync", "HttpRequestMessage -> Task<HttpResponseMessage>", Method),
+            ("BaseAddress", "System.Uri", Property),
+            ("Timeout", "System.TimeSpan", Property),
+            ("DefaultRequestHeaders", "HttpRequestHeaders", Property),
+        ]),
+    ]
+};
+
 impl ExternalIndex {
     /// Create a new empty external index
     pub fn new() -> Self {
@@ -264,6 +579,121 @@ impl ExternalIndex {
         self.add_method("System.Net.Http.HttpClient.SendAsync", "System.Net.Http");
     }
 
+    /// Index the bundled manifest of BCL type members, so member completion
+    /// and hover work on framework types.
+    pub fn index_framework_members(&mut self) {
+        for (type_name, assembly, members) in BCL_MEMBERS {
+            let members = members
+                .iter()
+                .map(|(name, member_type, kind)| ManifestMember {
+                    name: name.to_string(),
+                    member_type: member_type.to_string(),
+                    kind: *kind,
+                })
+                .collect();
+            self.add_manifest_type(
+                assembly,
+                ManifestType {
+                    name: type_name.to_string(),
+                    members,
+                },
+            );
+        }
+        for (abbreviation, type_name) in FSHARP_ABBREVIATIONS {
+            self.type_names
+                .insert(abbreviation.to_string(), type_name.to_string());
+        }
+    }
+
+    /// Index the types and members of an exported assembly manifest.
+    pub fn add_manifest(&mut self, manifest: AssemblyManifest) {
+        for ty in manifest.types {
+            self.add_manifest_type(&manifest.assembly, ty);
+        }
+    }
+
+    /// Index every `*.json` manifest in `dir` (see [`ASSEMBLY_MANIFEST_DIR`]),
+    /// returning how many were loaded. Unreadable manifests are skipped with
+    /// a warning.
+    pub fn load_manifests(&mut self, dir: &Path) -> usize {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return 0;
+        };
+        let mut paths: Vec<_> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        paths.sort();
+
+        let mut loaded = 0;
+        for path in paths {
+            let manifest = std::fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|json| serde_json::from_str(&json).map_err(|e| e.to_string()));
+            match manifest {
+                Ok(manifest) => {
+                    self.add_manifest(manifest);
+                    loaded += 1;
+                }
+                Err(e) => tracing::warn!("Skipping assembly manifest {:?}: {}", path, e),
+            }
+        }
+        loaded
+    }
+
+    fn add_manifest_type(&mut self, assembly: &str, ty: ManifestType) {
+        if !self.symbols.contains_key(&ty.name) {
+            self.add_type(&ty.name, assembly);
+        }
+
+        // `List` and `String` as well as `System.Collections.Generic.List`1`
+        let short = ty.name.rsplit('.').next().unwrap_or(&ty.name);
+        let short = short.split('`').next().unwrap_or(short);
+        self.type_names
+            .entry(short.to_string())
+            .or_insert_with(|| ty.name.clone());
+
+        for member in ty.members {
+            let qualified = format!("{}.{}", ty.name, member.name);
+            if !self.symbols.contains_key(&qualified) {
+                match member.kind {
+                    MemberKind::Method => self.add_method(&qualified, assembly),
+                    _ => self.add_property(&qualified, assembly),
+                }
+            }
+
+            let members = self.members.entry(ty.name.clone()).or_default();
+            if !members
+                .iter()
+                .any(|existing| existing.member == member.name)
+            {
+                members.push(TypeMember {
+                    type_name: ty.name.clone(),
+                    member: member.name,
+                    member_type: member.member_type,
+                    kind: member.kind,
+                });
+            }
+        }
+    }
+
+    /// Members of a type, by its qualified name, short name (`String`) or
+    /// F# abbreviation (`string`).
+    pub fn members(&self, type_name: &str) -> Option<&[TypeMember]> {
+        let qualified = self
+            .type_names
+            .get(type_name)
+            .map_or(type_name, String::as_str);
+        self.members.get(qualified).map(Vec::as_slice)
+    }
+
+    /// A member of a type named as for [`members`](Self::members).
+    pub fn member(&self, type_name: &str, member_name: &str) -> Option<&TypeMember> {
+        self.members(type_name)?
+            .iter()
+            .find(|member| member.member == member_name)
+    }
+
     /// Add a type symbol
     fn add_type(&mut self, qualified_name: &str, assembly: &str) {
         self.add_symbol(
@@ -334,6 +764,47 @@ impl ExternalIndex {
             .collect()
     }
 
+    /// Package (or assembly) a name the workspace doesn't define most
+    /// likely comes from, given the modules `opens`ed where it's used.
+    ///
+    /// A known symbol, or the type of a known member, names its assembly.
+    /// Otherwise it's the referenced package whose name is the longest
+    /// dotted prefix of the name, trying it as written and under each open.
+    /// Only a qualified name (`Type.member`) is tried under the opens: a bare
+    /// name is as likely a local the index doesn't track.
+    pub fn package_of(&self, name: &str, opens: &[String]) -> Option<String> {
+        let candidates: Vec<String> = std::iter::once(name.to_string())
+            .chain(opens.iter().map(|open| format!("{}.{}", open, name)))
+            .collect();
+
+        for candidate in &candidates {
+            let owner = candidate.rsplit_once('.').map(|(owner, _)| owner);
+            let known = self
+                .find_symbol(candidate)
+                .or_else(|| owner.and_then(|owner| self.find_symbol(owner)));
+            if let Some(known) = known {
+                return Some(known.assembly.clone());
+            }
+        }
+
+        let tried = if name.contains('.') {
+            candidates.len()
+        } else {
+            1
+        };
+        candidates[..tried].iter().find_map(|candidate| {
+            self.packages
+                .iter()
+                .filter(|package| {
+                    candidate
+                        .strip_prefix(package.name.as_str())
+                        .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+                })
+                .max_by_key(|package| package.name.len())
+                .map(|package| package.name.clone())
+        })
+    }
+
     /// Check if the index contains any symbols
     pub fn is_empty(&self) -> bool {
         self.symbols.is_empty()
@@ -350,24 +821,27 @@ pub fn index_external_assemblies(
     package_refs: &[crate::fsproj::PackageReference],
 ) -> ExternalIndex {
     let mut index = ExternalIndex::new();
-
-    // For now, just index common types regardless of packages
-    // In a full implementation, this would:
-    // 1. Find the actual .dll files for each package
-    // 2. Read their metadata using a .NET reflection library
-    // 3. Extract public types and members
     index.index_common_types();
+    index.index_framework_members();
 
-    // TODO: Actually read the referenced packages
-    for _package in package_refs {
-        // Find package in NuGet cache or local packages folder
-        // Read .dll files and extract metadata
-        // This requires additional dependencies for PE file parsing
+    // Members of packages come from exported manifests (see `load_manifests`)
+    for package in package_refs {
+        if !index.packages.iter().any(|p| p.name == package.name) {
+            index.packages.push(package.clone());
+        }
     }
 
     index
 }
 
+/// [`index_external_assemblies`], plus the manifests exported into the
+/// workspace's `.rocketindex/assemblies/`.
+pub fn index_workspace_assemblies(root: &Path, package_refs: &[PackageReference]) -> ExternalIndex {
+    let mut index = index_external_assemblies(package_refs);
+    index.load_manifests(&root.join(".rocketindex").join(ASSEMBLY_MANIFEST_DIR));
+    index
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -410,6 +884,112 @@ mod tests {
             .any(|ext| ext.symbol.qualified == "System.Console"));
     }
 
+    #[test]
+    fn test_package_of() {
+        let index = index_external_assemblies(&[
+            PackageReference {
+                name: "Newtonsoft.Json".to_string(),
+                version: "13.0.1".to_string(),
+            },
+            PackageReference {
+                name: "Newtonsoft.Json.Bson".to_string(),
+                version: "1.0.2".to_string(),
+            },
+        ]);
+
+        // Known BCL members name their assembly, also through an open
+        assert_eq!(
+            index.package_of("System.Console.WriteLine", &[]).as_deref(),
+            Some("System.Console")
+        );
+        assert_eq!(
+            index
+                .package_of("Console.WriteLine", &["System".to_string()])
+                .as_deref(),
+            Some("System.Console")
+        );
+        // Otherwise the longest package prefix, as written or under an open
+        assert_eq!(
+            index
+                .package_of("Newtonsoft.Json.Bson.BsonReader", &[])
+                .as_deref(),
+            Some("Newtonsoft.Json.Bson")
+        );
+        assert_eq!(
+            index
+                .package_of(
+                    "JsonConvert.SerializeObject",
+                    &["Newtonsoft.Json".to_string()]
+                )
+                .as_deref(),
+            Some("Newtonsoft.Json")
+        );
+        assert_eq!(index.package_of("Newtonsoft.JsonX.Thing", &[]), None);
+        assert_eq!(index.package_of("printfn", &[]), None);
+        assert_eq!(
+            index.package_of("settings", &["Newtonsoft.Json".to_string()]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_framework_members() {
+        let index = index_external_assemblies(&[]);
+
+        // By qualified name, short name and F# abbreviation
+        let length = index.member("System.String", "Length").unwrap();
+        assert_eq!(length.member_type, "int");
+        assert_eq!(length.kind, MemberKind::Property);
+        assert!(index.member("String", "Substring").is_some());
+        assert!(index.member("string", "Substring").is_some());
+        assert!(index.member("ResizeArray", "Add").is_some());
+        assert!(index.member("List", "Count").is_some());
+        assert!(index.members("Widget").is_none());
+
+        // Members are symbols too
+        assert!(index.find_symbol("System.String.Length").is_some());
+        assert_eq!(
+            index
+                .find_symbol("System.Collections.Generic.List`1.AddRange")
+                .unwrap()
+                .assembly,
+            "System.Collections"
+        );
+    }
+
+    #[test]
+    fn test_load_manifests() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("Newtonsoft.Json.json"),
+            r#"{ "assembly": "Newtonsoft.Json",
+                 "types": [{ "name": "Newtonsoft.Json.JsonConvert",
+                             "members": [{ "name": "SerializeObject",
+                                           "type": "obj -> string",
+                                           "kind": "method" }] }] }"#,
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("broken.json"), "{").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "ignored").unwrap();
+
+        let mut index = ExternalIndex::new();
+        assert_eq!(index.load_manifests(dir.path()), 1);
+        assert_eq!(
+            index
+                .member("JsonConvert", "SerializeObject")
+                .unwrap()
+                .member_type,
+            "obj -> string"
+        );
+        assert_eq!(
+            index
+                .package_of("JsonConvert.SerializeObject", &["Newtonsoft.Json".into()])
+                .as_deref(),
+            Some("Newtonsoft.Json")
+        );
+        assert_eq!(index.load_manifests(&dir.path().join("missing")), 0);
+    }
+
     #[test]
     fn test_empty_index() {
         let index = ExternalIndex::new();
@@ -0,0 +1,180 @@
+//! Cross-repository index federation.
+//!
+//! A workspace can attach the indexes of sibling repositories (the `attach`
+//! table of `.rocketindex.toml`), so symbols it uses from a shared library
+//! resolve to their definitions there. Attached indexes are only ever read:
+//! each repository keeps indexing itself.
+//!
+//! An attached index may have been built at another path than the one it's
+//! checked out at here, or packed with paths relative to its root, so every
+//! path read from it is rebased onto the attached checkout.
+
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::db::{SqliteIndex, DEFAULT_DB_NAME};
+use crate::{pack, CodeIndex, Result, Symbol};
+
+/// A sibling repository's index, opened read-only.
+pub struct AttachedIndex {
+    /// Name given in `.rocketindex.toml`, reported as the results' provenance
+    pub name: String,
+    /// Where the repository is checked out
+    pub root: PathBuf,
+    pub index: SqliteIndex,
+    /// Root the index was built at, if it recorded one
+    indexed_root: Option<PathBuf>,
+}
+
+impl AttachedIndex {
+    /// Open the index of the repository checked out at `root`: its own
+    /// `.rocketindex/index.db`, or else a pack in `.rocketindex/pack`.
+    pub fn open(name: &str, root: &Path) -> Result<Self> {
+        let root = root.canonicalize()?;
+        let index_dir = root.join(".rocketindex");
+        let db_path = index_dir.join(DEFAULT_DB_NAME);
+        let index = if db_path.exists() {
+            SqliteIndex::open_shared(&db_path)?
+        } else {
+            pack::open_pack(&index_dir.join("pack"))?.0
+        };
+        let indexed_root = index.get_metadata("workspace_root")?.map(PathBuf::from);
+
+        Ok(Self {
+            name: name.to_string(),
+            root,
+            index,
+            indexed_root,
+        })
+    }
+
+    /// Where `path`, as stored in the attached index, is in this checkout.
+    pub fn rebase(&self, path: &Path) -> PathBuf {
+        if path.is_relative() {
+            return self.root.join(path);
+        }
+        match self
+            .indexed_root
+            .as_deref()
+            .and_then(|indexed| path.strip_prefix(indexed).ok())
+        {
+            Some(relative) => self.root.join(relative),
+            None => path.to_path_buf(),
+        }
+    }
+
+    /// The definition of `qualified`, if this repository has one.
+    pub fn find_by_qualified(&self, qualified: &str) -> Result<Option<Symbol>> {
+        Ok(self.index.find_by_qualified(qualified)?.map(|mut symbol| {
+            symbol.location.file = self.rebase(&symbol.location.file);
+            symbol
+        }))
+    }
+
+    /// Add everything this repository defines and references to `code_index`,
+    /// marking its files with this repository's name.
+    pub fn load_into(&self, code_index: &mut CodeIndex) -> Result<()> {
+        code_index.add_attached_root(self.root.clone(), self.name.clone());
+        for file in self.index.list_files()? {
+            let rebased = self.rebase(&file);
+            for mut symbol in self.index.symbols_in_file(&file)? {
+                symbol.location.file = rebased.clone();
+                code_index.add_symbol(symbol);
+            }
+            for mut reference in self.index.references_in_file(&file)? {
+                reference.location.file = rebased.clone();
+                code_index.add_reference(rebased.clone(), reference);
+            }
+            for open in self.index.opens_for_file(&file)? {
+                code_index.add_open(rebased.clone(), open);
+            }
+            for alias in self.index.aliases_for_file(&file)? {
+                code_index.add_alias(rebased.clone(), alias);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Open every index `config` attaches to the workspace at `workspace`, in
+/// name order. Repositories that aren't checked out or indexed are skipped
+/// with a warning.
+pub fn open_attached(config: &Config, workspace: &Path) -> Vec<AttachedIndex> {
+    config
+        .attach
+        .iter()
+        .filter_map(
+            |(name, path)| match AttachedIndex::open(name, &workspace.join(path)) {
+                Ok(attached) => Some(attached),
+                Err(e) => {
+                    tracing::warn!("Skipping attached index '{}' ({}): {}", name, path, e);
+                    None
+                }
+            },
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Location, SymbolKind, Visibility};
+    use tempfile::TempDir;
+
+    fn index_repo(root: &Path, indexed_at: &Path, file: &Path) {
+        let index = SqliteIndex::create(&root.join(".rocketindex").join(DEFAULT_DB_NAME)).unwrap();
+        index
+            .set_metadata("workspace_root", &indexed_at.to_string_lossy())
+            .unwrap();
+        index
+            .insert_symbol(&Symbol::new(
+                "toSlug".to_string(),
+                "Shared.Text.toSlug".to_string(),
+                SymbolKind::Function,
+                Location::new(file.to_path_buf(), 4, 5),
+                Visibility::Public,
+                "fsharp".to_string(),
+            ))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_attached_paths_are_rebased_onto_the_checkout() {
+        let temp = TempDir::new().unwrap();
+        let shared = temp.path().join("shared-lib");
+        std::fs::create_dir_all(shared.join(".rocketindex")).unwrap();
+        // Indexed on a CI machine, checked out next to the workspace here
+        index_repo(
+            &shared,
+            Path::new("/ci/shared-lib"),
+            Path::new("/ci/shared-lib/src/Text.fs"),
+        );
+
+        let workspace = temp.path().join("app");
+        std::fs::create_dir_all(&workspace).unwrap();
+        std::fs::write(
+            workspace.join(".rocketindex.toml"),
+            "[attach]\nshared = \"../shared-lib\"\nmissing = \"../nowhere\"\n",
+        )
+        .unwrap();
+
+        let attached = open_attached(&Config::load(&workspace), &workspace);
+        assert_eq!(attached.len(), 1);
+        let shared_root = shared.canonicalize().unwrap();
+        let symbol = attached[0]
+            .find_by_qualified("Shared.Text.toSlug")
+            .unwrap()
+            .unwrap();
+        assert_eq!(symbol.location.file, shared_root.join("src/Text.fs"));
+        assert_eq!(
+            attached[0].rebase(Path::new("src/Text.fs")),
+            shared_root.join("src/Text.fs")
+        );
+
+        let mut code_index = CodeIndex::with_root(workspace.clone());
+        attached[0].load_into(&mut code_index).unwrap();
+        let loaded = code_index.get("Shared.Text.toSlug").unwrap();
+        assert_eq!(code_index.provenance(&loaded.location.file), Some("shared"));
+        assert_eq!(code_index.provenance(&workspace.join("src/App.fs")), None);
+    }
+}
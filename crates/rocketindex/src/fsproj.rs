@@ -22,7 +22,7 @@ pub struct FsprojInfo {
 }
 
 /// A NuGet package reference
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct PackageReference {
     /// Package name (e.g., "Newtonsoft.Json")
     pub name: String,
@@ -1,7 +1,16 @@
 //! Fuzzy string matching utilities for symbol lookup error recovery.
 //!
 //! Provides Levenshtein distance calculation and similar string suggestions
-//! to help agents recover from typos in symbol names.
+//! to help agents recover from typos in symbol names, and a subsequence
+//! scorer for the abbreviations typed into editor symbol pickers.
+//! [`combined_score`] ranks by whichever of the two fits a candidate better,
+//! so `rkt symbols --fuzzy` finds both `procesPayment` and `usrSvc`.
+//! [`rank_suggestions`] is the "did you mean" every command, the MCP tools
+//! and the LSP share when a symbol isn't found.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Symbol, SymbolKind};
 
 /// Calculate the Levenshtein (edit) distance between two strings.
 ///
@@ -60,7 +69,7 @@ pub fn levenshtein_distance(a: &str, b: &str) -> usize {
 }
 
 /// A suggestion with its edit distance from the query.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Suggestion {
     /// The suggested string
     pub value: String,
@@ -125,6 +134,200 @@ where
     suggestions
 }
 
+/// A name that could be suggested for a symbol that wasn't found.
+#[derive(Debug, Clone, Copy)]
+pub struct Candidate<'a> {
+    /// The name or qualified name of a symbol
+    pub value: &'a str,
+    pub kind: SymbolKind,
+    /// How often the symbol is referenced
+    pub references: u32,
+}
+
+/// Rank "did you mean" suggestions for `query`.
+///
+/// Candidates within `max_distance` edits are weighed by closeness (an edit
+/// that only changes case costs half), by kind (types and functions are
+/// looked up more often than members and values) and by popularity (the
+/// log of the reference count), so a well-used function one slip away
+/// beats a barely-used local the same distance off. Values offered twice,
+/// say as both name and qualified name, keep their best rank.
+///
+/// # Examples
+///
+/// ```
+/// use rocketindex::fuzzy::{rank_suggestions, Candidate};
+/// use rocketindex::SymbolKind;
+///
+/// let candidates = [
+///     Candidate { value: "chart", kind: SymbolKind::Value, references: 0 },
+///     Candidate { value: "charge", kind: SymbolKind::Function, references: 12 },
+/// ];
+/// let suggestions = rank_suggestions("chare", candidates, 2, 5);
+/// assert_eq!(suggestions[0].value, "charge");
+/// ```
+#[must_use]
+pub fn rank_suggestions<'a, I>(
+    query: &str,
+    candidates: I,
+    max_distance: usize,
+    max_suggestions: usize,
+) -> Vec<Suggestion>
+where
+    I: IntoIterator<Item = Candidate<'a>>,
+{
+    let folded_query = query.to_lowercase();
+    let mut ranked: std::collections::HashMap<&str, (i64, usize)> =
+        std::collections::HashMap::new();
+    for candidate in candidates {
+        let distance = levenshtein_distance(query, candidate.value);
+        if distance == 0 || distance > max_distance {
+            continue;
+        }
+        let case_blind = levenshtein_distance(&folded_query, &candidate.value.to_lowercase());
+        let cost = 100 * case_blind as i64 + 50 * (distance - case_blind) as i64
+            - kind_weight(candidate.kind)
+            - popularity_weight(candidate.references);
+        let best = ranked.entry(candidate.value).or_insert((cost, distance));
+        if cost < best.0 {
+            *best = (cost, distance);
+        }
+    }
+
+    let mut ranked: Vec<(&str, (i64, usize))> = ranked.into_iter().collect();
+    ranked.sort_by(|(a, (a_cost, _)), (b, (b_cost, _))| a_cost.cmp(b_cost).then(a.cmp(b)));
+    ranked
+        .into_iter()
+        .take(max_suggestions)
+        .map(|(value, (_, distance))| Suggestion::new(value.to_string(), distance))
+        .collect()
+}
+
+fn kind_weight(kind: SymbolKind) -> i64 {
+    match kind {
+        SymbolKind::Function
+        | SymbolKind::Class
+        | SymbolKind::Interface
+        | SymbolKind::Record
+        | SymbolKind::Union
+        | SymbolKind::Type => 30,
+        SymbolKind::Module | SymbolKind::Member => 15,
+        SymbolKind::Value => 0,
+    }
+}
+
+fn popularity_weight(references: u32) -> i64 {
+    // 10 per doubling, capped at 64 references
+    (10 * i64::from(u32::BITS - references.leading_zeros())).min(70)
+}
+
+/// Score `candidate` against an abbreviation `query`, as editor pickers do:
+/// every query character must appear in order (ignoring case).
+///
+/// Higher is better. Matches at the start of the candidate or of a word
+/// (after `.`, `_`, `:` or at a lowercase-to-uppercase hump) and runs of
+/// consecutive matches score extra; skipped characters cost a little, so
+/// shorter candidates win ties. Returns `None` when `query` isn't a
+/// subsequence of `candidate`.
+///
+/// # Examples
+///
+/// ```
+/// use rocketindex::fuzzy::fuzzy_score;
+///
+/// assert!(fuzzy_score("usvc", "UserService").is_some());
+/// assert!(fuzzy_score("usvc", "UserService") > fuzzy_score("usvc", "UsersServiceCache"));
+/// assert_eq!(fuzzy_score("xyz", "UserService"), None);
+/// ```
+#[must_use]
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+    let candidate: Vec<char> = candidate.chars().collect();
+    let mut score = 0i64;
+    let mut next = 0;
+    let mut previous_match: Option<usize> = None;
+
+    for q in query.chars() {
+        let q = q.to_lowercase().next()?;
+        let position =
+            (next..candidate.len()).find(|&i| candidate[i].to_lowercase().next() == Some(q))?;
+
+        score += 1;
+        if position == 0 || is_word_start(&candidate, position) {
+            score += 8;
+        }
+        if previous_match.is_some_and(|p| p + 1 == position) {
+            score += 5;
+        }
+        score -= (position - next) as i64;
+        previous_match = Some(position);
+        next = position + 1;
+    }
+
+    // Unmatched trailing characters cost less than gaps, but still break ties
+    let trailing = (candidate.len() - next) as i64;
+    Some(score - (trailing + 3) / 4)
+}
+
+fn is_word_start(chars: &[char], i: usize) -> bool {
+    let previous = chars[i - 1];
+    matches!(previous, '.' | '_' | ':' | '-' | '/' | '\\' | '#')
+        || (previous.is_lowercase() && chars[i].is_uppercase())
+}
+
+/// Best possible [`combined_score`], for a case-insensitive exact match.
+pub const MAX_SCORE: i64 = 100;
+
+/// Score an abbreviation matching as well as the query matching itself gets.
+/// Below [`MAX_SCORE`] so an exact match always ranks first.
+const ABBREVIATION_SCORE: i64 = 90;
+
+/// A symbol found by a fuzzy search, with how well it matched.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FuzzyMatch {
+    pub symbol: Symbol,
+    /// [`combined_score`] of the better of its name and qualified name
+    pub score: i64,
+    /// Edit distance from the query to the closer of its name and qualified name
+    pub distance: usize,
+}
+
+/// Score `candidate` for `query` as whichever fits better: an abbreviation
+/// ([`fuzzy_score`], scaled by how well the query matches itself) or a typo
+/// within `max_distance` edits (scaled by length, so one edit in a long name
+/// counts for less than in a short one).
+///
+/// Scores run up to [`MAX_SCORE`]; `None` when neither fits.
+///
+/// # Examples
+///
+/// ```
+/// use rocketindex::fuzzy::combined_score;
+///
+/// let abbreviation = combined_score("usrSvc", "UserService", 3).unwrap();
+/// let typo = combined_score("UserSevrice", "UserService", 3).unwrap();
+/// assert!(typo > abbreviation);
+/// assert_eq!(combined_score("usrSvc", "OrderQueue", 3), None);
+/// ```
+#[must_use]
+pub fn combined_score(query: &str, candidate: &str, max_distance: usize) -> Option<i64> {
+    if query.eq_ignore_ascii_case(candidate) {
+        return Some(MAX_SCORE);
+    }
+
+    let abbreviation = fuzzy_score(query, candidate).and_then(|score| {
+        let best = fuzzy_score(query, query)?.max(1);
+        Some((score * ABBREVIATION_SCORE / best).clamp(0, ABBREVIATION_SCORE))
+    });
+
+    let distance = levenshtein_distance(query, candidate);
+    let typo = (distance <= max_distance).then(|| {
+        let length = query.chars().count().max(candidate.chars().count()).max(1) as i64;
+        (MAX_SCORE - 1) * (length - distance as i64).max(0) / length
+    });
+
+    abbreviation.max(typo)
+}
+
 /// Default maximum edit distance for suggestions.
 pub const DEFAULT_MAX_DISTANCE: usize = 3;
 
@@ -178,6 +381,16 @@ mod tests {
         assert_eq!(levenshtein_distance("valeu", "value"), 2);
     }
 
+    #[test]
+    fn test_fuzzy_score_prefers_word_starts() {
+        // Hump matches beat the same letters scattered mid-word
+        assert!(fuzzy_score("ps", "PaymentService") > fuzzy_score("ps", "Perhaps"));
+        // Exact names beat longer ones
+        assert!(fuzzy_score("save", "save") > fuzzy_score("save", "saveAll"));
+        assert!(fuzzy_score("os", "Orders.save").is_some());
+        assert_eq!(fuzzy_score("abc", "acb"), None);
+    }
+
     #[test]
     fn test_find_similar_basic() {
         let candidates = ["apple", "apply", "banana", "application"];
@@ -241,4 +454,48 @@ mod tests {
         assert_eq!(suggestions[0].value, "processPayment");
         assert_eq!(suggestions[0].distance, 1);
     }
+
+    #[test]
+    fn test_rank_suggestions_weighs_kind_and_popularity() {
+        let candidate = |value, kind, references| Candidate {
+            value,
+            kind,
+            references,
+        };
+        let candidates = [
+            candidate("parseLine", SymbolKind::Value, 0),
+            candidate("parseLines", SymbolKind::Function, 40),
+            candidate("ParseLines", SymbolKind::Class, 0),
+            candidate("Parser.parseLines", SymbolKind::Function, 40),
+            candidate("parseLines", SymbolKind::Value, 0),
+        ];
+
+        let values: Vec<String> = rank_suggestions("parseLinez", candidates, 3, 5)
+            .into_iter()
+            .map(|s| s.value)
+            .collect();
+        // Same distance: the popular function beats the unused value, and a
+        // name offered twice appears once
+        assert_eq!(values, vec!["parseLines", "parseLine", "ParseLines"]);
+
+        // An edit that only changes case is the cheapest kind
+        let ranked = rank_suggestions("parselines", candidates, 3, 2);
+        assert_eq!(ranked[0].value, "parseLines");
+        assert_eq!(ranked[1].value, "ParseLines");
+    }
+
+    #[test]
+    fn test_combined_score_ranks_abbreviations_and_typos() {
+        let score = |query: &str, candidate: &str| combined_score(query, candidate, 2);
+
+        assert_eq!(score("userservice", "UserService"), Some(MAX_SCORE));
+        // Camel humps beat scattered matches of the same letters
+        assert!(score("usrSvc", "UserService") > score("usrSvc", "UsersRecoveryServiceCache"));
+        // A close typo of the whole name beats a bare abbreviation
+        assert!(score("procesPayment", "processPayment") > score("prPay", "processPayment"));
+        // Not a subsequence and too many edits
+        assert_eq!(score("usrSvc", "OrderQueue"), None);
+        // A typo that isn't a subsequence still matches
+        assert!(score("hepl", "help").is_some());
+    }
 }
@@ -1,8 +1,10 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
+use crate::subprocess;
+
 /// Git provenance information for a line or symbol.
 /// Fields ordered by importance for AI agents: why > when > reference > who
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,23 +24,52 @@ pub struct GitInfo {
     pub author: String,
 }
 
+/// Lines touched in one file by a working-tree diff.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChangedFile {
+    /// Path relative to the repository root
+    pub path: PathBuf,
+    /// Inclusive, 1-indexed line ranges in the new version of the file.
+    /// Pure deletions are recorded as the line the removed text sat next to.
+    pub ranges: Vec<(u32, u32)>,
+}
+
+/// Lines one commit added and deleted in one file, from `git log --numstat`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NumStat {
+    pub commit: String,
+    /// Absolute path
+    pub file: PathBuf,
+    /// Zero for binary files
+    pub added: u32,
+    pub deleted: u32,
+}
+
+/// Someone who wrote lines of a file, from `git blame`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Contributor {
+    pub author: String,
+    pub email: String,
+    /// Lines of the blamed range they last changed
+    pub lines: u32,
+}
+
 /// Check if we're in a git repository
 pub fn is_git_repo() -> bool {
-    Command::new("git")
-        .args(["rev-parse", "--git-dir"])
-        .output()
+    subprocess::output(Command::new("git").args(["rev-parse", "--git-dir"]))
         .map(|o| o.status.success())
         .unwrap_or(false)
 }
 
 /// Check if a file is tracked by git
 pub fn is_tracked(file: &Path) -> bool {
-    Command::new("git")
-        .args(["ls-files", "--error-unmatch"])
-        .arg(file)
-        .output()
-        .map(|o| o.status.success())
-        .unwrap_or(false)
+    subprocess::output(
+        Command::new("git")
+            .args(["ls-files", "--error-unmatch"])
+            .arg(file),
+    )
+    .map(|o| o.status.success())
+    .unwrap_or(false)
 }
 
 /// Extract conventional commit type from message (feat, fix, refactor, etc.)
@@ -76,14 +107,15 @@ pub fn get_blame(file: &Path, line: u32) -> Result<GitInfo> {
 
     // 1. Get commit hash from git blame --porcelain
     // We use porcelain to reliably get the commit hash even if we want formatted date later
-    let output = Command::new("git")
-        .arg("blame")
-        .arg("-L")
-        .arg(format!("{},{}", line, line))
-        .arg("--porcelain")
-        .arg(file)
-        .output()
-        .context("Failed to execute git blame")?;
+    let output = subprocess::output(
+        Command::new("git")
+            .arg("blame")
+            .arg("-L")
+            .arg(format!("{},{}", line, line))
+            .arg("--porcelain")
+            .arg(file),
+    )
+    .context("Failed to execute git blame")?;
 
     if !output.status.success() {
         anyhow::bail!(
@@ -112,6 +144,67 @@ pub fn get_blame(file: &Path, line: u32) -> Result<GitInfo> {
     get_commit_info(&commit)
 }
 
+/// Who last changed each of the lines `start_line..=end_line` of `file`,
+/// counted per author, most lines first. Uncommitted lines aren't counted.
+pub fn blame_contributors(file: &Path, start_line: u32, end_line: u32) -> Result<Vec<Contributor>> {
+    let mut command = Command::new("git");
+    command
+        .arg("blame")
+        .arg("--line-porcelain")
+        .arg("-L")
+        .arg(format!("{},{}", start_line, end_line.max(start_line)))
+        .arg(file);
+    if let Some(dir) = file.parent() {
+        command.current_dir(dir);
+    }
+    let output = subprocess::output(&mut command).context("Failed to execute git blame")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git blame failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(parse_line_porcelain(&String::from_utf8_lossy(
+        &output.stdout,
+    )))
+}
+
+/// Count lines per author in `git blame --line-porcelain` output, where
+/// every line's block starts with its commit hash and has `author` and
+/// `author-mail` headers.
+fn parse_line_porcelain(blame: &str) -> Vec<Contributor> {
+    let mut contributors: Vec<Contributor> = Vec::new();
+    let mut uncommitted = false;
+    let mut author = "";
+    for line in blame.lines() {
+        if let Some(name) = line.strip_prefix("author ") {
+            author = name;
+        } else if let Some(mail) = line.strip_prefix("author-mail ") {
+            if uncommitted {
+                continue;
+            }
+            let email = mail.trim_start_matches('<').trim_end_matches('>');
+            match contributors
+                .iter_mut()
+                .find(|c| c.author == author && c.email == email)
+            {
+                Some(contributor) => contributor.lines += 1,
+                None => contributors.push(Contributor {
+                    author: author.to_string(),
+                    email: email.to_string(),
+                    lines: 1,
+                }),
+            }
+        } else if !line.starts_with('\t') {
+            if let Some(hash) = line.split(' ').next().filter(|h| h.len() == 40) {
+                uncommitted = hash.chars().all(|c| c == '0');
+            }
+        }
+    }
+    contributors.sort_by(|a, b| b.lines.cmp(&a.lines).then_with(|| a.author.cmp(&b.author)));
+    contributors
+}
+
 /// Get history for a range of lines in a file.
 pub fn get_history(file: &Path, start_line: u32, end_line: u32) -> Result<Vec<GitInfo>> {
     if !is_git_repo() {
@@ -124,14 +217,15 @@ pub fn get_history(file: &Path, start_line: u32, end_line: u32) -> Result<Vec<Gi
 
     // git log -L start,end:file
     // Format: hash|author|short_date|relative_date|message
-    let output = Command::new("git")
-        .arg("log")
-        .arg("-L")
-        .arg(format!("{},{}:{}", start_line, end_line, file.display()))
-        .arg("--pretty=format:%H|%an|%ad|%ar|%s")
-        .arg("--date=short")
-        .output()
-        .context("Failed to execute git log")?;
+    let output = subprocess::output(
+        Command::new("git")
+            .arg("log")
+            .arg("-L")
+            .arg(format!("{},{}:{}", start_line, end_line, file.display()))
+            .arg("--pretty=format:%H|%an|%ad|%ar|%s")
+            .arg("--date=short"),
+    )
+    .context("Failed to execute git log")?;
 
     if !output.status.success() {
         anyhow::bail!(
@@ -153,16 +247,437 @@ pub fn get_history(file: &Path, start_line: u32, end_line: u32) -> Result<Vec<Gi
     Ok(history)
 }
 
+/// Absolute path of the repository root.
+pub fn repo_root() -> Result<PathBuf> {
+    let output = subprocess::output(Command::new("git").args(["rev-parse", "--show-toplevel"]))
+        .context("Failed to execute git rev-parse")?;
+
+    if !output.status.success() {
+        anyhow::bail!("Not in a git repository");
+    }
+
+    Ok(PathBuf::from(
+        String::from_utf8_lossy(&output.stdout).trim(),
+    ))
+}
+
+/// Files and line ranges changed in the working tree.
+///
+/// By default this is everything not yet committed (staged and unstaged,
+/// i.e. `git diff HEAD`); with `staged_only` it is just the index
+/// (`git diff --cached`). Untracked files are not included.
+pub fn changed_files(staged_only: bool) -> Result<Vec<ChangedFile>> {
+    if !is_git_repo() {
+        anyhow::bail!("Not in a git repository");
+    }
+
+    if staged_only || !has_head() {
+        // Without commits, everything staged is new
+        diff_ranges(&["--cached"])
+    } else {
+        diff_ranges(&["HEAD"])
+    }
+}
+
+/// Files and line ranges changed relative to `base`.
+///
+/// With a base (branch, tag or commit) this is everything the working tree
+/// adds since it forked from `base`: committed, staged and unstaged changes
+/// (`git diff $(git merge-base base HEAD)`). Without one it is the
+/// uncommitted changes, as with [`changed_files`].
+pub fn changed_ranges(base: Option<&str>) -> Result<Vec<ChangedFile>> {
+    let Some(base) = base else {
+        return changed_files(false);
+    };
+    if !is_git_repo() {
+        anyhow::bail!("Not in a git repository");
+    }
+
+    let output = subprocess::output(Command::new("git").args(["merge-base", base, "HEAD"]))
+        .context("Failed to execute git merge-base")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "Unknown base '{}': {}",
+            base,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    let fork_point = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+    diff_ranges(&[fork_point.as_str()])
+}
+
+/// A file as it is at some revision.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RevisionFile {
+    /// Path relative to the repository root
+    pub path: PathBuf,
+    pub content: String,
+}
+
+/// Read the files of `rev` (branch, tag or commit) accepted by `keep`,
+/// straight from git's object store, without checking the revision out.
+pub fn files_at_rev(rev: &str, keep: impl Fn(&Path) -> bool) -> Result<Vec<RevisionFile>> {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::process::Stdio;
+
+    if !is_git_repo() {
+        anyhow::bail!("Not in a git repository");
+    }
+
+    let output =
+        subprocess::output(Command::new("git").args(["ls-tree", "-r", "-z", "--full-tree", rev]))
+            .context("Failed to execute git ls-tree")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "Unknown revision '{}': {}",
+            rev,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    // Entries are "<mode> <type> <object>\t<path>", NUL-terminated
+    let listing = String::from_utf8_lossy(&output.stdout);
+    let mut wanted: Vec<(String, PathBuf)> = Vec::new();
+    for entry in listing.split('\0') {
+        let Some((meta, path)) = entry.split_once('\t') else {
+            continue;
+        };
+        let mut meta = meta.split(' ');
+        let (Some(_mode), Some("blob"), Some(object)) = (meta.next(), meta.next(), meta.next())
+        else {
+            continue;
+        };
+        let path = PathBuf::from(path);
+        if keep(&path) {
+            wanted.push((object.to_string(), path));
+        }
+    }
+    if wanted.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // One `cat-file --batch` for all blobs instead of a `git show` per file
+    let mut child = Command::new("git")
+        .args(["cat-file", "--batch"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .context("Failed to execute git cat-file")?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let objects: Vec<String> = wanted.iter().map(|(object, _)| object.clone()).collect();
+    // Written from another thread so a full stdout pipe can't deadlock us
+    let writer = std::thread::spawn(move || -> std::io::Result<()> {
+        for object in objects {
+            writeln!(stdin, "{}", object)?;
+        }
+        Ok(())
+    });
+
+    let mut reader = BufReader::new(child.stdout.take().expect("stdout is piped"));
+    let mut files = Vec::with_capacity(wanted.len());
+    for (object, path) in wanted {
+        // "<object> blob <size>", then the contents and a newline
+        let mut header = String::new();
+        reader.read_line(&mut header)?;
+        let size: usize = header
+            .split_whitespace()
+            .nth(2)
+            .and_then(|size| size.parse().ok())
+            .with_context(|| format!("Unexpected git cat-file output for {}", object))?;
+        let mut content = vec![0; size + 1];
+        reader.read_exact(&mut content)?;
+        content.truncate(size);
+        files.push(RevisionFile {
+            path,
+            content: String::from_utf8_lossy(&content).into_owned(),
+        });
+    }
+
+    writer
+        .join()
+        .expect("git cat-file writer panicked")
+        .context("Failed to write to git cat-file")?;
+    child.wait()?;
+    Ok(files)
+}
+
+/// Commit checked out in the repository containing `dir`.
+pub fn head_commit(dir: &Path) -> Result<String> {
+    let output = subprocess::output(
+        Command::new("git")
+            .args(["rev-parse", "HEAD"])
+            .current_dir(dir),
+    )
+    .context("Failed to execute git rev-parse")?;
+    if !output.status.success() {
+        anyhow::bail!("No commit checked out in {}", dir.display());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// HEAD and up to `count - 1` of its first-parent ancestors, newest first.
+pub fn recent_commits(dir: &Path, count: usize) -> Result<Vec<String>> {
+    let output = subprocess::output(
+        Command::new("git")
+            .args([
+                "rev-list",
+                "--first-parent",
+                "-n",
+                &count.to_string(),
+                "HEAD",
+            ])
+            .current_dir(dir),
+    )
+    .context("Failed to execute git rev-list")?;
+    if !output.status.success() {
+        anyhow::bail!("No commit checked out in {}", dir.display());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::to_string)
+        .collect())
+}
+
+/// Absolute paths of files changed by the commits between `since` and HEAD
+/// (`git diff --name-only`), in the repository containing `dir`.
+pub fn changed_since(dir: &Path, since: &str) -> Result<Vec<PathBuf>> {
+    let toplevel = toplevel_of(dir)?;
+    let output = subprocess::output(
+        Command::new("git")
+            .args([
+                "diff",
+                "--name-only",
+                "-z",
+                "--no-renames",
+                "--end-of-options",
+                since,
+                "HEAD",
+                "--",
+            ])
+            .current_dir(dir),
+    )
+    .context("Failed to execute git diff")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "Unknown revision '{}': {}",
+            since,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .split('\0')
+        .filter(|path| !path.is_empty())
+        .map(|path| toplevel.join(path))
+        .collect())
+}
+
+/// Absolute paths of files staged, modified, deleted or untracked in the
+/// working tree of the repository containing `dir` (`git status`). Ignored
+/// files are left out, as git leaves them out.
+///
+/// Both sides of a rename are listed; the old path no longer exists.
+pub fn working_tree_changes(dir: &Path) -> Result<Vec<PathBuf>> {
+    let toplevel = toplevel_of(dir)?;
+    let output = subprocess::output(
+        Command::new("git")
+            .args(["status", "--porcelain", "-z", "--untracked-files=all"])
+            .current_dir(dir),
+    )
+    .context("Failed to execute git status")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git status failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(parse_status(&String::from_utf8_lossy(&output.stdout))
+        .into_iter()
+        .map(|path| toplevel.join(path))
+        .collect())
+}
+
+/// What every non-merge commit since `since` (a `git log --since` date such
+/// as "6 months ago"; all history if `None`) changed, file by file, in the
+/// repository containing `dir`. One `git log --numstat` covers the whole
+/// range, however many files it touched.
+pub fn numstat(dir: &Path, since: Option<&str>) -> Result<Vec<NumStat>> {
+    let toplevel = toplevel_of(dir)?;
+    let mut command = Command::new("git");
+    command
+        .args([
+            "log",
+            "--numstat",
+            "-z",
+            "--no-renames",
+            "--no-merges",
+            "--format=%x01%H",
+        ])
+        .current_dir(dir);
+    if let Some(since) = since {
+        command.arg(format!("--since={}", since));
+    }
+    let output = subprocess::output(&mut command).context("Failed to execute git log")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git log failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(parse_numstat(
+        &String::from_utf8_lossy(&output.stdout),
+        &toplevel,
+    ))
+}
+
+/// Parse `git log --numstat -z --format=%x01%H`: each commit's hash after a
+/// `\x01`, then `added\tdeleted\tpath` per file, all NUL-separated.
+fn parse_numstat(log: &str, toplevel: &Path) -> Vec<NumStat> {
+    let mut stats = Vec::new();
+    let mut commit = "";
+    for record in log.split('\0') {
+        let record = record.trim_start_matches('\n');
+        if let Some(hash) = record.strip_prefix('\x01') {
+            commit = hash.trim();
+            continue;
+        }
+        let mut fields = record.splitn(3, '\t');
+        let (Some(added), Some(deleted), Some(path)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        stats.push(NumStat {
+            commit: commit.to_string(),
+            file: toplevel.join(path),
+            added: added.parse().unwrap_or(0),
+            deleted: deleted.parse().unwrap_or(0),
+        });
+    }
+    stats
+}
+
+fn toplevel_of(dir: &Path) -> Result<PathBuf> {
+    let output = subprocess::output(
+        Command::new("git")
+            .args(["rev-parse", "--show-toplevel"])
+            .current_dir(dir),
+    )
+    .context("Failed to execute git rev-parse")?;
+    if !output.status.success() {
+        anyhow::bail!("Not in a git repository");
+    }
+    Ok(PathBuf::from(
+        String::from_utf8_lossy(&output.stdout).trim(),
+    ))
+}
+
+/// Paths in `git status --porcelain -z` output. Entries are "XY path", and
+/// renames and copies are followed by a separate entry holding the old path.
+fn parse_status(status: &str) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    let mut entries = status.split('\0').filter(|entry| !entry.is_empty());
+    while let Some(entry) = entries.next() {
+        let (Some(code), Some(path)) = (entry.get(..2), entry.get(3..)) else {
+            continue;
+        };
+        paths.push(PathBuf::from(path));
+        if code.contains(['R', 'C']) {
+            if let Some(old) = entries.next() {
+                paths.push(PathBuf::from(old));
+            }
+        }
+    }
+    paths
+}
+
+/// Run `git diff --unified=0` with extra arguments and parse the result.
+fn diff_ranges(args: &[&str]) -> Result<Vec<ChangedFile>> {
+    let output = subprocess::output(
+        Command::new("git")
+            .args(["diff", "--unified=0", "--no-color", "--no-ext-diff"])
+            .args(args),
+    )
+    .context("Failed to execute git diff")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git diff failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    Ok(parse_diff(&String::from_utf8_lossy(&output.stdout)))
+}
+
+fn has_head() -> bool {
+    subprocess::output(Command::new("git").args(["rev-parse", "--verify", "--quiet", "HEAD"]))
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+/// Parse `git diff --unified=0` output into per-file changed line ranges.
+fn parse_diff(diff: &str) -> Vec<ChangedFile> {
+    let mut files: Vec<ChangedFile> = Vec::new();
+    // Whether hunks currently belong to files.last(); false for deleted files
+    let mut in_file = false;
+
+    for line in diff.lines() {
+        if line.starts_with("diff --git") {
+            in_file = false;
+        } else if let Some(path) = line.strip_prefix("+++ ") {
+            // Deleted files have no new version to map onto
+            in_file = path != "/dev/null";
+            if in_file {
+                let path = path.strip_prefix("b/").unwrap_or(path);
+                files.push(ChangedFile {
+                    path: PathBuf::from(path),
+                    ranges: Vec::new(),
+                });
+            }
+        } else if let Some(hunk) = line.strip_prefix("@@ ") {
+            if let (true, Some(range), Some(file)) =
+                (in_file, parse_hunk_header(hunk), files.last_mut())
+            {
+                file.ranges.push(range);
+            }
+        }
+    }
+
+    files.retain(|f| !f.ranges.is_empty());
+    files
+}
+
+/// Parse the new-file side of a hunk header (`-a,b +c,d @@ ...`).
+fn parse_hunk_header(hunk: &str) -> Option<(u32, u32)> {
+    let new_side = hunk.split_whitespace().find(|part| part.starts_with('+'))?;
+    let mut parts = new_side[1..].splitn(2, ',');
+    let start: u32 = parts.next()?.parse().ok()?;
+    let count: u32 = match parts.next() {
+        Some(count) => count.parse().ok()?,
+        None => 1,
+    };
+
+    if count == 0 {
+        // Pure deletion: git reports the line before the removed block
+        Some((start.max(1), start.max(1)))
+    } else {
+        Some((start, start + count - 1))
+    }
+}
+
 fn get_commit_info(commit: &str) -> Result<GitInfo> {
     // Get short date
-    let output = Command::new("git")
-        .arg("show")
-        .arg("-s")
-        .arg("--format=%an|%ad|%s")
-        .arg("--date=short")
-        .arg(commit)
-        .output()
-        .context("Failed to execute git show")?;
+    let output = subprocess::output(
+        Command::new("git")
+            .arg("show")
+            .arg("-s")
+            .arg("--format=%an|%ad|%s")
+            .arg("--date=short")
+            .arg(commit),
+    )
+    .context("Failed to execute git show")?;
 
     if !output.status.success() {
         anyhow::bail!(
@@ -183,13 +698,14 @@ fn get_commit_info(commit: &str) -> Result<GitInfo> {
     let commit_type = extract_commit_type(&message);
 
     // Get relative date in a second call
-    let relative_output = Command::new("git")
-        .arg("show")
-        .arg("-s")
-        .arg("--format=%ar")
-        .arg(commit)
-        .output()
-        .context("Failed to execute git show for relative date")?;
+    let relative_output = subprocess::output(
+        Command::new("git")
+            .arg("show")
+            .arg("-s")
+            .arg("--format=%ar")
+            .arg(commit),
+    )
+    .context("Failed to execute git show for relative date")?;
 
     let date_relative = String::from_utf8_lossy(&relative_output.stdout)
         .trim()
@@ -240,6 +756,84 @@ mod tests {
     // Unit tests for parsing functions (no git required)
     // =========================================================================
 
+    #[test]
+    fn test_parse_status() {
+        let status = " M src/App.fs\0R  src/New.fs\0src/Old.fs\0?? notes/todo.py\0D  gone.rs\0";
+        assert_eq!(
+            parse_status(status),
+            vec![
+                PathBuf::from("src/App.fs"),
+                PathBuf::from("src/New.fs"),
+                PathBuf::from("src/Old.fs"),
+                PathBuf::from("notes/todo.py"),
+                PathBuf::from("gone.rs"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_line_porcelain() {
+        let alice = "1111111111111111111111111111111111111111";
+        let bob = "2222222222222222222222222222222222222222";
+        let none = "0000000000000000000000000000000000000000";
+        let block = |hash: &str, author: &str, mail: &str, line: u32| {
+            format!(
+                "{hash} {line} {line}\nauthor {author}\nauthor-mail <{mail}>\nsummary s\nfilename a.py\n\tcode\n"
+            )
+        };
+        let blame = [
+            block(bob, "Bob", "bob@example.com", 1),
+            block(alice, "Alice", "alice@example.com", 2),
+            block(alice, "Alice", "alice@example.com", 3),
+            block(none, "Not Committed Yet", "not.committed.yet", 4),
+        ]
+        .concat();
+        assert_eq!(
+            parse_line_porcelain(&blame),
+            vec![
+                Contributor {
+                    author: "Alice".to_string(),
+                    email: "alice@example.com".to_string(),
+                    lines: 2,
+                },
+                Contributor {
+                    author: "Bob".to_string(),
+                    email: "bob@example.com".to_string(),
+                    lines: 1,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_numstat() {
+        let log = "\x01aaa111\0\n2\t1\tsrc/app.py\0-\t-\tlogo.png\0\x01bbb222\0\n1\t0\tsp ace.py\0";
+        let stats = parse_numstat(log, Path::new("/ws"));
+        assert_eq!(
+            stats,
+            vec![
+                NumStat {
+                    commit: "aaa111".to_string(),
+                    file: PathBuf::from("/ws/src/app.py"),
+                    added: 2,
+                    deleted: 1,
+                },
+                NumStat {
+                    commit: "aaa111".to_string(),
+                    file: PathBuf::from("/ws/logo.png"),
+                    added: 0,
+                    deleted: 0,
+                },
+                NumStat {
+                    commit: "bbb222".to_string(),
+                    file: PathBuf::from("/ws/sp ace.py"),
+                    added: 1,
+                    deleted: 0,
+                },
+            ]
+        );
+    }
+
     #[test]
     fn test_parse_log_line_valid() {
         let line = "abc1234def5678|John Doe|2024-12-04|3 days ago|feat: add new feature";
@@ -340,6 +934,52 @@ mod tests {
         assert!(!json.contains("commit_type"));
     }
 
+    // =========================================================================
+    // Unit tests for diff parsing
+    // =========================================================================
+
+    #[test]
+    fn test_parse_diff_ranges() {
+        let diff = "\
+diff --git a/src/App.fs b/src/App.fs
+index 1111111..2222222 100644
+--- a/src/App.fs
++++ b/src/App.fs
+@@ -3 +3 @@ let hello() =
+-let x = 1
++let x = 2
+@@ -10,0 +11,3 @@ let other() =
++a
++b
++c
+@@ -20,2 +23,0 @@ let removed() =
+-gone
+-gone
+diff --git a/old.fs b/old.fs
+deleted file mode 100644
+--- a/old.fs
++++ /dev/null
+@@ -1,2 +0,0 @@
+-x
+-y
+";
+        let files = parse_diff(diff);
+
+        assert_eq!(
+            files,
+            vec![ChangedFile {
+                path: PathBuf::from("src/App.fs"),
+                ranges: vec![(3, 3), (11, 13), (23, 23)],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_hunk_header_deletion_at_start() {
+        assert_eq!(parse_hunk_header("-1,2 +0,0 @@"), Some((1, 1)));
+        assert_eq!(parse_hunk_header("garbage"), None);
+    }
+
     // =========================================================================
     // Unit tests for commit type extraction
     // =========================================================================
@@ -0,0 +1,124 @@
+//! Whole-word text search over source files.
+//!
+//! The fallback for `rkt refs --grep`: dynamic dispatch, reflection and
+//! names assembled at runtime leave nothing in the references table, but
+//! the name still appears in the text. Matches are plain occurrences of the
+//! word, with no idea what they refer to, so callers should present them
+//! as lower confidence than anything the parsers found.
+
+use std::path::{Path, PathBuf};
+
+use rayon::prelude::*;
+use serde::Serialize;
+
+/// An occurrence of a word in a file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TextMatch {
+    pub file: PathBuf,
+    /// 1-based
+    pub line: u32,
+    /// 1-based, in characters
+    pub column: u32,
+    /// The matching line, trimmed
+    pub text: String,
+}
+
+/// Whole-word occurrences of `word` in `files`, ordered by file and position.
+///
+/// A match can't have an identifier character (alphanumeric or `_`)
+/// directly before or after it, so `User` doesn't match `UserId`. Files
+/// that can't be read as UTF-8 are skipped.
+///
+/// # Examples
+///
+/// ```no_run
+/// use rocketindex::grep::find_word;
+/// use std::path::PathBuf;
+///
+/// let matches = find_word(&[PathBuf::from("src/App.fs")], "handler");
+/// for m in matches {
+///     println!("{}:{}:{} {}", m.file.display(), m.line, m.column, m.text);
+/// }
+/// ```
+#[must_use]
+pub fn find_word(files: &[PathBuf], word: &str) -> Vec<TextMatch> {
+    if word.is_empty() {
+        return Vec::new();
+    }
+    let mut matches: Vec<TextMatch> = files
+        .par_iter()
+        .flat_map_iter(|file| match std::fs::read_to_string(file) {
+            Ok(source) => find_word_in(file, &source, word),
+            Err(_) => Vec::new(),
+        })
+        .collect();
+    matches.sort_by(|a, b| (&a.file, a.line, a.column).cmp(&(&b.file, b.line, b.column)));
+    matches
+}
+
+/// Whole-word occurrences of `word` in `source`, the text of `file`.
+#[must_use]
+pub fn find_word_in(file: &Path, source: &str, word: &str) -> Vec<TextMatch> {
+    let mut matches = Vec::new();
+    if word.is_empty() {
+        return matches;
+    }
+    for (line_idx, line) in source.lines().enumerate() {
+        for (start, _) in line.match_indices(word) {
+            let end = start + word.len();
+            let before = line[..start].chars().next_back();
+            let after = line[end..].chars().next();
+            if before.is_some_and(is_word_char) || after.is_some_and(is_word_char) {
+                continue;
+            }
+            matches.push(TextMatch {
+                file: file.to_path_buf(),
+                line: line_idx as u32 + 1,
+                column: line[..start].chars().count() as u32 + 1,
+                text: line.trim().to_string(),
+            });
+        }
+    }
+    matches
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn matches_whole_words_only() {
+        let source = "let user = getUser id\n  // user_id and User differ\n  send(user)\n";
+        let found: Vec<(u32, u32)> = find_word_in(Path::new("a.fs"), source, "user")
+            .iter()
+            .map(|m| (m.line, m.column))
+            .collect();
+        assert_eq!(found, vec![(1, 5), (3, 8)]);
+    }
+
+    #[test]
+    fn columns_count_characters() {
+        let found = find_word_in(Path::new("a.py"), "é = handler", "handler");
+        assert_eq!(found[0].column, 5);
+        assert_eq!(found[0].text, "é = handler");
+    }
+
+    #[test]
+    fn searches_files_in_order() {
+        let dir = TempDir::new().unwrap();
+        let (a, b) = (dir.path().join("a.rb"), dir.path().join("b.rb"));
+        std::fs::write(&b, "send(:handler)\n").unwrap();
+        std::fs::write(&a, "def handler; end\nhandler\n").unwrap();
+
+        let found: Vec<(PathBuf, u32)> = find_word(&[b.clone(), a.clone()], "handler")
+            .into_iter()
+            .map(|m| (m.file, m.line))
+            .collect();
+        assert_eq!(found, vec![(a.clone(), 1), (a, 2), (b, 1)]);
+    }
+}
@@ -0,0 +1,459 @@
+//! Type hierarchies: what a type inherits from, and what inherits from it.
+//!
+//! Ancestors follow a type's base class (`parent`), the interfaces and
+//! traits it implements and the modules it mixes in (Ruby `include`,
+//! `extend`, `prepend`). Descendants follow the same links backwards, via
+//! [`SqliteIndex::find_subclasses`], [`SqliteIndex::find_implementers`] and
+//! [`SqliteIndex::find_includers`].
+//!
+//! A type that is already on the path from the root is reported again with
+//! `cycle` set but not expanded, so cyclic data (a module that includes
+//! itself, two classes naming each other as parent) can't recurse forever.
+
+use std::collections::{HashMap, HashSet};
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::{Location, SqliteIndex, Symbol, SymbolKind};
+
+/// How a node relates to the node above it in the tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Relation {
+    /// Base class
+    Extends,
+    /// Interface, trait or derive
+    Implements,
+    /// Mixed-in module
+    Includes,
+}
+
+impl std::fmt::Display for Relation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Relation::Extends => write!(f, "extends"),
+            Relation::Implements => write!(f, "implements"),
+            Relation::Includes => write!(f, "includes"),
+        }
+    }
+}
+
+/// A type in a hierarchy tree.
+#[derive(Debug, Clone, Serialize)]
+pub struct HierarchyNode {
+    /// Qualified name, or the name as written when the type isn't indexed
+    /// (`Comparable`, `std::fmt::Debug`)
+    pub name: String,
+    pub relation: Relation,
+    /// Distance from the root type, starting at 1
+    pub depth: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<SymbolKind>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<Location>,
+    /// Already on the path from the root; not expanded again
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub cycle: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<HierarchyNode>,
+}
+
+/// The ancestor and descendant trees of a type.
+#[derive(Debug, Clone, Serialize)]
+pub struct TypeHierarchy {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub location: Location,
+    /// What the type inherits from; each node's children are its own ancestors
+    pub ancestors: Vec<HierarchyNode>,
+    /// What inherits from the type; each node's children are its own descendants
+    pub descendants: Vec<HierarchyNode>,
+}
+
+/// Build the hierarchy of the type named `name` (qualified, or a bare type
+/// name), following at most `max_depth` levels each way. `None` if no such
+/// type is indexed.
+pub fn type_hierarchy(
+    db: &SqliteIndex,
+    name: &str,
+    max_depth: usize,
+) -> Result<Option<TypeHierarchy>> {
+    let Some(root) = find_type(db, name)? else {
+        return Ok(None);
+    };
+    let mut path = vec![root.qualified.clone()];
+    let ancestors = ancestors(db, &root, 1, max_depth, &mut path)?;
+    let descendants = descendants(db, &root, 1, max_depth, &mut path)?;
+    Ok(Some(TypeHierarchy {
+        name: root.qualified,
+        kind: root.kind,
+        location: root.location,
+        ancestors,
+        descendants,
+    }))
+}
+
+fn ancestors(
+    db: &SqliteIndex,
+    symbol: &Symbol,
+    depth: usize,
+    max_depth: usize,
+    path: &mut Vec<String>,
+) -> Result<Vec<HierarchyNode>> {
+    let links = symbol
+        .parent
+        .iter()
+        .map(|name| (name, Relation::Extends))
+        .chain(
+            symbol
+                .implements
+                .iter()
+                .flatten()
+                .map(|name| (name, Relation::Implements)),
+        )
+        .chain(
+            symbol
+                .mixins
+                .iter()
+                .flatten()
+                .map(|name| (name, Relation::Includes)),
+        );
+
+    let mut nodes = Vec::new();
+    for (name, relation) in links {
+        let node = match find_type(db, name)? {
+            Some(ancestor) => expand(db, ancestor, relation, depth, max_depth, path, ancestors)?,
+            None => HierarchyNode {
+                name: name.clone(),
+                relation,
+                depth,
+                kind: None,
+                location: None,
+                cycle: false,
+                children: Vec::new(),
+            },
+        };
+        nodes.push(node);
+    }
+    Ok(nodes)
+}
+
+fn descendants(
+    db: &SqliteIndex,
+    symbol: &Symbol,
+    depth: usize,
+    max_depth: usize,
+    path: &mut Vec<String>,
+) -> Result<Vec<HierarchyNode>> {
+    // Parents and mixins are stored as written, so try the bare name too
+    let mut names = vec![symbol.qualified.as_str()];
+    if symbol.name != symbol.qualified {
+        names.push(&symbol.name);
+    }
+
+    let mut found: Vec<(Symbol, Relation)> = Vec::new();
+    for name in &names {
+        found.extend(
+            db.find_subclasses(name)?
+                .into_iter()
+                .map(|s| (s, Relation::Extends)),
+        );
+        found.extend(
+            db.find_includers(name)?
+                .into_iter()
+                .map(|s| (s, Relation::Includes)),
+        );
+    }
+    // Matched by bare name already
+    found.extend(
+        db.find_implementers(&symbol.qualified)?
+            .into_iter()
+            .map(|s| (s, Relation::Implements)),
+    );
+
+    let mut seen = HashSet::new();
+    let mut nodes = Vec::new();
+    for (descendant, relation) in found {
+        if !seen.insert((descendant.qualified.clone(), descendant.location.clone())) {
+            continue;
+        }
+        nodes.push(expand(
+            db,
+            descendant,
+            relation,
+            depth,
+            max_depth,
+            path,
+            descendants,
+        )?);
+    }
+    Ok(nodes)
+}
+
+type Walk = fn(&SqliteIndex, &Symbol, usize, usize, &mut Vec<String>) -> Result<Vec<HierarchyNode>>;
+
+/// A node for `symbol`, with `walk` applied below it unless it is already
+/// on the path or too deep.
+fn expand(
+    db: &SqliteIndex,
+    symbol: Symbol,
+    relation: Relation,
+    depth: usize,
+    max_depth: usize,
+    path: &mut Vec<String>,
+    walk: Walk,
+) -> Result<HierarchyNode> {
+    let cycle = path.contains(&symbol.qualified);
+    let children = if cycle || depth >= max_depth {
+        Vec::new()
+    } else {
+        path.push(symbol.qualified.clone());
+        let children = walk(db, &symbol, depth + 1, max_depth, path)?;
+        path.pop();
+        children
+    };
+    Ok(HierarchyNode {
+        name: symbol.qualified,
+        relation,
+        depth,
+        kind: Some(symbol.kind),
+        location: Some(symbol.location),
+        cycle,
+        children,
+    })
+}
+
+/// Kinds that can take part in a hierarchy.
+fn is_type(kind: SymbolKind) -> bool {
+    kind.is_instantiable() || matches!(kind, SymbolKind::Interface | SymbolKind::Module)
+}
+
+/// The indexed type `name` refers to: by qualified name, then by bare name.
+fn find_type(db: &SqliteIndex, name: &str) -> Result<Option<Symbol>> {
+    let name = name.trim_start_matches("::");
+    if let Some(symbol) = db.find_by_qualified(name)? {
+        if is_type(symbol.kind) {
+            return Ok(Some(symbol));
+        }
+    }
+    let bare = crate::type_name(name);
+    Ok(db
+        .search(bare, 50, None)?
+        .into_iter()
+        .find(|s| s.name == bare && is_type(s.kind)))
+}
+
+/// Render a hierarchy as indented text, ancestors first.
+pub fn format_hierarchy(hierarchy: &TypeHierarchy) -> String {
+    fn write_nodes(output: &mut String, nodes: &[HierarchyNode]) {
+        for node in nodes {
+            output.push_str(&format!(
+                "{}[{}] {} {}",
+                "  ".repeat(node.depth),
+                node.depth,
+                node.relation,
+                node.name
+            ));
+            if let Some(location) = &node.location {
+                output.push_str(&format!(" ({}:{})", location.file.display(), location.line));
+            }
+            if node.cycle {
+                output.push_str(" (cycle)");
+            }
+            output.push('\n');
+            write_nodes(output, &node.children);
+        }
+    }
+
+    let mut output = format!(
+        "{} ({}:{})\n",
+        hierarchy.name,
+        hierarchy.location.file.display(),
+        hierarchy.location.line
+    );
+    output.push_str("Ancestors:\n");
+    if hierarchy.ancestors.is_empty() {
+        output.push_str("  (none)\n");
+    }
+    write_nodes(&mut output, &hierarchy.ancestors);
+    output.push_str("Descendants:\n");
+    if hierarchy.descendants.is_empty() {
+        output.push_str("  (none)\n");
+    }
+    write_nodes(&mut output, &hierarchy.descendants);
+    output
+}
+
+/// Render a hierarchy as a Mermaid flowchart, with edges pointing from
+/// each type to what it inherits from. A type appears once however many
+/// paths reach it, so cycles show up as cycles.
+pub fn hierarchy_to_mermaid(hierarchy: &TypeHierarchy) -> String {
+    struct Graph<'a> {
+        ids: HashMap<&'a str, usize>,
+        nodes: String,
+        edges: String,
+    }
+
+    impl<'a> Graph<'a> {
+        fn id(&mut self, name: &'a str) -> usize {
+            let next = self.ids.len();
+            *self.ids.entry(name).or_insert_with(|| {
+                self.nodes.push_str(&format!(
+                    "    n{}[\"{}\"]\n",
+                    next,
+                    name.replace('"', "#quot;")
+                ));
+                next
+            })
+        }
+
+        fn edge(&mut self, from: usize, to: usize, relation: Relation) {
+            let edge = format!("    n{} -->|{}| n{}\n", from, relation, to);
+            if !self.edges.contains(&edge) {
+                self.edges.push_str(&edge);
+            }
+        }
+
+        fn ancestors(&mut self, from: usize, nodes: &'a [HierarchyNode]) {
+            for node in nodes {
+                let to = self.id(&node.name);
+                self.edge(from, to, node.relation);
+                self.ancestors(to, &node.children);
+            }
+        }
+
+        fn descendants(&mut self, to: usize, nodes: &'a [HierarchyNode]) {
+            for node in nodes {
+                let from = self.id(&node.name);
+                self.edge(from, to, node.relation);
+                self.descendants(from, &node.children);
+            }
+        }
+    }
+
+    let mut graph = Graph {
+        ids: HashMap::new(),
+        nodes: String::new(),
+        edges: String::new(),
+    };
+    let root = graph.id(&hierarchy.name);
+    graph.ancestors(root, &hierarchy.ancestors);
+    graph.descendants(root, &hierarchy.descendants);
+    format!(
+        "flowchart BT\n{}{}    style n{} stroke-width:3px\n",
+        graph.nodes, graph.edges, root
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Visibility;
+    use std::path::PathBuf;
+
+    fn class(qualified: &str, line: u32) -> Symbol {
+        let name = qualified.rsplit("::").next().unwrap().to_string();
+        Symbol::new(
+            name,
+            qualified.to_string(),
+            SymbolKind::Class,
+            Location::new(PathBuf::from("app.rb"), line, 1),
+            Visibility::Public,
+            "ruby".to_string(),
+        )
+    }
+
+    fn index() -> SqliteIndex {
+        let db = SqliteIndex::in_memory().unwrap();
+        let mut comparable = class("Comparable", 1);
+        comparable.kind = SymbolKind::Module;
+        let shape = class("Shapes::Shape", 2);
+        let square = class("Shapes::Square", 3)
+            .with_parent(Some("Shape".to_string()))
+            .with_mixins(Some(vec!["Comparable".to_string()]));
+        let cube = class("Shapes::Cube", 4).with_parent(Some("Shapes::Square".to_string()));
+        let mut drawable = class("Drawable", 5);
+        drawable.kind = SymbolKind::Interface;
+        let mut shape = shape;
+        shape.implements = Some(vec!["Drawable".to_string(), "Debug".to_string()]);
+        db.insert_symbols(&[comparable, shape, square, cube, drawable])
+            .unwrap();
+        db
+    }
+
+    fn names(nodes: &[HierarchyNode]) -> Vec<(usize, Relation, &str)> {
+        let mut out = Vec::new();
+        for node in nodes {
+            out.push((node.depth, node.relation, node.name.as_str()));
+            out.extend(names(&node.children));
+        }
+        out
+    }
+
+    #[test]
+    fn walks_ancestors_and_descendants() {
+        let db = index();
+        let hierarchy = type_hierarchy(&db, "Square", 10).unwrap().unwrap();
+        assert_eq!(hierarchy.name, "Shapes::Square");
+        assert_eq!(
+            names(&hierarchy.ancestors),
+            vec![
+                (1, Relation::Extends, "Shapes::Shape"),
+                (2, Relation::Implements, "Drawable"),
+                (2, Relation::Implements, "Debug"),
+                (1, Relation::Includes, "Comparable"),
+            ]
+        );
+        assert_eq!(
+            names(&hierarchy.descendants),
+            vec![(1, Relation::Extends, "Shapes::Cube")]
+        );
+
+        let drawable = type_hierarchy(&db, "Drawable", 10).unwrap().unwrap();
+        assert_eq!(
+            names(&drawable.descendants),
+            vec![
+                (1, Relation::Implements, "Shapes::Shape"),
+                (2, Relation::Extends, "Shapes::Square"),
+                (3, Relation::Extends, "Shapes::Cube"),
+            ]
+        );
+        let comparable = type_hierarchy(&db, "Comparable", 10).unwrap().unwrap();
+        assert_eq!(
+            names(&comparable.descendants),
+            vec![
+                (1, Relation::Includes, "Shapes::Square"),
+                (2, Relation::Extends, "Shapes::Cube"),
+            ]
+        );
+
+        let shallow = type_hierarchy(&db, "Drawable", 1).unwrap().unwrap();
+        assert!(shallow.descendants[0].children.is_empty());
+        assert!(type_hierarchy(&db, "Missing", 10).unwrap().is_none());
+    }
+
+    #[test]
+    fn stops_at_cycles() {
+        let db = SqliteIndex::in_memory().unwrap();
+        db.insert_symbols(&[
+            class("A", 1).with_parent(Some("B".to_string())),
+            class("B", 2).with_parent(Some("A".to_string())),
+        ])
+        .unwrap();
+
+        let hierarchy = type_hierarchy(&db, "A", 10).unwrap().unwrap();
+        let b = &hierarchy.ancestors[0];
+        assert_eq!((b.name.as_str(), b.cycle), ("B", false));
+        let a = &b.children[0];
+        assert_eq!((a.name.as_str(), a.cycle), ("A", true));
+        assert!(a.children.is_empty());
+        assert!(hierarchy.descendants[0].children[0].cycle);
+
+        let text = format_hierarchy(&hierarchy);
+        assert!(text.contains("    [2] extends A (app.rb:1) (cycle)\n"));
+        let mermaid = hierarchy_to_mermaid(&hierarchy);
+        assert!(mermaid.starts_with("flowchart BT\n    n0[\"A\"]\n    n1[\"B\"]\n"));
+        assert!(mermaid.contains("    n0 -->|extends| n1\n    n1 -->|extends| n0\n"));
+    }
+}
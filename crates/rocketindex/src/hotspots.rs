@@ -0,0 +1,134 @@
+//! Hotspots: code that changes often, is complex, and much else depends on.
+//!
+//! A file's (or module's) score is the number of commits that changed it,
+//! times its complexity, times one more than its fan-in (see
+//! [`crate::metrics`]). Code that never changes scores zero however tangled
+//! it is, and simple code that changes constantly scores low, so the top of
+//! the list is where refactoring pays off most.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::git::NumStat;
+use crate::metrics::{group_name, Grouping, Metrics};
+
+/// A file or module with its churn and what it scores.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Hotspot {
+    /// File, or directory for a module, relative to the workspace root
+    pub name: String,
+    /// Commits that changed it
+    pub commits: usize,
+    /// Lines added and deleted by those commits
+    pub lines_changed: u64,
+    pub complexity: u32,
+    pub fan_in: usize,
+    /// `commits × complexity × (fan_in + 1)`
+    pub score: u64,
+}
+
+/// Rank `metrics` (grouped by `grouping`) by the churn in `changes`, highest
+/// score first. Only what's still indexed is ranked, and only if it changed.
+#[must_use]
+pub fn rank(
+    metrics: &[Metrics],
+    changes: &[NumStat],
+    root: &Path,
+    grouping: Grouping,
+) -> Vec<Hotspot> {
+    // Distinct commits per group: several files of a module may change in
+    // the same commit
+    let mut churn: HashMap<String, (HashSet<&str>, u64)> = HashMap::new();
+    for change in changes {
+        let (commits, lines) = churn
+            .entry(group_name(&change.file, root, grouping))
+            .or_default();
+        commits.insert(change.commit.as_str());
+        *lines += u64::from(change.added) + u64::from(change.deleted);
+    }
+
+    let mut hotspots: Vec<Hotspot> = metrics
+        .iter()
+        .filter_map(|m| {
+            let (commits, lines_changed) = churn.get(&m.name)?;
+            let commits = commits.len();
+            Some(Hotspot {
+                name: m.name.clone(),
+                commits,
+                lines_changed: *lines_changed,
+                complexity: m.complexity,
+                fan_in: m.fan_in,
+                score: commits as u64 * u64::from(m.complexity) * (m.fan_in as u64 + 1),
+            })
+        })
+        .collect();
+    hotspots.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.name.cmp(&b.name)));
+    hotspots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn metrics(name: &str, complexity: u32, fan_in: usize) -> Metrics {
+        Metrics {
+            name: name.to_string(),
+            files: 1,
+            symbols: 1,
+            complexity,
+            max_nesting: 1,
+            fan_in,
+            fan_out: 0,
+        }
+    }
+
+    fn change(commit: &str, file: &str, added: u32) -> NumStat {
+        NumStat {
+            commit: commit.to_string(),
+            file: PathBuf::from(file),
+            added,
+            deleted: 1,
+        }
+    }
+
+    #[test]
+    fn ranks_by_churn_complexity_and_fan_in() {
+        let root = Path::new("/ws");
+        let files = [
+            metrics("billing/charge.py", 10, 3),
+            metrics("billing/refund.py", 40, 0),
+            metrics("app.py", 2, 0),
+            metrics("legacy.py", 90, 5),
+        ];
+        let changes = [
+            change("c1", "/ws/billing/charge.py", 10),
+            change("c2", "/ws/billing/charge.py", 5),
+            change("c2", "/ws/billing/refund.py", 1),
+            change("c3", "/ws/app.py", 1),
+            change("c3", "/ws/deleted.py", 1),
+        ];
+
+        let by_file = rank(&files, &changes, root, Grouping::File);
+        let scores: Vec<(&str, u64)> = by_file.iter().map(|h| (h.name.as_str(), h.score)).collect();
+        // 2 × 10 × 4, 1 × 40 × 1, 1 × 2 × 1; legacy.py never changed
+        assert_eq!(
+            scores,
+            vec![
+                ("billing/charge.py", 80),
+                ("billing/refund.py", 40),
+                ("app.py", 2)
+            ]
+        );
+        assert_eq!(by_file[0].lines_changed, 17);
+
+        let modules = [metrics("billing", 50, 1), metrics(".", 92, 0)];
+        let by_module = rank(&modules, &changes, root, Grouping::Module);
+        // Commit c2 changed two billing files but counts once
+        assert_eq!(by_module[0].name, "billing");
+        assert_eq!(by_module[0].commits, 2);
+        assert_eq!(by_module[0].score, 200);
+    }
+}
@@ -0,0 +1,481 @@
+//! Impact analysis for a diff.
+//!
+//! Maps changed line ranges onto the symbols whose spans they touch (see
+//! [`CodeIndex::symbols_in_range`]), then walks callers from each changed
+//! symbol with [`reverse_spider_with_confidence`] to report the blast radius:
+//! the callers that are affected, the entry points the change surfaces
+//! through, and the tests that reach it.
+//!
+//! Long caller lists are summarized by their gateways: callers that dominate
+//! others on the reverse call graph, i.e. every path from the change to those
+//! callers goes through the gateway. "All 2,000 callers are reached through
+//! these 3 functions" is often the useful answer.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::affected::{affected_tests, is_test_file, AffectedTest};
+use crate::git::ChangedFile;
+use crate::spider::reverse_spider_with_confidence;
+use crate::{CodeIndex, Confidence, Symbol};
+
+/// A symbol edited by the diff.
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangedSymbol {
+    pub symbol: Symbol,
+    /// First line of each changed range that touched the symbol
+    pub lines: Vec<u32>,
+}
+
+/// A symbol affected by the diff through its callees.
+#[derive(Debug, Clone, Serialize)]
+pub struct ImpactedSymbol {
+    pub symbol: Symbol,
+    /// The changed symbol it (transitively) calls
+    pub reaches: String,
+    /// Number of call edges between it and the changed symbol
+    pub depth: usize,
+}
+
+/// A caller that every path from the change to some other callers goes through.
+#[derive(Debug, Clone, Serialize)]
+pub struct Gateway {
+    pub symbol: Symbol,
+    /// Number of other callers only reached through it
+    pub dominates: usize,
+}
+
+/// Blast radius of a diff.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Impact {
+    pub changed: Vec<ChangedSymbol>,
+    /// Non-test callers of changed symbols, closest first
+    pub callers: Vec<ImpactedSymbol>,
+    /// Outermost callers dominating other callers, most dominated first.
+    /// Callers neither a gateway nor behind one are reached along several
+    /// paths, or directly.
+    pub gateways: Vec<Gateway>,
+    /// Changed symbols and callers that no non-test code calls
+    pub entry_points: Vec<ImpactedSymbol>,
+    pub tests: Vec<AffectedTest>,
+}
+
+/// Map a diff onto the symbols it edits.
+///
+/// `changes` paths are relative to `root` (the repository root). Results are
+/// ordered by file, then definition.
+#[must_use]
+pub fn changed_symbols(
+    index: &CodeIndex,
+    root: &Path,
+    changes: &[ChangedFile],
+) -> Vec<ChangedSymbol> {
+    let mut changed: Vec<ChangedSymbol> = Vec::new();
+    for file in changes {
+        let path = root.join(&file.path);
+        let mut positions: HashMap<String, usize> = HashMap::new();
+        let mut in_file: Vec<ChangedSymbol> = Vec::new();
+
+        for &(start, end) in &file.ranges {
+            for symbol in index.symbols_in_range(&path, start, end) {
+                let i = *positions
+                    .entry(symbol.qualified.clone())
+                    .or_insert_with(|| {
+                        in_file.push(ChangedSymbol {
+                            symbol: symbol.clone(),
+                            lines: Vec::new(),
+                        });
+                        in_file.len() - 1
+                    });
+                in_file[i].lines.push(start);
+            }
+        }
+
+        in_file.sort_by_key(|c| (c.symbol.location.line, c.symbol.location.column));
+        changed.extend(in_file);
+    }
+    changed
+}
+
+/// Walk callers of the changed symbols up to `max_depth` edges.
+///
+/// A caller reached from several changed symbols is reported once, at its
+/// shortest distance. Entry points are only reported below `max_depth`,
+/// where "no callers" means none were found rather than none were looked for.
+#[must_use]
+pub fn analyze_impact(
+    index: &CodeIndex,
+    changed: Vec<ChangedSymbol>,
+    max_depth: usize,
+    min_confidence: Confidence,
+) -> Impact {
+    let changed_names: HashSet<&str> = changed
+        .iter()
+        .map(|c| c.symbol.qualified.as_str())
+        .collect();
+
+    let mut callers: HashMap<String, ImpactedSymbol> = HashMap::new();
+    let mut entry_points: HashMap<String, ImpactedSymbol> = HashMap::new();
+    let mut graph = CallerGraph::default();
+
+    for c in &changed {
+        let reaches = c.symbol.qualified.as_str();
+        let result = reverse_spider_with_confidence(index, reaches, max_depth, min_confidence);
+        // Being called from a test doesn't stop a symbol being an entry point
+        let test_nodes: HashSet<&str> = result
+            .nodes
+            .iter()
+            .filter(|n| is_test_file(&n.symbol.location.file))
+            .map(|n| n.symbol.qualified.as_str())
+            .collect();
+        let called: HashSet<&str> = result
+            .edges
+            .iter()
+            .filter(|e| !test_nodes.contains(e.from.as_str()))
+            .map(|e| e.to.as_str())
+            .collect();
+        for edge in &result.edges {
+            if !test_nodes.contains(edge.from.as_str()) {
+                graph.add_edge(&edge.to, &edge.from);
+            }
+        }
+
+        for node in &result.nodes {
+            let qualified = &node.symbol.qualified;
+            if is_test_file(&node.symbol.location.file) {
+                continue;
+            }
+            let impacted = || ImpactedSymbol {
+                symbol: node.symbol.clone(),
+                reaches: reaches.to_string(),
+                depth: node.depth,
+            };
+
+            if node.depth > 0 && !changed_names.contains(qualified.as_str()) {
+                keep_closest(&mut callers, impacted());
+            }
+            if node.depth < max_depth && !called.contains(qualified.as_str()) {
+                keep_closest(&mut entry_points, impacted());
+            }
+        }
+    }
+
+    let names: Vec<&str> = changed_names.iter().copied().collect();
+    let tests = affected_tests(index, &names, max_depth, min_confidence);
+
+    let roots: Vec<&str> = changed
+        .iter()
+        .map(|c| c.symbol.qualified.as_str())
+        .collect();
+    let gateways = graph
+        .gateways(&roots)
+        .into_iter()
+        .filter_map(|(qualified, dominates)| {
+            let symbol = callers.get(&qualified)?.symbol.clone();
+            Some(Gateway { symbol, dominates })
+        })
+        .collect();
+
+    Impact {
+        changed,
+        callers: sorted(callers),
+        gateways,
+        entry_points: sorted(entry_points),
+        tests,
+    }
+}
+
+/// Reverse call graph of the impact walk: callee -> callers, in the order
+/// the edges were found.
+#[derive(Default)]
+struct CallerGraph {
+    names: Vec<String>,
+    ids: HashMap<String, usize>,
+    callers: Vec<Vec<usize>>,
+}
+
+impl CallerGraph {
+    fn id(&mut self, name: &str) -> usize {
+        if let Some(&id) = self.ids.get(name) {
+            return id;
+        }
+        self.names.push(name.to_string());
+        self.callers.push(Vec::new());
+        self.ids.insert(name.to_string(), self.names.len() - 1);
+        self.names.len() - 1
+    }
+
+    fn add_edge(&mut self, callee: &str, caller: &str) {
+        let (callee, caller) = (self.id(callee), self.id(caller));
+        if !self.callers[callee].contains(&caller) {
+            self.callers[callee].push(caller);
+        }
+    }
+
+    /// Callers not in `roots` whose immediate dominator is a root (or the
+    /// virtual entry above all roots) and that dominate at least one other
+    /// node, with the number they dominate.
+    fn gateways(&self, roots: &[&str]) -> Vec<(String, usize)> {
+        // Node 0 is a virtual entry calling into every root
+        let n = self.names.len() + 1;
+        let mut successors: Vec<Vec<usize>> = vec![Vec::new(); n];
+        successors[0] = roots
+            .iter()
+            .filter_map(|r| self.ids.get(*r))
+            .map(|&id| id + 1)
+            .collect();
+        for (callee, callers) in self.callers.iter().enumerate() {
+            successors[callee + 1] = callers.iter().map(|&c| c + 1).collect();
+        }
+        let idom = immediate_dominators(&successors);
+
+        // Dominator tree sizes; dominators come first in reverse postorder
+        let order = reverse_postorder(&successors);
+        let mut size = vec![1usize; n];
+        for &node in order.iter().skip(1).rev() {
+            if let Some(parent) = idom[node] {
+                size[parent] += size[node];
+            }
+        }
+
+        let is_root: HashSet<usize> = successors[0].iter().copied().collect();
+        let mut gateways: Vec<(String, usize)> = order
+            .iter()
+            .skip(1)
+            .filter(|node| !is_root.contains(node))
+            .filter(|&&node| idom[node].is_some_and(|d| d == 0 || is_root.contains(&d)))
+            .filter(|&&node| size[node] > 1)
+            .map(|&node| (self.names[node - 1].clone(), size[node] - 1))
+            .collect();
+        gateways.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        gateways
+    }
+}
+
+/// Nodes reachable from node 0, in reverse postorder of a depth-first walk.
+fn reverse_postorder(successors: &[Vec<usize>]) -> Vec<usize> {
+    let mut seen = vec![false; successors.len()];
+    let mut order = Vec::new();
+    let mut stack = vec![(0, 0)];
+    seen[0] = true;
+    while let Some((node, next)) = stack.pop() {
+        match successors[node].get(next) {
+            Some(&child) => {
+                stack.push((node, next + 1));
+                if !seen[child] {
+                    seen[child] = true;
+                    stack.push((child, 0));
+                }
+            }
+            None => order.push(node),
+        }
+    }
+    order.reverse();
+    order
+}
+
+/// Immediate dominator of each node reachable from node 0 (`Some(0)` for
+/// node 0 itself, `None` when unreachable), by Cooper, Harvey and Kennedy's
+/// iterative algorithm.
+fn immediate_dominators(successors: &[Vec<usize>]) -> Vec<Option<usize>> {
+    let order = reverse_postorder(successors);
+    let mut position = vec![usize::MAX; successors.len()];
+    for (i, &node) in order.iter().enumerate() {
+        position[node] = i;
+    }
+    let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); successors.len()];
+    for &node in &order {
+        for &child in &successors[node] {
+            predecessors[child].push(node);
+        }
+    }
+
+    let mut idom: Vec<Option<usize>> = vec![None; successors.len()];
+    idom[0] = Some(0);
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &node in order.iter().skip(1) {
+            let mut new_idom: Option<usize> = None;
+            for &pred in &predecessors[node] {
+                if idom[pred].is_none() {
+                    continue;
+                }
+                new_idom = Some(match new_idom {
+                    None => pred,
+                    Some(current) => {
+                        let (mut a, mut b) = (pred, current);
+                        while a != b {
+                            while position[a] > position[b] {
+                                a = idom[a].expect("processed");
+                            }
+                            while position[b] > position[a] {
+                                b = idom[b].expect("processed");
+                            }
+                        }
+                        a
+                    }
+                });
+            }
+            if new_idom.is_some() && idom[node] != new_idom {
+                idom[node] = new_idom;
+                changed = true;
+            }
+        }
+    }
+    idom
+}
+
+pub(crate) fn keep_closest(found: &mut HashMap<String, ImpactedSymbol>, impacted: ImpactedSymbol) {
+    match found.get(&impacted.symbol.qualified) {
+        Some(existing) if existing.depth <= impacted.depth => {}
+        _ => {
+            found.insert(impacted.symbol.qualified.clone(), impacted);
+        }
+    }
+}
+
+pub(crate) fn sorted(found: HashMap<String, ImpactedSymbol>) -> Vec<ImpactedSymbol> {
+    let mut found: Vec<ImpactedSymbol> = found.into_values().collect();
+    found.sort_by(|a, b| {
+        a.depth
+            .cmp(&b.depth)
+            .then_with(|| a.symbol.qualified.cmp(&b.symbol.qualified))
+    });
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Location, Reference, ReferenceKind, SymbolKind, Visibility};
+    use std::path::PathBuf;
+
+    fn add(index: &mut CodeIndex, qualified: &str, file: &str, line: u32, end_line: u32) {
+        let name = qualified.rsplit('.').next().unwrap();
+        index.add_symbol(Symbol::new(
+            name.to_string(),
+            qualified.to_string(),
+            SymbolKind::Function,
+            Location::with_end(PathBuf::from(file), line, 1, end_line, 1),
+            Visibility::Public,
+            "python".to_string(),
+        ));
+    }
+
+    fn call(index: &mut CodeIndex, name: &str, file: &str, line: u32) {
+        index.add_reference(
+            PathBuf::from(file),
+            Reference {
+                name: name.to_string(),
+                location: Location::new(PathBuf::from(file), line, 5),
+                kind: ReferenceKind::Read,
+            },
+        );
+    }
+
+    #[test]
+    fn test_impact_of_changed_function() {
+        let mut index = CodeIndex::with_root(PathBuf::from("/repo"));
+        add(
+            &mut index,
+            "app.billing.charge",
+            "/repo/app/billing.py",
+            1,
+            3,
+        );
+        add(
+            &mut index,
+            "app.billing.refund",
+            "/repo/app/billing.py",
+            5,
+            7,
+        );
+        add(&mut index, "app.orders.place", "/repo/app/orders.py", 1, 4);
+        add(&mut index, "app.api.checkout", "/repo/app/api.py", 1, 4);
+        add(
+            &mut index,
+            "tests.test_orders.test_place",
+            "/repo/tests/test_orders.py",
+            1,
+            3,
+        );
+        // checkout -> place -> charge; test_place -> place
+        call(&mut index, "app.billing.charge", "/repo/app/orders.py", 2);
+        call(&mut index, "app.orders.place", "/repo/app/api.py", 2);
+        call(
+            &mut index,
+            "app.orders.place",
+            "/repo/tests/test_orders.py",
+            2,
+        );
+
+        let diff = vec![ChangedFile {
+            path: PathBuf::from("app/billing.py"),
+            ranges: vec![(2, 2)],
+        }];
+        let changed = changed_symbols(&index, Path::new("/repo"), &diff);
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].symbol.qualified, "app.billing.charge");
+        assert_eq!(changed[0].lines, vec![2]);
+
+        let impact = analyze_impact(&index, changed, 5, Confidence::StringMatch);
+        let callers: Vec<(&str, usize)> = impact
+            .callers
+            .iter()
+            .map(|c| (c.symbol.qualified.as_str(), c.depth))
+            .collect();
+        assert_eq!(
+            callers,
+            vec![("app.orders.place", 1), ("app.api.checkout", 2)]
+        );
+        assert_eq!(impact.entry_points.len(), 1);
+        assert_eq!(impact.entry_points[0].symbol.qualified, "app.api.checkout");
+        assert_eq!(impact.tests.len(), 1);
+        assert_eq!(
+            impact.tests[0].symbol.as_ref().unwrap().qualified,
+            "tests.test_orders.test_place"
+        );
+        // Everything past place is reached through it
+        assert_eq!(impact.gateways.len(), 1);
+        assert_eq!(impact.gateways[0].symbol.qualified, "app.orders.place");
+        assert_eq!(impact.gateways[0].dominates, 1);
+    }
+
+    #[test]
+    fn test_gateways_dominate_the_callers_behind_them() {
+        let mut index = CodeIndex::with_root(PathBuf::from("/repo"));
+        for name in ["save", "place", "audit", "checkout", "batch", "main"] {
+            let file = format!("/repo/app/{}.py", name);
+            add(&mut index, &format!("app.{}", name), &file, 1, 9);
+        }
+        // place and audit call save; checkout and batch only go through
+        // place; main calls both checkout and audit
+        for (callee, caller) in [
+            ("app.save", "place"),
+            ("app.save", "audit"),
+            ("app.place", "checkout"),
+            ("app.place", "batch"),
+            ("app.checkout", "main"),
+            ("app.audit", "main"),
+        ] {
+            call(&mut index, callee, &format!("/repo/app/{}.py", caller), 2);
+        }
+
+        let changed = vec![ChangedSymbol {
+            symbol: index.get("app.save").unwrap().clone(),
+            lines: vec![1],
+        }];
+        let impact = analyze_impact(&index, changed, 5, Confidence::StringMatch);
+        assert_eq!(impact.callers.len(), 5);
+        let gateways: Vec<(&str, usize)> = impact
+            .gateways
+            .iter()
+            .map(|g| (g.symbol.qualified.as_str(), g.dominates))
+            .collect();
+        // main is reachable around place, through audit
+        assert_eq!(gateways, vec![("app.place", 2)]);
+    }
+}
@@ -60,13 +60,14 @@ use crate::{Location, Symbol};
 /// # Examples
 ///
 /// ```
-/// use rocketindex::Reference;
+/// use rocketindex::{Reference, ReferenceKind};
 /// use rocketindex::Location;
 /// use std::path::PathBuf;
 ///
 /// let reference = Reference {
 ///     name: "process_payment".to_string(),
 ///     location: Location::new(PathBuf::from("src/main.rs"), 25, 10),
+///     kind: ReferenceKind::Call,
 /// };
 /// assert_eq!(reference.name, "process_payment");
 /// ```
@@ -76,6 +77,248 @@ pub struct Reference {
     pub name: String,
     /// Where the reference appears (path is relative to workspace root)
     pub location: Location,
+    /// How the name is used there
+    #[serde(default)]
+    pub kind: ReferenceKind,
+}
+
+/// A local name a file gives a module, namespace or type: F#
+/// `module Db = MyApp.Data.Database` or C# `using Json = Newtonsoft.Json;`.
+///
+/// References written through the alias (`Db.connect`) resolve as if they
+/// were written through the target (`MyApp.Data.Database.connect`).
+///
+/// # Examples
+///
+/// ```
+/// use rocketindex::ModuleAlias;
+///
+/// let alias = ModuleAlias {
+///     alias: "Db".to_string(),
+///     target: "MyApp.Data.Database".to_string(),
+/// };
+/// assert_eq!(alias.expand("Db.connect").as_deref(), Some("MyApp.Data.Database.connect"));
+/// assert_eq!(alias.expand("Dbx.connect"), None);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModuleAlias {
+    /// The local name
+    pub alias: String,
+    /// What it stands for, as written
+    pub target: String,
+}
+
+impl ModuleAlias {
+    /// `name` with the alias replaced by its target, when `name` is the
+    /// alias or starts with it followed by a dot (or `::`, in Rust).
+    #[must_use]
+    pub fn expand(&self, name: &str) -> Option<String> {
+        if name == self.alias {
+            return Some(self.target.clone());
+        }
+        let rest = name.strip_prefix(&self.alias)?;
+        ["::", "."].iter().find_map(|separator| {
+            let rest = rest.strip_prefix(separator)?;
+            Some(format!("{}{}{}", self.target, separator, rest))
+        })
+    }
+}
+
+/// How a reference uses the name it refers to, judged from the syntax
+/// around it.
+///
+/// # Examples
+///
+/// ```
+/// use rocketindex::ReferenceKind;
+///
+/// assert_eq!("type-use".parse::<ReferenceKind>(), Ok(ReferenceKind::TypeUse));
+/// assert_eq!(ReferenceKind::Call.to_string(), "call");
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReferenceKind {
+    /// Called, or constructed: `process()`, `new Order()`
+    Call,
+    /// Named as a type: annotations, parameters, generic arguments
+    TypeUse,
+    /// Named by an import, `use`, `using` or `open`
+    Import,
+    /// Named as a base class. Languages that list base classes and
+    /// interfaces together (C#, Kotlin, Swift) report all of them as this
+    Inherit,
+    /// Named as an implemented interface or trait
+    Implement,
+    /// Any other use of the value
+    #[default]
+    Read,
+    /// Assigned to
+    Write,
+}
+
+impl ReferenceKind {
+    pub const ALL: [ReferenceKind; 7] = [
+        ReferenceKind::Call,
+        ReferenceKind::TypeUse,
+        ReferenceKind::Import,
+        ReferenceKind::Inherit,
+        ReferenceKind::Implement,
+        ReferenceKind::Read,
+        ReferenceKind::Write,
+    ];
+
+    /// The name used in CLI flags, JSON output and the database.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            ReferenceKind::Call => "call",
+            ReferenceKind::TypeUse => "type-use",
+            ReferenceKind::Import => "import",
+            ReferenceKind::Inherit => "inherit",
+            ReferenceKind::Implement => "implement",
+            ReferenceKind::Read => "read",
+            ReferenceKind::Write => "write",
+        }
+    }
+}
+
+impl std::fmt::Display for ReferenceKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for ReferenceKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        ReferenceKind::ALL
+            .into_iter()
+            .find(|k| k.as_str() == s)
+            .ok_or_else(|| {
+                format!(
+                    "unknown reference kind '{}' (expected call, type-use, import, inherit, implement, read or write)",
+                    s
+                )
+            })
+    }
+}
+
+/// How confident we are that a reference (or resolution) points at a symbol.
+///
+/// Ordered from least to most confident, so `confidence >= min` filters work.
+///
+/// # Examples
+///
+/// ```
+/// use rocketindex::Confidence;
+///
+/// assert!(Confidence::Exact > Confidence::Heuristic);
+/// assert_eq!("string-match".parse::<Confidence>(), Ok(Confidence::StringMatch));
+/// assert_eq!(Confidence::of_match("Utils.helper", "MyApp.Utils.helper"), Confidence::Qualified);
+/// ```
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum Confidence {
+    /// A symbol name found inside a string literal (reflection, DI registrations)
+    StringMatch,
+    /// Matched by short name only; may refer to a different symbol with the same name
+    #[default]
+    Heuristic,
+    /// Written with a partial qualifier or resolved through scope rules (opens, parent modules)
+    Qualified,
+    /// The fully qualified name matches an indexed symbol
+    Exact,
+}
+
+impl Confidence {
+    /// All levels, from least to most confident.
+    pub const ALL: [Confidence; 4] = [
+        Confidence::StringMatch,
+        Confidence::Heuristic,
+        Confidence::Qualified,
+        Confidence::Exact,
+    ];
+
+    /// Confidence that a reference written as `reference_name` refers to `qualified`.
+    #[must_use]
+    pub fn of_match(reference_name: &str, qualified: &str) -> Self {
+        if reference_name == qualified {
+            Confidence::Exact
+        } else if is_qualified_name(reference_name)
+            && qualified
+                .strip_suffix(reference_name)
+                .is_some_and(ends_with_separator)
+        {
+            Confidence::Qualified
+        } else {
+            Confidence::Heuristic
+        }
+    }
+
+    /// The name used in CLI flags, JSON output and the database.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Confidence::StringMatch => "string-match",
+            Confidence::Heuristic => "heuristic",
+            Confidence::Qualified => "qualified",
+            Confidence::Exact => "exact",
+        }
+    }
+}
+
+/// The type or module a member is defined in: `shapes::Square` for
+/// `shapes::Square::new`, `Square` for `Square#initialize`.
+fn owner_of(symbol: &Symbol) -> Option<&str> {
+    symbol
+        .qualified
+        .strip_suffix(&symbol.name)
+        .filter(|owner| owner.ends_with(['.', ':', '#']))
+        .map(|owner| owner.trim_end_matches(['.', ':', '#']))
+}
+
+/// What a call to the constructor `constructor` is made on: `Square` for
+/// `Square::new` or `Square.new`.
+fn constructor_receiver<'a>(reference_name: &'a str, constructor: &str) -> Option<&'a str> {
+    reference_name
+        .strip_suffix(constructor)
+        .filter(|receiver| receiver.ends_with(['.', ':']))
+        .map(|receiver| receiver.trim_end_matches(['.', ':']))
+        .filter(|receiver| !receiver.is_empty())
+}
+
+impl std::fmt::Display for Confidence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for Confidence {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Confidence::ALL
+            .into_iter()
+            .find(|c| c.as_str() == s)
+            .ok_or_else(|| {
+                format!(
+                    "unknown confidence '{}' (expected exact, qualified, heuristic or string-match)",
+                    s
+                )
+            })
+    }
+}
+
+/// Whether a name contains a namespace/member separator used by any supported language.
+fn is_qualified_name(name: &str) -> bool {
+    name.contains('.') || name.contains("::") || name.contains('\\')
+}
+
+fn ends_with_separator(prefix: &str) -> bool {
+    prefix.ends_with('.') || prefix.ends_with("::") || prefix.ends_with('\\')
 }
 
 /// The main index storing all symbols and their relationships.
@@ -135,6 +378,10 @@ pub struct CodeIndex {
     /// File (relative path) -> parsed opens/imports
     file_opens: HashMap<PathBuf, Vec<String>>,
 
+    /// File (relative path) -> module aliases
+    #[serde(default)]
+    file_aliases: HashMap<PathBuf, Vec<ModuleAlias>>,
+
     /// File compilation order from .fsproj (relative paths)
     /// Index 0 = first file compiled, higher = later
     /// Empty if no .fsproj was found
@@ -147,6 +394,16 @@ pub struct CodeIndex {
     /// Optional external index for .NET assembly symbols (not serialized - loaded separately)
     #[serde(skip)]
     external_index: Option<crate::external_index::ExternalIndex>,
+
+    /// Roots of attached repositories and their names (not serialized - see
+    /// [`crate::federation`])
+    #[serde(skip)]
+    attached_roots: Vec<(PathBuf, String)>,
+
+    /// Whether Ruby resolution follows Rails naming conventions (not
+    /// serialized - set from [`crate::config::Config::rails`])
+    #[serde(skip)]
+    rails_conventions: bool,
 }
 
 impl CodeIndex {
@@ -177,7 +434,7 @@ impl CodeIndex {
     }
 
     /// Convert an absolute path to a path relative to workspace root.
-    fn to_relative(&self, path: &Path) -> PathBuf {
+    pub(crate) fn to_relative(&self, path: &Path) -> PathBuf {
         if let Some(root) = &self.workspace_root {
             path.strip_prefix(root).unwrap_or(path).to_path_buf()
         } else {
@@ -186,7 +443,7 @@ impl CodeIndex {
     }
 
     /// Convert a relative path to an absolute path using workspace root.
-    fn to_absolute(&self, path: &Path) -> PathBuf {
+    pub(crate) fn to_absolute(&self, path: &Path) -> PathBuf {
         if path.is_absolute() {
             return path.to_path_buf();
         }
@@ -267,6 +524,17 @@ impl CodeIndex {
             .push(module);
     }
 
+    /// Add a module alias for a file.
+    ///
+    /// The file path will be converted to a relative path.
+    pub fn add_alias(&mut self, file: PathBuf, alias: ModuleAlias) {
+        let relative_file = self.to_relative(&file);
+        self.file_aliases
+            .entry(relative_file)
+            .or_default()
+            .push(alias);
+    }
+
     /// Get a symbol by its qualified name.
     ///
     /// Note: The returned symbol's file path is relative to the workspace root.
@@ -315,6 +583,44 @@ impl CodeIndex {
             .unwrap_or_default()
     }
 
+    /// Get the symbols whose definitions cover lines `start..=end` of a file.
+    ///
+    /// Each line maps to the innermost symbol whose start/end span contains
+    /// it, so editing a method reports the method rather than its class.
+    /// Symbols recorded without a span only cover their first line, and a
+    /// line no span covers falls back to a span-less definition above it.
+    /// Results are in definition order.
+    pub fn symbols_in_range(&self, file: &Path, start: u32, end: u32) -> Vec<&Symbol> {
+        let mut symbols = self.symbols_in_file(file);
+        symbols.sort_by_key(|s| (s.location.line, s.location.column));
+        let span_end = |s: &Symbol| s.location.end_line.max(s.location.line);
+
+        let mut touched = vec![false; symbols.len()];
+        for line in start..=end {
+            let innermost = symbols
+                .iter()
+                .enumerate()
+                .filter(|(_, s)| s.location.line <= line && line <= span_end(s))
+                .min_by_key(|(_, s)| span_end(s) - s.location.line)
+                .map(|(i, _)| i);
+            let hit = innermost.or_else(|| {
+                symbols
+                    .iter()
+                    .rposition(|s| s.location.line <= line)
+                    .filter(|&i| span_end(symbols[i]) == symbols[i].location.line)
+            });
+            if let Some(i) = hit {
+                touched[i] = true;
+            }
+        }
+
+        symbols
+            .into_iter()
+            .zip(touched)
+            .filter_map(|(s, hit)| hit.then_some(s))
+            .collect()
+    }
+
     /// Get all references in a file.
     ///
     /// The file path can be either absolute or relative.
@@ -337,6 +643,25 @@ impl CodeIndex {
             .unwrap_or(&[])
     }
 
+    /// Get all module aliases for a file.
+    ///
+    /// The file path can be either absolute or relative.
+    pub fn aliases_for_file(&self, file: &Path) -> &[ModuleAlias] {
+        let relative_file = self.to_relative(file);
+        self.file_aliases
+            .get(&relative_file)
+            .map(|aliases| aliases.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// `name` as written in `file` with a module alias expanded, or `None`
+    /// when it doesn't go through one of the file's aliases.
+    pub fn expand_alias(&self, name: &str, file: &Path) -> Option<String> {
+        self.aliases_for_file(file)
+            .iter()
+            .find_map(|alias| alias.expand(name))
+    }
+
     /// Find all references to a symbol across the codebase.
     ///
     /// Returns a list of locations where the symbol (or a name that could refer to it)
@@ -344,6 +669,8 @@ impl CodeIndex {
     /// - The short name (e.g., "helper")
     /// - The qualified name (e.g., "Utils.helper")
     /// - Any suffix of the qualified name
+    ///
+    /// References are ordered by location.
     pub fn find_references(&self, qualified_name: &str) -> Vec<&Reference> {
         let symbols = match self.definitions.get(qualified_name) {
             Some(s) if !s.is_empty() => s,
@@ -375,6 +702,7 @@ impl CodeIndex {
             }
         }
 
+        results.sort_by(|a, b| a.location.cmp(&b.location));
         results
     }
 
@@ -428,17 +756,34 @@ impl CodeIndex {
                 return b_prefix.cmp(&a_prefix);
             }
 
-            // Otherwise sort by name length (shorter = more specific)
-            a.name.len().cmp(&b.name.len())
+            // Otherwise sort by name length (shorter = more specific), and
+            // equally good matches by where they're defined
+            a.name
+                .len()
+                .cmp(&b.name.len())
+                .then_with(|| a.location.cmp(&b.location))
+                .then_with(|| a.qualified.cmp(&b.qualified))
         });
 
         results
     }
 
+    /// Iterate over every definition in the index.
+    pub fn symbols(&self) -> impl Iterator<Item = &Symbol> {
+        self.definitions.values().flatten()
+    }
+
+    /// Iterate over every reference in the index.
+    pub fn references(&self) -> impl Iterator<Item = &Reference> {
+        self.file_references.values().flatten()
+    }
+
     /// Get all qualified names in the index (for fuzzy matching).
     #[must_use]
     pub fn all_qualified_names(&self) -> Vec<String> {
-        self.definitions.keys().cloned().collect()
+        let mut names: Vec<String> = self.definitions.keys().cloned().collect();
+        names.sort();
+        names
     }
 
     /// Get all symbol names (short and qualified) for fuzzy matching.
@@ -451,7 +796,38 @@ impl CodeIndex {
                 names.insert(sym.qualified.clone());
             }
         }
-        names.into_iter().collect()
+        let mut names: Vec<String> = names.into_iter().collect();
+        names.sort();
+        names
+    }
+
+    /// "Did you mean" suggestions for a symbol that wasn't found, ranked by
+    /// [`crate::fuzzy::rank_suggestions`] with references counted by name.
+    #[must_use]
+    pub fn suggest(&self, query: &str) -> Vec<crate::fuzzy::Suggestion> {
+        let mut references: HashMap<&str, u32> = HashMap::new();
+        for reference in self.references() {
+            let name = reference
+                .name
+                .rsplit(['.', ':', '#'])
+                .next()
+                .unwrap_or(&reference.name);
+            *references.entry(name).or_default() += 1;
+        }
+        let candidates = self.symbols().flat_map(|symbol| {
+            let references = references.get(symbol.name.as_str()).copied().unwrap_or(0);
+            [&symbol.name, &symbol.qualified].map(|value| crate::fuzzy::Candidate {
+                value,
+                kind: symbol.kind,
+                references,
+            })
+        });
+        crate::fuzzy::rank_suggestions(
+            query,
+            candidates,
+            crate::fuzzy::DEFAULT_MAX_DISTANCE,
+            crate::fuzzy::DEFAULT_MAX_SUGGESTIONS,
+        )
     }
 
     /// Clear all data for a specific file (used before re-indexing).
@@ -477,8 +853,9 @@ impl CodeIndex {
         // Remove from file_references
         self.file_references.remove(&relative_file);
 
-        // Remove from file_opens
+        // Remove from file_opens and file_aliases
         self.file_opens.remove(&relative_file);
+        self.file_aliases.remove(&relative_file);
 
         // Clean up module_files (remove file from all module entries)
         for files in self.module_files.values_mut() {
@@ -488,15 +865,105 @@ impl CodeIndex {
 
     /// Get all symbols defined in a specific module.
     ///
-    /// Returns symbols whose qualified name starts with the given module prefix.
+    /// Returns symbols whose qualified name starts with the given module
+    /// prefix, ordered by location.
     #[must_use]
     pub fn symbols_in_module(&self, module: &str) -> Vec<&Symbol> {
         let prefix = format!("{}.", module);
-        self.definitions
+        let mut symbols: Vec<&Symbol> = self
+            .definitions
             .iter()
             .filter(|(qualified, _)| qualified.starts_with(&prefix) || *qualified == module)
             .flat_map(|(_, symbols)| symbols.iter())
-            .collect()
+            .collect();
+        symbols.sort_by(|a, b| a.location.cmp(&b.location));
+        symbols
+    }
+
+    /// Constructors of the type `type_qualified` (see
+    /// [`Symbol::is_constructor`]), ordered by location. Go's `NewType`
+    /// counts when it's in the type's package.
+    #[must_use]
+    pub fn constructors_of(&self, type_qualified: &str) -> Vec<&Symbol> {
+        let Some(ty) = self.get(type_qualified) else {
+            return Vec::new();
+        };
+        let go_constructor = format!("New{}", ty.name);
+        let mut constructors: Vec<&Symbol> = self
+            .symbols()
+            .filter(|symbol| symbol.is_constructor())
+            .filter(|symbol| {
+                let owner = owner_of(symbol);
+                owner == Some(type_qualified)
+                    || (symbol.name == go_constructor && owner == owner_of(ty))
+            })
+            .collect();
+        constructors.sort_by(|a, b| a.location.cmp(&b.location));
+        constructors
+    }
+
+    /// References that create a `type_qualified` through one of its
+    /// constructors (`Square::new`, `Square.new` in Ruby, `NewSquare` in
+    /// Go), with how sure the match is, ordered by location. Uses of the
+    /// type's own name (`Square()`, `new Square()`) are ordinary references
+    /// to it and aren't repeated here.
+    #[must_use]
+    pub fn instantiations(&self, type_qualified: &str) -> Vec<(&Reference, Confidence)> {
+        let Some(ty) = self.get(type_qualified) else {
+            return Vec::new();
+        };
+        let constructors = self.constructors_of(type_qualified);
+        let mut names: Vec<&str> = constructors
+            .iter()
+            .filter(|constructor| constructor.name != ty.name)
+            .map(|constructor| constructor.name.as_str())
+            .collect();
+        // Ruby's `new` runs `initialize`, whether or not the class defines one
+        if ty.language == "ruby" {
+            names.push("new");
+        }
+        let go_constructors: Vec<&Symbol> = constructors
+            .iter()
+            .copied()
+            .filter(|constructor| constructor.language == "go")
+            .collect();
+
+        let mut found = Vec::new();
+        for reference in self.references() {
+            let by_function = go_constructors.iter().find(|constructor| {
+                reference.name == constructor.name
+                    || reference.name == constructor.qualified
+                    || reference.name.ends_with(&format!(".{}", constructor.name))
+            });
+            if let Some(constructor) = by_function {
+                let confidence = Confidence::of_match(&reference.name, &constructor.qualified);
+                found.push((reference, confidence));
+                continue;
+            }
+            let receiver = names
+                .iter()
+                .find_map(|name| constructor_receiver(&reference.name, name));
+            let Some(receiver) = receiver else {
+                continue;
+            };
+            if receiver.rsplit(['.', ':']).next() == Some(ty.name.as_str()) {
+                found.push((reference, Confidence::of_match(receiver, type_qualified)));
+            }
+        }
+        found.sort_by(|(a, _), (b, _)| a.location.cmp(&b.location));
+        found
+    }
+
+    /// Types that implement or derive `interface` (see
+    /// [`Symbol::implements_type`]), ordered by location.
+    #[must_use]
+    pub fn find_implementers(&self, interface: &str) -> Vec<&Symbol> {
+        let mut symbols: Vec<&Symbol> = self
+            .symbols()
+            .filter(|symbol| symbol.implements_type(interface))
+            .collect();
+        symbols.sort_by(|a, b| a.location.cmp(&b.location));
+        symbols
     }
 
     /// Get the total number of indexed symbols.
@@ -623,18 +1090,26 @@ impl CodeIndex {
         self.type_cache.as_ref()?.get_type(qualified_name)
     }
 
-    /// Get all members of a type.
+    /// Get all members of a type, from the type cache or else the external
+    /// index (framework and package types).
     ///
-    /// Returns `None` if no type cache is loaded or the type is not found.
+    /// Returns `None` if neither knows the type.
     pub fn get_type_members(&self, type_name: &str) -> Option<&[TypeMember]> {
-        self.type_cache.as_ref()?.get_members(type_name)
+        self.type_cache
+            .as_ref()
+            .and_then(|cache| cache.get_members(type_name))
+            .or_else(|| self.external_index.as_ref()?.members(type_name))
     }
 
-    /// Get a specific member of a type.
+    /// Get a specific member of a type, looked up as for
+    /// [`get_type_members`](Self::get_type_members).
     ///
-    /// Returns `None` if no type cache is loaded, type not found, or member not found.
+    /// Returns `None` if the type or member is not found.
     pub fn get_type_member(&self, type_name: &str, member_name: &str) -> Option<&TypeMember> {
-        self.type_cache.as_ref()?.get_member(type_name, member_name)
+        self.type_cache
+            .as_ref()
+            .and_then(|cache| cache.get_member(type_name, member_name))
+            .or_else(|| self.external_index.as_ref()?.member(type_name, member_name))
     }
 
     // =========================================================================
@@ -670,13 +1145,48 @@ impl CodeIndex {
             .as_ref()
             .map_or(Vec::new(), |idx| idx.search(pattern))
     }
+
+    // =========================================================================
+    // Attached Repositories
+    // =========================================================================
+
+    /// Mark files under `root` (absolute) as coming from the attached
+    /// repository `name`.
+    pub fn add_attached_root(&mut self, root: PathBuf, name: String) {
+        self.attached_roots.push((root, name));
+    }
+
+    /// Name of the attached repository `file` comes from, or `None` for the
+    /// workspace's own files.
+    pub fn provenance(&self, file: &Path) -> Option<&str> {
+        let file = self.to_absolute(file);
+        self.attached_roots
+            .iter()
+            .find(|(root, _)| file.starts_with(root))
+            .map(|(_, name)| name.as_str())
+    }
+
+    // =========================================================================
+    // Framework Conventions
+    // =========================================================================
+
+    /// Resolve Ruby names by Rails conventions as well as scope rules (see
+    /// [`crate::languages::ruby::rails`]).
+    pub fn set_rails_conventions(&mut self, enabled: bool) {
+        self.rails_conventions = enabled;
+    }
+
+    /// Whether Rails conventions are in effect.
+    pub fn rails_conventions(&self) -> bool {
+        self.rails_conventions
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::type_cache::{MemberKind, TypeCacheSchema, TypeMember, TypedSymbol};
-    use crate::{Location, SymbolKind, Visibility};
+    use crate::{Location, ReferenceKind, SymbolKind, Visibility};
 
     fn make_symbol(name: &str, qualified: &str, file: &str) -> Symbol {
         Symbol {
@@ -695,6 +1205,32 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_confidence_of_match() {
+        assert_eq!(
+            Confidence::of_match("Utils.helper", "Utils.helper"),
+            Confidence::Exact
+        );
+        assert_eq!(
+            Confidence::of_match("Utils::helper", "app::Utils::helper"),
+            Confidence::Qualified
+        );
+        // A suffix that doesn't start at a segment boundary is not a qualifier
+        assert_eq!(
+            Confidence::of_match("ils.helper", "MyApp.Utils.helper"),
+            Confidence::Heuristic
+        );
+        // Receiver-style calls only match by short name
+        assert_eq!(
+            Confidence::of_match("obj.helper", "Utils.helper"),
+            Confidence::Heuristic
+        );
+        assert_eq!(
+            Confidence::of_match("helper", "Utils.helper"),
+            Confidence::Heuristic
+        );
+    }
+
     #[test]
     fn test_add_and_get_symbol() {
         let mut index = CodeIndex::new();
@@ -716,6 +1252,36 @@ mod tests {
         assert_eq!(symbols.len(), 2);
     }
 
+    #[test]
+    fn test_symbols_in_range_prefers_innermost_span() {
+        let mut index = CodeIndex::new();
+        let spanning = |name: &str, line: u32, end_line: u32| {
+            let mut sym = make_symbol(name, &format!("M.{}", name), "src/a.fs");
+            sym.location = Location::with_end(PathBuf::from("src/a.fs"), line, 1, end_line, 1);
+            sym
+        };
+        index.add_symbol(spanning("Service", 1, 20));
+        index.add_symbol(spanning("save", 3, 8));
+        index.add_symbol(spanning("load", 10, 15));
+        // No recorded span
+        index.add_symbol(spanning("helper", 30, 30));
+
+        let names = |start, end| -> Vec<String> {
+            index
+                .symbols_in_range(Path::new("src/a.fs"), start, end)
+                .iter()
+                .map(|s| s.name.clone())
+                .collect()
+        };
+        assert_eq!(names(5, 6), vec!["save"]);
+        assert_eq!(names(9, 9), vec!["Service"]);
+        assert_eq!(names(7, 11), vec!["Service", "save", "load"]);
+        // Below the span-less helper, fall back to it
+        assert_eq!(names(32, 33), vec!["helper"]);
+        // Between spans with nothing span-less above: nothing
+        assert!(names(25, 26).is_empty());
+    }
+
     #[test]
     fn test_search() {
         let mut index = CodeIndex::new();
@@ -766,6 +1332,7 @@ mod tests {
             Reference {
                 name: "helper".to_string(),
                 location: Location::new(PathBuf::from("src/Main.fs"), 10, 5),
+                kind: ReferenceKind::Read,
             },
         );
         index.add_reference(
@@ -773,6 +1340,7 @@ mod tests {
             Reference {
                 name: "Utils.helper".to_string(),
                 location: Location::new(PathBuf::from("src/Main.fs"), 15, 5),
+                kind: ReferenceKind::Read,
             },
         );
         index.add_reference(
@@ -780,6 +1348,7 @@ mod tests {
             Reference {
                 name: "helper".to_string(),
                 location: Location::new(PathBuf::from("src/Other.fs"), 20, 5),
+                kind: ReferenceKind::Read,
             },
         );
 
@@ -1105,6 +1674,24 @@ mod tests {
         assert!(index.get_type_member("NonExistent", "Name").is_none());
     }
 
+    #[test]
+    fn test_type_members_fall_back_to_external_index() {
+        let mut index = CodeIndex::new();
+        index.set_type_cache(make_type_cache());
+        index.set_external_index(crate::external_index::index_external_assemblies(&[]));
+
+        // The type cache answers for workspace types, the external index
+        // for framework ones
+        assert!(index.get_type_member("User", "Save").is_some());
+        let length = index.get_type_member("string", "Length").unwrap();
+        assert_eq!(length.type_name, "System.String");
+        assert!(index
+            .get_type_members("Console")
+            .unwrap()
+            .iter()
+            .any(|member| member.member == "WriteLine"));
+    }
+
     #[test]
     fn test_load_type_cache_missing_file() {
         let mut index = CodeIndex::new();
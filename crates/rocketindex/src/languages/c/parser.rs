@@ -3,8 +3,10 @@
 use std::cell::RefCell;
 use std::path::Path;
 
-use crate::parse::{find_child_by_kind, node_to_location, LanguageParser, ParseResult};
-use crate::{Reference, Symbol, SymbolKind, Visibility};
+use crate::parse::{
+    find_child_by_kind, node_to_location, reference_kind, LanguageParser, ParseResult,
+};
+use crate::{Reference, ReferenceKind, Symbol, SymbolKind, Visibility};
 
 // Thread-local parser reuse - avoids creating a new parser per file
 thread_local! {
@@ -40,6 +42,8 @@ impl LanguageParser for CParser {
             // Extract references in a separate pass
             extract_references_recursive(&root, source.as_bytes(), file, &mut result);
 
+            result.metrics = crate::metrics::measure(&root, &result.symbols);
+
             result
         })
     }
@@ -741,10 +745,25 @@ fn extract_references_recursive(
             result.references.push(Reference {
                 name: name.to_string(),
                 location: node_to_location(file, node),
+                kind: reference_kind(node),
             });
         }
     }
 
+    // field_expression outside a call is a struct field access: p.x or p->x
+    // (calls through a field are recorded below)
+    if node.kind() == "field_expression" && !is_call_target(node) {
+        if let Some(field) = node.child_by_field_name("field") {
+            if let Ok(name) = field.utf8_text(source) {
+                result.references.push(Reference {
+                    name: name.to_string(),
+                    location: node_to_location(file, &field),
+                    kind: reference_kind(&field),
+                });
+            }
+        }
+    }
+
     // call_expression represents a function call: functionName(args)
     // Extract the function name as a reference
     if node.kind() == "call_expression" {
@@ -758,6 +777,7 @@ fn extract_references_recursive(
             result.references.push(Reference {
                 name: func_name,
                 location,
+                kind: ReferenceKind::Call,
             });
         }
     }
@@ -770,6 +790,13 @@ fn extract_references_recursive(
     }
 }
 
+/// Check if a node is the function being called by its parent call_expression
+fn is_call_target(node: &tree_sitter::Node) -> bool {
+    node.parent().is_some_and(|parent| {
+        parent.kind() == "call_expression" && parent.child_by_field_name("function") == Some(*node)
+    })
+}
+
 /// Extract the function name from a call_expression node
 /// Handles simple calls like `foo()` and field expressions like `obj->method()`
 fn extract_call_function_name(node: &tree_sitter::Node, source: &[u8]) -> Option<String> {
@@ -1234,4 +1261,41 @@ struct Outer {
             qualified
         );
     }
+
+    #[test]
+    fn extracts_struct_field_references() {
+        let parser = CParser;
+        let source = r#"
+struct Point { int x; int y; };
+
+int shift(struct Point *p) {
+    p->x = p->y + 1;
+    p->move();
+    return p->x;
+}
+"#;
+        let result = parser.extract_symbols(Path::new("test.c"), source, 100);
+
+        let x_refs: Vec<_> = result
+            .references
+            .iter()
+            .filter(|r| r.name == "x")
+            .map(|r| (r.location.line, r.kind))
+            .collect();
+        assert_eq!(
+            x_refs,
+            vec![(5, ReferenceKind::Write), (7, ReferenceKind::Read)],
+            "found: {:?}",
+            result.references
+        );
+
+        // A call through a field is recorded once, as a call
+        let moves: Vec<_> = result
+            .references
+            .iter()
+            .filter(|r| r.name == "move")
+            .collect();
+        assert_eq!(moves.len(), 1);
+        assert_eq!(moves[0].kind, ReferenceKind::Call);
+    }
 }
@@ -10,7 +10,7 @@ use std::path::Path;
 
 use crate::parse::ParseResult;
 use crate::resolve::{ResolutionPath, ResolveResult, SymbolResolver};
-use crate::{CodeIndex, Reference};
+use crate::{CodeIndex, Reference, ReferenceKind};
 
 pub struct CResolver;
 
@@ -71,6 +71,7 @@ impl CResolver {
                 references.push(Reference {
                     name: parent.clone(),
                     location: symbol.location.clone(),
+                    kind: ReferenceKind::TypeUse,
                 });
             }
         }
@@ -86,6 +87,7 @@ impl CResolver {
                     end_line: 1,
                     end_column: 1,
                 },
+                kind: ReferenceKind::Import,
             });
         }
 
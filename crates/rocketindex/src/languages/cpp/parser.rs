@@ -3,8 +3,10 @@
 use std::cell::RefCell;
 use std::path::Path;
 
-use crate::parse::{find_child_by_kind, node_to_location, LanguageParser, ParseResult};
-use crate::{Reference, Symbol, SymbolKind, Visibility};
+use crate::parse::{
+    find_child_by_kind, node_to_location, reference_kind, LanguageParser, ParseResult,
+};
+use crate::{Reference, ReferenceKind, Symbol, SymbolKind, Visibility};
 
 // Thread-local parser reuse - avoids creating a new parser per file
 thread_local! {
@@ -40,6 +42,17 @@ impl LanguageParser for CppParser {
             // Extract references in a separate pass
             extract_references_recursive(&root, source.as_bytes(), file, &mut result);
 
+            crate::parse::capture_lambdas(
+                &root,
+                source.as_bytes(),
+                file,
+                &["lambda_expression"],
+                "cpp",
+                &mut result,
+            );
+
+            result.metrics = crate::metrics::measure(&root, &result.symbols);
+
             result
         })
     }
@@ -745,6 +758,7 @@ fn extract_references_recursive(
                     result.references.push(Reference {
                         name: name.to_string(),
                         location: node_to_location(file, node),
+                        kind: reference_kind(node),
                     });
                 }
             }
@@ -756,11 +770,25 @@ fn extract_references_recursive(
                     result.references.push(Reference {
                         name: name.to_string(),
                         location: node_to_location(file, node),
+                        kind: reference_kind(node),
                     });
                 }
                 return; // Don't recurse into qualified_identifier children
             }
         }
+        // field_expression outside a call is a member access: obj.field or ptr->field
+        // (calls through a field are recorded as calls below)
+        "field_expression" if !is_call_target(node) => {
+            if let Some(field) = node.child_by_field_name("field") {
+                if let Ok(name) = field.utf8_text(source) {
+                    result.references.push(Reference {
+                        name: name.to_string(),
+                        location: node_to_location(file, &field),
+                        kind: reference_kind(&field),
+                    });
+                }
+            }
+        }
         // call_expression represents a function/method call: functionName(args) or obj.method(args)
         "call_expression" => {
             if let Some(func_name) = extract_call_function_name(node, source) {
@@ -773,6 +801,7 @@ fn extract_references_recursive(
                 result.references.push(Reference {
                     name: func_name,
                     location,
+                    kind: ReferenceKind::Call,
                 });
             }
         }
@@ -787,6 +816,13 @@ fn extract_references_recursive(
     }
 }
 
+/// Check if a node is the function being called by its parent call_expression
+fn is_call_target(node: &tree_sitter::Node) -> bool {
+    node.parent().is_some_and(|parent| {
+        parent.kind() == "call_expression" && parent.child_by_field_name("function") == Some(*node)
+    })
+}
+
 /// Extract the function/method name from a call_expression node
 /// Handles simple calls like `foo()`, method calls like `obj.method()`, and qualified calls like `ns::func()`
 fn extract_call_function_name(node: &tree_sitter::Node, source: &[u8]) -> Option<String> {
@@ -1407,4 +1443,39 @@ public:
             ref_names
         );
     }
+
+    #[test]
+    fn extracts_member_field_references() {
+        let parser = CppParser;
+        let source = r#"
+class Point {
+public:
+    int x;
+    void move();
+};
+
+int shift(Point& p) {
+    p.move();
+    return p.x;
+}
+"#;
+        let result = parser.extract_symbols(Path::new("test.cpp"), source, 100);
+
+        assert!(
+            result
+                .references
+                .iter()
+                .any(|r| r.name == "x" && r.location.line == 10),
+            "found: {:?}",
+            result.references
+        );
+        assert_eq!(
+            result
+                .references
+                .iter()
+                .filter(|r| r.name == "move")
+                .count(),
+            1
+        );
+    }
 }
@@ -10,7 +10,7 @@ use std::path::Path;
 
 use crate::parse::ParseResult;
 use crate::resolve::{ResolutionPath, ResolveResult, SymbolResolver};
-use crate::{CodeIndex, Reference, Symbol, SymbolKind};
+use crate::{CodeIndex, Reference, ReferenceKind, Symbol, SymbolKind};
 
 pub struct CppResolver;
 
@@ -184,6 +184,7 @@ impl CppResolver {
                 references.push(Reference {
                     name: parent.clone(),
                     location: symbol.location.clone(),
+                    kind: ReferenceKind::Inherit,
                 });
             }
 
@@ -193,6 +194,7 @@ impl CppResolver {
                     references.push(Reference {
                         name: base.clone(),
                         location: symbol.location.clone(),
+                        kind: ReferenceKind::Inherit,
                     });
                 }
             }
@@ -209,6 +211,7 @@ impl CppResolver {
                     end_line: 1,
                     end_column: 1,
                 },
+                kind: ReferenceKind::Import,
             });
         }
 
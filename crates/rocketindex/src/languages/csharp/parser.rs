@@ -3,8 +3,8 @@
 use std::cell::RefCell;
 use std::path::Path;
 
-use crate::parse::{node_to_location, LanguageParser, ParseResult};
-use crate::{Reference, Symbol, SymbolKind, Visibility};
+use crate::parse::{node_to_location, reference_kind, LanguageParser, ParseResult};
+use crate::{ModuleAlias, Reference, ReferenceKind, Symbol, SymbolKind, Visibility};
 
 // Thread-local parser reuse - avoids creating a new parser per file
 thread_local! {
@@ -37,7 +37,18 @@ impl LanguageParser for CSharpParser {
 
             // Check for file-scoped namespace first - these don't have bodies,
             // so we need to pass the namespace to all sibling declarations
-            let file_namespace = extract_file_scoped_namespace(&root, source.as_bytes());
+            let file_namespace =
+                extract_file_scoped_namespace(&root, source.as_bytes()).or_else(|| {
+                    // No namespace at all: infer one from the project layout
+                    let declares_namespace = (0..root.child_count())
+                        .filter_map(|i| root.child(i))
+                        .any(|child| child.kind() == "namespace_declaration");
+                    if declares_namespace {
+                        None
+                    } else {
+                        crate::namespace::infer_csharp_namespace(file)
+                    }
+                });
 
             extract_recursive(
                 &root,
@@ -52,6 +63,21 @@ impl LanguageParser for CSharpParser {
             // Extract references in a separate pass
             extract_references_recursive(&root, source.as_bytes(), file, &mut result);
 
+            crate::parse::capture_lambdas(
+                &root,
+                source.as_bytes(),
+                file,
+                &["lambda_expression", "anonymous_method_expression"],
+                "csharp",
+                &mut result,
+            );
+            crate::conditional::tag_symbols(
+                &mut result,
+                &crate::conditional::preprocessor_regions(source),
+            );
+
+            result.metrics = crate::metrics::measure(&root, &result.symbols);
+
             result
         })
     }
@@ -280,13 +306,35 @@ fn extract_recursive(
                 "using_directive" => {
                     // Extract using statement for name resolution
                     // Handles: using System; using System.Collections.Generic;
-                    for i in 0..child.child_count() {
-                        if let Some(name_child) = child.child(i) {
-                            if name_child.kind() == "qualified_name"
-                                || name_child.kind() == "identifier"
-                            {
-                                if let Ok(text) = name_child.utf8_text(source) {
-                                    result.opens.push(text.to_string());
+                    // `using Json = Newtonsoft.Json;` is an alias, not an open
+                    if let Some(alias) = child.child_by_field_name("name") {
+                        let target = (0..child.child_count())
+                            .filter_map(|i| child.child(i))
+                            .find(|target| {
+                                target.id() != alias.id()
+                                    && matches!(
+                                        target.kind(),
+                                        "qualified_name" | "identifier" | "generic_name"
+                                    )
+                            });
+                        if let (Ok(alias), Some(Ok(target))) = (
+                            alias.utf8_text(source),
+                            target.map(|target| target.utf8_text(source)),
+                        ) {
+                            result.aliases.push(ModuleAlias {
+                                alias: alias.to_string(),
+                                target: target.to_string(),
+                            });
+                        }
+                    } else {
+                        for i in 0..child.child_count() {
+                            if let Some(name_child) = child.child(i) {
+                                if name_child.kind() == "qualified_name"
+                                    || name_child.kind() == "identifier"
+                                {
+                                    if let Ok(text) = name_child.utf8_text(source) {
+                                        result.opens.push(text.to_string());
+                                    }
                                 }
                             }
                         }
@@ -467,6 +515,18 @@ fn extract_class_members(
                         result.symbols.push(symbol);
                     }
                 }
+                "preproc_if" | "preproc_elif" | "preproc_else" => {
+                    // Members under #if/#elif/#else belong to the class all the same
+                    extract_class_members(
+                        &child,
+                        source,
+                        file,
+                        result,
+                        class_prefix,
+                        max_depth,
+                        current_depth + 1,
+                    );
+                }
                 "constructor_declaration" => {
                     if let Some(symbol) = extract_constructor(&child, source, file, class_prefix) {
                         result.symbols.push(symbol);
@@ -947,6 +1007,7 @@ fn extract_references_recursive(
                     result.references.push(Reference {
                         name: name.to_string(),
                         location: node_to_location(file, node),
+                        kind: reference_kind(node),
                     });
                 }
             }
@@ -959,6 +1020,7 @@ fn extract_references_recursive(
                     result.references.push(Reference {
                         name: name.to_string(),
                         location: node_to_location(file, node),
+                        kind: reference_kind(node),
                     });
                 }
             }
@@ -976,6 +1038,7 @@ fn extract_references_recursive(
                             result.references.push(Reference {
                                 name: name.to_string(),
                                 location: node_to_location(file, &name_node),
+                                kind: reference_kind(&name_node),
                             });
                         }
                     }
@@ -983,6 +1046,47 @@ fn extract_references_recursive(
             }
         }
 
+        // Field and property access: user.Name (method calls are handled below)
+        "member_access_expression" if !is_invocation_target(node) => {
+            if let Some(name_node) = node.child_by_field_name("name") {
+                if let Ok(name) = name_node.utf8_text(source) {
+                    result.references.push(Reference {
+                        name: name.to_string(),
+                        location: node_to_location(file, &name_node),
+                        kind: reference_kind(&name_node),
+                    });
+                }
+            }
+        }
+
+        // Members set by object initializers and `with` updates:
+        // new User { Name = ... }, user with { Name = ... }
+        "initializer_expression" | "with_initializer" => {
+            let targets: Vec<_> = if node.kind() == "with_initializer" {
+                node.named_child(0).into_iter().collect()
+            } else if node
+                .parent()
+                .is_some_and(|p| p.kind() == "object_creation_expression")
+            {
+                (0..node.named_child_count())
+                    .filter_map(|i| node.named_child(i))
+                    .filter(|child| child.kind() == "assignment_expression")
+                    .filter_map(|assignment| assignment.child_by_field_name("left"))
+                    .collect()
+            } else {
+                Vec::new()
+            };
+            for target in targets.iter().filter(|t| t.kind() == "identifier") {
+                if let Ok(name) = target.utf8_text(source) {
+                    result.references.push(Reference {
+                        name: name.to_string(),
+                        location: node_to_location(file, target),
+                        kind: ReferenceKind::Write,
+                    });
+                }
+            }
+        }
+
         // Method calls: policy.Execute(), Helper.Process(), Execute()
         "invocation_expression" => {
             // Extract the method being called
@@ -999,6 +1103,7 @@ fn extract_references_recursive(
                                 result.references.push(Reference {
                                     name: method_name.to_string(),
                                     location: node_to_location(file, &name_node),
+                                    kind: reference_kind(&name_node),
                                 });
                             }
                         }
@@ -1009,6 +1114,7 @@ fn extract_references_recursive(
                             result.references.push(Reference {
                                 name: name.to_string(),
                                 location: node_to_location(file, &function),
+                                kind: reference_kind(&function),
                             });
                         }
                     }
@@ -1020,6 +1126,7 @@ fn extract_references_recursive(
                                     result.references.push(Reference {
                                         name: name.to_string(),
                                         location: node_to_location(file, &name_node),
+                                        kind: reference_kind(&name_node),
                                     });
                                 }
                             }
@@ -1041,6 +1148,13 @@ fn extract_references_recursive(
     }
 }
 
+/// Check if a member access is the method being called by an invocation
+fn is_invocation_target(node: &tree_sitter::Node) -> bool {
+    node.parent().is_some_and(|parent| {
+        parent.kind() == "invocation_expression" && parent.child(0) == Some(*node)
+    })
+}
+
 /// Determine if an identifier or qualified_name is used as a type reference
 fn is_type_reference_context(node: &tree_sitter::Node, source: &[u8]) -> bool {
     let parent = match node.parent() {
@@ -1324,6 +1438,27 @@ namespace MyApp {
         assert_eq!(class.unwrap().qualified, "MyApp.Models.User");
     }
 
+    #[test]
+    fn test_namespace_inferred_from_project() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("Shop.csproj"), "<Project />").unwrap();
+        std::fs::create_dir(dir.path().join("Models")).unwrap();
+
+        let parser = CSharpParser;
+        let source = "public class User { }\n";
+        let result =
+            parser.extract_symbols(&dir.path().join("Models").join("User.cs"), source, 100);
+        let class = result.symbols.iter().find(|s| s.name == "User").unwrap();
+        assert_eq!(class.qualified, "Shop.Models.User");
+
+        // A declared namespace wins
+        let source = "namespace Acme { public class User { } }\n";
+        let result =
+            parser.extract_symbols(&dir.path().join("Models").join("User.cs"), source, 100);
+        let class = result.symbols.iter().find(|s| s.name == "User").unwrap();
+        assert_eq!(class.qualified, "Acme.User");
+    }
+
     #[test]
     fn test_constructor() {
         let source = r#"
@@ -1660,6 +1795,31 @@ public class Service {
         );
     }
 
+    #[test]
+    fn extracts_using_aliases() {
+        let source = r#"
+using System;
+using Json = Newtonsoft.Json.Linq;
+using static System.Math;
+
+namespace MyApp;
+"#;
+        let parser = CSharpParser;
+        let result = parser.extract_symbols(Path::new("Service.cs"), source, 100);
+
+        assert_eq!(
+            result.opens,
+            vec!["System".to_string(), "System.Math".to_string()]
+        );
+        assert_eq!(
+            result.aliases,
+            vec![ModuleAlias {
+                alias: "Json".to_string(),
+                target: "Newtonsoft.Json.Linq".to_string(),
+            }]
+        );
+    }
+
     #[test]
     fn extracts_csharp_references() {
         let source = r#"
@@ -1927,4 +2087,46 @@ public class MyClass : System.Object {
             "MyClass should have System.Object as qualified parent"
         );
     }
+
+    #[test]
+    fn extracts_property_and_initializer_references() {
+        let source = r#"
+class Service {
+    string Rename(User user) {
+        var copy = user with { Name = "b" };
+        var other = new User("a") { Name = "c" };
+        user.Save();
+        return user.Name;
+    }
+}
+"#;
+        let result = extract_symbols(Path::new("Service.cs"), source, 100);
+
+        let name_refs: Vec<_> = result
+            .references
+            .iter()
+            .filter(|r| r.name == "Name")
+            .map(|r| (r.location.line, r.kind))
+            .collect();
+        assert_eq!(
+            name_refs,
+            vec![
+                (4, ReferenceKind::Write),
+                (5, ReferenceKind::Write),
+                (7, ReferenceKind::Read)
+            ],
+            "found: {:?}",
+            result.references
+        );
+
+        // Method calls are still recorded once, by the invocation
+        assert_eq!(
+            result
+                .references
+                .iter()
+                .filter(|r| r.name == "Save")
+                .count(),
+            1
+        );
+    }
 }
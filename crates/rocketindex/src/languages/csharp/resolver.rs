@@ -10,7 +10,7 @@ use std::path::Path;
 
 use crate::parse::ParseResult;
 use crate::resolve::{ResolutionPath, ResolveResult, SymbolResolver};
-use crate::{CodeIndex, Reference, SymbolKind};
+use crate::{CodeIndex, Reference, ReferenceKind, SymbolKind};
 
 pub struct CSharpResolver;
 
@@ -163,6 +163,7 @@ impl CSharpResolver {
                 references.push(Reference {
                     name: parent.clone(),
                     location: symbol.location.clone(),
+                    kind: ReferenceKind::Inherit,
                 });
             }
 
@@ -172,6 +173,7 @@ impl CSharpResolver {
                     references.push(Reference {
                         name: iface.clone(),
                         location: symbol.location.clone(),
+                        kind: ReferenceKind::Implement,
                     });
                 }
             }
@@ -188,6 +190,7 @@ impl CSharpResolver {
                     end_line: 1,
                     end_column: 1,
                 },
+                kind: ReferenceKind::Import,
             });
         }
 
@@ -464,6 +467,37 @@ namespace MyApp
         assert_eq!(result.unwrap().symbol.qualified, "MyApp.User.Save");
     }
 
+    #[test]
+    fn resolve_through_using_alias() {
+        let mut index = CodeIndex::new();
+        let file = PathBuf::from("src/App.cs");
+
+        index.add_symbol(Symbol::new(
+            "Save".to_string(),
+            "MyApp.Models.User.Save".to_string(),
+            SymbolKind::Function,
+            Location::new(PathBuf::from("src/User.cs"), 5, 5),
+            Visibility::Public,
+            "csharp".to_string(),
+        ));
+
+        // using Models = MyApp.Models;
+        index.add_alias(
+            file.clone(),
+            crate::ModuleAlias {
+                alias: "Models".to_string(),
+                target: "MyApp.Models".to_string(),
+            },
+        );
+
+        let result = index.resolve_dotted("Models.User.Save", &file).unwrap();
+        assert_eq!(result.symbol.qualified, "MyApp.Models.User.Save");
+        // Other files don't see the alias
+        assert!(index
+            .resolve_dotted("Models.User.Save", Path::new("src/Other.cs"))
+            .is_none());
+    }
+
     #[test]
     fn resolve_struct_in_same_namespace() {
         let mut index = CodeIndex::new();
@@ -3,8 +3,8 @@
 use std::cell::RefCell;
 use std::path::Path;
 
-use crate::parse::{LanguageParser, ParseResult, ParseWarning, SyntaxError};
-use crate::{Location, Reference, Symbol, SymbolKind, Visibility};
+use crate::parse::{reference_kind, LanguageParser, ParseResult, ParseWarning, SyntaxError};
+use crate::{Location, ModuleAlias, Reference, ReferenceKind, Symbol, SymbolKind, Visibility};
 
 // Thread-local parser reuse - avoids creating a new parser per file
 thread_local! {
@@ -38,15 +38,42 @@ impl LanguageParser for FSharpParser {
             // Extract syntax errors from the tree
             extract_syntax_errors(&root, source.as_bytes(), file, &mut result.errors);
 
+            // Without a namespace or module declaration the whole file is an
+            // implicit module named after it
+            let declares_module = (0..root.child_count())
+                .filter_map(|i| root.child(i))
+                .any(|child| matches!(child.kind(), "namespace" | "named_module"));
+            let implicit_module = if declares_module {
+                None
+            } else {
+                crate::namespace::implicit_fsharp_module(file)
+            };
+            result.module_path = implicit_module.clone();
+
             extract_recursive(
                 &root,
                 source.as_bytes(),
                 file,
                 &mut result,
-                None, // No parent module yet
+                implicit_module.as_deref(),
                 max_depth,
             );
 
+            crate::parse::capture_lambdas(
+                &root,
+                source.as_bytes(),
+                file,
+                &["fun_expression", "function_expression"],
+                "fsharp",
+                &mut result,
+            );
+            crate::conditional::tag_symbols(
+                &mut result,
+                &crate::conditional::preprocessor_regions(source),
+            );
+
+            result.metrics = crate::metrics::measure(&root, &result.symbols);
+
             result
         })
     }
@@ -118,7 +145,7 @@ impl<'a> ExtractionContext<'a> {
             qualified: type_qualified.clone(),
             kind,
             location: node_to_location(self.file, name_node),
-            visibility: Visibility::Public,
+            visibility: extract_visibility(node, self.source),
             language: "fsharp".to_string(),
             parent: None,
             mixins: None,
@@ -246,13 +273,20 @@ impl<'a> ExtractionContext<'a> {
                 // Member name can be in different structures:
                 // - method_or_prop_defn -> property_or_ident -> identifier (for instance members like this.Add)
                 // - method_or_prop_defn -> property_or_ident -> identifier (for static members like Multiply)
+                // - property_or_ident -> identifier (for auto-properties like `member val Name`)
                 if let Some((name, location)) = self.extract_member_name_and_location(&child) {
                     // Skip 'this' and '_' self-identifiers
                     if name != "this" && name != "_" {
+                        // Auto-properties hold a value; everything else is a method
+                        let kind = if find_child_by_kind(&child, "val").is_some() {
+                            SymbolKind::Member
+                        } else {
+                            SymbolKind::Function
+                        };
                         let symbol = Symbol {
                             name: name.clone(),
                             qualified: qualified_name(&name, current_module),
-                            kind: SymbolKind::Function, // Methods are functions
+                            kind,
                             location,
                             visibility: extract_visibility(&child, self.source),
                             language: "fsharp".to_string(),
@@ -310,6 +344,17 @@ impl<'a> ExtractionContext<'a> {
                 }
             }
         }
+        // Auto-properties (`member val Name = ...`) have no method_or_prop_defn
+        if let Some(prop_or_ident) = find_child_by_kind(member_defn, "property_or_ident") {
+            if let Some(id_child) = find_child_by_kind(&prop_or_ident, "identifier") {
+                if let Ok(name) = id_child.utf8_text(self.source) {
+                    return Some((
+                        name.trim().to_string(),
+                        node_to_location(self.file, &id_child),
+                    ));
+                }
+            }
+        }
         // Fallback to direct identifier child
         for i in 0..member_defn.child_count() {
             if let Some(child) = member_defn.child(i) {
@@ -510,6 +555,23 @@ fn extract_recursive_with_depth(
                 .child_by_field_name("name")
                 .or_else(|| find_child_by_kind(node, "long_identifier"))
                 .or_else(|| find_child_by_kind(node, "identifier"));
+
+            // `module Db = MyApp.Data.Database` names another module
+            // rather than defining one
+            let aliased = node
+                .child_by_field_name("block")
+                .filter(|block| matches!(block.kind(), "dot_expression" | "long_identifier_or_op"))
+                .and_then(|block| block.utf8_text(source).ok());
+            if let (Some(name_node), Some(target)) = (module_name_node, aliased) {
+                if let Ok(alias) = name_node.utf8_text(source) {
+                    result.aliases.push(ModuleAlias {
+                        alias: alias.trim().to_string(),
+                        target: target.split_whitespace().collect(),
+                    });
+                    return;
+                }
+            }
+
             if let Some(name_node) = module_name_node {
                 if let Ok(name) = name_node.utf8_text(source) {
                     let trimmed = name.trim();
@@ -562,6 +624,16 @@ fn extract_recursive_with_depth(
             let name_node = node
                 .child_by_field_name("name")
                 .or_else(|| find_child_by_kind(node, "long_identifier"));
+            // The grammar reads `open type System.Math` as an open of `type`
+            // followed by the type's name
+            let name_node = name_node.and_then(|name_node| {
+                if name_node.utf8_text(source).ok()?.trim() == "type" {
+                    node.next_sibling()
+                        .filter(|next| next.kind() == "long_identifier_or_op")
+                } else {
+                    Some(name_node)
+                }
+            });
             if let Some(name_node) = name_node {
                 if let Ok(name) = name_node.utf8_text(source) {
                     result.opens.push(name.trim().to_string());
@@ -573,6 +645,10 @@ fn extract_recursive_with_depth(
             handle_function_or_value_defn(node, source, file, result, current_module);
         }
 
+        "ce_expression" => {
+            extract_builder_calls(node, source, file, result);
+        }
+
         "type_definition" => {
             // Doc comments are siblings of type_definition
             let doc = extract_doc_comment(node, source);
@@ -589,6 +665,7 @@ fn extract_recursive_with_depth(
                     result.references.push(Reference {
                         name: name.to_string(),
                         location: node_to_location(file, node),
+                        kind: reference_kind(node),
                     });
                 }
             }
@@ -942,12 +1019,13 @@ fn handle_function_or_value_defn(
                 let trimmed = pattern_text.trim();
                 let qualified = qualified_name(trimmed, current_module);
                 let attrs = extract_attributes(node, source);
+                let visibility = extract_visibility(node, source);
                 let symbol = Symbol {
                     name: trimmed.to_string(),
-                    qualified,
+                    qualified: qualified.clone(),
                     kind: SymbolKind::Function,
                     location: node_to_location(file, &active_pattern),
-                    visibility: extract_visibility(node, source),
+                    visibility,
                     language: "fsharp".to_string(),
                     parent: None,
                     mixins: None,
@@ -957,6 +1035,34 @@ fn handle_function_or_value_defn(
                     signature: signature.clone(),
                 };
                 result.symbols.push(symbol);
+
+                // Each case is matched by name (`| Even -> ...`), so index the
+                // cases too, owned by the pattern like union cases by their type
+                for i in 0..active_pattern.child_count() {
+                    let Some(case_node) = active_pattern.child(i) else {
+                        continue;
+                    };
+                    if case_node.kind() != "active_pattern_op_name" {
+                        continue;
+                    }
+                    if let Ok(case_name) = case_node.utf8_text(source) {
+                        let case_name = case_name.trim();
+                        result.symbols.push(Symbol {
+                            name: case_name.to_string(),
+                            qualified: qualified_name(case_name, current_module),
+                            kind: SymbolKind::Function,
+                            location: node_to_location(file, &case_node),
+                            visibility,
+                            language: "fsharp".to_string(),
+                            parent: Some(qualified.clone()),
+                            mixins: None,
+                            attributes: None,
+                            implements: None,
+                            doc: None,
+                            signature: None,
+                        });
+                    }
+                }
                 handled = true;
             }
         }
@@ -1017,7 +1123,7 @@ fn handle_function_or_value_defn(
                         attributes: if attrs.is_empty() { None } else { Some(attrs) },
                         implements: None,
                         doc,
-                        signature,
+                        signature: signature.or_else(|| extract_constructed_type(node, source)),
                     };
                     result.symbols.push(symbol);
                 }
@@ -1026,6 +1132,103 @@ fn handle_function_or_value_defn(
     }
 }
 
+/// The type of an unannotated value that is built with a constructor call:
+/// `let maybe = MaybeBuilder()` or `let p = new Person()`.
+///
+/// This is what links a computation expression's builder value to the
+/// builder type whose members its keywords call.
+fn extract_constructed_type(node: &tree_sitter::Node, source: &[u8]) -> Option<String> {
+    let body = node.child_by_field_name("body")?;
+    let (call, is_new) = if body.kind() == "prefixed_expression" {
+        find_child_by_kind(&body, "new")?;
+        (find_child_by_kind(&body, "application_expression")?, true)
+    } else {
+        (body, false)
+    };
+    if call.kind() != "application_expression" {
+        return None;
+    }
+    let constructor = call.child(0)?;
+    // Without `new`, only `Type()` reads as a constructor call; `Some 1` is
+    // just as much an application
+    if constructor.kind() != "long_identifier_or_op" || (!is_new && call.child(1)?.kind() != "unit")
+    {
+        return None;
+    }
+    let type_name = constructor.utf8_text(source).ok()?.trim();
+    let starts_upper = type_name
+        .rsplit('.')
+        .next()
+        .and_then(|last| last.chars().next())
+        .is_some_and(char::is_uppercase);
+    starts_upper.then(|| type_name.to_string())
+}
+
+/// Builders that ship with FSharp.Core; their members are never in the index.
+const CORE_BUILDERS: &[&str] = &["async", "backgroundTask", "query", "seq", "task"];
+
+/// The builder method each computation expression keyword desugars to.
+fn builder_method(keyword: &str) -> Option<&'static str> {
+    match keyword {
+        "let!" | "use!" | "do!" | "match!" => Some("Bind"),
+        "return" => Some("Return"),
+        "return!" => Some("ReturnFrom"),
+        "yield" => Some("Yield"),
+        "yield!" => Some("YieldFrom"),
+        _ => None,
+    }
+}
+
+/// Record the builder members a computation expression calls:
+/// `maybe { let! x = ... }` calls `maybe.Bind`, so spidering into the
+/// block reaches the builder type.
+fn extract_builder_calls(
+    node: &tree_sitter::Node,
+    source: &[u8],
+    file: &Path,
+    result: &mut ParseResult,
+) {
+    let Some(builder) = find_child_by_kind(node, "long_identifier_or_op")
+        .and_then(|builder| builder.utf8_text(source).ok())
+        .map(str::trim)
+    else {
+        return;
+    };
+    if CORE_BUILDERS.contains(&builder) {
+        return;
+    }
+    collect_builder_calls(node, builder, file, result, 0);
+}
+
+fn collect_builder_calls(
+    node: &tree_sitter::Node,
+    builder: &str,
+    file: &Path,
+    result: &mut ParseResult,
+    depth: usize,
+) {
+    if depth > MAX_HELPER_DEPTH {
+        return;
+    }
+    for i in 0..node.child_count() {
+        let Some(child) = node.child(i) else {
+            continue;
+        };
+        // Nested blocks call their own builder
+        if child.kind() == "ce_expression" {
+            continue;
+        }
+        if let Some(method) = builder_method(child.kind()) {
+            result.references.push(Reference {
+                name: format!("{}.{}", builder, method),
+                location: node_to_location(file, &child),
+                kind: ReferenceKind::Call,
+            });
+        }
+        collect_builder_calls(&child, builder, file, result, depth + 1);
+    }
+}
+
 /// Recursively find the first identifier node within a subtree.
 /// Useful for extracting the name from nested patterns.
 fn find_first_identifier<'a>(node: &tree_sitter::Node<'a>) -> Option<tree_sitter::Node<'a>> {
@@ -1100,6 +1303,18 @@ fn is_reference_context_with_depth(node: &tree_sitter::Node, depth: usize) -> bo
             // Application expressions are references
             "application_expression" | "infix_expression" | "prefix_expression" => true,
 
+            // Record construction and copy-and-update: `{ Name = ... }`, `{ p with Name = ... }`
+            "field_initializer" => true,
+
+            // The body of a let binding (e.g. `let n = p.Name`) is a reference
+            "function_or_value_defn"
+                if parent
+                    .child_by_field_name("body")
+                    .is_some_and(|body| body.id() == node.id()) =>
+            {
+                true
+            }
+
             // Check parent's parent for more context
             _ => is_reference_context_with_depth(&parent, depth + 1),
         }
@@ -1136,8 +1351,11 @@ fn extract_visibility(node: &tree_sitter::Node, source: &[u8]) -> Visibility {
         }
     }
 
-    // Similarly for value_declaration_left
-    if let Some(decl) = find_child_by_kind(node, "value_declaration_left") {
+    // Similarly for value_declaration_left and a type's name (`type internal T`)
+    for left in ["value_declaration_left", "type_name"] {
+        let Some(decl) = find_child_by_kind(node, left) else {
+            continue;
+        };
         if let Some(modifier) = find_child_by_kind(&decl, "access_modifier") {
             if let Ok(text) = modifier.utf8_text(source) {
                 return match text.trim() {
@@ -1265,6 +1483,18 @@ let process () = ()
         assert_eq!(module.unwrap().qualified, "MyApp.Services.Payment");
     }
 
+    #[test]
+    fn files_without_module_get_implicit_module() {
+        let source = r#"
+let run () = ()
+"#;
+        let result = extract_symbols(Path::new("scripts/build.fsx"), source, 500);
+
+        let run = result.symbols.iter().find(|s| s.name == "run").unwrap();
+        assert_eq!(run.qualified, "Build.run");
+        assert_eq!(result.module_path.as_deref(), Some("Build"));
+    }
+
     #[test]
     fn extracts_opens() {
         let source = r#"
@@ -1283,6 +1513,37 @@ let x = 1
             .contains(&"System.Collections.Generic".to_string()));
     }
 
+    #[test]
+    fn extracts_module_aliases_and_open_type() {
+        let source = r#"
+module MyApp.App
+
+open type System.Math
+module Db = MyApp.Data.Database
+module Inner =
+    let x = 1
+
+let conn = Db.connect ()
+"#;
+        let result = extract_symbols(Path::new("App.fs"), source, 500);
+
+        assert_eq!(result.opens, vec!["System.Math".to_string()]);
+        assert_eq!(
+            result.aliases,
+            vec![ModuleAlias {
+                alias: "Db".to_string(),
+                target: "MyApp.Data.Database".to_string(),
+            }]
+        );
+        // An alias isn't a module of its own, unlike `Inner`
+        assert!(!result.symbols.iter().any(|s| s.name == "Db"));
+        assert!(result
+            .symbols
+            .iter()
+            .any(|s| s.qualified == "MyApp.App.Inner" && s.kind == SymbolKind::Module));
+        assert!(result.references.iter().any(|r| r.name == "Db.connect"));
+    }
+
     #[test]
     fn extracts_visibility() {
         let source = r#"
@@ -1292,9 +1553,16 @@ let private helper x = x + 1
 let internal process x = x * 2
 let public main () = ()
 let defaultFn () = ()
+type internal Ledger = { Total: int }
+type private Cache() = class end
 "#;
         let result = extract_symbols(Path::new("test.fs"), source, 500);
 
+        let ledger = result.symbols.iter().find(|s| s.name == "Ledger").unwrap();
+        assert_eq!(ledger.visibility, Visibility::Internal);
+        let cache = result.symbols.iter().find(|s| s.name == "Cache").unwrap();
+        assert_eq!(cache.visibility, Visibility::Private);
+
         let helper = result.symbols.iter().find(|s| s.name == "helper");
         assert!(helper.is_some());
         assert_eq!(helper.unwrap().visibility, Visibility::Private);
@@ -1659,6 +1927,36 @@ type Person = {
         );
     }
 
+    #[test]
+    fn extracts_record_field_references() {
+        let source = r#"
+module MyApp
+
+type Person = { Name: string; Age: int }
+
+let alice = { Name = "Alice"; Age = 30 }
+let older = { alice with Age = 31 }
+let name = alice.Name
+"#;
+        let result = extract_symbols(Path::new("person.fs"), source, 500);
+
+        let refs_at = |name: &str| -> Vec<u32> {
+            result
+                .references
+                .iter()
+                .filter(|r| r.name == name)
+                .map(|r| r.location.line)
+                .collect()
+        };
+
+        // Record construction and copy-and-update initializers
+        assert_eq!(refs_at("Name"), vec![6], "found: {:?}", result.references);
+        assert_eq!(refs_at("Age"), vec![6, 7], "found: {:?}", result.references);
+
+        // Field access through a value
+        assert!(refs_at("alice.Name").contains(&8));
+    }
+
     #[test]
     fn extracts_type_members() {
         // QUIRK: Type members (instance and static) are not indexed
@@ -1701,7 +1999,6 @@ type Calculator() =
 
     #[test]
     fn extracts_active_patterns() {
-        // QUIRK: Active patterns are not indexed
         let source = r#"
 module MyApp
 
@@ -1728,6 +2025,83 @@ let (|Integer|_|) (str: string) =
             integer.is_some(),
             "Partial active pattern '(|Integer|_|)' should be indexed"
         );
+
+        // Each case is indexed under the pattern, but not the partial `_`
+        let even = result.symbols.iter().find(|s| s.name == "Even").unwrap();
+        assert_eq!(even.qualified, "MyApp.Even");
+        assert_eq!(even.parent.as_deref(), Some("MyApp.(|Even|Odd|)"));
+        assert!(result.symbols.iter().any(|s| s.qualified == "MyApp.Odd"));
+        assert!(result
+            .symbols
+            .iter()
+            .any(|s| s.qualified == "MyApp.Integer"));
+        assert!(!result.symbols.iter().any(|s| s.name == "_"));
+    }
+
+    #[test]
+    fn extracts_auto_properties() {
+        let source = r#"
+module MyApp
+
+type Person() =
+    member val Name = "" with get, set
+    member val Age = 0
+    member this.Greet() = "Hi " + this.Name
+"#;
+        let result = extract_symbols(Path::new("person.fs"), source, 500);
+
+        let name = result.symbols.iter().find(|s| s.name == "Name").unwrap();
+        assert_eq!(name.qualified, "MyApp.Person.Name");
+        assert_eq!(name.kind, SymbolKind::Member);
+        assert_eq!(name.location.line, 5);
+        assert!(result
+            .symbols
+            .iter()
+            .any(|s| s.qualified == "MyApp.Person.Age" && s.kind == SymbolKind::Member));
+        let greet = result.symbols.iter().find(|s| s.name == "Greet").unwrap();
+        assert_eq!(greet.kind, SymbolKind::Function);
+    }
+
+    #[test]
+    fn extracts_computation_expression_builder_calls() {
+        let source = r#"
+module MyApp
+
+type MaybeBuilder() =
+    member _.Bind(x, f) = Option.bind f x
+    member _.Return(x) = Some x
+
+let maybe = MaybeBuilder()
+
+let run () =
+    maybe {
+        let! x = Some 1
+        return x
+    }
+
+let fetch () =
+    async {
+        let! x = load ()
+        return x
+    }
+"#;
+        let result = extract_symbols(Path::new("maybe.fs"), source, 500);
+
+        // The builder value carries the type it was constructed as
+        let maybe = result.symbols.iter().find(|s| s.name == "maybe").unwrap();
+        assert_eq!(maybe.signature.as_deref(), Some("MaybeBuilder"));
+
+        let calls: Vec<(&str, u32)> = result
+            .references
+            .iter()
+            .filter(|r| r.name.contains(".Bind") || r.name.contains(".Return"))
+            .map(|r| (r.name.as_str(), r.location.line))
+            .collect();
+        assert_eq!(calls, vec![("maybe.Bind", 12), ("maybe.Return", 13)]);
+        assert!(result
+            .references
+            .iter()
+            .all(|r| !r.name.starts_with("maybe.") || r.kind == ReferenceKind::Call));
     }
 
     #[test]
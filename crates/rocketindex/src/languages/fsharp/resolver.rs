@@ -3,7 +3,7 @@
 use std::path::Path;
 
 use crate::resolve::{ResolutionPath, ResolveResult, SymbolResolver};
-use crate::{CodeIndex, Symbol};
+use crate::{CodeIndex, Symbol, SymbolKind};
 
 pub struct FSharpResolver;
 
@@ -84,10 +84,48 @@ impl SymbolResolver for FSharpResolver {
             }
         }
 
+        // Members reached through a value of a known type, e.g. the
+        // `maybe.Bind` a computation expression calls on its builder
+        if let Some((receiver, member)) = name.rsplit_once('.') {
+            if let Some(result) = resolve_value_member(self, index, receiver, member, from_file) {
+                return Some(result);
+            }
+        }
+
         None
     }
 }
 
+/// Resolve `member` on the type of the value `receiver`, as recorded in the
+/// value's signature (an annotation or the constructor it was built with).
+fn resolve_value_member<'a>(
+    resolver: &FSharpResolver,
+    index: &'a CodeIndex,
+    receiver: &str,
+    member: &str,
+    from_file: &Path,
+) -> Option<ResolveResult<'a>> {
+    let value = resolver.resolve(index, receiver, from_file)?.symbol;
+    if value.kind != SymbolKind::Value {
+        return None;
+    }
+    let value_type = value.signature.as_deref()?;
+    let type_symbol = resolver
+        .resolve(index, value_type, &value.location.file)?
+        .symbol;
+    let symbol = get_visible_from(
+        index,
+        &format!("{}.{}", type_symbol.qualified, member),
+        from_file,
+    )?;
+    Some(ResolveResult {
+        symbol,
+        resolution_path: ResolutionPath::ViaMemberAccess {
+            type_name: type_symbol.qualified.clone(),
+        },
+    })
+}
+
 /// Get a symbol by qualified name, but only if it's visible from the given file.
 fn get_visible_from<'a>(index: &'a CodeIndex, name: &str, from_file: &Path) -> Option<&'a Symbol> {
     let symbol = index.get(name)?;
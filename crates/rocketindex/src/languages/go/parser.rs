@@ -3,7 +3,9 @@
 use std::cell::RefCell;
 use std::path::Path;
 
-use crate::parse::{find_child_by_kind, node_to_location, LanguageParser, ParseResult};
+use crate::parse::{
+    find_child_by_kind, node_to_location, reference_kind, LanguageParser, ParseResult,
+};
 use crate::{Reference, Symbol, SymbolKind, Visibility};
 
 // Thread-local parser reuse - avoids creating a new parser per file
@@ -50,6 +52,17 @@ impl LanguageParser for GoParser {
             // Set module path from package
             result.module_path = package_name;
 
+            crate::parse::capture_lambdas(
+                &root,
+                source.as_bytes(),
+                file,
+                &["func_literal"],
+                "go",
+                &mut result,
+            );
+
+            result.metrics = crate::metrics::measure(&root, &result.symbols);
+
             result
         })
     }
@@ -367,6 +380,7 @@ fn extract_recursive(
                     result.references.push(Reference {
                         name: name.to_string(),
                         location: node_to_location(file, node),
+                        kind: reference_kind(node),
                     });
                 }
             }
@@ -379,6 +393,7 @@ fn extract_recursive(
                     result.references.push(Reference {
                         name: name.to_string(),
                         location: node_to_location(file, node),
+                        kind: reference_kind(node),
                     });
                 }
             }
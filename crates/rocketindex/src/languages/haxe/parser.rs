@@ -3,7 +3,9 @@
 use std::cell::RefCell;
 use std::path::Path;
 
-use crate::parse::{find_child_by_kind, node_to_location, LanguageParser, ParseResult};
+use crate::parse::{
+    find_child_by_kind, node_to_location, reference_kind, LanguageParser, ParseResult,
+};
 use crate::{Reference, Symbol, SymbolKind, Visibility};
 
 // Thread-local parser reuse - avoids creating a new parser per file
@@ -47,6 +49,8 @@ impl LanguageParser for HaxeParser {
                 max_depth,
             );
 
+            result.metrics = crate::metrics::measure(&root, &result.symbols);
+
             result
         })
     }
@@ -479,6 +483,7 @@ fn extract_recursive(
                     result.references.push(Reference {
                         name: name.to_string(),
                         location: node_to_location(file, node),
+                        kind: reference_kind(node),
                     });
                 }
             }
@@ -491,6 +496,21 @@ fn extract_recursive(
                     result.references.push(Reference {
                         name: name.to_string(),
                         location: node_to_location(file, node),
+                        kind: reference_kind(node),
+                    });
+                }
+            }
+        }
+
+        // Extract field references (e.g., "player.score"); member calls are
+        // recorded by call_expression below
+        "member_expression" if !is_call_object(node) => {
+            if let Some(member) = node.child_by_field_name("member") {
+                if let Ok(name) = member.utf8_text(source) {
+                    result.references.push(Reference {
+                        name: name.to_string(),
+                        location: node_to_location(file, &member),
+                        kind: reference_kind(&member),
                     });
                 }
             }
@@ -504,6 +524,7 @@ fn extract_recursive(
                     result.references.push(Reference {
                         name: obj_text.to_string(),
                         location: node_to_location(file, &object),
+                        kind: reference_kind(&object),
                     });
                 }
             }
@@ -512,6 +533,7 @@ fn extract_recursive(
                     result.references.push(Reference {
                         name: ctor_text.to_string(),
                         location: node_to_location(file, &constructor),
+                        kind: reference_kind(&constructor),
                     });
                 }
             }
@@ -532,6 +554,13 @@ fn extract_recursive(
     }
 }
 
+/// Check if a node is the callee of its parent call_expression
+fn is_call_object(node: &tree_sitter::Node) -> bool {
+    node.parent().is_some_and(|parent| {
+        parent.kind() == "call_expression" && parent.child_by_field_name("object") == Some(*node)
+    })
+}
+
 /// Determine if a node is used as a reference (not a definition)
 fn is_reference_context(node: &tree_sitter::Node) -> bool {
     let parent = match node.parent() {
@@ -864,4 +893,23 @@ class Main {
             .expect("Should find Main class");
         assert_eq!(class_sym.qualified, "Main");
     }
+
+    #[test]
+    fn extracts_haxe_field_references() {
+        let source = r#"
+class Game {
+    function tick(player:Player) {
+        player.move();
+        return player.score;
+    }
+}
+"#;
+        let parser = HaxeParser;
+        let result = parser.extract_symbols(std::path::Path::new("Game.hx"), source, 100);
+
+        let ref_names: Vec<_> = result.references.iter().map(|r| r.name.as_str()).collect();
+        assert!(ref_names.contains(&"score"), "found: {:?}", ref_names);
+        assert!(ref_names.contains(&"player.move"), "found: {:?}", ref_names);
+        assert!(!ref_names.contains(&"move"), "found: {:?}", ref_names);
+    }
 }
@@ -3,7 +3,9 @@
 use std::cell::RefCell;
 use std::path::Path;
 
-use crate::parse::{find_child_by_kind, node_to_location, LanguageParser, ParseResult};
+use crate::parse::{
+    find_child_by_kind, node_to_location, reference_kind, LanguageParser, ParseResult,
+};
 use crate::{Reference, Symbol, SymbolKind, Visibility};
 
 // Thread-local parser reuse - avoids creating a new parser per file
@@ -47,6 +49,17 @@ impl LanguageParser for JavaParser {
                 max_depth,
             );
 
+            crate::parse::capture_lambdas(
+                &root,
+                source.as_bytes(),
+                file,
+                &["lambda_expression"],
+                "java",
+                &mut result,
+            );
+
+            result.metrics = crate::metrics::measure(&root, &result.symbols);
+
             result
         })
     }
@@ -225,6 +238,20 @@ fn extract_recursive(
                         signature: None,
                     });
 
+                    // The superclass and interfaces are references too
+                    for field in ["superclass", "interfaces"] {
+                        if let Some(heritage) = node.child_by_field_name(field) {
+                            extract_recursive(
+                                &heritage,
+                                source,
+                                file,
+                                result,
+                                package,
+                                max_depth - 1,
+                            );
+                        }
+                    }
+
                     // Recurse into class body
                     if let Some(body) = node.child_by_field_name("body") {
                         for i in 0..body.child_count() {
@@ -590,6 +617,7 @@ fn extract_recursive(
                     result.references.push(Reference {
                         name: name.to_string(),
                         location: node_to_location(file, node),
+                        kind: reference_kind(node),
                     });
                 }
             }
@@ -602,6 +630,7 @@ fn extract_recursive(
                     result.references.push(Reference {
                         name: name.to_string(),
                         location: node_to_location(file, node),
+                        kind: reference_kind(node),
                     });
                 }
             }
@@ -614,6 +643,28 @@ fn extract_recursive(
                     result.references.push(Reference {
                         name: name.to_string(),
                         location: node_to_location(file, node),
+                        kind: reference_kind(node),
+                    });
+                }
+            }
+        }
+
+        // Extract field references from field_access nodes (e.g., "user.name", "this.count")
+        "field_access" => {
+            if let Some(field_node) = node.child_by_field_name("field") {
+                if let Ok(field_name) = field_node.utf8_text(source) {
+                    let reference_name = match node
+                        .child_by_field_name("object")
+                        .and_then(|object| object.utf8_text(source).ok())
+                    {
+                        Some(object_text) => format!("{}.{}", object_text, field_name),
+                        None => field_name.to_string(),
+                    };
+
+                    result.references.push(Reference {
+                        name: reference_name,
+                        location: node_to_location(file, &field_node),
+                        kind: reference_kind(&field_node),
                     });
                 }
             }
@@ -639,6 +690,7 @@ fn extract_recursive(
                     result.references.push(Reference {
                         name: reference_name,
                         location: node_to_location(file, &name_node),
+                        kind: reference_kind(&name_node),
                     });
                 }
             }
@@ -705,6 +757,9 @@ fn is_reference_context(node: &tree_sitter::Node) -> bool {
             false
         }
 
+        // Superclass and implemented/extended interfaces
+        "superclass" | "type_list" => true,
+
         // Method/constructor parameter - the type is a reference
         "formal_parameter" | "spread_parameter" | "receiver_parameter" => true,
 
@@ -1385,4 +1440,32 @@ class Helper {
             ref_names
         );
     }
+
+    #[test]
+    fn extracts_java_field_access_references() {
+        let source = r#"
+public class Counter {
+    private int count;
+
+    void bump(Counter other) {
+        this.count = other.count + 1;
+    }
+}
+"#;
+        let result = extract_symbols(Path::new("Counter.java"), source, 100);
+
+        let refs: Vec<_> = result
+            .references
+            .iter()
+            .filter(|r| r.name.ends_with(".count"))
+            .map(|r| (r.name.as_str(), r.kind))
+            .collect();
+        assert_eq!(
+            refs,
+            vec![
+                ("this.count", crate::ReferenceKind::Write),
+                ("other.count", crate::ReferenceKind::Read)
+            ]
+        );
+    }
 }
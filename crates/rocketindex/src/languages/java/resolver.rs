@@ -10,7 +10,7 @@ use std::path::Path;
 
 use crate::parse::ParseResult;
 use crate::resolve::{ResolutionPath, ResolveResult, SymbolResolver};
-use crate::{CodeIndex, Reference, SymbolKind};
+use crate::{CodeIndex, Reference, ReferenceKind, SymbolKind};
 
 pub struct JavaResolver;
 
@@ -157,6 +157,7 @@ impl JavaResolver {
                 references.push(Reference {
                     name: parent.clone(),
                     location: symbol.location.clone(),
+                    kind: ReferenceKind::Inherit,
                 });
             }
 
@@ -166,6 +167,7 @@ impl JavaResolver {
                     references.push(Reference {
                         name: iface.clone(),
                         location: symbol.location.clone(),
+                        kind: ReferenceKind::Implement,
                     });
                 }
             }
@@ -182,6 +184,7 @@ impl JavaResolver {
                     end_line: 1,
                     end_column: 1,
                 },
+                kind: ReferenceKind::Import,
             });
         }
 
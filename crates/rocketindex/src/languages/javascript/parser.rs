@@ -3,7 +3,9 @@
 use std::cell::RefCell;
 use std::path::Path;
 
-use crate::parse::{find_child_by_kind, node_to_location, LanguageParser, ParseResult};
+use crate::parse::{
+    find_child_by_kind, node_to_location, reference_kind, LanguageParser, ParseResult,
+};
 use crate::{Reference, Symbol, SymbolKind, Visibility};
 
 // Thread-local parser reuse - avoids creating a new parser per file
@@ -37,6 +39,17 @@ impl LanguageParser for JavaScriptParser {
 
             extract_recursive(&root, source.as_bytes(), file, &mut result, None, max_depth);
 
+            crate::parse::capture_lambdas(
+                &root,
+                source.as_bytes(),
+                file,
+                &["arrow_function", "function_expression"],
+                "javascript",
+                &mut result,
+            );
+
+            result.metrics = crate::metrics::measure(&root, &result.symbols);
+
             result
         })
     }
@@ -179,6 +192,18 @@ fn extract_recursive(
                         signature: None,
                     });
 
+                    // The extends clause is a reference too
+                    if let Some(heritage) = find_child_by_kind(node, "class_heritage") {
+                        extract_recursive(
+                            &heritage,
+                            source,
+                            file,
+                            result,
+                            parent_path,
+                            max_depth - 1,
+                        );
+                    }
+
                     // Recurse into class body
                     if let Some(body) = node.child_by_field_name("body") {
                         extract_class_body(&body, source, file, result, &qualified, max_depth - 1);
@@ -247,6 +272,7 @@ fn extract_recursive(
                     result.references.push(Reference {
                         name: name.to_string(),
                         location: node_to_location(file, node),
+                        kind: reference_kind(node),
                     });
                 }
             }
@@ -259,6 +285,7 @@ fn extract_recursive(
                     result.references.push(Reference {
                         name: name.to_string(),
                         location: node_to_location(file, node),
+                        kind: reference_kind(node),
                     });
                 }
             }
@@ -736,6 +763,11 @@ fn is_reference_context_with_depth(node: &tree_sitter::Node, depth: usize) -> bo
             return true;
         }
 
+        // Base class in an extends clause
+        "class_heritage" => {
+            return true;
+        }
+
         // Return/throw statements
         "return_statement" | "throw_statement" => {
             return true;
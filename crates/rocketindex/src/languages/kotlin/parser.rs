@@ -3,7 +3,9 @@
 use std::cell::RefCell;
 use std::path::Path;
 
-use crate::parse::{find_child_by_kind, node_to_location, LanguageParser, ParseResult};
+use crate::parse::{
+    find_child_by_kind, node_to_location, reference_kind, LanguageParser, ParseResult,
+};
 use crate::{Reference, Symbol, SymbolKind, Visibility};
 
 // Thread-local parser reuse - avoids creating a new parser per file
@@ -47,6 +49,17 @@ impl LanguageParser for KotlinParser {
                 max_depth,
             );
 
+            crate::parse::capture_lambdas(
+                &root,
+                source.as_bytes(),
+                file,
+                &["lambda_literal", "anonymous_function"],
+                "kotlin",
+                &mut result,
+            );
+
+            result.metrics = crate::metrics::measure(&root, &result.symbols);
+
             result
         })
     }
@@ -252,29 +265,6 @@ fn is_enum_class(node: &tree_sitter::Node, source: &[u8]) -> bool {
     false
 }
 
-/// Check if a class has the "data" modifier
-fn is_data_class(node: &tree_sitter::Node, source: &[u8]) -> bool {
-    if let Some(modifiers) = find_child_by_kind(node, "modifiers") {
-        let mut cursor = modifiers.walk();
-        if cursor.goto_first_child() {
-            loop {
-                let child = cursor.node();
-                if child.kind() == "class_modifier" {
-                    if let Ok(text) = child.utf8_text(source) {
-                        if text == "data" {
-                            return true;
-                        }
-                    }
-                }
-                if !cursor.goto_next_sibling() {
-                    break;
-                }
-            }
-        }
-    }
-    false
-}
-
 /// Extract parent class from class_declaration's delegation_specifiers
 /// In Kotlin, `class Dog : Animal()` - the first constructor_invocation is the parent class
 fn extract_parent_class(node: &tree_sitter::Node, source: &[u8]) -> Option<String> {
@@ -355,10 +345,8 @@ fn extract_recursive(
                         signature: None,
                     });
 
-                    // Extract data class constructor params
-                    if is_data_class(node, source) {
-                        extract_primary_constructor_params(node, source, file, result, &qualified);
-                    }
+                    // Extract properties declared in the primary constructor
+                    extract_primary_constructor_params(node, source, file, result, &qualified);
 
                     // Extract enum entries for enum classes
                     if is_enum_class(node, source) {
@@ -580,6 +568,7 @@ fn extract_recursive(
                         result.references.push(Reference {
                             name: name.to_string(),
                             location: node_to_location(file, &id),
+                            kind: reference_kind(&id),
                         });
                     }
                 }
@@ -605,6 +594,7 @@ fn extract_recursive(
                                 result.references.push(Reference {
                                     name: name.to_string(),
                                     location: node_to_location(file, &child),
+                                    kind: reference_kind(&child),
                                 });
                             }
                             break; // Found the callee
@@ -616,6 +606,7 @@ fn extract_recursive(
                                 result.references.push(Reference {
                                     name: full_name.to_string(),
                                     location: node_to_location(file, &child),
+                                    kind: reference_kind(&child),
                                 });
                             }
                             // Also extract just the method name (last part after the dot)
@@ -623,6 +614,7 @@ fn extract_recursive(
                                 result.references.push(Reference {
                                     name: method_name,
                                     location: node_to_location(file, &child),
+                                    kind: reference_kind(&child),
                                 });
                             }
                             break; // Found the callee
@@ -647,6 +639,7 @@ fn extract_recursive(
                         result.references.push(Reference {
                             name: name.to_string(),
                             location: node_to_location(file, node),
+                            kind: reference_kind(node),
                         });
                     }
                     // Also extract just the suffix (property name)
@@ -654,6 +647,7 @@ fn extract_recursive(
                         result.references.push(Reference {
                             name: prop_name,
                             location: node_to_location(file, node),
+                            kind: reference_kind(node),
                         });
                     }
                 }
@@ -667,6 +661,7 @@ fn extract_recursive(
                     result.references.push(Reference {
                         name: name.to_string(),
                         location: node_to_location(file, node),
+                        kind: reference_kind(node),
                     });
                 }
             }
@@ -687,7 +682,8 @@ fn extract_recursive(
     }
 }
 
-/// Extract primary constructor parameters for data classes
+/// Extract primary constructor parameters declared as properties (`val`/`var`),
+/// which includes every parameter of a data class
 fn extract_primary_constructor_params(
     node: &tree_sitter::Node,
     source: &[u8],
@@ -701,7 +697,7 @@ fn extract_primary_constructor_params(
             if cursor.goto_first_child() {
                 loop {
                     let child = cursor.node();
-                    if child.kind() == "class_parameter" {
+                    if child.kind() == "class_parameter" && is_property_parameter(&child) {
                         if let Some(id) = find_child_by_kind(&child, "identifier") {
                             if let Ok(name) = id.utf8_text(source) {
                                 let qualified = format!("{}.{}", class_qualified, name);
@@ -710,7 +706,7 @@ fn extract_primary_constructor_params(
                                     qualified,
                                     kind: SymbolKind::Member,
                                     location: node_to_location(file, &id),
-                                    visibility: Visibility::Public,
+                                    visibility: extract_visibility(&child, source),
                                     language: "kotlin".to_string(),
                                     parent: Some(class_qualified.to_string()),
                                     mixins: None,
@@ -731,6 +727,15 @@ fn extract_primary_constructor_params(
     }
 }
 
+/// Check if a constructor parameter declares a property (`val x` or `var x`)
+fn is_property_parameter(param: &tree_sitter::Node) -> bool {
+    let mut cursor = param.walk();
+    let declares = param
+        .children(&mut cursor)
+        .any(|child| matches!(child.kind(), "val" | "var"));
+    declares
+}
+
 /// Extract enum entries
 fn extract_enum_entries(
     body: &tree_sitter::Node,
@@ -1259,4 +1264,32 @@ class Client {
             ref_names
         );
     }
+
+    #[test]
+    fn extracts_constructor_property_declarations() {
+        let source = r#"
+package com.example
+
+class Account(val id: Int, private var balance: Long, label: String)
+"#;
+        let parser = KotlinParser;
+        let result = parser.extract_symbols(std::path::Path::new("Account.kt"), source, 100);
+
+        let id = result
+            .symbols
+            .iter()
+            .find(|s| s.qualified == "com.example.Account.id")
+            .expect("Should find id property");
+        assert_eq!(id.visibility, Visibility::Public);
+
+        let balance = result
+            .symbols
+            .iter()
+            .find(|s| s.name == "balance")
+            .expect("Should find balance property");
+        assert_eq!(balance.visibility, Visibility::Private);
+
+        // A plain constructor parameter isn't a property
+        assert!(!result.symbols.iter().any(|s| s.name == "label"));
+    }
 }
@@ -3,7 +3,9 @@
 use std::cell::RefCell;
 use std::path::Path;
 
-use crate::parse::{find_child_by_kind, node_to_location, LanguageParser, ParseResult};
+use crate::parse::{
+    find_child_by_kind, node_to_location, reference_kind, LanguageParser, ParseResult,
+};
 use crate::{Reference, Symbol, SymbolKind, Visibility};
 
 thread_local! {
@@ -36,6 +38,17 @@ impl LanguageParser for ObjCParser {
 
             extract_recursive(&root, source.as_bytes(), file, &mut result, None, max_depth);
 
+            crate::parse::capture_lambdas(
+                &root,
+                source.as_bytes(),
+                file,
+                &["block_literal"],
+                "objc",
+                &mut result,
+            );
+
+            result.metrics = crate::metrics::measure(&root, &result.symbols);
+
             result
         })
     }
@@ -309,6 +322,7 @@ fn extract_recursive(
                         result.references.push(Reference {
                             name: name.to_string(),
                             location: node_to_location(file, &func_node),
+                            kind: reference_kind(&func_node),
                         });
                     }
                 }
@@ -322,6 +336,7 @@ fn extract_recursive(
                     result.references.push(Reference {
                         name: name.to_string(),
                         location: node_to_location(file, node),
+                        kind: reference_kind(node),
                     });
                 }
             }
@@ -334,6 +349,7 @@ fn extract_recursive(
                 result.references.push(Reference {
                     name: name.to_string(),
                     location: node_to_location(file, node),
+                    kind: reference_kind(node),
                 });
             }
         }
@@ -423,6 +439,7 @@ fn extract_class_body(
                         result.references.push(Reference {
                             name: name.to_string(),
                             location: node_to_location(file, &child),
+                            kind: reference_kind(&child),
                         });
                     }
                 }
@@ -436,6 +453,7 @@ fn extract_class_body(
                                 result.references.push(Reference {
                                     name: name.to_string(),
                                     location: node_to_location(file, &child),
+                                    kind: reference_kind(&child),
                                 });
                             }
                         }
@@ -634,6 +652,7 @@ fn extract_message_reference(
                                 result.references.push(Reference {
                                     name: text.to_string(),
                                     location: node_to_location(file, &child),
+                                    kind: reference_kind(&child),
                                 });
                             }
                         } else {
@@ -641,6 +660,7 @@ fn extract_message_reference(
                             result.references.push(Reference {
                                 name: text.to_string(),
                                 location: node_to_location(file, &child),
+                                kind: reference_kind(&child),
                             });
                         }
                     }
@@ -659,6 +679,7 @@ fn extract_message_reference(
                             result.references.push(Reference {
                                 name: text.to_string(),
                                 location: node_to_location(file, &name_node),
+                                kind: reference_kind(&name_node),
                             });
                         }
                     }
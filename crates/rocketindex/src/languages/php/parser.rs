@@ -3,7 +3,9 @@
 use std::cell::RefCell;
 use std::path::Path;
 
-use crate::parse::{find_child_by_kind, node_to_location, LanguageParser, ParseResult};
+use crate::parse::{
+    find_child_by_kind, node_to_location, reference_kind, LanguageParser, ParseResult,
+};
 use crate::{Reference, Symbol, SymbolKind, Visibility};
 
 // Thread-local parser reuse - avoids creating a new parser per file
@@ -47,6 +49,17 @@ impl LanguageParser for PhpParser {
             // Extract references in a separate pass
             extract_references_recursive(&root, source.as_bytes(), file, &mut result);
 
+            crate::parse::capture_lambdas(
+                &root,
+                source.as_bytes(),
+                file,
+                &["anonymous_function", "arrow_function"],
+                "php",
+                &mut result,
+            );
+
+            result.metrics = crate::metrics::measure(&root, &result.symbols);
+
             result
         })
     }
@@ -698,13 +711,15 @@ fn extract_references_recursive(
     result: &mut ParseResult,
 ) {
     match node.kind() {
-        // name is a class/interface/trait name in PHP
+        // name is a class/interface/trait name in PHP, or a property accessed
+        // through an object ($user->name)
         "name" => {
-            if is_type_reference_context(node) {
+            if is_property_access(node) || is_type_reference_context(node) {
                 if let Ok(name) = node.utf8_text(source) {
                     result.references.push(Reference {
                         name: name.to_string(),
                         location: node_to_location(file, node),
+                        kind: reference_kind(node),
                     });
                 }
             }
@@ -716,6 +731,7 @@ fn extract_references_recursive(
                     result.references.push(Reference {
                         name: name.to_string(),
                         location: node_to_location(file, node),
+                        kind: reference_kind(node),
                     });
                 }
                 return; // Don't recurse into qualified_name children
@@ -732,6 +748,16 @@ fn extract_references_recursive(
     }
 }
 
+/// Check if a name is the property in `$obj->name` or `$obj?->name`
+fn is_property_access(node: &tree_sitter::Node) -> bool {
+    node.parent().is_some_and(|parent| {
+        matches!(
+            parent.kind(),
+            "member_access_expression" | "nullsafe_member_access_expression"
+        ) && parent.child_by_field_name("name") == Some(*node)
+    })
+}
+
 /// Check if a node is in a context where it represents a type reference (not a definition)
 fn is_type_reference_context(node: &tree_sitter::Node) -> bool {
     // A name is a reference when it's NOT in a definition context
@@ -1203,4 +1229,29 @@ class UserService implements Repository {
             ref_names
         );
     }
+
+    #[test]
+    fn extracts_php_property_references() {
+        let source = r#"<?php
+class User {
+    public $name;
+}
+
+function rename($user) {
+    $user->name = 'b';
+    return $user?->name;
+}
+"#;
+        let parser = PhpParser;
+        let result = parser.extract_symbols(std::path::Path::new("User.php"), source, 100);
+
+        let lines: Vec<_> = result
+            .references
+            .iter()
+            .filter(|r| r.name == "name")
+            .map(|r| r.location.line)
+            .collect();
+        assert!(lines.contains(&7), "found: {:?}", result.references);
+        assert!(lines.contains(&8), "found: {:?}", result.references);
+    }
 }
@@ -1,4 +1,5 @@
 pub mod parser;
+pub mod rails;
 pub mod resolver;
 
 pub use parser::RubyParser;
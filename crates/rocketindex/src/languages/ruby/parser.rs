@@ -3,7 +3,9 @@
 use std::cell::RefCell;
 use std::path::Path;
 
-use crate::parse::{find_child_by_kind, node_to_location, LanguageParser, ParseResult};
+use crate::parse::{
+    find_child_by_kind, node_to_location, reference_kind, LanguageParser, ParseResult,
+};
 use crate::{Reference, Symbol, SymbolKind, Visibility};
 
 // Thread-local parser reuse - avoids creating a new parser per file
@@ -37,6 +39,17 @@ impl LanguageParser for RubyParser {
 
             extract_recursive(&root, source.as_bytes(), file, &mut result, None, max_depth);
 
+            crate::parse::capture_lambdas(
+                &root,
+                source.as_bytes(),
+                file,
+                &["do_block", "block"],
+                "ruby",
+                &mut result,
+            );
+
+            result.metrics = crate::metrics::measure(&root, &result.symbols);
+
             result
         })
     }
@@ -164,6 +177,17 @@ impl From<VisibilityState> for Visibility {
     }
 }
 
+/// The visibility a call to `name` with arguments gives the methods it
+/// names, if it's a visibility modifier.
+fn visibility_modifier(name: &str) -> Option<VisibilityState> {
+    match name {
+        "private" | "private_class_method" => Some(VisibilityState::Private),
+        "protected" => Some(VisibilityState::Protected),
+        "public" | "public_class_method" => Some(VisibilityState::Public),
+        _ => None,
+    }
+}
+
 fn extract_recursive(
     node: &tree_sitter::Node,
     source: &[u8],
@@ -354,6 +378,7 @@ fn extract_recursive_inner(
                         result.references.push(Reference {
                             name: text.to_string(),
                             location: node_to_location(file, node),
+                            kind: reference_kind(node),
                         });
                     }
                 }
@@ -503,7 +528,7 @@ fn extract_recursive_inner(
                     language: "ruby".to_string(),
                     parent: None,
                     mixins: None,
-                    attributes: None,
+                    attributes: synthesized_by("alias"),
                     implements: None,
                     doc: None,
                     signature: None,
@@ -514,6 +539,34 @@ fn extract_recursive_inner(
         "call" => {
             if let Some(method) = node.child_by_field_name("method") {
                 if let Ok(name) = method.utf8_text(source) {
+                    // `private def helper` and `private :helper, :other`
+                    // change only the methods they name
+                    if let (Some(state), Some(args), None) = (
+                        visibility_modifier(name),
+                        node.child_by_field_name("arguments"),
+                        node.child_by_field_name("receiver"),
+                    ) {
+                        current_visibility = state;
+                        let separator = if in_singleton_class || name == "private_class_method" {
+                            "."
+                        } else {
+                            "#"
+                        };
+                        for (method_name, _) in literal_arguments(&args, source) {
+                            let qualified = match current_module {
+                                Some(m) => format!("{}{}{}", m, separator, method_name),
+                                None => method_name,
+                            };
+                            for symbol in result
+                                .symbols
+                                .iter_mut()
+                                .filter(|s| s.qualified == qualified)
+                            {
+                                symbol.visibility = state.into();
+                            }
+                        }
+                    }
+
                     // Handle require
                     if name == "require" || name == "require_relative" {
                         // Get argument
@@ -545,44 +598,31 @@ fn extract_recursive_inner(
                     }
                     // Handle alias_method :new_name, :old_name
                     else if name == "alias_method" {
-                        if let Some(args) = node.child_by_field_name("arguments") {
-                            // First argument is the new alias name
-                            for i in 0..args.child_count() {
-                                if let Some(arg) = args.child(i) {
-                                    let kind = arg.kind();
-                                    if kind == "simple_symbol" || kind == "symbol" {
-                                        if let Ok(sym_text) = arg.utf8_text(source) {
-                                            let alias_name =
-                                                sym_text.trim_start_matches(':').to_string();
-                                            let separator =
-                                                if in_singleton_class { "." } else { "#" };
-                                            let qualified = match current_module {
-                                                Some(m) => {
-                                                    format!("{}{}{}", m, separator, alias_name)
-                                                }
-                                                None => alias_name.to_string(),
-                                            };
-
-                                            result.symbols.push(Symbol {
-                                                name: alias_name,
-                                                qualified,
-                                                kind: SymbolKind::Function,
-                                                location: node_to_location(file, &arg),
-                                                visibility: Visibility::Public,
-                                                language: "ruby".to_string(),
-                                                parent: None,
-                                                mixins: None,
-                                                attributes: None,
-                                                implements: None,
-                                                doc: None,
-                                                signature: None,
-                                            });
-                                            // Only take the first argument (the new alias name)
-                                            break;
-                                        }
-                                    }
-                                }
-                            }
+                        // The first argument is the new name
+                        if let Some((alias_name, arg)) = node
+                            .child_by_field_name("arguments")
+                            .and_then(|args| literal_arguments(&args, source).into_iter().next())
+                        {
+                            let separator = if in_singleton_class { "." } else { "#" };
+                            let qualified = match current_module {
+                                Some(m) => format!("{}{}{}", m, separator, alias_name),
+                                None => alias_name.clone(),
+                            };
+
+                            result.symbols.push(Symbol {
+                                name: alias_name,
+                                qualified,
+                                kind: SymbolKind::Function,
+                                location: node_to_location(file, &arg),
+                                visibility: Visibility::Public,
+                                language: "ruby".to_string(),
+                                parent: None,
+                                mixins: None,
+                                attributes: synthesized_by(name),
+                                implements: None,
+                                doc: None,
+                                signature: None,
+                            });
                         }
                     }
                     // Handle attributes
@@ -591,46 +631,26 @@ fn extract_recursive_inner(
                         || name == "attr_accessor"
                     {
                         if let Some(args) = node.child_by_field_name("arguments") {
-                            // Iterate over all arguments
-                            for i in 0..args.child_count() {
-                                if let Some(arg) = args.child(i) {
-                                    let kind = arg.kind();
-                                    // Handle :symbol and "string"
-                                    let attr_name = if kind == "simple_symbol" || kind == "symbol" {
-                                        arg.utf8_text(source)
-                                            .ok()
-                                            .map(|s| s.trim_start_matches(':').to_string())
-                                    } else if kind == "string" {
-                                        find_child_by_kind(&arg, "string_content").and_then(|c| {
-                                            c.utf8_text(source).ok().map(|s| s.to_string())
-                                        })
-                                    } else {
-                                        None
-                                    };
-
-                                    if let Some(name) = attr_name {
-                                        let separator = "#";
-                                        let qualified = match current_module {
-                                            Some(m) => format!("{}{}{}", m, separator, name),
-                                            None => name.to_string(),
-                                        };
-
-                                        result.symbols.push(Symbol {
-                                            name,
-                                            qualified,
-                                            kind: SymbolKind::Member,
-                                            location: node_to_location(file, &arg),
-                                            visibility: Visibility::Public,
-                                            language: "ruby".to_string(),
-                                            parent: None,
-                                            mixins: None,
-                                            attributes: None,
-                                            implements: None,
-                                            doc: None,
-                                            signature: None,
-                                        });
-                                    }
-                                }
+                            for (attr_name, arg) in literal_arguments(&args, source) {
+                                let qualified = match current_module {
+                                    Some(m) => format!("{}#{}", m, attr_name),
+                                    None => attr_name.clone(),
+                                };
+
+                                result.symbols.push(Symbol {
+                                    name: attr_name,
+                                    qualified,
+                                    kind: SymbolKind::Member,
+                                    location: node_to_location(file, &arg),
+                                    visibility: Visibility::Public,
+                                    language: "ruby".to_string(),
+                                    parent: None,
+                                    mixins: None,
+                                    attributes: synthesized_by(name),
+                                    implements: None,
+                                    doc: None,
+                                    signature: None,
+                                });
                             }
                         }
                     }
@@ -677,87 +697,47 @@ fn extract_recursive_inner(
                     // Handle delegate - creates instance methods
                     // delegate :name, :email, to: :profile
                     // delegate :company_name, to: :company, prefix: true
+                    // delegate :street, to: :address, prefix: :home
                     else if name == "delegate" {
                         if let Some(args) = node.child_by_field_name("arguments") {
-                            let mut prefix: Option<String> = None;
-                            let mut method_names: Vec<(String, tree_sitter::Node)> = Vec::new();
-
-                            // First pass: collect method names and find prefix
-                            for i in 0..args.child_count() {
-                                if let Some(arg) = args.child(i) {
-                                    let kind = arg.kind();
-                                    if kind == "simple_symbol" || kind == "symbol" {
-                                        if let Ok(sym_text) = arg.utf8_text(source) {
-                                            let method_name =
-                                                sym_text.trim_start_matches(':').to_string();
-                                            method_names.push((method_name, arg));
-                                        }
-                                    } else if kind == "pair" {
-                                        // Check for prefix: true or prefix: :custom
-                                        if let Some(key) =
-                                            find_child_by_kind(&arg, "hash_key_symbol")
-                                        {
-                                            if let Ok(key_text) = key.utf8_text(source) {
-                                                if key_text == "prefix" {
-                                                    // Get the value
-                                                    if let Some(val) = arg.child(2) {
-                                                        if val.kind() == "true" {
-                                                            // prefix: true - use the target name
-                                                            // Find "to:" pair to get target
-                                                            for j in 0..args.child_count() {
-                                                                if let Some(to_pair) = args.child(j)
-                                                                {
-                                                                    if to_pair.kind() == "pair" {
-                                                                        if let Some(to_key) =
-                                                                            find_child_by_kind(
-                                                                                &to_pair,
-                                                                                "hash_key_symbol",
-                                                                            )
-                                                                        {
-                                                                            if let Ok(to_key_text) =
-                                                                                to_key.utf8_text(
-                                                                                    source,
-                                                                                )
-                                                                            {
-                                                                                if to_key_text
-                                                                                    == "to"
-                                                                                {
-                                                                                    if let Some(
-                                                                                        to_val,
-                                                                                    ) = to_pair
-                                                                                        .child(2)
-                                                                                    {
-                                                                                        if let Ok(
-                                                                                            to_text,
-                                                                                        ) = to_val
-                                                                                            .utf8_text(
-                                                                                            source,
-                                                                                        )
-                                                                                        {
-                                                                                            prefix = Some(to_text.trim_start_matches(':').to_string());
-                                                                                        }
-                                                                                    }
-                                                                                }
-                                                                            }
-                                                                        }
-                                                                    }
-                                                                }
-                                                            }
-                                                        }
-                                                    }
-                                                }
-                                            }
-                                        }
+                            let mut target = None;
+                            let mut prefix = None;
+                            for i in 0..args.named_child_count() {
+                                let Some(pair) = args.named_child(i) else {
+                                    continue;
+                                };
+                                if pair.kind() != "pair" {
+                                    continue;
+                                }
+                                let key = pair
+                                    .child_by_field_name("key")
+                                    .and_then(|key| key.utf8_text(source).ok())
+                                    .map(|key| key.trim_matches(':'));
+                                let value = pair.child_by_field_name("value");
+                                match (key, value) {
+                                    (Some("to"), Some(value)) => {
+                                        target = literal_name(&value, source);
+                                    }
+                                    // prefix: true uses the target's name
+                                    (Some("prefix"), Some(value)) if value.kind() == "true" => {
+                                        prefix = Some(None);
                                     }
+                                    (Some("prefix"), Some(value)) => {
+                                        prefix = literal_name(&value, source).map(Some);
+                                    }
+                                    _ => {}
                                 }
                             }
-
-                            // Second pass: create symbols for each method
-                            for (method_name, arg_node) in method_names {
-                                let final_name = if let Some(ref p) = prefix {
-                                    format!("{}_{}", p, method_name)
-                                } else {
-                                    method_name
+                            let prefix = match prefix {
+                                Some(None) => target.clone(),
+                                Some(custom) => custom,
+                                None => None,
+                            };
+
+                            for (method_name, arg_node) in literal_arguments(&args, source) {
+                                let final_name = match &prefix {
+                                    Some(p) => format!("{}_{}", p, method_name),
+                                    None => method_name,
                                 };
 
                                 let qualified = match current_module {
@@ -774,55 +754,45 @@ fn extract_recursive_inner(
                                     language: "ruby".to_string(),
                                     parent: None,
                                     mixins: None,
-                                    attributes: None,
+                                    attributes: synthesized_by(name),
                                     implements: None,
                                     doc: None,
-                                    signature: None,
+                                    signature: target
+                                        .as_ref()
+                                        .map(|target| format!("delegated to {}", target)),
                                 });
                             }
                         }
                     }
                     // Handle define_method - creates instance methods dynamically
                     // define_method :custom_method do ... end
+                    // Names built at runtime (define_method("#{role}?")) can't be indexed
                     else if name == "define_method" {
-                        if let Some(args) = node.child_by_field_name("arguments") {
-                            // First argument is the method name
-                            for i in 0..args.child_count() {
-                                if let Some(arg) = args.child(i) {
-                                    let kind = arg.kind();
-                                    if kind == "simple_symbol" || kind == "symbol" {
-                                        if let Ok(sym_text) = arg.utf8_text(source) {
-                                            let method_name =
-                                                sym_text.trim_start_matches(':').to_string();
-                                            let separator =
-                                                if in_singleton_class { "." } else { "#" };
-                                            let qualified = match current_module {
-                                                Some(m) => {
-                                                    format!("{}{}{}", m, separator, method_name)
-                                                }
-                                                None => method_name.clone(),
-                                            };
-
-                                            result.symbols.push(Symbol {
-                                                name: method_name,
-                                                qualified,
-                                                kind: SymbolKind::Function,
-                                                location: node_to_location(file, &arg),
-                                                visibility: current_visibility.into(),
-                                                language: "ruby".to_string(),
-                                                parent: None,
-                                                mixins: None,
-                                                attributes: None,
-                                                implements: None,
-                                                doc: None,
-                                                signature: None,
-                                            });
-                                            // Only take the first symbol argument
-                                            break;
-                                        }
-                                    }
-                                }
-                            }
+                        // The first argument is the method name
+                        if let Some((method_name, arg)) = node
+                            .child_by_field_name("arguments")
+                            .and_then(|args| literal_arguments(&args, source).into_iter().next())
+                        {
+                            let separator = if in_singleton_class { "." } else { "#" };
+                            let qualified = match current_module {
+                                Some(m) => format!("{}{}{}", m, separator, method_name),
+                                None => method_name.clone(),
+                            };
+
+                            result.symbols.push(Symbol {
+                                name: method_name,
+                                qualified,
+                                kind: SymbolKind::Function,
+                                location: node_to_location(file, &arg),
+                                visibility: current_visibility.into(),
+                                language: "ruby".to_string(),
+                                parent: None,
+                                mixins: None,
+                                attributes: synthesized_by(name),
+                                implements: None,
+                                doc: None,
+                                signature: None,
+                            });
                         }
                     }
                     // Handle Rails associations - creates instance methods
@@ -884,14 +854,13 @@ fn extract_recursive_inner(
                                     let kind = arg.kind();
                                     if kind == "simple_symbol" || kind == "symbol" {
                                         if let Ok(sym_text) = arg.utf8_text(source) {
-                                            let method_name = sym_text
-                                                .trim_start_matches(':')
-                                                .trim_end_matches('!')
-                                                .to_string();
+                                            let method_name =
+                                                sym_text.trim_start_matches(':').to_string();
                                             // Callbacks reference existing methods
                                             result.references.push(Reference {
                                                 name: method_name,
                                                 location: node_to_location(file, &arg),
+                                                kind: reference_kind(&arg),
                                             });
                                             // Only take the first symbol (method name)
                                             break;
@@ -916,6 +885,7 @@ fn extract_recursive_inner(
                                             result.references.push(Reference {
                                                 name: method_name,
                                                 location: node_to_location(file, &arg),
+                                                kind: reference_kind(&arg),
                                             });
                                             // Only take the first symbol
                                             break;
@@ -941,6 +911,7 @@ fn extract_recursive_inner(
                         result.references.push(Reference {
                             name: method_name,
                             location: node_to_location(file, &method),
+                            kind: reference_kind(&method),
                         });
                     }
                 }
@@ -954,6 +925,7 @@ fn extract_recursive_inner(
                     result.references.push(Reference {
                         name: name.to_string(),
                         location: node_to_location(file, node),
+                        kind: reference_kind(node),
                     });
                 }
             }
@@ -966,6 +938,7 @@ fn extract_recursive_inner(
                     result.references.push(Reference {
                         name: name.to_string(),
                         location: node_to_location(file, node),
+                        kind: reference_kind(node),
                     });
                 }
             }
@@ -991,6 +964,51 @@ fn extract_recursive_inner(
     }
 }
 
+/// Attribute on symbols that a class-level macro defines rather than a `def`
+pub const SYNTHESIZED: &str = "synthesized";
+
+/// Attributes of a method defined by the `macro_name` macro
+/// (`attr_accessor`, `define_method`, `delegate`, ...): [`SYNTHESIZED`]
+/// and the macro, so `rkt symbols --attribute synthesized` finds them all.
+fn synthesized_by(macro_name: &str) -> Option<Vec<String>> {
+    Some(vec![SYNTHESIZED.to_string(), macro_name.to_string()])
+}
+
+/// The name a literal argument spells out: `:name`, `:"name"` or `"name"`.
+///
+/// `None` for anything else, including strings with interpolation, whose
+/// value is only known at runtime.
+fn literal_name(node: &tree_sitter::Node, source: &[u8]) -> Option<String> {
+    match node.kind() {
+        "simple_symbol" => node
+            .utf8_text(source)
+            .ok()
+            .map(|text| text.trim_start_matches(':').to_string()),
+        "delimited_symbol" | "string" => {
+            if node.named_child_count() != 1 {
+                return None;
+            }
+            let content = node.named_child(0)?;
+            if content.kind() != "string_content" {
+                return None;
+            }
+            content.utf8_text(source).ok().map(str::to_string)
+        }
+        _ => None,
+    }
+}
+
+/// The literal names among a call's arguments, skipping options like `to:`.
+fn literal_arguments<'a>(
+    args: &tree_sitter::Node<'a>,
+    source: &[u8],
+) -> Vec<(String, tree_sitter::Node<'a>)> {
+    (0..args.named_child_count())
+        .filter_map(|i| args.named_child(i))
+        .filter_map(|arg| literal_name(&arg, source).map(|name| (name, arg)))
+        .collect()
+}
+
 fn qualified_name(name: &str, current_module: Option<&str>) -> String {
     match current_module {
         Some(m) => format!("{}::{}", m, name),
@@ -1751,6 +1769,40 @@ end
         );
     }
 
+    #[test]
+    fn visibility_modifiers_with_arguments_change_only_named_methods() {
+        let source = r#"
+class Billing
+  def charge; end
+
+  private def secret; end
+
+  def helper; end
+  private :helper
+
+  def self.build; end
+  private_class_method :build
+
+  def still_public; end
+end
+"#;
+        let result = extract_symbols(Path::new("billing.rb"), source, 500);
+        let visibility = |qualified: &str| {
+            result
+                .symbols
+                .iter()
+                .find(|s| s.qualified == qualified)
+                .unwrap()
+                .visibility
+        };
+
+        assert_eq!(visibility("Billing#charge"), Visibility::Public);
+        assert_eq!(visibility("Billing#secret"), Visibility::Private);
+        assert_eq!(visibility("Billing#helper"), Visibility::Private);
+        assert_eq!(visibility("Billing.build"), Visibility::Private);
+        assert_eq!(visibility("Billing#still_public"), Visibility::Public);
+    }
+
     #[test]
     fn extracts_class_methods_via_define_method() {
         // define_method is used for dynamic method definition
@@ -1784,6 +1836,50 @@ end
         );
     }
 
+    #[test]
+    fn marks_macro_defined_methods_as_synthesized() {
+        let source = r##"
+class User
+  attr_accessor "nickname"
+  define_method("active?") { true }
+  define_method("#{role}?") { true }
+  delegate :street, to: :address, prefix: :home
+  alias_method "display_name", :nickname
+
+  def save; end
+end
+"##;
+        let result = extract_symbols(Path::new("user.rb"), source, 500);
+        let synthesized: Vec<(&str, &str)> = result
+            .symbols
+            .iter()
+            .filter_map(|s| {
+                let attributes = s.attributes.as_ref()?;
+                assert_eq!(attributes[0], SYNTHESIZED);
+                Some((s.qualified.as_str(), attributes[1].as_str()))
+            })
+            .collect();
+        assert_eq!(
+            synthesized,
+            vec![
+                ("User#nickname", "attr_accessor"),
+                ("User#active?", "define_method"),
+                ("User#home_street", "delegate"),
+                ("User#display_name", "alias_method"),
+            ]
+        );
+
+        let street = result
+            .symbols
+            .iter()
+            .find(|s| s.name == "home_street")
+            .unwrap();
+        assert_eq!(street.signature.as_deref(), Some("delegated to address"));
+
+        let save = result.symbols.iter().find(|s| s.name == "save").unwrap();
+        assert_eq!(save.attributes, None);
+    }
+
     #[test]
     fn extracts_doc_comments() {
         // Test top-level class/module doc comments
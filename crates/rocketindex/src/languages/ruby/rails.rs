@@ -0,0 +1,220 @@
+//! Ruby on Rails naming conventions, for the opt-in Rails mode
+//! (`rails = true` in .rocketindex.toml).
+//!
+//! Rails connects code by name rather than by reference: `has_many
+//! :line_items` means the `LineItem` model, and `UsersController#show`
+//! renders `app/views/users/show.html.erb`. These helpers spell the
+//! mappings out so resolution and `rkt def` can follow them.
+
+use std::path::{Path, PathBuf};
+
+/// Singular and plural forms the suffix rules get wrong
+const IRREGULAR: &[(&str, &str)] = &[
+    ("person", "people"),
+    ("child", "children"),
+    ("man", "men"),
+    ("woman", "women"),
+    ("mouse", "mice"),
+    ("ox", "oxen"),
+];
+
+/// Words that are their own plural
+const UNCOUNTABLE: &[&str] = &[
+    "equipment",
+    "information",
+    "money",
+    "news",
+    "series",
+    "sheep",
+    "species",
+];
+
+/// `line_items` -> `line_item`, `people` -> `person`.
+pub fn singularize(word: &str) -> String {
+    let (head, last) = split_last_word(word);
+    if UNCOUNTABLE.contains(&last) {
+        return word.to_string();
+    }
+    if let Some((singular, _)) = IRREGULAR.iter().find(|(_, plural)| *plural == last) {
+        return format!("{}{}", head, singular);
+    }
+    let singular = if let Some(stem) = last.strip_suffix("ies") {
+        format!("{}y", stem)
+    } else if ["sses", "shes", "ches", "xes", "zes"]
+        .iter()
+        .any(|suffix| last.ends_with(suffix))
+    {
+        last[..last.len() - 2].to_string()
+    } else if last.ends_with("ss") || last.ends_with("us") {
+        last.to_string()
+    } else {
+        last.strip_suffix('s').unwrap_or(last).to_string()
+    };
+    format!("{}{}", head, singular)
+}
+
+/// `line_item` -> `line_items`, `person` -> `people`.
+pub fn pluralize(word: &str) -> String {
+    let (head, last) = split_last_word(word);
+    if UNCOUNTABLE.contains(&last) {
+        return word.to_string();
+    }
+    if let Some((_, plural)) = IRREGULAR.iter().find(|(singular, _)| *singular == last) {
+        return format!("{}{}", head, plural);
+    }
+    let consonant_y = last
+        .strip_suffix('y')
+        .filter(|stem| stem.chars().last().is_some_and(|c| !"aeiou".contains(c)));
+    let plural = if let Some(stem) = consonant_y {
+        format!("{}ies", stem)
+    } else if ["s", "sh", "ch", "x", "z"]
+        .iter()
+        .any(|suffix| last.ends_with(suffix))
+    {
+        format!("{}es", last)
+    } else {
+        format!("{}s", last)
+    };
+    format!("{}{}", head, plural)
+}
+
+/// `admin/line_items` -> `Admin::LineItems`.
+pub fn camelize(path: &str) -> String {
+    path.split('/')
+        .map(|segment| {
+            segment
+                .split('_')
+                .map(|word| {
+                    let mut chars = word.chars();
+                    chars.next().map_or_else(String::new, |first| {
+                        first.to_uppercase().chain(chars).collect()
+                    })
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("::")
+}
+
+/// `Admin::LineItem` -> `admin/line_item`.
+pub fn underscore(name: &str) -> String {
+    name.split("::")
+        .map(|segment| {
+            let mut snake = String::new();
+            for (i, c) in segment.chars().enumerate() {
+                if c.is_uppercase() && i > 0 {
+                    snake.push('_');
+                }
+                snake.extend(c.to_lowercase());
+            }
+            snake
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// The model a snake_case name stands for: a table (`line_items`), an
+/// association (`:line_item`) or a foreign key (`line_item_id`).
+///
+/// `None` for names that aren't lowercase identifiers.
+pub fn model_name(name: &str) -> Option<String> {
+    let name = name.trim_start_matches(':');
+    let name = name.strip_suffix("_id").unwrap_or(name);
+    let is_snake = name.chars().next().is_some_and(|c| c.is_ascii_lowercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    is_snake.then(|| camelize(&singularize(name)))
+}
+
+/// The controller and action of a qualified action name:
+/// `Admin::UsersController#show` -> (`Admin::UsersController`, `show`).
+pub fn controller_action(qualified: &str) -> Option<(&str, &str)> {
+    let (controller, action) = qualified.rsplit_once('#')?;
+    controller
+        .ends_with("Controller")
+        .then_some((controller, action))
+}
+
+/// View templates for a controller action, relative to `root`:
+/// `UsersController#show` renders `app/views/users/show.*`.
+pub fn action_views(root: &Path, qualified: &str) -> Vec<PathBuf> {
+    let Some((controller, action)) = controller_action(qualified) else {
+        return Vec::new();
+    };
+    let resource = underscore(controller.trim_end_matches("Controller"));
+    let dir = Path::new("app").join("views").join(resource);
+    let Ok(entries) = std::fs::read_dir(root.join(&dir)) else {
+        return Vec::new();
+    };
+    let prefix = format!("{}.", action);
+    let mut views: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .filter(|name| name.starts_with(&prefix))
+        .map(|name| dir.join(name))
+        .collect();
+    views.sort();
+    views
+}
+
+/// Everything before the last `_`-separated word, and that word.
+fn split_last_word(word: &str) -> (&str, &str) {
+    match word.rfind('_') {
+        Some(i) => word.split_at(i + 1),
+        None => ("", word),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inflects_like_rails() {
+        let pairs = [
+            ("user", "users"),
+            ("line_item", "line_items"),
+            ("category", "categories"),
+            ("address", "addresses"),
+            ("match", "matches"),
+            ("person", "people"),
+            ("news", "news"),
+            ("day", "days"),
+        ];
+        for (singular, plural) in pairs {
+            assert_eq!(pluralize(singular), plural);
+            assert_eq!(singularize(plural), singular);
+        }
+        assert_eq!(camelize("admin/line_items"), "Admin::LineItems");
+        assert_eq!(underscore("Admin::LineItem"), "admin/line_item");
+    }
+
+    #[test]
+    fn names_map_to_models() {
+        assert_eq!(model_name("line_items").as_deref(), Some("LineItem"));
+        assert_eq!(model_name(":author").as_deref(), Some("Author"));
+        assert_eq!(model_name("user_id").as_deref(), Some("User"));
+        assert_eq!(model_name("User"), None);
+        assert_eq!(model_name("users.find"), None);
+    }
+
+    #[test]
+    fn actions_map_to_views() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let views = dir.path().join("app/views/admin/users");
+        std::fs::create_dir_all(&views).unwrap();
+        for name in ["show.html.erb", "show.json.jbuilder", "index.html.erb"] {
+            std::fs::write(views.join(name), "").unwrap();
+        }
+
+        assert_eq!(
+            action_views(dir.path(), "Admin::UsersController#show"),
+            vec![
+                PathBuf::from("app/views/admin/users/show.html.erb"),
+                PathBuf::from("app/views/admin/users/show.json.jbuilder"),
+            ]
+        );
+        assert!(action_views(dir.path(), "Admin::User#show").is_empty());
+    }
+}
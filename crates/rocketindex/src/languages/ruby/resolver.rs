@@ -2,8 +2,12 @@
 
 use std::path::Path;
 
+use super::rails;
 use crate::resolve::{ResolutionPath, ResolveResult, SymbolResolver};
-use crate::{CodeIndex, SymbolKind};
+use crate::{CodeIndex, Symbol, SymbolKind};
+
+/// How many superclasses to follow before giving up (guards against cycles)
+const MAX_SUPERCLASS_DEPTH: usize = 32;
 
 pub struct RubyResolver;
 
@@ -43,14 +47,65 @@ impl SymbolResolver for RubyResolver {
             // For now, step 1 covers top-level if they are fully qualified or just simple names.
         }
 
+        // 4. Rails conventions: methods of the file's classes and their
+        // superclasses (`before_action :authenticate` naming a method of
+        // ApplicationController), then the model a table or association
+        // name stands for (`line_items` -> LineItem)
+        if index.rails_conventions() {
+            if let Some(result) = resolve_in_class_chain(index, name, from_file) {
+                return Some(result);
+            }
+            if let Some(symbol) = rails::model_name(name).and_then(|model| index.get(&model)) {
+                return Some(ResolveResult {
+                    symbol,
+                    resolution_path: ResolutionPath::Convention("rails model".to_string()),
+                });
+            }
+        }
+
         None
     }
 }
 
+/// Resolve `name` as an instance method of a class defined in `from_file`,
+/// or of one of its superclasses.
+fn resolve_in_class_chain<'a>(
+    index: &'a CodeIndex,
+    name: &str,
+    from_file: &Path,
+) -> Option<ResolveResult<'a>> {
+    let classes = index
+        .symbols_in_file(from_file)
+        .into_iter()
+        .filter(|symbol| symbol.kind == SymbolKind::Class);
+    for class in classes {
+        let mut current: Option<&Symbol> = Some(class);
+        for depth in 0..MAX_SUPERCLASS_DEPTH {
+            let Some(owner) = current else { break };
+            if let Some(symbol) = index.get(&format!("{}#{}", owner.qualified, name)) {
+                let resolution_path = if depth == 0 {
+                    ResolutionPath::SameModule
+                } else {
+                    ResolutionPath::ParentModule(owner.qualified.clone())
+                };
+                return Some(ResolveResult {
+                    symbol,
+                    resolution_path,
+                });
+            }
+            current = owner
+                .parent
+                .as_deref()
+                .and_then(|superclass| index.get(superclass.trim_start_matches("::")));
+        }
+    }
+    None
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{CodeIndex, Location, Symbol, Visibility};
+    use crate::{CodeIndex, Location, Visibility};
     use std::path::PathBuf;
 
     #[test]
@@ -82,4 +137,75 @@ mod tests {
         assert!(result.is_some());
         assert_eq!(result.unwrap().symbol.qualified, "MyApp::Utils::Helper");
     }
+
+    fn ruby_symbol(qualified: &str, kind: SymbolKind, file: &str, parent: Option<&str>) -> Symbol {
+        let name = qualified.rsplit(['#', ':']).next().unwrap().to_string();
+        let mut symbol = Symbol::new(
+            name,
+            qualified.to_string(),
+            kind,
+            Location::new(PathBuf::from(file), 1, 1),
+            Visibility::Public,
+            "ruby".to_string(),
+        );
+        symbol.parent = parent.map(str::to_string);
+        symbol
+    }
+
+    #[test]
+    fn resolves_rails_conventions_only_when_enabled() {
+        let mut index = CodeIndex::new();
+        for symbol in [
+            ruby_symbol(
+                "ApplicationController",
+                SymbolKind::Class,
+                "app/controllers/application_controller.rb",
+                None,
+            ),
+            ruby_symbol(
+                "ApplicationController#authenticate_user!",
+                SymbolKind::Function,
+                "app/controllers/application_controller.rb",
+                None,
+            ),
+            ruby_symbol(
+                "UsersController",
+                SymbolKind::Class,
+                "app/controllers/users_controller.rb",
+                Some("ApplicationController"),
+            ),
+            ruby_symbol(
+                "LineItem",
+                SymbolKind::Class,
+                "app/models/line_item.rb",
+                None,
+            ),
+        ] {
+            index.add_symbol(symbol);
+        }
+        let controller = Path::new("app/controllers/users_controller.rb");
+
+        assert!(RubyResolver
+            .resolve(&index, "authenticate_user!", controller)
+            .is_none());
+
+        index.set_rails_conventions(true);
+        let callback = RubyResolver
+            .resolve(&index, "authenticate_user!", controller)
+            .unwrap();
+        assert_eq!(
+            callback.symbol.qualified,
+            "ApplicationController#authenticate_user!"
+        );
+        assert_eq!(
+            callback.resolution_path,
+            ResolutionPath::ParentModule("ApplicationController".to_string())
+        );
+
+        let model = RubyResolver
+            .resolve(&index, "line_items", controller)
+            .unwrap();
+        assert_eq!(model.symbol.qualified, "LineItem");
+        assert_eq!(model.confidence(), crate::Confidence::Heuristic);
+    }
 }
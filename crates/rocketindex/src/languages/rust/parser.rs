@@ -1,10 +1,13 @@
 //! Symbol extraction from Rust source files using tree-sitter.
 
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::path::Path;
 
-use crate::parse::{find_child_by_kind, node_to_location, LanguageParser, ParseResult};
-use crate::{Reference, Symbol, SymbolKind, Visibility};
+use crate::parse::{
+    find_child_by_kind, node_to_location, reference_kind, LanguageParser, ParseResult,
+};
+use crate::{ModuleAlias, Reference, Symbol, SymbolKind, Visibility};
 
 // Thread-local parser reuse - avoids creating a new parser per file
 thread_local! {
@@ -24,6 +27,16 @@ thread_local! {
             .expect("tree-sitter-rust grammar incompatible with tree-sitter version");
         parser
     });
+
+    // Parser for expansions of macro_rules! invocations, which may themselves
+    // contain macro bodies parsed with MACRO_PARSER
+    static EXPANSION_PARSER: RefCell<tree_sitter::Parser> = RefCell::new({
+        let mut parser = tree_sitter::Parser::new();
+        parser
+            .set_language(&tree_sitter_rust::LANGUAGE.into())
+            .expect("tree-sitter-rust grammar incompatible with tree-sitter version");
+        parser
+    });
 }
 
 pub struct RustParser;
@@ -46,6 +59,25 @@ impl LanguageParser for RustParser {
 
             extract_recursive(&root, source.as_bytes(), file, &mut result, None, max_depth);
 
+            // Impls and macros are linked up once the whole file is known
+            let mut items = CrateItems::default();
+            collect_crate_items(&root, source.as_bytes(), None, &mut items, max_depth);
+            link_trait_impls(&mut result, &items.impls);
+            expand_macro_invocations(&items, source.as_bytes(), file, &mut result, max_depth);
+
+            crate::parse::capture_lambdas(
+                &root,
+                source.as_bytes(),
+                file,
+                &["closure_expression"],
+                "rust",
+                &mut result,
+            );
+            let regions = crate::conditional::cfg_regions(&root, source.as_bytes(), file);
+            crate::conditional::tag_symbols(&mut result, &regions);
+
+            result.metrics = crate::metrics::measure(&root, &result.symbols);
+
             result
         })
     }
@@ -276,21 +308,27 @@ fn extract_recursive(
                     let qualified = qualified_name(name, parent_path);
                     let visibility = extract_visibility(node, source);
                     let doc = extract_doc_comments(node, source);
+                    let attributes = extract_attributes(node, source);
 
                     result.symbols.push(Symbol {
                         name: name.to_string(),
-                        qualified,
+                        qualified: qualified.clone(),
                         kind: SymbolKind::Class, // Using Class for struct
                         location: node_to_location(file, &name_node),
                         visibility,
                         language: "rust".to_string(),
                         parent: None,
                         mixins: None,
-                        attributes: extract_attributes(node, source),
-                        implements: None,
+                        attributes: attributes.clone(),
+                        implements: derived_traits(attributes.as_deref()),
                         doc,
                         signature: None,
                     });
+
+                    // Extract named struct fields
+                    if let Some(body) = find_child_by_kind(node, "field_declaration_list") {
+                        extract_struct_fields(&body, source, file, result, &qualified);
+                    }
                 }
             }
         }
@@ -301,6 +339,7 @@ fn extract_recursive(
                     let qualified = qualified_name(name, parent_path);
                     let visibility = extract_visibility(node, source);
                     let doc = extract_doc_comments(node, source);
+                    let attributes = extract_attributes(node, source);
 
                     result.symbols.push(Symbol {
                         name: name.to_string(),
@@ -311,8 +350,8 @@ fn extract_recursive(
                         language: "rust".to_string(),
                         parent: None,
                         mixins: None,
-                        attributes: extract_attributes(node, source),
-                        implements: None,
+                        attributes: attributes.clone(),
+                        implements: derived_traits(attributes.as_deref()),
                         doc,
                         signature: None,
                     });
@@ -378,6 +417,22 @@ fn extract_recursive(
                     None => type_name.clone(),
                 };
 
+                // The trait and type named in the header are references
+                for i in 0..node.child_count() {
+                    if let Some(child) = node.child(i) {
+                        if child.kind() != "declaration_list" {
+                            extract_recursive(
+                                &child,
+                                source,
+                                file,
+                                result,
+                                parent_path,
+                                max_depth - 1,
+                            );
+                        }
+                    }
+                }
+
                 // Extract methods from impl block
                 if let Some(body) = find_child_by_kind(node, "declaration_list") {
                     for i in 0..body.child_count() {
@@ -391,7 +446,23 @@ fn extract_recursive(
                                     &impl_path,
                                     trait_name.as_deref(),
                                 );
-                            } else if child.kind() == "const_item" || child.kind() == "type_item" {
+                                // References from the signature and body
+                                for j in 0..child.child_count() {
+                                    if let Some(part) = child.child(j) {
+                                        extract_recursive(
+                                            &part,
+                                            source,
+                                            file,
+                                            result,
+                                            Some(&impl_path),
+                                            max_depth - 1,
+                                        );
+                                    }
+                                }
+                            } else if matches!(
+                                child.kind(),
+                                "const_item" | "type_item" | "macro_invocation"
+                            ) {
                                 extract_recursive(
                                     &child,
                                     source,
@@ -509,7 +580,9 @@ fn extract_recursive(
 
         "use_declaration" => {
             // Extract use statements for imports
-            extract_use_statement(node, source, result);
+            if let Some(argument) = node.child_by_field_name("argument") {
+                extract_use_tree(&argument, source, None, result);
+            }
         }
 
         "macro_invocation" => {
@@ -529,6 +602,27 @@ fn extract_recursive(
                     result.references.push(Reference {
                         name: name.to_string(),
                         location: node_to_location(file, node),
+                        kind: reference_kind(node),
+                    });
+                }
+            }
+        }
+
+        // Field accesses, struct literal initializers and struct patterns
+        // reference the field by name (e.g. `p.x`, `Point { x: 1 }`)
+        "field_identifier" | "shorthand_field_identifier" => {
+            let is_field_use = node.parent().is_some_and(|p| {
+                matches!(
+                    p.kind(),
+                    "field_expression" | "field_initializer" | "field_pattern"
+                )
+            });
+            if is_field_use {
+                if let Ok(name) = node.utf8_text(source) {
+                    result.references.push(Reference {
+                        name: name.to_string(),
+                        location: node_to_location(file, node),
+                        kind: reference_kind(node),
                     });
                 }
             }
@@ -1088,6 +1182,45 @@ fn extract_enum_variants(
     }
 }
 
+/// Extract named struct fields
+fn extract_struct_fields(
+    body: &tree_sitter::Node,
+    source: &[u8],
+    file: &Path,
+    result: &mut ParseResult,
+    struct_path: &str,
+) {
+    for i in 0..body.child_count() {
+        if let Some(child) = body.child(i) {
+            if child.kind() == "field_declaration" {
+                if let Some(name_node) = child.child_by_field_name("name") {
+                    if let Ok(name) = name_node.utf8_text(source) {
+                        let qualified = format!("{}::{}", struct_path, name);
+
+                        result.symbols.push(Symbol {
+                            name: name.to_string(),
+                            qualified,
+                            kind: SymbolKind::Member, // Using Member for struct fields
+                            location: node_to_location(file, &name_node),
+                            visibility: extract_visibility(&child, source),
+                            language: "rust".to_string(),
+                            parent: Some(struct_path.to_string()),
+                            mixins: None,
+                            attributes: extract_attributes(&child, source),
+                            implements: None,
+                            doc: extract_doc_comments(&child, source),
+                            signature: child
+                                .child_by_field_name("type")
+                                .and_then(|t| t.utf8_text(source).ok())
+                                .map(|t| t.to_string()),
+                        });
+                    }
+                }
+            }
+        }
+    }
+}
+
 /// Extract the type name from an impl block (e.g., "Foo" from "impl Foo")
 fn extract_impl_type_name(node: &tree_sitter::Node, source: &[u8]) -> Option<String> {
     // Look for type_identifier in the impl
@@ -1237,49 +1370,315 @@ fn extract_impl_method(
     }
 }
 
-/// Extract use statements
-fn extract_use_statement(node: &tree_sitter::Node, source: &[u8], result: &mut ParseResult) {
-    // Simple extraction: get the full use path
-    if let Some(arg) = node.child_by_field_name("argument") {
-        if let Ok(text) = arg.utf8_text(source) {
-            // Handle use foo::bar; -> opens "foo::bar"
-            // Handle use foo::bar as baz; -> opens "foo::bar"
-            // Handle use foo::{a, b}; -> opens "foo::a", "foo::b"
-
-            let text = text.trim();
-
-            // Check for use list: foo::{a, b}
-            if text.contains('{') {
-                if let Some(brace_pos) = text.find('{') {
-                    let prefix = text[..brace_pos].trim_end_matches("::");
-                    // Extract items from braces
-                    if let Some(end_brace) = text.find('}') {
-                        let items = &text[brace_pos + 1..end_brace];
-                        for item in items.split(',') {
-                            let item = item.trim();
-                            if !item.is_empty() {
-                                // Handle "self" specially
-                                if item == "self" {
-                                    result.opens.push(prefix.to_string());
-                                } else {
-                                    result.opens.push(format!("{}::{}", prefix, item));
-                                }
-                            }
-                        }
-                    }
+/// Turn the tree of a `use` declaration into opens, with `as` renames also
+/// recorded as aliases:
+///
+/// - `use foo::{bar, baz::*}` opens `foo::bar` and `foo::baz`
+/// - `use foo::{self}` opens `foo`
+/// - `use foo::Bar as Baz` opens `foo::Bar` and aliases `Baz` to it
+fn extract_use_tree(
+    node: &tree_sitter::Node,
+    source: &[u8],
+    prefix: Option<&str>,
+    result: &mut ParseResult,
+) {
+    let text = |node: &tree_sitter::Node| node.utf8_text(source).ok().map(str::trim);
+    let join = |path: &str| match prefix {
+        Some(prefix) => format!("{}::{}", prefix, path),
+        None => path.to_string(),
+    };
+
+    match node.kind() {
+        "use_list" => {
+            for i in 0..node.named_child_count() {
+                if let Some(child) = node.named_child(i) {
+                    extract_use_tree(&child, source, prefix, result);
                 }
-            } else if text.contains(" as ") {
-                // use foo::bar as baz -> import foo::bar
-                if let Some(as_pos) = text.find(" as ") {
-                    result.opens.push(text[..as_pos].trim().to_string());
+            }
+        }
+        "scoped_use_list" => {
+            let path = node
+                .child_by_field_name("path")
+                .and_then(|path| text(&path))
+                .map(join);
+            if let Some(list) = node.child_by_field_name("list") {
+                extract_use_tree(&list, source, path.as_deref().or(prefix), result);
+            }
+        }
+        "use_as_clause" => {
+            let path = node.child_by_field_name("path").and_then(|n| text(&n));
+            let alias = node.child_by_field_name("alias").and_then(|n| text(&n));
+            if let Some(path) = path {
+                let target = join(path);
+                // `use foo as _` only brings trait methods into scope
+                if let Some(alias) = alias.filter(|alias| *alias != "_") {
+                    result.aliases.push(ModuleAlias {
+                        alias: alias.to_string(),
+                        target: target.clone(),
+                    });
                 }
-            } else {
-                result.opens.push(text.to_string());
+                result.opens.push(target);
+            }
+        }
+        "use_wildcard" => {
+            // `foo::*` opens `foo`; a bare `*` in a list opens the prefix
+            match node.named_child(0).and_then(|path| text(&path)) {
+                Some(path) => result.opens.push(join(path)),
+                None => result.opens.extend(prefix.map(str::to_string)),
+            }
+        }
+        "self" if prefix.is_some() => result.opens.extend(prefix.map(str::to_string)),
+        _ => {
+            if let Some(path) = text(node) {
+                result.opens.push(join(path));
             }
         }
     }
 }
 
+/// Traits a type gets from `#[derive(...)]`.
+fn derived_traits(attributes: Option<&[String]>) -> Option<Vec<String>> {
+    let traits: Vec<String> = attributes?
+        .iter()
+        .filter_map(|attribute| attribute.strip_prefix("derive("))
+        .flat_map(|list| list.trim_end_matches(')').split(','))
+        .map(|name| name.trim().rsplit("::").next().unwrap_or("").to_string())
+        .filter(|name| !name.is_empty())
+        .collect();
+    (!traits.is_empty()).then_some(traits)
+}
+
+/// Items collected in a second pass over the file: which traits are
+/// implemented for which types, and the `macro_rules!` defined and invoked.
+#[derive(Default)]
+struct CrateItems<'tree> {
+    /// (qualified type, trait) for each `impl Trait for Type`
+    impls: Vec<(String, String)>,
+    /// `macro_rules!` definitions simple enough to expand, by name
+    macros: HashMap<String, Vec<MacroRule>>,
+    /// Macro invocations, with the module or type path they appear under
+    invocations: Vec<(tree_sitter::Node<'tree>, Option<String>)>,
+}
+
+/// One `(...) => { ... }` arm of a `macro_rules!` whose matcher is only
+/// metavariables: `($name:ident, $ty:ty)`.
+struct MacroRule {
+    /// Metavariable names, with their `$`
+    params: Vec<String>,
+    /// The transcriber, without its delimiters
+    body: String,
+}
+
+fn collect_crate_items<'tree>(
+    node: &tree_sitter::Node<'tree>,
+    source: &[u8],
+    parent_path: Option<&str>,
+    items: &mut CrateItems<'tree>,
+    max_depth: usize,
+) {
+    if max_depth == 0 {
+        return;
+    }
+
+    let mut path = parent_path.map(str::to_string);
+    match node.kind() {
+        "mod_item" => {
+            let name = node.child_by_field_name("name");
+            if let Some(name) = name.and_then(|n| n.utf8_text(source).ok()) {
+                path = Some(qualified_name(name, parent_path));
+            }
+        }
+        "impl_item" => {
+            if let Some(type_name) = extract_impl_type_name(node, source) {
+                let impl_path = qualified_name(&type_name, parent_path);
+                if let Some(trait_name) = extract_impl_trait_name(node, source) {
+                    items.impls.push((impl_path.clone(), trait_name));
+                }
+                path = Some(impl_path);
+            }
+        }
+        "macro_definition" => {
+            let name = node.child_by_field_name("name");
+            if let Some(name) = name.and_then(|n| n.utf8_text(source).ok()) {
+                let rules = macro_rules(node, source);
+                if !rules.is_empty() {
+                    items.macros.insert(name.to_string(), rules);
+                }
+            }
+            return;
+        }
+        "macro_invocation" => {
+            items.invocations.push((*node, path));
+            return;
+        }
+        _ => {}
+    }
+
+    for i in 0..node.child_count() {
+        if let Some(child) = node.child(i) {
+            collect_crate_items(&child, source, path.as_deref(), items, max_depth - 1);
+        }
+    }
+}
+
+/// The arms of a `macro_rules!` that match only metavariables and expand
+/// without repetitions.
+fn macro_rules(node: &tree_sitter::Node, source: &[u8]) -> Vec<MacroRule> {
+    let mut rules = Vec::new();
+    let mut cursor = node.walk();
+    for rule in node.children(&mut cursor) {
+        if rule.kind() != "macro_rule" {
+            continue;
+        }
+        let (Some(left), Some(right)) = (
+            rule.child_by_field_name("left"),
+            rule.child_by_field_name("right"),
+        ) else {
+            continue;
+        };
+
+        let mut params = Vec::new();
+        let mut simple = true;
+        let mut inner = left.walk();
+        for part in left.named_children(&mut inner) {
+            let name = part.child_by_field_name("name");
+            match name.and_then(|n| n.utf8_text(source).ok()) {
+                Some(name) if part.kind() == "token_binding_pattern" => {
+                    params.push(name.to_string())
+                }
+                _ => simple = false,
+            }
+        }
+
+        let body = right.utf8_text(source).unwrap_or("");
+        if simple && !body.contains("$(") && body.len() >= 2 {
+            rules.push(MacroRule {
+                params,
+                body: body[1..body.len() - 1].to_string(),
+            });
+        }
+    }
+    rules
+}
+
+/// Add the traits of `impl Trait for Type` blocks to `implements` of the
+/// types they're for, when those are defined in the same file.
+fn link_trait_impls(result: &mut ParseResult, impls: &[(String, String)]) {
+    for (type_path, trait_name) in impls {
+        let target = result.symbols.iter_mut().find(|symbol| {
+            symbol.qualified == *type_path
+                && matches!(symbol.kind, SymbolKind::Class | SymbolKind::Union)
+        });
+        if let Some(symbol) = target {
+            let implements = symbol.implements.get_or_insert_with(Vec::new);
+            if !implements.contains(trait_name) {
+                implements.push(trait_name.clone());
+            }
+        }
+    }
+}
+
+/// Synthesize the items that invocations of this file's simple
+/// `macro_rules!` produce: `getter!(width, u32);` with
+/// `($name:ident, $ty:ty) => { pub fn $name() -> $ty { .. } }` defines
+/// `width`. The items are located at the invocation.
+fn expand_macro_invocations(
+    items: &CrateItems,
+    source: &[u8],
+    file: &Path,
+    result: &mut ParseResult,
+    max_depth: usize,
+) {
+    for (invocation, parent_path) in &items.invocations {
+        let Some(name_node) = invocation.child_by_field_name("macro") else {
+            continue;
+        };
+        let Some(rules) = name_node
+            .utf8_text(source)
+            .ok()
+            .and_then(|name| items.macros.get(name))
+        else {
+            continue;
+        };
+        // Brace-delimited bodies are already read as items
+        let Some(args) = find_child_by_kind(invocation, "token_tree")
+            .and_then(|tree| tree.utf8_text(source).ok())
+            .filter(|args| !args.starts_with('{'))
+            .and_then(macro_arguments)
+        else {
+            continue;
+        };
+        let Some(expansion) = rules
+            .iter()
+            .find(|rule| rule.params.len() == args.len())
+            .and_then(|rule| expand_macro_rule(rule, &args))
+        else {
+            continue;
+        };
+
+        let mut expanded = ParseResult::default();
+        EXPANSION_PARSER.with(|parser| {
+            if let Some(tree) = parser.borrow_mut().parse(&expansion, None) {
+                extract_recursive(
+                    &tree.root_node(),
+                    expansion.as_bytes(),
+                    file,
+                    &mut expanded,
+                    parent_path.as_deref(),
+                    max_depth.saturating_sub(1),
+                );
+            }
+        });
+        let location = node_to_location(file, &name_node);
+        for mut symbol in expanded.symbols {
+            symbol.location = location.clone();
+            result.symbols.push(symbol);
+        }
+    }
+}
+
+/// The comma-separated arguments of an invocation's `(...)` or `[...]`.
+fn macro_arguments(tree: &str) -> Option<Vec<&str>> {
+    let inner = tree.get(1..tree.len().checked_sub(1)?)?;
+    let mut args = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in inner.char_indices() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                args.push(inner[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    args.push(inner[start..].trim());
+    if args.last() == Some(&"") {
+        args.pop();
+    }
+    Some(args)
+}
+
+/// The transcriber of `rule` with its metavariables replaced by `args`.
+fn expand_macro_rule(rule: &MacroRule, args: &[&str]) -> Option<String> {
+    let mut bindings: Vec<(&str, &str)> = rule
+        .params
+        .iter()
+        .map(String::as_str)
+        .zip(args.iter().copied())
+        .collect();
+    // `$name` must not eat the start of `$name_suffix`
+    bindings.sort_by_key(|(param, _)| std::cmp::Reverse(param.len()));
+
+    let mut expansion = rule.body.replace("$crate", "crate");
+    for (param, arg) in bindings {
+        expansion = expansion.replace(param, arg);
+    }
+    (!expansion.contains('$')).then_some(expansion)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1350,6 +1749,56 @@ pub struct Point {
         assert_eq!(sym.visibility, Visibility::Public);
     }
 
+    #[test]
+    fn extracts_rust_struct_fields_and_field_references() {
+        let source = r#"
+pub struct Point {
+    /// Horizontal position.
+    pub x: i32,
+    y: i32,
+}
+
+fn shift(p: Point) -> Point {
+    let y = p.y;
+    Point { x: p.x + 1, y }
+}
+"#;
+        let result = extract_symbols(std::path::Path::new("test.rs"), source, 100);
+
+        let x = result
+            .symbols
+            .iter()
+            .find(|s| s.qualified == "Point::x")
+            .expect("Should find field Point::x");
+        assert_eq!(x.kind, SymbolKind::Member);
+        assert_eq!(x.parent.as_deref(), Some("Point"));
+        assert_eq!(x.visibility, Visibility::Public);
+        assert_eq!(x.signature.as_deref(), Some("i32"));
+        assert_eq!(x.doc.as_deref(), Some("Horizontal position."));
+
+        let y = result
+            .symbols
+            .iter()
+            .find(|s| s.qualified == "Point::y")
+            .expect("Should find field Point::y");
+        assert_eq!(y.visibility, Visibility::Private);
+
+        // Field access (`p.x`) and struct literal initializer (`x: ...`)
+        let x_refs: Vec<_> = result
+            .references
+            .iter()
+            .filter(|r| r.name == "x")
+            .map(|r| r.location.line)
+            .collect();
+        assert_eq!(x_refs, vec![10, 10], "found: {:?}", result.references);
+
+        // Field access (`p.y`) plus shorthand initializer (`{ y }`)
+        assert!(result
+            .references
+            .iter()
+            .any(|r| r.name == "y" && r.location.line == 9));
+    }
+
     #[test]
     fn extracts_rust_function() {
         let source = r#"
@@ -1680,6 +2129,129 @@ cfg_rt! {
         assert_eq!(spawn.visibility, Visibility::Public);
     }
 
+    #[test]
+    fn links_trait_impls_and_derives() {
+        let source = r#"
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Point;
+
+pub enum Shape { Dot }
+
+impl std::fmt::Display for Point {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        render(f)
+    }
+}
+
+impl<T> From<T> for Shape {
+    fn from(_: T) -> Self { Shape::Dot }
+}
+
+impl Point {
+    pub fn origin() -> Self { Point }
+}
+"#;
+        let result = extract_symbols(std::path::Path::new("test.rs"), source, 100);
+
+        let point = result.symbols.iter().find(|s| s.name == "Point").unwrap();
+        assert_eq!(
+            point.implements.as_deref(),
+            Some(
+                &[
+                    "Debug".to_string(),
+                    "Clone".to_string(),
+                    "Serialize".to_string(),
+                    "Display".to_string()
+                ][..]
+            )
+        );
+        let shape = result.symbols.iter().find(|s| s.name == "Shape").unwrap();
+        assert_eq!(shape.implements, Some(vec!["From".to_string()]));
+
+        // Methods are qualified under the type, whichever impl they're in
+        for qualified in ["Point::fmt", "Shape::from", "Point::origin"] {
+            assert!(
+                result.symbols.iter().any(|s| s.qualified == qualified),
+                "missing {}",
+                qualified
+            );
+        }
+
+        // The impl header and method bodies are searched for references
+        let implemented = result
+            .references
+            .iter()
+            .find(|r| r.name.ends_with("Display"))
+            .unwrap();
+        assert_eq!(implemented.kind, crate::ReferenceKind::Implement);
+        assert!(result.references.iter().any(|r| r.name == "render"));
+    }
+
+    #[test]
+    fn extracts_use_trees_and_renames() {
+        let source = r#"
+use std::collections::{HashMap, hash_map::{self, Entry}};
+use std::io::prelude::*;
+use crate::ui::Widget as W;
+use std::fmt::Write as _;
+"#;
+        let result = extract_symbols(std::path::Path::new("test.rs"), source, 100);
+
+        assert_eq!(
+            result.opens,
+            vec![
+                "std::collections::HashMap",
+                "std::collections::hash_map",
+                "std::collections::hash_map::Entry",
+                "std::io::prelude",
+                "crate::ui::Widget",
+                "std::fmt::Write",
+            ]
+        );
+        assert_eq!(
+            result.aliases,
+            vec![ModuleAlias {
+                alias: "W".to_string(),
+                target: "crate::ui::Widget".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn synthesizes_items_from_simple_macros() {
+        let source = r#"
+macro_rules! getter {
+    ($name:ident, $ty:ty) => {
+        pub fn $name() -> $ty { todo!() }
+    };
+}
+
+macro_rules! repeated {
+    ($($name:ident),*) => { $(fn $name() {})* };
+}
+
+mod dims {
+    getter!(width, u32);
+    getter!(height, u32,);
+}
+
+repeated!(skipped);
+"#;
+        let result = extract_symbols(std::path::Path::new("test.rs"), source, 100);
+
+        let width = result
+            .symbols
+            .iter()
+            .find(|s| s.qualified == "dims::width")
+            .expect("Should synthesize dims::width");
+        assert_eq!(width.kind, SymbolKind::Function);
+        assert_eq!(width.signature.as_deref(), Some("pub fn width() -> u32"));
+        // Located at the invocation
+        assert_eq!(width.location.line, 13);
+        assert!(result.symbols.iter().any(|s| s.qualified == "dims::height"));
+        assert!(!result.symbols.iter().any(|s| s.name == "skipped"));
+    }
+
     #[test]
     fn extracts_struct_inside_macro_invocation() {
         let source = r#"
@@ -14,8 +14,11 @@ impl SymbolResolver for RustResolver {
         name: &str,
         from_file: &Path,
     ) -> Option<ResolveResult<'a>> {
-        // 1. Try exact qualified name match
-        if let Some(symbol) = index.get(name) {
+        // 1. Try exact qualified name match, with or without a leading `crate::`
+        let bare = name
+            .strip_prefix("crate::")
+            .or_else(|| name.strip_prefix("self::"));
+        if let Some(symbol) = std::iter::once(name).chain(bare).find_map(|n| index.get(n)) {
             return Some(ResolveResult {
                 symbol,
                 resolution_path: ResolutionPath::Qualified,
@@ -42,7 +45,15 @@ impl SymbolResolver for RustResolver {
 
         // 3. Try to resolve via use statements (opens)
         let file_opens = index.opens_for_file(from_file);
-        for open in file_opens {
+        // Parsed symbols are qualified from the crate root down, without the
+        // `crate::` a `use` may start with
+        let candidates = file_opens.iter().flat_map(|open| {
+            let bare = open
+                .strip_prefix("crate::")
+                .or_else(|| open.strip_prefix("self::"));
+            std::iter::once(open.as_str()).chain(bare)
+        });
+        for open in candidates {
             // For "use foo::bar", if we're looking for "bar", check if open ends with "::bar"
             if open.ends_with(&format!("::{}", name)) {
                 if let Some(resolved) = index.get(open) {
@@ -330,4 +341,42 @@ mod tests {
         assert!(result.is_some());
         assert_eq!(result.unwrap().symbol.qualified, "crate::utils::helper");
     }
+
+    #[test]
+    fn resolves_crate_relative_use_and_renames() {
+        let mut index = CodeIndex::new();
+        index.add_symbol(Symbol::new(
+            "Widget".to_string(),
+            "ui::Widget".to_string(),
+            SymbolKind::Class,
+            Location::new(PathBuf::from("src/lib.rs"), 2, 5),
+            Visibility::Public,
+            "rust".to_string(),
+        ));
+        index.add_symbol(Symbol::new(
+            "new".to_string(),
+            "ui::Widget::new".to_string(),
+            SymbolKind::Function,
+            Location::new(PathBuf::from("src/lib.rs"), 4, 9),
+            Visibility::Public,
+            "rust".to_string(),
+        ));
+        let main = PathBuf::from("src/main.rs");
+        let result = crate::parse::extract_symbols(
+            &main,
+            "use crate::ui::{Widget, Widget as W};\nfn main() { W::new(); }\n",
+            100,
+        );
+        for open in result.opens {
+            index.add_open(main.clone(), open);
+        }
+        for alias in result.aliases {
+            index.add_alias(main.clone(), alias);
+        }
+
+        let resolved = index.resolve("Widget", &main).unwrap();
+        assert_eq!(resolved.symbol.qualified, "ui::Widget");
+        let resolved = index.resolve("W::new", &main).unwrap();
+        assert_eq!(resolved.symbol.qualified, "ui::Widget::new");
+    }
 }
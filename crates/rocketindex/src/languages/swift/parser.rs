@@ -3,7 +3,9 @@
 use std::cell::RefCell;
 use std::path::Path;
 
-use crate::parse::{find_child_by_kind, node_to_location, LanguageParser, ParseResult};
+use crate::parse::{
+    find_child_by_kind, node_to_location, reference_kind, LanguageParser, ParseResult,
+};
 use crate::{Reference, Symbol, SymbolKind, Visibility};
 
 // Thread-local parser reuse - avoids creating a new parser per file
@@ -37,6 +39,17 @@ impl LanguageParser for SwiftParser {
 
             extract_recursive(&root, source.as_bytes(), file, &mut result, None, max_depth);
 
+            crate::parse::capture_lambdas(
+                &root,
+                source.as_bytes(),
+                file,
+                &["lambda_literal"],
+                "swift",
+                &mut result,
+            );
+
+            result.metrics = crate::metrics::measure(&root, &result.symbols);
+
             result
         })
     }
@@ -614,6 +627,7 @@ fn extract_recursive(
                     result.references.push(Reference {
                         name: name.to_string(),
                         location: node_to_location(file, &id),
+                        kind: reference_kind(&id),
                     });
                 }
             }
@@ -634,6 +648,7 @@ fn extract_recursive(
                             result.references.push(Reference {
                                 name: name.to_string(),
                                 location: node_to_location(file, &callee),
+                                kind: reference_kind(&callee),
                             });
                         }
                     }
@@ -644,6 +659,7 @@ fn extract_recursive(
                             result.references.push(Reference {
                                 name: name.to_string(),
                                 location: node_to_location(file, &callee),
+                                kind: reference_kind(&callee),
                             });
                         }
                     }
@@ -662,6 +678,7 @@ fn extract_recursive(
                         result.references.push(Reference {
                             name: name.to_string(),
                             location: node_to_location(file, node),
+                            kind: reference_kind(node),
                         });
                     }
                 }
@@ -675,6 +692,7 @@ fn extract_recursive(
                     result.references.push(Reference {
                         name: name.to_string(),
                         location: node_to_location(file, node),
+                        kind: reference_kind(node),
                     });
                 }
             }
@@ -3,7 +3,9 @@
 use std::cell::RefCell;
 use std::path::Path;
 
-use crate::parse::{find_child_by_kind, node_to_location, LanguageParser, ParseResult};
+use crate::parse::{
+    find_child_by_kind, node_to_location, reference_kind, LanguageParser, ParseResult,
+};
 use crate::{Reference, Symbol, SymbolKind, Visibility};
 
 // Thread-local parser reuse - avoids creating a new parser per file
@@ -89,6 +91,17 @@ impl LanguageParser for TypeScriptParser {
 
             extract_recursive(&root, source.as_bytes(), file, &mut result, None, max_depth);
 
+            crate::parse::capture_lambdas(
+                &root,
+                source.as_bytes(),
+                file,
+                &["arrow_function", "function_expression"],
+                "typescript",
+                &mut result,
+            );
+
+            result.metrics = crate::metrics::measure(&root, &result.symbols);
+
             result
         })
     }
@@ -278,6 +291,18 @@ fn extract_recursive(
                         signature: None,
                     });
 
+                    // The extends and implements clauses are references too
+                    if let Some(heritage) = find_child_by_kind(node, "class_heritage") {
+                        extract_recursive(
+                            &heritage,
+                            source,
+                            file,
+                            result,
+                            parent_path,
+                            max_depth - 1,
+                        );
+                    }
+
                     // Recurse into class body
                     if let Some(body) = node.child_by_field_name("body") {
                         extract_class_body(&body, source, file, result, &qualified, max_depth - 1);
@@ -490,6 +515,7 @@ fn extract_recursive(
                     result.references.push(Reference {
                         name: name.to_string(),
                         location: node_to_location(file, node),
+                        kind: reference_kind(node),
                     });
                 }
             }
@@ -502,6 +528,7 @@ fn extract_recursive(
                     result.references.push(Reference {
                         name: name.to_string(),
                         location: node_to_location(file, node),
+                        kind: reference_kind(node),
                     });
                 }
             }
@@ -0,0 +1,98 @@
+//! Layering rules between parts of the tree.
+//!
+//! A [`LayerRule`] says files matching `from` must not depend on files
+//! matching `to`, e.g. `src/Domain/*` must not depend on `src/Web/*`. Rules
+//! are recorded in the index (see [`SqliteIndex::add_layer_rule`]) and
+//! checked against file dependencies from resolved references (see
+//! [`SqliteIndex::file_dependencies`]).
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::config::wildcard_match;
+use crate::db::LayerRule;
+use crate::SqliteIndex;
+
+/// A file dependency that breaks a layering rule.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Violation {
+    pub rule: LayerRule,
+    /// The file that shouldn't depend on `to`, relative to the root
+    pub from: PathBuf,
+    /// The file it depends on, relative to the root
+    pub to: PathBuf,
+}
+
+/// Check the recorded rules against `index`. Paths in rules and in the
+/// result are relative to `root`.
+pub fn check(index: &SqliteIndex, root: &Path) -> Result<Vec<Violation>> {
+    Ok(violations(
+        &index.layer_rules()?,
+        &index.file_dependencies()?,
+        root,
+    ))
+}
+
+/// The dependencies (referencing file, defining file) that break `rules`.
+#[must_use]
+pub fn violations(rules: &[LayerRule], deps: &[(PathBuf, PathBuf)], root: &Path) -> Vec<Violation> {
+    let relative = |path: &Path| path.strip_prefix(root).unwrap_or(path).to_path_buf();
+    let matches = |pattern: &str, path: &Path| {
+        wildcard_match(pattern, &path.to_string_lossy().replace('\\', "/"))
+    };
+
+    let mut found = Vec::new();
+    for (from, to) in deps {
+        let (from, to) = (relative(from), relative(to));
+        for rule in rules {
+            if matches(&rule.from, &from) && matches(&rule.to, &to) {
+                found.push(Violation {
+                    rule: rule.clone(),
+                    from: from.clone(),
+                    to: to.clone(),
+                });
+            }
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(from: &str, to: &str) -> LayerRule {
+        LayerRule {
+            from: from.to_string(),
+            to: to.to_string(),
+        }
+    }
+
+    #[test]
+    fn reports_dependencies_across_forbidden_layers() {
+        let root = Path::new("/repo");
+        let deps = vec![
+            (
+                PathBuf::from("/repo/src/Domain/Order.fs"),
+                PathBuf::from("/repo/src/Web/Routes.fs"),
+            ),
+            (
+                PathBuf::from("/repo/src/Web/Routes.fs"),
+                PathBuf::from("/repo/src/Domain/Order.fs"),
+            ),
+        ];
+        let rules = vec![rule("src/Domain/*", "src/Web/*")];
+
+        assert_eq!(
+            violations(&rules, &deps, root),
+            vec![Violation {
+                rule: rules[0].clone(),
+                from: PathBuf::from("src/Domain/Order.fs"),
+                to: PathBuf::from("src/Web/Routes.fs"),
+            }]
+        );
+        assert!(violations(&[rule("src/Data/*", "src/Web/*")], &deps, root).is_empty());
+    }
+}
@@ -47,37 +47,69 @@
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
+pub mod affected;
 pub mod batch;
+pub mod check;
+pub mod compare;
+pub mod conditional;
 pub mod config;
+pub mod context;
+pub mod cycles;
 pub mod db;
+pub mod dependencies;
+pub mod error_events;
+pub mod export;
 pub mod external_index;
+pub mod federation;
 pub mod fsproj;
 pub mod fuzzy;
 pub mod git;
+pub mod grep;
+pub mod hierarchy;
+pub mod hotspots;
+pub mod impact;
 pub mod index;
 pub mod languages;
+pub mod layers;
+pub mod meta;
+pub mod metrics;
+pub mod namespace;
+pub mod organize;
+pub mod owners;
+pub mod pack;
 pub mod parse;
 pub mod pidfile;
 pub mod ranking;
+pub mod rename;
 pub mod resolve;
+pub mod sourcemap;
 pub mod spider;
 pub mod stacktrace;
+pub mod string_refs;
+pub mod subprocess;
+pub mod test_map;
 pub mod type_cache;
 pub mod watch;
+pub mod write_lock;
 
 // Re-export main types
 pub use db::SqliteIndex;
 pub use fsproj::{find_fsproj_files, parse_fsproj, FsprojInfo};
-pub use index::{CodeIndex, Reference};
+pub use index::{CodeIndex, Confidence, ModuleAlias, Reference, ReferenceKind};
 pub use parse::{extract_symbols, ParseWarning, SyntaxError};
 pub use ranking::{DetailLevel, RankedSymbol, RankingConfig};
 pub use resolve::ResolveResult;
-pub use stacktrace::{parse_stacktrace, StackFrame, StacktraceLanguage, StacktraceResult};
+pub use stacktrace::{
+    parse_stacktrace, ChainedException, GeneratedPosition, StackFrame, StacktraceLanguage,
+    StacktraceResult, TraceBlock, TraceSplitter,
+};
 pub use type_cache::{MemberKind, TypeCache, TypeCacheSchema, TypeMember, TypedSymbol};
 
 /// A location in source code (file, line, column) with start and end positions.
 ///
-/// All positions are 1-indexed to match editor conventions.
+/// All positions are 1-indexed to match editor conventions. Locations
+/// order by file path, compared as text the way SQLite orders the index,
+/// then by position; results are listed in this order.
 ///
 /// # Examples
 ///
@@ -97,6 +129,7 @@ pub use type_cache::{MemberKind, TypeCache, TypeCacheSchema, TypeMember, TypedSy
 ///     10, 15,  // end line, column
 /// );
 /// assert_eq!(span.end_column, 15);
+/// assert!(loc < span);
 /// ```
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Location {
@@ -130,6 +163,24 @@ impl Location {
     }
 }
 
+impl Ord for Location {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.file
+            .as_os_str()
+            .cmp(other.file.as_os_str())
+            .then(self.line.cmp(&other.line))
+            .then(self.column.cmp(&other.column))
+            .then(self.end_line.cmp(&other.end_line))
+            .then(self.end_column.cmp(&other.end_column))
+    }
+}
+
+impl PartialOrd for Location {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 /// The kind of symbol (function, type, module, etc.)
 ///
 /// # Examples
@@ -180,11 +231,45 @@ impl SymbolKind {
     /// Returns true if this symbol kind can be a caller (i.e., contains executable code).
     ///
     /// Only Functions and Members can contain code that calls other functions.
-    /// Values, Types, Modules, etc. cannot be callers.
+    /// Values, Types, Modules, etc. cannot be callers. Constructors are
+    /// Functions or Members too; see [`Symbol::is_constructor`].
     #[must_use]
     pub const fn is_callable(self) -> bool {
         matches!(self, SymbolKind::Function | SymbolKind::Member)
     }
+
+    /// Returns true if values of this kind are built by calling it or one of
+    /// its constructors, so creating one counts as a call.
+    #[must_use]
+    pub const fn is_instantiable(self) -> bool {
+        matches!(
+            self,
+            SymbolKind::Class | SymbolKind::Record | SymbolKind::Union | SymbolKind::Type
+        )
+    }
+}
+
+impl std::str::FromStr for SymbolKind {
+    type Err = String;
+
+    /// Parse a kind name in any case (`Function`, `function`).
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "module" => Ok(SymbolKind::Module),
+            "function" => Ok(SymbolKind::Function),
+            "value" => Ok(SymbolKind::Value),
+            "type" => Ok(SymbolKind::Type),
+            "record" => Ok(SymbolKind::Record),
+            "union" => Ok(SymbolKind::Union),
+            "interface" => Ok(SymbolKind::Interface),
+            "class" => Ok(SymbolKind::Class),
+            "member" => Ok(SymbolKind::Member),
+            _ => Err(format!(
+                "unknown symbol kind '{}' (expected module, function, value, type, record, union, interface, class or member)",
+                s
+            )),
+        }
+    }
 }
 
 /// Visibility of a symbol
@@ -196,6 +281,86 @@ pub enum Visibility {
     Private,
 }
 
+impl std::fmt::Display for Visibility {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(db::visibility_to_str(*self))
+    }
+}
+
+impl std::str::FromStr for Visibility {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "public" => Ok(Visibility::Public),
+            "internal" => Ok(Visibility::Internal),
+            "private" => Ok(Visibility::Private),
+            _ => Err(format!(
+                "unknown visibility '{}' (expected public, internal or private)",
+                s
+            )),
+        }
+    }
+}
+
+/// The bare name of an attribute as written: `[<System.ObsoleteAttribute("x")>]`
+/// is `Obsolete`.
+pub(crate) fn attribute_name(attribute: &str) -> &str {
+    let name = attribute
+        .trim_start_matches(['@', '#', '[', '<'])
+        .split(['(', ']', '>', ' '])
+        .next()
+        .unwrap_or_default();
+    let name = name.rsplit(['.', ':']).next().unwrap_or(name);
+    name.strip_suffix("Attribute").unwrap_or(name)
+}
+
+/// The bare name of an implemented type as recorded: `System.IComparable<T>`,
+/// `std::fmt::Display` and `\\App\\Contracts\\Jsonable` are `IComparable`,
+/// `Display` and `Jsonable`.
+pub(crate) fn type_name(name: &str) -> &str {
+    let name = name.split(['<', '[', '(']).next().unwrap_or(name).trim();
+    name.rsplit(['.', ':', '\\', '/']).next().unwrap_or(name)
+}
+
+/// An HTTP route a handler is mapped to by an attribute or decorator.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Route {
+    /// Upper-case HTTP method, when the attribute names one
+    pub method: Option<String>,
+    /// Path template as written (`/users/{id}`); empty for `[HttpGet]`
+    pub path: String,
+}
+
+/// HTTP methods route attributes are named after.
+const HTTP_METHODS: [&str; 7] = ["get", "post", "put", "delete", "patch", "head", "options"];
+
+/// The route an attribute maps its symbol to: `@app.route("/x")`,
+/// `@GetMapping("/x")`, `[HttpGet("{id}")]`, `@router.post("/x")`, ...
+fn attribute_route(attribute: &str) -> Option<Route> {
+    let name = attribute_name(attribute).to_ascii_lowercase();
+    let path = attribute
+        .split_once('(')
+        .and_then(|(_, args)| args.split(['"', '\'']).nth(1))
+        .map(str::to_string);
+    // Framework-specific spellings need no path; `get`/`route` alone do
+    let bare = name
+        .strip_prefix("http")
+        .or_else(|| name.strip_suffix("mapping"));
+    let verb = bare.unwrap_or(&name);
+    let method = HTTP_METHODS
+        .contains(&verb)
+        .then(|| verb.to_ascii_uppercase());
+    if method.is_none() && !matches!(verb, "route" | "request") {
+        return None;
+    }
+    let path = match bare {
+        Some(_) => path.unwrap_or_default(),
+        None => path?,
+    };
+    Some(Route { method, path })
+}
+
 fn default_language() -> String {
     "fsharp".to_string()
 }
@@ -304,12 +469,107 @@ impl Symbol {
         }
     }
 
+    /// Whether an attribute marks the symbol as deprecated (`[<Obsolete>]`,
+    /// `@Deprecated`, `#[deprecated]`, `@deprecated`, ...).
+    #[must_use]
+    pub fn is_deprecated(&self) -> bool {
+        self.has_attribute("deprecated") || self.has_attribute("obsolete")
+    }
+
+    /// Whether the symbol carries an attribute, annotation or decorator
+    /// named `name`, ignoring case, namespaces, arguments and an
+    /// `Attribute` suffix: `Obsolete` matches `[<System.Obsolete("...")>]`.
+    #[must_use]
+    pub fn has_attribute(&self, name: &str) -> bool {
+        let wanted = attribute_name(name);
+        self.attributes
+            .iter()
+            .flatten()
+            .any(|attribute| attribute_name(attribute).eq_ignore_ascii_case(wanted))
+    }
+
+    /// Stable GUID of the symbol, the same across file moves and reindexing
+    /// (see [`db::symbol_guid`]).
+    #[must_use]
+    pub fn guid(&self) -> String {
+        db::symbol_guid(&self.language, &self.qualified, self.signature.as_deref())
+    }
+
+    /// Whether the symbol is a constructor or initializer: a callable named
+    /// after its type (C#, Java, C++, Kotlin) or by its language's
+    /// convention (`new`, `__init__`, `initialize`, `init`, `constructor`,
+    /// `__construct`, Go's `NewType`).
+    #[must_use]
+    pub fn is_constructor(&self) -> bool {
+        if !self.kind.is_callable() {
+            return false;
+        }
+        let name = self.name.as_str();
+        match self.language.as_str() {
+            "rust" | "fsharp" | "haxe" => name == "new",
+            "python" => matches!(name, "__init__" | "__new__"),
+            "ruby" => name == "initialize",
+            "php" => name == "__construct",
+            "javascript" | "typescript" => name == "constructor",
+            "swift" => name == "init",
+            "objc" => name.starts_with("init"),
+            "go" => name
+                .strip_prefix("New")
+                .is_some_and(|t| t.starts_with(char::is_uppercase)),
+            "kotlin" if name == "constructor" => true,
+            _ => self
+                .qualified
+                .strip_suffix(name)
+                .filter(|owner| owner.ends_with(['.', ':']))
+                .map(|owner| owner.trim_end_matches(['.', ':']))
+                .is_some_and(|owner| owner.rsplit(['.', ':']).next() == Some(name)),
+        }
+    }
+
+    /// Whether the symbol implements or derives `interface`, comparing bare
+    /// names (see [`type_name`]) so a qualified or generic name on either
+    /// side matches: `IComparable` matches `System.IComparable<Order>`.
+    #[must_use]
+    pub fn implements_type(&self, interface: &str) -> bool {
+        let wanted = type_name(interface);
+        self.implements
+            .iter()
+            .flatten()
+            .any(|implemented| type_name(implemented) == wanted)
+    }
+
+    /// HTTP routes the symbol handles, from its route attributes.
+    #[must_use]
+    pub fn routes(&self) -> Vec<Route> {
+        self.attributes
+            .iter()
+            .flatten()
+            .filter_map(|attribute| attribute_route(attribute))
+            .collect()
+    }
+
     /// Create a symbol with a parent class/type
     pub fn with_parent(mut self, parent: Option<String>) -> Self {
         self.parent = parent;
         self
     }
 
+    /// Whether this is a synthetic symbol for a lambda or closure (see
+    /// [`crate::parse::capture_lambdas`]), named `<lambda1>`, `<lambda2>`, ...
+    pub fn is_lambda(&self) -> bool {
+        self.name.starts_with("<lambda") && self.name.ends_with('>')
+    }
+
+    /// The symbol a lambda is written in, by qualified name: `main` for
+    /// `main.<lambda1>`. `None` for anything that isn't a lambda.
+    pub fn lambda_owner(&self) -> Option<&str> {
+        if !self.is_lambda() {
+            return None;
+        }
+        let owner = self.qualified.strip_suffix(&self.name)?;
+        owner.strip_suffix("::").or_else(|| owner.strip_suffix('.'))
+    }
+
     /// Create a symbol with mixins (include/extend/prepend)
     pub fn with_mixins(mut self, mixins: Option<Vec<String>>) -> Self {
         self.mixins = mixins;
@@ -359,8 +619,31 @@ pub enum IndexError {
     #[error("Symbol not found: {0}")]
     SymbolNotFound(String),
 
+    #[error("Unknown project: {0}")]
+    ProjectNotFound(String),
+
     #[error("Database error: {0}")]
     DatabaseError(#[from] rusqlite::Error),
+
+    #[error("Index schema v{found} isn't supported by this version of rocketindex (v{supported})")]
+    IncompatibleSchema { found: u32, supported: u32 },
+
+    #[error("Failed to migrate index schema from v{from} to v{to}: {message}")]
+    MigrationFailed { from: u32, to: u32, message: String },
+
+    #[error("Index {0} is being updated by another process")]
+    IndexBusy(PathBuf),
+}
+
+impl IndexError {
+    /// Whether the index on disk can't be used as is and has to be rebuilt.
+    #[must_use]
+    pub fn needs_rebuild(&self) -> bool {
+        matches!(
+            self,
+            IndexError::IncompatibleSchema { .. } | IndexError::MigrationFailed { .. }
+        )
+    }
 }
 
 pub type Result<T> = std::result::Result<T, IndexError>;
@@ -410,6 +693,97 @@ mod tests {
         assert!(!SymbolKind::Class.is_callable());
     }
 
+    #[test]
+    fn test_symbol_is_constructor() {
+        let callable = |name: &str, qualified: &str, language: &str| {
+            Symbol::new(
+                name.to_string(),
+                qualified.to_string(),
+                SymbolKind::Function,
+                Location::new(PathBuf::from("src/a"), 1, 1),
+                Visibility::Public,
+                language.to_string(),
+            )
+        };
+        assert!(callable("new", "shapes::Square::new", "rust").is_constructor());
+        assert!(callable("__init__", "shapes.Square.__init__", "python").is_constructor());
+        assert!(callable("initialize", "Square#initialize", "ruby").is_constructor());
+        assert!(callable("Square", "App.Square.Square", "csharp").is_constructor());
+        assert!(callable("Square", "shapes::Square::Square", "cpp").is_constructor());
+        assert!(callable("NewSquare", "shapes.NewSquare", "go").is_constructor());
+
+        assert!(!callable("area", "shapes::Square::area", "rust").is_constructor());
+        assert!(!callable("Square", "App.Square", "csharp").is_constructor());
+        assert!(!callable("Newline", "fmt.Newline", "go").is_constructor());
+        let mut class = callable("new", "Square.new", "rust");
+        class.kind = SymbolKind::Class;
+        assert!(!class.is_constructor());
+        assert!(SymbolKind::Class.is_instantiable());
+        assert!(!SymbolKind::Interface.is_instantiable());
+    }
+
+    #[test]
+    fn test_symbol_is_deprecated() {
+        let symbol = |attributes: &[&str]| {
+            Symbol::new(
+                "old".to_string(),
+                "Api.old".to_string(),
+                SymbolKind::Function,
+                Location::new(PathBuf::from("api.fs"), 1, 1),
+                Visibility::Public,
+                "fsharp".to_string(),
+            )
+            .with_attributes(Some(attributes.iter().map(|a| a.to_string()).collect()))
+        };
+        assert!(symbol(&["Obsolete"]).is_deprecated());
+        assert!(symbol(&["@Deprecated"]).is_deprecated());
+        assert!(symbol(&["#[deprecated(note = \"use new\")]"]).is_deprecated());
+        assert!(symbol(&["System.ObsoleteAttribute(\"x\")"]).is_deprecated());
+        assert!(!symbol(&["@Override"]).is_deprecated());
+        assert!(!symbol(&[]).is_deprecated());
+    }
+
+    #[test]
+    fn test_symbol_routes() {
+        let routes = |attribute: &str| {
+            Symbol::new(
+                "show".to_string(),
+                "Users.show".to_string(),
+                SymbolKind::Function,
+                Location::new(PathBuf::from("users.py"), 1, 1),
+                Visibility::Public,
+                "python".to_string(),
+            )
+            .with_attributes(Some(vec![attribute.to_string()]))
+            .routes()
+        };
+        let route = |method: Option<&str>, path: &str| {
+            vec![Route {
+                method: method.map(str::to_string),
+                path: path.to_string(),
+            }]
+        };
+        assert_eq!(
+            routes("@app.route(\"/users/<id>\")"),
+            route(None, "/users/<id>")
+        );
+        assert_eq!(
+            routes("@router.get('/users/{id}')"),
+            route(Some("GET"), "/users/{id}")
+        );
+        assert_eq!(
+            routes("@PostMapping(\"/users\")"),
+            route(Some("POST"), "/users")
+        );
+        assert_eq!(
+            routes("HttpDelete(\"{id}\")"),
+            route(Some("DELETE"), "{id}")
+        );
+        assert_eq!(routes("[HttpGet]"), route(Some("GET"), ""));
+        assert!(routes("@property").is_empty());
+        assert!(routes("@cache.get").is_empty());
+    }
+
     #[test]
     fn test_symbol_creation() {
         let sym = Symbol::new(
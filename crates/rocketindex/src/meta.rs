@@ -0,0 +1,397 @@
+//! Team metadata kept outside the index: a committable JSON file.
+//!
+//! The index is a cache anyone can delete and rebuild, but some of what's
+//! in it was recorded by hand: rename aliases (see
+//! [`SqliteIndex::add_alias`]), notes on symbols, watchlists and layering
+//! rules (see [`crate::layers`]). `rkt meta export` writes those to
+//! [`META_FILE`] at the workspace root so they can be committed, and
+//! `rkt meta import` merges them back into a fresh index.
+//!
+//! Notes and watchlist entries are keyed by the GUID (see
+//! [`crate::db::symbol_guid`]) of the symbol they're about, with its
+//! qualified name alongside for readers and as a fallback: on import a
+//! symbol is looked up by GUID first, then by name, so entries follow a
+//! symbol through a rename and survive a signature change.
+
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::db::LayerRule;
+use crate::{SqliteIndex, Symbol};
+
+/// Default file name, at the workspace root next to `.rocketindex.toml`.
+pub const META_FILE: &str = ".rocketindex-meta.json";
+
+/// Version of the file format. Files from a newer version are refused
+/// rather than half imported; version 1 files (aliases only) still import.
+pub const VERSION: u32 = 2;
+
+/// The contents of a metadata file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Meta {
+    pub version: u32,
+    /// Rename aliases, by old name
+    #[serde(default)]
+    pub aliases: Vec<AliasEntry>,
+    /// Notes on symbols
+    #[serde(default)]
+    pub notes: Vec<NoteEntry>,
+    /// Symbols on watchlists
+    #[serde(default)]
+    pub watchlists: Vec<WatchEntry>,
+    /// Layering rules
+    #[serde(default)]
+    pub layers: Vec<LayerRule>,
+}
+
+/// An "old name → new name" alias with the GUID of the symbol it leads to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AliasEntry {
+    /// Qualified name the symbol used to have
+    pub old: String,
+    /// Qualified name it has now
+    pub new: String,
+    /// GUID of the symbol named `new`, if it was indexed at export. Used to
+    /// find the symbol if `new` is no longer its name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub guid: Option<String>,
+}
+
+/// A note on a symbol.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NoteEntry {
+    /// GUID of the symbol
+    pub guid: String,
+    /// Its qualified name at export
+    pub symbol: String,
+    pub text: String,
+}
+
+/// A symbol on a watchlist.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WatchEntry {
+    pub list: String,
+    /// GUID of the symbol
+    pub guid: String,
+    /// Its qualified name at export
+    pub symbol: String,
+}
+
+/// What [`import`] did.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ImportReport {
+    /// Entries recorded or changed
+    pub added: usize,
+    /// Entries the index already had
+    pub unchanged: usize,
+    /// Names of symbols that aren't indexed, by GUID or by name (their
+    /// entries are recorded anyway)
+    pub unresolved: Vec<String>,
+}
+
+/// The symbol an entry is about: the one with its GUID if there is one,
+/// else the one with its name.
+fn resolve(index: &SqliteIndex, guid: Option<&str>, name: &str) -> Result<Option<Symbol>> {
+    if let Some(symbol) = guid.map(|g| index.find_by_guid(g)).transpose()?.flatten() {
+        return Ok(Some(symbol));
+    }
+    Ok(index.find_by_qualified(name)?)
+}
+
+/// The metadata recorded in `index`.
+pub fn export(index: &SqliteIndex) -> Result<Meta> {
+    let aliases = index
+        .aliases()?
+        .into_iter()
+        .map(|alias| {
+            let guid = index.find_by_qualified(&alias.new)?.map(|s| s.guid());
+            Ok(AliasEntry {
+                old: alias.old,
+                new: alias.new,
+                guid,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    // GUIDs recorded in the index may predate a signature change; export the
+    // current one where the symbol can still be found.
+    let notes = index
+        .notes()?
+        .into_iter()
+        .map(|note| {
+            let current = resolve(index, Some(&note.guid), &note.qualified)?;
+            Ok(NoteEntry {
+                guid: current.as_ref().map_or(note.guid, Symbol::guid),
+                symbol: current.map_or(note.qualified, |s| s.qualified),
+                text: note.text,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let watchlists = index
+        .watchlists(None)?
+        .into_iter()
+        .map(|watched| {
+            let current = resolve(index, Some(&watched.guid), &watched.qualified)?;
+            Ok(WatchEntry {
+                list: watched.list,
+                guid: current.as_ref().map_or(watched.guid, Symbol::guid),
+                symbol: current.map_or(watched.qualified, |s| s.qualified),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Meta {
+        version: VERSION,
+        aliases,
+        notes,
+        watchlists,
+        layers: index.layer_rules()?,
+    })
+}
+
+/// Merge `meta` into `index`. Alias entries in the file win over what the
+/// index has for the same old name; nothing is removed.
+pub fn import(index: &SqliteIndex, meta: &Meta) -> Result<ImportReport> {
+    if meta.version > VERSION {
+        bail!(
+            "metadata file version {} is newer than this rkt supports ({})",
+            meta.version,
+            VERSION
+        );
+    }
+    let mut report = ImportReport::default();
+    let mut count = |added: bool| {
+        if added {
+            report.added += 1;
+        } else {
+            report.unchanged += 1;
+        }
+    };
+    let mut unresolved = Vec::new();
+
+    let existing = index.aliases()?;
+    for entry in &meta.aliases {
+        // Follow the symbol by GUID if it was renamed again since export
+        let new = match resolve(index, entry.guid.as_deref(), &entry.new)? {
+            Some(symbol) if index.find_by_qualified(&entry.new)?.is_none() => symbol.qualified,
+            Some(_) => entry.new.clone(),
+            None => {
+                unresolved.push(entry.old.clone());
+                entry.new.clone()
+            }
+        };
+        let known = existing
+            .iter()
+            .any(|alias| alias.old == entry.old && alias.new == new);
+        if !known {
+            index.add_alias(&entry.old, &new)?;
+        }
+        count(!known);
+    }
+
+    let existing = index.notes()?;
+    for entry in &meta.notes {
+        let (guid, qualified) = match resolve(index, Some(&entry.guid), &entry.symbol)? {
+            Some(symbol) => (symbol.guid(), symbol.qualified),
+            None => {
+                unresolved.push(entry.symbol.clone());
+                (entry.guid.clone(), entry.symbol.clone())
+            }
+        };
+        let known = existing
+            .iter()
+            .any(|note| (note.guid == guid || note.guid == entry.guid) && note.text == entry.text);
+        if !known {
+            index.add_note(&guid, &qualified, &entry.text)?;
+        }
+        count(!known);
+    }
+
+    for entry in &meta.watchlists {
+        let (guid, qualified) = match resolve(index, Some(&entry.guid), &entry.symbol)? {
+            Some(symbol) => (symbol.guid(), symbol.qualified),
+            None => {
+                unresolved.push(entry.symbol.clone());
+                (entry.guid.clone(), entry.symbol.clone())
+            }
+        };
+        count(index.watch_symbol(&entry.list, &guid, &qualified)?);
+    }
+
+    for rule in &meta.layers {
+        count(index.add_layer_rule(rule)?);
+    }
+
+    unresolved.dedup();
+    report.unresolved = unresolved;
+    Ok(report)
+}
+
+/// Read a metadata file.
+pub fn read(path: &Path) -> Result<Meta> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&text).with_context(|| format!("Invalid metadata in {}", path.display()))
+}
+
+/// Write a metadata file, pretty-printed with a trailing newline so it
+/// diffs well.
+pub fn write(path: &Path, meta: &Meta) -> Result<()> {
+    let mut text = serde_json::to_string_pretty(meta)?;
+    text.push('\n');
+    std::fs::write(path, text).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Location, Symbol, SymbolKind, Visibility};
+    use std::path::PathBuf;
+
+    fn symbol(qualified: &str) -> Symbol {
+        Symbol::new(
+            qualified.rsplit('.').next().unwrap().to_string(),
+            qualified.to_string(),
+            SymbolKind::Function,
+            Location::new(PathBuf::from("src/Billing.fs"), 3, 5),
+            Visibility::Public,
+            "fsharp".to_string(),
+        )
+    }
+
+    #[test]
+    fn round_trips_aliases_into_a_fresh_index() {
+        let index = SqliteIndex::in_memory().unwrap();
+        let charge = symbol("Billing.charge");
+        index.insert_symbol(&charge).unwrap();
+        index.add_alias("Billing.bill", "Billing.charge").unwrap();
+        index.add_alias("Legacy.pay", "Legacy.settle").unwrap();
+
+        let meta = export(&index).unwrap();
+        assert_eq!(
+            meta.aliases,
+            vec![
+                AliasEntry {
+                    old: "Billing.bill".to_string(),
+                    new: "Billing.charge".to_string(),
+                    guid: Some(charge.guid()),
+                },
+                AliasEntry {
+                    old: "Legacy.pay".to_string(),
+                    new: "Legacy.settle".to_string(),
+                    guid: None,
+                },
+            ]
+        );
+
+        let fresh = SqliteIndex::in_memory().unwrap();
+        fresh.insert_symbol(&charge).unwrap();
+        let report = import(&fresh, &meta).unwrap();
+        assert_eq!(report.added, 2);
+        assert_eq!(report.unresolved, vec!["Legacy.pay"]);
+        assert_eq!(fresh.aliases().unwrap().len(), 2);
+
+        // Importing again changes nothing
+        assert_eq!(import(&fresh, &meta).unwrap().unchanged, 2);
+
+        let newer = Meta {
+            version: VERSION + 1,
+            ..export(&fresh).unwrap()
+        };
+        assert!(import(&fresh, &newer).is_err());
+    }
+
+    #[test]
+    fn round_trips_notes_watchlists_and_layers() {
+        let index = SqliteIndex::in_memory().unwrap();
+        let charge = symbol("Billing.charge");
+        index.insert_symbol(&charge).unwrap();
+        index
+            .add_note(&charge.guid(), &charge.qualified, "Retries on timeout")
+            .unwrap();
+        index
+            .watch_symbol("payments", &charge.guid(), &charge.qualified)
+            .unwrap();
+        let layer = LayerRule {
+            from: "src/Domain/*".to_string(),
+            to: "src/Web/*".to_string(),
+        };
+        index.add_layer_rule(&layer).unwrap();
+
+        let meta = export(&index).unwrap();
+        assert_eq!(
+            meta.notes,
+            vec![NoteEntry {
+                guid: charge.guid(),
+                symbol: "Billing.charge".to_string(),
+                text: "Retries on timeout".to_string(),
+            }]
+        );
+        assert_eq!(meta.watchlists[0].guid, charge.guid());
+        assert_eq!(meta.layers, vec![layer]);
+
+        let fresh = SqliteIndex::in_memory().unwrap();
+        fresh.insert_symbol(&charge).unwrap();
+        let report = import(&fresh, &meta).unwrap();
+        assert_eq!(report.added, 3);
+        assert!(report.unresolved.is_empty());
+        assert_eq!(
+            fresh
+                .notes_for(&charge.guid(), &charge.qualified)
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(fresh.watchlists(Some("payments")).unwrap().len(), 1);
+
+        // Importing again changes nothing
+        assert_eq!(import(&fresh, &meta).unwrap().unchanged, 3);
+    }
+
+    #[test]
+    fn resolves_entries_by_guid_before_name() {
+        let index = SqliteIndex::in_memory().unwrap();
+        let charge = symbol("Billing.charge");
+        index.insert_symbol(&charge).unwrap();
+
+        // Names in the file are stale; the GUIDs still identify the symbol
+        let meta = Meta {
+            version: VERSION,
+            aliases: vec![AliasEntry {
+                old: "Billing.bill".to_string(),
+                new: "Billing.settle".to_string(),
+                guid: Some(charge.guid()),
+            }],
+            notes: vec![NoteEntry {
+                guid: charge.guid(),
+                symbol: "Billing.settle".to_string(),
+                text: "Retries on timeout".to_string(),
+            }],
+            watchlists: vec![WatchEntry {
+                list: "payments".to_string(),
+                guid: charge.guid(),
+                symbol: "Billing.settle".to_string(),
+            }],
+            layers: vec![],
+        };
+        let report = import(&index, &meta).unwrap();
+        assert!(report.unresolved.is_empty());
+        assert_eq!(index.aliases().unwrap()[0].new, "Billing.charge");
+        assert_eq!(index.notes().unwrap()[0].qualified, "Billing.charge");
+        assert_eq!(
+            index.watchlists(None).unwrap()[0].qualified,
+            "Billing.charge"
+        );
+    }
+
+    #[test]
+    fn reads_version_1_files() {
+        let meta: Meta =
+            serde_json::from_str(r#"{"version": 1, "aliases": [{"old": "A.b", "new": "A.c"}]}"#)
+                .unwrap();
+        let index = SqliteIndex::in_memory().unwrap();
+        let report = import(&index, &meta).unwrap();
+        assert_eq!(report.added, 1);
+        assert_eq!(report.unresolved, vec!["A.b"]);
+    }
+}
@@ -0,0 +1,385 @@
+//! Complexity metrics per file and per module.
+//!
+//! Each parser measures its file's syntax tree as it extracts symbols:
+//!
+//! - complexity: an approximate cyclomatic complexity, one per function or
+//!   method plus one per decision point (branch, loop, case, catch,
+//!   conditional expression, `&&` and `||`), summed over the file
+//! - max nesting: the deepest stack of branches, loops, matches and `try`
+//!   blocks, where an `else if` continues its chain rather than nesting
+//!
+//! Decision points are recognized by node kind across the grammars, which
+//! name them alike (`if_statement`, `if_expression`, `for_in_statement`),
+//! so the numbers are comparable between languages without being exact in
+//! any of them.
+//!
+//! The index keeps these per file (see [`crate::SqliteIndex::file_stats`]).
+//! Symbol counts and fan-in/fan-out (how many other files or modules
+//! reference this one, and how many it references) come from the symbols
+//! and references already indexed, and [`report`] rolls everything up by
+//! file or by module, a module being a directory.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::Symbol;
+
+/// Branches and loops: each is a decision point that also nests.
+const BRANCH_KINDS: &[&str] = &[
+    "if_statement",
+    "if_expression",
+    "if_let_expression",
+    "guard_statement",
+    "if",
+    "unless",
+    "elif_clause",
+    "elsif",
+    "else_if_clause",
+    "for_statement",
+    "for_in_statement",
+    "for_expression",
+    "for_range_loop",
+    "enhanced_for_statement",
+    "foreach_statement",
+    "for_each_statement",
+    "while_statement",
+    "while_expression",
+    "while",
+    "until",
+    "for",
+    "do_statement",
+    "do_while_statement",
+    "repeat_while_statement",
+    "loop_expression",
+];
+
+/// Blocks that nest without being a decision point themselves; their cases
+/// and catches are.
+const BLOCK_KINDS: &[&str] = &[
+    "switch_statement",
+    "switch_expression",
+    "match_expression",
+    "match_statement",
+    "when_expression",
+    "case",
+    "try_statement",
+    "try_expression",
+    "begin",
+];
+
+/// Decision points that don't nest.
+const DECISION_KINDS: &[&str] = &[
+    "case_clause",
+    "case_statement",
+    "switch_case",
+    "switch_section",
+    "switch_rule",
+    "switch_block_statement_group",
+    "expression_case",
+    "type_case",
+    "communication_case",
+    "match_arm",
+    "case_block",
+    "rule",
+    "when_entry",
+    "when",
+    "catch_clause",
+    "catch_block",
+    "except_clause",
+    "rescue",
+    "conditional_expression",
+    "ternary_expression",
+    "conditional",
+    "elvis_expression",
+];
+
+/// Short-circuit operators, as the grammars' anonymous tokens.
+const BOOLEAN_OPERATORS: &[&str] = &["&&", "||", "and", "or"];
+
+/// What the syntax tree of one file measures.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct FileMetrics {
+    /// Approximate cyclomatic complexity, summed over the file
+    pub complexity: u32,
+    /// Deepest nesting of branches, loops, matches and `try` blocks
+    pub max_nesting: u32,
+}
+
+/// Measure the syntax tree under `root`, whose functions and methods are
+/// among `symbols`.
+pub(crate) fn measure(root: &tree_sitter::Node, symbols: &[Symbol]) -> FileMetrics {
+    let functions = symbols.iter().filter(|s| s.kind.is_callable()).count() as u32;
+    let mut decisions = 0u32;
+    let mut max_nesting = 0u32;
+    // Nesting depth at each level of the walk, the current node's last
+    let mut depths = vec![0u32];
+
+    let mut cursor = root.walk();
+    'walk: loop {
+        let node = cursor.node();
+        let kind = node.kind();
+        let parent_depth = *depths.last().unwrap_or(&0);
+        let mut depth = parent_depth;
+        if !node.is_named() {
+            if BOOLEAN_OPERATORS.contains(&kind) {
+                decisions += 1;
+            }
+        } else if BRANCH_KINDS.contains(&kind) {
+            decisions += 1;
+            if !continues_chain(&node) {
+                depth += 1;
+            }
+        } else if BLOCK_KINDS.contains(&kind) {
+            depth += 1;
+        } else if DECISION_KINDS.contains(&kind) {
+            decisions += 1;
+        }
+        max_nesting = max_nesting.max(depth);
+
+        if cursor.goto_first_child() {
+            depths.push(depth);
+            continue;
+        }
+        while !cursor.goto_next_sibling() {
+            if !cursor.goto_parent() {
+                break 'walk;
+            }
+            depths.pop();
+        }
+    }
+
+    FileMetrics {
+        complexity: decisions + functions.max(1),
+        max_nesting,
+    }
+}
+
+/// Whether a branch is the `else if` (or `elif`) of an enclosing one.
+fn continues_chain(node: &tree_sitter::Node) -> bool {
+    if matches!(node.kind(), "elif_clause" | "elsif" | "else_if_clause") {
+        return true;
+    }
+    let Some(parent) = node.parent() else {
+        return false;
+    };
+    match parent.kind() {
+        "else_clause" | "else" => true,
+        // Grammars without an else node put the `else` token just before it
+        _ => node
+            .prev_sibling()
+            .is_some_and(|previous| previous.kind() == "else"),
+    }
+}
+
+/// How to roll files up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Grouping {
+    /// One row per file
+    #[default]
+    File,
+    /// One row per directory
+    Module,
+}
+
+impl std::str::FromStr for Grouping {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "file" => Ok(Grouping::File),
+            "module" => Ok(Grouping::Module),
+            _ => Err(format!(
+                "unknown grouping '{}' (expected file or module)",
+                s
+            )),
+        }
+    }
+}
+
+/// The file, or module (directory) for [`Grouping::Module`], that `file`
+/// counts toward, relative to `root` with `/` separators.
+#[must_use]
+pub fn group_name(file: &Path, root: &Path, grouping: Grouping) -> String {
+    let relative = file.strip_prefix(root).unwrap_or(file);
+    let path = match grouping {
+        Grouping::File => relative,
+        Grouping::Module => relative.parent().unwrap_or(Path::new("")),
+    };
+    match path.to_string_lossy() {
+        name if name.is_empty() => ".".to_string(),
+        name => name.replace('\\', "/"),
+    }
+}
+
+/// A file's metrics as stored in the index, with its symbol count.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileStats {
+    pub file: PathBuf,
+    pub symbols: usize,
+    pub metrics: FileMetrics,
+}
+
+/// Metrics of a file or module.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Metrics {
+    /// File, or directory for a module, relative to the workspace root
+    pub name: String,
+    pub files: usize,
+    pub symbols: usize,
+    pub complexity: u32,
+    pub max_nesting: u32,
+    /// Other files or modules that reference this one
+    pub fan_in: usize,
+    /// Other files or modules this one references
+    pub fan_out: usize,
+}
+
+/// Roll `files` up by `grouping`, counting fan-in and fan-out from
+/// `dependencies` (referencing file, referenced file). Most complex first.
+#[must_use]
+pub fn report(
+    files: &[FileStats],
+    dependencies: &[(PathBuf, PathBuf)],
+    root: &Path,
+    grouping: Grouping,
+) -> Vec<Metrics> {
+    let group_of = |file: &Path| group_name(file, root, grouping);
+
+    let mut groups: BTreeMap<String, Metrics> = BTreeMap::new();
+    for stats in files {
+        let name = group_of(&stats.file);
+        let group = groups.entry(name.clone()).or_insert_with(|| Metrics {
+            name,
+            files: 0,
+            symbols: 0,
+            complexity: 0,
+            max_nesting: 0,
+            fan_in: 0,
+            fan_out: 0,
+        });
+        group.files += 1;
+        group.symbols += stats.symbols;
+        group.complexity += stats.metrics.complexity;
+        group.max_nesting = group.max_nesting.max(stats.metrics.max_nesting);
+    }
+
+    let edges: BTreeSet<(String, String)> = dependencies
+        .iter()
+        .map(|(from, to)| (group_of(from), group_of(to)))
+        .filter(|(from, to)| from != to)
+        .collect();
+    for (from, to) in &edges {
+        if let Some(group) = groups.get_mut(from) {
+            group.fan_out += 1;
+        }
+        if let Some(group) = groups.get_mut(to) {
+            group.fan_in += 1;
+        }
+    }
+
+    let mut metrics: Vec<Metrics> = groups.into_values().collect();
+    metrics.sort_by(|a, b| {
+        b.complexity
+            .cmp(&a.complexity)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    metrics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn measured(file: &str, source: &str) -> FileMetrics {
+        crate::extract_symbols(Path::new(file), source, 500).metrics
+    }
+
+    #[test]
+    fn counts_decisions_and_nesting_across_languages() {
+        let python = "def ship(order):\n    if order.paid and order.items:\n        for item in order.items:\n            if item.fragile:\n                wrap(item)\n    elif order.retry:\n        retry(order)\n\ndef noop():\n    pass\n";
+        // 2 functions; if, and, for, if, elif
+        assert_eq!(
+            measured("ship.py", python),
+            FileMetrics {
+                complexity: 7,
+                max_nesting: 3
+            }
+        );
+
+        let rust = "fn classify(n: i32) -> &'static str {\n    if n < 0 {\n        \"negative\"\n    } else if n == 0 || n == 1 {\n        \"small\"\n    } else {\n        match n % 2 {\n            0 => \"even\",\n            _ => \"odd\",\n        }\n    }\n}\n";
+        // 1 function; if, else if, ||, two match arms
+        assert_eq!(
+            measured("classify.rs", rust),
+            FileMetrics {
+                complexity: 6,
+                max_nesting: 2
+            }
+        );
+
+        let typescript = "export function total(items: number[]): number {\n  let sum = 0;\n  for (const item of items) {\n    sum += item > 0 ? item : 0;\n  }\n  return sum;\n}\n";
+        // 1 function; for, ternary
+        assert_eq!(
+            measured("total.ts", typescript),
+            FileMetrics {
+                complexity: 3,
+                max_nesting: 1
+            }
+        );
+    }
+
+    #[test]
+    fn rolls_files_up_by_module() {
+        let root = Path::new("/ws");
+        let stats = |file: &str, symbols: usize, complexity: u32, max_nesting: u32| FileStats {
+            file: PathBuf::from(file),
+            symbols,
+            metrics: FileMetrics {
+                complexity,
+                max_nesting,
+            },
+        };
+        let files = [
+            stats("/ws/billing/charge.py", 3, 12, 4),
+            stats("/ws/billing/refund.py", 2, 5, 2),
+            stats("/ws/app.py", 1, 2, 1),
+        ];
+        let dependencies = [
+            (
+                PathBuf::from("/ws/app.py"),
+                PathBuf::from("/ws/billing/charge.py"),
+            ),
+            (
+                PathBuf::from("/ws/app.py"),
+                PathBuf::from("/ws/billing/refund.py"),
+            ),
+            (
+                PathBuf::from("/ws/billing/refund.py"),
+                PathBuf::from("/ws/billing/charge.py"),
+            ),
+        ];
+
+        let by_file = report(&files, &dependencies, root, Grouping::File);
+        assert_eq!(by_file[0].name, "billing/charge.py");
+        assert_eq!((by_file[0].fan_in, by_file[0].fan_out), (2, 0));
+        assert_eq!(by_file[2].name, "app.py");
+        assert_eq!((by_file[2].fan_in, by_file[2].fan_out), (0, 2));
+
+        let by_module = report(&files, &dependencies, root, Grouping::Module);
+        assert_eq!(
+            by_module[0],
+            Metrics {
+                name: "billing".to_string(),
+                files: 2,
+                symbols: 5,
+                complexity: 17,
+                max_nesting: 4,
+                fan_in: 1,
+                fan_out: 0,
+            }
+        );
+        assert_eq!(by_module[1].name, ".");
+        assert_eq!(by_module[1].fan_out, 1);
+    }
+}
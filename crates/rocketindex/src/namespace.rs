@@ -0,0 +1,132 @@
+//! Namespaces for F# and C# files that don't declare one.
+//!
+//! Without a `module`/`namespace` line every symbol of such a file would sit
+//! in the global namespace, and same-named helpers in different files would
+//! collide. The parsers use these heuristics instead:
+//!
+//! - F# follows the compiler: a file without a declaration (a script, or the
+//!   last file of an executable) is an implicit module named after the file.
+//! - C# follows the IDE convention for new files: the project's
+//!   `RootNamespace` (the project file's name unless set) followed by the
+//!   folders between the project and the file.
+
+use std::path::Path;
+
+/// The implicit module of an F# file: its name without the extension, with
+/// the first letter capitalized (`build.fsx` -> `Build`).
+pub fn implicit_fsharp_module(file: &Path) -> Option<String> {
+    let stem = file.file_stem()?.to_str()?;
+    let mut chars = stem.chars();
+    let first = chars.next()?;
+    Some(first.to_uppercase().chain(chars).collect())
+}
+
+/// The namespace the IDE would give a new C# file at `file`, if it is inside
+/// a project (the nearest directory above it with a `.csproj`).
+pub fn infer_csharp_namespace(file: &Path) -> Option<String> {
+    let mut folders = Vec::new();
+    let mut dir = file.parent()?;
+    loop {
+        if let Some(project) = find_project_file(dir, "csproj") {
+            let root_namespace = std::fs::read_to_string(&project)
+                .ok()
+                .and_then(|xml| root_namespace(&xml))
+                .or_else(|| project.file_stem()?.to_str().map(identifier))?;
+            folders.push(root_namespace);
+            folders.reverse();
+            return Some(folders.join("."));
+        }
+        // Repositories don't nest projects across their root
+        if dir.join(".git").exists() {
+            return None;
+        }
+        folders.push(identifier(dir.file_name()?.to_str()?));
+        dir = dir.parent()?;
+    }
+}
+
+fn find_project_file(dir: &Path, extension: &str) -> Option<std::path::PathBuf> {
+    std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .find(|path| path.extension().is_some_and(|ext| ext == extension) && path.is_file())
+}
+
+/// `<RootNamespace>` of an MSBuild project, if set.
+fn root_namespace(xml: &str) -> Option<String> {
+    let start = xml.find("<RootNamespace>")? + "<RootNamespace>".len();
+    let end = start + xml[start..].find("</RootNamespace>")?;
+    let value = xml[start..end].trim();
+    (!value.is_empty() && !value.contains('$')).then(|| value.to_string())
+}
+
+/// A folder or project name as a namespace segment: characters that can't
+/// appear in an identifier become `_`, as the IDE does.
+fn identifier(name: &str) -> String {
+    let mut segment: String = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '_' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if segment.starts_with(|c: char| c.is_ascii_digit()) {
+        segment.insert(0, '_');
+    }
+    segment
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_implicit_fsharp_module() {
+        assert_eq!(
+            implicit_fsharp_module(Path::new("scripts/build.fsx")).as_deref(),
+            Some("Build")
+        );
+        assert_eq!(
+            implicit_fsharp_module(Path::new("src/Program.fs")).as_deref(),
+            Some("Program")
+        );
+    }
+
+    #[test]
+    fn test_infer_csharp_namespace() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir(root.join(".git")).unwrap();
+        let project = root.join("src").join("Shop.Api");
+        fs::create_dir_all(project.join("Order Handlers")).unwrap();
+        fs::write(project.join("Shop.Api.csproj"), "<Project />").unwrap();
+
+        let file = project.join("Order Handlers").join("Checkout.cs");
+        assert_eq!(
+            infer_csharp_namespace(&file).as_deref(),
+            Some("Shop.Api.Order_Handlers")
+        );
+        assert_eq!(
+            infer_csharp_namespace(&project.join("Program.cs")).as_deref(),
+            Some("Shop.Api")
+        );
+
+        fs::write(
+            project.join("Shop.Api.csproj"),
+            "<Project><PropertyGroup><RootNamespace>Acme.Shop</RootNamespace></PropertyGroup></Project>",
+        )
+        .unwrap();
+        assert_eq!(
+            infer_csharp_namespace(&file).as_deref(),
+            Some("Acme.Shop.Order_Handlers")
+        );
+
+        // Outside any project
+        assert_eq!(infer_csharp_namespace(&root.join("Loose.cs")), None);
+    }
+}
@@ -0,0 +1,90 @@
+//! Organize `open` statements.
+//!
+//! Shared by the LSP's "Organize opens" code action and the MCP
+//! `organize_opens` tool, so both propose the same edit.
+
+/// Sorted replacement for a file's block of `open` statements.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrganizedOpens {
+    /// The sorted statements, one per line
+    pub text: String,
+    /// 0-indexed line of the first `open`
+    pub start_line: usize,
+    /// 0-indexed line of the last `open`
+    pub end_line: usize,
+    /// Byte length of the last `open` line; the replaced range ends there
+    pub end_column: usize,
+}
+
+/// Sort the `open` statements of `content`: shallower modules first, then
+/// alphabetically (case-insensitive).
+///
+/// Returns `None` when there are fewer than two opens or they are already
+/// in order.
+pub fn organize_opens(content: &str) -> Option<OrganizedOpens> {
+    let lines: Vec<&str> = content.lines().collect();
+
+    // Find all open statements and their locations
+    let opens: Vec<(usize, &str)> = lines
+        .iter()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let module_name = line.trim().strip_prefix("open ")?.trim();
+            Some((i, module_name))
+        })
+        .collect();
+
+    // Need at least 2 opens to organize
+    if opens.len() < 2 {
+        return None;
+    }
+
+    let start_line = opens.first()?.0;
+    let end_line = opens.last()?.0;
+
+    let current_order: Vec<&str> = opens.iter().map(|(_, name)| *name).collect();
+    let mut module_names = current_order.clone();
+    module_names.sort_by(|a, b| {
+        // Sort by depth first (fewer dots = higher priority)
+        let a_depth = a.matches('.').count();
+        let b_depth = b.matches('.').count();
+        a_depth
+            .cmp(&b_depth)
+            .then_with(|| a.to_lowercase().cmp(&b.to_lowercase()))
+    });
+
+    if current_order == module_names {
+        return None;
+    }
+
+    let text = module_names
+        .iter()
+        .map(|name| format!("open {}", name))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    Some(OrganizedOpens {
+        text,
+        start_line,
+        end_line,
+        end_column: lines[end_line].len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_organize_opens() {
+        let content = "module Test\n\nopen System.IO\nopen Zebra\n  open Apple\n\nlet x = 1";
+        let organized = organize_opens(content).unwrap();
+        assert_eq!(organized.text, "open Apple\nopen Zebra\nopen System.IO");
+        assert_eq!(organized.start_line, 2);
+        assert_eq!(organized.end_line, 4);
+        assert_eq!(organized.end_column, "  open Apple".len());
+
+        assert!(organize_opens("open Apple\nopen Zebra\n").is_none());
+        assert!(organize_opens("open Apple\n").is_none());
+    }
+}
@@ -0,0 +1,195 @@
+//! Code ownership: who to ask about a symbol.
+//!
+//! Two sources, reported side by side since they answer different
+//! questions:
+//!
+//! - `CODEOWNERS` (in `.github/`, the repository root or `docs/`, the first
+//!   found, as GitHub looks for it): the team or people responsible for the
+//!   file. Patterns follow gitignore syntax and the last matching rule wins.
+//! - `git blame` over the symbol's lines: who actually wrote it, most lines
+//!   first.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use serde::Serialize;
+
+use crate::git::{self, Contributor};
+use crate::{SqliteIndex, Symbol};
+
+/// Where `CODEOWNERS` may live, relative to the repository root, in the
+/// order GitHub checks them.
+pub const CODEOWNERS_PATHS: &[&str] = &[".github/CODEOWNERS", "CODEOWNERS", "docs/CODEOWNERS"];
+
+/// The rules of a `CODEOWNERS` file.
+#[derive(Debug)]
+pub struct CodeOwners {
+    /// The repository root patterns are relative to
+    root: PathBuf,
+    /// The file the rules came from
+    file: PathBuf,
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug)]
+struct Rule {
+    pattern: String,
+    /// 1-indexed line in the file
+    line: usize,
+    owners: Vec<String>,
+    matcher: Gitignore,
+}
+
+/// The `CODEOWNERS` rule that applies to a file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OwnerRule {
+    /// `path:line` of the rule
+    pub source: String,
+    pub pattern: String,
+    /// Empty if the rule removes ownership
+    pub owners: Vec<String>,
+}
+
+/// Who owns and who wrote a symbol.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Ownership {
+    /// The last `CODEOWNERS` rule matching the symbol's file, if any
+    pub rule: Option<OwnerRule>,
+    /// Authors of the symbol's lines, most lines first; empty outside git
+    pub contributors: Vec<Contributor>,
+}
+
+impl CodeOwners {
+    /// Read the `CODEOWNERS` of the repository at `root`, if it has one.
+    pub fn load(root: &Path) -> Result<Option<Self>> {
+        for relative in CODEOWNERS_PATHS {
+            let file = root.join(relative);
+            if file.is_file() {
+                let text = std::fs::read_to_string(&file)
+                    .with_context(|| format!("Failed to read {}", file.display()))?;
+                return Ok(Some(Self::parse(root, &file, &text)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Parse `text`, the contents of `file`. Comments, blank lines and
+    /// patterns that aren't valid globs are skipped.
+    pub fn parse(root: &Path, file: &Path, text: &str) -> Self {
+        let rules = text
+            .lines()
+            .enumerate()
+            .filter_map(|(index, line)| {
+                let line_text = line.split(" #").next().unwrap_or(line).trim();
+                if line_text.is_empty() || line_text.starts_with('#') {
+                    return None;
+                }
+                let mut fields = line_text.split_whitespace();
+                let pattern = fields.next()?.to_string();
+                let mut builder = GitignoreBuilder::new(root);
+                builder.add_line(None, &pattern).ok()?;
+                Some(Rule {
+                    matcher: builder.build().ok()?,
+                    pattern,
+                    line: index + 1,
+                    owners: fields.map(String::from).collect(),
+                })
+            })
+            .collect();
+        Self {
+            root: root.to_path_buf(),
+            file: file.to_path_buf(),
+            rules,
+        }
+    }
+
+    /// The rule that decides who owns `path` (absolute, or relative to the
+    /// repository root): the last one matching it or a parent directory.
+    #[must_use]
+    pub fn rule_for(&self, path: &Path) -> Option<OwnerRule> {
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+        if relative.has_root() {
+            return None;
+        }
+        let rule = self.rules.iter().rev().find(|rule| {
+            rule.matcher
+                .matched_path_or_any_parents(relative, false)
+                .is_ignore()
+        })?;
+        let file = self.file.strip_prefix(&self.root).unwrap_or(&self.file);
+        Some(OwnerRule {
+            source: format!("{}:{}", file.display(), rule.line),
+            pattern: rule.pattern.clone(),
+            owners: rule.owners.clone(),
+        })
+    }
+}
+
+/// Who owns `symbol` according to `codeowners` and who wrote its lines.
+/// Blame is best effort: files outside git have no contributors.
+///
+/// Parsers that record only a symbol's name leave its span on one line, so
+/// the span is read from the index (see [`SqliteIndex::get_source`]).
+#[must_use]
+pub fn ownership(
+    codeowners: Option<&CodeOwners>,
+    index: &SqliteIndex,
+    symbol: &Symbol,
+) -> Ownership {
+    let location = &symbol.location;
+    let (start, end) = index
+        .get_source(symbol, 0)
+        .map_or((location.line, location.end_line), |source| {
+            (source.symbol_line, source.symbol_end_line)
+        });
+    Ownership {
+        rule: codeowners.and_then(|owners| owners.rule_for(&location.file)),
+        contributors: git::blame_contributors(&location.file, start, end).unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn last_matching_rule_wins() {
+        let root = Path::new("/ws");
+        let codeowners = CodeOwners::parse(
+            root,
+            Path::new("/ws/.github/CODEOWNERS"),
+            "# Default owners\n\
+             *                @acme/core\n\
+             \n\
+             /billing/        @acme/billing alice@example.com\n\
+             *.sql            @acme/dba  # schema changes\n\
+             billing/legacy/\n",
+        );
+        let owners = |path: &str| {
+            codeowners
+                .rule_for(Path::new(path))
+                .map(|rule| (rule.source, rule.owners))
+        };
+
+        assert_eq!(
+            owners("/ws/app.py"),
+            Some((
+                ".github/CODEOWNERS:2".to_string(),
+                vec!["@acme/core".to_string()]
+            ))
+        );
+        assert_eq!(
+            owners("/ws/billing/charge.py").unwrap().1,
+            vec!["@acme/billing", "alice@example.com"]
+        );
+        assert_eq!(owners("billing/schema.sql").unwrap().1, vec!["@acme/dba"]);
+        // A rule without owners leaves the file unowned
+        assert_eq!(
+            owners("/ws/billing/legacy/old.py"),
+            Some((".github/CODEOWNERS:6".to_string(), vec![]))
+        );
+        // Outside the repository nothing applies
+        assert_eq!(owners("/elsewhere/app.py"), None);
+    }
+}
@@ -0,0 +1,326 @@
+//! Read-only index distribution.
+//!
+//! A pack is a directory holding a frozen copy of the SQLite index
+//! ([`PACK_DB_NAME`]) and a JSON manifest ([`MANIFEST_NAME`]) describing it.
+//! Packs are built once (e.g. in CI), stored as artifacts, and opened
+//! immutable by agents that only ever query.
+//!
+//! ```no_run
+//! use rocketindex::{pack, SqliteIndex};
+//! use std::path::Path;
+//!
+//! let index = SqliteIndex::open(Path::new(".rocketindex/index.db")).unwrap();
+//! let manifest = pack::pack_index(&index, Path::new("dist/index-pack"), "1.2.0").unwrap();
+//! println!("{} symbols", manifest.symbols);
+//!
+//! let (packed, _manifest) = pack::open_pack(Path::new("dist/index-pack")).unwrap();
+//! assert_eq!(packed.count_symbols().unwrap(), manifest.symbols);
+//! ```
+//!
+//! A pack can also be published for a commit: [`compress_pack`] gzips the
+//! database and describes it with a [`PublishedIndex`] carrying its SHA-256,
+//! and [`restore_published`] checks and unpacks it into a fresh checkout, so
+//! CI jobs and new clones skip a cold index.
+
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::db::{SqliteIndex, SCHEMA_VERSION};
+use crate::{IndexError, Result};
+
+/// File name of the packed database inside a pack directory.
+pub const PACK_DB_NAME: &str = "index.db";
+
+/// File name of the manifest inside a pack directory.
+pub const MANIFEST_NAME: &str = "manifest.json";
+
+/// Version of the pack layout (directory contents and manifest fields).
+pub const PACK_FORMAT_VERSION: u32 = 1;
+
+/// Describes a pack so consumers can check compatibility without opening the database.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackManifest {
+    /// Pack layout version ([`PACK_FORMAT_VERSION`] when written)
+    pub format_version: u32,
+    /// SQLite schema version of the packed database
+    pub schema_version: u32,
+    /// Release of rkt that wrote the pack (as `git describe` names it)
+    pub rocketindex_version: String,
+    /// Workspace root the index was built from; packed paths are relative to it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workspace_root: Option<PathBuf>,
+    pub symbols: usize,
+    pub files: usize,
+    /// Size of the packed database in bytes
+    pub size_bytes: u64,
+    /// Unix timestamp (seconds) when the pack was written
+    pub created_at: u64,
+}
+
+/// Describes a compressed pack published for one commit.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PublishedIndex {
+    /// Commit the index was built at
+    pub commit: String,
+    /// Hex SHA-256 of the compressed artifact
+    pub sha256: String,
+    /// Size of the compressed artifact in bytes
+    pub compressed_bytes: u64,
+    pub pack: PackManifest,
+}
+
+/// Name of the compressed artifact published for `commit`.
+#[must_use]
+pub fn artifact_name(commit: &str) -> String {
+    format!("{}.db.gz", commit)
+}
+
+/// Name of the [`PublishedIndex`] published alongside the artifact for `commit`.
+#[must_use]
+pub fn published_manifest_name(commit: &str) -> String {
+    format!("{}.json", commit)
+}
+
+/// Pack `index` into `out_dir`, which must not already contain a pack.
+///
+/// `tool_version` is the release of the tool writing the pack; the library
+/// can't know it, since the crate version doesn't change between releases.
+pub fn pack_index(index: &SqliteIndex, out_dir: &Path, tool_version: &str) -> Result<PackManifest> {
+    std::fs::create_dir_all(out_dir)?;
+    let db_path = out_dir.join(PACK_DB_NAME);
+    index.pack(&db_path)?;
+
+    let manifest = PackManifest {
+        format_version: PACK_FORMAT_VERSION,
+        schema_version: SCHEMA_VERSION,
+        rocketindex_version: tool_version.to_string(),
+        workspace_root: index.get_metadata("workspace_root")?.map(PathBuf::from),
+        symbols: index.count_symbols()?,
+        files: index.list_files()?.len(),
+        size_bytes: std::fs::metadata(&db_path)?.len(),
+        created_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+    };
+    std::fs::write(
+        out_dir.join(MANIFEST_NAME),
+        serde_json::to_string_pretty(&manifest)?,
+    )?;
+
+    Ok(manifest)
+}
+
+/// Open the pack in `dir` read-only, checking its manifest first.
+pub fn open_pack(dir: &Path) -> Result<(SqliteIndex, PackManifest)> {
+    let manifest = read_manifest(dir)?;
+    if manifest.format_version != PACK_FORMAT_VERSION {
+        return Err(IndexError::IoError(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!(
+                "Unsupported pack format version {} (expected {})",
+                manifest.format_version, PACK_FORMAT_VERSION
+            ),
+        )));
+    }
+
+    let index = SqliteIndex::open_read_only(&dir.join(PACK_DB_NAME))?;
+    Ok((index, manifest))
+}
+
+/// Gzip the database of the pack in `dir` for publishing at `commit`.
+pub fn compress_pack(dir: &Path, commit: &str) -> Result<(Vec<u8>, PublishedIndex)> {
+    let pack = read_manifest(dir)?;
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&std::fs::read(dir.join(PACK_DB_NAME))?)?;
+    let artifact = encoder.finish()?;
+
+    let published = PublishedIndex {
+        commit: commit.to_string(),
+        sha256: sha256_hex(&artifact),
+        compressed_bytes: artifact.len() as u64,
+        pack,
+    };
+    Ok((artifact, published))
+}
+
+/// Check a downloaded artifact against its [`PublishedIndex`] and unpack it
+/// to `dest` as the index of the checkout at `root`, replacing any index
+/// already there the way [`SqliteIndex::replace`] does. Hold the write lock
+/// for `dest` while this runs.
+///
+/// Nothing is written unless the artifact is intact and its schema matches
+/// this version of rocketindex.
+pub fn restore_published(
+    artifact: &[u8],
+    published: &PublishedIndex,
+    dest: &Path,
+    root: &Path,
+) -> Result<SqliteIndex> {
+    if artifact.len() as u64 != published.compressed_bytes
+        || sha256_hex(artifact) != published.sha256
+    {
+        return Err(IndexError::IoError(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!(
+                "Index artifact for {} failed its integrity check",
+                published.commit
+            ),
+        )));
+    }
+    if published.pack.format_version != PACK_FORMAT_VERSION {
+        return Err(IndexError::IoError(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!(
+                "Unsupported pack format version {} (expected {})",
+                published.pack.format_version, PACK_FORMAT_VERSION
+            ),
+        )));
+    }
+    if published.pack.schema_version != SCHEMA_VERSION {
+        return Err(IndexError::IncompatibleSchema {
+            found: published.pack.schema_version,
+            supported: SCHEMA_VERSION,
+        });
+    }
+
+    let mut db = Vec::new();
+    GzDecoder::new(artifact).read_to_end(&mut db)?;
+    // Unpacked beside the live index and copied into it, so readers of an
+    // index already at `dest` never see a half-written file
+    let staged = SqliteIndex::staging_path(dest);
+    std::fs::write(&staged, db)?;
+    let unpacked = SqliteIndex::open(&staged).and_then(|index| {
+        index.unpack(root)?;
+        Ok(index)
+    });
+    match unpacked {
+        Ok(index) => index.replace(dest)?,
+        Err(e) => {
+            let _ = std::fs::remove_file(&staged);
+            return Err(e);
+        }
+    }
+    SqliteIndex::open(dest)
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Read the manifest of the pack in `dir`.
+pub fn read_manifest(dir: &Path) -> Result<PackManifest> {
+    let path = dir.join(MANIFEST_NAME);
+    if !path.exists() {
+        return Err(IndexError::IndexNotFound);
+    }
+    Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+}
+
+/// Whether `dir` looks like a pack directory.
+#[must_use]
+pub fn is_pack(dir: &Path) -> bool {
+    dir.join(MANIFEST_NAME).is_file() && dir.join(PACK_DB_NAME).is_file()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Location, Symbol, SymbolKind, Visibility};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_pack_round_trip_is_relative_and_read_only() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path().join("ws");
+        let index = SqliteIndex::create(&dir.path().join("index.db")).unwrap();
+        index
+            .set_metadata("workspace_root", &root.to_string_lossy())
+            .unwrap();
+        index
+            .insert_symbol(&Symbol::new(
+                "run".to_string(),
+                "App.run".to_string(),
+                SymbolKind::Function,
+                Location::new(root.join("src/App.fs"), 3, 5),
+                Visibility::Public,
+                "fsharp".to_string(),
+            ))
+            .unwrap();
+        index.set_file_mtime(&root.join("src/App.fs"), 42).unwrap();
+
+        let out = dir.path().join("pack");
+        let manifest = pack_index(&index, &out, "1.2.0-3-gabc1234").unwrap();
+        assert_eq!(manifest.symbols, 1);
+        assert_eq!(manifest.rocketindex_version, "1.2.0-3-gabc1234");
+        assert_eq!(manifest.schema_version, SCHEMA_VERSION);
+        assert!(is_pack(&out));
+        // A second pack into the same place must not clobber the first
+        assert!(pack_index(&index, &out, "1.2.0").is_err());
+
+        let (packed, read) = open_pack(&out).unwrap();
+        assert_eq!(read, manifest);
+
+        let symbol = packed.find_by_qualified("App.run").unwrap().unwrap();
+        assert_eq!(symbol.location.file, PathBuf::from("src").join("App.fs"));
+        assert_eq!(packed.search("run", 10, None).unwrap().len(), 1);
+        assert!(packed.get_tracked_files().unwrap().is_empty());
+        assert!(packed.set_metadata("x", "y").is_err());
+    }
+
+    #[test]
+    fn test_published_pack_restores_into_another_checkout() {
+        let dir = TempDir::new().unwrap();
+        let built_at = dir.path().join("ci");
+        let index = SqliteIndex::create(&dir.path().join("index.db")).unwrap();
+        index
+            .set_metadata("workspace_root", &built_at.to_string_lossy())
+            .unwrap();
+        index
+            .insert_symbol(&Symbol::new(
+                "run".to_string(),
+                "App.run".to_string(),
+                SymbolKind::Function,
+                Location::new(built_at.join("src/App.fs"), 3, 5),
+                Visibility::Public,
+                "fsharp".to_string(),
+            ))
+            .unwrap();
+        pack_index(&index, &dir.path().join("pack"), "1.2.0").unwrap();
+        let (artifact, published) = compress_pack(&dir.path().join("pack"), "abc123").unwrap();
+        assert_eq!(published.compressed_bytes, artifact.len() as u64);
+        assert_eq!(artifact_name("abc123"), "abc123.db.gz");
+
+        let clone = dir.path().join("clone");
+        std::fs::create_dir_all(&clone).unwrap();
+        let dest = clone.join("index.db");
+
+        // A corrupted download is rejected before anything is written
+        let mut corrupted = artifact.clone();
+        let last = corrupted.len() - 1;
+        corrupted[last] ^= 0xff;
+        assert!(restore_published(&corrupted, &published, &dest, &clone).is_err());
+        assert!(!dest.exists());
+
+        let restored = restore_published(&artifact, &published, &dest, &clone).unwrap();
+        let symbol = restored.find_by_qualified("App.run").unwrap().unwrap();
+        assert_eq!(symbol.location.file, clone.join("src").join("App.fs"));
+        assert_eq!(restored.get_metadata("packed").unwrap(), None);
+        restored.set_file_mtime(&symbol.location.file, 7).unwrap();
+
+        // Pulling again copies into the live index under its open readers
+        restore_published(&artifact, &published, &dest, &clone).unwrap();
+        assert!(restored.find_by_qualified("App.run").unwrap().is_some());
+        assert!(!SqliteIndex::staging_path(&dest).exists());
+    }
+}
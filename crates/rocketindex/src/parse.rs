@@ -57,7 +57,7 @@ use crate::languages::{
     c, cpp, csharp, fsharp, go, haxe, java, javascript, kotlin, objc, php, python, ruby, rust,
     swift, typescript,
 };
-use crate::{Location, Reference, Symbol};
+use crate::{Location, ModuleAlias, Reference, ReferenceKind, Symbol};
 
 /// A syntax error detected during parsing.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -86,12 +86,16 @@ pub struct ParseResult {
     pub references: Vec<Reference>,
     /// Module opens/imports in this file
     pub opens: Vec<String>,
+    /// Local names for modules and namespaces (`module M = ...`, `using M = ...`)
+    pub aliases: Vec<ModuleAlias>,
     /// The module/namespace path for this file
     pub module_path: Option<String>,
     /// Syntax errors detected during parsing
     pub errors: Vec<SyntaxError>,
     /// Warnings generated during parsing (non-fatal issues like depth limits)
     pub warnings: Vec<ParseWarning>,
+    /// Complexity and nesting of the file
+    pub metrics: crate::metrics::FileMetrics,
 }
 
 /// Trait for language-specific parsers.
@@ -158,6 +162,163 @@ pub fn node_to_location(file: &Path, node: &tree_sitter::Node) -> Location {
     )
 }
 
+/// Add a synthetic symbol for each significant lambda or closure under
+/// `root`, so references made only inside callbacks have a caller of their
+/// own instead of being pinned on whatever was defined above them.
+///
+/// `kinds` are the grammar's node kinds for lambdas. A lambda is captured
+/// when it refers to something other than its own parameters and isn't on
+/// the same line as a
+/// named definition (`const handler = () => save()` is `handler`). It is
+/// named after the definition it is written in plus its ordinal there:
+/// `main.<lambda1>`, `main.<lambda2>`, `Billing::run::<lambda1>`, or after
+/// the file's module for top-level callbacks. Its location spans the whole
+/// lambda.
+pub(crate) fn capture_lambdas(
+    root: &tree_sitter::Node,
+    source: &[u8],
+    file: &Path,
+    kinds: &[&str],
+    language: &str,
+    result: &mut ParseResult,
+) {
+    let position = |location: &Location| (location.line, location.column);
+
+    // Each lambda, its parameter names, the names of the nodes around it
+    // (innermost first), and the top-level item it is in
+    let mut lambdas = Vec::new();
+    let mut cursor = root.walk();
+    'walk: loop {
+        let node = cursor.node();
+        if kinds.contains(&node.kind()) {
+            let mut named = Vec::new();
+            let mut top = node;
+            let mut ancestor = node.parent();
+            while let Some(parent) = ancestor {
+                if parent.parent().is_none() {
+                    break;
+                }
+                if let Some(name) = parent.child_by_field_name("name") {
+                    named.push(position(&node_to_location(file, &name)));
+                }
+                top = parent;
+                ancestor = parent.parent();
+            }
+            let mut parameters = std::collections::HashSet::new();
+            for field in ["parameters", "parameter"] {
+                if let Some(list) = node.child_by_field_name(field) {
+                    collect_identifiers(&list, source, &mut parameters);
+                }
+            }
+            lambdas.push((
+                node_to_location(file, &node),
+                parameters,
+                named,
+                node_to_location(file, &top),
+            ));
+        }
+        if cursor.goto_first_child() {
+            continue;
+        }
+        while !cursor.goto_next_sibling() {
+            if !cursor.goto_parent() {
+                break 'walk;
+            }
+        }
+    }
+
+    let named_symbols = result.symbols.len();
+    let mut ordinals: std::collections::HashMap<String, usize> = Default::default();
+    for (lambda, parameters, named, top) in lambdas {
+        let (start, end) = (position(&lambda), (lambda.end_line, lambda.end_column));
+        let refers = result.references.iter().any(|reference| {
+            let at = position(&reference.location);
+            let head = reference.name.split(['.', ':']).next().unwrap_or_default();
+            start <= at && at <= end && !parameters.contains(head)
+        });
+        let symbols = &result.symbols[..named_symbols];
+        if !refers
+            || symbols
+                .iter()
+                .any(|s| s.location.line == lambda.line && position(&s.location) <= start)
+        {
+            continue;
+        }
+
+        // The named definition around the lambda, or failing that (grammars
+        // without `name` fields) the nearest one before it in the same
+        // top-level item
+        let within_top =
+            |s: &&Symbol| position(&s.location) >= position(&top) && position(&s.location) <= start;
+        let owner = named
+            .iter()
+            .find_map(|&at| symbols.iter().find(|s| position(&s.location) == at))
+            .or_else(|| {
+                symbols
+                    .iter()
+                    .filter(within_top)
+                    .filter(|s| s.kind.is_callable())
+                    .max_by_key(|s| position(&s.location))
+            })
+            .or_else(|| {
+                symbols
+                    .iter()
+                    .filter(within_top)
+                    .filter(|s| s.kind.is_instantiable() || s.kind == crate::SymbolKind::Module)
+                    .max_by_key(|s| position(&s.location))
+            })
+            .map(|s| s.qualified.clone())
+            .or_else(|| result.module_path.clone())
+            .unwrap_or_else(|| {
+                file.file_stem()
+                    .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned())
+            });
+
+        let ordinal = ordinals.entry(owner.clone()).or_default();
+        *ordinal += 1;
+        let name = format!("<lambda{}>", ordinal);
+        let separator = if matches!(language, "rust" | "cpp") || owner.contains("::") {
+            "::"
+        } else {
+            "."
+        };
+        let qualified = format!("{}{}{}", owner, separator, name);
+        result.symbols.push(Symbol::new(
+            name,
+            qualified,
+            crate::SymbolKind::Function,
+            lambda,
+            crate::Visibility::Private,
+            language.to_string(),
+        ));
+    }
+}
+
+/// The text of every identifier under `node`.
+fn collect_identifiers<'a>(
+    node: &tree_sitter::Node,
+    source: &'a [u8],
+    names: &mut std::collections::HashSet<&'a str>,
+) {
+    let mut cursor = node.walk();
+    'walk: loop {
+        let node = cursor.node();
+        if node.child_count() == 0 && node.kind().ends_with("identifier") {
+            if let Ok(name) = node.utf8_text(source) {
+                names.insert(name);
+            }
+        }
+        if cursor.goto_first_child() {
+            continue;
+        }
+        while !cursor.goto_next_sibling() {
+            if !cursor.goto_parent() {
+                break 'walk;
+            }
+        }
+    }
+}
+
 /// Find a child node by its kind.
 /// Uses cursor-based iteration for O(n) instead of O(n²) performance.
 pub fn find_child_by_kind<'a>(
@@ -177,3 +338,311 @@ pub fn find_child_by_kind<'a>(
     }
     None
 }
+
+/// How the name at `node` is used, judged from the nodes around it.
+///
+/// Works from node kinds and field names the tree-sitter grammars share
+/// (`call_expression`/`function`, `assignment`/`left`, `superclass`, ...),
+/// so every extractor can use it. Anything unrecognized is a read.
+pub fn reference_kind(node: &tree_sitter::Node) -> ReferenceKind {
+    if let Some(kind) = declaration_context(node) {
+        return kind;
+    }
+
+    if is_type_node(node.kind()) && !is_constructed(node) {
+        return ReferenceKind::TypeUse;
+    }
+
+    // Climb from `method` in `obj.method` to the member access, then look at
+    // what the whole expression is used for
+    let mut child = *node;
+    while let Some(parent) = child.parent() {
+        let is = |field: &str| parent.child_by_field_name(field) == Some(child);
+        let kind = parent.kind();
+
+        if CALLS.contains(&kind) {
+            let callee = ["function", "method", "name", "macro", "constructor", "type"]
+                .iter()
+                .find_map(|field| parent.child_by_field_name(field))
+                .or_else(|| parent.named_child(0));
+            return if callee == Some(child) {
+                ReferenceKind::Call
+            } else {
+                ReferenceKind::Read
+            };
+        }
+        if ASSIGNMENTS.contains(&kind) {
+            let target = parent
+                .child_by_field_name("left")
+                .or_else(|| parent.named_child(0));
+            return if target == Some(child) {
+                ReferenceKind::Write
+            } else {
+                ReferenceKind::Read
+            };
+        }
+        if UPDATES.contains(&kind) {
+            return ReferenceKind::Write;
+        }
+        if is("type") || is_type_node(kind) {
+            return ReferenceKind::TypeUse;
+        }
+
+        let is_member = ["name", "property", "field", "attribute", "member"]
+            .iter()
+            .any(|field| is(field));
+        let is_head = WRAPPERS.contains(&kind) && parent.named_child(0) == Some(child);
+        if !(is_member || is_head) {
+            break;
+        }
+        child = parent;
+    }
+    ReferenceKind::Read
+}
+
+/// Import, inheritance and implementation clauses only ever contain names,
+/// so any enclosing one decides.
+fn declaration_context(node: &tree_sitter::Node) -> Option<ReferenceKind> {
+    let mut child = *node;
+    while let Some(parent) = child.parent() {
+        let kind = parent.kind();
+        if IMPORTS.contains(&kind) {
+            return Some(ReferenceKind::Import);
+        }
+        if IMPLEMENTS.contains(&kind)
+            || (kind == "impl_item" && parent.child_by_field_name("trait") == Some(child))
+        {
+            return Some(ReferenceKind::Implement);
+        }
+        if INHERITS.contains(&kind)
+            || (kind == "class_definition"
+                && parent.child_by_field_name("superclasses") == Some(child))
+        {
+            return Some(ReferenceKind::Inherit);
+        }
+        child = parent;
+    }
+    None
+}
+
+/// `Order` in `new Order()` is the constructor being called.
+fn is_constructed(node: &tree_sitter::Node) -> bool {
+    let mut child = *node;
+    while let Some(parent) = child.parent() {
+        if CALLS.contains(&parent.kind()) {
+            return parent.child_by_field_name("type") == Some(child)
+                || parent.child_by_field_name("constructor") == Some(child);
+        }
+        if !(is_type_node(parent.kind()) && parent.named_child(0) == Some(child)) {
+            return false;
+        }
+        child = parent;
+    }
+    false
+}
+
+fn is_type_node(kind: &str) -> bool {
+    kind == "type" || kind.ends_with("_type") || kind.starts_with("type_")
+}
+
+const CALLS: &[&str] = &[
+    "call_expression",
+    "call",
+    "invocation_expression",
+    "method_invocation",
+    "function_call_expression",
+    "member_call_expression",
+    "nullsafe_member_call_expression",
+    "scoped_call_expression",
+    "macro_invocation",
+    "object_creation_expression",
+    "new_expression",
+    "constructor_invocation",
+    "application_expression",
+    "message_expression",
+];
+
+const ASSIGNMENTS: &[&str] = &[
+    "assignment",
+    "assignment_expression",
+    "assignment_statement",
+    "augmented_assignment",
+    "augmented_assignment_expression",
+    "compound_assignment_expr",
+    "operator_assignment",
+];
+
+const UPDATES: &[&str] = &["update_expression", "inc_statement", "dec_statement"];
+
+/// Nodes whose first child names what the whole node refers to
+const WRAPPERS: &[&str] = &[
+    "generic_name",
+    "generic_type",
+    "parameterized_type",
+    "template_type",
+    "template_function",
+    "directly_assignable_expression",
+    "expression_list",
+];
+
+const IMPORTS: &[&str] = &[
+    "import_statement",
+    "import_from_statement",
+    "future_import_statement",
+    "import_declaration",
+    "import_spec",
+    "import_header",
+    "import_decl",
+    "use_declaration",
+    "using_directive",
+    "namespace_use_declaration",
+    "preproc_include",
+    "extern_crate_declaration",
+];
+
+const INHERITS: &[&str] = &[
+    "superclass",
+    "extends_clause",
+    "extends_interfaces",
+    "class_heritage",
+    "base_list",
+    "base_class_clause",
+    "base_clause",
+    "delegation_specifier",
+    "inheritance_specifier",
+];
+
+const IMPLEMENTS: &[&str] = &[
+    "implements_clause",
+    "super_interfaces",
+    "class_interface_clause",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Kinds of the references named `name`, in source order
+    fn kinds(file: &str, source: &str, name: &str) -> Vec<ReferenceKind> {
+        let result = extract_symbols(Path::new(file), source, 100);
+        let mut refs: Vec<_> = result
+            .references
+            .iter()
+            .filter(|r| r.name == name)
+            .collect();
+        refs.sort_by_key(|r| (r.location.line, r.location.column));
+        refs.dedup_by_key(|r| (r.location.line, r.location.column));
+        refs.iter().map(|r| r.kind).collect()
+    }
+
+    #[test]
+    fn test_reference_kinds_typescript() {
+        let source =
+            "function checkout(a: Order) { const o = new Order(); o.total = 2; save(o); }\n\
+                      class Shop extends Base implements Store {}\n";
+        assert_eq!(
+            kinds("shop.ts", source, "Order"),
+            vec![ReferenceKind::TypeUse, ReferenceKind::Call]
+        );
+        assert_eq!(
+            kinds("shop.ts", source, "total"),
+            vec![ReferenceKind::Write]
+        );
+        assert_eq!(kinds("shop.ts", source, "save"), vec![ReferenceKind::Call]);
+        assert_eq!(
+            kinds("shop.ts", source, "o"),
+            vec![ReferenceKind::Read, ReferenceKind::Read]
+        );
+        assert_eq!(
+            kinds("shop.ts", source, "Base"),
+            vec![ReferenceKind::Inherit]
+        );
+        assert_eq!(
+            kinds("shop.ts", source, "Store"),
+            vec![ReferenceKind::Implement]
+        );
+    }
+
+    #[test]
+    fn test_reference_kinds_java_python_and_rust() {
+        let source = "class Shop extends Base implements Store { void m() { save(o); } }\n";
+        assert_eq!(
+            kinds("Shop.java", source, "Base"),
+            vec![ReferenceKind::Inherit]
+        );
+        assert_eq!(
+            kinds("Shop.java", source, "Store"),
+            vec![ReferenceKind::Implement]
+        );
+        assert_eq!(
+            kinds("Shop.java", source, "save"),
+            vec![ReferenceKind::Call]
+        );
+
+        let source = "from shop import Base\n\ndef add(item: Item):\n    return price(item)\n";
+        assert_eq!(
+            kinds("cart.py", source, "Base"),
+            vec![ReferenceKind::Import]
+        );
+        assert_eq!(
+            kinds("cart.py", source, "Item"),
+            vec![ReferenceKind::TypeUse]
+        );
+        assert_eq!(kinds("cart.py", source, "price"), vec![ReferenceKind::Call]);
+
+        let source = "fn add(&mut self) { write_total(f); self.count = 1; }\n";
+        assert_eq!(
+            kinds("cart.rs", source, "write_total"),
+            vec![ReferenceKind::Call]
+        );
+        assert_eq!(
+            kinds("cart.rs", source, "count"),
+            vec![ReferenceKind::Write]
+        );
+    }
+
+    /// Lambdas captured in `file`, as (qualified, first line, last line)
+    fn lambdas(file: &str, source: &str) -> Vec<(String, u32, u32)> {
+        extract_symbols(Path::new(file), source, 100)
+            .symbols
+            .into_iter()
+            .filter(Symbol::is_lambda)
+            .map(|s| (s.qualified, s.location.line, s.location.end_line))
+            .collect()
+    }
+
+    #[test]
+    fn captures_lambdas_that_refer_to_something() {
+        let source = r#"function setup(app) {
+  app.get("/", (req, res) => {
+    render(res);
+  });
+  const handler = () => save();
+  finish();
+}
+
+describe("app", () => {
+  setup(server);
+});
+"#;
+        assert_eq!(
+            lambdas("src/app.js", source),
+            vec![
+                ("setup.<lambda1>".to_string(), 2, 4),
+                ("app.<lambda1>".to_string(), 9, 11),
+            ]
+        );
+
+        let source = "fn run(items: &[u32]) {\n    items.iter().for_each(|item| {\n        store(item);\n    });\n    let total = items.iter().map(|x| x * 2);\n}\n";
+        assert_eq!(
+            lambdas("src/lib.rs", source),
+            vec![("run::<lambda1>".to_string(), 2, 4)]
+        );
+
+        let source = "class Cart\n  def checkout\n    items.each do |item|\n      charge(item)\n    end\n  end\nend\n";
+        assert_eq!(
+            lambdas("cart.rb", source),
+            vec![("Cart#checkout.<lambda1>".to_string(), 3, 5)]
+        );
+    }
+}
@@ -133,14 +133,14 @@ fn read_pid_file(path: &Path) -> Result<Option<u32>, PidFileError> {
 
 /// Check if a process with the given PID is still alive.
 #[cfg(unix)]
-fn is_process_alive(pid: u32) -> bool {
+pub fn is_process_alive(pid: u32) -> bool {
     // On Unix, sending signal 0 checks if process exists without actually signaling
     // SAFETY: This is a standard Unix API call
     unsafe { libc::kill(pid as i32, 0) == 0 }
 }
 
 #[cfg(windows)]
-fn is_process_alive(pid: u32) -> bool {
+pub fn is_process_alive(pid: u32) -> bool {
     use std::ptr::null_mut;
 
     // PROCESS_QUERY_LIMITED_INFORMATION = 0x1000
@@ -171,12 +171,62 @@ fn is_process_alive(pid: u32) -> bool {
 }
 
 #[cfg(not(any(unix, windows)))]
-fn is_process_alive(_pid: u32) -> bool {
+pub fn is_process_alive(_pid: u32) -> bool {
     // On unknown platforms, assume the process might be alive
     // This is conservative - it may report false positives
     true
 }
 
+/// Ask the watch process for `root` to shut down.
+///
+/// On Unix this sends SIGINT, so the process stops the way Ctrl+C would and
+/// removes its PID file; on Windows the process is terminated. Returns the
+/// PID that was signalled, or `None` if no watch process is running.
+pub fn stop_watch_process(root: &Path) -> std::io::Result<Option<u32>> {
+    let Some(pid) = find_watch_process(root) else {
+        return Ok(None);
+    };
+    signal_stop(pid)?;
+    Ok(Some(pid))
+}
+
+#[cfg(unix)]
+fn signal_stop(pid: u32) -> std::io::Result<()> {
+    // SAFETY: This is a standard Unix API call
+    if unsafe { libc::kill(pid as i32, libc::SIGINT) } == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error())
+    }
+}
+
+#[cfg(windows)]
+fn signal_stop(pid: u32) -> std::io::Result<()> {
+    // PROCESS_TERMINATE = 0x0001
+    const PROCESS_TERMINATE: u32 = 0x0001;
+
+    unsafe {
+        let handle = windows_sys::Win32::System::Threading::OpenProcess(PROCESS_TERMINATE, 0, pid);
+        if handle.is_null() {
+            return Err(std::io::Error::last_os_error());
+        }
+        let result = windows_sys::Win32::System::Threading::TerminateProcess(handle, 1);
+        windows_sys::Win32::Foundation::CloseHandle(handle);
+        if result == 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(any(unix, windows)))]
+fn signal_stop(_pid: u32) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "stopping a watch process is not supported on this platform",
+    ))
+}
+
 /// Remove a stale PID file if the process is no longer running.
 /// Returns true if a stale file was removed.
 pub fn cleanup_stale_pidfile(root: &Path) -> bool {
@@ -346,6 +396,12 @@ mod tests {
         assert!(matches!(result, Err(PidFileError::InvalidContents)));
     }
 
+    #[test]
+    fn test_stop_watch_process_without_session() {
+        let dir = setup_test_dir();
+        assert!(stop_watch_process(dir.path()).unwrap().is_none());
+    }
+
     #[test]
     fn test_is_process_alive_current_process() {
         // Our own process should definitely be alive
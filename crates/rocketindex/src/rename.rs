@@ -0,0 +1,920 @@
+//! Workspace-wide symbol rename.
+//!
+//! [`plan_rename`] turns a symbol's definition and resolved references into
+//! per-file text edits, checking each one against the file on disk so a stale
+//! index can't produce a bad edit. References written through the symbol,
+//! like `Utils.run` when renaming the module `Utils`, are edited too.
+//! [`plan_rename_with`] plans against other contents, such as an editor's
+//! unsaved buffers, and can take the symbol's string-literal references
+//! (see [`crate::string_refs`]): those edits are kept apart, since a name in
+//! a string may not mean the symbol and should be confirmed by hand. A plan
+//! can be shown as a unified diff or applied with [`RenamePlan::apply`],
+//! which backs up every touched file and swaps all the new contents in only
+//! once every one of them has been written.
+
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::spider::try_resolve_reference;
+use crate::{CodeIndex, Confidence, IndexError, Location, Reference, Result};
+
+/// Suffix of the temporary files written next to each target while applying.
+const TEMP_SUFFIX: &str = ".rkt-rename";
+
+/// Lines of unchanged context around each hunk in [`RenamePlan::unified_diff`].
+const DIFF_CONTEXT: usize = 3;
+
+/// A single replacement of the old short name.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RenameEdit {
+    pub line: u32,
+    /// 1-indexed byte column where the name starts
+    pub column: u32,
+    /// 1-indexed byte column one past the end of the name
+    pub end_column: u32,
+}
+
+/// All edits to one file, with its contents before and after.
+#[derive(Debug, Clone, Serialize)]
+pub struct FileRename {
+    /// Absolute path of the file
+    pub file: PathBuf,
+    pub edits: Vec<RenameEdit>,
+    /// Edits inside string literals (DI registrations, reflection), to be
+    /// confirmed by hand; `updated` includes them
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub string_edits: Vec<RenameEdit>,
+    #[serde(skip)]
+    pub original: String,
+    #[serde(skip)]
+    pub updated: String,
+}
+
+/// An index location that couldn't be edited because the file doesn't have
+/// the expected name there (usually because the index is stale).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SkippedLocation {
+    pub file: PathBuf,
+    pub line: u32,
+    pub column: u32,
+}
+
+/// The edits needed to rename a symbol across the workspace.
+#[derive(Debug, Clone, Serialize)]
+pub struct RenamePlan {
+    /// Qualified name of the renamed symbol
+    pub symbol: String,
+    pub old_name: String,
+    pub new_name: String,
+    /// Workspace root; diffs show paths relative to it
+    pub root: PathBuf,
+    pub files: Vec<FileRename>,
+    pub skipped: Vec<SkippedLocation>,
+}
+
+/// Plan renaming the symbol `qualified` to `new_name` (a short name).
+///
+/// Covers every definition of the symbol (overloads, partial declarations)
+/// and every reference that resolves to it.
+pub fn plan_rename(index: &CodeIndex, qualified: &str, new_name: &str) -> Result<RenamePlan> {
+    plan_rename_with(index, qualified, new_name, &[], |path| {
+        std::fs::read_to_string(path)
+    })
+}
+
+/// Like [`plan_rename`], also editing the string-literal references in
+/// `strings` and reading each file's current contents with `read`.
+pub fn plan_rename_with(
+    index: &CodeIndex,
+    qualified: &str,
+    new_name: &str,
+    strings: &[Reference],
+    mut read: impl FnMut(&Path) -> std::io::Result<String>,
+) -> Result<RenamePlan> {
+    if !is_valid_name(new_name) {
+        return Err(IndexError::IoError(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("'{}' is not a valid identifier", new_name),
+        )));
+    }
+
+    let definitions = index.get_all(qualified);
+    let Some(first) = definitions.first() else {
+        return Err(IndexError::SymbolNotFound(qualified.to_string()));
+    };
+    let old_name = first.name.clone();
+
+    let target =
+        |location, written, tail, source| Target::new(index, location, written, tail, source);
+    let mut targets: Vec<Target> = definitions
+        .iter()
+        .map(|s| target(&s.location, s.name.as_str(), "", Source::Definition))
+        .collect();
+    // Matched by name, so each is kept only once it resolves to the symbol
+    targets.extend(
+        index
+            .find_references(qualified)
+            .into_iter()
+            .map(|r| target(&r.location, r.name.as_str(), "", Source::ByName)),
+    );
+    targets.extend(
+        member_references(index, qualified, &old_name)
+            .map(|(r, tail)| target(&r.location, r.name.as_str(), tail, Source::Definition)),
+    );
+    targets.extend(
+        strings
+            .iter()
+            .map(|r| target(&r.location, r.name.as_str(), "", Source::String)),
+    );
+
+    let mut by_file: BTreeMap<PathBuf, Vec<Target>> = BTreeMap::new();
+    for target in targets {
+        by_file
+            .entry(target.location.file.clone())
+            .or_default()
+            .push(target);
+    }
+
+    let mut files = Vec::new();
+    let mut skipped = Vec::new();
+    for (file, targets) in by_file {
+        let original = read(&file)?;
+        let line_starts = line_starts(&original);
+
+        let mut edits: Vec<RenameEdit> = Vec::new();
+        let mut string_edits: Vec<RenameEdit> = Vec::new();
+        for Target {
+            location,
+            written,
+            tail,
+            source,
+        } in targets
+        {
+            if source == Source::ByName
+                && !resolves_to(
+                    index,
+                    qualified,
+                    &original,
+                    &line_starts,
+                    &location,
+                    written,
+                )
+            {
+                continue;
+            }
+            let located = locate_in_span(&original, &line_starts, &location, &old_name, tail)
+                .or_else(|| {
+                    let (line, column) = (location.line, location.column);
+                    locate_name(
+                        &original,
+                        &line_starts,
+                        line,
+                        column,
+                        written,
+                        &old_name,
+                        tail,
+                    )
+                });
+            let list = if source == Source::String {
+                &mut string_edits
+            } else {
+                &mut edits
+            };
+            match located {
+                Some(edit) if !list.contains(&edit) => list.push(edit),
+                Some(_) => {}
+                None => skipped.push(SkippedLocation {
+                    file: file.clone(),
+                    line: location.line,
+                    column: location.column,
+                }),
+            }
+        }
+        if edits.is_empty() && string_edits.is_empty() {
+            continue;
+        }
+        edits.sort_by_key(|e| (e.line, e.column));
+        string_edits.sort_by_key(|e| (e.line, e.column));
+
+        let mut all: Vec<&RenameEdit> = edits.iter().chain(&string_edits).collect();
+        all.sort_by_key(|e| (e.line, e.column));
+        let mut updated = original.clone();
+        for edit in all.into_iter().rev() {
+            let start = line_starts[edit.line as usize - 1] + edit.column as usize - 1;
+            let end = start + (edit.end_column - edit.column) as usize;
+            updated.replace_range(start..end, new_name);
+        }
+
+        files.push(FileRename {
+            file,
+            edits,
+            string_edits,
+            original,
+            updated,
+        });
+    }
+
+    Ok(RenamePlan {
+        symbol: qualified.to_string(),
+        old_name,
+        new_name: new_name.to_string(),
+        root: index
+            .workspace_root()
+            .map(Path::to_path_buf)
+            .unwrap_or_default(),
+        files,
+        skipped,
+    })
+}
+
+impl RenamePlan {
+    /// Qualified name of the symbol once renamed.
+    #[must_use]
+    pub fn new_qualified(&self) -> String {
+        let parent = self
+            .symbol
+            .strip_suffix(self.old_name.as_str())
+            .unwrap_or_default();
+        format!("{}{}", parent, self.new_name)
+    }
+
+    /// Total number of edits across all files, string-literal ones included.
+    #[must_use]
+    pub fn edit_count(&self) -> usize {
+        self.files
+            .iter()
+            .map(|f| f.edits.len() + f.string_edits.len())
+            .sum()
+    }
+
+    /// Number of string-literal edits, which need confirming by hand.
+    #[must_use]
+    pub fn string_edit_count(&self) -> usize {
+        self.files.iter().map(|f| f.string_edits.len()).sum()
+    }
+
+    /// The plan as a unified diff, with paths relative to the workspace root.
+    #[must_use]
+    pub fn unified_diff(&self) -> String {
+        let mut out = String::new();
+        for file in &self.files {
+            let path = file.file.strip_prefix(&self.root).unwrap_or(&file.file);
+            let path = path.to_string_lossy().replace('\\', "/");
+            out.push_str(&format!("--- a/{}\n+++ b/{}\n", path, path));
+
+            let old: Vec<&str> = file.original.lines().collect();
+            let new: Vec<&str> = file.updated.lines().collect();
+            let mut changed: Vec<usize> = file
+                .edits
+                .iter()
+                .chain(&file.string_edits)
+                .map(|e| e.line as usize - 1)
+                .collect();
+            changed.sort_unstable();
+            changed.dedup();
+
+            // Renames never add or remove lines, so hunks line up one-to-one
+            for hunk in group_hunks(&changed, old.len()) {
+                let (start, end) = hunk;
+                out.push_str(&format!(
+                    "@@ -{},{} +{},{} @@\n",
+                    start + 1,
+                    end - start,
+                    start + 1,
+                    end - start
+                ));
+                for i in start..end {
+                    if changed.binary_search(&i).is_ok() {
+                        out.push_str(&format!("-{}\n+{}\n", old[i], new[i]));
+                    } else {
+                        out.push_str(&format!(" {}\n", old[i]));
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    /// Write the planned edits, copying each original file into `backup_dir` first.
+    ///
+    /// Fails without touching any file if one of them changed since the plan
+    /// was made. New contents are written to temporary files beside the
+    /// targets and only moved into place once all of them were written.
+    pub fn apply(&self, backup_dir: &Path) -> Result<()> {
+        for file in &self.files {
+            if std::fs::read_to_string(&file.file)? != file.original {
+                return Err(IndexError::IoError(std::io::Error::other(format!(
+                    "{} changed since the rename was planned",
+                    file.file.display()
+                ))));
+            }
+        }
+
+        let backups: Vec<PathBuf> = self
+            .files
+            .iter()
+            .map(|f| backup_dir.join(backup_name(&f.file, &self.root)))
+            .collect();
+        for (file, backup) in self.files.iter().zip(&backups) {
+            if let Some(parent) = backup.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(backup, &file.original)?;
+        }
+
+        let temps: Vec<PathBuf> = self.files.iter().map(|f| temp_path(&f.file)).collect();
+        for (file, temp) in self.files.iter().zip(&temps) {
+            if let Err(e) = write_like(temp, &file.updated, &file.file) {
+                for temp in &temps {
+                    let _ = std::fs::remove_file(temp);
+                }
+                return Err(e.into());
+            }
+        }
+
+        for (i, (file, temp)) in self.files.iter().zip(&temps).enumerate() {
+            if let Err(e) = std::fs::rename(temp, &file.file) {
+                // Put back what was already replaced and drop the rest
+                for (done, backup) in self.files[..i].iter().zip(&backups) {
+                    let _ = std::fs::copy(backup, &done.file);
+                }
+                for temp in &temps[i..] {
+                    let _ = std::fs::remove_file(temp);
+                }
+                return Err(e.into());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Why a place is expected to have the old name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Source {
+    /// A definition of the symbol, or a reference written through it
+    Definition,
+    /// A reference whose name matches the symbol's, which may mean another
+    /// symbol of the same name
+    ByName,
+    /// A string-literal reference
+    String,
+}
+
+/// A place the old name should be found.
+struct Target<'a> {
+    location: Location,
+    /// The name as written there
+    written: &'a str,
+    /// What follows the old name in `written`
+    tail: &'a str,
+    source: Source,
+}
+
+impl<'a> Target<'a> {
+    fn new(
+        index: &CodeIndex,
+        location: &Location,
+        written: &'a str,
+        tail: &'a str,
+        source: Source,
+    ) -> Self {
+        Self {
+            location: index.make_location_absolute(location),
+            written,
+            tail,
+            source,
+        }
+    }
+}
+
+/// Whether the reference at `location` means the symbol `qualified`, going
+/// by the token its span covers in `text` (`Billing.charge` for a reference
+/// recorded as `charge`) or else the name it was recorded under.
+///
+/// The file's resolver decides first. Languages without one fall back to a
+/// bare name in a file defining the symbol, then to the resolution callers
+/// and spider use, which must be at least [`Confidence::Qualified`] sure.
+fn resolves_to(
+    index: &CodeIndex,
+    qualified: &str,
+    text: &str,
+    line_starts: &[usize],
+    location: &Location,
+    written: &str,
+) -> bool {
+    let spanned = (location.end_line == location.line && location.end_column > location.column)
+        .then(|| {
+            let start = line_starts.get((location.line as usize).checked_sub(1)?)?
+                + location.column as usize
+                - 1;
+            text.get(start..start + (location.end_column - location.column) as usize)
+        })
+        .flatten()
+        .filter(|token| token.ends_with(written) || written.ends_with(*token));
+
+    let file = &location.file;
+    spanned
+        .into_iter()
+        .chain([written])
+        .any(|name| match index.resolve_dotted(name, file) {
+            Some(resolved) => resolved.symbol.qualified == qualified,
+            None => {
+                let defined_here = || {
+                    index
+                        .symbols_in_file(file)
+                        .iter()
+                        .any(|s| s.qualified == qualified && s.name == name)
+                };
+                defined_here()
+                    || try_resolve_reference(index, name, index.opens_for_file(file), file)
+                        .is_some_and(|(resolved, confidence)| {
+                            resolved == qualified && confidence >= Confidence::Qualified
+                        })
+            }
+        })
+}
+
+/// References to members of the symbol written through it, such as
+/// `Utils.run` or `MyApp.Utils.run` when renaming `MyApp.Utils`, with the
+/// part of each that follows the old name (`.run`).
+///
+/// Anything written before the old name must be the end of the symbol's
+/// own parent, so `Other.Utils.run` is left alone.
+fn member_references<'a>(
+    index: &'a CodeIndex,
+    qualified: &'a str,
+    old_name: &'a str,
+) -> impl Iterator<Item = (&'a Reference, &'a str)> {
+    let parent = qualified.strip_suffix(old_name).unwrap_or_default();
+    let tails: HashSet<&str> = index
+        .symbols()
+        .filter_map(|s| s.qualified.strip_prefix(qualified))
+        .filter(|tail| tail.starts_with('.') || tail.starts_with("::"))
+        .collect();
+
+    index.references().filter_map(move |reference| {
+        let written = reference.name.as_str();
+        written.match_indices(old_name).find_map(|(i, _)| {
+            let (prefix, tail) = (&written[..i], &written[i + old_name.len()..]);
+            let qualifier_ok = prefix.is_empty()
+                || ((prefix.ends_with('.') || prefix.ends_with("::")) && parent.ends_with(prefix));
+            (qualifier_ok && tails.contains(tail)).then_some((reference, tail))
+        })
+    })
+}
+
+/// Byte offset at which each line of `text` starts.
+fn line_starts(text: &str) -> Vec<usize> {
+    std::iter::once(0)
+        .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+        .collect()
+}
+
+/// Find `old_name` at the end of a location's span, just before `tail`: the
+/// token the parser recorded, such as `run` or `Utils.run`.
+///
+/// Locations without a span (or spanning lines) fall back to
+/// [`locate_name`].
+fn locate_in_span(
+    text: &str,
+    line_starts: &[usize],
+    location: &Location,
+    old_name: &str,
+    tail: &str,
+) -> Option<RenameEdit> {
+    if location.end_line != location.line || location.end_column <= location.column {
+        return None;
+    }
+    let line_start = *line_starts.get((location.line as usize).checked_sub(1)?)?;
+    let token_start = line_start + location.column as usize - 1;
+    let token_end = line_start + location.end_column as usize - 1;
+    let end = token_end.checked_sub(tail.len())?;
+    let start = end.checked_sub(old_name.len())?;
+    if start < token_start || text.get(start..end)? != old_name || text.get(end..token_end)? != tail
+    {
+        return None;
+    }
+    // Don't rename `run` at the end of `prerun`
+    if text[token_start..start]
+        .chars()
+        .next_back()
+        .is_some_and(is_identifier_char)
+    {
+        return None;
+    }
+
+    let end_column = location.end_column - tail.len() as u32;
+    Some(RenameEdit {
+        line: location.line,
+        column: end_column - old_name.len() as u32,
+        end_column,
+    })
+}
+
+/// Find `old_name` at an index location where `written` (the name as the
+/// index recorded it, possibly qualified like `Utils.run`) starts, followed
+/// by `tail` within it.
+fn locate_name(
+    text: &str,
+    line_starts: &[usize],
+    line: u32,
+    column: u32,
+    written: &str,
+    old_name: &str,
+    tail: &str,
+) -> Option<RenameEdit> {
+    let line_start = *line_starts.get((line as usize).checked_sub(1)?)?;
+    let line_end = text[line_start..]
+        .find('\n')
+        .map_or(text.len(), |i| line_start + i);
+    let start = line_start + (column as usize).checked_sub(1)?;
+    let rest = text.get(start..line_end)?;
+
+    // The qualified form the reference was written as: rename the segment
+    // before the tail (the last one, unless it's a member reference)
+    let renamed = written
+        .strip_suffix(tail)
+        .filter(|head| head.ends_with(old_name));
+    let offset = if let (true, Some(head)) = (rest.starts_with(written), renamed) {
+        let prefix = &head[..head.len() - old_name.len()];
+        if !(prefix.is_empty() || prefix.ends_with('.') || prefix.ends_with("::")) {
+            return None;
+        }
+        prefix.len()
+    } else if tail.is_empty() && rest.starts_with(old_name) {
+        0
+    } else {
+        return None;
+    };
+
+    // Don't rename `run` inside `runAll`
+    let after = rest[offset + old_name.len()..].chars().next();
+    if after.is_some_and(is_identifier_char) {
+        return None;
+    }
+
+    let column = column + offset as u32;
+    Some(RenameEdit {
+        line,
+        column,
+        end_column: column + old_name.len() as u32,
+    })
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '\''
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name
+            .chars()
+            .all(|c| is_identifier_char(c) || c == '!' || c == '?')
+}
+
+/// Merge changed line indexes into `[start, end)` hunks with context.
+fn group_hunks(changed: &[usize], line_count: usize) -> Vec<(usize, usize)> {
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for &line in changed {
+        let start = line.saturating_sub(DIFF_CONTEXT);
+        let end = (line + DIFF_CONTEXT + 1).min(line_count);
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+    hunks
+}
+
+fn backup_name(file: &Path, root: &Path) -> PathBuf {
+    match file.strip_prefix(root) {
+        Ok(relative) => relative.to_path_buf(),
+        // Mirror absolute paths outside the root under the backup directory
+        Err(_) => file
+            .components()
+            .filter(|c| matches!(c, std::path::Component::Normal(_)))
+            .collect(),
+    }
+}
+
+fn temp_path(file: &Path) -> PathBuf {
+    let mut name = file.file_name().unwrap_or_default().to_os_string();
+    name.push(TEMP_SUFFIX);
+    file.with_file_name(name)
+}
+
+/// Write `contents` to `path` with the permissions of `like`.
+fn write_like(path: &Path, contents: &str, like: &Path) -> std::io::Result<()> {
+    std::fs::write(path, contents)?;
+    std::fs::set_permissions(path, std::fs::metadata(like)?.permissions())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ReferenceKind, Symbol, SymbolKind, Visibility};
+    use tempfile::TempDir;
+
+    fn workspace() -> (TempDir, CodeIndex) {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path().to_path_buf();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(
+            root.join("src/Utils.fs"),
+            "module Utils\n\nlet run x = x\nlet runAll xs = List.map run xs\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("src/App.fs"),
+            "module App\n\nlet main () =\n    Utils.run 1\n",
+        )
+        .unwrap();
+
+        let mut index = CodeIndex::with_root(root.clone());
+        index.add_symbol(Symbol::new(
+            "run".to_string(),
+            "Utils.run".to_string(),
+            SymbolKind::Function,
+            Location::new(PathBuf::from("src/Utils.fs"), 3, 5),
+            Visibility::Public,
+            "fsharp".to_string(),
+        ));
+        for (file, name, line, column) in [
+            ("src/Utils.fs", "run", 4, 26),
+            ("src/App.fs", "Utils.run", 4, 5),
+            // Stale: the file no longer has the name here
+            ("src/App.fs", "Utils.run", 1, 1),
+        ] {
+            index.add_reference(
+                PathBuf::from(file),
+                Reference {
+                    name: name.to_string(),
+                    location: Location::new(PathBuf::from(file), line, column),
+                    kind: ReferenceKind::Read,
+                },
+            );
+        }
+        (dir, index)
+    }
+
+    #[test]
+    fn test_plan_rename_edits_definition_and_references() {
+        let (dir, index) = workspace();
+        let plan = plan_rename(&index, "Utils.run", "execute").unwrap();
+
+        assert_eq!(plan.old_name, "run");
+        assert_eq!(plan.new_qualified(), "Utils.execute");
+        assert_eq!(plan.edit_count(), 3);
+        assert_eq!(plan.skipped.len(), 1);
+
+        let app = plan
+            .files
+            .iter()
+            .find(|f| f.file.ends_with("App.fs"))
+            .unwrap();
+        assert_eq!(
+            app.updated,
+            "module App\n\nlet main () =\n    Utils.execute 1\n"
+        );
+        let utils = plan
+            .files
+            .iter()
+            .find(|f| f.file.ends_with("Utils.fs"))
+            .unwrap();
+        assert_eq!(
+            utils.updated,
+            "module Utils\n\nlet execute x = x\nlet runAll xs = List.map execute xs\n"
+        );
+
+        let diff = plan.unified_diff();
+        assert!(diff.contains("--- a/src/App.fs\n+++ b/src/App.fs\n@@ -1,4 +1,4 @@\n"));
+        assert!(diff.contains("-    Utils.run 1\n+    Utils.execute 1\n"));
+
+        assert!(plan_rename(&index, "Utils.run", "not valid").is_err());
+        assert!(matches!(
+            plan_rename(&index, "Utils.missing", "x"),
+            Err(IndexError::SymbolNotFound(_))
+        ));
+        drop(dir);
+    }
+
+    #[test]
+    fn test_plan_rename_uses_reference_spans() {
+        let (dir, mut index) = workspace();
+        std::fs::write(
+            dir.path().join("src/Report.fs"),
+            "module Report\n\nlet total = Utils.run 2\nlet other = prerun 3\n",
+        )
+        .unwrap();
+        // Recorded under the short name but spanning the qualified token
+        for (line, column, end_column) in [(3, 13, 22), (4, 13, 19)] {
+            index.add_reference(
+                PathBuf::from("src/Report.fs"),
+                Reference {
+                    name: "run".to_string(),
+                    location: Location::with_end(
+                        PathBuf::from("src/Report.fs"),
+                        line,
+                        column,
+                        line,
+                        end_column,
+                    ),
+                    kind: ReferenceKind::Call,
+                },
+            );
+        }
+
+        let plan = plan_rename(&index, "Utils.run", "execute").unwrap();
+        let report = plan
+            .files
+            .iter()
+            .find(|f| f.file.ends_with("Report.fs"))
+            .unwrap();
+        assert_eq!(
+            report.edits,
+            vec![RenameEdit {
+                line: 3,
+                column: 19,
+                end_column: 22
+            }]
+        );
+        assert!(report.updated.contains("let total = Utils.execute 2\n"));
+        assert!(report.updated.contains("let other = prerun 3\n"));
+    }
+
+    #[test]
+    fn test_plan_rename_edits_references_written_through_a_module() {
+        let (dir, mut index) = workspace();
+        std::fs::write(
+            dir.path().join("src/Report.fs"),
+            "module Report\n\nlet total = Lib.Utils.run 2\n",
+        )
+        .unwrap();
+        index.add_symbol(Symbol::new(
+            "Utils".to_string(),
+            "Utils".to_string(),
+            SymbolKind::Module,
+            Location::new(PathBuf::from("src/Utils.fs"), 1, 8),
+            Visibility::Public,
+            "fsharp".to_string(),
+        ));
+        // A different module's `Utils`, so not a reference to this one
+        index.add_reference(
+            PathBuf::from("src/Report.fs"),
+            Reference {
+                name: "Lib.Utils.run".to_string(),
+                location: Location::new(PathBuf::from("src/Report.fs"), 3, 13),
+                kind: ReferenceKind::Call,
+            },
+        );
+
+        let plan = plan_rename(&index, "Utils", "Helpers").unwrap();
+        assert_eq!(plan.edit_count(), 2);
+        let app = plan
+            .files
+            .iter()
+            .find(|f| f.file.ends_with("App.fs"))
+            .unwrap();
+        assert_eq!(
+            app.updated,
+            "module App\n\nlet main () =\n    Helpers.run 1\n"
+        );
+        assert!(!plan.files.iter().any(|f| f.file.ends_with("Report.fs")));
+
+        // Planned against unsaved contents instead of the files on disk
+        let unsaved = "module App\n\nlet main () =\n    Utils.run 1 |> ignore\n";
+        let plan = plan_rename_with(&index, "Utils", "Helpers", &[], |path| {
+            if path.ends_with("App.fs") {
+                Ok(unsaved.to_string())
+            } else {
+                std::fs::read_to_string(path)
+            }
+        })
+        .unwrap();
+        let app = plan
+            .files
+            .iter()
+            .find(|f| f.file.ends_with("App.fs"))
+            .unwrap();
+        assert_eq!(app.original, unsaved);
+        assert!(app.updated.contains("Helpers.run 1 |> ignore"));
+    }
+
+    #[test]
+    fn test_plan_rename_leaves_same_named_members_of_other_modules() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path().to_path_buf();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        let shop = "module Shop\n\nopen Payments\n\nlet a = Billing.charge 1\nlet b = Payments.charge 2\nlet c = charge 3\n";
+        std::fs::write(root.join("src/Shop.fs"), shop).unwrap();
+        let mut index = CodeIndex::with_root(root.clone());
+        for module in ["Billing", "Payments"] {
+            let file = format!("src/{}.fs", module);
+            std::fs::write(
+                root.join(&file),
+                format!("module {}\n\nlet charge x = x\n", module),
+            )
+            .unwrap();
+            index.add_symbol(Symbol::new(
+                "charge".to_string(),
+                format!("{}.charge", module),
+                SymbolKind::Function,
+                Location::new(PathBuf::from(file), 3, 5),
+                Visibility::Public,
+                "fsharp".to_string(),
+            ));
+        }
+        index.add_open(PathBuf::from("src/Shop.fs"), "Payments".to_string());
+        for (name, line) in [("Billing.charge", 5), ("Payments.charge", 6), ("charge", 7)] {
+            index.add_reference(
+                PathBuf::from("src/Shop.fs"),
+                Reference {
+                    name: name.to_string(),
+                    location: Location::new(PathBuf::from("src/Shop.fs"), line, 9),
+                    kind: ReferenceKind::Call,
+                },
+            );
+        }
+
+        let plan = plan_rename(&index, "Billing.charge", "bill").unwrap();
+        assert_eq!(plan.edit_count(), 2);
+        assert!(plan.skipped.is_empty());
+        let shop = plan
+            .files
+            .iter()
+            .find(|f| f.file.ends_with("Shop.fs"))
+            .unwrap();
+        assert_eq!(
+            shop.updated,
+            "module Shop\n\nopen Payments\n\nlet a = Billing.bill 1\nlet b = Payments.charge 2\nlet c = charge 3\n"
+        );
+        assert!(!plan.files.iter().any(|f| f.file.ends_with("Payments.fs")));
+
+        // The open makes the bare call one of Payments'
+        let plan = plan_rename(&index, "Payments.charge", "pay").unwrap();
+        assert_eq!(plan.edit_count(), 3);
+    }
+
+    #[test]
+    fn test_plan_rename_keeps_string_literal_edits_apart() {
+        let (dir, index) = workspace();
+        std::fs::write(
+            dir.path().join("src/Wiring.fs"),
+            "module Wiring\n\nlet handler = resolve \"Utils.run\"\n",
+        )
+        .unwrap();
+        let strings = [Reference {
+            name: "Utils.run".to_string(),
+            location: Location::with_end(dir.path().join("src/Wiring.fs"), 3, 24, 3, 33),
+            kind: ReferenceKind::Read,
+        }];
+
+        let plan = plan_rename_with(&index, "Utils.run", "execute", &strings, |path| {
+            std::fs::read_to_string(path)
+        })
+        .unwrap();
+        assert_eq!(plan.edit_count(), 4);
+        assert_eq!(plan.string_edit_count(), 1);
+        let wiring = plan
+            .files
+            .iter()
+            .find(|f| f.file.ends_with("Wiring.fs"))
+            .unwrap();
+        assert!(wiring.edits.is_empty());
+        assert_eq!(
+            wiring.string_edits,
+            vec![RenameEdit {
+                line: 3,
+                column: 30,
+                end_column: 33
+            }]
+        );
+        assert!(wiring.updated.contains("resolve \"Utils.execute\""));
+        assert!(plan
+            .unified_diff()
+            .contains("+let handler = resolve \"Utils.execute\"\n"));
+    }
+
+    #[test]
+    fn test_apply_backs_up_and_refuses_stale_plans() {
+        let (dir, index) = workspace();
+        let plan = plan_rename(&index, "Utils.run", "execute").unwrap();
+        let backup = dir.path().join("backup");
+
+        plan.apply(&backup).unwrap();
+        let app = std::fs::read_to_string(dir.path().join("src/App.fs")).unwrap();
+        assert!(app.contains("Utils.execute 1"));
+        assert!(std::fs::read_to_string(backup.join("src/App.fs"))
+            .unwrap()
+            .contains("Utils.run 1"));
+        assert!(!dir.path().join("src/App.fs.rkt-rename").exists());
+
+        // Files no longer match the plan, so nothing is written
+        assert!(plan.apply(&dir.path().join("backup2")).is_err());
+        assert!(!dir.path().join("backup2").exists());
+    }
+}
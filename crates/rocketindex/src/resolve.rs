@@ -9,10 +9,10 @@
 use std::path::Path;
 
 use crate::languages::{
-    c, cpp, csharp, fsharp, java, javascript, kotlin, objc, php, ruby, swift, typescript,
+    c, cpp, csharp, fsharp, java, javascript, kotlin, objc, php, ruby, rust, swift, typescript,
 };
 use crate::type_cache::TypeMember;
-use crate::{CodeIndex, Symbol};
+use crate::{CodeIndex, Confidence, Symbol};
 
 /// Result of name resolution
 #[derive(Debug, Clone)]
@@ -37,6 +37,35 @@ pub enum ResolutionPath {
     /// Resolved via type-aware member access (RFC-001)
     /// Contains the type name that the member was resolved on
     ViaMemberAccess { type_name: String },
+    /// Resolved through a framework naming convention (Rails mode), which
+    /// names the convention, e.g. `rails model`
+    Convention(String),
+}
+
+impl ResolutionPath {
+    /// Confidence implied by how the symbol was found.
+    ///
+    /// Direct qualified matches and type-aware member access are exact; anything
+    /// found through scope rules (opens, enclosing modules) is qualified.
+    /// Naming conventions only suggest a symbol, so they're heuristic.
+    #[must_use]
+    pub fn confidence(&self) -> Confidence {
+        match self {
+            ResolutionPath::Qualified | ResolutionPath::ViaMemberAccess { .. } => Confidence::Exact,
+            ResolutionPath::ViaOpen(_)
+            | ResolutionPath::SameModule
+            | ResolutionPath::ParentModule(_) => Confidence::Qualified,
+            ResolutionPath::Convention(_) => Confidence::Heuristic,
+        }
+    }
+}
+
+impl ResolveResult<'_> {
+    /// Confidence of this resolution (see [`ResolutionPath::confidence`]).
+    #[must_use]
+    pub fn confidence(&self) -> Confidence {
+        self.resolution_path.confidence()
+    }
 }
 
 /// Result of resolving a member access expression (e.g., `user.Name`)
@@ -80,6 +109,17 @@ impl CodeIndex {
     /// The resolved symbol if found, None otherwise
     #[must_use]
     pub fn resolve(&self, name: &str, from_file: &Path) -> Option<ResolveResult<'_>> {
+        // A name written through one of the file's module aliases means the
+        // aliased path; the alias shadows anything of the same name
+        if let Some(expanded) = self.expand_alias(name, from_file) {
+            if let Some(result) = self.resolve_unaliased(&expanded, from_file) {
+                return Some(result);
+            }
+        }
+        self.resolve_unaliased(name, from_file)
+    }
+
+    fn resolve_unaliased(&self, name: &str, from_file: &Path) -> Option<ResolveResult<'_>> {
         let extension = from_file
             .extension()
             .and_then(|e| e.to_str())
@@ -99,6 +139,7 @@ impl CodeIndex {
             "php" => php::PhpResolver.resolve(self, name, from_file),
             "swift" => swift::SwiftResolver.resolve(self, name, from_file),
             "rb" => ruby::RubyResolver.resolve(self, name, from_file),
+            "rs" => rust::RustResolver.resolve(self, name, from_file),
             "ts" | "tsx" => typescript::TypeScriptResolver.resolve(self, name, from_file),
             "js" | "jsx" | "mjs" | "cjs" => {
                 javascript::JavaScriptResolver.resolve(self, name, from_file)
@@ -111,6 +152,15 @@ impl CodeIndex {
     /// Resolve a dotted name like "PaymentService.processPayment"
     #[must_use]
     pub fn resolve_dotted(&self, name: &str, from_file: &Path) -> Option<ResolveResult<'_>> {
+        if let Some(expanded) = self.expand_alias(name, from_file) {
+            if let Some(result) = self.resolve_dotted_unaliased(&expanded, from_file) {
+                return Some(result);
+            }
+        }
+        self.resolve_dotted_unaliased(name, from_file)
+    }
+
+    fn resolve_dotted_unaliased(&self, name: &str, from_file: &Path) -> Option<ResolveResult<'_>> {
         let extension = from_file
             .extension()
             .and_then(|e| e.to_str())
@@ -130,6 +180,7 @@ impl CodeIndex {
             "php" => php::PhpResolver.resolve_dotted(self, name, from_file),
             "swift" => swift::SwiftResolver.resolve_dotted(self, name, from_file),
             "rb" => ruby::RubyResolver.resolve_dotted(self, name, from_file),
+            "rs" => rust::RustResolver.resolve_dotted(self, name, from_file),
             "ts" | "tsx" => typescript::TypeScriptResolver.resolve_dotted(self, name, from_file),
             "js" | "jsx" | "mjs" | "cjs" => {
                 javascript::JavaScriptResolver.resolve_dotted(self, name, from_file)
@@ -0,0 +1,331 @@
+//! Source maps (version 3), for mapping bundled or minified JavaScript back
+//! to the sources it was built from.
+//!
+//! A generated file names its map in a trailing
+//! `//# sourceMappingURL=...` comment, either a path relative to the file
+//! or an inline `data:` URL; without one, `file.js.map` next to the file is
+//! tried. Positions are 1-indexed in and out, as stack traces print them,
+//! while the map itself counts from zero.
+
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Deserialize;
+
+/// A parsed source map.
+#[derive(Debug, Clone)]
+pub struct SourceMap {
+    /// Original sources, resolved against the map's directory
+    sources: Vec<PathBuf>,
+    names: Vec<String>,
+    /// Segments of each generated line, by generated column
+    lines: Vec<Vec<Segment>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Segment {
+    column: u32,
+    source: usize,
+    line: u32,
+    source_column: u32,
+    name: Option<usize>,
+}
+
+/// Where a generated position came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OriginalPosition {
+    pub file: PathBuf,
+    pub line: u32,
+    pub column: u32,
+    /// The identifier at the position, if the map names one
+    pub name: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawMap {
+    version: u32,
+    #[serde(default)]
+    source_root: Option<String>,
+    sources: Vec<Option<String>>,
+    #[serde(default)]
+    names: Vec<String>,
+    mappings: String,
+}
+
+impl SourceMap {
+    /// Parse the JSON of a map read from `dir`, against which relative
+    /// sources resolve.
+    pub fn parse(json: &str, dir: &Path) -> Result<Self> {
+        let raw: RawMap = serde_json::from_str(json).context("Invalid source map")?;
+        if raw.version != 3 {
+            bail!("Unsupported source map version {}", raw.version);
+        }
+        let root = raw.source_root.unwrap_or_default();
+        let sources = raw
+            .sources
+            .iter()
+            .map(|source| resolve_source(dir, &root, source.as_deref().unwrap_or_default()))
+            .collect();
+        Ok(Self {
+            sources,
+            names: raw.names,
+            lines: decode_mappings(&raw.mappings)?,
+        })
+    }
+
+    /// The map of the generated file `file`, if it has one.
+    pub fn load_for(file: &Path) -> Result<Option<Self>> {
+        let dir = file.parent().unwrap_or(Path::new(""));
+        let generated = std::fs::read_to_string(file)
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+        let url = generated
+            .lines()
+            .rev()
+            .take(5)
+            .find_map(|line| {
+                let line = line.trim();
+                line.strip_prefix("//# sourceMappingURL=")
+                    .or_else(|| line.strip_prefix("//@ sourceMappingURL="))
+            })
+            .map(str::trim);
+
+        let (json, map_dir) = match url {
+            Some(url) if url.starts_with("data:") => {
+                let (_, data) = url
+                    .split_once(";base64,")
+                    .context("Inline source map isn't base64")?;
+                let bytes = decode_base64(data).context("Invalid base64 in inline source map")?;
+                (String::from_utf8(bytes)?, dir.to_path_buf())
+            }
+            Some(url) if !url.contains("://") => {
+                let path = normalize(&dir.join(url));
+                if !path.is_file() {
+                    return Ok(None);
+                }
+                let json = std::fs::read_to_string(&path)?;
+                (json, path.parent().unwrap_or(dir).to_path_buf())
+            }
+            Some(_) => return Ok(None),
+            None => {
+                let mut sibling = file.as_os_str().to_owned();
+                sibling.push(".map");
+                let path = PathBuf::from(sibling);
+                if !path.is_file() {
+                    return Ok(None);
+                }
+                (std::fs::read_to_string(&path)?, dir.to_path_buf())
+            }
+        };
+        Self::parse(&json, &map_dir).map(Some)
+    }
+
+    /// The original position of the generated `line` and `column`, from
+    /// the closest mapping at or before the column.
+    #[must_use]
+    pub fn lookup(&self, line: u32, column: u32) -> Option<OriginalPosition> {
+        let segments = self.lines.get(line.checked_sub(1)? as usize)?;
+        let column = column.saturating_sub(1);
+        let at = segments.partition_point(|segment| segment.column <= column);
+        let segment = segments.get(at.checked_sub(1)?)?;
+        Some(OriginalPosition {
+            file: self.sources.get(segment.source)?.clone(),
+            line: segment.line + 1,
+            column: segment.source_column + 1,
+            name: segment.name.and_then(|name| self.names.get(name).cloned()),
+        })
+    }
+}
+
+/// Where a source named in a map is: `sourceRoot` plus the source, with
+/// URL prefixes bundlers add (`webpack://app/`, `file://`) removed, and
+/// relative to the map's directory, or to the current directory if only
+/// that exists.
+fn resolve_source(dir: &Path, root: &str, source: &str) -> PathBuf {
+    let joined = if root.is_empty() || source.contains("://") {
+        source.to_string()
+    } else {
+        format!("{}/{}", root.trim_end_matches('/'), source)
+    };
+    let path = match joined.split_once("://") {
+        Some(("file", path)) => path,
+        // The part before the first slash names the bundle, not a directory
+        Some((_, rest)) => rest.split_once('/').map_or(rest, |(_, path)| path),
+        None => joined.as_str(),
+    };
+    let path = Path::new(path);
+    let beside_map = normalize(&dir.join(path));
+    if !beside_map.exists() {
+        if let Ok(cwd) = std::env::current_dir() {
+            let in_cwd = normalize(&cwd.join(path));
+            if in_cwd.exists() {
+                return in_cwd;
+            }
+        }
+    }
+    beside_map
+}
+
+/// `path` with `.` and `..` resolved without touching the file system.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// The value of a base64 digit.
+fn base64_digit(byte: u8) -> Option<u32> {
+    match byte {
+        b'A'..=b'Z' => Some(u32::from(byte - b'A')),
+        b'a'..=b'z' => Some(u32::from(byte - b'a') + 26),
+        b'0'..=b'9' => Some(u32::from(byte - b'0') + 52),
+        b'+' | b'-' => Some(62),
+        b'/' | b'_' => Some(63),
+        _ => None,
+    }
+}
+
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(text.len() * 3 / 4);
+    let (mut buffer, mut bits) = (0u32, 0u32);
+    for byte in text
+        .bytes()
+        .filter(|&b| b != b'=' && !b.is_ascii_whitespace())
+    {
+        buffer = (buffer << 6) | base64_digit(byte)?;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Some(bytes)
+}
+
+/// Decode the `mappings` field: one `;`-separated group per generated
+/// line, of `,`-separated segments of base64 VLQ fields, each relative to
+/// the same field of the previous segment (the generated column only
+/// within a line).
+fn decode_mappings(mappings: &str) -> Result<Vec<Vec<Segment>>> {
+    let mut lines = Vec::new();
+    let (mut source, mut line, mut source_column, mut name) = (0i64, 0i64, 0i64, 0i64);
+    for group in mappings.split(';') {
+        let mut segments = Vec::new();
+        let mut column = 0i64;
+        for text in group.split(',').filter(|text| !text.is_empty()) {
+            // Deltas that push a field out of range are as broken as bad digits
+            let invalid = || anyhow!("Invalid mapping '{}'", text);
+            let fields = decode_vlq(text).ok_or_else(invalid)?;
+            column = column.checked_add(fields[0]).ok_or_else(invalid)?;
+            // A lone column maps to no source
+            if fields.len() < 4 {
+                continue;
+            }
+            source = source.checked_add(fields[1]).ok_or_else(invalid)?;
+            line = line.checked_add(fields[2]).ok_or_else(invalid)?;
+            source_column = source_column.checked_add(fields[3]).ok_or_else(invalid)?;
+            let named = match fields.get(4) {
+                Some(&delta) => {
+                    name = name.checked_add(delta).ok_or_else(invalid)?;
+                    Some(usize::try_from(name).map_err(|_| invalid())?)
+                }
+                None => None,
+            };
+            segments.push(Segment {
+                column: u32::try_from(column).map_err(|_| invalid())?,
+                source: usize::try_from(source).map_err(|_| invalid())?,
+                line: u32::try_from(line).map_err(|_| invalid())?,
+                source_column: u32::try_from(source_column).map_err(|_| invalid())?,
+                name: named,
+            });
+        }
+        segments.sort_by_key(|segment| segment.column);
+        lines.push(segments);
+    }
+    Ok(lines)
+}
+
+/// The signed values of a run of base64 VLQ digits: five bits each, low
+/// first, with the sixth bit set on all but a value's last digit and the
+/// lowest bit of the value its sign.
+fn decode_vlq(text: &str) -> Option<Vec<i64>> {
+    let mut values = Vec::new();
+    let (mut value, mut shift) = (0i64, 0u32);
+    for byte in text.bytes() {
+        let digit = i64::from(base64_digit(byte)?);
+        value |= (digit & 0b11111) << shift;
+        if digit & 0b100000 != 0 {
+            shift += 5;
+            if shift > 60 {
+                return None;
+            }
+            continue;
+        }
+        values.push(if value & 1 == 1 {
+            -(value >> 1)
+        } else {
+            value >> 1
+        });
+        value = 0;
+        shift = 0;
+    }
+    (shift == 0 && !values.is_empty()).then_some(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_vlq_and_looks_up_positions() {
+        assert_eq!(decode_vlq("AAgBC"), Some(vec![0, 0, 16, 1]));
+        assert_eq!(decode_vlq("D"), Some(vec![-1]));
+        assert_eq!(decode_vlq("g"), None);
+        assert_eq!(decode_base64("eyJhIjoxfQ=="), Some(b"{\"a\":1}".to_vec()));
+
+        // Line 1: column 0 -> app.ts 1:0, column 9 -> app.ts 2:2 named
+        // "charge"; line 2: column 4 -> util.ts 5:0
+        let map = SourceMap::parse(
+            r#"{"version":3,"sourceRoot":"","sources":["webpack://shop/./src/app.ts","../src/util.ts"],"names":["charge"],"mappings":"AAAA,SACEA;ICGF"}"#,
+            Path::new("/ws/dist"),
+        )
+        .unwrap();
+        assert_eq!(
+            map.lookup(1, 12),
+            Some(OriginalPosition {
+                file: PathBuf::from("/ws/dist/src/app.ts"),
+                line: 2,
+                column: 3,
+                name: Some("charge".to_string()),
+            })
+        );
+        assert_eq!(map.lookup(1, 1).unwrap().line, 1);
+        let util = map.lookup(2, 5).unwrap();
+        assert_eq!(util.file, PathBuf::from("/ws/src/util.ts"));
+        assert_eq!(util.line, 5);
+        assert_eq!(map.lookup(3, 1), None);
+    }
+
+    #[test]
+    fn rejects_mappings_that_overflow() {
+        // The largest line delta there is; enough of them overflow the line
+        let huge = "+//////////f";
+        assert_eq!(decode_vlq(huge), Some(vec![(1 << 59) - 1]));
+        let mappings = vec![format!("AA{huge}A"); 17].join(",");
+        let error = decode_mappings(&mappings).unwrap_err().to_string();
+        assert!(error.starts_with("Invalid mapping"), "{}", error);
+
+        // A field that goes negative
+        let error = decode_mappings("AADA").unwrap_err().to_string();
+        assert!(error.starts_with("Invalid mapping"), "{}", error);
+    }
+}
@@ -4,28 +4,81 @@
 //! a dependency graph. This is useful for understanding code flow and
 //! identifying which symbols are reachable from a given entry point.
 
-use std::collections::{HashSet, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::path::Path;
 
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
 use crate::index::Reference;
-use crate::{CodeIndex, Symbol};
+use crate::{CodeIndex, Confidence, Location, Symbol, SymbolKind};
 
 /// A node in the spider's dependency graph.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SpiderNode {
     /// The symbol at this node
     pub symbol: Symbol,
     /// Depth from the entry point (0 = entry point itself)
     pub depth: usize,
+    /// Confidence of the edge that reached this node (`Exact` for the entry point)
+    pub confidence: Confidence,
+    /// Qualified name of the node this one was first reached from (`None`
+    /// for the entry point). Following parents gives a shortest path back.
+    #[serde(default)]
+    pub parent: Option<String>,
+    /// Attached repository the symbol is defined in (`None` for the
+    /// workspace's own; see [`crate::federation`])
+    #[serde(default)]
+    pub source: Option<String>,
+}
+
+/// A call between two spidered symbols.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SpiderEdge {
+    /// Qualified name of the caller
+    pub from: String,
+    /// Qualified name of the callee
+    pub to: String,
+    /// Where the caller refers to the callee, in the order found
+    #[serde(default)]
+    pub sites: Vec<Location>,
+}
+
+/// A referenced name the workspace doesn't define.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExternalNode {
+    /// The name as referenced
+    pub name: String,
+    /// Package or assembly it most likely comes from (see
+    /// [`ExternalIndex::package_of`](crate::external_index::ExternalIndex::package_of))
+    pub package: Option<String>,
+    /// Qualified names of the spidered symbols referring to it
+    pub referenced_from: Vec<String>,
+}
+
+/// External nodes from one package, from [`SpiderResult::external_by_package`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExternalPackage<'a> {
+    /// `None` for names that couldn't be attributed to a package
+    pub package: Option<&'a str>,
+    pub nodes: Vec<&'a ExternalNode>,
 }
 
 /// Result of spidering from an entry point.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct SpiderResult {
     /// Nodes visited in breadth-first order
     pub nodes: Vec<SpiderNode>,
+    /// Caller -> callee edges between visited nodes, in discovery order
+    pub edges: Vec<SpiderEdge>,
     /// Symbols that couldn't be resolved (external or undefined)
     pub unresolved: Vec<String>,
+    /// Unresolved references, with the package each is attributed to
+    #[serde(default)]
+    pub external_nodes: Vec<ExternalNode>,
+    /// Whether the traversal stopped at its node cap with more left to visit
+    #[serde(default)]
+    pub truncated: bool,
 }
 
 impl SpiderResult {
@@ -46,8 +99,78 @@ impl SpiderResult {
     pub fn at_depth(&self, depth: usize) -> Vec<&SpiderNode> {
         self.nodes.iter().filter(|n| n.depth == depth).collect()
     }
+
+    /// The edge between two nodes, if there is one.
+    pub fn edge(&self, from: &str, to: &str) -> Option<&SpiderEdge> {
+        self.edges.iter().find(|e| e.from == from && e.to == to)
+    }
+
+    /// External nodes grouped by package: attributed packages by name,
+    /// then the names no package claims.
+    pub fn external_by_package(&self) -> Vec<ExternalPackage<'_>> {
+        let mut groups: Vec<ExternalPackage<'_>> = Vec::new();
+        for node in &self.external_nodes {
+            let package = node.package.as_deref();
+            match groups.iter_mut().find(|g| g.package == package) {
+                Some(group) => group.nodes.push(node),
+                None => groups.push(ExternalPackage {
+                    package,
+                    nodes: vec![node],
+                }),
+            }
+        }
+        groups.sort_by_key(|g| (g.package.is_none(), g.package));
+        groups
+    }
+
+    fn add_external(&mut self, name: &str, package: Option<String>, from: &str) {
+        let node = match self.external_nodes.iter_mut().find(|n| n.name == name) {
+            Some(node) => node,
+            None => {
+                self.external_nodes.push(ExternalNode {
+                    name: name.to_string(),
+                    package,
+                    referenced_from: Vec::new(),
+                });
+                self.external_nodes.last_mut().expect("just pushed")
+            }
+        };
+        if !node.referenced_from.iter().any(|f| f == from) {
+            node.referenced_from.push(from.to_string());
+        }
+    }
+
+    fn add_edge(&mut self, from: &str, to: &str, sites: &[Location]) {
+        let edge = match self.edges.iter_mut().find(|e| e.from == from && e.to == to) {
+            Some(edge) => edge,
+            None => {
+                self.edges.push(SpiderEdge {
+                    from: from.to_string(),
+                    to: to.to_string(),
+                    sites: Vec::new(),
+                });
+                self.edges.last_mut().expect("just pushed")
+            }
+        };
+        for site in sites {
+            if !edge.sites.contains(site) {
+                edge.sites.push(site.clone());
+            }
+        }
+    }
 }
 
+/// A symbol waiting to be visited: qualified name, depth, confidence of the
+/// edge that reached it, and the node it was reached from.
+type QueueEntry = (String, usize, Confidence, Option<String>);
+
+/// Most nodes [`reverse_spider`] returns; see [`reverse_spider_limited`].
+pub const MAX_REVERSE_NODES: usize = 10_000;
+
+/// Lookups (frontier symbols or references to one symbol) below which a
+/// reverse spider level is walked on the calling thread.
+const PARALLEL_THRESHOLD: usize = 64;
+
 /// Spider from an entry point symbol, following references up to a maximum depth.
 ///
 /// # Arguments
@@ -59,14 +182,28 @@ impl SpiderResult {
 /// A `SpiderResult` containing all reachable symbols in breadth-first order.
 #[must_use]
 pub fn spider(index: &CodeIndex, entry_point: &str, max_depth: usize) -> SpiderResult {
+    spider_with_confidence(index, entry_point, max_depth, Confidence::StringMatch)
+}
+
+/// Spider from an entry point, only following edges resolved with at least `min_confidence`.
+///
+/// Raising the minimum trades recall for precision: heuristic (name-only)
+/// matches are the main source of false edges in large codebases.
+#[must_use]
+pub fn spider_with_confidence(
+    index: &CodeIndex,
+    entry_point: &str,
+    max_depth: usize,
+    min_confidence: Confidence,
+) -> SpiderResult {
     let mut result = SpiderResult::new();
     let mut visited: HashSet<String> = HashSet::new();
-    let mut queue: VecDeque<(String, usize)> = VecDeque::new();
+    let mut queue: VecDeque<QueueEntry> = VecDeque::new();
 
     // Start with the entry point
-    queue.push_back((entry_point.to_string(), 0));
+    queue.push_back((entry_point.to_string(), 0, Confidence::Exact, None));
 
-    while let Some((qualified_name, depth)) = queue.pop_front() {
+    while let Some((qualified_name, depth, confidence, parent)) = queue.pop_front() {
         // Skip if already visited
         if visited.contains(&qualified_name) {
             continue;
@@ -79,6 +216,9 @@ pub fn spider(index: &CodeIndex, entry_point: &str, max_depth: usize) -> SpiderR
                 result.nodes.push(SpiderNode {
                     symbol: symbol.clone(),
                     depth,
+                    confidence,
+                    parent,
+                    source: index.provenance(&symbol.location.file).map(str::to_string),
                 });
 
                 // Don't follow references beyond max depth
@@ -92,17 +232,29 @@ pub fn spider(index: &CodeIndex, entry_point: &str, max_depth: usize) -> SpiderR
 
                 // Try to resolve each reference and add to queue
                 for reference in references {
-                    if let Some(resolved) =
+                    if let Some((resolved, confidence)) =
                         try_resolve_reference(index, &reference.name, opens, &symbol.location.file)
                     {
-                        if !visited.contains(&resolved) {
-                            queue.push_back((resolved, depth + 1));
+                        if confidence >= min_confidence {
+                            result.add_edge(
+                                &symbol.qualified,
+                                &resolved,
+                                std::slice::from_ref(&reference.location),
+                            );
+                            if !visited.contains(&resolved) {
+                                let parent = Some(symbol.qualified.clone());
+                                queue.push_back((resolved, depth + 1, confidence, parent));
+                            }
                         }
                     } else {
                         // Track unresolved references
                         if !result.unresolved.contains(&reference.name) {
                             result.unresolved.push(reference.name.clone());
                         }
+                        let package = index
+                            .external_index()
+                            .and_then(|external| external.package_of(&reference.name, opens));
+                        result.add_external(&reference.name, package, &symbol.qualified);
                     }
                 }
             }
@@ -121,23 +273,44 @@ pub fn spider(index: &CodeIndex, entry_point: &str, max_depth: usize) -> SpiderR
 
 /// Try to resolve a reference name to a qualified symbol name.
 ///
-/// This attempts resolution in order:
+/// A name written through one of the file's module aliases is tried with the
+/// alias expanded first. Then this attempts resolution in order:
 /// 1. Direct match (already qualified) - respecting compilation order
 /// 2. Via open statements - respecting compilation order
-/// 3. Partial match on the name - respecting compilation order
+/// 3. A member of a value whose type is known
+/// 4. Partial match on the name - respecting compilation order
+///
+/// Direct matches are `Exact` and opens are `Qualified`; partial matches are
+/// scored with [`Confidence::of_match`].
 ///
 /// The `from_file` parameter is used to respect F# compilation order:
 /// a symbol is only visible if its defining file comes before `from_file`.
-fn try_resolve_reference(
+pub(crate) fn try_resolve_reference(
     index: &CodeIndex,
     name: &str,
     opens: &[String],
     from_file: &Path,
-) -> Option<String> {
+) -> Option<(String, Confidence)> {
+    // Names written through a module alias (`Db.connect` after
+    // `module Db = MyApp.Data.Database`) resolve as the aliased path
+    if let Some(expanded) = index.expand_alias(name, from_file) {
+        if let Some(resolved) = try_resolve_unaliased(index, &expanded, opens, from_file) {
+            return Some(resolved);
+        }
+    }
+    try_resolve_unaliased(index, name, opens, from_file)
+}
+
+fn try_resolve_unaliased(
+    index: &CodeIndex,
+    name: &str,
+    opens: &[String],
+    from_file: &Path,
+) -> Option<(String, Confidence)> {
     // Try direct match first (respecting compilation order)
     if let Some(symbol) = index.get(name) {
         if index.can_reference(from_file, &symbol.location.file) {
-            return Some(name.to_string());
+            return Some((name.to_string(), Confidence::Exact));
         }
     }
 
@@ -146,24 +319,58 @@ fn try_resolve_reference(
         let qualified = format!("{}.{}", open, name);
         if let Some(symbol) = index.get(&qualified) {
             if index.can_reference(from_file, &symbol.location.file) {
-                return Some(qualified);
+                return Some((qualified, Confidence::Qualified));
             }
         }
     }
 
+    // Members reached through a value of a known type, e.g. the `maybe.Bind`
+    // an F# computation expression calls on its builder
+    if let Some((receiver, member)) = name.rsplit_once('.') {
+        if let Some(qualified) = resolve_value_member(index, receiver, member, opens, from_file) {
+            return Some((qualified, Confidence::Qualified));
+        }
+    }
+
     // Try partial match - look for symbols ending with this name
     // This handles cases like "List.map" where we need to find "Microsoft.FSharp.Collections.List.map"
     // Filter by compilation order
     let search_results = index.search(name);
     for matched in search_results {
         if index.can_reference(from_file, &matched.location.file) {
-            return Some(matched.qualified.clone());
+            return Some((
+                matched.qualified.clone(),
+                Confidence::of_match(name, &matched.qualified),
+            ));
         }
     }
 
     None
 }
 
+/// Resolve `member` on the type of the value `receiver`, going by the type
+/// recorded in the value's signature.
+fn resolve_value_member(
+    index: &CodeIndex,
+    receiver: &str,
+    member: &str,
+    opens: &[String],
+    from_file: &Path,
+) -> Option<String> {
+    let (value, _) = try_resolve_reference(index, receiver, opens, from_file)?;
+    let value = index.get(&value)?;
+    if value.kind != SymbolKind::Value {
+        return None;
+    }
+    let (value_type, _) =
+        try_resolve_reference(index, value.signature.as_deref()?, opens, from_file)?;
+    let qualified = format!("{}.{}", value_type, member);
+    let symbol = index.get(&qualified)?;
+    index
+        .can_reference(from_file, &symbol.location.file)
+        .then_some(qualified)
+}
+
 /// Spider from a file's entry points (top-level definitions).
 ///
 /// This finds all top-level symbols in the file and spiders from each.
@@ -185,11 +392,20 @@ pub fn spider_from_file(index: &CodeIndex, file: &Path, max_depth: usize) -> Spi
             }
         }
 
+        for edge in sub_result.edges {
+            combined_result.add_edge(&edge.from, &edge.to, &edge.sites);
+        }
+
         for unresolved in sub_result.unresolved {
             if !combined_result.unresolved.contains(&unresolved) {
                 combined_result.unresolved.push(unresolved);
             }
         }
+        for node in sub_result.external_nodes {
+            for from in &node.referenced_from {
+                combined_result.add_external(&node.name, node.package.clone(), from);
+            }
+        }
     }
 
     combined_result
@@ -206,58 +422,178 @@ pub fn spider_from_file(index: &CodeIndex, file: &Path, max_depth: usize) -> Spi
 /// * `max_depth` - Maximum depth to traverse (0 = only entry point)
 ///
 /// # Returns
-/// A `SpiderResult` containing all callers in breadth-first order.
+/// A `SpiderResult` containing callers in breadth-first order, at most
+/// [`MAX_REVERSE_NODES`] of them.
 #[must_use]
 pub fn reverse_spider(index: &CodeIndex, entry_point: &str, max_depth: usize) -> SpiderResult {
-    let mut result = SpiderResult::new();
-    let mut visited: HashSet<String> = HashSet::new();
-    let mut queue: VecDeque<(String, usize)> = VecDeque::new();
+    reverse_spider_with_confidence(index, entry_point, max_depth, Confidence::StringMatch)
+}
 
-    // Start with the entry point
-    queue.push_back((entry_point.to_string(), 0));
+/// Reverse spider that ignores references matched with less than `min_confidence`.
+///
+/// See [`Confidence::of_match`] for how a reference is scored against its target.
+/// Returns at most [`MAX_REVERSE_NODES`] nodes.
+#[must_use]
+pub fn reverse_spider_with_confidence(
+    index: &CodeIndex,
+    entry_point: &str,
+    max_depth: usize,
+    min_confidence: Confidence,
+) -> SpiderResult {
+    reverse_spider_limited(
+        index,
+        entry_point,
+        max_depth,
+        min_confidence,
+        MAX_REVERSE_NODES,
+    )
+}
 
-    while let Some((qualified_name, depth)) = queue.pop_front() {
-        // Skip if already visited
-        if visited.contains(&qualified_name) {
-            continue;
-        }
-        visited.insert(qualified_name.clone());
+/// Reverse spider returning at most `max_nodes` nodes.
+///
+/// Callers are found a level at a time. Large levels, and symbols with many
+/// references, are looked up on rayon's thread pool, so a logging helper
+/// with tens of thousands of callers doesn't walk them one by one. The
+/// result doesn't depend on scheduling: each symbol's callers are taken in
+/// source order and merged in frontier order. When the cap is reached, the
+/// nodes found so far are returned with [`SpiderResult::truncated`] set.
+#[must_use]
+pub fn reverse_spider_limited(
+    index: &CodeIndex,
+    entry_point: &str,
+    max_depth: usize,
+    min_confidence: Confidence,
+    max_nodes: usize,
+) -> SpiderResult {
+    let mut result = SpiderResult::new();
+    let Some(symbol) = index.get(entry_point) else {
+        result.unresolved.push(entry_point.to_string());
+        return result;
+    };
+    result.nodes.push(SpiderNode {
+        symbol: symbol.clone(),
+        depth: 0,
+        confidence: Confidence::Exact,
+        parent: None,
+        source: index.provenance(&symbol.location.file).map(str::to_string),
+    });
+
+    let mut visited: HashSet<String> = HashSet::from([entry_point.to_string()]);
+    let mut frontier = vec![entry_point.to_string()];
+    let mut depth = 0;
+    while depth < max_depth && !frontier.is_empty() {
+        depth += 1;
+        let lookup = |callee: &String| callers_of(index, callee, min_confidence);
+        let callers: Vec<Vec<Caller<'_>>> = if frontier.len() >= PARALLEL_THRESHOLD {
+            frontier.par_iter().map(lookup).collect()
+        } else {
+            frontier.iter().map(lookup).collect()
+        };
+
+        let mut next = Vec::new();
+        for (callee, callers) in frontier.iter().zip(callers) {
+            // A lambda is called from the code that defines it
+            let owner = index.get(callee).and_then(|lambda| {
+                let owner = index.get(lambda.lambda_owner()?)?;
+                Some((owner, Confidence::Exact, &lambda.location))
+            });
+            let callers = callers
+                .into_iter()
+                .map(|(caller, confidence, reference)| (caller, confidence, &reference.location))
+                .chain(owner);
+
+            // Edges into `callee` are only found here, so they can be
+            // merged without searching all of `result.edges`
+            let mut edges: HashMap<&str, usize> = HashMap::new();
+            for (caller, confidence, site) in callers {
+                if !visited.contains(&caller.qualified) {
+                    if result.nodes.len() >= max_nodes {
+                        result.truncated = true;
+                        continue;
+                    }
+                    visited.insert(caller.qualified.clone());
+                    let symbol = index.get(&caller.qualified).unwrap_or(caller);
+                    result.nodes.push(SpiderNode {
+                        symbol: symbol.clone(),
+                        depth,
+                        confidence,
+                        parent: Some(callee.clone()),
+                        source: index.provenance(&symbol.location.file).map(str::to_string),
+                    });
+                    next.push(caller.qualified.clone());
+                }
 
-        // Try to find the symbol
-        match index.get(&qualified_name) {
-            Some(symbol) => {
-                result.nodes.push(SpiderNode {
-                    symbol: symbol.clone(),
-                    depth,
+                let at = *edges.entry(&caller.qualified).or_insert_with(|| {
+                    result.edges.push(SpiderEdge {
+                        from: caller.qualified.clone(),
+                        to: callee.clone(),
+                        sites: Vec::new(),
+                    });
+                    result.edges.len() - 1
                 });
-
-                // Don't follow callers beyond max depth
-                if depth >= max_depth {
-                    continue;
+                let sites = &mut result.edges[at].sites;
+                if !sites.contains(site) {
+                    sites.push(site.clone());
                 }
+            }
+        }
+        frontier = next;
+    }
 
-                // Find all references TO this symbol
-                let references = index.find_references(&qualified_name);
+    result
+}
 
-                // For each reference, find the containing symbol (the caller)
-                for reference in references {
-                    if let Some(caller) = find_containing_symbol(index, reference) {
-                        if !visited.contains(&caller.qualified) {
-                            queue.push_back((caller.qualified.clone(), depth + 1));
-                        }
-                    }
-                }
-            }
-            None => {
-                // Entry point not found
-                if depth == 0 {
-                    result.unresolved.push(qualified_name);
+/// A caller found by [`callers_of`]: the calling symbol, the confidence of
+/// the match, and the reference it calls through.
+type Caller<'a> = (&'a Symbol, Confidence, &'a Reference);
+
+/// Callers of `callee` at or above `min_confidence`, in source order of
+/// their references. Code that creates a type through its constructors
+/// calls the type.
+fn callers_of<'a>(
+    index: &'a CodeIndex,
+    callee: &str,
+    min_confidence: Confidence,
+) -> Vec<Caller<'a>> {
+    let mut references: Vec<(&'a Reference, Confidence)> = index
+        .find_references(callee)
+        .into_iter()
+        .map(|reference| (reference, Confidence::of_match(&reference.name, callee)))
+        .collect();
+    if index.get(callee).is_some_and(|s| s.kind.is_instantiable()) {
+        references.extend(index.instantiations(callee));
+        references.sort_by(|(a, _), (b, _)| a.location.cmp(&b.location));
+    }
+
+    let rails = index.rails_conventions();
+    let caller = |&(reference, confidence): &(&'a Reference, Confidence)| {
+        let mut confidence = confidence;
+        if rails && is_ruby(&reference.location.file) {
+            // Scope rules and Rails conventions can tell a call to this
+            // method from a call to a same-named one elsewhere
+            match index.resolve(&reference.name, &reference.location.file) {
+                Some(resolved) if resolved.symbol.qualified == callee => {
+                    confidence = confidence.max(resolved.confidence());
                 }
+                Some(_) => return None,
+                None => {}
             }
         }
+        if confidence < min_confidence {
+            return None;
+        }
+        let containing = if rails && is_ruby(&reference.location.file) {
+            find_containing_symbol_or_class(index, reference)
+        } else {
+            find_containing_symbol(index, reference)
+        };
+        containing.map(|caller| (caller, confidence, reference))
+    };
+    if references.len() >= PARALLEL_THRESHOLD {
+        references.par_iter().filter_map(caller).collect()
+    } else {
+        references.iter().filter_map(caller).collect()
     }
-
-    result
 }
 
 /// Find the symbol that contains a given reference (for determining callers).
@@ -268,49 +604,286 @@ pub fn reverse_spider(index: &CodeIndex, entry_point: &str, max_depth: usize) ->
 ///
 /// Only considers callable symbols (Function, Member) as potential callers,
 /// filtering out variables, types, modules, etc. which cannot be callers.
-fn find_containing_symbol<'a>(index: &'a CodeIndex, reference: &Reference) -> Option<&'a Symbol> {
+/// Lambdas span their whole body, so they only contain references inside it.
+pub fn find_containing_symbol<'a>(
+    index: &'a CodeIndex,
+    reference: &Reference,
+) -> Option<&'a Symbol> {
     let symbols = index.symbols_in_file(&reference.location.file);
 
     // Find the callable symbol that most likely contains this reference
     // Heuristic: the callable symbol with the largest line number that's still <= reference line
     symbols
         .into_iter()
-        .filter(|s| s.location.line <= reference.location.line)
+        .filter(|s| precedes(s, reference))
         .filter(|s| s.kind.is_callable())
-        .max_by_key(|s| s.location.line)
+        .max_by_key(|s| (s.location.line, s.location.column))
+}
+
+/// Whether `symbol` starts before `reference` and, for a lambda, is still
+/// open there.
+fn precedes(symbol: &Symbol, reference: &Reference) -> bool {
+    let at = (reference.location.line, reference.location.column);
+    let location = &symbol.location;
+    location.line <= reference.location.line
+        && (!symbol.is_lambda()
+            || ((location.line, location.column) <= at
+                && at <= (location.end_line, location.end_column)))
+}
+
+/// Like [`find_containing_symbol`], but a reference in a class body before
+/// any method belongs to the class, so Rails callbacks (`before_action
+/// :authenticate`) and validations have a caller.
+fn find_containing_symbol_or_class<'a>(
+    index: &'a CodeIndex,
+    reference: &Reference,
+) -> Option<&'a Symbol> {
+    index
+        .symbols_in_file(&reference.location.file)
+        .into_iter()
+        .filter(|s| precedes(s, reference))
+        .filter(|s| s.kind.is_callable() || s.kind == SymbolKind::Class)
+        .max_by_key(|s| (s.location.line, s.location.column))
+}
+
+fn is_ruby(file: &Path) -> bool {
+    file.extension().is_some_and(|extension| extension == "rb")
 }
 
 /// Format spider result for display.
+///
+/// Nodes are printed as a tree: each under the node it was first reached
+/// from, indented by depth, with the call site of that edge. Nodes also
+/// reached along other paths appear only once.
 pub fn format_spider_result(result: &SpiderResult) -> String {
     let mut output = String::new();
 
+    let mut children: HashMap<&str, Vec<&SpiderNode>> = HashMap::new();
+    let mut roots = Vec::new();
+    let visited: HashSet<&str> = result
+        .nodes
+        .iter()
+        .map(|n| n.symbol.qualified.as_str())
+        .collect();
     for node in &result.nodes {
+        match node.parent.as_deref() {
+            Some(parent) if visited.contains(parent) => {
+                children.entry(parent).or_default().push(node)
+            }
+            _ => roots.push(node),
+        }
+    }
+
+    // Depth-first, so each subtree follows its parent
+    let mut stack: Vec<&SpiderNode> = roots.into_iter().rev().collect();
+    while let Some(node) = stack.pop() {
         let loc = &node.symbol.location;
         let indent = "  ".repeat(node.depth);
         output.push_str(&format!(
-            "{}{}:{}:{} {}\n",
+            "{}{}:{}:{} {}",
             indent,
             loc.file.display(),
             loc.line,
             loc.column,
             node.symbol.qualified
         ));
+        if let Some(source) = &node.source {
+            output.push_str(&format!(" [{}]", source));
+        }
+        if let Some(site) = call_site(result, node) {
+            output.push_str(&format!(" (at {}:{})", site.file.display(), site.line));
+        }
+        output.push('\n');
+
+        if let Some(kids) = children.get(node.symbol.qualified.as_str()) {
+            stack.extend(kids.iter().rev());
+        }
+    }
+    if result.truncated {
+        output.push_str(&format!(
+            "... stopped after {} symbols; narrow the search with --depth\n",
+            result.nodes.len()
+        ));
     }
 
-    if !result.unresolved.is_empty() {
-        output.push_str("\nUnresolved references:\n");
-        for name in &result.unresolved {
-            output.push_str(&format!("  {} <external>\n", name));
+    let external = result.external_by_package();
+    if !external.is_empty() {
+        output.push_str("\nExternal references:\n");
+        for group in external {
+            output.push_str(&format!(
+                "  {}\n",
+                group.package.unwrap_or("<unknown package>")
+            ));
+            for node in group.nodes {
+                output.push_str(&format!("    {}\n", node.name));
+            }
         }
     }
 
     output
 }
 
+/// First call site of the edge that reached `node` from its parent. The
+/// parent is the caller when spidering forward and the callee in reverse.
+fn call_site<'a>(result: &'a SpiderResult, node: &SpiderNode) -> Option<&'a Location> {
+    let parent = node.parent.as_deref()?;
+    let child = node.symbol.qualified.as_str();
+    result
+        .edge(parent, child)
+        .or_else(|| result.edge(child, parent))?
+        .sites
+        .first()
+}
+
+/// Fill colors for modules, assigned in order of first appearance.
+const MODULE_COLORS: [&str; 8] = [
+    "#cfe2f3", "#d9ead3", "#fff2cc", "#f4cccc", "#d9d2e9", "#fce5cd", "#d0e0e3", "#ead1dc",
+];
+
+/// A spider node as drawn by the graph renderers.
+struct GraphNode<'a> {
+    id: String,
+    node: &'a SpiderNode,
+    /// Definition file, relative to the root when possible
+    file: String,
+    color: &'static str,
+}
+
+impl GraphNode<'_> {
+    fn tooltip(&self) -> String {
+        format!("{}:{}", self.file, self.node.symbol.location.line)
+    }
+}
+
+/// Nodes with their ids, shared by the graph renderers.
+struct GraphNodes<'a> {
+    ids: HashMap<&'a str, String>,
+    nodes: Vec<GraphNode<'a>>,
+}
+
+impl<'a> GraphNodes<'a> {
+    fn new(result: &'a SpiderResult, root: Option<&Path>) -> Self {
+        let mut ids = HashMap::new();
+        let mut colors: HashMap<String, &'static str> = HashMap::new();
+        let mut nodes = Vec::new();
+        for (i, node) in result.nodes.iter().enumerate() {
+            let id = format!("n{}", i);
+            ids.insert(node.symbol.qualified.as_str(), id.clone());
+
+            let loc = &node.symbol.location;
+            let file = root
+                .and_then(|root| loc.file.strip_prefix(root).ok())
+                .unwrap_or(&loc.file);
+
+            let next = colors.len();
+            let color = *colors
+                .entry(module_name(&node.symbol))
+                .or_insert(MODULE_COLORS[next % MODULE_COLORS.len()]);
+            nodes.push(GraphNode {
+                id,
+                node,
+                file: file.display().to_string(),
+                color,
+            });
+        }
+        Self { ids, nodes }
+    }
+
+    fn edges<'r>(&'r self, result: &'r SpiderResult) -> impl Iterator<Item = (&'r str, &'r str)> {
+        result.edges.iter().filter_map(|e| {
+            Some((
+                self.ids.get(e.from.as_str())?.as_str(),
+                self.ids.get(e.to.as_str())?.as_str(),
+            ))
+        })
+    }
+}
+
+/// The module a symbol belongs to: its qualified name minus the last segment.
+fn module_name(symbol: &Symbol) -> String {
+    let qualified = symbol.qualified.as_str();
+    match qualified.rfind(['.', ':']) {
+        Some(end) => qualified[..end].trim_end_matches(':').to_string(),
+        None => symbol.location.file.display().to_string(),
+    }
+}
+
+/// Render a spider result as a Graphviz DOT digraph.
+///
+/// Nodes are colored by module and carry `file:line` tooltips, with paths
+/// shown relative to `root` when given. The entry point is drawn bold.
+#[must_use]
+pub fn spider_to_dot(result: &SpiderResult, root: Option<&Path>) -> String {
+    let graph = GraphNodes::new(result, root);
+    let mut out =
+        String::from("digraph spider {\n    rankdir=LR;\n    node [shape=box, style=filled];\n");
+    for n in &graph.nodes {
+        let style = if n.node.depth == 0 {
+            ", penwidth=2"
+        } else {
+            ""
+        };
+        out.push_str(&format!(
+            "    {} [label={}, tooltip={}, fillcolor=\"{}\"{}];\n",
+            n.id,
+            dot_string(&n.node.symbol.qualified),
+            dot_string(&n.tooltip()),
+            n.color,
+            style
+        ));
+    }
+    for (from, to) in graph.edges(result) {
+        out.push_str(&format!("    {} -> {};\n", from, to));
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// Render a spider result as a Mermaid flowchart, for pasting into Markdown.
+///
+/// Nodes are colored by module and carry `file:line` tooltips, with paths
+/// shown relative to `root` when given.
+#[must_use]
+pub fn spider_to_mermaid(result: &SpiderResult, root: Option<&Path>) -> String {
+    let graph = GraphNodes::new(result, root);
+    let mut out = String::from("flowchart LR\n");
+    for n in &graph.nodes {
+        out.push_str(&format!(
+            "    {}[\"{}\"]\n",
+            n.id,
+            mermaid_string(&n.node.symbol.qualified)
+        ));
+    }
+    for (from, to) in graph.edges(result) {
+        out.push_str(&format!("    {} --> {}\n", from, to));
+    }
+    for n in &graph.nodes {
+        out.push_str(&format!("    style {} fill:{}\n", n.id, n.color));
+        out.push_str(&format!(
+            "    click {} href \"{}#L{}\" \"{}\"\n",
+            n.id,
+            mermaid_string(&n.file),
+            n.node.symbol.location.line,
+            mermaid_string(&n.tooltip())
+        ));
+    }
+    out
+}
+
+/// Quote a string for DOT.
+fn dot_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Escape a string for a quoted Mermaid label.
+fn mermaid_string(s: &str) -> String {
+    s.replace('"', "#quot;")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{Location, Reference, SymbolKind, Visibility};
+    use crate::{Location, Reference, ReferenceKind, SymbolKind, Visibility};
     use std::path::PathBuf;
 
     fn make_symbol(name: &str, qualified: &str, file: &str, line: u32) -> Symbol {
@@ -344,6 +917,7 @@ mod tests {
         Reference {
             name: name.to_string(),
             location: Location::new(PathBuf::from(file), line, 1),
+            kind: ReferenceKind::Read,
         }
     }
 
@@ -384,6 +958,81 @@ mod tests {
             .any(|n| n.symbol.qualified == "Utils.helper" && n.depth == 1));
     }
 
+    #[test]
+    fn test_spider_prints_call_tree() {
+        let mut index = CodeIndex::new();
+        index.add_symbol(make_symbol("main", "Program.main", "src/Program.fs", 10));
+        index.add_symbol(make_symbol("run", "Program.run", "src/Program.fs", 20));
+        index.add_symbol(make_symbol("helper", "Utils.helper", "src/Utils.fs", 5));
+        index.add_symbol(make_symbol("save", "Store.save", "src/Store.fs", 3));
+
+        // main calls helper and run, helper calls save
+        for (name, file, line) in [
+            ("Utils.helper", "src/Program.fs", 12),
+            ("Program.run", "src/Program.fs", 13),
+            ("Store.save", "src/Utils.fs", 7),
+        ] {
+            index.add_reference(PathBuf::from(file), make_reference(name, file, line));
+        }
+
+        let result = spider(&index, "Program.main", 5);
+        let parent_of = |qualified: &str| {
+            result
+                .nodes
+                .iter()
+                .find(|n| n.symbol.qualified == qualified)
+                .and_then(|n| n.parent.clone())
+        };
+        assert_eq!(parent_of("Program.main"), None);
+        assert_eq!(parent_of("Store.save").as_deref(), Some("Utils.helper"));
+        let edge = result.edge("Utils.helper", "Store.save").unwrap();
+        assert_eq!(
+            edge.sites,
+            vec![Location::new(PathBuf::from("src/Utils.fs"), 7, 1)]
+        );
+
+        // save sits under helper, not after every depth-1 node
+        assert_eq!(
+            format_spider_result(&result),
+            "src/Program.fs:10:1 Program.main\n\
+             \x20 src/Utils.fs:5:1 Utils.helper (at src/Program.fs:12)\n\
+             \x20   src/Store.fs:3:1 Store.save (at src/Utils.fs:7)\n\
+             \x20 src/Program.fs:20:1 Program.run (at src/Program.fs:13)\n"
+        );
+    }
+
+    #[test]
+    fn test_spider_records_edge_confidence() {
+        let mut index = CodeIndex::new();
+        index.add_symbol(make_symbol("main", "Program.main", "src/Program.fs", 10));
+        index.add_symbol(make_symbol("helper", "Utils.helper", "src/Utils.fs", 5));
+        index.add_symbol(make_symbol("log", "Logging.log", "src/Logging.fs", 1));
+
+        index.add_reference(
+            PathBuf::from("src/Program.fs"),
+            make_reference("Utils.helper", "src/Program.fs", 15),
+        );
+        index.add_reference(
+            PathBuf::from("src/Program.fs"),
+            make_reference("log", "src/Program.fs", 16),
+        );
+
+        let result = spider(&index, "Program.main", 1);
+        let confidence_of = |qualified: &str| {
+            result
+                .nodes
+                .iter()
+                .find(|n| n.symbol.qualified == qualified)
+                .map(|n| n.confidence)
+        };
+        assert_eq!(confidence_of("Program.main"), Some(Confidence::Exact));
+        assert_eq!(confidence_of("Utils.helper"), Some(Confidence::Exact));
+        assert_eq!(confidence_of("Logging.log"), Some(Confidence::Heuristic));
+
+        let precise = spider_with_confidence(&index, "Program.main", 1, Confidence::Exact);
+        assert_eq!(precise.nodes.len(), 2);
+    }
+
     #[test]
     fn test_spider_respects_max_depth() {
         let mut index = CodeIndex::new();
@@ -418,6 +1067,59 @@ mod tests {
         assert!(result.unresolved.contains(&"Console.WriteLine".to_string()));
     }
 
+    #[test]
+    fn test_spider_attributes_externals_to_packages() {
+        use crate::external_index::index_external_assemblies;
+        use crate::fsproj::PackageReference;
+
+        let mut index = CodeIndex::new();
+        index.add_symbol(make_symbol("main", "Program.main", "src/Program.fs", 10));
+        index.add_open(PathBuf::from("src/Program.fs"), "System".to_string());
+        index.add_open(
+            PathBuf::from("src/Program.fs"),
+            "Newtonsoft.Json".to_string(),
+        );
+        for (name, line) in [
+            ("Console.WriteLine", 11),
+            ("JsonConvert.SerializeObject", 12),
+            ("mystery", 13),
+        ] {
+            index.add_reference(
+                PathBuf::from("src/Program.fs"),
+                make_reference(name, "src/Program.fs", line),
+            );
+        }
+        index.set_external_index(index_external_assemblies(&[PackageReference {
+            name: "Newtonsoft.Json".to_string(),
+            version: "13.0.1".to_string(),
+        }]));
+
+        let result = spider(&index, "Program.main", 5);
+        let groups: Vec<(Option<&str>, Vec<&str>)> = result
+            .external_by_package()
+            .iter()
+            .map(|g| (g.package, g.nodes.iter().map(|n| n.name.as_str()).collect()))
+            .collect();
+        assert_eq!(
+            groups,
+            vec![
+                (Some("Newtonsoft.Json"), vec!["JsonConvert.SerializeObject"]),
+                (Some("System.Console"), vec!["Console.WriteLine"]),
+                (None, vec!["mystery"]),
+            ]
+        );
+        assert_eq!(
+            result.external_nodes[0].referenced_from,
+            vec!["Program.main"]
+        );
+
+        let text = format_spider_result(&result);
+        assert!(text.contains(
+            "External references:\n  Newtonsoft.Json\n    JsonConvert.SerializeObject\n"
+        ));
+        assert!(text.ends_with("  <unknown package>\n    mystery\n"));
+    }
+
     #[test]
     fn test_spider_result_files() {
         let mut index = CodeIndex::new();
@@ -466,6 +1168,76 @@ mod tests {
             .any(|n| n.symbol.qualified == "MyApp.Utils.helper"));
     }
 
+    #[test]
+    fn test_spider_follows_computation_expression_builders() {
+        let mut index = CodeIndex::new();
+        index.add_symbol(make_symbol("run", "MyApp.run", "src/App.fs", 10));
+        index.add_symbol(make_symbol_with_kind(
+            "MaybeBuilder",
+            "MyApp.MaybeBuilder",
+            "src/Maybe.fs",
+            1,
+            SymbolKind::Class,
+        ));
+        index.add_symbol(make_symbol(
+            "Bind",
+            "MyApp.MaybeBuilder.Bind",
+            "src/Maybe.fs",
+            2,
+        ));
+        let mut maybe =
+            make_symbol_with_kind("maybe", "MyApp.maybe", "src/Maybe.fs", 5, SymbolKind::Value);
+        maybe.signature = Some("MaybeBuilder".to_string());
+        index.add_symbol(maybe);
+        index.add_open(PathBuf::from("src/App.fs"), "MyApp".to_string());
+
+        // `maybe { let! x = ... }` calls the builder's Bind
+        index.add_reference(
+            PathBuf::from("src/App.fs"),
+            make_reference("maybe.Bind", "src/App.fs", 12),
+        );
+
+        let result = spider(&index, "MyApp.run", 5);
+
+        assert!(result
+            .nodes
+            .iter()
+            .any(|n| n.symbol.qualified == "MyApp.MaybeBuilder.Bind"));
+        assert!(result.unresolved.is_empty());
+    }
+
+    #[test]
+    fn test_spider_expands_module_aliases() {
+        let mut index = CodeIndex::new();
+        index.add_symbol(make_symbol("main", "Program.main", "src/Program.fs", 10));
+        index.add_symbol(make_symbol(
+            "connect",
+            "MyApp.Data.Database.connect",
+            "src/Database.fs",
+            5,
+        ));
+        index.add_alias(
+            PathBuf::from("src/Program.fs"),
+            crate::ModuleAlias {
+                alias: "Db".to_string(),
+                target: "MyApp.Data.Database".to_string(),
+            },
+        );
+        index.add_reference(
+            PathBuf::from("src/Program.fs"),
+            make_reference("Db.connect", "src/Program.fs", 15),
+        );
+
+        let result = spider(&index, "Program.main", 5);
+
+        let connect = result
+            .nodes
+            .iter()
+            .find(|n| n.symbol.qualified == "MyApp.Data.Database.connect")
+            .expect("alias should be expanded");
+        assert_eq!(connect.depth, 1);
+    }
+
     // =========================================================================
     // Reverse Spider Tests
     // =========================================================================
@@ -510,6 +1282,214 @@ mod tests {
             .any(|n| n.symbol.qualified == "Program.main" && n.depth == 1));
     }
 
+    #[test]
+    fn test_reverse_spider_reaches_callers_through_lambdas() {
+        let mut index = CodeIndex::new();
+        index.add_symbol(make_symbol("render", "View.render", "src/View.fs", 1));
+        index.add_symbol(make_symbol("setup", "App.setup", "src/App.fs", 1));
+        // A callback on lines 3-5 of setup, then a call after it
+        let mut lambda = make_symbol("<lambda1>", "App.setup.<lambda1>", "src/App.fs", 3);
+        lambda.location = Location::with_end(PathBuf::from("src/App.fs"), 3, 20, 5, 6);
+        index.add_symbol(lambda);
+        index.add_symbol(make_symbol("save", "Db.save", "src/Db.fs", 1));
+        index.add_reference(
+            PathBuf::from("src/App.fs"),
+            make_reference("View.render", "src/App.fs", 4),
+        );
+        index.add_reference(
+            PathBuf::from("src/App.fs"),
+            make_reference("Db.save", "src/App.fs", 6),
+        );
+
+        let result = reverse_spider(&index, "View.render", 5);
+        let callers: Vec<(&str, usize)> = result
+            .nodes
+            .iter()
+            .map(|n| (n.symbol.qualified.as_str(), n.depth))
+            .collect();
+        assert_eq!(
+            callers,
+            vec![
+                ("View.render", 0),
+                ("App.setup.<lambda1>", 1),
+                ("App.setup", 2)
+            ]
+        );
+        let edge = result.edge("App.setup", "App.setup.<lambda1>").unwrap();
+        assert_eq!(edge.sites[0].line, 3);
+
+        // Past the end of the lambda, references belong to setup again
+        let result = reverse_spider(&index, "Db.save", 1);
+        assert_eq!(result.nodes[1].symbol.qualified, "App.setup");
+    }
+
+    #[test]
+    fn test_reverse_spider_counts_instantiation_as_a_call() {
+        let mut index = CodeIndex::new();
+        let rust = |name: &str, qualified: &str, file: &str, line: u32, kind: SymbolKind| Symbol {
+            language: "rust".to_string(),
+            ..make_symbol_with_kind(name, qualified, file, line, kind)
+        };
+        index.add_symbol(rust(
+            "Square",
+            "shapes::Square",
+            "src/shapes.rs",
+            1,
+            SymbolKind::Class,
+        ));
+        index.add_symbol(rust(
+            "new",
+            "shapes::Square::new",
+            "src/shapes.rs",
+            4,
+            SymbolKind::Function,
+        ));
+        index.add_symbol(rust(
+            "build",
+            "app::build",
+            "src/app.rs",
+            1,
+            SymbolKind::Function,
+        ));
+        index.add_symbol(rust(
+            "draw",
+            "app::draw",
+            "src/app.rs",
+            10,
+            SymbolKind::Function,
+        ));
+        index.add_symbol(rust(
+            "other",
+            "app::other",
+            "src/app.rs",
+            20,
+            SymbolKind::Function,
+        ));
+        for (name, line) in [
+            ("Square::new", 2),
+            ("shapes::Square::new", 11),
+            ("Vec::new", 21),
+        ] {
+            index.add_reference(
+                PathBuf::from("src/app.rs"),
+                make_reference(name, "src/app.rs", line),
+            );
+        }
+
+        assert_eq!(
+            index
+                .constructors_of("shapes::Square")
+                .iter()
+                .map(|s| s.qualified.as_str())
+                .collect::<Vec<_>>(),
+            vec!["shapes::Square::new"]
+        );
+        let result = reverse_spider(&index, "shapes::Square", 1);
+        let callers: Vec<(&str, Confidence)> = result
+            .nodes
+            .iter()
+            .filter(|n| n.depth == 1)
+            .map(|n| (n.symbol.qualified.as_str(), n.confidence))
+            .collect();
+        assert_eq!(
+            callers,
+            vec![
+                ("app::build", Confidence::Heuristic),
+                ("app::draw", Confidence::Exact),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reverse_spider_min_confidence_drops_name_only_callers() {
+        let mut index = CodeIndex::new();
+        index.add_symbol(make_symbol("helper", "Utils.helper", "src/Utils.fs", 5));
+        index.add_symbol(make_symbol("main", "Program.main", "src/Program.fs", 10));
+        index.add_symbol(make_symbol("run", "Other.run", "src/Other.fs", 1));
+
+        // main calls Utils.helper explicitly; run calls some "helper" by name only
+        index.add_reference(
+            PathBuf::from("src/Program.fs"),
+            make_reference("Utils.helper", "src/Program.fs", 15),
+        );
+        index.add_reference(
+            PathBuf::from("src/Other.fs"),
+            make_reference("helper", "src/Other.fs", 3),
+        );
+
+        let all = reverse_spider(&index, "Utils.helper", 1);
+        assert_eq!(all.nodes.len(), 3);
+        let run = all
+            .nodes
+            .iter()
+            .find(|n| n.symbol.qualified == "Other.run")
+            .unwrap();
+        assert_eq!(run.confidence, Confidence::Heuristic);
+
+        let precise =
+            reverse_spider_with_confidence(&index, "Utils.helper", 1, Confidence::Qualified);
+        assert_eq!(precise.nodes.len(), 2);
+        assert_eq!(precise.nodes[1].symbol.qualified, "Program.main");
+        assert_eq!(precise.nodes[1].confidence, Confidence::Exact);
+    }
+
+    #[test]
+    fn test_reverse_spider_follows_rails_callbacks() {
+        let mut index = CodeIndex::new();
+        let base = "app/controllers/application_controller.rb";
+        let users = "app/controllers/users_controller.rb";
+        let admin = "app/controllers/admin_controller.rb";
+        index.add_symbol(make_symbol_with_kind(
+            "ApplicationController",
+            "ApplicationController",
+            base,
+            1,
+            SymbolKind::Class,
+        ));
+        index.add_symbol(make_symbol(
+            "authenticate",
+            "ApplicationController#authenticate",
+            base,
+            2,
+        ));
+        let mut controller = make_symbol_with_kind(
+            "UsersController",
+            "UsersController",
+            users,
+            1,
+            SymbolKind::Class,
+        );
+        controller.parent = Some("ApplicationController".to_string());
+        index.add_symbol(controller);
+        // AdminController has its own authenticate, which its callback names
+        index.add_symbol(make_symbol_with_kind(
+            "AdminController",
+            "AdminController",
+            admin,
+            1,
+            SymbolKind::Class,
+        ));
+        index.add_symbol(make_symbol(
+            "authenticate",
+            "AdminController#authenticate",
+            admin,
+            4,
+        ));
+        for file in [users, admin] {
+            // before_action :authenticate
+            index.add_reference(PathBuf::from(file), make_reference("authenticate", file, 2));
+        }
+
+        let plain = reverse_spider(&index, "ApplicationController#authenticate", 1);
+        assert_eq!(plain.nodes.len(), 1, "class-level callbacks have no caller");
+
+        index.set_rails_conventions(true);
+        let rails = reverse_spider(&index, "ApplicationController#authenticate", 1);
+        assert_eq!(rails.nodes.len(), 2);
+        assert_eq!(rails.nodes[1].symbol.qualified, "UsersController");
+        assert_eq!(rails.nodes[1].confidence, Confidence::Qualified);
+    }
+
     #[test]
     fn test_reverse_spider_respects_max_depth() {
         let mut index = CodeIndex::new();
@@ -564,6 +1544,39 @@ mod tests {
             .any(|n| n.symbol.qualified == "Tests.test" && n.depth == 1));
     }
 
+    #[test]
+    fn test_reverse_spider_wide_fan_out_is_ordered_and_capped() {
+        let mut index = CodeIndex::new();
+        index.add_symbol(make_symbol("log", "Log.log", "src/Log.fs", 1));
+        // Enough callers for the lookups to go parallel, added out of order
+        for i in (0..300).rev() {
+            let file = format!("src/Caller{:03}.fs", i);
+            let name = format!("run{:03}", i);
+            index.add_symbol(make_symbol(&name, &format!("App.{}", name), &file, 1));
+            index.add_reference(PathBuf::from(&file), make_reference("log", &file, 2));
+        }
+
+        let result = reverse_spider(&index, "Log.log", 1);
+        assert!(!result.truncated);
+        assert_eq!(result.nodes.len(), 301);
+        let callers: Vec<&str> = result.nodes[1..]
+            .iter()
+            .map(|n| n.symbol.qualified.as_str())
+            .collect();
+        let mut sorted = callers.clone();
+        sorted.sort();
+        assert_eq!(callers, sorted, "callers come back in source order");
+        assert_eq!(result.edges.len(), 300);
+        assert_eq!(result.edges[0].from, "App.run000");
+
+        let capped = reverse_spider_limited(&index, "Log.log", 1, Confidence::StringMatch, 10);
+        assert!(capped.truncated);
+        assert_eq!(capped.nodes.len(), 10);
+        assert_eq!(capped.nodes[9].symbol.qualified, "App.run008");
+        // Edges only join nodes in the result
+        assert_eq!(capped.edges.len(), 9);
+    }
+
     #[test]
     fn test_reverse_spider_not_found() {
         let index = CodeIndex::new();
@@ -662,4 +1675,58 @@ mod tests {
             "Caller should be a Function, not a Value"
         );
     }
+
+    #[test]
+    fn test_graph_renderers() {
+        let mut index = CodeIndex::new();
+        index.add_symbol(make_symbol(
+            "main",
+            "Program.main",
+            "/repo/src/Program.fs",
+            10,
+        ));
+        index.add_symbol(make_symbol(
+            "helper",
+            "Utils.helper",
+            "/repo/src/Utils.fs",
+            5,
+        ));
+        index.add_symbol(make_symbol(
+            "format",
+            "Utils.format",
+            "/repo/src/Utils.fs",
+            8,
+        ));
+        index.add_reference(
+            PathBuf::from("/repo/src/Program.fs"),
+            make_reference("Utils.helper", "/repo/src/Program.fs", 12),
+        );
+        index.add_reference(
+            PathBuf::from("/repo/src/Utils.fs"),
+            make_reference("Utils.format", "/repo/src/Utils.fs", 6),
+        );
+
+        let result = spider(&index, "Program.main", 5);
+        assert!(result.edges.contains(&SpiderEdge {
+            from: "Program.main".to_string(),
+            to: "Utils.helper".to_string(),
+            sites: vec![Location::new(PathBuf::from("/repo/src/Program.fs"), 12, 1)],
+        }));
+
+        let dot = spider_to_dot(&result, Some(Path::new("/repo")));
+        assert!(dot.starts_with("digraph spider {"));
+        assert!(dot.contains(
+            "n0 [label=\"Program.main\", tooltip=\"src/Program.fs:10\", fillcolor=\"#cfe2f3\", penwidth=2];"
+        ));
+        assert!(dot.contains("n0 -> n1;"));
+        // Same module, same color
+        assert!(dot.contains("tooltip=\"src/Utils.fs:5\", fillcolor=\"#d9ead3\""));
+        assert!(dot.contains("tooltip=\"src/Utils.fs:8\", fillcolor=\"#d9ead3\""));
+
+        let mermaid = spider_to_mermaid(&result, Some(Path::new("/repo")));
+        assert!(mermaid.starts_with("flowchart LR\n"));
+        assert!(mermaid.contains("    n0[\"Program.main\"]\n"));
+        assert!(mermaid.contains("    n0 --> n1\n"));
+        assert!(mermaid.contains("    click n1 href \"src/Utils.fs#L5\" \"src/Utils.fs:5\"\n"));
+    }
 }
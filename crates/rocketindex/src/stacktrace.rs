@@ -1,10 +1,17 @@
 //! Stacktrace parsing for multiple languages.
 //!
 //! Parses stacktrace text and extracts structured frame information
-//! for use with symbol enrichment.
+//! for use with symbol enrichment: Java and Kotlin stack traces, Python
+//! tracebacks, Node/V8 stacks, Go panics, Ruby backtraces and Rust
+//! backtraces, including exceptions chained to their causes (Java's
+//! `Caused by:`, Python's chained tracebacks, Node's `[cause]:`).
 
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use crate::sourcemap::SourceMap;
 
 /// A single frame extracted from a stacktrace.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -56,6 +63,108 @@ pub struct StacktraceResult {
     pub detected_language: Option<StacktraceLanguage>,
     /// Lines that couldn't be parsed as frames
     pub unparsed_lines: Vec<String>,
+    /// The exception or error type of the outermost exception that names
+    /// one (e.g., "com.example.PaymentException", "ZeroDivisionError")
+    #[serde(default)]
+    pub exception: Option<String>,
+    /// The exceptions of the trace, outermost first, each caused by the
+    /// next; a single one unless the trace chains them
+    #[serde(default)]
+    pub chain: Vec<ChainedException>,
+}
+
+/// One exception of a stacktrace and the frames printed under it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainedException {
+    /// Its type, if the header names one
+    pub exception: Option<String>,
+    /// The message after the type, if any
+    pub message: Option<String>,
+    /// Its frames, as indices into [`StacktraceResult::frames`]
+    pub frames: Range<usize>,
+}
+
+/// Where a frame pointed before [`StacktraceResult::apply_sourcemaps`]
+/// mapped it to its original source.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GeneratedPosition {
+    /// The bundled file, as the trace names it
+    pub file: PathBuf,
+    pub line: u32,
+    pub column: Option<u32>,
+}
+
+impl StacktraceResult {
+    /// Map JavaScript frames in bundled or minified files back to their
+    /// original sources, through the files' source maps (see
+    /// [`crate::sourcemap`]). Frames whose file isn't on disk, such as the
+    /// URL of a deployed bundle, are looked for under `dirs` by the end of
+    /// their path: `https://cdn/static/js/app.js` as `static/js/app.js`,
+    /// `js/app.js`, then `app.js`.
+    ///
+    /// Returns, for each frame, where it pointed before if it was mapped.
+    pub fn apply_sourcemaps(&mut self, dirs: &[PathBuf]) -> Vec<Option<GeneratedPosition>> {
+        let mut maps: HashMap<PathBuf, Option<SourceMap>> = HashMap::new();
+        self.frames
+            .iter_mut()
+            .map(|frame| {
+                if frame.language != Some(StacktraceLanguage::JavaScript) {
+                    return None;
+                }
+                let (file, line) = (frame.file.as_ref()?, frame.line?);
+                let generated = generated_file(file, dirs)?;
+                let map = maps
+                    .entry(generated)
+                    .or_insert_with_key(|generated| SourceMap::load_for(generated).ok().flatten())
+                    .as_ref()?;
+                let original = map.lookup(line, frame.column.unwrap_or(1))?;
+                let before = GeneratedPosition {
+                    file: file.clone(),
+                    line,
+                    column: frame.column,
+                };
+                frame.is_user_code = !is_framework_code(
+                    &frame.symbol,
+                    Some(&original.file),
+                    StacktraceLanguage::JavaScript,
+                );
+                frame.file = Some(original.file);
+                frame.line = Some(original.line);
+                frame.column = Some(original.column);
+                Some(before)
+            })
+            .collect()
+    }
+
+    /// Which exception of [`chain`](Self::chain) the frame at `index` of
+    /// [`frames`](Self::frames) belongs to.
+    #[must_use]
+    pub fn chain_index(&self, index: usize) -> Option<usize> {
+        self.chain
+            .iter()
+            .position(|link| link.frames.contains(&index))
+    }
+}
+
+/// The generated file a frame's `file` names, on disk or under `dirs`.
+fn generated_file(file: &Path, dirs: &[PathBuf]) -> Option<PathBuf> {
+    if file.is_file() {
+        return Some(file.to_path_buf());
+    }
+    let text = file.to_string_lossy();
+    let path = match text.split_once("://") {
+        // Drop the host
+        Some((_, rest)) => rest.split_once('/').map_or("", |(_, path)| path),
+        None => &text,
+    };
+    let path = path.split(['?', '#']).next().unwrap_or(path);
+    let parts: Vec<&str> = path.split('/').filter(|part| !part.is_empty()).collect();
+    (0..parts.len()).find_map(|start| {
+        let suffix: PathBuf = parts[start..].iter().collect();
+        dirs.iter()
+            .map(|dir| dir.join(&suffix))
+            .find(|candidate| candidate.is_file())
+    })
 }
 
 /// Parse a stacktrace string into structured frames.
@@ -78,18 +187,61 @@ pub struct StacktraceResult {
 /// ```
 pub fn parse_stacktrace(text: &str) -> StacktraceResult {
     let mut result = StacktraceResult::default();
+    // In the order printed; Python prints causes first
+    let mut chain = vec![ChainedException::default()];
+    let mut causes_first = false;
+    // Node prints the throwing line with a caret under it above the trace
+    let mut in_excerpt = false;
+    // Python prints each frame's source line after it
+    let mut after_python_frame = false;
 
     for line in text.lines() {
         let trimmed = line.trim();
         if trimmed.is_empty() {
+            in_excerpt = false;
+            continue;
+        }
+        if in_excerpt || is_caret_line(trimmed) || is_elision(trimmed) {
+            continue;
+        }
+        if is_source_excerpt_header(trimmed) {
+            in_excerpt = true;
             continue;
         }
 
+        let mut header = trimmed;
+        if let Some((rest, cause_follows)) = chain_link(trimmed) {
+            causes_first |= !cause_follows;
+            let start = result.frames.len();
+            if let Some(last) = chain.last_mut() {
+                last.frames.end = start;
+            }
+            chain.push(ChainedException {
+                frames: start..start,
+                ..ChainedException::default()
+            });
+            after_python_frame = false;
+            if rest.is_empty() {
+                continue;
+            }
+            header = rest;
+        }
+
+        if let Some(link) = chain.last_mut().filter(|link| link.exception.is_none()) {
+            if let Some(name) = exception_type(header) {
+                link.message = exception_message(header, &name);
+                link.exception = Some(name);
+            }
+        }
+
         // Skip exception headers first
-        if is_exception_header(trimmed) {
+        if header != trimmed || is_exception_header(trimmed) {
+            after_python_frame = false;
             continue;
         }
 
+        let python_source = std::mem::take(&mut after_python_frame);
+
         // Try each language parser in order of specificity
         // More specific patterns first to avoid false positives
         if let Some(frame) = try_parse_rust(trimmed) {
@@ -104,6 +256,9 @@ pub fn parse_stacktrace(text: &str) -> StacktraceResult {
                 result.detected_language = Some(StacktraceLanguage::Python);
             }
             result.frames.push(frame);
+            after_python_frame = true;
+        } else if python_source {
+            // The source line of the frame above
         } else if let Some(frame) = try_parse_ruby(trimmed) {
             // Ruby: path:line:in `method'
             if result.detected_language.is_none() {
@@ -123,19 +278,202 @@ pub fn parse_stacktrace(text: &str) -> StacktraceResult {
             }
             result.frames.push(frame);
         } else if let Some(frame) = try_parse_go(trimmed) {
-            // Go: func(args) or path:line +0x...
+            // Go: func(args) followed by path:line +0x...
             if result.detected_language.is_none() {
                 result.detected_language = Some(StacktraceLanguage::Go);
             }
-            result.frames.push(frame);
-        } else {
+            let start = chain.last().map_or(0, |link| link.frames.start);
+            match result.frames[start..].last_mut() {
+                Some(function)
+                    if frame.symbol == "<location>"
+                        && function.language == Some(StacktraceLanguage::Go)
+                        && function.symbol != "<location>"
+                        && function.file.is_none() =>
+                {
+                    function.is_user_code = function.is_user_code
+                        && !is_framework_code("", frame.file.as_ref(), StacktraceLanguage::Go);
+                    function.file = frame.file;
+                    function.line = frame.line;
+                }
+                _ => result.frames.push(frame),
+            }
+        } else if trimmed != "}" {
             result.unparsed_lines.push(trimmed.to_string());
         }
     }
 
+    if let Some(last) = chain.last_mut() {
+        last.frames.end = result.frames.len();
+    }
+    chain.retain(|link| link.exception.is_some() || !link.frames.is_empty());
+    if causes_first {
+        chain.reverse();
+    }
+    result.exception = chain.iter().find_map(|link| link.exception.clone());
+    result.chain = chain;
     result
 }
 
+/// A stacktrace cut out of a stream of log lines by [`TraceSplitter`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceBlock {
+    /// 1-indexed line of the stream the trace starts on
+    pub line: usize,
+    pub text: String,
+}
+
+/// Cuts stacktraces out of a stream of log lines as they arrive, for
+/// following a log (`rkt analyze --follow`).
+///
+/// A trace starts at a frame (the line before it too, if that names an
+/// exception) or at a line only traces start with, such as Python's
+/// `Traceback (most recent call last):`, and goes on while lines are
+/// indented, frames, exception headers or links of a chain. Blank lines
+/// are kept if the trace goes on after them.
+#[derive(Debug, Default)]
+pub struct TraceSplitter {
+    /// Lines of the trace being read
+    block: Vec<String>,
+    /// Line of the stream the trace starts on
+    start: usize,
+    /// Lines read so far
+    lines: usize,
+    /// The last line when no trace is open, which heads one that starts next
+    previous: Option<String>,
+    /// Blank lines held back until the next line shows if the trace goes on
+    blanks: usize,
+}
+
+impl TraceSplitter {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read the next line; returns the trace it ended, if it ended one.
+    pub fn push(&mut self, line: &str) -> Option<TraceBlock> {
+        self.lines += 1;
+        let trimmed = line.trim();
+        if self.block.is_empty() {
+            if starts_trace(line, trimmed) {
+                self.start = self.lines;
+                if let Some(header) = self.previous.take().filter(|p| heads_trace(p.trim())) {
+                    self.block.push(header);
+                    self.start -= 1;
+                }
+                self.block.push(line.to_string());
+            } else {
+                self.previous = Some(line.to_string());
+            }
+            return None;
+        }
+        if trimmed.is_empty() {
+            self.blanks += 1;
+            return None;
+        }
+        if continues_trace(line, trimmed) {
+            let blanks = std::mem::take(&mut self.blanks);
+            self.block
+                .extend(std::iter::repeat_n(String::new(), blanks));
+            self.block.push(line.to_string());
+            return None;
+        }
+        // The trace is over; the line may head the next one
+        let finished = self.flush();
+        self.lines -= 1;
+        self.push(line);
+        finished
+    }
+
+    /// The trace being read, if any: at the end of the stream, or when it
+    /// has gone quiet.
+    pub fn flush(&mut self) -> Option<TraceBlock> {
+        if self.block.is_empty() {
+            return None;
+        }
+        self.blanks = 0;
+        Some(TraceBlock {
+            line: self.start,
+            text: std::mem::take(&mut self.block).join("\n"),
+        })
+    }
+}
+
+/// Whether a log line is a frame, in any of the formats parsed.
+fn is_frame_line(trimmed: &str) -> bool {
+    try_parse_rust(trimmed).is_some()
+        || try_parse_python(trimmed).is_some()
+        || try_parse_ruby(trimmed).is_some()
+        || try_parse_java(trimmed).is_some()
+        || try_parse_javascript(trimmed).is_some()
+        || try_parse_go(trimmed).is_some()
+}
+
+/// Whether a log line starts a trace: a line only traces start with, an
+/// indented frame, or Ruby's first frame, which isn't indented. (Go's
+/// function lines look too much like other log lines to start one.)
+fn starts_trace(line: &str, trimmed: &str) -> bool {
+    ["Traceback", "panic:", "goroutine ", "stack backtrace:"]
+        .iter()
+        .any(|start| trimmed.starts_with(start))
+        || (line.starts_with([' ', '\t']) && is_frame_line(trimmed))
+        || (trimmed.contains(":in `") && try_parse_ruby(trimmed).is_some())
+}
+
+/// Whether the line before a trace names its exception.
+fn heads_trace(trimmed: &str) -> bool {
+    !trimmed.is_empty() && (is_exception_header(trimmed) || exception_type(trimmed).is_some())
+}
+
+/// Whether a log line goes on with the trace before it.
+fn continues_trace(line: &str, trimmed: &str) -> bool {
+    line.starts_with([' ', '\t'])
+        || trimmed == "}"
+        || chain_link(trimmed).is_some()
+        || is_elision(trimmed)
+        || heads_trace(trimmed)
+        || is_frame_line(trimmed)
+}
+
+/// Whether a line starts the next exception of a chain, and if so the rest
+/// of the line (the exception's header, if on the same line) and whether
+/// that exception caused the one before it. Python prints the cause first.
+fn chain_link(line: &str) -> Option<(&str, bool)> {
+    const PYTHON_LINKS: &[&str] = &[
+        "The above exception was the direct cause of the following exception:",
+        "During handling of the above exception, another exception occurred:",
+    ];
+    if PYTHON_LINKS.contains(&line) {
+        return Some(("", false));
+    }
+    line.strip_prefix("Caused by: ")
+        .or_else(|| line.strip_prefix("[cause]: "))
+        .map(|rest| (rest.trim(), true))
+}
+
+/// Whether a line is the `file:line` Node prints above the source line
+/// that threw, rather than a frame.
+fn is_source_excerpt_header(line: &str) -> bool {
+    let Some((file, line_num)) = line.rsplit_once(':') else {
+        return false;
+    };
+    line_num.parse::<u32>().is_ok()
+        && [".js", ".mjs", ".cjs", ".ts", ".mts", ".cts", ".jsx", ".tsx"]
+            .iter()
+            .any(|extension| file.ends_with(extension))
+}
+
+/// Whether a line only points at a column (`^`, Python's `~~~^^^`).
+fn is_caret_line(line: &str) -> bool {
+    line.contains('^') && line.chars().all(|c| matches!(c, '^' | '~' | ' '))
+}
+
+/// Whether a line stands for frames left out: Java's `... 12 more`,
+/// Node's `... 7 lines matching cause stack trace ...`.
+fn is_elision(line: &str) -> bool {
+    line.starts_with("... ") && (line.ends_with(" more") || line.ends_with(" ..."))
+}
+
 /// Check if a line is an exception header (not a frame)
 fn is_exception_header(line: &str) -> bool {
     // Java: "java.lang.NullPointerException: message"
@@ -153,6 +491,61 @@ fn is_exception_header(line: &str) -> bool {
         || (line.contains("Error:") && !line.trim().starts_with("at "))
 }
 
+/// The exception type a header line names, if it names one.
+fn exception_type(line: &str) -> Option<String> {
+    // Ruby puts the class last: "app.rb:42:in `charge': declined (PaymentError)"
+    if line.contains(":in `") {
+        let name = line.strip_suffix(')')?.rsplit_once('(')?.1;
+        return is_type_name(name).then(|| name.to_string());
+    }
+
+    // Java: Exception in thread "main" com.example.PaymentException: declined
+    let line = match line.strip_prefix("Exception in thread \"") {
+        Some(rest) => rest.split_once("\" ")?.1,
+        None => line.strip_prefix("Caused by: ").unwrap_or(line),
+    };
+    // Python, JS, Java, C#: "TypeError: message", or the bare name
+    let name = line.split_once(':').map_or(line, |(name, _)| name).trim();
+    is_type_name(name).then(|| name.to_string())
+}
+
+/// The message after the exception type `name` in a header line.
+fn exception_message(line: &str, name: &str) -> Option<String> {
+    let message = if line.contains(":in `") {
+        // Ruby: "app.rb:42:in `charge': declined (PaymentError)"
+        line.split_once("': ")?.1.rsplit_once(" (")?.0
+    } else {
+        line.split_once(name)?.1.strip_prefix(':')?
+    };
+    Some(message.trim().to_string()).filter(|message| !message.is_empty())
+}
+
+/// Whether `name` looks like an error type: a (possibly qualified)
+/// identifier whose last segment is capitalized and reads like one.
+fn is_type_name(name: &str) -> bool {
+    let valid = |c: char| c.is_alphanumeric() || matches!(c, '_' | '$' | '.' | ':');
+    let last = name.rsplit(['.', ':']).next().unwrap_or(name);
+    !name.is_empty()
+        && name.chars().all(valid)
+        && last.starts_with(|c: char| c.is_ascii_uppercase())
+        && [
+            "Error",
+            "Exception",
+            "Exit",
+            "Interrupt",
+            "Fault",
+            "Failure",
+        ]
+        .iter()
+        .any(|suffix| last.ends_with(suffix))
+}
+
+/// Whether a source line raises or throws (`raise Foo`, `throw new Foo()`).
+pub fn is_throw_site(line: &str) -> bool {
+    line.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .any(|word| matches!(word, "raise" | "throw" | "fail"))
+}
+
 // Default framework patterns for common languages
 const JAVA_FRAMEWORK_PREFIXES: &[&str] = &[
     "java.",
@@ -186,6 +579,7 @@ const PYTHON_FRAMEWORK_PATTERNS: &[&str] = &[
 
 const JS_FRAMEWORK_PATTERNS: &[&str] = &[
     "node_modules/",
+    "node:",
     "internal/",
     "timers.js",
     "events.js",
@@ -204,7 +598,11 @@ const RUST_FRAMEWORK_PATTERNS: &[&str] = &[
 const GO_FRAMEWORK_PATTERNS: &[&str] =
     &["runtime.", "runtime/", "net/http.", "syscall.", "internal/"];
 
-fn is_framework_code(symbol: &str, file: Option<&PathBuf>, language: StacktraceLanguage) -> bool {
+pub(crate) fn is_framework_code(
+    symbol: &str,
+    file: Option<&PathBuf>,
+    language: StacktraceLanguage,
+) -> bool {
     let patterns = match language {
         StacktraceLanguage::Java => JAVA_FRAMEWORK_PREFIXES,
         StacktraceLanguage::Ruby => RUBY_FRAMEWORK_PATTERNS,
@@ -242,7 +640,12 @@ fn try_parse_java(line: &str) -> Option<StackFrame> {
 
     // Find the opening paren for file info
     let paren_start = line.find('(')?;
-    let symbol = line[..paren_start].to_string();
+    // Drop the module or class loader: "java.base/java.lang.Thread.run"
+    let symbol = line[..paren_start]
+        .rsplit('/')
+        .next()
+        .unwrap_or_default()
+        .to_string();
 
     // Java symbols should look like package.Class.method (dots, no spaces before paren)
     // This distinguishes from JS's "at method (file:line:col)" format
@@ -379,13 +782,33 @@ fn try_parse_python(line: &str) -> Option<StackFrame> {
 /// or: "at /path/file.js:42:15"
 fn try_parse_javascript(line: &str) -> Option<StackFrame> {
     let line = line.strip_prefix("at ")?;
+    // Node opens the error's own properties (such as `[cause]`) after the
+    // last frame
+    let line = line.strip_suffix(" {").unwrap_or(line);
 
     // Check for format: "method (file:line:col)"
     if let Some(paren_start) = line.find('(') {
-        let method = line[..paren_start].trim().to_string();
-        let paren_end = line.find(')')?;
+        let method = line[..paren_start].trim();
+        let method = method
+            .strip_prefix("async ")
+            .or_else(|| method.strip_prefix("new "))
+            .unwrap_or(method)
+            .to_string();
+        let paren_end = line.rfind(')')?;
         let file_info = &line[paren_start + 1..paren_end];
 
+        // Built-ins: "at Array.map (<anonymous>)", "at JSON.parse (native)"
+        if matches!(file_info, "<anonymous>" | "native") && !method.contains(' ') {
+            return Some(StackFrame {
+                symbol: method,
+                file: None,
+                line: None,
+                column: None,
+                is_user_code: false,
+                language: Some(StacktraceLanguage::JavaScript),
+            });
+        }
+
         let (file, line_num, column) = parse_js_location(file_info)?;
         let is_user_code = !is_framework_code(&method, Some(&file), StacktraceLanguage::JavaScript);
 
@@ -415,7 +838,9 @@ fn try_parse_javascript(line: &str) -> Option<StackFrame> {
 }
 
 fn parse_js_location(s: &str) -> Option<(PathBuf, u32, Option<u32>)> {
-    // Format: /path/file.js:42:15 or /path/file.js:42
+    // Format: /path/file.js:42:15 or /path/file.js:42, or an ES module's
+    // file:///path/file.mjs:42:15
+    let s = s.strip_prefix("file://").unwrap_or(s);
     let parts: Vec<&str> = s.rsplitn(3, ':').collect();
     match parts.len() {
         3 => {
@@ -480,22 +905,37 @@ fn try_parse_rust(line: &str) -> Option<StackFrame> {
 }
 
 /// Parse a Go stacktrace line.
-/// Format: "main.handler(0x1234)"
+/// Format: "main.handler(0x1234)", "main.(*Server).handle(...)",
+/// "created by main.main in goroutine 1"
 /// or: "        /path/file.go:42 +0x1a"
 fn try_parse_go(line: &str) -> Option<StackFrame> {
     let trimmed = line.trim();
 
+    // The goroutine's creator: "created by main.serve in goroutine 1"
+    let creator = trimmed
+        .strip_prefix("created by ")
+        .map(|rest| rest.split(" in goroutine ").next().unwrap_or(rest));
+
     // Check for function line: "package.function(args)"
-    if trimmed.contains('(') && !trimmed.starts_with('/') && !trimmed.starts_with('.') {
-        let paren_pos = trimmed.find('(')?;
-        let symbol = trimmed[..paren_pos].to_string();
+    if creator.is_some()
+        || (trimmed.ends_with(')') && !trimmed.starts_with('/') && !trimmed.starts_with('.'))
+    {
+        let symbol = match creator {
+            Some(symbol) => symbol.to_string(),
+            None => trimmed[..trimmed.rfind('(')?].to_string(),
+        };
 
-        // Skip if it looks like a file path
-        if symbol.contains('/') && !symbol.contains('.') {
+        // Skip if it looks like a file path, or isn't a name at all
+        if (symbol.contains('/') && !symbol.contains('.'))
+            || symbol.is_empty()
+            || symbol.contains(' ')
+        {
             return None;
         }
 
-        let is_user_code = !is_framework_code(&symbol, None, StacktraceLanguage::Go);
+        // Built-ins such as `panic` aren't qualified by a package
+        let is_user_code =
+            symbol.contains('.') && !is_framework_code(&symbol, None, StacktraceLanguage::Go);
 
         return Some(StackFrame {
             symbol,
@@ -586,6 +1026,45 @@ java.lang.NullPointerException: null
         assert_eq!(result.frames[0].line, None);
     }
 
+    #[test]
+    fn test_java_caused_by_chain() {
+        let trace = r#"
+Exception in thread "main" com.example.BillingException: charge failed
+	at com.example.Billing.charge(Billing.java:40)
+	at java.base/java.lang.Thread.run(Thread.java:833)
+Caused by: java.sql.SQLException: connection refused
+	at app//com.example.Db.connect(Db.kt:12)
+	at com.example.Billing.charge(Billing.java:38)
+	... 1 more
+"#;
+        let result = parse_stacktrace(trace);
+
+        assert_eq!(result.frames.len(), 4);
+        assert!(result.unparsed_lines.is_empty());
+        assert_eq!(result.frames[1].symbol, "java.lang.Thread.run");
+        assert_eq!(result.frames[2].symbol, "com.example.Db.connect");
+        assert_eq!(
+            result.exception.as_deref(),
+            Some("com.example.BillingException")
+        );
+        assert_eq!(
+            result.chain,
+            vec![
+                ChainedException {
+                    exception: Some("com.example.BillingException".to_string()),
+                    message: Some("charge failed".to_string()),
+                    frames: 0..2,
+                },
+                ChainedException {
+                    exception: Some("java.sql.SQLException".to_string()),
+                    message: Some("connection refused".to_string()),
+                    frames: 2..4,
+                },
+            ]
+        );
+        assert_eq!(result.chain_index(3), Some(1));
+    }
+
     // ============= Ruby Tests =============
 
     #[test]
@@ -662,6 +1141,47 @@ Traceback (most recent call last):
         assert!(result.frames[1].is_user_code); // User code
     }
 
+    #[test]
+    fn test_python_chained_tracebacks() {
+        let trace = r#"
+Traceback (most recent call last):
+  File "/app/db.py", line 12, in connect
+    sock.connect(address)
+    ~~~~~~~~~~~~^^^^^^^^^
+ConnectionRefusedError: [Errno 111] Connection refused
+
+The above exception was the direct cause of the following exception:
+
+Traceback (most recent call last):
+  File "/app/billing.py", line 30, in charge
+    db.connect(url)
+  File "/app/db.py", line 14, in connect
+    raise DatabaseError("unavailable") from e
+app.db.DatabaseError: unavailable
+"#;
+        let result = parse_stacktrace(trace);
+
+        let symbols: Vec<&str> = result.frames.iter().map(|f| f.symbol.as_str()).collect();
+        assert_eq!(symbols, vec!["connect", "charge", "connect"]);
+        assert!(
+            result.unparsed_lines.is_empty(),
+            "{:?}",
+            result.unparsed_lines
+        );
+        assert_eq!(result.detected_language, Some(StacktraceLanguage::Python));
+
+        // Outermost first, though Python prints the cause first
+        assert_eq!(result.exception.as_deref(), Some("app.db.DatabaseError"));
+        assert_eq!(result.chain.len(), 2);
+        assert_eq!(result.chain[0].frames, 1..3);
+        assert_eq!(result.chain[0].message.as_deref(), Some("unavailable"));
+        assert_eq!(
+            result.chain[1].exception.as_deref(),
+            Some("ConnectionRefusedError")
+        );
+        assert_eq!(result.chain[1].frames, 0..1);
+    }
+
     // ============= JavaScript Tests =============
 
     #[test]
@@ -712,6 +1232,57 @@ Error: Something went wrong
         assert!(result.frames[2].is_user_code); // User code
     }
 
+    #[test]
+    fn test_node_excerpt_async_builtins_and_cause() {
+        let trace = r#"
+/app/server.js:10
+    throw new Error('charge failed', { cause: err });
+    ^
+
+Error: charge failed
+    at async charge (file:///app/billing.mjs:10:11)
+    at new Checkout (/app/checkout.js:4:5)
+    at Array.map (<anonymous>)
+    at process.processTicksAndRejections (node:internal/process/task_queues:95:5) {
+  [cause]: TypeError: fetch failed
+      at connect (/app/db.js:3:9)
+}
+"#;
+        let result = parse_stacktrace(trace);
+
+        assert_eq!(
+            result.detected_language,
+            Some(StacktraceLanguage::JavaScript)
+        );
+        assert!(
+            result.unparsed_lines.is_empty(),
+            "{:?}",
+            result.unparsed_lines
+        );
+        let symbols: Vec<&str> = result.frames.iter().map(|f| f.symbol.as_str()).collect();
+        assert_eq!(
+            symbols,
+            vec![
+                "charge",
+                "Checkout",
+                "Array.map",
+                "process.processTicksAndRejections",
+                "connect"
+            ]
+        );
+        assert_eq!(
+            result.frames[0].file,
+            Some(PathBuf::from("/app/billing.mjs"))
+        );
+        assert!(!result.frames[2].is_user_code);
+        assert!(!result.frames[3].is_user_code);
+
+        assert_eq!(result.exception.as_deref(), Some("Error"));
+        assert_eq!(result.chain.len(), 2);
+        assert_eq!(result.chain[1].exception.as_deref(), Some("TypeError"));
+        assert_eq!(result.chain[1].frames, 4..5);
+    }
+
     // ============= Rust Tests =============
 
     #[test]
@@ -788,6 +1359,34 @@ main.myHandler(0x789)
         assert!(functions[2].is_user_code); // User code
     }
 
+    #[test]
+    fn test_go_panic_frames_carry_their_location() {
+        let trace = r#"
+panic: runtime error: invalid memory address or nil pointer dereference
+[signal SIGSEGV: segmentation violation code=0x1 addr=0x0 pc=0x48f5a3]
+
+goroutine 7 [running]:
+panic({0x4a2f20?, 0x5c1d30?})
+	/usr/local/go/src/runtime/panic.go:770 +0x132
+main.(*Server).handle(0xc000012345, {0x0, 0x0})
+	/app/server.go:42 +0x23
+created by main.(*Server).serve in goroutine 1
+	/app/server.go:30 +0x85
+"#;
+        let result = parse_stacktrace(trace);
+
+        assert_eq!(result.detected_language, Some(StacktraceLanguage::Go));
+        assert_eq!(result.frames.len(), 3);
+        assert!(!result.frames[0].is_user_code);
+        let handle = &result.frames[1];
+        assert_eq!(handle.symbol, "main.(*Server).handle");
+        assert_eq!(handle.file, Some(PathBuf::from("/app/server.go")));
+        assert_eq!(handle.line, Some(42));
+        assert!(handle.is_user_code);
+        assert_eq!(result.frames[2].symbol, "main.(*Server).serve");
+        assert_eq!(result.frames[2].line, Some(30));
+    }
+
     // ============= Edge Cases =============
 
     #[test]
@@ -804,6 +1403,43 @@ main.myHandler(0x789)
         assert!(result.frames.is_empty());
     }
 
+    #[test]
+    fn test_exception_type_from_header() {
+        let cases = [
+            (
+                "Exception in thread \"main\" com.example.PaymentException: declined\n\tat com.example.Billing.charge(Billing.java:12)",
+                Some("com.example.PaymentException"),
+            ),
+            (
+                "Traceback (most recent call last):\n  File \"app.py\", line 3, in charge\nZeroDivisionError: division by zero",
+                Some("ZeroDivisionError"),
+            ),
+            (
+                "TypeError: x is undefined\n    at charge (/app/billing.js:3:7)",
+                Some("TypeError"),
+            ),
+            (
+                "app/billing.rb:42:in `charge': card declined (Billing::PaymentError)\n\tfrom app/main.rb:3:in `run'",
+                Some("Billing::PaymentError"),
+            ),
+            ("thread 'main' panicked at 'boom', src/main.rs:2:5", None),
+        ];
+        for (trace, expected) in cases {
+            let result = parse_stacktrace(trace);
+            assert_eq!(result.exception.as_deref(), expected, "{}", trace);
+        }
+    }
+
+    #[test]
+    fn test_is_throw_site() {
+        assert!(is_throw_site("        raise PaymentError(\"declined\")"));
+        assert!(is_throw_site("throw new PaymentException(reason);"));
+        assert!(!is_throw_site(
+            "public void charge() throws PaymentException {"
+        ));
+        assert!(!is_throw_site("except PaymentError as e:"));
+    }
+
     #[test]
     fn test_mixed_garbage() {
         let trace = r#"
@@ -816,4 +1452,80 @@ More random text
         assert_eq!(result.frames.len(), 1);
         assert_eq!(result.unparsed_lines.len(), 2);
     }
+
+    #[test]
+    fn test_sourcemaps_map_bundle_frames_to_original_sources() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let js = dir.path().join("dist/static/js");
+        std::fs::create_dir_all(&js).unwrap();
+        std::fs::write(
+            js.join("app.min.js"),
+            "function a(){throw new Error(\"declined\")}\n//# sourceMappingURL=app.min.js.map\n",
+        )
+        .unwrap();
+        std::fs::write(
+            js.join("app.min.js.map"),
+            r#"{"version":3,"sources":["../../../src/app.ts"],"names":["charge"],"mappings":"AAAA,SACEA"}"#,
+        )
+        .unwrap();
+
+        let mut result = parse_stacktrace(
+            "Error: declined\n    at a (https://cdn.example.com/static/js/app.min.js?v=3:1:12)\n    at Array.map (<anonymous>)\n",
+        );
+        let generated = result.apply_sourcemaps(&[dir.path().join("dist")]);
+
+        assert_eq!(
+            generated,
+            vec![
+                Some(GeneratedPosition {
+                    file: PathBuf::from("https://cdn.example.com/static/js/app.min.js?v=3"),
+                    line: 1,
+                    column: Some(12),
+                }),
+                None
+            ]
+        );
+        let frame = &result.frames[0];
+        assert_eq!(frame.file, Some(dir.path().join("src/app.ts")));
+        assert_eq!((frame.line, frame.column), (Some(2), Some(3)));
+        assert!(frame.is_user_code);
+    }
+
+    #[test]
+    fn test_trace_splitter_cuts_traces_out_of_a_log() {
+        let log = "INFO starting\n\
+                   java.lang.IllegalStateException: boom\n\
+                   \tat com.example.A.run(A.java:10)\n\
+                   \tat com.example.Main.main(Main.java:3)\n\
+                   INFO next request\n\
+                   Traceback (most recent call last):\n\
+                   \x20 File \"app.py\", line 3, in <module>\n\
+                   \x20   charge()\n\
+                   \n\
+                   During handling of the above exception, another exception occurred:\n\
+                   \n\
+                   Traceback (most recent call last):\n\
+                   \x20 File \"app.py\", line 5, in <module>\n\
+                   ValueError: declined\n\
+                   INFO done\n\
+                   \x20 File \"tail.py\", line 1, in <module>";
+        let mut splitter = TraceSplitter::new();
+        let mut blocks: Vec<TraceBlock> = log.lines().filter_map(|l| splitter.push(l)).collect();
+        blocks.extend(splitter.flush());
+
+        assert_eq!(blocks.len(), 3);
+        assert_eq!(blocks[0].line, 2);
+        assert_eq!(parse_stacktrace(&blocks[0].text).frames.len(), 2);
+        assert_eq!(
+            parse_stacktrace(&blocks[0].text).exception.as_deref(),
+            Some("java.lang.IllegalStateException")
+        );
+        assert_eq!(blocks[1].line, 6);
+        let python = parse_stacktrace(&blocks[1].text);
+        assert_eq!(python.chain.len(), 2);
+        assert_eq!(python.exception.as_deref(), Some("ValueError"));
+        // The stream ended inside a trace
+        assert_eq!(blocks[2].line, 16);
+        assert!(splitter.flush().is_none());
+    }
 }
@@ -0,0 +1,215 @@
+//! String-literal symbol references.
+//!
+//! Reflection, DI container registrations (`services.AddScoped("MyApp.UserService")`)
+//! and Rails-style `"Admin::User".constantize` refer to symbols by name inside
+//! string literals, which the syntactic parsers never see. This module scans
+//! literals for exact matches of known qualified names and records them as
+//! low-confidence references, kept apart from syntactic ones in the database.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::db::SqliteIndex;
+use crate::{Location, Reference, ReferenceKind, Result, SymbolKind};
+
+/// Scan the string literals in `source` for exact matches of `known` qualified names.
+///
+/// A literal only matches when it is a qualified path (contains a separator such
+/// as `.` or `::`) or names a type-like symbol. Bare names like `"name"` or
+/// `"id"` are too common in ordinary strings to be useful.
+///
+/// # Examples
+///
+/// ```
+/// use rocketindex::string_refs::scan_string_references;
+/// use rocketindex::SymbolKind;
+/// use std::collections::HashMap;
+/// use std::path::Path;
+///
+/// let known = HashMap::from([("MyApp.UserService".to_string(), SymbolKind::Class)]);
+/// let refs = scan_string_references(
+///     Path::new("Startup.cs"),
+///     r#"services.AddScoped(Type.GetType("MyApp.UserService"));"#,
+///     &known,
+/// );
+/// assert_eq!(refs.len(), 1);
+/// assert_eq!(refs[0].location.column, 34);
+/// ```
+#[must_use]
+pub fn scan_string_references(
+    file: &Path,
+    source: &str,
+    known: &HashMap<String, SymbolKind>,
+) -> Vec<Reference> {
+    let mut refs = Vec::new();
+
+    for (line_idx, line) in source.lines().enumerate() {
+        for (start, content) in string_literals(line) {
+            let Some(kind) = known.get(content) else {
+                continue;
+            };
+            if !is_qualified(content) && !is_type_like(*kind) {
+                continue;
+            }
+
+            let line_no = line_idx as u32 + 1;
+            let column = line[..start].chars().count() as u32 + 1;
+            let end_column = column + content.chars().count() as u32;
+            refs.push(Reference {
+                name: content.to_string(),
+                location: Location::with_end(
+                    file.to_path_buf(),
+                    line_no,
+                    column,
+                    line_no,
+                    end_column,
+                ),
+                kind: ReferenceKind::Read,
+            });
+        }
+    }
+
+    refs
+}
+
+/// Rebuild the string-literal references for `files` in the index.
+///
+/// Existing string-literal references are cleared first, since a symbol added
+/// anywhere in the workspace can create matches in files that didn't change.
+/// Returns the number of references recorded.
+pub fn index_string_references(index: &SqliteIndex, files: &[PathBuf]) -> Result<usize> {
+    index.clear_string_references()?;
+
+    let known: HashMap<String, SymbolKind> = index.qualified_names()?.into_iter().collect();
+    if known.is_empty() {
+        return Ok(0);
+    }
+
+    let mut count = 0;
+    for file in files {
+        let Ok(source) = std::fs::read_to_string(file) else {
+            continue;
+        };
+        let refs = scan_string_references(file, &source, &known);
+        if refs.is_empty() {
+            continue;
+        }
+        let pairs: Vec<_> = refs.iter().map(|r| (file.as_path(), r)).collect();
+        index.insert_string_references(&pairs)?;
+        count += refs.len();
+    }
+
+    Ok(count)
+}
+
+/// Single-line string literals as `(byte offset of content, content)` pairs.
+///
+/// Handles `"`, `'` and `` ` `` quotes with backslash escapes. Literals containing
+/// whitespace or escapes can never be a symbol name, so they are skipped.
+fn string_literals(line: &str) -> Vec<(usize, &str)> {
+    let mut literals = Vec::new();
+    let bytes = line.as_bytes();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let quote = bytes[i];
+        if !matches!(quote, b'"' | b'\'' | b'`') {
+            i += 1;
+            continue;
+        }
+
+        let start = i + 1;
+        let mut end = start;
+        let mut escaped = false;
+        while end < bytes.len() && bytes[end] != quote {
+            if bytes[end] == b'\\' {
+                escaped = true;
+                end += 1;
+            }
+            end += 1;
+        }
+        if end >= bytes.len() {
+            break;
+        }
+
+        let content = &line[start..end];
+        if !escaped && !content.is_empty() && !content.contains(char::is_whitespace) {
+            literals.push((start, content));
+        }
+        i = end + 1;
+    }
+
+    literals
+}
+
+fn is_qualified(name: &str) -> bool {
+    name.contains("::") || name.contains('.') || name.contains('\\') || name.contains('#')
+}
+
+fn is_type_like(kind: SymbolKind) -> bool {
+    matches!(
+        kind,
+        SymbolKind::Class
+            | SymbolKind::Module
+            | SymbolKind::Interface
+            | SymbolKind::Record
+            | SymbolKind::Type
+            | SymbolKind::Union
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn known(names: &[(&str, SymbolKind)]) -> HashMap<String, SymbolKind> {
+        names
+            .iter()
+            .map(|(name, kind)| (name.to_string(), *kind))
+            .collect()
+    }
+
+    #[test]
+    fn matches_qualified_names_in_any_quote_style() {
+        let known = known(&[
+            ("Admin::User", SymbolKind::Class),
+            ("MyApp.Handlers.process", SymbolKind::Function),
+        ]);
+        let source = "klass = 'Admin::User'.constantize\nrun(`MyApp.Handlers.process`)\n";
+
+        let refs = scan_string_references(Path::new("app.rb"), source, &known);
+
+        assert_eq!(refs.len(), 2);
+        assert_eq!(refs[0].name, "Admin::User");
+        assert_eq!((refs[0].location.line, refs[0].location.column), (1, 10));
+        assert_eq!(refs[0].location.end_column, 21);
+        assert_eq!(refs[1].name, "MyApp.Handlers.process");
+        assert_eq!(refs[1].location.line, 2);
+    }
+
+    #[test]
+    fn ignores_bare_non_type_names_and_partial_matches() {
+        let known = known(&[
+            ("name", SymbolKind::Value),
+            ("UserService", SymbolKind::Class),
+            ("MyApp.UserService", SymbolKind::Class),
+        ]);
+        let source = r#"log("name", "MyApp.UserService is ready", "UserService")"#;
+
+        let refs = scan_string_references(Path::new("a.js"), source, &known);
+
+        assert_eq!(refs.len(), 1);
+        assert_eq!(refs[0].name, "UserService");
+    }
+
+    #[test]
+    fn skips_escaped_quotes() {
+        let known = known(&[("A.B", SymbolKind::Function)]);
+        let source = r#"let s = "say \"hi\""; let t = "A.B";"#;
+
+        let refs = scan_string_references(Path::new("a.ts"), source, &known);
+
+        assert_eq!(refs.len(), 1);
+        assert_eq!(refs[0].name, "A.B");
+    }
+}
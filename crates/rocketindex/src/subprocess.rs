@@ -0,0 +1,119 @@
+//! Running external tools (git, dotnet) under a time limit.
+//!
+//! A hung `git` (a credential prompt, a stale lock on a network mount)
+//! would otherwise hang whatever command is waiting on it. The limit is
+//! process-wide and set once, by `rkt --timeout`; without it commands run
+//! to completion as before.
+
+use std::io::{self, Read};
+use std::process::{Child, Command, ExitStatus, Output, Stdio};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+static TIMEOUT: OnceLock<Duration> = OnceLock::new();
+
+/// How often a running command is checked for having finished
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Limit every command run through this module to `timeout`.
+///
+/// Only the first call has an effect.
+pub fn set_timeout(timeout: Duration) {
+    let _ = TIMEOUT.set(timeout);
+}
+
+/// The limit set with [`set_timeout`], if any.
+pub fn timeout() -> Option<Duration> {
+    TIMEOUT.get().copied()
+}
+
+/// [`Command::output`], killing the command if it outlives the timeout.
+///
+/// A command that's killed fails with [`io::ErrorKind::TimedOut`].
+pub fn output(command: &mut Command) -> io::Result<Output> {
+    output_within(command, timeout())
+}
+
+/// [`Command::status`], killing the command if it outlives the timeout.
+pub fn status(command: &mut Command) -> io::Result<ExitStatus> {
+    status_within(command, timeout())
+}
+
+fn output_within(command: &mut Command, timeout: Option<Duration>) -> io::Result<Output> {
+    let Some(timeout) = timeout else {
+        return command.output();
+    };
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    // Drained as the command runs so a full pipe can't stall it
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+    let status = wait(&mut child, command, timeout)?;
+    Ok(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+fn status_within(command: &mut Command, timeout: Option<Duration>) -> io::Result<ExitStatus> {
+    let Some(timeout) = timeout else {
+        return command.status();
+    };
+    let mut child = command.spawn()?;
+    wait(&mut child, command, timeout)
+}
+
+fn wait(child: &mut Child, command: &Command, timeout: Duration) -> io::Result<ExitStatus> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(status);
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!(
+                    "{} timed out after {}s",
+                    command.get_program().to_string_lossy(),
+                    timeout.as_secs_f64()
+                ),
+            ));
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+fn drain(pipe: Option<impl Read + Send + 'static>) -> std::thread::JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut bytes = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut bytes);
+        }
+        bytes
+    })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_finish_or_are_killed() {
+        let timeout = Some(Duration::from_millis(500));
+
+        let output = output_within(Command::new("sh").args(["-c", "echo done"]), timeout).unwrap();
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"done\n");
+
+        let started = Instant::now();
+        let error = status_within(Command::new("sleep").arg("10"), timeout).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
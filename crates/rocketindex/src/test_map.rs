@@ -0,0 +1,288 @@
+//! Test-to-code mapping.
+//!
+//! Classifies symbols as tests from their attributes (`[<Fact>]`,
+//! `#[test]`, `@Test`) and naming conventions (`test_*` in a test file), and
+//! maps a symbol to the tests that reach it by walking its callers. The
+//! classification is stored with each symbol in SQLite (`is_test`).
+
+use crate::affected::{is_test_file, tests_reaching, AffectedTest};
+use crate::{CodeIndex, Confidence, Symbol, SymbolKind};
+
+/// Attributes that mark a test across xUnit, NUnit, MSTest, JUnit and Rust,
+/// lowercased and without an `Attribute` suffix or arguments.
+const TEST_ATTRIBUTES: [&str; 11] = [
+    "test",
+    "fact",
+    "theory",
+    "testmethod",
+    "datatestmethod",
+    "testcase",
+    "testcasesource",
+    "parameterizedtest",
+    "property",
+    "rstest",
+    "quickcheck",
+];
+
+/// Languages whose parsers record attributes, so a test must carry one.
+const ATTRIBUTE_LANGUAGES: [&str; 2] = ["fsharp", "rust"];
+
+/// Whether `symbol` is a test case.
+///
+/// A function or method is a test when it carries a test attribute, or when
+/// it lives in a test file (see [`is_test_file`]) and follows its language's
+/// naming convention: `test_*`/`test*` for pytest, minitest, Go and JUnit 3.
+/// For languages whose attributes aren't indexed (C#, Java, Kotlin), public
+/// methods of a test file also count. RSpec and Jest blocks aren't symbols;
+/// [`tests_for`] reports them at file level instead.
+#[must_use]
+pub fn is_test_symbol(symbol: &Symbol) -> bool {
+    if !matches!(symbol.kind, SymbolKind::Function | SymbolKind::Member) {
+        return false;
+    }
+    if has_test_attribute(symbol) {
+        return true;
+    }
+    if !is_test_file(&symbol.location.file)
+        || ATTRIBUTE_LANGUAGES.contains(&symbol.language.as_str())
+    {
+        return false;
+    }
+
+    let name = symbol.name.as_str();
+    let named_test = name
+        .strip_prefix("test")
+        .or_else(|| name.strip_prefix("Test"))
+        .is_some_and(|rest| {
+            rest.is_empty() || rest.starts_with('_') || rest.starts_with(char::is_uppercase)
+        });
+    named_test
+        || (matches!(symbol.language.as_str(), "csharp" | "java" | "kotlin")
+            && symbol.kind == SymbolKind::Member
+            && symbol.visibility == crate::Visibility::Public)
+}
+
+fn has_test_attribute(symbol: &Symbol) -> bool {
+    symbol.attributes.iter().flatten().any(|attribute| {
+        // `tokio::test`, `Xunit.FactAttribute(Skip = "...")`, `test`
+        let name = attribute.split('(').next().unwrap_or_default().trim();
+        let name = name.rsplit([':', '.']).next().unwrap_or_default();
+        let name = name.to_lowercase();
+        let name = name.strip_suffix("attribute").unwrap_or(&name);
+        TEST_ATTRIBUTES.contains(&name)
+    })
+}
+
+/// Tests that (transitively) call `symbol`, up to `max_depth` caller edges.
+///
+/// Results are ordered by file, then line. Test files that only reach the
+/// symbol from code outside any test symbol (RSpec `it` blocks) are reported
+/// with `symbol: None`.
+#[must_use]
+pub fn tests_for(
+    index: &CodeIndex,
+    symbol: &str,
+    max_depth: usize,
+    min_confidence: Confidence,
+) -> Vec<AffectedTest> {
+    tests_reaching(index, &[symbol], max_depth, min_confidence, is_test_symbol)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Location, Reference, ReferenceKind, Visibility};
+    use std::path::PathBuf;
+
+    fn symbol(qualified: &str, kind: SymbolKind, file: &str, language: &str) -> Symbol {
+        let name = qualified.rsplit(['.', '#']).next().unwrap();
+        Symbol::new(
+            name.to_string(),
+            qualified.to_string(),
+            kind,
+            Location::new(PathBuf::from(file), 1, 1),
+            Visibility::Public,
+            language.to_string(),
+        )
+    }
+
+    fn with_attributes(symbol: Symbol, attributes: &[&str]) -> Symbol {
+        symbol.with_attributes(Some(attributes.iter().map(|a| a.to_string()).collect()))
+    }
+
+    #[test]
+    fn test_classifies_by_attribute() {
+        let fact = with_attributes(
+            symbol(
+                "Tests.saves user",
+                SymbolKind::Function,
+                "Tests.fs",
+                "fsharp",
+            ),
+            &["Fact"],
+        );
+        assert!(is_test_symbol(&fact));
+
+        let tokio = with_attributes(
+            symbol(
+                "crate::db::tests::opens",
+                SymbolKind::Function,
+                "src/db.rs",
+                "rust",
+            ),
+            &["tokio::test"],
+        );
+        assert!(is_test_symbol(&tokio));
+
+        let skipped = with_attributes(
+            symbol(
+                "UserTests.Saves",
+                SymbolKind::Member,
+                "src/UserTests.cs",
+                "csharp",
+            ),
+            &["Xunit.FactAttribute(Skip = \"flaky\")"],
+        );
+        assert!(is_test_symbol(&skipped));
+
+        // Rust helpers in a test module carry no #[test]
+        let helper = with_attributes(
+            symbol(
+                "crate::db::tests::fixture",
+                SymbolKind::Function,
+                "src/db.rs",
+                "rust",
+            ),
+            &["allow(dead_code)"],
+        );
+        assert!(!is_test_symbol(&helper));
+    }
+
+    #[test]
+    fn test_classifies_by_naming_convention() {
+        for (qualified, kind, file, language) in [
+            (
+                "tests.test_user.test_save",
+                SymbolKind::Function,
+                "tests/test_user.py",
+                "python",
+            ),
+            (
+                "UserTest#test_save",
+                SymbolKind::Member,
+                "test/user_test.rb",
+                "ruby",
+            ),
+            (
+                "user.TestSave",
+                SymbolKind::Function,
+                "user/user_test.go",
+                "go",
+            ),
+            (
+                "UserTest.testSave",
+                SymbolKind::Member,
+                "src/test/UserTest.java",
+                "java",
+            ),
+            (
+                "UserTests.Saves",
+                SymbolKind::Member,
+                "tests/UserTests.cs",
+                "csharp",
+            ),
+        ] {
+            assert!(
+                is_test_symbol(&symbol(qualified, kind, file, language)),
+                "{}",
+                qualified
+            );
+        }
+
+        for (qualified, kind, file, language) in [
+            // Not in a test file
+            (
+                "app.user.test_connection",
+                SymbolKind::Function,
+                "app/user.py",
+                "python",
+            ),
+            // Helpers and types in test files
+            (
+                "tests.conftest.make_user",
+                SymbolKind::Function,
+                "tests/conftest.py",
+                "python",
+            ),
+            (
+                "tests.test_user.TestUser",
+                SymbolKind::Class,
+                "tests/test_user.py",
+                "python",
+            ),
+            (
+                "tests.test_user.testing",
+                SymbolKind::Function,
+                "tests/test_user.py",
+                "python",
+            ),
+            // F# tests need an attribute
+            (
+                "Tests.testSave",
+                SymbolKind::Function,
+                "tests/Tests.fs",
+                "fsharp",
+            ),
+        ] {
+            assert!(
+                !is_test_symbol(&symbol(qualified, kind, file, language)),
+                "{}",
+                qualified
+            );
+        }
+    }
+
+    #[test]
+    fn test_tests_for_skips_helpers_in_test_files() {
+        let mut index = CodeIndex::new();
+        index.add_symbol(symbol(
+            "app.user.save",
+            SymbolKind::Function,
+            "app/user.py",
+            "python",
+        ));
+        index.add_symbol(symbol(
+            "tests.helpers.make_user",
+            SymbolKind::Function,
+            "tests/helpers.py",
+            "python",
+        ));
+        index.add_symbol(symbol(
+            "tests.test_user.test_save",
+            SymbolKind::Function,
+            "tests/test_user.py",
+            "python",
+        ));
+        // test_save -> make_user -> save
+        for (name, file) in [
+            ("save", "tests/helpers.py"),
+            ("make_user", "tests/test_user.py"),
+        ] {
+            index.add_reference(
+                PathBuf::from(file),
+                Reference {
+                    name: name.to_string(),
+                    location: Location::new(PathBuf::from(file), 2, 5),
+                    kind: ReferenceKind::Read,
+                },
+            );
+        }
+
+        let tests = tests_for(&index, "app.user.save", 5, Confidence::StringMatch);
+        let found: Vec<(Option<&str>, usize)> = tests
+            .iter()
+            .map(|t| (t.symbol.as_ref().map(|s| s.qualified.as_str()), t.depth))
+            .collect();
+        assert_eq!(found, vec![(Some("tests.test_user.test_save"), 2)]);
+    }
+}
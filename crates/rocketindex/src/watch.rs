@@ -35,6 +35,7 @@ pub struct FileWatcher {
     watcher: RecommendedWatcher,
     receiver: Receiver<Result<Event, notify::Error>>,
     root: PathBuf,
+    ignore_files: IgnoreFiles,
 }
 
 impl FileWatcher {
@@ -61,6 +62,7 @@ impl FileWatcher {
             watcher,
             receiver: rx,
             root: root.to_path_buf(),
+            ignore_files: IgnoreFiles::new(root),
         })
     }
 
@@ -116,7 +118,14 @@ impl FileWatcher {
 
     /// Process a raw notify event into our WatchEvent type.
     fn process_event(&self, event: Event) -> Option<WatchEvent> {
-        process_event_filtered(event)
+        match process_event_filtered(event)? {
+            // Moved somewhere ignored: as far as the index goes, it's gone
+            WatchEvent::Renamed(old, new) if self.ignore_files.is_ignored(&new) => {
+                Some(WatchEvent::Deleted(old))
+            }
+            event if self.ignore_files.is_ignored(event.path()) => None,
+            event => Some(event),
+        }
     }
 }
 
@@ -200,6 +209,7 @@ pub struct DebouncedFileWatcher {
     debouncer: Debouncer<RecommendedWatcher, RecommendedCache>,
     receiver: std::sync::mpsc::Receiver<DebounceEventResult>,
     root: PathBuf,
+    ignore_files: IgnoreFiles,
 }
 
 impl DebouncedFileWatcher {
@@ -223,6 +233,7 @@ impl DebouncedFileWatcher {
             debouncer,
             receiver: rx,
             root: root.to_path_buf(),
+            ignore_files: IgnoreFiles::new(root),
         })
     }
 
@@ -311,6 +322,9 @@ impl DebouncedFileWatcher {
 
         // Get the first supported file path from the event
         let path = event.paths.iter().find(|p| is_supported_file(p))?.clone();
+        if self.ignore_files.is_ignored(&path) {
+            return None;
+        }
 
         match event.kind {
             EventKind::Create(_) => Some(WatchEvent::Created(path)),
@@ -322,6 +336,71 @@ impl DebouncedFileWatcher {
     }
 }
 
+/// Index-only ignore file, in gitignore syntax.
+///
+/// Read alongside `.gitignore` (and whether or not `respect_gitignore` is
+/// set), so files can be kept out of the index without ignoring them in git.
+pub const IGNORE_FILE: &str = ".rocketindexignore";
+
+/// The [`IGNORE_FILE`]s that apply to paths under a root.
+///
+/// Files are read on each check, so edits to them take effect in a running
+/// watcher without a restart. As in git, a file deeper in the tree overrides
+/// one above it, and a `!pattern` re-includes.
+#[derive(Debug, Clone)]
+pub struct IgnoreFiles {
+    root: PathBuf,
+}
+
+impl IgnoreFiles {
+    pub fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+        }
+    }
+
+    /// Whether an ignore file between the root and `path` excludes it.
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        let Ok(relative) = path.strip_prefix(&self.root) else {
+            return false;
+        };
+        let mut dirs = vec![self.root.clone()];
+        for component in relative.parent().into_iter().flat_map(Path::components) {
+            let dir = dirs[dirs.len() - 1].join(component);
+            dirs.push(dir);
+        }
+
+        for dir in dirs.iter().rev() {
+            let ignore_file = dir.join(IGNORE_FILE);
+            if !ignore_file.is_file() {
+                continue;
+            }
+            let (matcher, error) = ignore::gitignore::Gitignore::new(&ignore_file);
+            if let Some(error) = error {
+                tracing::warn!("Invalid pattern in {}: {}", ignore_file.display(), error);
+            }
+            match matcher.matched_path_or_any_parents(path, false) {
+                ignore::Match::Ignore(_) => return true,
+                ignore::Match::Whitelist(_) => return false,
+                ignore::Match::None => {}
+            }
+        }
+        false
+    }
+}
+
+impl WatchEvent {
+    /// The file the event is about (the new path of a rename).
+    pub fn path(&self) -> &Path {
+        match self {
+            WatchEvent::Created(path)
+            | WatchEvent::Modified(path)
+            | WatchEvent::Deleted(path)
+            | WatchEvent::Renamed(_, path) => path,
+        }
+    }
+}
+
 /// Check if a path is a supported source file.
 /// Supported: C, C++, C#, F#, Go, Java, JavaScript, Kotlin, Objective-C, PHP, Python, Ruby, Rust, Swift, TypeScript.
 pub fn is_supported_file(path: &Path) -> bool {
@@ -390,6 +469,7 @@ pub fn find_source_files(root: &Path) -> std::io::Result<Vec<PathBuf>> {
 /// - Respects .gitignore files (including nested ones) when `respect_gitignore` is true
 /// - Respects global gitignore (~/.gitignore)
 /// - Respects .git/info/exclude
+/// - Respects `.rocketindexignore` files ([`IGNORE_FILE`]), always
 /// - Applies custom directory exclusions on top
 pub fn find_source_files_with_exclusions(
     root: &Path,
@@ -443,6 +523,7 @@ pub fn find_source_files_with_config(
         .require_git(false) // Still work in non-git directories
         .ignore(respect_gitignore) // Respect .ignore files
         .parents(respect_gitignore) // Check parent directories for ignore files
+        .add_custom_ignore_filename(IGNORE_FILE) // Index-only exclusions
         .overrides(overrides); // Apply custom exclusions
 
     for entry in builder.build() {
@@ -537,6 +618,31 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_rocketindexignore_excludes_files() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("generated/keep")).unwrap();
+        for file in ["main.rs", "generated/schema.rs", "generated/keep/api.rs"] {
+            std::fs::write(root.join(file), "fn f() {}").unwrap();
+        }
+        std::fs::write(root.join(IGNORE_FILE), "generated/\n").unwrap();
+
+        // Applies even when .gitignore files aren't respected
+        let files = find_source_files_with_config(root, &[], false).unwrap();
+        assert_eq!(files, vec![root.join("main.rs")]);
+
+        let ignore_files = IgnoreFiles::new(root);
+        assert!(ignore_files.is_ignored(&root.join("generated/schema.rs")));
+        assert!(!ignore_files.is_ignored(&root.join("main.rs")));
+
+        // A deeper file can re-include what a shallower one ignores
+        std::fs::write(root.join(IGNORE_FILE), "*.rs\n").unwrap();
+        std::fs::write(root.join("generated/keep").join(IGNORE_FILE), "!api.rs\n").unwrap();
+        assert!(ignore_files.is_ignored(&root.join("generated/schema.rs")));
+        assert!(!ignore_files.is_ignored(&root.join("generated/keep/api.rs")));
+    }
+
     // ==================== Event Filtering Tests ====================
 
     #[test]
@@ -0,0 +1,100 @@
+//! Advisory lock serializing writers of an index.
+//!
+//! `rkt index`, the CLI's auto-refresh, watch mode and the LSP all write the
+//! same database. SQLite keeps each transaction whole, but two processes that
+//! notice the same stale files would both reindex them. Whoever holds this
+//! lock decides what needs updating and writes it; everyone else waits, then
+//! finds the work already done.
+//!
+//! The lock is an OS file lock on `index.db.lock`, so it is released when the
+//! holder exits, however it exits.
+
+use std::fs::{File, OpenOptions, TryLockError};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::{IndexError, Result};
+
+/// How long writers wait for the lock by default
+pub const DEFAULT_LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// First retry delay; doubles after every failed attempt
+const INITIAL_BACKOFF: Duration = Duration::from_millis(10);
+
+/// Longest delay between attempts
+const MAX_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Exclusive right to write an index, released on drop.
+#[derive(Debug)]
+pub struct WriteLock {
+    _file: File,
+}
+
+/// The lock file for the database at `db_path`: `index.db.lock` beside it.
+pub fn lock_path(db_path: &Path) -> PathBuf {
+    let mut path = db_path.as_os_str().to_owned();
+    path.push(".lock");
+    PathBuf::from(path)
+}
+
+/// Take the write lock for `db_path` if nobody holds it.
+pub fn try_acquire(db_path: &Path) -> Result<Option<WriteLock>> {
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(lock_path(db_path))?;
+    match file.try_lock() {
+        Ok(()) => Ok(Some(WriteLock { _file: file })),
+        Err(TryLockError::WouldBlock) => Ok(None),
+        Err(TryLockError::Error(e)) => Err(e.into()),
+    }
+}
+
+/// Take the write lock for `db_path`, retrying with exponential backoff.
+///
+/// Fails with [`IndexError::IndexBusy`] if another process still holds it
+/// after `timeout`.
+pub fn acquire(db_path: &Path, timeout: Duration) -> Result<WriteLock> {
+    let deadline = Instant::now() + timeout;
+    let mut backoff = INITIAL_BACKOFF;
+    loop {
+        if let Some(lock) = try_acquire(db_path)? {
+            return Ok(lock);
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return Err(IndexError::IndexBusy(db_path.to_path_buf()));
+        }
+        std::thread::sleep(backoff.min(deadline - now));
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn second_writer_waits_for_the_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("index.db");
+
+        let held = acquire(&db_path, DEFAULT_LOCK_TIMEOUT).unwrap();
+        assert!(lock_path(&db_path).exists());
+        assert!(try_acquire(&db_path).unwrap().is_none());
+        assert!(matches!(
+            acquire(&db_path, Duration::from_millis(50)),
+            Err(IndexError::IndexBusy(_))
+        ));
+
+        // Released as soon as the holder lets go
+        let waiter = std::thread::spawn({
+            let db_path = db_path.clone();
+            move || acquire(&db_path, DEFAULT_LOCK_TIMEOUT).is_ok()
+        });
+        std::thread::sleep(Duration::from_millis(50));
+        drop(held);
+        assert!(waiter.join().unwrap());
+    }
+}